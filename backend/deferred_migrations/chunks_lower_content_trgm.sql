@@ -0,0 +1,8 @@
+-- Applied by the backend at startup, not by sqlx, only when
+-- LOWERCASE_CONTENT_SEARCH is set. The web server queries this index in the
+-- same mode, matching case-insensitive terms with `lower(text_content) LIKE`
+-- instead of ILIKE. It roughly doubles the trigram index footprint of chunks,
+-- so deployments that don't use the mode never build it. See src/main.rs.
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_chunks_text_content_lower_trgm
+    ON chunks USING gin (lower(text_content) gin_trgm_ops);
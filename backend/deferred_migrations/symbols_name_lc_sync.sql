@@ -0,0 +1,16 @@
+-- Applied by the backend at startup, not by sqlx, once the name_lc backfill
+-- has completed two consecutive passes without finding a row to fix and no
+-- row disagrees with LOWER(name). See src/backfill.rs.
+
+ALTER TABLE symbols ALTER COLUMN name_lc SET NOT NULL;
+
+CREATE OR REPLACE FUNCTION symbols_sync_name_lc() RETURNS trigger AS $$
+BEGIN
+    NEW.name_lc := LOWER(NEW.name);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER symbols_name_lc_sync
+    BEFORE INSERT OR UPDATE OF name, name_lc ON symbols
+    FOR EACH ROW EXECUTE FUNCTION symbols_sync_name_lc();
@@ -0,0 +1,12 @@
+-- Watermarks for long-running, resumable data backfills driven from admin endpoints.
+
+CREATE TABLE IF NOT EXISTS backfill_state (
+    job TEXT PRIMARY KEY,
+    -- Highest row id processed in the current pass; 0 when a pass starts.
+    last_id BIGINT NOT NULL DEFAULT 0,
+    pass_rows_updated BIGINT NOT NULL DEFAULT 0,
+    total_rows_updated BIGINT NOT NULL DEFAULT 0,
+    -- Consecutive complete passes that found nothing to fix.
+    clean_passes INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,6 @@
+-- Optional zstd-compressed chunk storage. When compressed_content is set the
+-- chunk's text_content is left empty, so content search (LIKE/trigram scans
+-- and extract_context) does not see compressed chunks; file views and
+-- snippets decode them on read.
+
+ALTER TABLE chunks ADD COLUMN IF NOT EXISTS compressed_content BYTEA;
@@ -0,0 +1,6 @@
+-- Tree entry metadata for indexed files. kind is 'regular', 'symlink' or
+-- 'submodule'; mode is the git file mode and stays NULL for files indexed
+-- from a working tree or before this column existed.
+
+ALTER TABLE files ADD COLUMN IF NOT EXISTS kind TEXT NOT NULL DEFAULT 'regular';
+ALTER TABLE files ADD COLUMN IF NOT EXISTS mode INTEGER;
@@ -0,0 +1,5 @@
+-- Declaration header captured for a definition, e.g. `fn foo(a: u32) -> bool`.
+-- Only some extractors record one, so it stays NULL for everything else,
+-- including references indexed before this column existed.
+
+ALTER TABLE symbol_references ADD COLUMN IF NOT EXISTS signature TEXT;
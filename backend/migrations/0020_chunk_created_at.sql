@@ -0,0 +1,5 @@
+-- Upload time for chunks, matching content_blobs.created_at. The orphan
+-- sweep leaves recent chunks alone because an upload sends chunks before the
+-- mappings that reference them. Existing chunks get the migration time.
+
+ALTER TABLE chunks ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ DEFAULT NOW();
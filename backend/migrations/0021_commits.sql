@@ -0,0 +1,13 @@
+-- Author, time and subject of each indexed commit, so the UI can show more
+-- than a bare SHA. Manifests from indexers that predate the
+-- `commit_metadata` section leave it empty.
+
+CREATE TABLE IF NOT EXISTS commits (
+    repository TEXT NOT NULL,
+    commit_sha TEXT NOT NULL,
+    author_name TEXT NOT NULL,
+    author_email TEXT NOT NULL,
+    committed_at TIMESTAMPTZ NOT NULL,
+    subject TEXT NOT NULL,
+    PRIMARY KEY (repository, commit_sha)
+);
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// Every definition at a commit with the declaration signature its extractor
+/// stored, if any.
+const DEFINITIONS_SQL: &str = "
+    SELECT
+        sn.namespace,
+        s.name,
+        f.file_path,
+        sr.line_number,
+        sr.signature
+    FROM files f
+    JOIN symbols s ON s.content_hash = f.content_hash
+    JOIN symbol_references sr ON sr.symbol_id = s.id AND sr.kind = 'definition'
+    JOIN symbol_namespaces sn ON sn.id = sr.namespace_id
+    WHERE f.repository = $1 AND f.commit_sha = $2
+";
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DefinitionSite {
+    pub file_path: String,
+    pub line: i32,
+    /// The declaration header, e.g. `fn foo(a: u32) -> bool`, for
+    /// extractors that capture one.
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ApiSymbol {
+    pub namespace: String,
+    pub name: String,
+    pub definitions: Vec<DefinitionSite>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ApiSymbolChange {
+    pub namespace: String,
+    pub name: String,
+    pub before: Vec<DefinitionSite>,
+    pub after: Vec<DefinitionSite>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct ApiDiff {
+    pub added: Vec<ApiSymbol>,
+    pub removed: Vec<ApiSymbol>,
+    pub modified: Vec<ApiSymbolChange>,
+}
+
+type DefinitionSet = BTreeMap<(String, String), Vec<DefinitionSite>>;
+
+/// Compares the symbols defined at two commits, keyed by namespace and name.
+/// The index records no visibility, so every extracted definition counts. A
+/// symbol is modified when the stored signatures of its definitions differ.
+/// Signatures are whitespace-normalized, so reformatting or moving a
+/// definition is not a change, and definitions without one are not compared.
+pub async fn api_diff(
+    pool: &PgPool,
+    repository: &str,
+    base_commit: &str,
+    head_commit: &str,
+) -> sqlx::Result<ApiDiff> {
+    let base = load_definitions(pool, repository, base_commit).await?;
+    let head = load_definitions(pool, repository, head_commit).await?;
+    Ok(diff_definitions(base, head))
+}
+
+async fn load_definitions(
+    pool: &PgPool,
+    repository: &str,
+    commit: &str,
+) -> sqlx::Result<DefinitionSet> {
+    let rows: Vec<(String, String, String, i32, Option<String>)> = sqlx::query_as(DEFINITIONS_SQL)
+        .bind(repository)
+        .bind(commit)
+        .fetch_all(pool)
+        .await?;
+
+    let mut definitions = DefinitionSet::new();
+    for (namespace, name, file_path, line, signature) in rows {
+        definitions
+            .entry((namespace, name))
+            .or_default()
+            .push(DefinitionSite {
+                file_path,
+                line,
+                signature,
+            });
+    }
+    for sites in definitions.values_mut() {
+        sites.sort();
+    }
+    Ok(definitions)
+}
+
+fn diff_definitions(base: DefinitionSet, mut head: DefinitionSet) -> ApiDiff {
+    let mut diff = ApiDiff::default();
+    for (key, before) in base {
+        match head.remove(&key) {
+            None => {
+                let (namespace, name) = key;
+                diff.removed.push(ApiSymbol {
+                    namespace,
+                    name,
+                    definitions: before,
+                });
+            }
+            Some(after) if signature_changed(&before, &after) => {
+                let (namespace, name) = key;
+                diff.modified.push(ApiSymbolChange {
+                    namespace,
+                    name,
+                    before,
+                    after,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    diff.added = head
+        .into_iter()
+        .map(|((namespace, name), definitions)| ApiSymbol {
+            namespace,
+            name,
+            definitions,
+        })
+        .collect();
+    diff
+}
+
+fn signature_changed(before: &[DefinitionSite], after: &[DefinitionSite]) -> bool {
+    let before = signatures(before);
+    let after = signatures(after);
+    !before.is_empty() && !after.is_empty() && before != after
+}
+
+fn signatures(sites: &[DefinitionSite]) -> Vec<&str> {
+    let mut signatures: Vec<&str> = sites
+        .iter()
+        .filter_map(|site| site.signature.as_deref())
+        .collect();
+    signatures.sort_unstable();
+    signatures
+}
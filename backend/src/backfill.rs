@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use tracing::info;
+
+/// `backfill_state` key for the `symbols.name_lc` backfill.
+pub const NAME_LC_JOB: &str = "symbols_name_lc";
+
+/// Complete passes without a fix needed before the sync trigger may go in.
+pub const REQUIRED_CLEAN_PASSES: i32 = 2;
+
+const NAME_LC_TRIGGER: &str = "symbols_name_lc_sync";
+const NAME_LC_TRIGGER_SQL: &str = include_str!("../deferred_migrations/symbols_name_lc_sync.sql");
+
+#[derive(Debug, Clone, Copy)]
+pub struct BackfillOptions {
+    pub batch_size: i64,
+    /// Batches to run in this call; non-positive means until the pass ends.
+    pub max_batches: i64,
+    /// Sleep between batches to bound write amplification on a live table.
+    pub pause: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct BackfillProgress {
+    pub rows_updated: i64,
+    pub batches_run: i64,
+    /// Watermark to resume from; reset to 0 once a pass completes.
+    pub last_id: i64,
+    pub pass_complete: bool,
+    pub clean_passes: i32,
+    pub ready_for_constraint: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameLcGuard {
+    AlreadyInstalled,
+    Installed,
+    /// The backfill hasn't completed enough clean passes yet.
+    AwaitingBackfill {
+        clean_passes: i32,
+    },
+    /// Passes came back clean, but rows written since then disagree again.
+    BadRowsRemain,
+}
+
+/// Rewrites `symbols.name_lc` wherever it disagrees with `LOWER(name)`,
+/// walking ids in keyset order. Each batch commits together with its
+/// watermark, so an interrupted run resumes where it stopped.
+pub async fn backfill_name_lc(
+    pool: &PgPool,
+    options: BackfillOptions,
+) -> sqlx::Result<BackfillProgress> {
+    let batch_size = options.batch_size.max(1);
+    let max_batches = if options.max_batches <= 0 {
+        i64::MAX
+    } else {
+        options.max_batches
+    };
+
+    sqlx::query("INSERT INTO backfill_state (job) VALUES ($1) ON CONFLICT (job) DO NOTHING")
+        .bind(NAME_LC_JOB)
+        .execute(pool)
+        .await?;
+
+    let mut progress = BackfillProgress {
+        rows_updated: 0,
+        batches_run: 0,
+        last_id: 0,
+        pass_complete: false,
+        clean_passes: 0,
+        ready_for_constraint: false,
+    };
+
+    while progress.batches_run < max_batches {
+        if progress.batches_run > 0 && !options.pause.is_zero() {
+            tokio::time::sleep(options.pause).await;
+        }
+
+        let mut tx = pool.begin().await?;
+        // The row lock keeps concurrent runs from processing the same range.
+        let (last_id, clean_passes): (i64, i32) = sqlx::query_as(
+            "SELECT last_id, clean_passes FROM backfill_state WHERE job = $1 FOR UPDATE",
+        )
+        .bind(NAME_LC_JOB)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let (batch_end, updated): (Option<i64>, i64) = sqlx::query_as(
+            "WITH batch AS (
+                SELECT id
+                FROM symbols
+                WHERE id > $1
+                ORDER BY id
+                LIMIT $2
+            ), fixed AS (
+                UPDATE symbols s
+                SET name_lc = LOWER(s.name)
+                FROM batch
+                WHERE s.id = batch.id
+                  AND s.name_lc IS DISTINCT FROM LOWER(s.name)
+                RETURNING 1
+            )
+            SELECT (SELECT MAX(id)::BIGINT FROM batch), (SELECT COUNT(*) FROM fixed)",
+        )
+        .bind(last_id)
+        .bind(batch_size)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        progress.batches_run += 1;
+        progress.clean_passes = clean_passes;
+
+        match batch_end {
+            Some(batch_end) => {
+                sqlx::query(
+                    "UPDATE backfill_state
+                     SET last_id = $2,
+                         pass_rows_updated = pass_rows_updated + $3,
+                         total_rows_updated = total_rows_updated + $3,
+                         updated_at = NOW()
+                     WHERE job = $1",
+                )
+                .bind(NAME_LC_JOB)
+                .bind(batch_end)
+                .bind(updated)
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+                progress.rows_updated += updated;
+                progress.last_id = batch_end;
+            }
+            None => {
+                let clean_passes: i32 = sqlx::query_scalar(
+                    "UPDATE backfill_state
+                     SET clean_passes = CASE WHEN pass_rows_updated = 0
+                             THEN clean_passes + 1
+                             ELSE 0
+                         END,
+                         last_id = 0,
+                         pass_rows_updated = 0,
+                         updated_at = NOW()
+                     WHERE job = $1
+                     RETURNING clean_passes",
+                )
+                .bind(NAME_LC_JOB)
+                .fetch_one(&mut *tx)
+                .await?;
+                tx.commit().await?;
+                info!(clean_passes, "name_lc backfill pass complete");
+                progress.clean_passes = clean_passes;
+                progress.last_id = 0;
+                progress.pass_complete = true;
+                break;
+            }
+        }
+    }
+
+    progress.ready_for_constraint = progress.clean_passes >= REQUIRED_CLEAN_PASSES;
+    Ok(progress)
+}
+
+/// Installs the trigger that keeps `name_lc` in sync with `name`, but only
+/// after the backfill has verified the table; otherwise reports what is still
+/// missing. Run it inside a transaction so the DDL applies atomically.
+pub async fn ensure_name_lc_trigger(conn: &mut PgConnection) -> sqlx::Result<NameLcGuard> {
+    let installed: bool = sqlx::query_scalar(
+        "SELECT EXISTS (
+            SELECT 1 FROM pg_trigger
+            WHERE tgname = $1 AND tgrelid = 'symbols'::regclass
+        )",
+    )
+    .bind(NAME_LC_TRIGGER)
+    .fetch_one(&mut *conn)
+    .await?;
+    if installed {
+        return Ok(NameLcGuard::AlreadyInstalled);
+    }
+
+    let clean_passes: i32 =
+        sqlx::query_scalar("SELECT clean_passes FROM backfill_state WHERE job = $1")
+            .bind(NAME_LC_JOB)
+            .fetch_optional(&mut *conn)
+            .await?
+            .unwrap_or(0);
+    if clean_passes < REQUIRED_CLEAN_PASSES {
+        return Ok(NameLcGuard::AwaitingBackfill { clean_passes });
+    }
+
+    // Rows can be written between passes, so check the whole table once more.
+    let bad_rows_remain: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM symbols WHERE name_lc IS DISTINCT FROM LOWER(name))",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if bad_rows_remain {
+        return Ok(NameLcGuard::BadRowsRemain);
+    }
+
+    sqlx::raw_sql(NAME_LC_TRIGGER_SQL)
+        .execute(&mut *conn)
+        .await?;
+    Ok(NameLcGuard::Installed)
+}
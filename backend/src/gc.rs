@@ -1,8 +1,10 @@
 use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
 use serde::Serialize;
-use sqlx::{FromRow, PgPool};
+use sqlx::{FromRow, PgConnection, PgPool};
 use tracing::warn;
 
 use crate::ApiErrorKind;
@@ -12,19 +14,143 @@ pub struct GcOutcome {
     pub branches_evaluated: usize,
     pub snapshots_removed: usize,
     pub commits_pruned: usize,
+    pub content_blobs_removed: usize,
+    pub chunks_removed: usize,
+    /// Per-branch breakdown, only collected for detailed runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Vec<GcDetail>>,
+}
+
+/// What a GC run removed from one branch.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct GcDetail {
+    pub repository: String,
+    pub branch: String,
+    pub snapshots_removed: Vec<String>,
+    /// Removed snapshots whose commit data was deleted too; the others are
+    /// still referenced by another branch or snapshot.
+    pub commits_pruned: Vec<String>,
+}
+
+/// Limits for one orphan sweep, so a large backlog is worked off over several
+/// runs instead of holding connections away from ingestion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrphanSweepOptions {
+    /// Content hashes or chunks deleted per transaction.
+    pub batch_size: i64,
+    /// No new batch starts once the sweep has run this long.
+    pub time_budget: Duration,
+    /// Blobs and chunks younger than this are kept even when nothing uses
+    /// them yet, since uploads send them before the files and mappings that
+    /// reference them.
+    pub grace_period: Duration,
+}
+
+impl Default for OrphanSweepOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 500,
+            time_budget: Duration::from_secs(30),
+            grace_period: Duration::from_secs(3600),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Default, PartialEq, Eq)]
+pub struct OrphanSweepOutcome {
+    pub content_blobs_removed: usize,
+    pub chunks_removed: usize,
+    /// Set when the time budget ran out with orphans possibly left over.
+    pub budget_exhausted: bool,
+}
+
+/// Latest background GC run, shared between the GC loop and the status
+/// endpoint. `None` until the first run finishes.
+pub type GcStatus = Arc<Mutex<Option<LastGcRun>>>;
+
+/// What the most recent background GC run did and when.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LastGcRun {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub branches_evaluated: usize,
+    pub snapshots_removed: usize,
+    pub commits_pruned: usize,
+    pub content_blobs_removed: usize,
+    pub chunks_removed: usize,
+    /// Set when the run failed, in which case the counts are all zero.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl LastGcRun {
+    pub fn new(
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        result: &Result<GcOutcome, ApiErrorKind>,
+    ) -> Self {
+        let finished_at =
+            started_at + chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::zero());
+        let mut run = Self {
+            started_at,
+            finished_at,
+            duration_ms: duration.as_millis() as u64,
+            branches_evaluated: 0,
+            snapshots_removed: 0,
+            commits_pruned: 0,
+            content_blobs_removed: 0,
+            chunks_removed: 0,
+            error: None,
+        };
+        match result {
+            Ok(outcome) => {
+                run.branches_evaluated = outcome.branches_evaluated;
+                run.snapshots_removed = outcome.snapshots_removed;
+                run.commits_pruned = outcome.commits_pruned;
+                run.content_blobs_removed = outcome.content_blobs_removed;
+                run.chunks_removed = outcome.chunks_removed;
+            }
+            Err(err) => run.error = Some(err.to_string()),
+        }
+        run
+    }
 }
 
 pub struct GarbageCollector {
     pool: PgPool,
+    orphan_sweep: OrphanSweepOptions,
 }
 
 impl GarbageCollector {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            orphan_sweep: OrphanSweepOptions::default(),
+        }
+    }
+
+    pub fn with_orphan_sweep(mut self, options: OrphanSweepOptions) -> Self {
+        self.orphan_sweep = options;
+        self
+    }
+
+    /// Applies every branch's retention policy once, then sweeps content
+    /// nothing references any more. With `detailed`, the outcome also names
+    /// the snapshots and commits removed per branch.
+    pub async fn run_once(&self, detailed: bool) -> Result<GcOutcome, ApiErrorKind> {
+        let mut outcome = self.apply_retention(detailed).await?;
+        let swept = sweep_orphans(&self.pool, self.orphan_sweep).await?;
+        outcome.content_blobs_removed = swept.content_blobs_removed;
+        outcome.chunks_removed = swept.chunks_removed;
+        Ok(outcome)
     }
 
-    pub async fn run_once(&self) -> Result<GcOutcome, ApiErrorKind> {
-        let mut outcome = GcOutcome::default();
+    async fn apply_retention(&self, detailed: bool) -> Result<GcOutcome, ApiErrorKind> {
+        let mut outcome = GcOutcome {
+            details: detailed.then(Vec::new),
+            ..GcOutcome::default()
+        };
 
         let policies = sqlx::query_as!(
             BranchPolicyRow,
@@ -123,24 +249,157 @@ impl GarbageCollector {
 
             outcome.snapshots_removed += removals.len();
 
-            for commit in removals {
-                if commit_is_protected(&self.pool, &repository, &commit).await? {
+            let mut pruned = Vec::new();
+            for commit in &removals {
+                if commit_is_protected(&self.pool, &repository, commit).await? {
                     continue;
                 }
-                match prune_commit_data(&self.pool, &repository, &commit).await {
-                    Ok(true) => outcome.commits_pruned += 1,
+                match prune_commit_data(&self.pool, &repository, commit).await {
+                    Ok(true) => pruned.push(commit.clone()),
                     Ok(false) => {}
                     Err(err) => {
                         warn!(error = ?err, repo = %repository, commit = %commit, "failed to prune commit during GC")
                     }
                 }
             }
+            outcome.commits_pruned += pruned.len();
+
+            if let Some(details) = outcome.details.as_mut() {
+                details.push(GcDetail {
+                    repository,
+                    branch,
+                    snapshots_removed: removals,
+                    commits_pruned: pruned,
+                });
+            }
         }
 
         Ok(outcome)
     }
 }
 
+/// Deletes content blobs that no file points at, with their chunk mappings and
+/// symbols, and then chunks that no mapping uses. Work is done in batches of
+/// `options.batch_size`, each in its own transaction, until nothing is left or
+/// the time budget runs out.
+pub async fn sweep_orphans(
+    pool: &PgPool,
+    options: OrphanSweepOptions,
+) -> Result<OrphanSweepOutcome, ApiErrorKind> {
+    let started = Instant::now();
+    let batch_size = options.batch_size.max(1);
+    let grace_secs = options.grace_period.as_secs_f64();
+    let mut outcome = OrphanSweepOutcome::default();
+
+    loop {
+        if started.elapsed() >= options.time_budget {
+            outcome.budget_exhausted = true;
+            return Ok(outcome);
+        }
+        let removed = sweep_orphan_blob_batch(pool, batch_size, grace_secs).await?;
+        outcome.content_blobs_removed += removed;
+        if (removed as i64) < batch_size {
+            break;
+        }
+    }
+
+    loop {
+        if started.elapsed() >= options.time_budget {
+            outcome.budget_exhausted = true;
+            return Ok(outcome);
+        }
+        let removed = sqlx::query(
+            "DELETE FROM chunks
+             WHERE chunk_hash IN (
+                 SELECT c.chunk_hash
+                 FROM chunks c
+                 WHERE (c.created_at IS NULL
+                        OR c.created_at < NOW() - make_interval(secs => $2))
+                   AND NOT EXISTS (
+                       SELECT 1 FROM content_blob_chunks cbc
+                       WHERE cbc.chunk_hash = c.chunk_hash
+                   )
+                 LIMIT $1
+                 FOR UPDATE SKIP LOCKED
+             )",
+        )
+        .bind(batch_size)
+        .bind(grace_secs)
+        .execute(pool)
+        .await
+        .map_err(ApiErrorKind::from)?
+        .rows_affected() as usize;
+        outcome.chunks_removed += removed;
+        if (removed as i64) < batch_size {
+            break;
+        }
+    }
+
+    Ok(outcome)
+}
+
+async fn sweep_orphan_blob_batch(
+    pool: &PgPool,
+    batch_size: i64,
+    grace_secs: f64,
+) -> Result<usize, ApiErrorKind> {
+    let mut tx = pool.begin().await.map_err(ApiErrorKind::from)?;
+
+    // Locking the rows keeps a file insert from pointing at a blob while it
+    // is being removed; the insert waits and then fails its foreign key.
+    let hashes: Vec<String> = sqlx::query_scalar(
+        "SELECT cb.hash
+         FROM content_blobs cb
+         WHERE (cb.created_at IS NULL
+                OR cb.created_at < NOW() - make_interval(secs => $2))
+           AND NOT EXISTS (SELECT 1 FROM files f WHERE f.content_hash = cb.hash)
+         LIMIT $1
+         FOR UPDATE SKIP LOCKED",
+    )
+    .bind(batch_size)
+    .bind(grace_secs)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(ApiErrorKind::from)?;
+
+    if hashes.is_empty() {
+        tx.commit().await.map_err(ApiErrorKind::from)?;
+        return Ok(0);
+    }
+
+    sqlx::query(
+        "DELETE FROM symbol_references WHERE symbol_id IN (
+            SELECT id FROM symbols WHERE content_hash = ANY($1)
+        )",
+    )
+    .bind(&hashes)
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiErrorKind::from)?;
+
+    sqlx::query("DELETE FROM symbols WHERE content_hash = ANY($1)")
+        .bind(&hashes)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiErrorKind::from)?;
+
+    sqlx::query("DELETE FROM content_blob_chunks WHERE content_hash = ANY($1)")
+        .bind(&hashes)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiErrorKind::from)?;
+
+    let removed = sqlx::query("DELETE FROM content_blobs WHERE hash = ANY($1)")
+        .bind(&hashes)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiErrorKind::from)?
+        .rows_affected() as usize;
+
+    tx.commit().await.map_err(ApiErrorKind::from)?;
+    Ok(removed)
+}
+
 fn compute_keep_set(
     snapshots: &[BranchSnapshotRow],
     latest_keep_count: i32,
@@ -284,6 +543,13 @@ pub async fn prune_commit_data(
 
     let files_deleted = files_deleted_result.rows_affected();
 
+    sqlx::query("DELETE FROM commits WHERE repository = $1 AND commit_sha = $2")
+        .bind(repository)
+        .bind(commit_sha)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiErrorKind::from)?;
+
     if files_deleted == 0 {
         tx.commit().await.map_err(ApiErrorKind::from)?;
         return Ok(false);
@@ -393,11 +659,19 @@ pub async fn prune_repository_data(
             .map_err(ApiErrorKind::from)?
             .rows_affected();
 
+        let commits_deleted = sqlx::query("DELETE FROM commits WHERE repository = $1")
+            .bind(repository)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiErrorKind::from)?
+            .rows_affected();
+
         total_deleted = total_deleted
             .saturating_add(branches_deleted as i64)
             .saturating_add(policies_deleted as i64)
             .saturating_add(live_deleted as i64)
-            .saturating_add(snapshots_deleted as i64);
+            .saturating_add(snapshots_deleted as i64)
+            .saturating_add(commits_deleted as i64);
 
         tx.commit().await.map_err(ApiErrorKind::from)?;
     }
@@ -503,3 +777,220 @@ pub async fn prune_repository_data(
 
     Ok(total_deleted)
 }
+
+/// Counts the rows [`prune_repository_data`] would report as deleted for
+/// `repository`, without deleting anything.
+pub async fn count_repository_data(pool: &PgPool, repository: &str) -> Result<i64, ApiErrorKind> {
+    sqlx::query_scalar(
+        "SELECT (SELECT COUNT(*) FROM branches WHERE repository = $1)
+              + (SELECT COUNT(*) FROM branch_policies WHERE repository = $1)
+              + (SELECT COUNT(*) FROM repo_live_branches WHERE repository = $1)
+              + (SELECT COUNT(*) FROM branch_snapshots WHERE repository = $1)
+              + (SELECT COUNT(*) FROM commits WHERE repository = $1)
+              + (SELECT COUNT(*) FROM files WHERE repository = $1)",
+    )
+    .bind(repository)
+    .fetch_one(pool)
+    .await
+    .map_err(ApiErrorKind::from)
+}
+
+#[derive(Debug, Serialize, Default, PartialEq, Eq)]
+pub struct PurgeCounts {
+    pub files_deleted: i64,
+    pub symbols_deleted: i64,
+    pub symbol_references_deleted: i64,
+    pub symbol_names_deleted: i64,
+    pub blob_chunks_deleted: i64,
+    pub chunks_deleted: i64,
+    /// Chunks of the purged blob that other blobs still use. Their text is
+    /// still searchable through those blobs.
+    pub shared_chunks_retained: i64,
+    pub blobs_deleted: i64,
+}
+
+/// Removes one content hash from every table in a single transaction. Files
+/// that pointed at it are deleted rather than tombstoned, since `files`
+/// requires a content hash; chunks are only removed once no other blob uses
+/// them.
+pub async fn purge_content_hash(
+    pool: &PgPool,
+    content_hash: &str,
+) -> Result<PurgeCounts, ApiErrorKind> {
+    let mut counts = PurgeCounts::default();
+    let mut tx = pool.begin().await.map_err(ApiErrorKind::from)?;
+
+    // Locking the blob row keeps a concurrent upload from re-linking it
+    // while its dependents are being removed.
+    let exists: Option<(String,)> =
+        sqlx::query_as("SELECT hash FROM content_blobs WHERE hash = $1 FOR UPDATE")
+            .bind(content_hash)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(ApiErrorKind::from)?;
+    if exists.is_none() {
+        tx.commit().await.map_err(ApiErrorKind::from)?;
+        return Ok(counts);
+    }
+
+    let chunk_hashes: Vec<String> = sqlx::query_scalar(
+        "SELECT DISTINCT chunk_hash FROM content_blob_chunks WHERE content_hash = $1",
+    )
+    .bind(content_hash)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(ApiErrorKind::from)?;
+
+    counts.symbol_references_deleted = sqlx::query(
+        "DELETE FROM symbol_references WHERE symbol_id IN (
+            SELECT id FROM symbols WHERE content_hash = $1
+        )",
+    )
+    .bind(content_hash)
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiErrorKind::from)?
+    .rows_affected() as i64;
+
+    let symbol_names: Vec<String> =
+        sqlx::query_scalar("DELETE FROM symbols WHERE content_hash = $1 RETURNING name_lc")
+            .bind(content_hash)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(ApiErrorKind::from)?;
+    counts.symbols_deleted = symbol_names.len() as i64;
+
+    counts.symbol_names_deleted = sqlx::query(
+        "DELETE FROM unique_symbols us
+         WHERE us.name_lc = ANY($1)
+           AND NOT EXISTS (SELECT 1 FROM symbols s WHERE s.name_lc = us.name_lc)",
+    )
+    .bind(&symbol_names)
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiErrorKind::from)?
+    .rows_affected() as i64;
+
+    counts.files_deleted = sqlx::query("DELETE FROM files WHERE content_hash = $1")
+        .bind(content_hash)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiErrorKind::from)?
+        .rows_affected() as i64;
+
+    counts.blob_chunks_deleted =
+        sqlx::query("DELETE FROM content_blob_chunks WHERE content_hash = $1")
+            .bind(content_hash)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiErrorKind::from)?
+            .rows_affected() as i64;
+
+    counts.chunks_deleted = sqlx::query(
+        "DELETE FROM chunks c
+         WHERE c.chunk_hash = ANY($1)
+           AND NOT EXISTS (
+               SELECT 1 FROM content_blob_chunks cbc WHERE cbc.chunk_hash = c.chunk_hash
+           )",
+    )
+    .bind(&chunk_hashes)
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiErrorKind::from)?
+    .rows_affected() as i64;
+    counts.shared_chunks_retained = chunk_hashes.len() as i64 - counts.chunks_deleted;
+
+    counts.blobs_deleted = sqlx::query("DELETE FROM content_blobs WHERE hash = $1")
+        .bind(content_hash)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiErrorKind::from)?
+        .rows_affected() as i64;
+
+    tx.commit().await.map_err(ApiErrorKind::from)?;
+
+    if counts.shared_chunks_retained > 0 {
+        warn!(
+            content_hash,
+            shared_chunks = counts.shared_chunks_retained,
+            "purged blob shared chunks with other blobs; those chunks were kept"
+        );
+    }
+
+    Ok(counts)
+}
+
+#[derive(Debug, Serialize, Default, PartialEq, Eq)]
+pub struct NamespaceRelinkCounts {
+    pub references_relinked: i64,
+    /// References dropped because the canonical namespace already held an
+    /// identical row.
+    pub duplicate_references_deleted: i64,
+    pub namespaces_deleted: i64,
+}
+
+/// Points every symbol reference at the canonical (lowest) `symbol_namespaces`
+/// id for its namespace string, then removes the rows it no longer uses.
+/// Duplicate namespace rows only appear when the table has been rebuilt or
+/// deduplicated out of band; relinking first keeps the `ON DELETE CASCADE`
+/// from taking their references with them.
+pub async fn relink_reference_namespaces(
+    conn: &mut PgConnection,
+) -> Result<NamespaceRelinkCounts, ApiErrorKind> {
+    const STALE_NAMESPACES: &str = "WITH stale AS (
+             SELECT id, canonical_id
+             FROM (
+                 SELECT id, MIN(id) OVER (PARTITION BY namespace) AS canonical_id
+                 FROM symbol_namespaces
+             ) ranked
+             WHERE id <> canonical_id
+         ) ";
+    let duplicate_references_deleted = sqlx::query(&format!(
+        "{STALE_NAMESPACES}
+         DELETE FROM symbol_references sr
+         USING stale
+         WHERE sr.namespace_id = stale.id
+           AND EXISTS (
+               SELECT 1
+               FROM symbol_references kept
+               WHERE kept.symbol_id = sr.symbol_id
+                 AND kept.namespace_id = stale.canonical_id
+                 AND kept.line_number = sr.line_number
+                 AND kept.column_number = sr.column_number
+                 AND kept.kind IS NOT DISTINCT FROM sr.kind
+           )"
+    ))
+    .execute(&mut *conn)
+    .await
+    .map_err(ApiErrorKind::from)?
+    .rows_affected() as i64;
+
+    let references_relinked = sqlx::query(&format!(
+        "{STALE_NAMESPACES}
+         UPDATE symbol_references sr
+         SET namespace_id = stale.canonical_id
+         FROM stale
+         WHERE sr.namespace_id = stale.id"
+    ))
+    .execute(&mut *conn)
+    .await
+    .map_err(ApiErrorKind::from)?
+    .rows_affected() as i64;
+
+    let namespaces_deleted = sqlx::query(&format!(
+        "{STALE_NAMESPACES}
+         DELETE FROM symbol_namespaces sn
+         USING stale
+         WHERE sn.id = stale.id"
+    ))
+    .execute(&mut *conn)
+    .await
+    .map_err(ApiErrorKind::from)?
+    .rows_affected() as i64;
+
+    Ok(NamespaceRelinkCounts {
+        references_relinked,
+        duplicate_references_deleted,
+        namespaces_deleted,
+    })
+}
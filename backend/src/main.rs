@@ -1,18 +1,23 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::future::Future;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::mem;
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
+mod api_diff;
+mod backfill;
 mod gc;
+mod metrics;
+mod schema;
 
 use anyhow::{Context, Result, anyhow};
 use axum::{
     Json, Router,
-    extract::{DefaultBodyLimit, State},
+    extract::{DefaultBodyLimit, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::{get, post},
@@ -21,11 +26,15 @@ use base64::Engine;
 use base64::engine::general_purpose::STANDARD as BASE64;
 use clap::Parser;
 use futures::{StreamExt, TryStreamExt, stream::FuturesUnordered};
+use pointer_indexer_types::validation::{
+    describe_rejections, normalize_file_pointers, validate_branch_heads, validate_commit_metadata,
+};
 use pointer_indexer_types::{
-    BranchHead, ChunkMapping, ContentBlob, FilePointer, ReferenceRecord, SymbolNamespaceRecord,
-    SymbolRecord, UniqueChunk,
+    BranchHead, ChunkMapping, CommitMetadata, ContentBlob, FilePointer, ManifestCompression,
+    ReferenceRecord, SymbolNamespaceRecord, SymbolRecord, UniqueChunk,
 };
 use serde::{Deserialize, Serialize, de::IgnoredAny};
+use sha2::{Digest, Sha256};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::{Acquire, PgConnection, PgPool, Postgres, QueryBuilder, Transaction};
 use tempfile::Builder;
@@ -33,14 +42,21 @@ use thiserror::Error;
 use tokio::fs::File as TokioFile;
 use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader as TokioBufReader};
 use tokio::net::TcpListener;
+use tokio::task::JoinSet;
 use tokio::{signal, time};
 use tracing::info;
 
+use crate::api_diff::ApiDiff;
+use crate::backfill::{BackfillOptions, BackfillProgress, NameLcGuard};
 use crate::gc::{
-    GarbageCollector, commit_is_protected, is_latest_commit_on_any_branch, prune_commit_data,
-    prune_repository_data,
+    GarbageCollector, GcDetail, GcStatus, LastGcRun, NamespaceRelinkCounts, OrphanSweepOptions,
+    OrphanSweepOutcome, PurgeCounts, commit_is_protected, count_repository_data,
+    is_latest_commit_on_any_branch, prune_commit_data, prune_repository_data, purge_content_hash,
+    relink_reference_namespaces, sweep_orphans,
 };
-use chrono::Utc;
+use crate::metrics::IngestMetrics;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
 use zstd::stream::read::Decoder;
 
 #[derive(Debug, Parser)]
@@ -57,12 +73,46 @@ struct ServerConfig {
     enable_gc: bool,
     #[arg(long, env = "GC_INTERVAL_SECS", default_value_t = 3600)]
     gc_interval_secs: u64,
+    /// Orphaned blobs or chunks deleted per transaction by the GC sweep.
+    #[arg(long, env = "GC_ORPHAN_BATCH_SIZE", default_value_t = 500)]
+    gc_orphan_batch_size: i64,
+    /// Longest a single orphan sweep keeps starting new batches.
+    #[arg(long, env = "GC_ORPHAN_TIME_BUDGET_SECS", default_value_t = 30)]
+    gc_orphan_time_budget_secs: u64,
+    /// Unreferenced blobs and chunks younger than this are kept, since an
+    /// upload in progress may not have linked them yet.
+    #[arg(long, env = "GC_ORPHAN_GRACE_SECS", default_value_t = 3600)]
+    gc_orphan_grace_secs: u64,
+    /// Hashes looked up per query by `chunks/need`; larger requests are split
+    /// into concurrent batches.
+    #[arg(long, env = "CHUNK_NEED_BATCH_SIZE", default_value_t = 10_000)]
+    chunk_need_batch_size: usize,
+    /// Store uploaded chunk text zstd-compressed. Compressed chunks are still
+    /// served by file views and snippets but are not matched by content search.
+    #[arg(long, env = "COMPRESS_CHUNK_CONTENT", default_value_t = false)]
+    compress_chunk_content: bool,
+    /// Shards accepted by one `manifest/shards` request.
+    #[arg(long, env = "MAX_BULK_SHARDS", default_value_t = 64)]
+    max_bulk_shards: usize,
+    /// Build the `lower(text_content)` trigram index that the web server's
+    /// lowercase content search queries. Off by default, since the index
+    /// roughly doubles chunk trigram storage.
+    #[arg(long, env = "LOWERCASE_CONTENT_SEARCH", default_value_t = false)]
+    lowercase_content_search: bool,
 }
 
 #[derive(Clone)]
 struct AppState {
     pool: PgPool,
     scratch_dir: PathBuf,
+    chunk_need_batch_size: usize,
+    compress_chunk_content: bool,
+    max_bulk_shards: usize,
+    orphan_sweep: OrphanSweepOptions,
+    /// Pause between background GC runs; `None` when the GC loop is off.
+    gc_interval: Option<Duration>,
+    gc_status: GcStatus,
+    metrics: Arc<IngestMetrics>,
 }
 
 #[derive(Debug, Error)]
@@ -75,6 +125,10 @@ enum ApiErrorKind {
     Compression(#[from] std::io::Error),
     #[error("internal error: {0}")]
     Internal(#[from] anyhow::Error),
+    /// Records the backend refuses to store, e.g. file paths that escape the
+    /// repository root.
+    #[error("{0}")]
+    Invalid(String),
 }
 
 #[derive(Debug)]
@@ -107,6 +161,9 @@ impl From<ApiErrorKind> for AppError {
                 tracing::error!(error = ?err, "internal error");
                 AppError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
             }
+            ApiErrorKind::Invalid(message) => {
+                AppError::new(StatusCode::UNPROCESSABLE_ENTITY, message)
+            }
         }
     }
 }
@@ -135,6 +192,25 @@ struct ChunkNeedResponse {
     missing: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ChunkStatRequest {
+    hashes: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ChunkStat {
+    hash: String,
+    /// Line count recorded by a blob mapping that uses the chunk; `None` when
+    /// the chunk was uploaded but no mapping references it yet.
+    line_count: Option<i32>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ChunkStatResponse {
+    present: Vec<ChunkStat>,
+    missing: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ContentNeedRequest {
     hashes: Vec<String>,
@@ -162,12 +238,30 @@ struct ManifestChunkPayload {
     chunk_index: i32,
     total_chunks: i32,
     data: String,
+    /// Hex SHA-256 of the base64-decoded `data`, checked before storing it.
+    sha256: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ManifestFinalizePayload {
     upload_id: String,
+    compression: Option<ManifestCompression>,
+    /// Legacy flag meaning zstd; ignored when `compression` is set.
     compressed: Option<bool>,
+    /// Hex SHA-256 of every chunk concatenated in order, before decompression.
+    manifest_sha256: Option<String>,
+}
+
+/// Records ingested from a finalized manifest, per section, so clients can
+/// check them against what they sent.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+struct ManifestFinalizeResponse {
+    files: u64,
+    symbols: u64,
+    references: u64,
+    namespaces: u64,
+    branches: u64,
+    commits: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -175,7 +269,16 @@ struct ManifestShardPayload {
     section: String,
     shard_index: Option<u64>,
     data: String,
+    compression: Option<ManifestCompression>,
+    /// Legacy flag meaning zstd; ignored when `compression` is set.
     compressed: Option<bool>,
+    /// Hex SHA-256 of the base64-decoded `data`, before decompression.
+    sha256: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestShardsPayload {
+    shards: Vec<ManifestShardPayload>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -200,6 +303,8 @@ enum ManifestEnvelope {
     ReferenceRecord(ReferenceRecord),
     #[serde(rename = "branch_head")]
     BranchHead(BranchHead),
+    #[serde(rename = "commit_metadata")]
+    CommitMetadata(CommitMetadata),
 }
 
 #[tokio::main]
@@ -229,35 +334,78 @@ async fn main() -> Result<()> {
         .await
         .context("failed to connect to postgres")?;
 
-    sqlx::migrate!("./migrations")
+    let migrator = sqlx::migrate!("./migrations");
+    schema::preflight(&pool, &migrator)
+        .await
+        .context("schema preflight failed")?;
+
+    migrator
         .run(&pool)
         .await
         .context("database migration failed")?;
 
+    if let Err(err) = install_name_lc_trigger_if_ready(&pool).await {
+        tracing::warn!(error = ?err, "failed to check the symbols.name_lc sync trigger");
+    }
+
+    if config.lowercase_content_search {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            match sqlx::raw_sql(LOWER_CONTENT_INDEX_SQL).execute(&pool).await {
+                Ok(_) => info!("lowercase content trigram index is in place"),
+                Err(err) => tracing::warn!(
+                    error = ?err,
+                    "failed to build the lowercase content trigram index"
+                ),
+            }
+        });
+    }
+
     let app_state = AppState {
         pool: pool.clone(),
         scratch_dir: config.scratch_dir.clone(),
+        chunk_need_batch_size: config.chunk_need_batch_size.max(1),
+        compress_chunk_content: config.compress_chunk_content,
+        max_bulk_shards: config.max_bulk_shards.max(1),
+        orphan_sweep: OrphanSweepOptions {
+            batch_size: config.gc_orphan_batch_size.max(1),
+            time_budget: Duration::from_secs(config.gc_orphan_time_budget_secs),
+            grace_period: Duration::from_secs(config.gc_orphan_grace_secs),
+        },
+        gc_interval: config
+            .enable_gc
+            .then(|| Duration::from_secs(config.gc_interval_secs.max(60))),
+        gc_status: GcStatus::default(),
+        metrics: Arc::default(),
     };
 
-    if config.enable_gc {
-        let interval = Duration::from_secs(config.gc_interval_secs.max(60));
-        spawn_gc_loop(pool.clone(), interval);
+    if let Some(interval) = app_state.gc_interval {
+        spawn_gc_loop(
+            pool.clone(),
+            interval,
+            app_state.orphan_sweep,
+            app_state.gc_status.clone(),
+        );
     }
 
     let app = Router::new()
         // New ingestion routes
         .route("/api/v1/blobs/upload", post(blobs_upload))
         .route("/api/v1/chunks/need", post(chunks_need))
+        .route("/api/v1/chunks/stat", post(chunks_stat))
         .route("/api/v1/chunks/upload", post(chunks_upload))
         .route("/api/v1/mappings/upload", post(mappings_upload))
         .route("/api/v1/blobs/need", post(blobs_need))
         .route("/api/v1/index/blobs/upload", post(blobs_upload))
         .route("/api/v1/index/chunks/need", post(chunks_need))
+        .route("/api/v1/index/chunks/stat", post(chunks_stat))
         .route("/api/v1/index/chunks/upload", post(chunks_upload))
         .route("/api/v1/index/mappings/upload", post(mappings_upload))
         .route("/api/v1/index/blobs/need", post(blobs_need))
         .route("/api/v1/manifest/shard", post(manifest_shard))
         .route("/api/v1/index/manifest/shard", post(manifest_shard))
+        .route("/api/v1/manifest/shards", post(manifest_shards))
+        .route("/api/v1/index/manifest/shards", post(manifest_shards))
         // Manifest upload routes
         .route("/api/v1/manifest/chunk", post(manifest_chunk))
         .route("/api/v1/manifest/finalize", post(manifest_finalize))
@@ -266,9 +414,21 @@ async fn main() -> Result<()> {
         // Pruning routes
         .route("/api/v1/prune/commit", post(prune_commit_handler))
         .route("/api/v1/prune/branch", post(prune_branch_handler))
+        .route("/api/v1/prune/branch_delete", post(branch_delete_handler))
         .route("/api/v1/prune/repo", post(prune_repo_handler))
         .route("/api/v1/prune/policy", post(apply_retention_policy_handler))
+        .route("/api/v1/symbols/api_diff", post(api_diff_handler))
+        .route("/api/v1/repos", get(repos_handler))
+        .route("/api/v1/branches/head", post(branch_head_handler))
+        .route("/api/v1/index/branches/head", post(branch_head_handler))
         .route("/api/v1/admin/gc", post(run_gc_handler))
+        .route("/api/v1/admin/gc/status", get(gc_status_handler))
+        .route("/api/v1/admin/gc/orphans", post(sweep_orphans_handler))
+        .route("/api/v1/admin/purge/content", post(purge_content_handler))
+        .route(
+            "/api/v1/admin/backfill_name_lc",
+            post(backfill_name_lc_handler),
+        )
         .route(
             "/api/v1/admin/rebuild_symbol_cache",
             post(rebuild_symbol_cache_handler),
@@ -281,7 +441,12 @@ async fn main() -> Result<()> {
             "/api/v1/admin/refresh_symbol_cache",
             post(refresh_symbol_cache_handler),
         )
+        .route(
+            "/api/v1/admin/relink_namespaces",
+            post(relink_namespaces_handler),
+        )
         .route("/healthz", get(health_check))
+        .route("/metrics", get(metrics_handler))
         .with_state(app_state)
         .layer(DefaultBodyLimit::max(64 * 1024 * 1024));
 
@@ -299,13 +464,51 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn spawn_gc_loop(pool: PgPool, interval: Duration) {
+/// Opt-in index for lowercase content search. Built concurrently in the
+/// background so neither startup nor ingestion waits for it.
+const LOWER_CONTENT_INDEX_SQL: &str =
+    include_str!("../deferred_migrations/chunks_lower_content_trgm.sql");
+
+/// Deferred follow-up to the name_lc backfill: the sync trigger only goes in
+/// once the backfill has verified every row.
+async fn install_name_lc_trigger_if_ready(pool: &PgPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let guard = backfill::ensure_name_lc_trigger(&mut tx).await?;
+    tx.commit().await?;
+    match guard {
+        NameLcGuard::AlreadyInstalled => {}
+        NameLcGuard::Installed => info!("installed symbols.name_lc sync trigger"),
+        NameLcGuard::AwaitingBackfill { clean_passes } => info!(
+            clean_passes,
+            required = backfill::REQUIRED_CLEAN_PASSES,
+            "deferring symbols.name_lc sync trigger until POST /api/v1/admin/backfill_name_lc completes clean passes"
+        ),
+        NameLcGuard::BadRowsRemain => tracing::warn!(
+            "deferring symbols.name_lc sync trigger: rows disagree with LOWER(name); rerun the name_lc backfill"
+        ),
+    }
+    Ok(())
+}
+
+fn spawn_gc_loop(
+    pool: PgPool,
+    interval: Duration,
+    orphan_sweep: OrphanSweepOptions,
+    status: GcStatus,
+) {
     tokio::spawn(async move {
-        let collector = GarbageCollector::new(pool);
+        let collector = GarbageCollector::new(pool).with_orphan_sweep(orphan_sweep);
         loop {
-            if let Err(err) = collector.run_once().await {
+            let started_at = Utc::now();
+            let started = std::time::Instant::now();
+            let result = collector.run_once(false).await;
+            if let Err(err) = &result {
                 tracing::error!(error = ?err, "background garbage collection run failed");
             }
+            let run = LastGcRun::new(started_at, started.elapsed(), &result);
+            *status
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(run);
             time::sleep(interval).await;
         }
     });
@@ -376,21 +579,109 @@ async fn chunks_need(
         }));
     }
 
-    let existing: Vec<(String,)> =
-        sqlx::query_as("SELECT chunk_hash FROM chunks WHERE chunk_hash = ANY($1)")
-            .bind(&payload.hashes)
-            .fetch_all(&state.pool)
+    let missing = find_missing_chunks(
+        &state.pool,
+        payload.hashes,
+        state.chunk_need_batch_size,
+        MAX_PARALLEL_INGEST,
+    )
+    .await?;
+
+    Ok(Json(ChunkNeedResponse { missing }))
+}
+
+/// Returns the hashes with no `chunks` row, in request order and without
+/// repeats. Hashes are looked up `batch_size` at a time, with up to
+/// `max_parallel` queries in flight.
+async fn find_missing_chunks(
+    pool: &PgPool,
+    hashes: Vec<String>,
+    batch_size: usize,
+    max_parallel: usize,
+) -> Result<Vec<String>, ApiErrorKind> {
+    let mut futures = FuturesUnordered::new();
+    let mut present = HashSet::new();
+
+    for batch in hashes.chunks(batch_size.max(1)) {
+        let pool = pool.clone();
+        let batch = batch.to_vec();
+        futures.push(tokio::spawn(async move {
+            sqlx::query_scalar::<_, String>(
+                "SELECT chunk_hash FROM chunks WHERE chunk_hash = ANY($1)",
+            )
+            .bind(batch)
+            .fetch_all(&pool)
             .await
-            .map_err(ApiErrorKind::from)?;
+        }));
 
-    let present: HashSet<String> = existing.into_iter().map(|row| row.0).collect();
-    let missing: Vec<String> = payload
-        .hashes
+        if futures.len() >= max_parallel
+            && max_parallel > 0
+            && let Some(res) = futures.next().await
+        {
+            present.extend(res.map_err(|err| ApiErrorKind::Internal(anyhow!(err)))??);
+        }
+    }
+
+    while let Some(res) = futures.next().await {
+        present.extend(res.map_err(|err| ApiErrorKind::Internal(anyhow!(err)))??);
+    }
+
+    let mut seen = HashSet::new();
+    Ok(hashes
         .into_iter()
-        .filter(|h| !present.contains(h))
-        .collect();
+        .filter(|hash| !present.contains(hash) && seen.insert(hash.clone()))
+        .collect())
+}
 
-    Ok(Json(ChunkNeedResponse { missing }))
+async fn chunks_stat(
+    State(state): State<AppState>,
+    Json(payload): Json<ChunkStatRequest>,
+) -> ApiResult<Json<ChunkStatResponse>> {
+    let response = stat_chunks(&state.pool, payload.hashes, state.chunk_need_batch_size).await?;
+    Ok(Json(response))
+}
+
+/// Splits `hashes` into chunks already stored, with a line count taken from
+/// any mapping that uses them, and chunks still missing. Both lists keep
+/// request order without repeats.
+async fn stat_chunks(
+    pool: &PgPool,
+    hashes: Vec<String>,
+    batch_size: usize,
+) -> Result<ChunkStatResponse, ApiErrorKind> {
+    let mut line_counts: HashMap<String, Option<i32>> = HashMap::new();
+    for batch in hashes.chunks(batch_size.max(1)) {
+        let rows: Vec<(String, Option<i32>)> = sqlx::query_as(
+            "SELECT c.chunk_hash,
+                    (SELECT cbc.chunk_line_count
+                     FROM content_blob_chunks cbc
+                     WHERE cbc.chunk_hash = c.chunk_hash
+                     LIMIT 1)
+             FROM chunks c
+             WHERE c.chunk_hash = ANY($1)",
+        )
+        .bind(batch)
+        .fetch_all(pool)
+        .await
+        .map_err(ApiErrorKind::from)?;
+        line_counts.extend(rows);
+    }
+
+    let mut seen = HashSet::new();
+    let mut response = ChunkStatResponse {
+        present: Vec::new(),
+        missing: Vec::new(),
+    };
+    for hash in hashes {
+        if !seen.insert(hash.clone()) {
+            continue;
+        }
+        match line_counts.get(&hash) {
+            Some(&line_count) => response.present.push(ChunkStat { hash, line_count }),
+            None => response.missing.push(hash),
+        }
+    }
+    Ok(response)
 }
 
 async fn blobs_need(
@@ -428,9 +719,24 @@ async fn chunks_upload(
         return Ok(StatusCode::ACCEPTED);
     }
 
-    let mut qb = QueryBuilder::new("INSERT INTO chunks (chunk_hash, text_content) ");
-    qb.push_values(payload.chunks, |mut b, chunk| {
-        b.push_bind(chunk.chunk_hash).push_bind(chunk.text_content);
+    let rows = payload
+        .chunks
+        .into_iter()
+        .map(|chunk| {
+            if !state.compress_chunk_content {
+                return Ok((chunk.chunk_hash, chunk.text_content, None));
+            }
+            let compressed = compress_chunk_text(&chunk.text_content)?;
+            Ok((chunk.chunk_hash, String::new(), Some(compressed)))
+        })
+        .collect::<Result<Vec<_>, ApiErrorKind>>()?;
+
+    let mut qb =
+        QueryBuilder::new("INSERT INTO chunks (chunk_hash, text_content, compressed_content) ");
+    qb.push_values(rows, |mut b, (chunk_hash, text_content, compressed)| {
+        b.push_bind(chunk_hash)
+            .push_bind(text_content)
+            .push_bind(compressed);
     });
     qb.push(" ON CONFLICT (chunk_hash) DO NOTHING");
 
@@ -442,6 +748,12 @@ async fn chunks_upload(
     Ok(StatusCode::ACCEPTED)
 }
 
+/// Compresses chunk text for the `compressed_content` column; readers decode
+/// it back to the exact original bytes.
+fn compress_chunk_text(text: &str) -> Result<Vec<u8>, ApiErrorKind> {
+    Ok(zstd::stream::encode_all(text.as_bytes(), 0)?)
+}
+
 async fn mappings_upload(
     State(state): State<AppState>,
     Json(payload): Json<ChunkMappingUploadRequest>,
@@ -474,6 +786,25 @@ async fn manifest_chunk(
     State(state): State<AppState>,
     Json(payload): Json<ManifestChunkPayload>,
 ) -> ApiResult<StatusCode> {
+    let data = decode_manifest_chunk(&payload)?;
+
+    sqlx::query(
+        "INSERT INTO upload_chunks (upload_id, chunk_index, total_chunks, data)\n         VALUES ($1, $2, $3, $4)\n         ON CONFLICT (upload_id, chunk_index) DO UPDATE\n         SET total_chunks = EXCLUDED.total_chunks, data = EXCLUDED.data",
+    )
+    .bind(&payload.upload_id)
+    .bind(payload.chunk_index)
+    .bind(payload.total_chunks)
+    .bind(data)
+    .execute(&state.pool)
+    .await
+    .map_err(ApiErrorKind::from)?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Validates a chunk's position and base64-decodes it, checking the decoded
+/// bytes against `sha256` when the client sent one.
+fn decode_manifest_chunk(payload: &ManifestChunkPayload) -> ApiResult<Vec<u8>> {
     if payload.chunk_index < 0
         || payload.total_chunks <= 0
         || payload.chunk_index >= payload.total_chunks
@@ -490,54 +821,196 @@ async fn manifest_chunk(
             format!("invalid base64 data: {err}"),
         )
     })?;
+    if let Some(expected) = &payload.sha256 {
+        verify_sha256(
+            &format!("manifest chunk {}", payload.chunk_index),
+            expected,
+            &hex::encode(Sha256::digest(&data)),
+        )?;
+    }
+    Ok(data)
+}
 
-    sqlx::query(
-        "INSERT INTO upload_chunks (upload_id, chunk_index, total_chunks, data)\n         VALUES ($1, $2, $3, $4)\n         ON CONFLICT (upload_id, chunk_index) DO UPDATE\n         SET total_chunks = EXCLUDED.total_chunks, data = EXCLUDED.data",
-    )
-    .bind(&payload.upload_id)
-    .bind(payload.chunk_index)
-    .bind(payload.total_chunks)
-    .bind(data)
-    .execute(&state.pool)
-    .await
-    .map_err(ApiErrorKind::from)?;
-
-    Ok(StatusCode::ACCEPTED)
+/// Rejects an upload whose digest differs from the one the client computed,
+/// which usually means the body was truncated or altered in transit.
+fn verify_sha256(what: &str, expected: &str, actual: &str) -> ApiResult<()> {
+    if expected.trim().eq_ignore_ascii_case(actual) {
+        return Ok(());
+    }
+    Err(AppError::new(
+        StatusCode::UNPROCESSABLE_ENTITY,
+        format!(
+            "{what} sha256 mismatch: expected {}, computed {actual}; the upload was likely truncated or corrupted in transit",
+            expected.trim()
+        ),
+    ))
 }
 
 async fn manifest_shard(
     State(state): State<AppState>,
     Json(payload): Json<ManifestShardPayload>,
 ) -> ApiResult<StatusCode> {
-    let compressed = payload.compressed.unwrap_or(true);
+    let data = decode_manifest_shard(&payload)?;
+    let rows = process_manifest_section(
+        &state.pool,
+        &state.metrics,
+        &payload.section,
+        payload.shard_index,
+        &data,
+    )
+    .await?;
+    state.metrics.record_rows(&payload.section, rows as u64);
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Ingests several shards, possibly of different sections, in one request.
+/// Every shard is decoded before any is ingested. Namespaces, symbols and
+/// file pointers go first, then references, then branch heads, so a branch
+/// never points at a snapshot whose rows are still arriving. Shards within a
+/// phase run concurrently.
+async fn manifest_shards(
+    State(state): State<AppState>,
+    Json(payload): Json<ManifestShardsPayload>,
+) -> ApiResult<(StatusCode, Json<ManifestFinalizeResponse>)> {
+    if payload.shards.len() > state.max_bulk_shards {
+        return Err(AppError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "at most {} shards per request, got {}",
+                state.max_bulk_shards,
+                payload.shards.len()
+            ),
+        ));
+    }
+
+    let mut phases: [Vec<(ManifestShardPayload, Vec<u8>)>; 3] = Default::default();
+    for shard in payload.shards {
+        let Some(phase) = manifest_section_phase(&shard.section) else {
+            return Err(AppError::new(
+                StatusCode::BAD_REQUEST,
+                format!("unknown manifest shard section: {}", shard.section),
+            ));
+        };
+        let data = decode_manifest_shard(&shard)?;
+        phases[phase].push((shard, data));
+    }
+
+    let mut ingested = ManifestFinalizeResponse::default();
+    for shards in phases {
+        let counts: Vec<(String, usize)> = futures::stream::iter(shards)
+            .map(|(shard, data)| {
+                let pool = state.pool.clone();
+                let metrics = state.metrics.clone();
+                async move {
+                    let rows = process_manifest_section(
+                        &pool,
+                        &metrics,
+                        &shard.section,
+                        shard.shard_index,
+                        &data,
+                    )
+                    .await?;
+                    Ok::<_, ApiErrorKind>((shard.section, rows))
+                }
+            })
+            .buffer_unordered(MAX_PARALLEL_INGEST)
+            .try_collect()
+            .await?;
+        for (section, rows) in counts {
+            state.metrics.record_rows(&section, rows as u64);
+            let total = match section.as_str() {
+                "file_pointer" => &mut ingested.files,
+                "symbol_record" => &mut ingested.symbols,
+                "reference_record" => &mut ingested.references,
+                "symbol_namespace" => &mut ingested.namespaces,
+                "commit_metadata" => &mut ingested.commits,
+                _ => &mut ingested.branches,
+            };
+            *total += rows as u64;
+        }
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(ingested)))
+}
+
+/// The `manifest_shards` phase a section is ingested in, or `None` for an
+/// unknown section.
+fn manifest_section_phase(section: &str) -> Option<usize> {
+    match section {
+        "file_pointer" | "symbol_namespace" | "symbol_record" | "commit_metadata" => Some(0),
+        "reference_record" => Some(1),
+        "branch_head" => Some(2),
+        _ => None,
+    }
+}
+
+/// Base64-decodes and decompresses a shard. Shards default to zstd.
+fn decode_manifest_shard(payload: &ManifestShardPayload) -> ApiResult<Vec<u8>> {
     let bytes = BASE64.decode(payload.data.as_bytes()).map_err(|err| {
         AppError::new(
             StatusCode::BAD_REQUEST,
             format!("invalid base64 data: {err}"),
         )
     })?;
+    if let Some(expected) = &payload.sha256 {
+        verify_sha256(
+            &format!("manifest shard {}", payload.section),
+            expected,
+            &hex::encode(Sha256::digest(&bytes)),
+        )?;
+    }
 
-    let data = if compressed {
-        let mut decoder = Decoder::new(bytes.as_slice()).map_err(ApiErrorKind::Compression)?;
-        let mut out = Vec::new();
-        decoder
-            .read_to_end(&mut out)
-            .map_err(ApiErrorKind::Compression)?;
-        out
-    } else {
-        bytes
-    };
+    let compression = manifest_compression(
+        payload.compression,
+        payload.compressed,
+        ManifestCompression::Zstd,
+    );
+    if compression == ManifestCompression::None {
+        return Ok(bytes);
+    }
+    let mut out = Vec::new();
+    manifest_decoder(bytes.as_slice(), compression)
+        .and_then(|mut decoder| decoder.read_to_end(&mut out))
+        .map_err(ApiErrorKind::Compression)?;
+    Ok(out)
+}
 
-    process_manifest_section(&state.pool, &payload.section, payload.shard_index, &data).await?;
+/// Resolves a payload's codec, falling back to the legacy `compressed` flag
+/// (which only ever meant zstd) and then to `default`.
+fn manifest_compression(
+    compression: Option<ManifestCompression>,
+    compressed: Option<bool>,
+    default: ManifestCompression,
+) -> ManifestCompression {
+    match (compression, compressed) {
+        (Some(compression), _) => compression,
+        (None, Some(true)) => ManifestCompression::Zstd,
+        (None, Some(false)) => ManifestCompression::None,
+        (None, None) => default,
+    }
+}
 
-    Ok(StatusCode::ACCEPTED)
+fn manifest_decoder<'a, R: Read + 'a>(
+    reader: R,
+    compression: ManifestCompression,
+) -> std::io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        ManifestCompression::Zstd => Box::new(Decoder::new(reader)?),
+        ManifestCompression::Gzip => Box::new(GzDecoder::new(reader)),
+        ManifestCompression::None => Box::new(reader),
+    })
 }
 
 async fn manifest_finalize(
     State(state): State<AppState>,
     Json(payload): Json<ManifestFinalizePayload>,
-) -> ApiResult<StatusCode> {
-    let compressed = payload.compressed.unwrap_or(false);
+) -> ApiResult<(StatusCode, Json<ManifestFinalizeResponse>)> {
+    let compression = manifest_compression(
+        payload.compression,
+        payload.compressed,
+        ManifestCompression::None,
+    );
     let mut rows = sqlx::query_as::<_, UploadChunkRow>(
         "SELECT chunk_index, total_chunks, data \
          FROM upload_chunks \
@@ -553,6 +1026,7 @@ async fn manifest_finalize(
         .map_err(ApiErrorKind::Compression)?;
     let mut expected_total: Option<i32> = None;
     let mut seen_chunks: i32 = 0;
+    let mut digest = Sha256::new();
 
     while let Some(row) = rows.try_next().await.map_err(ApiErrorKind::from)? {
         if let Some(expected) = expected_total {
@@ -582,6 +1056,7 @@ async fn manifest_finalize(
         temp_file
             .write_all(&row.data)
             .map_err(ApiErrorKind::Compression)?;
+        digest.update(&row.data);
         seen_chunks += 1;
     }
 
@@ -601,6 +1076,13 @@ async fn manifest_finalize(
             "missing manifest chunks",
         ));
     }
+    if let Some(expected) = &payload.manifest_sha256 {
+        verify_sha256(
+            &format!("manifest {}", payload.upload_id),
+            expected,
+            &hex::encode(digest.finalize()),
+        )?;
+    }
 
     temp_file
         .seek(SeekFrom::Start(0))
@@ -610,12 +1092,11 @@ async fn manifest_finalize(
         .prefix("pointer-backend-manifest")
         .tempfile_in(&state.scratch_dir)
         .map_err(ApiErrorKind::Compression)?;
-    if compressed {
-        let mut decoder = Decoder::new(temp_file).map_err(ApiErrorKind::Compression)?;
+    // The boxed decoder is not `Send`, so it must be gone before the next await.
+    {
+        let mut decoder =
+            manifest_decoder(temp_file, compression).map_err(ApiErrorKind::Compression)?;
         std::io::copy(&mut decoder, &mut plain_file).map_err(ApiErrorKind::Compression)?;
-    } else {
-        let mut source = temp_file;
-        std::io::copy(&mut source, &mut plain_file).map_err(ApiErrorKind::Compression)?;
     }
 
     plain_file
@@ -627,7 +1108,7 @@ async fn manifest_finalize(
         .try_clone()
         .map_err(ApiErrorKind::Compression)?;
     let reader = TokioBufReader::new(TokioFile::from_std(std_file));
-    ingest_manifest_stream(&state.pool, reader).await?;
+    let ingested = ingest_manifest_stream(&state.pool, &state.metrics, reader).await?;
 
     sqlx::query("DELETE FROM upload_chunks WHERE upload_id = $1")
         .bind(&payload.upload_id)
@@ -635,63 +1116,97 @@ async fn manifest_finalize(
         .await
         .map_err(ApiErrorKind::from)?;
 
-    Ok(StatusCode::CREATED)
+    for (section, rows) in [
+        ("file_pointer", ingested.files),
+        ("symbol_record", ingested.symbols),
+        ("reference_record", ingested.references),
+        ("symbol_namespace", ingested.namespaces),
+        ("branch_head", ingested.branches),
+        ("commit_metadata", ingested.commits),
+    ] {
+        state.metrics.record_rows(section, rows);
+    }
+    state.metrics.record_finalization();
+
+    info!(
+        upload_id = %payload.upload_id,
+        files = ingested.files,
+        symbols = ingested.symbols,
+        references = ingested.references,
+        namespaces = ingested.namespaces,
+        branches = ingested.branches,
+        commits = ingested.commits,
+        "manifest ingested"
+    );
+    Ok((StatusCode::CREATED, Json(ingested)))
 }
 
 async fn process_manifest_section(
     pool: &PgPool,
+    metrics: &Arc<IngestMetrics>,
     section: &str,
     shard_index: Option<u64>,
     data: &[u8],
-) -> Result<(), ApiErrorKind> {
-    match section {
+) -> Result<usize, ApiErrorKind> {
+    let rows = match section {
         "file_pointer" => process_file_pointer_data(pool, data).await?,
         "symbol_namespace" => process_symbol_namespace_data(pool, data).await?,
         "symbol_record" => process_symbol_data(pool, data).await?,
-        "reference_record" => process_reference_data(pool, data).await?,
+        "reference_record" => process_reference_data(pool, metrics, data).await?,
         "branch_head" => process_branch_data(pool, data).await?,
+        "commit_metadata" => process_commit_metadata_data(pool, data).await?,
         other => {
             return Err(ApiErrorKind::Internal(anyhow!(
                 "unknown manifest shard section: {}",
                 other
             )));
         }
-    }
+    };
 
     if let Some(idx) = shard_index {
         info!(section = section, shard = idx, "manifest shard ingested");
     }
 
-    Ok(())
+    Ok(rows)
 }
 
-async fn process_file_pointer_data(pool: &PgPool, data: &[u8]) -> Result<(), ApiErrorKind> {
-    let chunks = chunk_records(data, |line| {
+async fn process_file_pointer_data(pool: &PgPool, data: &[u8]) -> Result<usize, ApiErrorKind> {
+    let mut chunks = chunk_records(data, |line| {
         serde_json::from_slice::<FilePointer>(line).map_err(ApiErrorKind::Serde)
     })?;
+    let rejected: Vec<String> = chunks
+        .iter_mut()
+        .filter_map(|chunk| normalize_file_pointers(chunk).err())
+        .flatten()
+        .collect();
+    reject_records("file pointers", rejected)?;
+    let rows = chunks.iter().map(Vec::len).sum();
     ingest_chunks(
         pool,
         chunks,
         insert_file_pointers_batch,
         MAX_PARALLEL_INGEST,
     )
-    .await
+    .await?;
+    Ok(rows)
 }
 
-async fn process_symbol_data(pool: &PgPool, data: &[u8]) -> Result<(), ApiErrorKind> {
+async fn process_symbol_data(pool: &PgPool, data: &[u8]) -> Result<usize, ApiErrorKind> {
     let chunks = chunk_records(data, |line| {
         serde_json::from_slice::<SymbolRecord>(line).map_err(ApiErrorKind::Serde)
     })?;
+    let rows = chunks.iter().map(Vec::len).sum();
     ingest_chunks(
         pool,
         chunks,
         insert_symbol_records_batch,
         MAX_PARALLEL_INGEST,
     )
-    .await
+    .await?;
+    Ok(rows)
 }
 
-async fn process_symbol_namespace_data(pool: &PgPool, data: &[u8]) -> Result<(), ApiErrorKind> {
+async fn process_symbol_namespace_data(pool: &PgPool, data: &[u8]) -> Result<usize, ApiErrorKind> {
     let raw_chunks = chunk_records(data, |line| {
         serde_json::from_slice::<SymbolNamespaceRecord>(line).map_err(ApiErrorKind::Serde)
     })?;
@@ -699,51 +1214,107 @@ async fn process_symbol_namespace_data(pool: &PgPool, data: &[u8]) -> Result<(),
         .into_iter()
         .map(|chunk| chunk.into_iter().map(|record| record.namespace).collect())
         .collect();
+    let rows = string_chunks.iter().map(Vec::len).sum();
     ingest_chunks(
         pool,
         string_chunks,
         insert_symbol_namespaces_batch,
         MAX_PARALLEL_INGEST,
     )
-    .await
+    .await?;
+    Ok(rows)
 }
 
-async fn process_reference_data(pool: &PgPool, data: &[u8]) -> Result<(), ApiErrorKind> {
+async fn process_reference_data(
+    pool: &PgPool,
+    metrics: &Arc<IngestMetrics>,
+    data: &[u8],
+) -> Result<usize, ApiErrorKind> {
     let chunks = chunk_records(data, |line| {
         serde_json::from_slice::<ReferenceRecord>(line).map_err(ApiErrorKind::Serde)
     })?;
+    let rows = chunks.iter().map(Vec::len).sum();
+    let metrics = metrics.clone();
     ingest_chunks(
         pool,
         chunks,
-        insert_reference_records_batch,
+        move |pool, chunk| insert_reference_records_batch(pool, chunk, metrics.clone()),
         MAX_PARALLEL_INGEST,
     )
-    .await
+    .await?;
+    Ok(rows)
 }
 
-async fn process_branch_data(pool: &PgPool, data: &[u8]) -> Result<(), ApiErrorKind> {
+async fn process_branch_data(pool: &PgPool, data: &[u8]) -> Result<usize, ApiErrorKind> {
     let batches = chunk_records(data, |line| {
         serde_json::from_slice::<BranchHead>(line).map_err(ApiErrorKind::Serde)
     })?;
+    let rejected: Vec<String> = batches
+        .iter()
+        .filter_map(|batch| validate_branch_heads(batch).err())
+        .flatten()
+        .collect();
+    reject_records("branch heads", rejected)?;
+    let rows = batches.iter().map(Vec::len).sum();
     ingest_chunks(
         pool,
         batches,
         upsert_branch_heads_batch,
         MAX_PARALLEL_INGEST,
     )
-    .await
+    .await?;
+    Ok(rows)
+}
+
+async fn process_commit_metadata_data(pool: &PgPool, data: &[u8]) -> Result<usize, ApiErrorKind> {
+    let batches = chunk_records(data, |line| {
+        serde_json::from_slice::<CommitMetadata>(line).map_err(ApiErrorKind::Serde)
+    })?;
+    let rejected: Vec<String> = batches
+        .iter()
+        .filter_map(|batch| validate_commit_metadata(batch).err())
+        .flatten()
+        .collect();
+    reject_records("commit metadata", rejected)?;
+    let rows = batches.iter().map(Vec::len).sum();
+    ingest_chunks(
+        pool,
+        batches,
+        upsert_commit_metadata_batch,
+        MAX_PARALLEL_INGEST,
+    )
+    .await?;
+    Ok(rows)
 }
 
-async fn ingest_manifest_stream<R>(pool: &PgPool, reader: R) -> Result<(), ApiErrorKind>
+/// Fails with every rejection listed when any `what` records were refused.
+fn reject_records(what: &str, rejected: Vec<String>) -> Result<(), ApiErrorKind> {
+    if rejected.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiErrorKind::Invalid(describe_rejections(what, &rejected)))
+    }
+}
+
+async fn ingest_manifest_stream<R>(
+    pool: &PgPool,
+    metrics: &Arc<IngestMetrics>,
+    reader: R,
+) -> Result<ManifestFinalizeResponse, ApiErrorKind>
 where
     R: AsyncBufRead + Unpin,
 {
+    let mut ingested = ManifestFinalizeResponse::default();
     let mut lines = reader.lines();
     let mut file_buffer: Vec<FilePointer> = Vec::with_capacity(INSERT_BATCH_SIZE);
     let mut symbol_buffer: Vec<SymbolRecord> = Vec::with_capacity(INSERT_BATCH_SIZE);
     let mut namespace_buffer: Vec<SymbolNamespaceRecord> = Vec::with_capacity(INSERT_BATCH_SIZE);
     let mut reference_buffer: Vec<ReferenceRecord> = Vec::with_capacity(INSERT_BATCH_SIZE);
     let mut branches: Vec<BranchHead> = Vec::new();
+    let mut commits: Vec<CommitMetadata> = Vec::new();
+    // After the first rejected record nothing more is written; the rest of
+    // the manifest is only read to list every rejection in the error.
+    let mut rejected: Vec<String> = Vec::new();
 
     while let Some(line) = lines.next_line().await.map_err(ApiErrorKind::Compression)? {
         let trimmed = line.trim();
@@ -753,10 +1324,21 @@ where
 
         let envelope: ManifestEnvelope =
             serde_json::from_str(trimmed).map_err(ApiErrorKind::Serde)?;
+        if !rejected.is_empty()
+            && !matches!(
+                envelope,
+                ManifestEnvelope::FilePointer(_)
+                    | ManifestEnvelope::BranchHead(_)
+                    | ManifestEnvelope::CommitMetadata(_)
+            )
+        {
+            continue;
+        }
 
         match envelope {
             ManifestEnvelope::ContentBlob(_) => {}
             ManifestEnvelope::SymbolNamespace(namespace) => {
+                ingested.namespaces += 1;
                 namespace_buffer.push(namespace);
                 if namespace_buffer.len() >= INSERT_BATCH_SIZE {
                     let chunk = mem::take(&mut namespace_buffer)
@@ -772,7 +1354,14 @@ where
                     .await?;
                 }
             }
-            ManifestEnvelope::FilePointer(pointer) => {
+            ManifestEnvelope::FilePointer(mut pointer) => {
+                if let Err(reasons) = normalize_file_pointers(std::slice::from_mut(&mut pointer)) {
+                    rejected.extend(reasons);
+                }
+                if !rejected.is_empty() {
+                    continue;
+                }
+                ingested.files += 1;
                 file_buffer.push(pointer);
                 if file_buffer.len() >= INSERT_BATCH_SIZE {
                     let chunk = mem::take(&mut file_buffer);
@@ -786,6 +1375,7 @@ where
                 }
             }
             ManifestEnvelope::SymbolRecord(symbol) => {
+                ingested.symbols += 1;
                 symbol_buffer.push(symbol);
                 if symbol_buffer.len() >= INSERT_BATCH_SIZE {
                     let chunk = mem::take(&mut symbol_buffer);
@@ -799,24 +1389,43 @@ where
                 }
             }
             ManifestEnvelope::ReferenceRecord(reference) => {
+                ingested.references += 1;
                 reference_buffer.push(reference);
                 if reference_buffer.len() >= INSERT_BATCH_SIZE {
                     let chunk = mem::take(&mut reference_buffer);
+                    let metrics = metrics.clone();
                     ingest_chunks(
                         pool,
                         vec![chunk],
-                        insert_reference_records_batch,
+                        move |pool, chunk| {
+                            insert_reference_records_batch(pool, chunk, metrics.clone())
+                        },
                         MAX_PARALLEL_INGEST,
                     )
                     .await?;
                 }
             }
             ManifestEnvelope::BranchHead(branch) => {
+                if let Err(reasons) = validate_branch_heads(std::slice::from_ref(&branch)) {
+                    rejected.extend(reasons);
+                    continue;
+                }
+                ingested.branches += 1;
                 branches.push(branch);
             }
+            ManifestEnvelope::CommitMetadata(commit) => {
+                if let Err(reasons) = validate_commit_metadata(std::slice::from_ref(&commit)) {
+                    rejected.extend(reasons);
+                    continue;
+                }
+                ingested.commits += 1;
+                commits.push(commit);
+            }
         }
     }
 
+    reject_records("manifest records", rejected)?;
+
     if !file_buffer.is_empty() {
         ingest_chunks(
             pool,
@@ -849,10 +1458,11 @@ where
         .await?;
     }
     if !reference_buffer.is_empty() {
+        let metrics = metrics.clone();
         ingest_chunks(
             pool,
             vec![reference_buffer],
-            insert_reference_records_batch,
+            move |pool, chunk| insert_reference_records_batch(pool, chunk, metrics.clone()),
             MAX_PARALLEL_INGEST,
         )
         .await?;
@@ -866,12 +1476,28 @@ where
         )
         .await?;
     }
+    if !commits.is_empty() {
+        ingest_chunks(
+            pool,
+            chunk_vec(commits),
+            upsert_commit_metadata_batch,
+            MAX_PARALLEL_INGEST,
+        )
+        .await?;
+    }
 
-    Ok(())
+    Ok(ingested)
 }
 
 const INSERT_BATCH_SIZE: usize = 1000;
+
 const MAX_PARALLEL_INGEST: usize = 8;
+/// Extra attempts an ingest batch gets after a transient database error.
+const INGEST_RETRIES: u32 = 3;
+const INGEST_RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+/// SQLSTATEs for a serialization failure and a detected deadlock. Postgres
+/// rolls back the losing transaction, so running the batch again is safe.
+const TRANSIENT_SQLSTATES: [&str; 2] = ["40001", "40P01"];
 
 fn chunk_records<T, F>(data: &[u8], mut parse: F) -> Result<Vec<Vec<T>>, ApiErrorKind>
 where
@@ -925,34 +1551,115 @@ fn chunk_vec<T>(records: Vec<T>) -> Vec<Vec<T>> {
     chunks
 }
 
+/// Runs `make_task` over `chunks` with at most `max_parallel` in flight. A
+/// batch that hits a deadlock or serialization failure is retried with
+/// backoff; any other failure stops new batches and aborts the running ones.
+/// Every error seen by then is logged, and the aborted tasks are awaited so
+/// none still holds a pool connection once this returns.
 async fn ingest_chunks<T, Fut>(
     pool: &PgPool,
     chunks: Vec<Vec<T>>,
-    make_task: impl Fn(PgPool, Vec<T>) -> Fut + Send + Sync,
+    make_task: impl Fn(PgPool, Vec<T>) -> Fut + Clone + Send + Sync + 'static,
     max_parallel: usize,
 ) -> Result<(), ApiErrorKind>
 where
-    T: Send + 'static,
+    T: Clone + Send + 'static,
     Fut: Future<Output = Result<(), ApiErrorKind>> + Send + 'static,
 {
-    let mut futures = FuturesUnordered::new();
+    let total = chunks.len();
+    let mut tasks = JoinSet::new();
+    let mut errors = Vec::new();
 
     for chunk in chunks.into_iter() {
-        let pool_clone = pool.clone();
-        futures.push(tokio::spawn(make_task(pool_clone, chunk)));
+        if max_parallel > 0 && tasks.len() >= max_parallel {
+            if let Some(res) = tasks.join_next().await {
+                record_task_result(res, &mut errors);
+            }
+            if !errors.is_empty() {
+                break;
+            }
+        }
+        // The batch is kept so a retry can rebuild its task.
+        let first_attempt = make_task(pool.clone(), chunk.clone());
+        let make_task = make_task.clone();
+        let pool = pool.clone();
+        tasks.spawn(retry_transient(first_attempt, move || {
+            make_task(pool.clone(), chunk.clone())
+        }));
+    }
+
+    while let Some(res) = tasks.join_next().await {
+        record_task_result(res, &mut errors);
+        if !errors.is_empty() {
+            tasks.abort_all();
+        }
+    }
+
+    combine_task_errors(errors, total)
+}
 
-        if futures.len() >= max_parallel && max_parallel > 0 {
-            if let Some(res) = futures.next().await {
-                res.map_err(|err| ApiErrorKind::Internal(anyhow!(err)))??;
+/// Awaits `first`, then reruns the batch through `retry` while it keeps
+/// failing with a transient database error, doubling the delay each time.
+async fn retry_transient<Fut>(
+    first: Fut,
+    mut retry: impl FnMut() -> Fut,
+) -> Result<(), ApiErrorKind>
+where
+    Fut: Future<Output = Result<(), ApiErrorKind>>,
+{
+    let mut result = first.await;
+    let mut delay = INGEST_RETRY_BASE_DELAY;
+    for attempt in 1..=INGEST_RETRIES {
+        match &result {
+            Err(err) if is_transient_db_error(err) => {
+                tracing::warn!(error = %err, attempt, ?delay, "retrying ingest batch");
             }
+            _ => break,
         }
+        time::sleep(delay).await;
+        delay *= 2;
+        result = retry().await;
     }
+    result
+}
 
-    while let Some(res) = futures.next().await {
-        res.map_err(|err| ApiErrorKind::Internal(anyhow!(err)))??;
+/// Whether `err` is a deadlock or serialization failure that a rerun of the
+/// same batch can get past. Constraint violations and bad data are not.
+fn is_transient_db_error(err: &ApiErrorKind) -> bool {
+    match err {
+        ApiErrorKind::Database(sqlx::Error::Database(db_err)) => db_err
+            .code()
+            .is_some_and(|code| TRANSIENT_SQLSTATES.contains(&code.as_ref())),
+        _ => false,
     }
+}
 
-    Ok(())
+fn record_task_result(
+    res: Result<Result<(), ApiErrorKind>, tokio::task::JoinError>,
+    errors: &mut Vec<ApiErrorKind>,
+) {
+    match res {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => errors.push(err),
+        Err(err) if err.is_cancelled() => {}
+        Err(err) => errors.push(ApiErrorKind::Internal(anyhow!(err))),
+    }
+}
+
+fn combine_task_errors(errors: Vec<ApiErrorKind>, total: usize) -> Result<(), ApiErrorKind> {
+    if errors.len() <= 1 {
+        return errors.into_iter().next().map_or(Ok(()), Err);
+    }
+    for err in &errors {
+        tracing::error!(error = %err, "ingest batch failed");
+    }
+    let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    Err(ApiErrorKind::Internal(anyhow!(
+        "{} of {} batches failed: {}",
+        errors.len(),
+        total,
+        messages.join("; ")
+    )))
 }
 
 async fn insert_file_pointers_batch(
@@ -963,16 +1670,19 @@ async fn insert_file_pointers_batch(
         return Ok(());
     }
 
-    let mut qb =
-        QueryBuilder::new("INSERT INTO files (repository, commit_sha, file_path, content_hash) ");
+    let mut qb = QueryBuilder::new(
+        "INSERT INTO files (repository, commit_sha, file_path, content_hash, kind, mode) ",
+    );
     qb.push_values(chunk.iter(), |mut b, file| {
         b.push_bind(&file.repository)
             .push_bind(&file.commit_sha)
             .push_bind(&file.file_path)
-            .push_bind(&file.content_hash);
+            .push_bind(&file.content_hash)
+            .push_bind(file.kind.as_str())
+            .push_bind(file.mode.map(|mode| mode as i32));
     });
     qb.push(
-        " ON CONFLICT (repository, commit_sha, file_path) DO UPDATE SET content_hash = EXCLUDED.content_hash",
+        " ON CONFLICT (repository, commit_sha, file_path) DO UPDATE SET content_hash = EXCLUDED.content_hash, kind = EXCLUDED.kind, mode = EXCLUDED.mode",
     );
 
     qb.build()
@@ -1046,6 +1756,7 @@ async fn insert_symbol_namespaces_batch(
 async fn insert_reference_records_batch(
     pool: PgPool,
     chunk: Vec<ReferenceRecord>,
+    metrics: Arc<IngestMetrics>,
 ) -> Result<(), ApiErrorKind> {
     if chunk.is_empty() {
         return Ok(());
@@ -1063,9 +1774,11 @@ async fn insert_reference_records_batch(
             content_hash TEXT,
             namespace TEXT,
             name TEXT,
+            name_lc TEXT,
             kind TEXT,
             line_number INT,
-            column_number INT
+            column_number INT,
+            signature TEXT
         ) ON COMMIT DROP",
     )
     .execute(&mut *tx)
@@ -1073,7 +1786,7 @@ async fn insert_reference_records_batch(
     .map_err(|err| ApiErrorKind::from(err))?;
 
     let mut staging_qb = QueryBuilder::new(
-        "INSERT INTO staging_symbol_references (content_hash, namespace, name, kind, line_number, column_number) ",
+        "INSERT INTO staging_symbol_references (content_hash, namespace, name, name_lc, kind, line_number, column_number, signature) ",
     );
     staging_qb.push_values(chunk.iter(), |mut b, reference| {
         let line: i32 = reference.line.try_into().unwrap_or(i32::MAX);
@@ -1082,9 +1795,11 @@ async fn insert_reference_records_batch(
         b.push_bind(&reference.content_hash)
             .push_bind(namespace)
             .push_bind(&reference.name)
+            .push_bind(reference.name.to_lowercase())
             .push_bind(&reference.kind)
             .push_bind(line)
-            .push_bind(column);
+            .push_bind(column)
+            .push_bind(&reference.signature);
     });
     staging_qb
         .build()
@@ -1092,14 +1807,42 @@ async fn insert_reference_records_batch(
         .await
         .map_err(|err| ApiErrorKind::from(err))?;
 
+    // Reference shards may arrive before the symbol (or namespace) shards they
+    // point at. Both rows are fully derivable from the reference itself, so
+    // create them here instead of letting the joins below drop the reference.
+    let symbols_created = sqlx::query(
+        "INSERT INTO symbols (content_hash, name, name_lc)
+         SELECT DISTINCT data.content_hash, data.name, data.name_lc
+         FROM staging_symbol_references data
+         JOIN content_blobs cb
+           ON cb.hash = data.content_hash
+         ORDER BY data.content_hash, data.name
+         ON CONFLICT (content_hash, name) DO NOTHING",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiErrorKind::from)?
+    .rows_affected();
+
     sqlx::query(
-        "INSERT INTO symbol_references (symbol_id, namespace_id, kind, line_number, column_number)
-         SELECT s.id, sn.id, data.kind, data.line_number, data.column_number
-         FROM (
-             SELECT content_hash, namespace, name, kind, line_number, column_number
-             FROM staging_symbol_references
-             ORDER BY namespace, content_hash, name, line_number, column_number, kind
-         ) AS data
+        "INSERT INTO symbol_namespaces (namespace)
+         SELECT DISTINCT namespace
+         FROM staging_symbol_references
+         ORDER BY namespace
+         ON CONFLICT (namespace) DO NOTHING",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiErrorKind::from)?;
+
+    sqlx::query(
+        "INSERT INTO symbol_references (symbol_id, namespace_id, kind, line_number, column_number, signature)
+         SELECT s.id, sn.id, data.kind, data.line_number, data.column_number, data.signature
+         FROM (
+             SELECT content_hash, namespace, name, kind, line_number, column_number, signature
+             FROM staging_symbol_references
+             ORDER BY namespace, content_hash, name, line_number, column_number, kind
+         ) AS data
          JOIN symbols s
            ON s.content_hash = data.content_hash
           AND s.name = data.name
@@ -1113,34 +1856,84 @@ async fn insert_reference_records_batch(
 
     tx.commit().await.map_err(|err| ApiErrorKind::from(err))?;
 
+    if symbols_created > 0 {
+        let total = metrics.record_reference_created_symbols(symbols_created);
+        info!(
+            symbols_created,
+            total, "created symbols for references that arrived before their symbol records"
+        );
+    }
+
     Ok(())
 }
 
-async fn upsert_branch_heads_batch(
+async fn upsert_commit_metadata_batch(
     pool: PgPool,
-    chunk: Vec<BranchHead>,
+    chunk: Vec<CommitMetadata>,
 ) -> Result<(), ApiErrorKind> {
     if chunk.is_empty() {
         return Ok(());
     }
 
-    let mut tx = pool.begin().await.map_err(ApiErrorKind::from)?;
-
-    let mut qb = QueryBuilder::new("INSERT INTO branches (repository, branch, commit_sha) ");
-    qb.push_values(chunk.iter(), |mut b, branch| {
-        b.push_bind(&branch.repository)
-            .push_bind(&branch.branch)
-            .push_bind(&branch.commit_sha);
+    let mut qb = QueryBuilder::new(
+        "INSERT INTO commits (repository, commit_sha, author_name, author_email, committed_at, subject) ",
+    );
+    qb.push_values(&chunk, |mut b, commit| {
+        b.push_bind(&commit.repository)
+            .push_bind(&commit.commit_sha)
+            .push_bind(&commit.author_name)
+            .push_bind(&commit.author_email)
+            .push("to_timestamp(")
+            .push_bind_unseparated(commit.committed_at as f64)
+            .push_unseparated(")")
+            .push_bind(&commit.subject);
     });
     qb.push(
-        " ON CONFLICT (repository, branch)
-          DO UPDATE SET commit_sha = EXCLUDED.commit_sha, indexed_at = NOW()",
+        " ON CONFLICT (repository, commit_sha)
+          DO UPDATE SET author_name = EXCLUDED.author_name,
+                        author_email = EXCLUDED.author_email,
+                        committed_at = EXCLUDED.committed_at,
+                        subject = EXCLUDED.subject",
     );
 
     qb.build()
-        .execute(&mut *tx)
+        .execute(&pool)
         .await
         .map_err(ApiErrorKind::from)?;
+    Ok(())
+}
+
+async fn upsert_branch_heads_batch(
+    pool: PgPool,
+    chunk: Vec<BranchHead>,
+) -> Result<(), ApiErrorKind> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await.map_err(ApiErrorKind::from)?;
+
+    let heads: Vec<&BranchHead> = chunk
+        .iter()
+        .filter(|branch| !branch.snapshot_only)
+        .collect();
+    if !heads.is_empty() {
+        let mut qb = QueryBuilder::new("INSERT INTO branches (repository, branch, commit_sha) ");
+        qb.push_values(heads, |mut b, branch| {
+            b.push_bind(&branch.repository)
+                .push_bind(&branch.branch)
+                .push_bind(&branch.commit_sha);
+        });
+        qb.push(
+            " ON CONFLICT (repository, branch)
+              DO UPDATE SET commit_sha = EXCLUDED.commit_sha, indexed_at = NOW()",
+        );
+
+        qb.build()
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiErrorKind::from)?;
+    }
 
     for branch in &chunk {
         let (policy_latest_keep, snapshot_specs, policy_specified, live_state) =
@@ -1168,19 +1961,26 @@ async fn upsert_branch_heads_batch(
             ))
         })?;
 
-        sqlx::query(
+        // A snapshot-only backfill without a policy keeps the branch's
+        // existing policy and only creates one when the branch is new.
+        let policy_sql = if branch.snapshot_only && !policy_specified {
+            "INSERT INTO branch_policies (repository, branch, latest_keep_count, updated_at)
+                 VALUES ($1, $2, $3, NOW())
+                 ON CONFLICT (repository, branch) DO NOTHING"
+        } else {
             "INSERT INTO branch_policies (repository, branch, latest_keep_count, updated_at)
                  VALUES ($1, $2, $3, NOW())
                  ON CONFLICT (repository, branch)
                  DO UPDATE SET latest_keep_count = EXCLUDED.latest_keep_count,
-                               updated_at = NOW()",
-        )
-        .bind(&branch.repository)
-        .bind(&branch.branch)
-        .bind(latest_keep)
-        .execute(&mut *tx)
-        .await
-        .map_err(ApiErrorKind::from)?;
+                               updated_at = NOW()"
+        };
+        sqlx::query(policy_sql)
+            .bind(&branch.repository)
+            .bind(&branch.branch)
+            .bind(latest_keep)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiErrorKind::from)?;
 
         if policy_specified {
             sqlx::query(
@@ -1314,6 +2114,9 @@ struct PruneRepoRequest {
     repository: String,
     #[serde(default = "default_prune_repo_batch_size")]
     batch_size: i64,
+    /// Report what would be deleted without deleting it.
+    #[serde(default)]
+    dry_run: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -1338,11 +2141,117 @@ struct PruneBranchResponse {
     message: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct BranchDeleteRequest {
+    repository: String,
+    branch: String,
+    /// Allows deleting the repository's only branch.
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct BranchDeleteResponse {
+    repository: String,
+    branch: String,
+    deleted: bool,
+    snapshots_deleted: u64,
+    commits_pruned: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiDiffRequest {
+    repository: String,
+    base_commit: String,
+    head_commit: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ApiDiffResponse {
+    repository: String,
+    base_commit: String,
+    head_commit: String,
+    #[serde(flatten)]
+    diff: ApiDiff,
+}
+
+#[derive(Debug, Deserialize)]
+struct BranchHeadRequest {
+    repository: String,
+    branch: String,
+}
+
+#[derive(Debug, Serialize)]
+struct BranchHeadResponse {
+    /// `None` when the branch has never been indexed.
+    commit_sha: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoStatusQuery {
+    repository: Option<String>,
+    /// Only branches whose latest index is older than this many seconds.
+    stale_than_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct RepoStatusResponse {
+    repositories: Vec<RepoStatus>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct RepoStatus {
+    repository: String,
+    /// The branch named in `repo_live_branches`, if any.
+    live_branch: Option<String>,
+    branches: Vec<BranchStatus>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct BranchStatus {
+    branch: String,
+    commit_sha: String,
+    indexed_at: Option<DateTime<Utc>>,
+    snapshot_count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurgeContentRequest {
+    content_hash: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PurgeContentResponse {
+    content_hash: String,
+    purged: bool,
+    #[serde(flatten)]
+    counts: PurgeCounts,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GcRequest {
+    /// Also list the snapshots and commits removed on each branch.
+    #[serde(default)]
+    detailed: bool,
+}
+
 #[derive(Debug, Serialize)]
 struct GcResponse {
     branches_evaluated: usize,
     snapshots_removed: usize,
     commits_pruned: usize,
+    content_blobs_removed: usize,
+    chunks_removed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Vec<GcDetail>>,
+}
+
+#[derive(Debug, Serialize)]
+struct GcStatusResponse {
+    enabled: bool,
+    interval_secs: Option<u64>,
+    last_run: Option<LastGcRun>,
+    next_run_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1375,6 +2284,16 @@ struct RefreshSymbolCacheResponse {
     shard_count: usize,
 }
 
+#[derive(Debug, Deserialize)]
+struct BackfillNameLcRequest {
+    #[serde(default = "default_symbol_cache_batch_size")]
+    batch_size: i64,
+    #[serde(default = "default_symbol_cache_max_batches")]
+    max_batches: i64,
+    #[serde(default = "default_backfill_pause_ms")]
+    pause_ms: u64,
+}
+
 #[derive(Debug, Serialize)]
 struct RebuildSymbolCacheResponse {
     message: String,
@@ -1418,15 +2337,233 @@ async fn prune_branch_handler(
     State(state): State<AppState>,
     Json(payload): Json<PruneBranchRequest>,
 ) -> ApiResult<Json<PruneBranchResponse>> {
-    let mut affected_commits = HashSet::new();
+    let deletion = delete_branch(&state.pool, &payload.repository, &payload.branch).await?;
+    if !deletion.found {
+        return Ok(Json(PruneBranchResponse {
+            repository: payload.repository,
+            branch: payload.branch,
+            pruned: false,
+            message: "Branch not found".to_string(),
+        }));
+    }
 
-    let latest_commit_opt: Option<String> =
+    Ok(Json(PruneBranchResponse {
+        repository: payload.repository,
+        branch: payload.branch,
+        pruned: true,
+        message: format!(
+            "Deleted branch metadata and pruned {} unreferenced commits",
+            deletion.commits_pruned
+        ),
+    }))
+}
+
+/// Removes a branch deleted upstream. Refuses to remove a repository's only
+/// branch unless `force` is set, since that unpublishes the repository.
+async fn branch_delete_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<BranchDeleteRequest>,
+) -> ApiResult<Json<BranchDeleteResponse>> {
+    if !payload.force && is_only_branch(&state.pool, &payload.repository, &payload.branch).await? {
+        return Err(AppError::new(
+            StatusCode::CONFLICT,
+            format!(
+                "{} is the only branch of {}; pass force: true to delete it",
+                payload.branch, payload.repository
+            ),
+        ));
+    }
+
+    let deletion = delete_branch(&state.pool, &payload.repository, &payload.branch).await?;
+    Ok(Json(BranchDeleteResponse {
+        repository: payload.repository,
+        branch: payload.branch,
+        deleted: deletion.found,
+        snapshots_deleted: deletion.snapshots_deleted,
+        commits_pruned: deletion.commits_pruned,
+    }))
+}
+
+async fn api_diff_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<ApiDiffRequest>,
+) -> ApiResult<Json<ApiDiffResponse>> {
+    for commit in [&payload.base_commit, &payload.head_commit] {
+        if !commit_is_indexed(&state.pool, &payload.repository, commit).await? {
+            return Err(AppError::new(
+                StatusCode::NOT_FOUND,
+                format!("{} has no indexed commit {}", payload.repository, commit),
+            ));
+        }
+    }
+
+    let diff = api_diff::api_diff(
+        &state.pool,
+        &payload.repository,
+        &payload.base_commit,
+        &payload.head_commit,
+    )
+    .await
+    .map_err(ApiErrorKind::from)?;
+    Ok(Json(ApiDiffResponse {
+        repository: payload.repository,
+        base_commit: payload.base_commit,
+        head_commit: payload.head_commit,
+        diff,
+    }))
+}
+
+async fn commit_is_indexed(
+    pool: &PgPool,
+    repository: &str,
+    commit: &str,
+) -> Result<bool, ApiErrorKind> {
+    let indexed: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM files WHERE repository = $1 AND commit_sha = $2)",
+    )
+    .bind(repository)
+    .bind(commit)
+    .fetch_one(pool)
+    .await?;
+    Ok(indexed)
+}
+
+/// The indexed head of a branch, which incremental indexing diffs against.
+async fn branch_head_handler(
+    State(state): State<AppState>,
+    Json(request): Json<BranchHeadRequest>,
+) -> ApiResult<Json<BranchHeadResponse>> {
+    let commit_sha =
         sqlx::query_scalar("SELECT commit_sha FROM branches WHERE repository = $1 AND branch = $2")
-            .bind(&payload.repository)
-            .bind(&payload.branch)
+            .bind(&request.repository)
+            .bind(&request.branch)
             .fetch_optional(&state.pool)
             .await
             .map_err(ApiErrorKind::from)?;
+    Ok(Json(BranchHeadResponse { commit_sha }))
+}
+
+async fn repos_handler(
+    State(state): State<AppState>,
+    Query(query): Query<RepoStatusQuery>,
+) -> ApiResult<Json<RepoStatusResponse>> {
+    let repositories = repo_statuses(
+        &state.pool,
+        query.repository.as_deref(),
+        query.stale_than_secs,
+    )
+    .await?;
+    Ok(Json(RepoStatusResponse { repositories }))
+}
+
+#[derive(sqlx::FromRow)]
+struct RepoStatusRow {
+    repository: String,
+    branch: String,
+    commit_sha: String,
+    indexed_at: Option<DateTime<Utc>>,
+    snapshot_count: i64,
+    live_branch: Option<String>,
+}
+
+/// Indexed branches grouped by repository. A branch's `indexed_at` is when
+/// its head was last indexed; branches with no timestamp count as stale.
+async fn repo_statuses(
+    pool: &PgPool,
+    repository: Option<&str>,
+    stale_than_secs: Option<u64>,
+) -> Result<Vec<RepoStatus>, ApiErrorKind> {
+    let stale_than_secs = stale_than_secs.map(|secs| secs.min(i64::MAX as u64) as i64);
+    let rows: Vec<RepoStatusRow> = sqlx::query_as(
+        "SELECT
+            b.repository,
+            b.branch,
+            b.commit_sha,
+            COALESCE(b.indexed_at, snap.latest_indexed_at) AS indexed_at,
+            snap.snapshot_count,
+            lb.branch AS live_branch
+         FROM branches b
+         CROSS JOIN LATERAL (
+             SELECT COUNT(*) AS snapshot_count, MAX(bs.indexed_at) AS latest_indexed_at
+             FROM branch_snapshots bs
+             WHERE bs.repository = b.repository AND bs.branch = b.branch
+         ) snap
+         LEFT JOIN repo_live_branches lb ON lb.repository = b.repository
+         WHERE ($1::TEXT IS NULL OR b.repository = $1)
+           AND (
+               $2::BIGINT IS NULL
+               OR COALESCE(b.indexed_at, snap.latest_indexed_at, '-infinity')
+                   < NOW() - make_interval(secs => $2::FLOAT8)
+           )
+         ORDER BY b.repository, b.branch",
+    )
+    .bind(repository)
+    .bind(stale_than_secs)
+    .fetch_all(pool)
+    .await?;
+
+    let mut statuses: Vec<RepoStatus> = Vec::new();
+    for row in rows {
+        let branch = BranchStatus {
+            branch: row.branch,
+            commit_sha: row.commit_sha,
+            indexed_at: row.indexed_at,
+            snapshot_count: row.snapshot_count,
+        };
+        match statuses.last_mut() {
+            Some(status) if status.repository == row.repository => status.branches.push(branch),
+            _ => statuses.push(RepoStatus {
+                repository: row.repository,
+                live_branch: row.live_branch,
+                branches: vec![branch],
+            }),
+        }
+    }
+    Ok(statuses)
+}
+
+/// Whether `branch` exists and no other branch of `repository` does.
+async fn is_only_branch(
+    pool: &PgPool,
+    repository: &str,
+    branch: &str,
+) -> Result<bool, ApiErrorKind> {
+    let (this, others): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*) FILTER (WHERE branch = $2), COUNT(*) FILTER (WHERE branch <> $2)
+         FROM branches
+         WHERE repository = $1",
+    )
+    .bind(repository)
+    .bind(branch)
+    .fetch_one(pool)
+    .await?;
+    Ok(this > 0 && others == 0)
+}
+
+/// What [`delete_branch`] removed.
+#[derive(Debug, Default)]
+struct BranchDeletion {
+    /// False when the branch had no head, policy or snapshot rows.
+    found: bool,
+    snapshots_deleted: u64,
+    commits_pruned: u64,
+}
+
+/// Deletes a branch's head, snapshots, policies and live-branch marker, then
+/// prunes the commits it pointed at that no remaining branch protects.
+async fn delete_branch(
+    pool: &PgPool,
+    repository: &str,
+    branch: &str,
+) -> Result<BranchDeletion, ApiErrorKind> {
+    let mut affected_commits = HashSet::new();
+
+    let latest_commit_opt: Option<String> =
+        sqlx::query_scalar("SELECT commit_sha FROM branches WHERE repository = $1 AND branch = $2")
+            .bind(repository)
+            .bind(branch)
+            .fetch_optional(pool)
+            .await?;
     if let Some(commit_sha) = &latest_commit_opt {
         affected_commits.insert(commit_sha.clone());
     }
@@ -1434,80 +2571,86 @@ async fn prune_branch_handler(
     let snapshot_commits: Vec<String> = sqlx::query_scalar(
         "SELECT commit_sha FROM branch_snapshots WHERE repository = $1 AND branch = $2",
     )
-    .bind(&payload.repository)
-    .bind(&payload.branch)
-    .fetch_all(&state.pool)
-    .await
-    .map_err(ApiErrorKind::from)?;
+    .bind(repository)
+    .bind(branch)
+    .fetch_all(pool)
+    .await?;
     affected_commits.extend(snapshot_commits);
 
-    let mut tx = state.pool.begin().await.map_err(ApiErrorKind::from)?;
+    let mut tx = pool.begin().await?;
     let branches_deleted =
         sqlx::query("DELETE FROM branches WHERE repository = $1 AND branch = $2")
-            .bind(&payload.repository)
-            .bind(&payload.branch)
+            .bind(repository)
+            .bind(branch)
             .execute(&mut *tx)
-            .await
-            .map_err(ApiErrorKind::from)?
+            .await?
             .rows_affected();
 
-    let policies_deleted =
-        sqlx::query("DELETE FROM branch_policies WHERE repository = $1 AND branch = $2")
-            .bind(&payload.repository)
-            .bind(&payload.branch)
+    // Snapshots and the live marker cascade from the policy row, but are
+    // deleted first so branches without a policy lose them too.
+    let snapshots_deleted =
+        sqlx::query("DELETE FROM branch_snapshots WHERE repository = $1 AND branch = $2")
+            .bind(repository)
+            .bind(branch)
             .execute(&mut *tx)
-            .await
-            .map_err(ApiErrorKind::from)?
+            .await?
             .rows_affected();
 
-    let snapshots_deleted = if policies_deleted == 0 {
-        sqlx::query("DELETE FROM branch_snapshots WHERE repository = $1 AND branch = $2")
-            .bind(&payload.repository)
-            .bind(&payload.branch)
+    sqlx::query("DELETE FROM repo_live_branches WHERE repository = $1 AND branch = $2")
+        .bind(repository)
+        .bind(branch)
+        .execute(&mut *tx)
+        .await?;
+
+    let policies_deleted =
+        sqlx::query("DELETE FROM branch_policies WHERE repository = $1 AND branch = $2")
+            .bind(repository)
+            .bind(branch)
             .execute(&mut *tx)
-            .await
-            .map_err(ApiErrorKind::from)?
-            .rows_affected()
-    } else {
-        0
-    };
+            .await?
+            .rows_affected();
 
-    tx.commit().await.map_err(ApiErrorKind::from)?;
+    tx.commit().await?;
 
     if branches_deleted == 0 && policies_deleted == 0 && snapshots_deleted == 0 {
-        return Ok(Json(PruneBranchResponse {
-            repository: payload.repository,
-            branch: payload.branch,
-            pruned: false,
-            message: "Branch not found".to_string(),
-        }));
+        return Ok(BranchDeletion::default());
     }
 
-    let mut pruned_count = 0;
+    let mut commits_pruned = 0;
     for commit_sha in affected_commits {
-        if commit_is_protected(&state.pool, &payload.repository, &commit_sha).await? {
+        if commit_is_protected(pool, repository, &commit_sha).await? {
             continue;
         }
-        if prune_commit_data(&state.pool, &payload.repository, &commit_sha).await? {
-            pruned_count += 1;
+        if prune_commit_data(pool, repository, &commit_sha).await? {
+            commits_pruned += 1;
         }
     }
 
-    Ok(Json(PruneBranchResponse {
-        repository: payload.repository,
-        branch: payload.branch,
-        pruned: true,
-        message: format!(
-            "Deleted branch metadata and pruned {} unreferenced commits",
-            pruned_count
-        ),
-    }))
+    Ok(BranchDeletion {
+        found: true,
+        snapshots_deleted,
+        commits_pruned,
+    })
 }
 
 async fn prune_repo_handler(
     State(state): State<AppState>,
     Json(payload): Json<PruneRepoRequest>,
 ) -> ApiResult<Json<PruneRepoResponse>> {
+    if payload.dry_run.unwrap_or(false) {
+        let deleted_rows = count_repository_data(&state.pool, &payload.repository).await?;
+        return Ok(Json(PruneRepoResponse {
+            repository: payload.repository,
+            pruned: false,
+            deleted_rows,
+            message: if deleted_rows > 0 {
+                "Dry run: repository data would be pruned".to_string()
+            } else {
+                "No data found for the specified repository".to_string()
+            },
+        }));
+    }
+
     let deleted_rows =
         prune_repository_data(&state.pool, &payload.repository, payload.batch_size).await?;
     let pruned = deleted_rows > 0;
@@ -1524,32 +2667,119 @@ async fn prune_repo_handler(
     }))
 }
 
-async fn run_gc_handler(State(state): State<AppState>) -> ApiResult<Json<GcResponse>> {
-    let collector = GarbageCollector::new(state.pool.clone());
-    let outcome = collector.run_once().await?;
+async fn run_gc_handler(
+    State(state): State<AppState>,
+    payload: Option<Json<GcRequest>>,
+) -> ApiResult<Json<GcResponse>> {
+    // The body is optional so bodiless calls keep getting the aggregate.
+    let Json(request) = payload.unwrap_or_default();
+    let collector = GarbageCollector::new(state.pool.clone()).with_orphan_sweep(state.orphan_sweep);
+    let outcome = collector.run_once(request.detailed).await?;
     Ok(Json(GcResponse {
         branches_evaluated: outcome.branches_evaluated,
         snapshots_removed: outcome.snapshots_removed,
         commits_pruned: outcome.commits_pruned,
+        content_blobs_removed: outcome.content_blobs_removed,
+        chunks_removed: outcome.chunks_removed,
+        details: outcome.details,
     }))
 }
 
-async fn cleanup_symbol_cache_handler(
+/// Reports the background GC loop's last run and when the next one is due.
+async fn gc_status_handler(State(state): State<AppState>) -> Json<GcStatusResponse> {
+    let last_run = state
+        .gc_status
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+    // The loop sleeps for the interval after each run finishes.
+    let next_run_at = match (&last_run, state.gc_interval) {
+        (Some(run), Some(interval)) => chrono::Duration::from_std(interval)
+            .ok()
+            .map(|interval| run.finished_at + interval),
+        _ => None,
+    };
+    Json(GcStatusResponse {
+        enabled: state.gc_interval.is_some(),
+        interval_secs: state.gc_interval.map(|interval| interval.as_secs()),
+        last_run,
+        next_run_at,
+    })
+}
+
+/// Runs only the orphan sweep, without applying retention policies.
+async fn sweep_orphans_handler(
     State(state): State<AppState>,
-    Json(payload): Json<CleanupSymbolCacheRequest>,
-) -> ApiResult<Json<CleanupSymbolCacheResponse>> {
-    let batch_size = payload.batch_size.max(1);
-    let max_batches = payload.max_batches.max(1);
-    let mut names_deleted = 0_i64;
-    let mut batches_run = 0_i64;
+) -> ApiResult<Json<OrphanSweepOutcome>> {
+    let outcome = sweep_orphans(&state.pool, state.orphan_sweep).await?;
+    info!(?outcome, "swept orphaned content");
+    Ok(Json(outcome))
+}
 
-    let mut conn = state.pool.acquire().await.map_err(ApiErrorKind::from)?;
+async fn purge_content_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<PurgeContentRequest>,
+) -> ApiResult<Json<PurgeContentResponse>> {
+    let content_hash = payload.content_hash.trim();
+    if content_hash.is_empty() {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "content_hash must not be empty",
+        ));
+    }
 
-    for _ in 0..max_batches {
-        let result = sqlx::query(
-            "
-            WITH doomed AS (
-                SELECT us.name_lc
+    let counts = purge_content_hash(&state.pool, content_hash).await?;
+    info!(content_hash, ?counts, "purged content hash");
+    Ok(Json(PurgeContentResponse {
+        content_hash: content_hash.to_string(),
+        purged: counts.blobs_deleted > 0,
+        counts,
+    }))
+}
+
+async fn relink_namespaces_handler(
+    State(state): State<AppState>,
+) -> ApiResult<Json<NamespaceRelinkCounts>> {
+    let mut tx = state.pool.begin().await.map_err(ApiErrorKind::from)?;
+    let counts = relink_reference_namespaces(&mut tx).await?;
+    tx.commit().await.map_err(ApiErrorKind::from)?;
+    info!(?counts, "relinked symbol reference namespaces");
+    Ok(Json(counts))
+}
+
+async fn backfill_name_lc_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<BackfillNameLcRequest>,
+) -> ApiResult<Json<BackfillProgress>> {
+    let progress = backfill::backfill_name_lc(
+        &state.pool,
+        BackfillOptions {
+            batch_size: payload.batch_size,
+            max_batches: payload.max_batches,
+            pause: Duration::from_millis(payload.pause_ms),
+        },
+    )
+    .await
+    .map_err(ApiErrorKind::from)?;
+    Ok(Json(progress))
+}
+
+async fn cleanup_symbol_cache_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<CleanupSymbolCacheRequest>,
+) -> ApiResult<Json<CleanupSymbolCacheResponse>> {
+    let batch_size = payload.batch_size.max(1);
+    let max_batches = payload.max_batches.max(1);
+    let mut names_deleted = 0_i64;
+    let mut batches_run = 0_i64;
+
+    let mut conn = state.pool.acquire().await.map_err(ApiErrorKind::from)?;
+
+    for _ in 0..max_batches {
+        let result = sqlx::query(
+            "
+            WITH doomed AS (
+                SELECT us.name_lc
                 FROM unique_symbols us
                 LEFT JOIN symbols s ON s.name_lc = us.name_lc
                 WHERE s.name_lc IS NULL
@@ -1807,6 +3037,10 @@ fn default_symbol_cache_max_batches() -> i64 {
     50
 }
 
+fn default_backfill_pause_ms() -> u64 {
+    100
+}
+
 fn default_prune_repo_batch_size() -> i64 {
     10_000
 }
@@ -1824,6 +3058,15 @@ struct RetentionPolicyConfig {
     keep_latest: bool,
 
     max_commits_to_keep: Option<i32>,
+    /// Also keep every commit a branch indexed within this many days, however
+    /// many that is. See [`commits_within_age`].
+    #[serde(default)]
+    keep_newer_than_days: Option<i64>,
+    /// Prune commits whose most recent indexing is older than this many days,
+    /// unless another rule keeps them. The same window as
+    /// `keep_newer_than_days`; when both are set the longer one applies.
+    #[serde(default)]
+    max_age_days: Option<i64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -1879,11 +3122,12 @@ async fn apply_retention_policy(
         // Get commits ordered by branch indexing time (most recent first)
         // This approach uses the branches table to order commits by recency
         let recent_commits: Vec<String> = sqlx::query_scalar(
-            "SELECT DISTINCT f.commit_sha
+            "SELECT f.commit_sha
              FROM files f
              LEFT JOIN branches b ON f.commit_sha = b.commit_sha AND f.repository = b.repository
              WHERE f.repository = $1
-             ORDER BY b.indexed_at DESC NULLS LAST, f.commit_sha
+             GROUP BY f.commit_sha
+             ORDER BY MAX(b.indexed_at) DESC NULLS LAST, f.commit_sha
              LIMIT $2",
         )
         .bind(&config.repository)
@@ -1897,6 +3141,11 @@ async fn apply_retention_policy(
         }
     }
 
+    // Keep anything indexed within the age window, on top of the count limit
+    if let Some(days) = config.keep_newer_than_days.max(config.max_age_days) {
+        commits_to_keep.extend(commits_within_age(pool, &config.repository, days).await?);
+    }
+
     // Find commits that should be pruned (not in commits_to_keep)
     let commits_to_prune: Vec<String> = all_commits
         .into_iter()
@@ -1911,6 +3160,2148 @@ async fn apply_retention_policy(
     Ok(())
 }
 
+/// Commits of `repository` whose most recent `branches` or
+/// `branch_snapshots` indexing falls within the last `days` days. Commits that
+/// were never given an indexing timestamp are included too: their age is
+/// unknown, so age-based retention never prunes them.
+async fn commits_within_age(
+    pool: &PgPool,
+    repository: &str,
+    days: i64,
+) -> Result<Vec<String>, ApiErrorKind> {
+    sqlx::query_scalar(
+        "WITH indexed AS (
+             SELECT commit_sha, indexed_at FROM branches WHERE repository = $1
+             UNION ALL
+             SELECT commit_sha, indexed_at FROM branch_snapshots WHERE repository = $1
+         )
+         SELECT f.commit_sha
+         FROM (SELECT DISTINCT commit_sha FROM files WHERE repository = $1) f
+         LEFT JOIN indexed i ON i.commit_sha = f.commit_sha
+         GROUP BY f.commit_sha
+         HAVING MAX(i.indexed_at) IS NULL
+             OR MAX(i.indexed_at) >= NOW() - $2 * INTERVAL '1 day'",
+    )
+    .bind(repository)
+    .bind(days.max(0) as f64)
+    .fetch_all(pool)
+    .await
+    .map_err(ApiErrorKind::from)
+}
+
 async fn health_check() -> &'static str {
     "ok"
 }
+
+async fn metrics_handler(State(state): State<AppState>) -> ApiResult<impl IntoResponse> {
+    let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM upload_chunks")
+        .fetch_one(&state.pool)
+        .await
+        .map_err(ApiErrorKind::from)?;
+    Ok((
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.render(pending),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gc::GcOutcome;
+    use pointer_indexer_types::FileKind;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    type StoredReference = (String, String, Option<String>, i32, i32);
+    /// A definition to seed: (name, line, stored signature).
+    type SeededDefinition = (&'static str, usize, Option<&'static str>);
+
+    /// `None` when `DATABASE_URL` is unset and database tests are skipped.
+    async fn test_pool() -> Option<PgPool> {
+        let Ok(url) = std::env::var("DATABASE_URL") else {
+            eprintln!("DATABASE_URL is not set; skipping database test");
+            return None;
+        };
+        let pool = PgPoolOptions::new()
+            .max_connections(4)
+            .connect(&url)
+            .await
+            .expect("failed to connect to postgres");
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("database migration failed");
+        Some(pool)
+    }
+
+    async fn insert_blob(pool: &PgPool, hash: &str) {
+        sqlx::query(
+            "INSERT INTO content_blobs (hash, language, byte_len, line_count)
+             VALUES ($1, 'rust', 0, 0)
+             ON CONFLICT (hash) DO NOTHING",
+        )
+        .bind(hash)
+        .execute(pool)
+        .await
+        .expect("failed to insert content blob");
+    }
+
+    fn sample_records(hash: &str) -> (Vec<SymbolRecord>, Vec<ReferenceRecord>) {
+        let reference = |name: &str, kind: &str, line: usize| ReferenceRecord {
+            content_hash: hash.to_string(),
+            namespace: Some("demo::module".to_string()),
+            name: name.to_string(),
+            fully_qualified: format!("demo::module::{name}"),
+            kind: Some(kind.to_string()),
+            line,
+            column: 5,
+            end_line: line,
+            end_column: 5 + name.chars().count(),
+            signature: None,
+        };
+        let references = vec![
+            reference("Widget", "definition", 1),
+            reference("Widget", "reference", 10),
+            reference("render", "definition", 3),
+            reference("render", "reference", 12),
+        ];
+        let symbols = ["Widget", "render"]
+            .into_iter()
+            .map(|name| SymbolRecord {
+                content_hash: hash.to_string(),
+                name: name.to_string(),
+            })
+            .collect();
+        (symbols, references)
+    }
+
+    async fn stored_references(pool: &PgPool, hash: &str) -> Vec<StoredReference> {
+        sqlx::query_as(
+            "SELECT s.name, sn.namespace, sr.kind, sr.line_number, sr.column_number
+             FROM symbol_references sr
+             JOIN symbols s ON s.id = sr.symbol_id
+             JOIN symbol_namespaces sn ON sn.id = sr.namespace_id
+             WHERE s.content_hash = $1
+             ORDER BY s.name, sr.line_number, sr.kind",
+        )
+        .bind(hash)
+        .fetch_all(pool)
+        .await
+        .expect("failed to load references")
+    }
+
+    async fn cleanup(pool: &PgPool, hashes: &[&str]) {
+        for hash in hashes {
+            sqlx::query("DELETE FROM content_blobs WHERE hash = $1")
+                .bind(hash)
+                .execute(pool)
+                .await
+                .expect("failed to clean up content blob");
+        }
+    }
+
+    #[tokio::test]
+    async fn references_before_symbols_are_not_dropped() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let hash = format!("test-refs-first-{}", std::process::id());
+        insert_blob(&pool, &hash).await;
+        let (symbols, references) = sample_records(&hash);
+        let expected = references.len();
+        let metrics = Arc::new(IngestMetrics::default());
+
+        insert_reference_records_batch(pool.clone(), references, metrics.clone())
+            .await
+            .expect("reference batch failed");
+        insert_symbol_records_batch(pool.clone(), symbols)
+            .await
+            .expect("symbol batch failed");
+
+        let stored = stored_references(&pool, &hash).await;
+        cleanup(&pool, &[&hash]).await;
+        assert_eq!(stored.len(), expected);
+        assert!(
+            metrics
+                .render(0)
+                .contains("\npointer_reference_created_symbols_total 2\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn ingestion_order_produces_identical_rows() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let symbols_first = format!("test-symbols-first-{}", std::process::id());
+        let references_first = format!("test-references-first-{}", std::process::id());
+        insert_blob(&pool, &symbols_first).await;
+        insert_blob(&pool, &references_first).await;
+
+        let (symbols, references) = sample_records(&symbols_first);
+        insert_symbol_records_batch(pool.clone(), symbols)
+            .await
+            .expect("symbol batch failed");
+        insert_reference_records_batch(pool.clone(), references, Arc::default())
+            .await
+            .expect("reference batch failed");
+
+        let (symbols, references) = sample_records(&references_first);
+        insert_reference_records_batch(pool.clone(), references, Arc::default())
+            .await
+            .expect("reference batch failed");
+        insert_symbol_records_batch(pool.clone(), symbols)
+            .await
+            .expect("symbol batch failed");
+
+        let normal = stored_references(&pool, &symbols_first).await;
+        let reordered = stored_references(&pool, &references_first).await;
+        cleanup(&pool, &[&symbols_first, &references_first]).await;
+        assert!(!normal.is_empty());
+        assert_eq!(normal, reordered);
+    }
+
+    #[tokio::test]
+    async fn manifest_ingestion_reports_section_counts() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let hash = format!("test-manifest-counts-{}", std::process::id());
+        let repository = format!("manifest-counts-{}", std::process::id());
+        insert_blob(&pool, &hash).await;
+        let (symbols, references) = sample_records(&hash);
+        let entry = |section: &str, payload: serde_json::Value| {
+            serde_json::json!({ "section": section, "payload": payload }).to_string()
+        };
+
+        let mut lines = vec![
+            entry("content_blob", serde_json::json!({ "hash": hash })),
+            entry(
+                "symbol_namespace",
+                serde_json::json!({ "namespace": "demo" }),
+            ),
+            entry(
+                "symbol_namespace",
+                serde_json::json!({ "namespace": "demo::module" }),
+            ),
+            entry(
+                "file_pointer",
+                serde_json::json!({
+                    "repository": repository,
+                    "commit_sha": "abc123",
+                    "file_path": "src/lib.rs",
+                    "content_hash": hash,
+                }),
+            ),
+        ];
+        lines.extend(
+            symbols
+                .iter()
+                .map(|symbol| entry("symbol_record", serde_json::to_value(symbol).unwrap())),
+        );
+        lines.extend(
+            references.iter().map(|reference| {
+                entry("reference_record", serde_json::to_value(reference).unwrap())
+            }),
+        );
+        lines.push(entry(
+            "branch_head",
+            serde_json::json!({
+                "repository": repository,
+                "branch": "main",
+                "commit_sha": "abc123",
+            }),
+        ));
+        lines.push(entry(
+            "commit_metadata",
+            serde_json::json!({
+                "repository": repository,
+                "commit_sha": "abc123",
+                "author_name": "Pointer",
+                "author_email": "pointer@example.com",
+                "committed_at": 1_700_000_000,
+                "subject": "Fix GC race",
+            }),
+        ));
+        let manifest = lines.join("\n");
+
+        let ingested = ingest_manifest_stream(&pool, &Arc::default(), manifest.as_bytes()).await;
+        let subject: Option<String> = sqlx::query_scalar(
+            "SELECT subject FROM commits
+             WHERE repository = $1 AND commit_sha = 'abc123'
+               AND committed_at = to_timestamp(1700000000)",
+        )
+        .bind(&repository)
+        .fetch_optional(&pool)
+        .await
+        .expect("failed to load commit metadata");
+        sqlx::query("DELETE FROM commits WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up commits");
+        sqlx::query("DELETE FROM branches WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branches");
+        sqlx::query("DELETE FROM branch_policies WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branch policies");
+        cleanup(&pool, &[&hash]).await;
+
+        assert_eq!(
+            ingested.expect("manifest ingestion failed"),
+            ManifestFinalizeResponse {
+                files: 1,
+                symbols: symbols.len() as u64,
+                references: references.len() as u64,
+                namespaces: 2,
+                branches: 1,
+                commits: 1,
+            }
+        );
+        assert_eq!(subject.as_deref(), Some("Fix GC race"));
+    }
+
+    #[tokio::test]
+    async fn bulk_shards_ingest_mixed_sections_with_references_resolved() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let hash = format!("test-bulk-shards-{}", std::process::id());
+        let repository = format!("bulk-shards-{}", std::process::id());
+        insert_blob(&pool, &hash).await;
+        let scratch = tempfile::tempdir().expect("failed to create scratch dir");
+        let state = AppState {
+            pool: pool.clone(),
+            scratch_dir: scratch.path().to_path_buf(),
+            chunk_need_batch_size: 1,
+            compress_chunk_content: false,
+            max_bulk_shards: 8,
+            orphan_sweep: OrphanSweepOptions::default(),
+            gc_interval: None,
+            gc_status: GcStatus::default(),
+            metrics: Arc::default(),
+        };
+        let (symbols, references) = sample_records(&hash);
+        let shard = |section: &str, index: u64, records: Vec<serde_json::Value>| {
+            let data = records
+                .iter()
+                .map(|record| record.to_string() + "\n")
+                .collect::<String>();
+            serde_json::json!({
+                "section": section,
+                "shard_index": index,
+                "compression": "none",
+                "data": BASE64.encode(data),
+            })
+        };
+        let to_values = |records: &[ReferenceRecord]| {
+            records
+                .iter()
+                .map(|record| serde_json::to_value(record).unwrap())
+                .collect::<Vec<_>>()
+        };
+        // Listed in the reverse of the order they have to be applied in, with
+        // the references split across two shards.
+        let (early_refs, late_refs) = references.split_at(2);
+        let payload: ManifestShardsPayload = serde_json::from_value(serde_json::json!({
+            "shards": [
+                shard("branch_head", 0, vec![serde_json::json!({
+                    "repository": repository,
+                    "branch": "main",
+                    "commit_sha": "abc123",
+                })]),
+                shard("reference_record", 0, to_values(early_refs)),
+                shard("reference_record", 1, to_values(late_refs)),
+                shard(
+                    "symbol_record",
+                    0,
+                    symbols.iter().map(|symbol| serde_json::to_value(symbol).unwrap()).collect(),
+                ),
+                shard("symbol_namespace", 0, vec![serde_json::json!({ "namespace": "demo::module" })]),
+                shard("file_pointer", 0, vec![serde_json::json!({
+                    "repository": repository,
+                    "commit_sha": "abc123",
+                    "file_path": "src/lib.rs",
+                    "content_hash": hash,
+                })]),
+            ]
+        }))
+        .unwrap();
+        let response = manifest_shards(State(state.clone()), Json(payload)).await;
+
+        let too_many: ManifestShardsPayload = serde_json::from_value(serde_json::json!({
+            "shards": vec![shard("symbol_namespace", 0, Vec::new()); 9],
+        }))
+        .unwrap();
+        let rejected = manifest_shards(State(state.clone()), Json(too_many)).await;
+
+        let stored = stored_references(&pool, &hash).await;
+        let files: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM files WHERE repository = $1 AND content_hash = $2",
+        )
+        .bind(&repository)
+        .bind(&hash)
+        .fetch_one(&pool)
+        .await
+        .expect("failed to count files");
+        let head: Option<String> = sqlx::query_scalar(
+            "SELECT commit_sha FROM branches WHERE repository = $1 AND branch = 'main'",
+        )
+        .bind(&repository)
+        .fetch_optional(&pool)
+        .await
+        .expect("failed to load branch head");
+        sqlx::query("DELETE FROM branches WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branches");
+        sqlx::query("DELETE FROM branch_policies WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branch policies");
+        cleanup(&pool, &[&hash]).await;
+
+        let (status, Json(ingested)) = response.expect("bulk shard ingestion failed");
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(
+            ingested,
+            ManifestFinalizeResponse {
+                files: 1,
+                symbols: symbols.len() as u64,
+                references: references.len() as u64,
+                namespaces: 1,
+                branches: 1,
+                commits: 0,
+            }
+        );
+        assert_eq!(
+            rejected.err().map(|err| err.status),
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+        assert_eq!(files, 1);
+        assert_eq!(head.as_deref(), Some("abc123"));
+        assert_eq!(stored.len(), references.len());
+        assert!(
+            stored
+                .iter()
+                .all(|(_, namespace, ..)| namespace == "demo::module")
+        );
+        assert_eq!(
+            stored
+                .iter()
+                .map(|(name, ..)| name.as_str())
+                .collect::<HashSet<_>>(),
+            HashSet::from(["Widget", "render"])
+        );
+    }
+
+    #[tokio::test]
+    async fn unsafe_paths_and_names_are_rejected_before_any_write() {
+        // Nothing may reach the database, so a pool that never connects will do.
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+        let state = AppState {
+            pool: pool.clone(),
+            scratch_dir: PathBuf::from("unused"),
+            chunk_need_batch_size: 1,
+            compress_chunk_content: false,
+            max_bulk_shards: 64,
+            orphan_sweep: OrphanSweepOptions::default(),
+            gc_interval: None,
+            gc_status: GcStatus::default(),
+            metrics: Arc::default(),
+        };
+        let pointer = |repository: &str, path: &str| {
+            serde_json::json!({
+                "repository": repository,
+                "commit_sha": "abc123",
+                "file_path": path,
+                "content_hash": "hash",
+            })
+        };
+        let shard = |section: &str, records: &[serde_json::Value]| {
+            let data = records
+                .iter()
+                .map(|record| record.to_string() + "\n")
+                .collect::<String>();
+            serde_json::from_value::<ManifestShardPayload>(serde_json::json!({
+                "section": section,
+                "compression": "none",
+                "data": BASE64.encode(data),
+            }))
+            .unwrap()
+        };
+
+        let files = shard(
+            "file_pointer",
+            &[
+                pointer("pointer", "src/lib.rs"),
+                pointer("pointer", "../../etc/passwd"),
+                pointer("pointer", "/etc/passwd"),
+                pointer("pointer", "src/nul\u{0}.rs"),
+            ],
+        );
+        let err = manifest_shard(State(state.clone()), Json(files))
+            .await
+            .expect_err("unsafe paths were accepted");
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(
+            err.message.starts_with("rejected 3 file pointers:"),
+            "{}",
+            err.message
+        );
+        assert!(
+            err.message.contains("\"../../etc/passwd\""),
+            "{}",
+            err.message
+        );
+        assert!(err.message.contains("\"/etc/passwd\""), "{}", err.message);
+
+        let branches = shard(
+            "branch_head",
+            &[serde_json::json!({
+                "repository": "pointer",
+                "branch": "feature/../main",
+                "commit_sha": "abc123",
+            })],
+        );
+        let err = manifest_shard(State(state.clone()), Json(branches))
+            .await
+            .expect_err("unsafe branch name was accepted");
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let entry = |section: &str, payload: serde_json::Value| {
+            serde_json::json!({ "section": section, "payload": payload }).to_string()
+        };
+        let manifest = [
+            entry("file_pointer", pointer("pointer", "src//lib.rs")),
+            entry("file_pointer", pointer("org/pointer", "src/lib.rs")),
+            entry(
+                "symbol_namespace",
+                serde_json::json!({ "namespace": "demo" }),
+            ),
+            entry("file_pointer", pointer("pointer", "src\\lib.rs")),
+        ]
+        .join("\n");
+        let err = ingest_manifest_stream(&pool, &Arc::default(), manifest.as_bytes())
+            .await
+            .expect_err("unsafe manifest records were accepted");
+        let message = err.to_string();
+        assert!(matches!(err, ApiErrorKind::Invalid(_)), "{message}");
+        assert!(
+            message.starts_with("rejected 2 manifest records:"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn manifest_shards_decode_with_each_codec() {
+        let plain = b"{\"repository\":\"demo\"}\n".to_vec();
+        let zstd = zstd::stream::encode_all(plain.as_slice(), 0).unwrap();
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&plain).unwrap();
+        let gzip = gzip.finish().unwrap();
+
+        let decode = |fields: serde_json::Value, data: &[u8]| {
+            let mut payload = serde_json::json!({
+                "section": "file_pointer",
+                "data": BASE64.encode(data),
+            });
+            for (key, value) in fields.as_object().unwrap() {
+                payload[key] = value.clone();
+            }
+            let payload: ManifestShardPayload = serde_json::from_value(payload).unwrap();
+            decode_manifest_shard(&payload).map_err(|err| err.message)
+        };
+
+        let cases = [
+            (serde_json::json!({"compression": "zstd"}), &zstd),
+            (serde_json::json!({"compression": "gzip"}), &gzip),
+            (serde_json::json!({"compression": "none"}), &plain),
+            // Legacy payloads: `compressed: true` and a missing field mean zstd.
+            (serde_json::json!({"compressed": true}), &zstd),
+            (serde_json::json!({"compressed": false}), &plain),
+            (serde_json::json!({}), &zstd),
+            (
+                serde_json::json!({"compression": "gzip", "compressed": true}),
+                &gzip,
+            ),
+        ];
+        for (fields, data) in cases {
+            assert_eq!(decode(fields.clone(), data), Ok(plain.clone()), "{fields}");
+        }
+        assert!(decode(serde_json::json!({"compression": "gzip"}), &zstd).is_err());
+    }
+
+    #[test]
+    fn corrupted_manifest_chunks_fail_their_checksum() {
+        let data = b"{\"repository\":\"demo\"}\n".to_vec();
+        let sha256 = hex::encode(Sha256::digest(&data));
+        let chunk = |data: &[u8], sha256: Option<&str>| ManifestChunkPayload {
+            upload_id: "upload".to_string(),
+            chunk_index: 0,
+            total_chunks: 1,
+            data: BASE64.encode(data),
+            sha256: sha256.map(str::to_string),
+        };
+
+        assert_eq!(
+            decode_manifest_chunk(&chunk(&data, Some(&sha256.to_uppercase()))).ok(),
+            Some(data.clone())
+        );
+        assert_eq!(
+            decode_manifest_chunk(&chunk(&data[..10], None)).ok(),
+            Some(data[..10].to_vec())
+        );
+        let err = decode_manifest_chunk(&chunk(&data[..10], Some(&sha256)))
+            .expect_err("truncated chunk should be rejected");
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(err.message.contains("manifest chunk 0 sha256 mismatch"));
+
+        let shard: ManifestShardPayload = serde_json::from_value(serde_json::json!({
+            "section": "file_pointer",
+            "compression": "none",
+            "data": BASE64.encode(&data[..10]),
+            "sha256": sha256,
+        }))
+        .unwrap();
+        let err = decode_manifest_shard(&shard).expect_err("truncated shard should be rejected");
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn api_diff_reports_added_removed_and_changed_definitions() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let repository = format!("api-diff-{}", std::process::id());
+        // `wrap` moves and is reformatted across lines, which leaves its
+        // normalized signature unchanged; `legacy` only gains a signature.
+        let commits: [(&str, Vec<SeededDefinition>); 2] = [
+            (
+                "base",
+                vec![
+                    ("keep", 1, Some("fn keep()")),
+                    ("scale", 2, Some("fn scale(x: u8)")),
+                    ("old", 3, Some("fn old()")),
+                    ("wrap", 4, Some("pub fn wrap(a: u32, b: u32) -> u32")),
+                    ("legacy", 5, None),
+                ],
+            ),
+            (
+                "head",
+                vec![
+                    ("wrap", 1, Some("pub fn wrap(a: u32, b: u32) -> u32")),
+                    ("keep", 5, Some("fn keep()")),
+                    ("scale", 6, Some("fn scale(x: u16)")),
+                    ("new", 7, Some("fn new()")),
+                    ("legacy", 8, Some("fn legacy()")),
+                ],
+            ),
+        ];
+        let mut hashes = Vec::new();
+        for (commit, definitions) in commits {
+            let hash = format!("{repository}:{commit}");
+            insert_blob(&pool, &hash).await;
+            sqlx::query(
+                "INSERT INTO files (repository, commit_sha, file_path, content_hash)
+                 VALUES ($1, $2, 'src/lib.rs', $3)",
+            )
+            .bind(&repository)
+            .bind(commit)
+            .bind(&hash)
+            .execute(&pool)
+            .await
+            .expect("failed to insert file");
+
+            let symbols = definitions
+                .iter()
+                .map(|(name, _, _)| SymbolRecord {
+                    content_hash: hash.clone(),
+                    name: name.to_string(),
+                })
+                .collect();
+            let references = definitions
+                .iter()
+                .map(|(name, line, signature)| ReferenceRecord {
+                    content_hash: hash.clone(),
+                    namespace: None,
+                    name: name.to_string(),
+                    fully_qualified: name.to_string(),
+                    kind: Some("definition".to_string()),
+                    line: *line,
+                    column: 4,
+                    end_line: *line,
+                    end_column: 4 + name.len(),
+                    signature: signature.map(str::to_string),
+                })
+                .collect();
+            insert_symbol_records_batch(pool.clone(), symbols)
+                .await
+                .expect("symbol batch failed");
+            insert_reference_records_batch(pool.clone(), references, Arc::default())
+                .await
+                .expect("reference batch failed");
+            hashes.push(hash);
+        }
+
+        let diff = api_diff::api_diff(&pool, &repository, "base", "head").await;
+        for hash in &hashes {
+            cleanup(&pool, &[hash.as_str()]).await;
+        }
+
+        let diff = diff.expect("api diff failed");
+        let names = |symbols: &[api_diff::ApiSymbol]| -> Vec<String> {
+            symbols.iter().map(|symbol| symbol.name.clone()).collect()
+        };
+        assert_eq!(names(&diff.added), ["new"]);
+        assert_eq!(names(&diff.removed), ["old"]);
+        assert_eq!(diff.modified.len(), 1);
+        let scale = &diff.modified[0];
+        assert_eq!(scale.name, "scale");
+        assert_eq!(
+            scale.before[0].signature.as_deref(),
+            Some("fn scale(x: u8)")
+        );
+        assert_eq!(
+            scale.after[0].signature.as_deref(),
+            Some("fn scale(x: u16)")
+        );
+    }
+
+    #[tokio::test]
+    async fn ingest_chunks_reports_every_failure_and_cancels_the_rest() {
+        use std::sync::Arc;
+
+        struct DropCounter(Arc<AtomicU64>);
+        impl Drop for DropCounter {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+        let dropped = Arc::new(AtomicU64::new(0));
+        let finished = Arc::new(AtomicU64::new(0));
+        let chunks = vec![vec![1], vec![2], vec![0], vec![0], vec![0], vec![0]];
+
+        let started = std::time::Instant::now();
+        let (task_dropped, task_finished) = (dropped.clone(), finished.clone());
+        let result = ingest_chunks(
+            &pool,
+            chunks,
+            move |_pool, chunk: Vec<u32>| {
+                let guard = DropCounter(task_dropped.clone());
+                let finished = task_finished.clone();
+                async move {
+                    let _guard = guard;
+                    if chunk[0] > 0 {
+                        return Err(ApiErrorKind::Internal(anyhow!("batch {} broke", chunk[0])));
+                    }
+                    time::sleep(Duration::from_secs(60)).await;
+                    finished.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            },
+            0,
+        )
+        .await;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("2 of 6 batches failed"), "{message}");
+        assert!(message.contains("batch 1 broke"), "{message}");
+        assert!(message.contains("batch 2 broke"), "{message}");
+        assert_eq!(finished.load(Ordering::SeqCst), 0);
+        assert_eq!(dropped.load(Ordering::SeqCst), 6);
+    }
+
+    #[derive(Debug)]
+    struct FakeDbError(&'static str);
+
+    impl std::fmt::Display for FakeDbError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "fake database error {}", self.0)
+        }
+    }
+
+    impl std::error::Error for FakeDbError {}
+
+    impl sqlx::error::DatabaseError for FakeDbError {
+        fn message(&self) -> &str {
+            "fake database error"
+        }
+
+        fn code(&self) -> Option<std::borrow::Cow<'_, str>> {
+            Some(self.0.into())
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            sqlx::error::ErrorKind::Other
+        }
+    }
+
+    fn db_error(code: &'static str) -> ApiErrorKind {
+        ApiErrorKind::Database(sqlx::Error::Database(Box::new(FakeDbError(code))))
+    }
+
+    #[test]
+    fn only_deadlocks_and_serialization_failures_are_transient() {
+        assert!(is_transient_db_error(&db_error("40001")));
+        assert!(is_transient_db_error(&db_error("40P01")));
+        // Unique and foreign key violations, bad input.
+        assert!(!is_transient_db_error(&db_error("23505")));
+        assert!(!is_transient_db_error(&db_error("23503")));
+        assert!(!is_transient_db_error(&db_error("22P02")));
+        assert!(!is_transient_db_error(&ApiErrorKind::Database(
+            sqlx::Error::RowNotFound
+        )));
+        assert!(!is_transient_db_error(&ApiErrorKind::Internal(anyhow!(
+            "deadlock detected"
+        ))));
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried_until_the_batch_succeeds() {
+        let attempts = &AtomicU64::new(0);
+        let attempt = || async move {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(db_error("40P01")),
+                1 => Err(db_error("40001")),
+                _ => Ok(()),
+            }
+        };
+        let result = retry_transient(attempt(), attempt).await;
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn permanent_failures_and_exhausted_retries_are_returned() {
+        let attempts = &AtomicU64::new(0);
+        let attempt = || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(db_error("23505"))
+        };
+        let result = retry_transient(attempt(), attempt).await;
+        assert!(is_db_code(result, "23505"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        let attempts = &AtomicU64::new(0);
+        let attempt = || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(db_error("40001"))
+        };
+        let result = retry_transient(attempt(), attempt).await;
+        assert!(is_db_code(result, "40001"));
+        assert_eq!(
+            attempts.load(Ordering::SeqCst),
+            u64::from(INGEST_RETRIES) + 1
+        );
+    }
+
+    fn is_db_code(result: Result<(), ApiErrorKind>, expected: &str) -> bool {
+        matches!(
+            result,
+            Err(ApiErrorKind::Database(sqlx::Error::Database(err)))
+                if err.code().as_deref() == Some(expected)
+        )
+    }
+
+    #[test]
+    fn reference_records_without_spans_end_where_they_start() {
+        let legacy = br#"{"content_hash":"h","namespace":null,"name":"render","fully_qualified":"render","kind":"reference","line":4,"column":7}"#;
+        let record: ReferenceRecord = serde_json::from_slice(legacy).unwrap();
+        assert_eq!((record.end_line, record.end_column), (4, 7));
+        assert_eq!(record.signature, None);
+
+        let spanned = br#"{"content_hash":"h","namespace":null,"name":"render","fully_qualified":"render","kind":"reference","line":4,"column":7,"end_line":4,"end_column":13}"#;
+        let record: ReferenceRecord = serde_json::from_slice(spanned).unwrap();
+        assert_eq!((record.end_line, record.end_column), (4, 13));
+    }
+
+    #[test]
+    fn file_pointers_without_a_kind_ingest_as_regular_files() {
+        let legacy = br#"{"section":"file_pointer","payload":{"repository":"demo","commit_sha":"c","file_path":"src/lib.rs","content_hash":"h"}}"#;
+        let ManifestEnvelope::FilePointer(pointer) =
+            serde_json::from_slice::<ManifestEnvelope>(legacy).unwrap()
+        else {
+            panic!("expected a file pointer");
+        };
+        assert_eq!((pointer.kind, pointer.mode), (FileKind::Regular, None));
+
+        let linked = br#"{"section":"file_pointer","payload":{"repository":"demo","commit_sha":"c","file_path":"current","content_hash":"h","kind":"symlink","mode":40960}}"#;
+        let ManifestEnvelope::FilePointer(pointer) =
+            serde_json::from_slice::<ManifestEnvelope>(linked).unwrap()
+        else {
+            panic!("expected a file pointer");
+        };
+        assert_eq!(
+            (pointer.kind, pointer.mode),
+            (FileKind::Symlink, Some(0o120000))
+        );
+    }
+
+    async fn scrape_metrics(state: &AppState) -> String {
+        let response = metrics_handler(State(state.clone()))
+            .await
+            .expect("metrics failed")
+            .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("failed to read metrics body");
+        String::from_utf8(body.to_vec()).expect("metrics are not utf-8")
+    }
+
+    fn metric_value(metrics: &str, series: &str) -> u64 {
+        metrics
+            .lines()
+            .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| panic!("missing series {series} in:\n{metrics}"))
+    }
+
+    #[tokio::test]
+    async fn metrics_count_rows_from_shards_and_finalized_manifests() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let repository = format!("metrics-{}", std::process::id());
+        let hash = format!("test-metrics-{}", std::process::id());
+        insert_blob(&pool, &hash).await;
+        let scratch = tempfile::tempdir().expect("failed to create scratch dir");
+        let state = AppState {
+            pool: pool.clone(),
+            scratch_dir: scratch.path().to_path_buf(),
+            chunk_need_batch_size: 1,
+            compress_chunk_content: false,
+            max_bulk_shards: 64,
+            orphan_sweep: OrphanSweepOptions::default(),
+            gc_interval: None,
+            gc_status: GcStatus::default(),
+            metrics: Arc::default(),
+        };
+        let pointer = |path: &str| FilePointer {
+            repository: repository.clone(),
+            commit_sha: "abc123".to_string(),
+            file_path: path.to_string(),
+            content_hash: hash.clone(),
+            kind: FileKind::Regular,
+            mode: None,
+        };
+        let before = scrape_metrics(&state).await;
+
+        let shard = [pointer("src/a.rs"), pointer("src/b.rs")]
+            .iter()
+            .map(|pointer| serde_json::to_string(pointer).unwrap() + "\n")
+            .collect::<String>();
+        let shard: ManifestShardPayload = serde_json::from_value(serde_json::json!({
+            "section": "file_pointer",
+            "compression": "none",
+            "data": BASE64.encode(shard),
+        }))
+        .unwrap();
+        let shard_status = manifest_shard(State(state.clone()), Json(shard)).await;
+
+        let upload_id = format!("metrics-upload-{}", std::process::id());
+        let manifest = serde_json::json!({
+            "section": "file_pointer",
+            "payload": pointer("src/c.rs"),
+        })
+        .to_string()
+            + "\n";
+        let chunk_status = manifest_chunk(
+            State(state.clone()),
+            Json(ManifestChunkPayload {
+                upload_id: upload_id.clone(),
+                chunk_index: 0,
+                total_chunks: 1,
+                data: BASE64.encode(manifest),
+                sha256: None,
+            }),
+        )
+        .await;
+        let pending = scrape_metrics(&state).await;
+        let finalized = manifest_finalize(
+            State(state.clone()),
+            Json(ManifestFinalizePayload {
+                upload_id: upload_id.clone(),
+                compression: Some(ManifestCompression::None),
+                compressed: None,
+                manifest_sha256: None,
+            }),
+        )
+        .await;
+        let after = scrape_metrics(&state).await;
+
+        sqlx::query("DELETE FROM upload_chunks WHERE upload_id = $1")
+            .bind(&upload_id)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up upload chunks");
+        cleanup(&pool, &[&hash]).await;
+
+        assert!(
+            shard_status.is_ok(),
+            "shard failed: {:?}",
+            shard_status.err()
+        );
+        assert!(
+            chunk_status.is_ok(),
+            "chunk failed: {:?}",
+            chunk_status.err()
+        );
+        assert!(finalized.is_ok(), "finalize failed: {:?}", finalized.err());
+        let files = r#"pointer_ingested_rows_total{section="files"}"#;
+        assert_eq!(metric_value(&before, files), 0);
+        assert_eq!(metric_value(&after, files), 3);
+        assert_eq!(
+            metric_value(&after, r#"pointer_ingested_rows_total{section="symbols"}"#),
+            0
+        );
+        assert_eq!(
+            metric_value(&before, "pointer_manifest_finalizations_total"),
+            0
+        );
+        assert_eq!(
+            metric_value(&after, "pointer_manifest_finalizations_total"),
+            1
+        );
+        assert!(metric_value(&pending, "pointer_upload_chunks_pending") >= 1);
+    }
+
+    #[tokio::test]
+    async fn gc_status_reports_the_last_run_and_next_schedule() {
+        let state = AppState {
+            pool: PgPoolOptions::new()
+                .connect_lazy("postgres://localhost/unused")
+                .unwrap(),
+            scratch_dir: PathBuf::from("unused"),
+            chunk_need_batch_size: 1,
+            compress_chunk_content: false,
+            max_bulk_shards: 64,
+            orphan_sweep: OrphanSweepOptions::default(),
+            gc_interval: Some(Duration::from_secs(600)),
+            gc_status: GcStatus::default(),
+            metrics: Arc::default(),
+        };
+
+        let Json(before) = gc_status_handler(State(state.clone())).await;
+        assert!(before.enabled);
+        assert_eq!(before.interval_secs, Some(600));
+        assert!(before.last_run.is_none());
+        assert!(before.next_run_at.is_none());
+
+        let started_at = Utc::now();
+        let outcome = GcOutcome {
+            branches_evaluated: 3,
+            snapshots_removed: 2,
+            commits_pruned: 1,
+            ..GcOutcome::default()
+        };
+        let run = LastGcRun::new(started_at, Duration::from_millis(1500), &Ok(outcome));
+        *state.gc_status.lock().unwrap() = Some(run.clone());
+
+        let Json(after) = gc_status_handler(State(state)).await;
+        assert_eq!(after.last_run.as_ref(), Some(&run));
+        assert_eq!(run.duration_ms, 1500);
+        assert_eq!(
+            (
+                run.branches_evaluated,
+                run.snapshots_removed,
+                run.commits_pruned
+            ),
+            (3, 2, 1)
+        );
+        assert_eq!(
+            after.next_run_at,
+            Some(
+                started_at + chrono::Duration::milliseconds(1500) + chrono::Duration::seconds(600)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn retention_keeps_commits_inside_the_age_window() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let repository = format!("retention-age-{}", std::process::id());
+        let hash = format!("test-retention-age-{}", std::process::id());
+        insert_blob(&pool, &hash).await;
+        sqlx::query("INSERT INTO branch_policies (repository, branch) VALUES ($1, 'main')")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to insert branch policy");
+        // (commit, days since it was indexed)
+        let commits = [("c-today", 0), ("c-last-week", 7), ("c-last-year", 365)];
+        for (commit, age_days) in commits {
+            sqlx::query(
+                "INSERT INTO files (repository, commit_sha, file_path, content_hash)
+                 VALUES ($1, $2, 'src/lib.rs', $3)",
+            )
+            .bind(&repository)
+            .bind(commit)
+            .bind(&hash)
+            .execute(&pool)
+            .await
+            .expect("failed to insert file");
+            sqlx::query(
+                "INSERT INTO branch_snapshots (repository, branch, commit_sha, indexed_at)
+                 VALUES ($1, 'main', $2, NOW() - $3 * INTERVAL '1 day')",
+            )
+            .bind(&repository)
+            .bind(commit)
+            .bind(age_days as f64)
+            .execute(&pool)
+            .await
+            .expect("failed to insert snapshot");
+        }
+        sqlx::query(
+            "INSERT INTO branches (repository, branch, commit_sha) VALUES ($1, 'main', 'c-today')",
+        )
+        .bind(&repository)
+        .execute(&pool)
+        .await
+        .expect("failed to insert branch");
+
+        let result = apply_retention_policy(
+            &pool,
+            &RetentionPolicyConfig {
+                repository: repository.clone(),
+                keep_latest: false,
+                max_commits_to_keep: Some(1),
+                keep_newer_than_days: Some(30),
+                max_age_days: None,
+            },
+        )
+        .await;
+        let mut remaining: Vec<String> =
+            sqlx::query_scalar("SELECT DISTINCT commit_sha FROM files WHERE repository = $1")
+                .bind(&repository)
+                .fetch_all(&pool)
+                .await
+                .expect("failed to load remaining commits");
+        remaining.sort();
+
+        sqlx::query("DELETE FROM branches WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branches");
+        sqlx::query("DELETE FROM branch_policies WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branch policies");
+        cleanup(&pool, &[&hash]).await;
+
+        assert!(result.is_ok(), "retention failed: {:?}", result.err());
+        assert_eq!(remaining, ["c-last-week", "c-today"]);
+    }
+
+    #[tokio::test]
+    async fn retention_max_age_keeps_recent_and_untimestamped_commits() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let repository = format!("retention-max-age-{}", std::process::id());
+        let hash = format!("test-retention-max-age-{}", std::process::id());
+        insert_blob(&pool, &hash).await;
+        sqlx::query("INSERT INTO branch_policies (repository, branch) VALUES ($1, 'main')")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to insert branch policy");
+        for commit in [
+            "c-today",
+            "c-last-week",
+            "c-last-year",
+            "c-null-branch",
+            "c-unindexed",
+        ] {
+            sqlx::query(
+                "INSERT INTO files (repository, commit_sha, file_path, content_hash)
+                 VALUES ($1, $2, 'src/lib.rs', $3)",
+            )
+            .bind(&repository)
+            .bind(commit)
+            .bind(&hash)
+            .execute(&pool)
+            .await
+            .expect("failed to insert file");
+        }
+        // (commit, days since it was indexed); c-unindexed has no snapshot at all
+        for (commit, age_days) in [("c-last-week", 7), ("c-last-year", 365)] {
+            sqlx::query(
+                "INSERT INTO branch_snapshots (repository, branch, commit_sha, indexed_at)
+                 VALUES ($1, 'main', $2, NOW() - $3 * INTERVAL '1 day')",
+            )
+            .bind(&repository)
+            .bind(commit)
+            .bind(age_days as f64)
+            .execute(&pool)
+            .await
+            .expect("failed to insert snapshot");
+        }
+        sqlx::query(
+            "INSERT INTO branches (repository, branch, commit_sha, indexed_at)
+             VALUES ($1, 'main', 'c-today', NOW()), ($1, 'dev', 'c-null-branch', NULL)",
+        )
+        .bind(&repository)
+        .execute(&pool)
+        .await
+        .expect("failed to insert branches");
+
+        let result = apply_retention_policy(
+            &pool,
+            &RetentionPolicyConfig {
+                repository: repository.clone(),
+                keep_latest: false,
+                max_commits_to_keep: None,
+                keep_newer_than_days: None,
+                max_age_days: Some(30),
+            },
+        )
+        .await;
+        let mut remaining: Vec<String> =
+            sqlx::query_scalar("SELECT DISTINCT commit_sha FROM files WHERE repository = $1")
+                .bind(&repository)
+                .fetch_all(&pool)
+                .await
+                .expect("failed to load remaining commits");
+        remaining.sort();
+
+        sqlx::query("DELETE FROM branches WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branches");
+        sqlx::query("DELETE FROM branch_policies WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branch policies");
+        cleanup(&pool, &[&hash]).await;
+
+        assert!(result.is_ok(), "retention failed: {:?}", result.err());
+        assert_eq!(
+            remaining,
+            ["c-last-week", "c-null-branch", "c-today", "c-unindexed"]
+        );
+    }
+
+    #[tokio::test]
+    async fn repo_statuses_report_branch_freshness() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let repository = format!("repo-status-{}", std::process::id());
+        for branch in ["main", "dev"] {
+            sqlx::query("INSERT INTO branch_policies (repository, branch) VALUES ($1, $2)")
+                .bind(&repository)
+                .bind(branch)
+                .execute(&pool)
+                .await
+                .expect("failed to insert branch policy");
+        }
+        // (branch, head commit, days since it was indexed)
+        let heads = [("main", "c-main", 0), ("dev", "c-dev", 2)];
+        for (branch, commit, age_days) in heads {
+            sqlx::query(
+                "INSERT INTO branches (repository, branch, commit_sha, indexed_at)
+                 VALUES ($1, $2, $3, NOW() - $4 * INTERVAL '1 day')",
+            )
+            .bind(&repository)
+            .bind(branch)
+            .bind(commit)
+            .bind(age_days as f64)
+            .execute(&pool)
+            .await
+            .expect("failed to insert branch");
+        }
+        for (branch, commit) in [("main", "c-main"), ("main", "c-main-old"), ("dev", "c-dev")] {
+            sqlx::query(
+                "INSERT INTO branch_snapshots (repository, branch, commit_sha) VALUES ($1, $2, $3)",
+            )
+            .bind(&repository)
+            .bind(branch)
+            .bind(commit)
+            .execute(&pool)
+            .await
+            .expect("failed to insert snapshot");
+        }
+        sqlx::query("INSERT INTO repo_live_branches (repository, branch) VALUES ($1, 'main')")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to insert live branch");
+
+        let all = repo_statuses(&pool, Some(&repository), None).await;
+        let stale = repo_statuses(&pool, Some(&repository), Some(3600)).await;
+        let ancient = repo_statuses(&pool, Some(&repository), Some(3 * 86_400)).await;
+
+        sqlx::query("DELETE FROM branches WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branches");
+        sqlx::query("DELETE FROM branch_policies WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branch policies");
+
+        let all = all.expect("repo status lookup failed");
+        let json = serde_json::to_value(RepoStatusResponse { repositories: all }).unwrap();
+        let status = &json["repositories"][0];
+        assert_eq!(json["repositories"].as_array().unwrap().len(), 1);
+        assert_eq!(status["repository"], repository.as_str());
+        assert_eq!(status["live_branch"], "main");
+        let branches = status["branches"].as_array().unwrap();
+        let summary: Vec<(&str, &str, i64)> = branches
+            .iter()
+            .map(|branch| {
+                assert!(
+                    branch["indexed_at"].is_string(),
+                    "unexpected branch: {branch}"
+                );
+                (
+                    branch["branch"].as_str().unwrap(),
+                    branch["commit_sha"].as_str().unwrap(),
+                    branch["snapshot_count"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(summary, [("dev", "c-dev", 1), ("main", "c-main", 2)]);
+
+        let stale = stale.expect("repo status lookup failed");
+        assert_eq!(stale.len(), 1);
+        let stale_branches: Vec<&str> = stale[0]
+            .branches
+            .iter()
+            .map(|branch| branch.branch.as_str())
+            .collect();
+        assert_eq!(stale_branches, ["dev"]);
+
+        assert!(ancient.expect("repo status lookup failed").is_empty());
+    }
+
+    /// The name_lc tests scan the whole `symbols` table and share the
+    /// backfill watermark, so they must not overlap.
+    static NAME_LC_TESTS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    async fn insert_symbol(conn: &mut PgConnection, hash: &str, name: &str, name_lc: &str) -> i64 {
+        sqlx::query_scalar(
+            "INSERT INTO symbols (content_hash, name, name_lc)
+             VALUES ($1, $2, $3)
+             RETURNING id::BIGINT",
+        )
+        .bind(hash)
+        .bind(name)
+        .bind(name_lc)
+        .fetch_one(conn)
+        .await
+        .expect("failed to insert symbol")
+    }
+
+    async fn stored_name_lcs(pool: &PgPool, hash: &str) -> Vec<(String, String)> {
+        sqlx::query_as("SELECT name, name_lc FROM symbols WHERE content_hash = $1 ORDER BY id")
+            .bind(hash)
+            .fetch_all(pool)
+            .await
+            .expect("failed to load symbols")
+    }
+
+    fn backfill_options(batch_size: i64, max_batches: i64) -> BackfillOptions {
+        BackfillOptions {
+            batch_size,
+            max_batches,
+            pause: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn name_lc_backfill_resumes_from_watermark() {
+        let _serial = NAME_LC_TESTS.lock().await;
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let hash = format!("test-name-lc-{}", std::process::id());
+        insert_blob(&pool, &hash).await;
+        // Lock out concurrent inserts so the fixture gets consecutive ids.
+        let mut tx = pool.begin().await.expect("failed to begin transaction");
+        sqlx::query("LOCK TABLE symbols IN EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await
+            .expect("failed to lock symbols");
+        let first_id = insert_symbol(&mut tx, &hash, "Alpha", "alpha").await;
+        insert_symbol(&mut tx, &hash, "Beta", "WRONG").await;
+        insert_symbol(&mut tx, &hash, "Gamma", "gamma ").await;
+        insert_symbol(&mut tx, &hash, "Delta", "DELTA").await;
+        tx.commit().await.expect("failed to commit fixture");
+
+        // Start just before the fixture so unrelated rows don't shift batches.
+        sqlx::query(
+            "INSERT INTO backfill_state (job, last_id) VALUES ($1, $2)
+             ON CONFLICT (job) DO UPDATE
+             SET last_id = EXCLUDED.last_id, pass_rows_updated = 0, clean_passes = 0",
+        )
+        .bind(backfill::NAME_LC_JOB)
+        .bind(first_id - 1)
+        .execute(&pool)
+        .await
+        .expect("failed to seed watermark");
+
+        // Stop after one batch, as if the run had been interrupted.
+        let interrupted = backfill::backfill_name_lc(&pool, backfill_options(2, 1))
+            .await
+            .expect("first batch failed");
+        let after_interrupt = stored_name_lcs(&pool, &hash).await;
+
+        let resumed = backfill::backfill_name_lc(&pool, backfill_options(2, 0))
+            .await
+            .expect("resumed backfill failed");
+        let after_resume = stored_name_lcs(&pool, &hash).await;
+
+        let mut passes = Vec::new();
+        for _ in 0..2 {
+            passes.push(
+                backfill::backfill_name_lc(&pool, backfill_options(10_000, 0))
+                    .await
+                    .expect("verification pass failed"),
+            );
+        }
+        cleanup(&pool, &[&hash]).await;
+
+        assert_eq!(interrupted.rows_updated, 1);
+        assert_eq!(interrupted.last_id, first_id + 1);
+        assert!(!interrupted.pass_complete);
+        assert_eq!(
+            after_interrupt[2],
+            ("Gamma".to_string(), "gamma ".to_string())
+        );
+
+        assert_eq!(resumed.rows_updated, 2);
+        assert!(resumed.pass_complete);
+        assert_eq!(resumed.clean_passes, 0);
+        assert!(
+            after_resume
+                .iter()
+                .all(|(name, name_lc)| *name_lc == name.to_lowercase()),
+            "{after_resume:?}"
+        );
+
+        assert_eq!(passes[0].rows_updated, 0);
+        assert_eq!(passes[0].clean_passes, 1);
+        assert!(!passes[0].ready_for_constraint);
+        assert_eq!(passes[1].clean_passes, 2);
+        assert!(passes[1].ready_for_constraint);
+    }
+
+    #[tokio::test]
+    async fn name_lc_trigger_waits_for_clean_backfill() {
+        let _serial = NAME_LC_TESTS.lock().await;
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let hash = format!("test-name-lc-guard-{}", std::process::id());
+        // Everything happens in one transaction that is rolled back, so the
+        // trigger never outlives the test.
+        let mut tx = pool.begin().await.expect("failed to begin transaction");
+        sqlx::query(
+            "INSERT INTO content_blobs (hash, language, byte_len, line_count)
+             VALUES ($1, 'rust', 0, 0)",
+        )
+        .bind(&hash)
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert content blob");
+        sqlx::query("DELETE FROM backfill_state WHERE job = $1")
+            .bind(backfill::NAME_LC_JOB)
+            .execute(&mut *tx)
+            .await
+            .expect("failed to reset backfill state");
+
+        let guard = backfill::ensure_name_lc_trigger(&mut tx).await.unwrap();
+        assert_eq!(guard, NameLcGuard::AwaitingBackfill { clean_passes: 0 });
+
+        sqlx::query("INSERT INTO backfill_state (job, clean_passes) VALUES ($1, $2)")
+            .bind(backfill::NAME_LC_JOB)
+            .bind(backfill::REQUIRED_CLEAN_PASSES)
+            .execute(&mut *tx)
+            .await
+            .expect("failed to record clean passes");
+        let bad_id = insert_symbol(&mut tx, &hash, "Widget", "WIDGET").await;
+        let guard = backfill::ensure_name_lc_trigger(&mut tx).await.unwrap();
+        assert_eq!(guard, NameLcGuard::BadRowsRemain);
+
+        sqlx::query("UPDATE symbols SET name_lc = LOWER(name) WHERE id = $1")
+            .bind(bad_id)
+            .execute(&mut *tx)
+            .await
+            .expect("failed to fix symbol");
+        let guard = backfill::ensure_name_lc_trigger(&mut tx).await.unwrap();
+        assert_eq!(guard, NameLcGuard::Installed);
+
+        let synced_id = insert_symbol(&mut tx, &hash, "Gadget", "WRONG").await;
+        let synced: String = sqlx::query_scalar("SELECT name_lc FROM symbols WHERE id = $1")
+            .bind(synced_id)
+            .fetch_one(&mut *tx)
+            .await
+            .expect("failed to read symbol");
+        assert_eq!(synced, "gadget");
+        let guard = backfill::ensure_name_lc_trigger(&mut tx).await.unwrap();
+        assert_eq!(guard, NameLcGuard::AlreadyInstalled);
+
+        tx.rollback().await.expect("failed to roll back");
+    }
+
+    async fn link_chunk(pool: &PgPool, hash: &str, chunk_hash: &str, index: i32) {
+        sqlx::query(
+            "INSERT INTO chunks (chunk_hash, text_content) VALUES ($1, $1)
+             ON CONFLICT (chunk_hash) DO NOTHING",
+        )
+        .bind(chunk_hash)
+        .execute(pool)
+        .await
+        .expect("failed to insert chunk");
+        sqlx::query(
+            "INSERT INTO content_blob_chunks (content_hash, chunk_hash, chunk_index, chunk_line_count)
+             VALUES ($1, $2, $3, 1)",
+        )
+        .bind(hash)
+        .bind(chunk_hash)
+        .bind(index)
+        .execute(pool)
+        .await
+        .expect("failed to link chunk");
+    }
+
+    async fn rows_for_hash(pool: &PgPool, hash: &str) -> Vec<i64> {
+        let mut counts = Vec::new();
+        for sql in [
+            "SELECT COUNT(*) FROM content_blobs WHERE hash = $1",
+            "SELECT COUNT(*) FROM content_blob_chunks WHERE content_hash = $1",
+            "SELECT COUNT(*) FROM files WHERE content_hash = $1",
+            "SELECT COUNT(*) FROM symbols WHERE content_hash = $1",
+            "SELECT COUNT(*) FROM symbol_references sr
+             JOIN symbols s ON s.id = sr.symbol_id
+             WHERE s.content_hash = $1",
+        ] {
+            let count: i64 = sqlx::query_scalar(sql)
+                .bind(hash)
+                .fetch_one(pool)
+                .await
+                .expect("failed to count rows");
+            counts.push(count);
+        }
+        counts
+    }
+
+    #[tokio::test]
+    async fn purge_content_hash_removes_only_that_blob() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let pid = std::process::id();
+        let secret = format!("test-purge-secret-{pid}");
+        let kept = format!("test-purge-kept-{pid}");
+        let repository = format!("test-purge-repo-{pid}");
+        let shared_chunk = format!("test-purge-shared-chunk-{pid}");
+        let secret_chunk = format!("test-purge-secret-chunk-{pid}");
+        let kept_chunk = format!("test-purge-kept-chunk-{pid}");
+        let secret_name = format!("leaked_token_{pid}");
+        insert_blob(&pool, &secret).await;
+        insert_blob(&pool, &kept).await;
+
+        link_chunk(&pool, &secret, &shared_chunk, 0).await;
+        link_chunk(&pool, &secret, &secret_chunk, 1).await;
+        link_chunk(&pool, &kept, &shared_chunk, 0).await;
+        link_chunk(&pool, &kept, &kept_chunk, 1).await;
+
+        for (commit_sha, file_path, hash) in [
+            ("c1", "config/secrets.env", &secret),
+            ("c2", "config/secrets.env", &secret),
+            ("c2", "src/lib.rs", &kept),
+        ] {
+            sqlx::query(
+                "INSERT INTO files (repository, commit_sha, file_path, content_hash)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(&repository)
+            .bind(commit_sha)
+            .bind(file_path)
+            .bind(hash)
+            .execute(&pool)
+            .await
+            .expect("failed to insert file");
+        }
+
+        let mut conn = pool.acquire().await.expect("failed to acquire connection");
+        for (hash, name) in [(&secret, secret_name.as_str()), (&kept, "Widget")] {
+            let symbol_id = insert_symbol(&mut conn, hash, name, &name.to_lowercase()).await;
+            sqlx::query(
+                "WITH namespace AS (
+                    INSERT INTO symbol_namespaces (namespace) VALUES ('')
+                    ON CONFLICT (namespace) DO UPDATE SET namespace = EXCLUDED.namespace
+                    RETURNING id
+                )
+                INSERT INTO symbol_references (symbol_id, namespace_id, kind, line_number, column_number)
+                SELECT $1, namespace.id, 'definition', 1, 1 FROM namespace",
+            )
+            .bind(symbol_id as i32)
+            .execute(&mut *conn)
+            .await
+            .expect("failed to insert reference");
+            sqlx::query(
+                "INSERT INTO unique_symbols (name_lc) VALUES ($1)
+                 ON CONFLICT (name_lc) DO NOTHING",
+            )
+            .bind(name.to_lowercase())
+            .execute(&mut *conn)
+            .await
+            .expect("failed to insert unique symbol");
+        }
+        drop(conn);
+
+        let kept_before = rows_for_hash(&pool, &kept).await;
+        let counts = purge_content_hash(&pool, &secret)
+            .await
+            .expect("purge failed");
+        let secret_after = rows_for_hash(&pool, &secret).await;
+        let kept_after = rows_for_hash(&pool, &kept).await;
+        let remaining_chunks: Vec<String> = sqlx::query_scalar(
+            "SELECT chunk_hash FROM chunks WHERE chunk_hash = ANY($1) ORDER BY 1",
+        )
+        .bind([&shared_chunk, &secret_chunk, &kept_chunk])
+        .fetch_all(&pool)
+        .await
+        .expect("failed to load chunks");
+        let secret_name_left: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM unique_symbols WHERE name_lc = $1)")
+                .bind(&secret_name)
+                .fetch_one(&pool)
+                .await
+                .expect("failed to check unique_symbols");
+        let repeat = purge_content_hash(&pool, &secret)
+            .await
+            .expect("second purge failed");
+        cleanup(&pool, &[&kept]).await;
+        sqlx::query("DELETE FROM chunks WHERE chunk_hash = ANY($1)")
+            .bind([&shared_chunk, &kept_chunk])
+            .execute(&pool)
+            .await
+            .expect("failed to clean up chunks");
+
+        assert_eq!(
+            counts,
+            PurgeCounts {
+                files_deleted: 2,
+                symbols_deleted: 1,
+                symbol_references_deleted: 1,
+                symbol_names_deleted: 1,
+                blob_chunks_deleted: 2,
+                chunks_deleted: 1,
+                shared_chunks_retained: 1,
+                blobs_deleted: 1,
+            }
+        );
+        assert_eq!(secret_after, vec![0, 0, 0, 0, 0]);
+        assert!(!secret_name_left);
+        assert_eq!(kept_after, kept_before);
+        assert_eq!(kept_after, vec![1, 2, 1, 1, 1]);
+        let mut expected_chunks = vec![kept_chunk.clone(), shared_chunk.clone()];
+        expected_chunks.sort();
+        assert_eq!(remaining_chunks, expected_chunks);
+        assert_eq!(repeat, PurgeCounts::default());
+    }
+
+    #[tokio::test]
+    async fn detailed_gc_names_removed_snapshots_and_commits() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let repository = format!("test-gc-detail-{}", std::process::id());
+        let hashes: Vec<String> = ["c1", "c2", "c3"]
+            .iter()
+            .map(|commit| format!("{repository}-{commit}"))
+            .collect();
+
+        for branch in ["main", "release"] {
+            sqlx::query(
+                "INSERT INTO branch_policies (repository, branch, latest_keep_count)
+                 VALUES ($1, $2, 1)",
+            )
+            .bind(&repository)
+            .bind(branch)
+            .execute(&pool)
+            .await
+            .expect("failed to insert branch policy");
+        }
+        // c1 is also the release snapshot, so it loses its main snapshot but
+        // keeps its data.
+        for (branch, commit, hours_ago) in [
+            ("main", "c1", 3),
+            ("main", "c2", 2),
+            ("main", "c3", 1),
+            ("release", "c1", 3),
+        ] {
+            sqlx::query(
+                "INSERT INTO branch_snapshots (repository, branch, commit_sha, indexed_at)
+                 VALUES ($1, $2, $3, NOW() - make_interval(hours => $4))",
+            )
+            .bind(&repository)
+            .bind(branch)
+            .bind(commit)
+            .bind(hours_ago)
+            .execute(&pool)
+            .await
+            .expect("failed to insert snapshot");
+        }
+        for (commit, hash) in ["c1", "c2", "c3"].into_iter().zip(&hashes) {
+            insert_blob(&pool, hash).await;
+            sqlx::query(
+                "INSERT INTO files (repository, commit_sha, file_path, content_hash)
+                 VALUES ($1, $2, 'src/lib.rs', $3)",
+            )
+            .bind(&repository)
+            .bind(commit)
+            .bind(hash)
+            .execute(&pool)
+            .await
+            .expect("failed to insert file");
+        }
+
+        let outcome = GarbageCollector::new(pool.clone()).run_once(true).await;
+        sqlx::query("DELETE FROM branch_policies WHERE repository = $1")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up branch policies");
+        let hash_refs: Vec<&str> = hashes.iter().map(String::as_str).collect();
+        cleanup(&pool, &hash_refs).await;
+
+        let outcome = outcome.expect("gc failed");
+        let details = outcome.details.expect("detailed run should list details");
+        assert_eq!(
+            details
+                .iter()
+                .map(|detail| detail.snapshots_removed.len())
+                .sum::<usize>(),
+            outcome.snapshots_removed
+        );
+        assert_eq!(
+            details
+                .iter()
+                .map(|detail| detail.commits_pruned.len())
+                .sum::<usize>(),
+            outcome.commits_pruned
+        );
+        let ours: Vec<&GcDetail> = details
+            .iter()
+            .filter(|detail| detail.repository == repository)
+            .collect();
+        assert_eq!(
+            ours,
+            [&GcDetail {
+                repository: repository.clone(),
+                branch: "main".to_string(),
+                snapshots_removed: vec!["c2".to_string(), "c1".to_string()],
+                commits_pruned: vec!["c2".to_string()],
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn chunk_need_batches_report_each_missing_hash_once() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let prefix = format!("test-chunk-need-{}", std::process::id());
+        let hashes: Vec<String> = (0..10).map(|i| format!("{prefix}-{i}")).collect();
+        let present: Vec<String> = hashes.iter().step_by(3).cloned().collect();
+        for hash in &present {
+            sqlx::query("INSERT INTO chunks (chunk_hash, text_content) VALUES ($1, '')")
+                .bind(hash)
+                .execute(&pool)
+                .await
+                .expect("failed to insert chunk");
+        }
+
+        // More hashes than one batch, with a repeat spanning two batches.
+        let mut request = hashes.clone();
+        request.push(hashes[1].clone());
+        let missing = find_missing_chunks(&pool, request, 3, 2).await;
+        sqlx::query("DELETE FROM chunks WHERE chunk_hash = ANY($1)")
+            .bind(&present)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up chunks");
+
+        let expected: Vec<String> = hashes
+            .into_iter()
+            .filter(|hash| !present.contains(hash))
+            .collect();
+        assert_eq!(missing.expect("chunk lookup failed"), expected);
+    }
+
+    #[tokio::test]
+    async fn chunk_stat_reports_line_counts_for_present_chunks() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let prefix = format!("test-chunk-stat-{}", std::process::id());
+        let blob_hash = format!("{prefix}-blob");
+        let mapped = format!("{prefix}-mapped");
+        let unmapped = format!("{prefix}-unmapped");
+        let absent = format!("{prefix}-absent");
+        for hash in [&mapped, &unmapped] {
+            sqlx::query("INSERT INTO chunks (chunk_hash, text_content) VALUES ($1, '')")
+                .bind(hash)
+                .execute(&pool)
+                .await
+                .expect("failed to insert chunk");
+        }
+        sqlx::query(
+            "INSERT INTO content_blobs (hash, language, byte_len, line_count)
+             VALUES ($1, NULL, 0, 7)",
+        )
+        .bind(&blob_hash)
+        .execute(&pool)
+        .await
+        .expect("failed to insert content blob");
+        sqlx::query(
+            "INSERT INTO content_blob_chunks (content_hash, chunk_hash, chunk_index, chunk_line_count)
+             VALUES ($1, $2, 0, 7)",
+        )
+        .bind(&blob_hash)
+        .bind(&mapped)
+        .execute(&pool)
+        .await
+        .expect("failed to map chunk");
+
+        let request = vec![
+            absent.clone(),
+            unmapped.clone(),
+            mapped.clone(),
+            absent.clone(),
+        ];
+        let stats = stat_chunks(&pool, request, 2).await;
+        sqlx::query("DELETE FROM content_blobs WHERE hash = $1")
+            .bind(&blob_hash)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up content blob");
+        sqlx::query("DELETE FROM chunks WHERE chunk_hash = ANY($1)")
+            .bind(vec![mapped.clone(), unmapped.clone()])
+            .execute(&pool)
+            .await
+            .expect("failed to clean up chunks");
+
+        assert_eq!(
+            stats.expect("chunk stat failed"),
+            ChunkStatResponse {
+                present: vec![
+                    ChunkStat {
+                        hash: unmapped,
+                        line_count: None,
+                    },
+                    ChunkStat {
+                        hash: mapped,
+                        line_count: Some(7),
+                    },
+                ],
+                missing: vec![absent],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn namespace_relink_restores_references_after_a_rebuild() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let pid = std::process::id();
+        let hash = format!("test-relink-namespaces-{pid}");
+        let namespace = format!("relink::fixture{pid}");
+        // Everything runs in one transaction that is rolled back, so dropping
+        // the uniqueness constraint to fake a rebuilt table stays local.
+        let mut tx = pool.begin().await.expect("failed to begin transaction");
+        sqlx::query(
+            "ALTER TABLE symbol_namespaces DROP CONSTRAINT symbol_namespaces_namespace_key",
+        )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to drop namespace constraint");
+        sqlx::query(
+            "INSERT INTO content_blobs (hash, language, byte_len, line_count)
+             VALUES ($1, 'rust', 0, 0)",
+        )
+        .bind(&hash)
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert content blob");
+        let symbol_id = insert_symbol(&mut tx, &hash, "render", "render").await as i32;
+        let mut namespace_ids = Vec::new();
+        for _ in 0..2 {
+            let id: i32 = sqlx::query_scalar(
+                "INSERT INTO symbol_namespaces (namespace) VALUES ($1) RETURNING id",
+            )
+            .bind(&namespace)
+            .fetch_one(&mut *tx)
+            .await
+            .expect("failed to insert namespace");
+            namespace_ids.push(id);
+        }
+        let (canonical, stale) = (namespace_ids[0], namespace_ids[1]);
+        // Line 1 exists under both ids; line 2 only under the stale one.
+        for (namespace_id, line) in [(canonical, 1), (stale, 1), (stale, 2)] {
+            sqlx::query(
+                "INSERT INTO symbol_references (symbol_id, namespace_id, kind, line_number, column_number)
+                 VALUES ($1, $2, 'definition', $3, 1)",
+            )
+            .bind(symbol_id)
+            .bind(namespace_id)
+            .bind(line)
+            .execute(&mut *tx)
+            .await
+            .expect("failed to insert reference");
+        }
+
+        let counts = relink_reference_namespaces(&mut tx)
+            .await
+            .expect("relink failed");
+        let references: Vec<(i32, String, i32)> = sqlx::query_as(
+            "SELECT sr.namespace_id, sn.namespace, sr.line_number
+             FROM symbol_references sr
+             JOIN symbol_namespaces sn ON sn.id = sr.namespace_id
+             WHERE sr.symbol_id = $1
+             ORDER BY sr.line_number",
+        )
+        .bind(symbol_id)
+        .fetch_all(&mut *tx)
+        .await
+        .expect("failed to load references");
+        let namespace_rows: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM symbol_namespaces WHERE namespace = $1")
+                .bind(&namespace)
+                .fetch_one(&mut *tx)
+                .await
+                .expect("failed to count namespaces");
+        tx.rollback().await.expect("failed to roll back");
+
+        assert_eq!(
+            counts,
+            NamespaceRelinkCounts {
+                references_relinked: 1,
+                duplicate_references_deleted: 1,
+                namespaces_deleted: 1,
+            }
+        );
+        assert_eq!(
+            references,
+            [
+                (canonical, namespace.clone(), 1),
+                (canonical, namespace.clone(), 2),
+            ]
+        );
+        assert_eq!(namespace_rows, 1);
+    }
+
+    #[tokio::test]
+    async fn orphan_sweep_removes_unreferenced_blobs_and_chunks() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let pid = std::process::id();
+        let repository = format!("test-orphan-sweep-{pid}");
+        let stale = format!("test-orphan-stale-{pid}");
+        let fresh = format!("test-orphan-fresh-{pid}");
+        let kept = format!("test-orphan-kept-{pid}");
+        let stale_chunk = format!("test-orphan-stale-chunk-{pid}");
+        let shared_chunk = format!("test-orphan-shared-chunk-{pid}");
+        let fresh_chunk = format!("test-orphan-fresh-chunk-{pid}");
+        let loose_chunk = format!("test-orphan-loose-chunk-{pid}");
+        for hash in [&stale, &fresh, &kept] {
+            insert_blob(&pool, hash).await;
+        }
+        link_chunk(&pool, &stale, &stale_chunk, 0).await;
+        link_chunk(&pool, &stale, &shared_chunk, 1).await;
+        link_chunk(&pool, &kept, &shared_chunk, 0).await;
+        link_chunk(&pool, &fresh, &fresh_chunk, 0).await;
+        sqlx::query("INSERT INTO chunks (chunk_hash, text_content) VALUES ($1, $1)")
+            .bind(&loose_chunk)
+            .execute(&pool)
+            .await
+            .expect("failed to insert chunk");
+        sqlx::query(
+            "INSERT INTO files (repository, commit_sha, file_path, content_hash)
+             VALUES ($1, 'c1', 'src/lib.rs', $2)",
+        )
+        .bind(&repository)
+        .bind(&kept)
+        .execute(&pool)
+        .await
+        .expect("failed to insert file");
+        // Everything but the fresh blob and its chunk predates the grace period.
+        sqlx::query(
+            "UPDATE content_blobs SET created_at = NOW() - INTERVAL '2 hours'
+             WHERE hash = ANY($1)",
+        )
+        .bind(vec![stale.clone(), kept.clone()])
+        .execute(&pool)
+        .await
+        .expect("failed to age blobs");
+        sqlx::query(
+            "UPDATE chunks SET created_at = NOW() - INTERVAL '2 hours'
+             WHERE chunk_hash = ANY($1)",
+        )
+        .bind(vec![
+            stale_chunk.clone(),
+            shared_chunk.clone(),
+            loose_chunk.clone(),
+        ])
+        .execute(&pool)
+        .await
+        .expect("failed to age chunks");
+
+        let options = OrphanSweepOptions {
+            batch_size: 2,
+            time_budget: Duration::from_secs(60),
+            grace_period: Duration::from_secs(3600),
+        };
+        let out_of_time = sweep_orphans(
+            &pool,
+            OrphanSweepOptions {
+                time_budget: Duration::ZERO,
+                ..options
+            },
+        )
+        .await;
+        let swept = sweep_orphans(&pool, options).await;
+        let blobs: Vec<String> =
+            sqlx::query_scalar("SELECT hash FROM content_blobs WHERE hash = ANY($1) ORDER BY hash")
+                .bind(vec![stale.clone(), fresh.clone(), kept.clone()])
+                .fetch_all(&pool)
+                .await
+                .expect("failed to load blobs");
+        let chunks: Vec<String> = sqlx::query_scalar(
+            "SELECT chunk_hash FROM chunks WHERE chunk_hash = ANY($1) ORDER BY chunk_hash",
+        )
+        .bind(vec![
+            stale_chunk.clone(),
+            shared_chunk.clone(),
+            fresh_chunk.clone(),
+            loose_chunk.clone(),
+        ])
+        .fetch_all(&pool)
+        .await
+        .expect("failed to load chunks");
+        cleanup(&pool, &[stale.as_str(), fresh.as_str(), kept.as_str()]).await;
+        sqlx::query("DELETE FROM chunks WHERE chunk_hash = ANY($1)")
+            .bind(&chunks)
+            .execute(&pool)
+            .await
+            .expect("failed to clean up chunks");
+
+        assert_eq!(
+            out_of_time.expect("sweep failed"),
+            OrphanSweepOutcome {
+                content_blobs_removed: 0,
+                chunks_removed: 0,
+                budget_exhausted: true,
+            }
+        );
+        // Orphans left by other fixtures may be swept too.
+        let swept = swept.expect("sweep failed");
+        assert!(!swept.budget_exhausted);
+        assert!(swept.content_blobs_removed >= 1);
+        assert!(swept.chunks_removed >= 2);
+        assert_eq!(blobs, [fresh.clone(), kept.clone()]);
+        assert_eq!(chunks, [fresh_chunk.clone(), shared_chunk.clone()]);
+    }
+
+    #[tokio::test]
+    async fn prune_repo_dry_run_counts_match_a_real_prune() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let pid = std::process::id();
+        let repository = format!("test-prune-dry-run-{pid}");
+        let shared = format!("test-prune-dry-run-shared-{pid}");
+        let own = format!("test-prune-dry-run-own-{pid}");
+        insert_blob(&pool, &shared).await;
+        insert_blob(&pool, &own).await;
+        for (commit_sha, file_path, hash) in [
+            ("c1", "src/a.rs", &shared),
+            ("c1", "src/b.rs", &own),
+            ("c2", "src/a.rs", &shared),
+        ] {
+            sqlx::query(
+                "INSERT INTO files (repository, commit_sha, file_path, content_hash)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(&repository)
+            .bind(commit_sha)
+            .bind(file_path)
+            .bind(hash)
+            .execute(&pool)
+            .await
+            .expect("failed to insert file");
+        }
+        sqlx::query(
+            "INSERT INTO branches (repository, branch, commit_sha) VALUES ($1, 'main', 'c2')",
+        )
+        .bind(&repository)
+        .execute(&pool)
+        .await
+        .expect("failed to insert branch");
+        sqlx::query("INSERT INTO branch_policies (repository, branch) VALUES ($1, 'main')")
+            .bind(&repository)
+            .execute(&pool)
+            .await
+            .expect("failed to insert branch policy");
+
+        let previewed = count_repository_data(&pool, &repository).await;
+        let files_after_preview: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM files WHERE repository = $1")
+                .bind(&repository)
+                .fetch_one(&pool)
+                .await
+                .expect("failed to count files");
+        let deleted = prune_repository_data(&pool, &repository, 1).await;
+        let previewed_after_prune = count_repository_data(&pool, &repository).await;
+        cleanup(&pool, &[shared.as_str(), own.as_str()]).await;
+
+        assert_eq!(previewed.expect("dry run failed"), 5);
+        assert_eq!(files_after_preview, 3);
+        assert_eq!(deleted.expect("prune failed"), 5);
+        assert_eq!(previewed_after_prune.expect("dry run failed"), 0);
+    }
+}
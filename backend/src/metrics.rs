@@ -0,0 +1,91 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Process-lifetime ingestion counters, rendered by `GET /metrics` in the
+/// Prometheus text format.
+#[derive(Debug, Default)]
+pub struct IngestMetrics {
+    files: AtomicU64,
+    symbols: AtomicU64,
+    references: AtomicU64,
+    namespaces: AtomicU64,
+    branches: AtomicU64,
+    commits: AtomicU64,
+    manifest_finalizations: AtomicU64,
+    reference_created_symbols: AtomicU64,
+}
+
+impl IngestMetrics {
+    /// Counts `rows` ingested for a manifest section. Unknown sections are
+    /// ignored, since they are rejected before any row is written.
+    pub fn record_rows(&self, section: &str, rows: u64) {
+        let counter = match section {
+            "file_pointer" => &self.files,
+            "symbol_record" => &self.symbols,
+            "reference_record" => &self.references,
+            "symbol_namespace" => &self.namespaces,
+            "branch_head" => &self.branches,
+            "commit_metadata" => &self.commits,
+            _ => return,
+        };
+        counter.fetch_add(rows, Ordering::Relaxed);
+    }
+
+    pub fn record_finalization(&self) {
+        self.manifest_finalizations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts symbols created on the fly for references that arrived before
+    /// their symbol records, returning the running total.
+    pub fn record_reference_created_symbols(&self, symbols: u64) -> u64 {
+        self.reference_created_symbols
+            .fetch_add(symbols, Ordering::Relaxed)
+            + symbols
+    }
+
+    /// The counters plus the `upload_chunks` backlog, which is read from the
+    /// database on each scrape rather than tracked here.
+    pub fn render(&self, pending_upload_chunks: i64) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP pointer_ingested_rows_total Manifest rows ingested, by section.\n");
+        out.push_str("# TYPE pointer_ingested_rows_total counter\n");
+        for (section, counter) in [
+            ("files", &self.files),
+            ("symbols", &self.symbols),
+            ("references", &self.references),
+            ("namespaces", &self.namespaces),
+            ("branches", &self.branches),
+            ("commits", &self.commits),
+        ] {
+            let _ = writeln!(
+                out,
+                "pointer_ingested_rows_total{{section=\"{section}\"}} {}",
+                counter.load(Ordering::Relaxed)
+            );
+        }
+        out.push_str(
+            "# HELP pointer_manifest_finalizations_total Chunked manifest uploads finalized.\n",
+        );
+        out.push_str("# TYPE pointer_manifest_finalizations_total counter\n");
+        let _ = writeln!(
+            out,
+            "pointer_manifest_finalizations_total {}",
+            self.manifest_finalizations.load(Ordering::Relaxed)
+        );
+        out.push_str(
+            "# HELP pointer_reference_created_symbols_total Symbols created by reference ingestion before their symbol records arrived.\n",
+        );
+        out.push_str("# TYPE pointer_reference_created_symbols_total counter\n");
+        let _ = writeln!(
+            out,
+            "pointer_reference_created_symbols_total {}",
+            self.reference_created_symbols.load(Ordering::Relaxed)
+        );
+        out.push_str(
+            "# HELP pointer_upload_chunks_pending Manifest chunks uploaded but not yet finalized.\n",
+        );
+        out.push_str("# TYPE pointer_upload_chunks_pending gauge\n");
+        let _ = writeln!(out, "pointer_upload_chunks_pending {pending_upload_chunks}");
+        out
+    }
+}
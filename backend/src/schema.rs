@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result, bail};
+use sqlx::PgPool;
+use sqlx::migrate::{AppliedMigration, Migrate, Migration, Migrator};
+use tracing::{info, warn};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SchemaStatus {
+    Current,
+    Behind { pending: Vec<i64> },
+}
+
+/// Compares the migrations recorded in the database with the ones embedded in
+/// this binary. Refuses to continue when the database has been migrated by a
+/// newer build (or by a build with different migration contents), since the
+/// queries in this binary may no longer match the schema.
+pub async fn preflight(pool: &PgPool, migrator: &Migrator) -> Result<SchemaStatus> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .context("failed to acquire connection for schema preflight")?;
+    conn.ensure_migrations_table()
+        .await
+        .context("failed to ensure migrations table")?;
+    if let Some(version) = conn
+        .dirty_version()
+        .await
+        .context("failed to read dirty migration version")?
+    {
+        bail!("database migration {version} is partially applied; fix the schema manually");
+    }
+    let applied = conn
+        .list_applied_migrations()
+        .await
+        .context("failed to list applied migrations")?;
+
+    let known: Vec<Migration> = migrator
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .cloned()
+        .collect();
+
+    let status = evaluate_schema(&known, &applied)?;
+    match &status {
+        SchemaStatus::Current => info!("database schema is up to date"),
+        SchemaStatus::Behind { pending } => {
+            warn!(
+                ?pending,
+                "database schema is behind; pending migrations will be applied"
+            )
+        }
+    }
+    Ok(status)
+}
+
+pub fn evaluate_schema(known: &[Migration], applied: &[AppliedMigration]) -> Result<SchemaStatus> {
+    let known_by_version: HashMap<i64, &Migration> = known
+        .iter()
+        .map(|migration| (migration.version, migration))
+        .collect();
+
+    let mut unknown: Vec<i64> = Vec::new();
+    for applied_migration in applied {
+        match known_by_version.get(&applied_migration.version) {
+            Some(migration) if migration.checksum != applied_migration.checksum => {
+                bail!(
+                    "database migration {} ({}) does not match the version embedded in this binary",
+                    migration.version,
+                    migration.description
+                );
+            }
+            Some(_) => {}
+            None => unknown.push(applied_migration.version),
+        }
+    }
+
+    if !unknown.is_empty() {
+        unknown.sort_unstable();
+        bail!(
+            "database schema is newer than this binary (unknown migrations: {:?}); refusing to start",
+            unknown
+        );
+    }
+
+    let mut pending: Vec<i64> = known
+        .iter()
+        .map(|migration| migration.version)
+        .filter(|version| !applied.iter().any(|m| m.version == *version))
+        .collect();
+    pending.sort_unstable();
+
+    if pending.is_empty() {
+        Ok(SchemaStatus::Current)
+    } else {
+        Ok(SchemaStatus::Behind { pending })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use sqlx::migrate::MigrationType;
+
+    use super::*;
+
+    fn migration(version: i64, sql: &'static str) -> Migration {
+        Migration::new(
+            version,
+            Cow::Borrowed("test"),
+            MigrationType::Simple,
+            Cow::Borrowed(sql),
+            false,
+        )
+    }
+
+    fn applied(migration: &Migration) -> AppliedMigration {
+        AppliedMigration {
+            version: migration.version,
+            checksum: migration.checksum.clone(),
+        }
+    }
+
+    #[test]
+    fn refuses_to_start_when_database_has_extra_migration() {
+        let known = vec![migration(1, "SELECT 1"), migration(2, "SELECT 2")];
+        let mut db = known.iter().map(applied).collect::<Vec<_>>();
+        db.push(applied(&migration(3, "SELECT 3")));
+
+        let err = evaluate_schema(&known, &db).unwrap_err();
+        assert!(err.to_string().contains("newer than this binary"));
+    }
+
+    #[test]
+    fn reports_pending_migrations_when_behind() {
+        let known = vec![migration(1, "SELECT 1"), migration(2, "SELECT 2")];
+        let db = vec![applied(&known[0])];
+
+        assert_eq!(
+            evaluate_schema(&known, &db).unwrap(),
+            SchemaStatus::Behind { pending: vec![2] }
+        );
+    }
+
+    #[test]
+    fn refuses_to_start_on_checksum_mismatch() {
+        let known = vec![migration(1, "SELECT 1")];
+        let db = vec![applied(&migration(1, "SELECT 42"))];
+
+        assert!(evaluate_schema(&known, &db).is_err());
+    }
+}
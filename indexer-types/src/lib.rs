@@ -1,5 +1,10 @@
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
+pub mod validation;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentBlob {
     pub hash: String,
@@ -15,6 +20,7 @@ pub struct SymbolRecord {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "ReferenceRecordWire")]
 pub struct ReferenceRecord {
     pub content_hash: String,
     pub namespace: Option<String>,
@@ -23,6 +29,46 @@ pub struct ReferenceRecord {
     pub kind: Option<String>,
     pub line: usize,
     pub column: usize,
+    pub end_line: usize,
+    /// Exclusive, in the same unit as `column`.
+    pub end_column: usize,
+    /// Declaration header of a definition, when the extractor captured one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// Records written before spans were tracked have no end position; they
+/// deserialize as spans that end where they start.
+#[derive(Deserialize)]
+struct ReferenceRecordWire {
+    content_hash: String,
+    namespace: Option<String>,
+    name: String,
+    fully_qualified: String,
+    kind: Option<String>,
+    line: usize,
+    column: usize,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+    #[serde(default)]
+    signature: Option<String>,
+}
+
+impl From<ReferenceRecordWire> for ReferenceRecord {
+    fn from(wire: ReferenceRecordWire) -> Self {
+        Self {
+            end_line: wire.end_line.unwrap_or(wire.line),
+            end_column: wire.end_column.unwrap_or(wire.column),
+            content_hash: wire.content_hash,
+            namespace: wire.namespace,
+            name: wire.name,
+            fully_qualified: wire.fully_qualified,
+            kind: wire.kind,
+            line: wire.line,
+            column: wire.column,
+            signature: wire.signature,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +82,39 @@ pub struct FilePointer {
     pub commit_sha: String,
     pub file_path: String,
     pub content_hash: String,
+    /// Manifests written before this field existed only carried regular files.
+    #[serde(default)]
+    pub kind: FileKind,
+    /// The git tree entry mode, e.g. `0o100755` for an executable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+}
+
+/// What a tree entry is. A symlink's content is its target path and a
+/// submodule's content is the commit it pins.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    #[default]
+    Regular,
+    Symlink,
+    Submodule,
+}
+
+impl FileKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Regular => "regular",
+            Self::Symlink => "symlink",
+            Self::Submodule => "submodule",
+        }
+    }
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -45,6 +124,82 @@ pub struct IndexReport {
     pub file_pointers: Vec<FilePointer>,
     pub reference_records: Vec<ReferenceRecord>,
     pub branches: Vec<BranchHead>,
+    /// Reports from indexers that predate commit metadata have none.
+    #[serde(default)]
+    pub commits: Vec<CommitMetadata>,
+    /// Files whose pointers were carried forward from the base commit of an
+    /// incremental run without being re-extracted.
+    #[serde(default)]
+    pub unchanged_files: usize,
+    /// Files whose symbol extraction panicked. They are still indexed, just
+    /// without symbols or references.
+    #[serde(default)]
+    pub extraction_failures: Vec<ExtractionFailure>,
+    /// Walked files left out of the index, by reason.
+    #[serde(default)]
+    pub skipped_files: SkippedFiles,
+}
+
+/// A file whose symbol extraction panicked, with the panic message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionFailure {
+    pub file_path: String,
+    pub message: String,
+}
+
+/// Counts of walked files an index run left out, by reason.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkippedFiles {
+    /// Under a built-in skipped directory such as `target` or `node_modules`.
+    pub filtered: usize,
+    /// Not in the git index of a working-tree run.
+    pub untracked: usize,
+    /// Matched a configured ignore pattern or, in a commit-tree run, an
+    /// ignore file. Working-tree runs drop ignore-file matches while walking,
+    /// before they can be counted.
+    pub ignored: usize,
+    /// Larger than the maximum file size.
+    pub too_large: usize,
+    /// Had a line longer than the maximum line length.
+    pub long_lines: usize,
+}
+
+impl IndexReport {
+    /// Record counts; a report carries no chunk content, so chunk fields are 0.
+    pub fn summary(&self) -> IndexSummary {
+        IndexSummary {
+            content_blobs: self.content_blobs.len(),
+            file_pointers: self.file_pointers.len(),
+            symbol_records: self.symbol_records.len(),
+            reference_records: self.reference_records.len(),
+            unique_chunks: 0,
+            chunk_bytes: 0,
+            unchanged_files: self.unchanged_files,
+            extraction_failures: self.extraction_failures.len(),
+            skipped_files: self.skipped_files,
+        }
+    }
+}
+
+/// What an index run produced, as reported by a dry run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexSummary {
+    pub content_blobs: usize,
+    pub file_pointers: usize,
+    pub symbol_records: usize,
+    pub reference_records: usize,
+    pub unique_chunks: usize,
+    /// Total text size of the unique chunks.
+    pub chunk_bytes: u64,
+    /// File pointers carried forward unchanged from the base commit.
+    #[serde(default)]
+    pub unchanged_files: usize,
+    /// Files indexed without symbols because their extraction panicked.
+    #[serde(default)]
+    pub extraction_failures: usize,
+    #[serde(default)]
+    pub skipped_files: SkippedFiles,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +224,24 @@ pub struct BranchHead {
     pub commit_sha: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub policy: Option<BranchPolicy>,
+    /// Records `commit_sha` as a snapshot of the branch without moving its
+    /// head, for backfilling historical commits.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub snapshot_only: bool,
+}
+
+/// Who made an indexed commit, when, and why, so it can be shown as more
+/// than a bare SHA.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitMetadata {
+    pub repository: String,
+    pub commit_sha: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Commit time in seconds since the Unix epoch.
+    pub committed_at: i64,
+    /// First line of the commit message.
+    pub subject: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -84,3 +257,43 @@ pub struct ChunkMapping {
     pub chunk_index: usize,
     pub chunk_line_count: i32,
 }
+
+/// How a manifest shard or upload is compressed on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestCompression {
+    Zstd,
+    Gzip,
+    None,
+}
+
+impl ManifestCompression {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Zstd => "zstd",
+            Self::Gzip => "gzip",
+            Self::None => "none",
+        }
+    }
+}
+
+impl fmt::Display for ManifestCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ManifestCompression {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "zstd" => Ok(Self::Zstd),
+            "gzip" => Ok(Self::Gzip),
+            "none" => Ok(Self::None),
+            other => Err(format!(
+                "unknown manifest compression '{other}'; expected zstd, gzip or none"
+            )),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Checks on the paths and names an index run hands to the backend. File
+//! paths end up as tree prefixes and URL segments, so shapes that would
+//! escape the repository root or confuse prefix matching are refused rather
+//! than stored.
+
+use std::fmt;
+
+use crate::{BranchHead, CommitMetadata, FilePointer};
+
+/// Why a file path, repository name or branch name was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidName {
+    Empty,
+    Absolute,
+    DotSegment,
+    EmptySegment,
+    ControlCharacter,
+    Backslash,
+    Disallowed(char),
+}
+
+impl fmt::Display for InvalidName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("is empty"),
+            Self::Absolute => f.write_str("is absolute"),
+            Self::DotSegment => f.write_str("has a `.` or `..` segment"),
+            Self::EmptySegment => f.write_str("has an empty segment"),
+            Self::ControlCharacter => f.write_str("contains a control character"),
+            Self::Backslash => f.write_str("contains a backslash"),
+            Self::Disallowed(ch) => write!(f, "contains the disallowed character {ch:?}"),
+        }
+    }
+}
+
+/// Returns `path` with repeated and trailing slashes collapsed, or why it
+/// can't be stored as a repository-relative path.
+pub fn normalize_file_path(path: &str) -> Result<String, InvalidName> {
+    if path.chars().any(char::is_control) {
+        return Err(InvalidName::ControlCharacter);
+    }
+    if path.contains('\\') {
+        return Err(InvalidName::Backslash);
+    }
+    if path.starts_with('/') {
+        return Err(InvalidName::Absolute);
+    }
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if segments.is_empty() {
+        return Err(InvalidName::Empty);
+    }
+    if segments
+        .iter()
+        .any(|segment| matches!(*segment, "." | ".."))
+    {
+        return Err(InvalidName::DotSegment);
+    }
+    Ok(segments.join("/"))
+}
+
+/// Repository names are a single URL segment: ASCII letters, digits and
+/// `-_.+@`.
+pub fn validate_repository_name(name: &str) -> Result<(), InvalidName> {
+    check_name(name, |ch| matches!(ch, '-' | '_' | '.' | '+' | '@'))?;
+    if matches!(name, "." | "..") {
+        return Err(InvalidName::DotSegment);
+    }
+    Ok(())
+}
+
+/// Branch names take the repository name characters plus `/`, without
+/// empty, `.` or `..` segments.
+pub fn validate_branch_name(name: &str) -> Result<(), InvalidName> {
+    check_name(name, |ch| matches!(ch, '-' | '_' | '.' | '+' | '@' | '/'))?;
+    for segment in name.split('/') {
+        match segment {
+            "" => return Err(InvalidName::EmptySegment),
+            "." | ".." => return Err(InvalidName::DotSegment),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_name(name: &str, allowed: impl Fn(char) -> bool) -> Result<(), InvalidName> {
+    if name.is_empty() {
+        return Err(InvalidName::Empty);
+    }
+    match name
+        .chars()
+        .find(|ch| !ch.is_ascii_alphanumeric() && !allowed(*ch))
+    {
+        None => Ok(()),
+        Some(ch) if ch.is_control() => Err(InvalidName::ControlCharacter),
+        Some('\\') => Err(InvalidName::Backslash),
+        Some(ch) => Err(InvalidName::Disallowed(ch)),
+    }
+}
+
+/// Normalizes every pointer's path in place. On failure, returns one
+/// description per rejected pointer and leaves the slice partly normalized.
+pub fn normalize_file_pointers(pointers: &mut [FilePointer]) -> Result<(), Vec<String>> {
+    let mut rejected = Vec::new();
+    for pointer in pointers.iter_mut() {
+        if let Err(reason) = validate_repository_name(&pointer.repository) {
+            rejected.push(format!("repository {:?} {reason}", pointer.repository));
+            continue;
+        }
+        match normalize_file_path(&pointer.file_path) {
+            Ok(path) => pointer.file_path = path,
+            Err(reason) => rejected.push(format!("{:?} {reason}", pointer.file_path)),
+        }
+    }
+    if rejected.is_empty() {
+        Ok(())
+    } else {
+        Err(rejected)
+    }
+}
+
+/// Checks the repository and branch name of every head, returning one
+/// description per rejected head.
+pub fn validate_branch_heads(heads: &[BranchHead]) -> Result<(), Vec<String>> {
+    let rejected: Vec<String> = heads
+        .iter()
+        .filter_map(|head| {
+            if let Err(reason) = validate_repository_name(&head.repository) {
+                return Some(format!("repository {:?} {reason}", head.repository));
+            }
+            validate_branch_name(&head.branch)
+                .err()
+                .map(|reason| format!("branch {:?} {reason}", head.branch))
+        })
+        .collect();
+    if rejected.is_empty() {
+        Ok(())
+    } else {
+        Err(rejected)
+    }
+}
+
+/// Checks the repository name of every commit record, returning one
+/// description per rejected record.
+pub fn validate_commit_metadata(commits: &[CommitMetadata]) -> Result<(), Vec<String>> {
+    let rejected: Vec<String> = commits
+        .iter()
+        .filter_map(|commit| {
+            validate_repository_name(&commit.repository)
+                .err()
+                .map(|reason| format!("repository {:?} {reason}", commit.repository))
+        })
+        .collect();
+    if rejected.is_empty() {
+        Ok(())
+    } else {
+        Err(rejected)
+    }
+}
+
+/// Rejections listed in one error message, so a manifest full of bad paths
+/// doesn't produce a megabyte-long response.
+const MAX_LISTED_REJECTIONS: usize = 20;
+
+/// Summarizes rejected `what` (e.g. "file pointers") for an error message.
+pub fn describe_rejections(what: &str, rejected: &[String]) -> String {
+    let listed = rejected[..rejected.len().min(MAX_LISTED_REJECTIONS)].join("; ");
+    match rejected.len().saturating_sub(MAX_LISTED_REJECTIONS) {
+        0 => format!("rejected {} {what}: {listed}", rejected.len()),
+        more => format!(
+            "rejected {} {what}: {listed}; and {more} more",
+            rejected.len()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_paths_normalize_redundant_slashes() {
+        assert_eq!(normalize_file_path("src/lib.rs").unwrap(), "src/lib.rs");
+        assert_eq!(
+            normalize_file_path("src//db///mod.rs").unwrap(),
+            "src/db/mod.rs"
+        );
+        assert_eq!(normalize_file_path("docs/").unwrap(), "docs");
+        assert_eq!(
+            normalize_file_path("a/.hidden/..rc").unwrap(),
+            "a/.hidden/..rc"
+        );
+        assert_eq!(
+            normalize_file_path("names with spaces/é.txt").unwrap(),
+            "names with spaces/é.txt"
+        );
+    }
+
+    #[test]
+    fn file_paths_reject_escaping_and_unprintable_shapes() {
+        let cases = [
+            ("", InvalidName::Empty),
+            ("/", InvalidName::Absolute),
+            ("//", InvalidName::Absolute),
+            ("/etc/passwd", InvalidName::Absolute),
+            ("../../etc/passwd", InvalidName::DotSegment),
+            ("src/../secret", InvalidName::DotSegment),
+            ("./src/lib.rs", InvalidName::DotSegment),
+            ("src/.", InvalidName::DotSegment),
+            ("src/lib\0.rs", InvalidName::ControlCharacter),
+            ("src/lib\n.rs", InvalidName::ControlCharacter),
+            ("src/\x1b[31m.rs", InvalidName::ControlCharacter),
+            ("src\\lib.rs", InvalidName::Backslash),
+            ("C:\\Windows", InvalidName::Backslash),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(normalize_file_path(path), Err(expected), "{path:?}");
+        }
+    }
+
+    #[test]
+    fn repository_and_branch_names_use_a_whitelist() {
+        for name in ["pointer", "my-repo_2.0", "c++", "team@host"] {
+            assert_eq!(validate_repository_name(name), Ok(()), "{name:?}");
+        }
+        let cases = [
+            ("", InvalidName::Empty),
+            (".", InvalidName::DotSegment),
+            ("..", InvalidName::DotSegment),
+            ("org/repo", InvalidName::Disallowed('/')),
+            ("my repo", InvalidName::Disallowed(' ')),
+            ("repo\0", InvalidName::ControlCharacter),
+            ("repo\\x", InvalidName::Backslash),
+            ("répo", InvalidName::Disallowed('é')),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(validate_repository_name(name), Err(expected), "{name:?}");
+        }
+
+        for name in ["main", "feature/login-v2", "release/1.2", "user@fix+1"] {
+            assert_eq!(validate_branch_name(name), Ok(()), "{name:?}");
+        }
+        let cases = [
+            ("", InvalidName::Empty),
+            ("/main", InvalidName::EmptySegment),
+            ("feature/", InvalidName::EmptySegment),
+            ("feature//x", InvalidName::EmptySegment),
+            ("feature/../main", InvalidName::DotSegment),
+            ("..", InvalidName::DotSegment),
+            ("main branch", InvalidName::Disallowed(' ')),
+            ("main~1", InvalidName::Disallowed('~')),
+            ("main\n", InvalidName::ControlCharacter),
+            ("feature\\x", InvalidName::Backslash),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(validate_branch_name(name), Err(expected), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn file_pointers_are_normalized_or_all_offenders_listed() {
+        let pointer = |repository: &str, path: &str| FilePointer {
+            repository: repository.to_string(),
+            commit_sha: "abc123".to_string(),
+            file_path: path.to_string(),
+            content_hash: "hash".to_string(),
+            kind: Default::default(),
+            mode: None,
+        };
+
+        let mut good = vec![
+            pointer("pointer", "src//lib.rs"),
+            pointer("pointer", "README.md"),
+        ];
+        assert_eq!(normalize_file_pointers(&mut good), Ok(()));
+        assert_eq!(good[0].file_path, "src/lib.rs");
+
+        let mut bad = vec![
+            pointer("pointer", "../../etc/passwd"),
+            pointer("pointer", "src/lib.rs"),
+            pointer("org/pointer", "src/lib.rs"),
+            pointer("pointer", "/abs"),
+        ];
+        assert_eq!(
+            normalize_file_pointers(&mut bad),
+            Err(vec![
+                "\"../../etc/passwd\" has a `.` or `..` segment".to_string(),
+                "repository \"org/pointer\" contains the disallowed character '/'".to_string(),
+                "\"/abs\" is absolute".to_string(),
+            ])
+        );
+    }
+}
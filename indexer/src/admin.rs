@@ -318,6 +318,7 @@ struct PrunePolicyRequest {
     repository: String,
     keep_latest: bool,
     max_commits_to_keep: Option<i32>,
+    max_age_days: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -336,6 +337,7 @@ fn prune_policy(
         repository: payload.repository,
         keep_latest: payload.keep_latest,
         max_commits_to_keep: payload.max_commits_to_keep,
+        max_age_days: payload.max_age_days,
     };
     let response: PrunePolicyResponse =
         post_json(client, &endpoints.prune_policy, api_key, &request)?
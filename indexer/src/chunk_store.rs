@@ -88,4 +88,12 @@ impl ChunkStore {
     pub fn len(&self) -> usize {
         self.index.len()
     }
+
+    pub fn chunk_len(&self, hash: &str) -> Option<usize> {
+        self.index.get(hash).map(|info| info.len)
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.index.values().map(|info| info.len as u64).sum()
+    }
 }
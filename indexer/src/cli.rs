@@ -1,15 +1,18 @@
 use std::env;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use anyhow::Result;
 use clap::{ArgAction, Args, Parser, Subcommand};
 use humantime::parse_duration;
+use pointer_indexer_types::ManifestCompression;
 use tracing::info;
 
 use crate::admin;
-use crate::config::{BranchPolicyConfig, IndexerConfig, SnapshotPolicyConfig};
+use crate::config::{BranchPolicyConfig, IgnoreConfig, IndexerConfig, SnapshotPolicyConfig};
 use crate::engine::Indexer;
+use crate::hooks::{DropSymbolsMatching, NamespacePrefix};
 use crate::output;
 use crate::upload;
 use crate::utils;
@@ -63,6 +66,13 @@ pub struct IndexArgs {
     /// Upload all symbol and reference records, even if content hashes already exist.
     #[arg(long, action = ArgAction::SetTrue)]
     pub full_symbol_upload: bool,
+    /// Compression for uploaded manifest shards: zstd, gzip or none.
+    #[arg(
+        long,
+        env = "POINTER_MANIFEST_COMPRESSION",
+        default_value_t = ManifestCompression::Zstd
+    )]
+    pub manifest_compression: ManifestCompression,
     /// Mark this branch as the live branch for the repository.
     #[arg(long = "live", action = ArgAction::SetTrue, conflicts_with = "not_live")]
     pub live: bool,
@@ -75,6 +85,48 @@ pub struct IndexArgs {
     /// Snapshot retention policies in the format "<interval>:<count>", e.g. "7d:4".
     #[arg(long = "snapshot-policy")]
     pub snapshot_policies: Vec<SnapshotPolicyArg>,
+    /// Drop extracted symbols whose name matches this regex. May be repeated.
+    #[arg(long = "drop-symbols")]
+    pub drop_symbols: Vec<String>,
+    /// Prefix added to every extracted namespace.
+    #[arg(long)]
+    pub namespace_prefix: Option<String>,
+    /// Index the `--commit` tree from git objects instead of the working tree
+    /// and record it as a snapshot of `--branch` without moving the branch
+    /// head. Without `--branch`, a synthetic `commit/<sha>` branch is used.
+    #[arg(long, action = ArgAction::SetTrue, requires = "commit")]
+    pub backfill: bool,
+    /// Only re-extract files changed since this commit; unchanged files keep
+    /// their indexed content. With `--upload-url`, unchanged files whose
+    /// content the backend lacks are indexed in full.
+    #[arg(long, conflicts_with = "incremental")]
+    pub base_commit: Option<String>,
+    /// Like `--base-commit`, using the branch head the backend last indexed.
+    #[arg(long, action = ArgAction::SetTrue, requires = "upload_url")]
+    pub incremental: bool,
+    /// Also index untracked working-tree files. By default only files in the
+    /// git index are indexed; ignored files are always skipped.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "backfill")]
+    pub include_untracked: bool,
+    /// Skip files matching this gitignore-style pattern, relative to the
+    /// repository root (e.g. "vendor/" or "**/generated/**"). May be repeated.
+    #[arg(long = "ignore")]
+    pub ignore_patterns: Vec<String>,
+    /// Skip files larger than this many bytes.
+    #[arg(long)]
+    pub max_file_size: Option<u64>,
+    /// Skip files with a line longer than this many bytes, such as minified
+    /// bundles.
+    #[arg(long)]
+    pub max_line_length: Option<usize>,
+    /// Also skip files matched by `.pointerignore` files and, with
+    /// `--backfill`, by `.gitignore` files in the indexed commit.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub use_ignore_files: bool,
+    /// Index as usual but only report what would be uploaded. With
+    /// `--upload-url`, the backend is asked which chunks it already has.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub dry_run: bool,
 }
 
 pub fn run() -> Result<()> {
@@ -95,27 +147,83 @@ fn run_index(args: IndexArgs) -> Result<()> {
         .unwrap_or_else(|| utils::default_repo_name(&repo_path));
     let output_dir = resolve_output_dir(&args.output_dir)?;
 
-    let repo_meta =
-        utils::resolve_repo_metadata(&repo_path, args.commit.clone(), args.branch.clone())?;
-
-    let config = IndexerConfig::new(
-        repo_path.clone(),
-        repository.clone(),
-        repo_meta.branch,
-        repo_meta.commit,
-        output_dir.clone(),
-        build_branch_policy(&args),
-    );
+    let mut config = if args.backfill {
+        backfill_config(&args, &repo_path, &repository, &output_dir)?
+    } else {
+        let repo_meta =
+            utils::resolve_repo_metadata(&repo_path, args.commit.clone(), args.branch.clone())?;
+        IndexerConfig::new(
+            repo_path.clone(),
+            repository.clone(),
+            repo_meta.branch,
+            repo_meta.commit,
+            output_dir.clone(),
+            build_branch_policy(&args),
+        )
+    };
+    for pattern in &args.drop_symbols {
+        config = config.with_hook(Arc::new(DropSymbolsMatching::new(pattern)?));
+    }
+    if let Some(prefix) = args.namespace_prefix.as_deref() {
+        config = config.with_hook(Arc::new(NamespacePrefix::new(prefix)));
+    }
+    if args.include_untracked {
+        config = config.with_untracked();
+    }
+    config = config.with_ignore(IgnoreConfig {
+        patterns: args.ignore_patterns.clone(),
+        max_file_bytes: args.max_file_size,
+        max_line_length: args.max_line_length,
+        use_ignore_files: args.use_ignore_files,
+    });
+    let base_commit = if args.incremental {
+        indexed_branch_head(&args, &config)?
+    } else {
+        args.base_commit.clone()
+    };
+    if let Some(base_commit) = base_commit {
+        config = config.with_base_commit(base_commit);
+        if let Some(url) = args.upload_url.as_deref() {
+            config = config.with_content_check(Arc::new(upload::BackendContentCheck::new(
+                url,
+                args.upload_api_key.as_deref(),
+            )?));
+        }
+    }
+    if args.dry_run {
+        config = config.with_dry_run();
+    }
+    let dry_run = config.dry_run;
 
     let indexer = Indexer::new(config);
     let artifacts = indexer.run()?;
     output::write_report(&output_dir, &artifacts)?;
 
-    if let Some(url) = args.upload_url.as_deref() {
+    let options = upload::UploadOptions {
+        incremental_symbols: !args.full_symbol_upload,
+        manifest_compression: args.manifest_compression,
+    };
+    if dry_run {
+        let report = upload::dry_run(
+            args.upload_url.as_deref(),
+            args.upload_api_key.as_deref(),
+            &artifacts,
+            &options,
+        )?;
+        let summary = report.summary;
+        info!(
+            content_blobs = summary.content_blobs,
+            unique_chunks = summary.unique_chunks,
+            chunks_already_stored = ?report.chunks_already_stored,
+            symbol_records = summary.symbol_records,
+            reference_records = summary.reference_records,
+            skipped_files = ?summary.skipped_files,
+            estimated_upload_bytes = report.estimated_upload_bytes,
+            compression = %args.manifest_compression,
+            "dry run; nothing uploaded"
+        );
+    } else if let Some(url) = args.upload_url.as_deref() {
         info!(%url, "uploading index to backend");
-        let options = upload::UploadOptions {
-            incremental_symbols: !args.full_symbol_upload,
-        };
         upload::upload_index_with_options(
             url,
             args.upload_api_key.as_deref(),
@@ -129,6 +237,64 @@ fn run_index(args: IndexArgs) -> Result<()> {
     Ok(())
 }
 
+/// Config for `--backfill`: the commit's own tree, recorded as a snapshot of
+/// the given branch or as the head of a synthetic one.
+fn backfill_config(
+    args: &IndexArgs,
+    repo_path: &Path,
+    repository: &str,
+    output_dir: &Path,
+) -> Result<IndexerConfig> {
+    let revision = args.commit.as_deref().unwrap_or("HEAD");
+    let commit = utils::resolve_commit(repo_path, revision)?;
+    let branch = args
+        .branch
+        .clone()
+        .unwrap_or_else(|| synthetic_branch(&commit));
+    info!(%commit, %branch, "backfilling commit tree");
+
+    let config = IndexerConfig::new(
+        repo_path.to_path_buf(),
+        repository.to_string(),
+        Some(branch),
+        commit,
+        output_dir.to_path_buf(),
+        build_branch_policy(args),
+    )
+    .with_commit_tree();
+    Ok(if args.branch.is_some() {
+        config.with_snapshot_only()
+    } else {
+        config
+    })
+}
+
+fn synthetic_branch(commit: &str) -> String {
+    format!("commit/{}", &commit[..commit.len().min(12)])
+}
+
+/// The head the backend holds for the branch being indexed, for
+/// `--incremental`. `None` means every file is indexed.
+fn indexed_branch_head(args: &IndexArgs, config: &IndexerConfig) -> Result<Option<String>> {
+    let (Some(url), Some(branch)) = (args.upload_url.as_deref(), config.branch.as_deref()) else {
+        info!("no branch to diff against; indexing every file");
+        return Ok(None);
+    };
+    let head = upload::fetch_branch_head(
+        url,
+        args.upload_api_key.as_deref(),
+        &config.repository,
+        branch,
+    )?;
+    match &head {
+        Some(head) => {
+            info!(%branch, base = %head, "indexing changes since the indexed branch head")
+        }
+        None => info!(%branch, "branch not indexed yet; indexing every file"),
+    }
+    Ok(head)
+}
+
 fn build_branch_policy(args: &IndexArgs) -> Option<BranchPolicyConfig> {
     let branch = args.branch.as_ref()?;
     if branch.trim().is_empty() {
@@ -275,6 +441,9 @@ pub struct PrunePolicyArgs {
     pub keep_latest: bool,
     #[arg(long)]
     pub max_commits_to_keep: Option<i32>,
+    /// Prune commits last indexed more than this many days ago.
+    #[arg(long)]
+    pub max_age_days: Option<i64>,
 }
 
 #[derive(Debug, Args)]
@@ -1,4 +1,16 @@
+use std::collections::HashSet;
+use std::fmt;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::hooks::ExtractionHook;
+
+/// Reports which content hashes the backend does not store yet.
+pub trait ContentCheck: fmt::Debug + Send + Sync {
+    fn missing(&self, hashes: &[String]) -> Result<HashSet<String>>;
+}
 
 #[derive(Debug, Clone)]
 pub struct SnapshotPolicyConfig {
@@ -13,6 +25,22 @@ pub struct BranchPolicyConfig {
     pub snapshot_policies: Vec<SnapshotPolicyConfig>,
 }
 
+/// Which walked files are left out of the index.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreConfig {
+    /// Gitignore-style patterns matched against paths relative to the repo
+    /// root, e.g. `vendor/`, `**/generated/**` or `*.min.js`.
+    pub patterns: Vec<String>,
+    /// Files larger than this many bytes are skipped.
+    pub max_file_bytes: Option<u64>,
+    /// Files with a line longer than this many bytes, typically minified or
+    /// generated code, are skipped.
+    pub max_line_length: Option<usize>,
+    /// Also honor `.pointerignore` files, and in commit-tree walks
+    /// `.gitignore` files. Working-tree walks always honor `.gitignore`.
+    pub use_ignore_files: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct IndexerConfig {
     pub repo_path: PathBuf,
@@ -21,6 +49,25 @@ pub struct IndexerConfig {
     pub commit: String,
     pub output_dir: PathBuf,
     pub branch_policy: Option<BranchPolicyConfig>,
+    /// Transforms applied to each file's extraction, in order.
+    pub hooks: Vec<Arc<dyn ExtractionHook>>,
+    /// Read files from `commit`'s git tree instead of the working tree.
+    pub read_commit_tree: bool,
+    /// Commit the backend already holds for this branch. Files unchanged
+    /// since it are not re-extracted; their file pointers are re-emitted with
+    /// the same content hashes.
+    pub base_commit: Option<String>,
+    /// Confirms the backend still holds the content of files carried forward
+    /// from `base_commit`. Files whose content it lacks are indexed in full.
+    pub content_check: Option<Arc<dyn ContentCheck>>,
+    /// Also index working-tree files git does not track yet. Ignored files
+    /// are still skipped.
+    pub include_untracked: bool,
+    /// Record `branch` as a snapshot of `commit` without moving its head.
+    pub snapshot_only: bool,
+    /// Report what would be uploaded instead of uploading.
+    pub dry_run: bool,
+    pub ignore: IgnoreConfig,
 }
 
 impl IndexerConfig {
@@ -40,6 +87,57 @@ impl IndexerConfig {
             commit,
             output_dir,
             branch_policy,
+            hooks: Vec::new(),
+            read_commit_tree: false,
+            base_commit: None,
+            content_check: None,
+            include_untracked: false,
+            snapshot_only: false,
+            dry_run: false,
+            ignore: IgnoreConfig::default(),
         }
     }
+
+    pub fn with_hook(mut self, hook: Arc<dyn ExtractionHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Indexes `commit` as committed, ignoring the working tree.
+    pub fn with_commit_tree(mut self) -> Self {
+        self.read_commit_tree = true;
+        self
+    }
+
+    /// Only extracts files that changed since `base_commit`.
+    pub fn with_base_commit(mut self, base_commit: String) -> Self {
+        self.base_commit = Some(base_commit);
+        self
+    }
+
+    pub fn with_content_check(mut self, check: Arc<dyn ContentCheck>) -> Self {
+        self.content_check = Some(check);
+        self
+    }
+
+    /// Indexes untracked working-tree files alongside tracked ones.
+    pub fn with_untracked(mut self) -> Self {
+        self.include_untracked = true;
+        self
+    }
+
+    pub fn with_snapshot_only(mut self) -> Self {
+        self.snapshot_only = true;
+        self
+    }
+
+    pub fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    pub fn with_ignore(mut self, ignore: IgnoreConfig) -> Self {
+        self.ignore = ignore;
+        self
+    }
 }
@@ -1,30 +1,41 @@
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::io::Cursor;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
 use anyhow::{Context, Result};
-use crossbeam_channel::bounded;
+use crossbeam_channel::{Sender, bounded};
+use git2::{Delta, DiffOptions, ObjectType, Repository, Tree, TreeWalkMode, TreeWalkResult};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use ignore::{WalkBuilder, WalkState};
 use rayon::iter::ParallelBridge;
 use rayon::prelude::*;
 use tracing::{debug, info, warn};
 
 use crate::chunk_store::ChunkStore;
-use crate::config::IndexerConfig;
+use crate::config::{IgnoreConfig, IndexerConfig};
 use crate::extractors::{self, ExtractedSymbol};
+use crate::hooks::FileContext;
 use crate::models::{
-    BranchHead, BranchPolicy, BranchSnapshotPolicy, ChunkMapping, ContentBlob, FilePointer,
-    IndexArtifacts, RecordWriter, ReferenceRecord, SymbolNamespaceRecord, SymbolRecord,
+    BranchHead, BranchPolicy, BranchSnapshotPolicy, ChunkMapping, ContentBlob, ExtractionFailure,
+    FileKind, FilePointer, IndexArtifacts, RecordWriter, ReferenceRecord, SkippedFiles,
+    SymbolNamespaceRecord, SymbolRecord,
 };
 use crate::utils;
 
 const MIN_CHUNK_SIZE: u32 = 64 * 1024;
 const AVG_CHUNK_SIZE: u32 = 256 * 1024;
 const MAX_CHUNK_SIZE: u32 = 1024 * 1024;
+const GIT_FILEMODE_LINK: i32 = 0o120000;
+const POINTER_IGNORE_FILE: &str = ".pointerignore";
+/// Ignore files read from a commit tree, lowest precedence first.
+const TREE_IGNORE_FILES: [&str; 2] = [".gitignore", POINTER_IGNORE_FILE];
 
 pub struct Indexer {
     config: IndexerConfig,
@@ -36,18 +47,6 @@ impl Indexer {
     }
 
     pub fn run(&self) -> Result<IndexArtifacts> {
-        let walker = WalkBuilder::new(&self.config.repo_path)
-            .git_ignore(true)
-            .git_exclude(true)
-            .hidden(false)
-            .ignore(true)
-            .build_parallel();
-
-        info!(
-            repo = %self.config.repo_path.display(),
-            "walker configured with git_ignore=true git_exclude=true ignore=true hidden=false"
-        );
-
         let scratch_dir = self.config.output_dir.join(".pointer-scratch");
         fs::create_dir_all(&scratch_dir).with_context(|| {
             format!(
@@ -61,22 +60,97 @@ impl Indexer {
         let skipped_non_file = Arc::new(AtomicUsize::new(0));
         let skipped_outside_repo = Arc::new(AtomicUsize::new(0));
         let skipped_filtered = Arc::new(AtomicUsize::new(0));
+        let skipped_untracked = Arc::new(AtomicUsize::new(0));
+        let skipped_ignored = Arc::new(AtomicUsize::new(0));
+        let ignore_patterns = Arc::new(ignore_matcher(
+            &self.config.repo_path,
+            &self.config.ignore.patterns,
+        )?);
+        let use_ignore_files = self.config.ignore.use_ignore_files;
+
+        let changed = match self.config.base_commit.as_deref() {
+            Some(base) => match changed_since(&self.config, base) {
+                Ok(changed) => {
+                    info!(%base, changed_files = changed.len(), "indexing changes since base commit");
+                    Some(Arc::new(changed))
+                }
+                Err(err) => {
+                    warn!(error = %err, %base, "could not diff against base commit; indexing every file");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let walker_thread = if self.config.read_commit_tree {
+            let tx = tx.clone();
+            let changed = changed.clone();
+            let repo_path = self.config.repo_path.clone();
+            let commit = self.config.commit.clone();
+            let seen_files = Arc::clone(&seen_files);
+            let skipped_non_file = Arc::clone(&skipped_non_file);
+            let skipped_filtered = Arc::clone(&skipped_filtered);
+            let skipped_ignored = Arc::clone(&skipped_ignored);
+            let ignore_patterns = Arc::clone(&ignore_patterns);
+            thread::spawn(move || {
+                walk_commit_tree(
+                    &repo_path,
+                    &commit,
+                    changed.as_deref(),
+                    &ignore_patterns,
+                    use_ignore_files,
+                    &tx,
+                    &seen_files,
+                    &skipped_non_file,
+                    &skipped_filtered,
+                    &skipped_ignored,
+                )
+            })
+        } else {
+            let tracked = if self.config.include_untracked {
+                None
+            } else {
+                tracked_paths(&self.config.repo_path)?.map(Arc::new)
+            };
+            let mut builder = WalkBuilder::new(&self.config.repo_path);
+            builder
+                .git_ignore(true)
+                .git_exclude(true)
+                .hidden(false)
+                .ignore(true);
+            if use_ignore_files {
+                builder.add_custom_ignore_filename(POINTER_IGNORE_FILE);
+            }
+            let walker = builder.build_parallel();
+
+            info!(
+                repo = %self.config.repo_path.display(),
+                ignore_patterns = self.config.ignore.patterns.len(),
+                use_ignore_files,
+                "walker configured with git_ignore=true git_exclude=true ignore=true hidden=false"
+            );
 
-        let walker_thread = {
             let tx = tx.clone();
             let repo_root = self.config.repo_path.clone();
             let seen_files = Arc::clone(&seen_files);
             let skipped_non_file = Arc::clone(&skipped_non_file);
             let skipped_outside_repo = Arc::clone(&skipped_outside_repo);
             let skipped_filtered = Arc::clone(&skipped_filtered);
+            let skipped_untracked = Arc::clone(&skipped_untracked);
+            let skipped_ignored = Arc::clone(&skipped_ignored);
             thread::spawn(move || {
                 walker.run(|| {
                     let tx = tx.clone();
                     let repo_root = repo_root.clone();
+                    let tracked = tracked.clone();
+                    let changed = changed.clone();
+                    let ignore_patterns = Arc::clone(&ignore_patterns);
                     let seen_files = Arc::clone(&seen_files);
                     let skipped_non_file = Arc::clone(&skipped_non_file);
                     let skipped_outside_repo = Arc::clone(&skipped_outside_repo);
                     let skipped_filtered = Arc::clone(&skipped_filtered);
+                    let skipped_untracked = Arc::clone(&skipped_untracked);
+                    let skipped_ignored = Arc::clone(&skipped_ignored);
                     Box::new(move |entry| {
                         match entry {
                             Ok(entry) => {
@@ -111,10 +185,31 @@ impl Indexer {
                                     return WalkState::Continue;
                                 }
 
+                                if matches_ignore_pattern(&ignore_patterns, &relative_path) {
+                                    skipped_ignored.fetch_add(1, Ordering::Relaxed);
+                                    debug!(path = %relative_path.display(), "skipping ignored file");
+                                    return WalkState::Continue;
+                                }
+
+                                if tracked
+                                    .as_ref()
+                                    .is_some_and(|tracked| !tracked.contains(&relative_path))
+                                {
+                                    skipped_untracked.fetch_add(1, Ordering::Relaxed);
+                                    debug!(path = %relative_path.display(), "skipping untracked file");
+                                    return WalkState::Continue;
+                                }
+
+                                let unchanged = changed
+                                    .as_ref()
+                                    .is_some_and(|changed| !changed.contains(&relative_path));
                                 if tx
                                     .send(FileEntry {
-                                        absolute: absolute_path,
                                         relative: relative_path,
+                                        source: FileSource::Disk(absolute_path),
+                                        kind: FileKind::Regular,
+                                        mode: None,
+                                        unchanged,
                                     })
                                     .is_err()
                                 {
@@ -129,6 +224,7 @@ impl Indexer {
                         WalkState::Continue
                     })
                 });
+                Ok(())
             })
         };
         drop(tx);
@@ -147,24 +243,69 @@ impl Indexer {
 
         let processed_ok = Arc::new(AtomicUsize::new(0));
         let processed_err = Arc::new(AtomicUsize::new(0));
-
-        rx.into_iter()
-            .par_bridge()
-            .for_each({
-                let chunk_store = chunk_store.clone();
-                let seen_hashes = seen_hashes.clone();
-                let content_blobs_writer = content_blobs_writer.clone();
-                let file_pointers_writer = file_pointers_writer.clone();
-                let symbol_records_writer = symbol_records_writer.clone();
-                let symbol_namespaces_writer = symbol_namespaces_writer.clone();
-                let reference_records_writer = reference_records_writer.clone();
-                let chunk_mappings_writer = chunk_mappings_writer.clone();
-                let seen_namespaces = seen_namespaces.clone();
-                let config = config.clone();
-                let processed_ok = Arc::clone(&processed_ok);
-                let processed_err = Arc::clone(&processed_err);
-
-                move |entry| match process_file(&config, &entry) {
+        let unchanged_files = Arc::new(AtomicUsize::new(0));
+        let extraction_failures = Arc::new(Mutex::new(Vec::new()));
+        let skipped_too_large = Arc::new(AtomicUsize::new(0));
+        let skipped_long_lines = Arc::new(AtomicUsize::new(0));
+
+        let carried = Arc::new(Mutex::new(Vec::new()));
+
+        let handle_entry = {
+            let chunk_store = chunk_store.clone();
+            let seen_hashes = seen_hashes.clone();
+            let content_blobs_writer = content_blobs_writer.clone();
+            let file_pointers_writer = file_pointers_writer.clone();
+            let symbol_records_writer = symbol_records_writer.clone();
+            let symbol_namespaces_writer = symbol_namespaces_writer.clone();
+            let reference_records_writer = reference_records_writer.clone();
+            let chunk_mappings_writer = chunk_mappings_writer.clone();
+            let seen_namespaces = seen_namespaces.clone();
+            let config = config.clone();
+            let processed_ok = Arc::clone(&processed_ok);
+            let processed_err = Arc::clone(&processed_err);
+            let unchanged_files = Arc::clone(&unchanged_files);
+            let extraction_failures = Arc::clone(&extraction_failures);
+            let skipped_too_large = Arc::clone(&skipped_too_large);
+            let skipped_long_lines = Arc::clone(&skipped_long_lines);
+            let carried = Arc::clone(&carried);
+
+            move |entry: FileEntry| {
+                let bytes = match read_entry(&entry) {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        processed_err.fetch_add(1, Ordering::Relaxed);
+                        warn!(error = %err, "failed to read file");
+                        return;
+                    }
+                };
+                match exceeded_limit(&config.ignore, &entry, &bytes) {
+                    Some(SizeLimit::FileSize) => {
+                        skipped_too_large.fetch_add(1, Ordering::Relaxed);
+                        debug!(path = %entry.relative.display(), "skipping file over the size limit");
+                        return;
+                    }
+                    Some(SizeLimit::LineLength) => {
+                        skipped_long_lines.fetch_add(1, Ordering::Relaxed);
+                        debug!(path = %entry.relative.display(), "skipping file over the line length limit");
+                        return;
+                    }
+                    None => {}
+                }
+                if entry.unchanged {
+                    unchanged_files.fetch_add(1, Ordering::Relaxed);
+                    let file_pointer = carry_forward(&config, &entry, &bytes);
+                    drop(bytes);
+                    if config.content_check.is_some() {
+                        carried
+                            .lock()
+                            .expect("carried files mutex poisoned")
+                            .push(CarriedFile::new(entry, file_pointer));
+                    } else if let Err(err) = file_pointers_writer.append(&file_pointer) {
+                        warn!(error = %err, "failed to record file pointer");
+                    }
+                    return;
+                }
+                match process_file(&config, &entry, &bytes) {
                     Ok(file_artifacts) => {
                         processed_ok.fetch_add(1, Ordering::Relaxed);
                         let FileArtifacts {
@@ -175,17 +316,27 @@ impl Indexer {
                             reference_records: file_references,
                             chunk_mappings: file_chunk_mappings,
                             chunk_writes,
+                            extraction_failure,
                         } = file_artifacts;
 
                         let content_hash = file_pointer.content_hash.clone();
 
+                        if let Some(message) = extraction_failure {
+                            extraction_failures
+                                .lock()
+                                .expect("extraction failures mutex poisoned")
+                                .push(ExtractionFailure {
+                                    file_path: file_pointer.file_path.clone(),
+                                    message,
+                                });
+                        }
+
                         if let Err(err) = file_pointers_writer.append(&file_pointer) {
                             warn!(error = %err, "failed to record file pointer");
                         }
 
                         let is_new_content = {
-                            let mut seen =
-                                seen_hashes.lock().expect("seen hashes mutex poisoned");
+                            let mut seen = seen_hashes.lock().expect("seen hashes mutex poisoned");
                             seen.insert(content_hash.clone())
                         };
 
@@ -217,8 +368,9 @@ impl Indexer {
                             for namespace in &file_namespaces {
                                 let ns = namespace.namespace.clone();
                                 let should_write = {
-                                    let mut guard =
-                                        seen_namespaces.lock().expect("namespace set mutex poisoned");
+                                    let mut guard = seen_namespaces
+                                        .lock()
+                                        .expect("namespace set mutex poisoned");
                                     guard.insert(ns.clone())
                                 };
                                 if should_write {
@@ -238,8 +390,7 @@ impl Indexer {
                                 }
                             }
 
-                            let mut store =
-                                chunk_store.lock().expect("chunk store mutex poisoned");
+                            let mut store = chunk_store.lock().expect("chunk store mutex poisoned");
                             for chunk in chunk_writes {
                                 if let Err(err) = store.insert(chunk.hash, chunk.text_content) {
                                     warn!(%content_hash, error = %err, "failed to insert chunk");
@@ -252,9 +403,48 @@ impl Indexer {
                         warn!(error = %err, "failed to process file");
                     }
                 }
-            });
+            }
+        };
 
-        walker_thread.join().expect("file walker thread panicked");
+        rx.into_iter().par_bridge().for_each(&handle_entry);
+        walker_thread.join().expect("file walker thread panicked")?;
+
+        let carried = std::mem::take(&mut *carried.lock().expect("carried files mutex poisoned"));
+        if let Some(check) = self
+            .config
+            .content_check
+            .as_ref()
+            .filter(|_| !carried.is_empty())
+        {
+            let hashes: Vec<String> = carried
+                .iter()
+                .map(|file| file.file_pointer.content_hash.clone())
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+            let missing = check
+                .missing(&hashes)
+                .context("failed to check which carried-forward contents the backend holds")?;
+            let (reindex, kept): (Vec<_>, Vec<_>) = carried
+                .into_iter()
+                .partition(|file| missing.contains(&file.file_pointer.content_hash));
+            for file in &kept {
+                if let Err(err) = file_pointers_writer.append(&file.file_pointer) {
+                    warn!(error = %err, "failed to record file pointer");
+                }
+            }
+            if !reindex.is_empty() {
+                info!(
+                    files = reindex.len(),
+                    "backend lacks the content of unchanged files; indexing them in full"
+                );
+                unchanged_files.fetch_sub(reindex.len(), Ordering::Relaxed);
+                reload_carried(&self.config, reindex)?
+                    .into_par_iter()
+                    .for_each(&handle_entry);
+            }
+        }
+        drop(handle_entry);
 
         let chunk_store = Arc::try_unwrap(chunk_store)
             .expect("chunk store still has outstanding references")
@@ -266,14 +456,31 @@ impl Indexer {
         let symbol_namespaces = symbol_namespaces_writer.into_store()?;
         let reference_records = reference_records_writer.into_store()?;
         let chunk_mappings = chunk_mappings_writer.into_store()?;
+        let extraction_failures = Arc::try_unwrap(extraction_failures)
+            .expect("extraction failures still have outstanding references")
+            .into_inner()
+            .expect("extraction failures mutex poisoned");
+        let skipped_files = SkippedFiles {
+            filtered: skipped_filtered.load(Ordering::Relaxed),
+            untracked: skipped_untracked.load(Ordering::Relaxed),
+            ignored: skipped_ignored.load(Ordering::Relaxed),
+            too_large: skipped_too_large.load(Ordering::Relaxed),
+            long_lines: skipped_long_lines.load(Ordering::Relaxed),
+        };
 
         info!(
             seen_files = seen_files.load(Ordering::Relaxed),
             skipped_non_file = skipped_non_file.load(Ordering::Relaxed),
             skipped_outside_repo = skipped_outside_repo.load(Ordering::Relaxed),
-            skipped_filtered = skipped_filtered.load(Ordering::Relaxed),
+            skipped_filtered = skipped_files.filtered,
+            skipped_untracked = skipped_files.untracked,
+            skipped_ignored = skipped_files.ignored,
+            skipped_too_large = skipped_files.too_large,
+            skipped_long_lines = skipped_files.long_lines,
             processed_ok = processed_ok.load(Ordering::Relaxed),
             processed_err = processed_err.load(Ordering::Relaxed),
+            unchanged_files = unchanged_files.load(Ordering::Relaxed),
+            extraction_failures = extraction_failures.len(),
             "indexer file scan summary"
         );
 
@@ -300,9 +507,18 @@ impl Indexer {
                 branch: branch.clone(),
                 commit_sha: self.config.commit.clone(),
                 policy,
+                snapshot_only: self.config.snapshot_only,
             });
         }
 
+        let commits: Vec<_> = utils::commit_metadata(
+            &self.config.repo_path,
+            &self.config.repository,
+            &self.config.commit,
+        )
+        .into_iter()
+        .collect();
+
         Ok(IndexArtifacts::new(
             content_blobs,
             symbol_records,
@@ -313,7 +529,11 @@ impl Indexer {
             chunk_store,
             branches,
             scratch_dir,
-        ))
+        )
+        .with_commits(commits)
+        .with_unchanged_files(unchanged_files.load(Ordering::Relaxed))
+        .with_extraction_failures(extraction_failures)
+        .with_skipped_files(skipped_files))
     }
 
     pub fn config(&self) -> &IndexerConfig {
@@ -322,8 +542,279 @@ impl Indexer {
 }
 
 struct FileEntry {
-    absolute: PathBuf,
     relative: PathBuf,
+    source: FileSource,
+    kind: FileKind,
+    mode: Option<u32>,
+    /// Unchanged since the base commit: only its file pointer is re-emitted.
+    unchanged: bool,
+}
+
+enum FileSource {
+    Disk(PathBuf),
+    /// Contents read from the git object database.
+    Blob(Vec<u8>),
+}
+
+/// The files in the git index under `repo_path`, relative to it. `None` when
+/// `repo_path` is not inside a git work tree, so every walked file counts as
+/// tracked.
+fn tracked_paths(repo_path: &Path) -> Result<Option<HashSet<PathBuf>>> {
+    let Ok(repo) = Repository::discover(repo_path) else {
+        return Ok(None);
+    };
+    let Some(prefix) = workdir_prefix(&repo, repo_path)? else {
+        return Ok(None);
+    };
+
+    let index = repo
+        .index()
+        .with_context(|| format!("failed to read the git index of {}", repo_path.display()))?;
+    Ok(Some(
+        index
+            .iter()
+            .filter_map(|entry| {
+                let path = PathBuf::from(String::from_utf8_lossy(&entry.path).into_owned());
+                path.strip_prefix(&prefix).ok().map(Path::to_path_buf)
+            })
+            .collect(),
+    ))
+}
+
+/// Where `repo_path` sits inside `repo`'s work tree; empty at the top level.
+/// `None` for a bare repository.
+fn workdir_prefix(repo: &Repository, repo_path: &Path) -> Result<Option<PathBuf>> {
+    let Some(workdir) = repo.workdir() else {
+        return Ok(None);
+    };
+    let workdir = fs::canonicalize(workdir)
+        .with_context(|| format!("failed to resolve {}", workdir.display()))?;
+    let root = fs::canonicalize(repo_path)
+        .with_context(|| format!("failed to resolve {}", repo_path.display()))?;
+    Ok(Some(
+        root.strip_prefix(&workdir)
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    ))
+}
+
+/// Paths added or modified since `base`, relative to the walk root: the
+/// repository root for a commit-tree walk, `repo_path` otherwise. Every other
+/// walked file is unchanged and keeps its base content hash.
+fn changed_since(config: &IndexerConfig, base: &str) -> Result<HashSet<PathBuf>> {
+    let repo = Repository::discover(&config.repo_path).with_context(|| {
+        format!(
+            "failed to open git repository at {}",
+            config.repo_path.display()
+        )
+    })?;
+    let base_tree = repo
+        .revparse_single(base)
+        .and_then(|object| object.peel_to_tree())
+        .with_context(|| format!("could not resolve the tree of base commit {base}"))?;
+
+    let mut options = DiffOptions::new();
+    let (diff, prefix) = if config.read_commit_tree {
+        let tree = repo
+            .revparse_single(&config.commit)
+            .and_then(|object| object.peel_to_tree())
+            .with_context(|| format!("could not resolve the tree of commit {}", config.commit))?;
+        let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&tree), Some(&mut options));
+        (diff, PathBuf::new())
+    } else {
+        options
+            .include_untracked(config.include_untracked)
+            .recurse_untracked_dirs(true);
+        let diff = repo.diff_tree_to_workdir_with_index(Some(&base_tree), Some(&mut options));
+        let prefix = workdir_prefix(&repo, &config.repo_path)?.unwrap_or_default();
+        (diff, prefix)
+    };
+    let diff = diff.with_context(|| format!("failed to diff against base commit {base}"))?;
+
+    Ok(diff
+        .deltas()
+        .filter(|delta| delta.status() != Delta::Deleted)
+        .filter_map(|delta| delta.new_file().path())
+        .filter_map(|path| path.strip_prefix(&prefix).ok().map(Path::to_path_buf))
+        .collect())
+}
+
+/// Sends every file in `commit`'s tree, read from the object database so the
+/// working tree does not need to be checked out at that commit. Symlinks are
+/// sent with their target as content and submodules with the pinned commit id.
+#[allow(clippy::too_many_arguments)]
+fn walk_commit_tree(
+    repo_path: &Path,
+    commit: &str,
+    changed: Option<&HashSet<PathBuf>>,
+    ignore_patterns: &Gitignore,
+    use_ignore_files: bool,
+    tx: &Sender<FileEntry>,
+    seen_files: &AtomicUsize,
+    skipped_non_file: &AtomicUsize,
+    skipped_filtered: &AtomicUsize,
+    skipped_ignored: &AtomicUsize,
+) -> Result<()> {
+    let repo = Repository::discover(repo_path)
+        .with_context(|| format!("failed to open git repository at {}", repo_path.display()))?;
+    let tree = repo
+        .revparse_single(commit)
+        .and_then(|object| object.peel_to_tree())
+        .with_context(|| format!("could not resolve the tree of commit {commit}"))?;
+    info!(repo = %repo_path.display(), %commit, "reading files from the commit tree");
+    let ignore_files = if use_ignore_files {
+        TreeIgnores::read(&repo, &tree)
+            .with_context(|| format!("failed to read the ignore files of commit {commit}"))?
+    } else {
+        TreeIgnores::default()
+    };
+
+    let mut failure = None;
+    let walked = tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        let kind = match entry.kind() {
+            Some(ObjectType::Tree) => return TreeWalkResult::Ok,
+            Some(ObjectType::Blob) if entry.filemode() == GIT_FILEMODE_LINK => FileKind::Symlink,
+            Some(ObjectType::Blob) => FileKind::Regular,
+            Some(ObjectType::Commit) => FileKind::Submodule,
+            _ => {
+                skipped_non_file.fetch_add(1, Ordering::Relaxed);
+                return TreeWalkResult::Ok;
+            }
+        };
+        let Some(name) = entry.name() else {
+            warn!(dir, "skipping tree entry with a non-UTF-8 name");
+            return TreeWalkResult::Ok;
+        };
+        let relative = Path::new(dir).join(name);
+        if should_skip(&relative) {
+            skipped_filtered.fetch_add(1, Ordering::Relaxed);
+            debug!(path = %relative.display(), "skipping filtered file");
+            return TreeWalkResult::Ok;
+        }
+        if matches_ignore_pattern(ignore_patterns, &relative) || ignore_files.is_ignored(&relative)
+        {
+            skipped_ignored.fetch_add(1, Ordering::Relaxed);
+            debug!(path = %relative.display(), "skipping ignored file");
+            return TreeWalkResult::Ok;
+        }
+
+        let bytes = if kind == FileKind::Submodule {
+            entry.id().to_string().into_bytes()
+        } else {
+            match repo.find_blob(entry.id()) {
+                Ok(blob) => blob.content().to_vec(),
+                Err(err) => {
+                    failure = Some(
+                        anyhow::Error::new(err)
+                            .context(format!("failed to read {} at {commit}", relative.display())),
+                    );
+                    return TreeWalkResult::Abort;
+                }
+            }
+        };
+        let unchanged = changed.is_some_and(|changed| !changed.contains(&relative));
+        if tx
+            .send(FileEntry {
+                relative,
+                source: FileSource::Blob(bytes),
+                kind,
+                mode: Some(entry.filemode() as u32),
+                unchanged,
+            })
+            .is_err()
+        {
+            return TreeWalkResult::Abort;
+        }
+        seen_files.fetch_add(1, Ordering::Relaxed);
+        TreeWalkResult::Ok
+    });
+
+    if let Some(err) = failure {
+        return Err(err);
+    }
+    walked.with_context(|| format!("failed to walk the tree of commit {commit}"))
+}
+
+/// Builds the matcher for `IgnoreConfig::patterns`, which are matched against
+/// repo-relative paths.
+fn ignore_matcher(repo_path: &Path, patterns: &[String]) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(repo_path);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .with_context(|| format!("invalid ignore pattern {pattern:?}"))?;
+    }
+    builder
+        .build()
+        .context("failed to build the ignore patterns")
+}
+
+/// Whether `relative` or one of its directories is ignored by `matcher`.
+fn matches_ignore_pattern(matcher: &Gitignore, relative: &Path) -> bool {
+    matcher
+        .matched_path_or_any_parents(relative, false)
+        .is_ignore()
+}
+
+/// The `.gitignore` and `.pointerignore` rules of a commit tree, one matcher
+/// per directory holding them. Within a directory `.pointerignore` rules are
+/// added last, so they override `.gitignore`.
+#[derive(Default)]
+struct TreeIgnores {
+    /// Deepest directories first, so nested rules take precedence.
+    matchers: Vec<(PathBuf, Gitignore)>,
+}
+
+impl TreeIgnores {
+    fn read(repo: &Repository, tree: &Tree<'_>) -> Result<Self> {
+        let mut files: BTreeMap<PathBuf, Vec<(usize, String)>> = BTreeMap::new();
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if let Some(rank) = entry
+                .name()
+                .and_then(|name| TREE_IGNORE_FILES.iter().position(|file| *file == name))
+                && entry.kind() == Some(ObjectType::Blob)
+                && let Ok(blob) = repo.find_blob(entry.id())
+            {
+                files
+                    .entry(PathBuf::from(dir))
+                    .or_default()
+                    .push((rank, String::from_utf8_lossy(blob.content()).into_owned()));
+            }
+            TreeWalkResult::Ok
+        })?;
+
+        let mut matchers = Vec::with_capacity(files.len());
+        for (dir, mut contents) in files {
+            contents.sort_by_key(|(rank, _)| *rank);
+            // Paths are made relative to `dir` before matching, so the
+            // matcher itself is unrooted.
+            let mut builder = GitignoreBuilder::new("");
+            for line in contents.iter().flat_map(|(_, content)| content.lines()) {
+                if let Err(err) = builder.add_line(None, line) {
+                    warn!(dir = %dir.display(), error = %err, "skipping invalid ignore rule");
+                }
+            }
+            matchers.push((dir, builder.build()?));
+        }
+        matchers.sort_by_key(|(dir, _)| Reverse(dir.components().count()));
+        Ok(Self { matchers })
+    }
+
+    fn is_ignored(&self, relative: &Path) -> bool {
+        for (dir, matcher) in &self.matchers {
+            let Ok(within) = relative.strip_prefix(dir) else {
+                continue;
+            };
+            let matched = matcher.matched_path_or_any_parents(within, false);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
 }
 
 struct ChunkWrite {
@@ -339,39 +830,191 @@ struct FileArtifacts {
     reference_records: Vec<ReferenceRecord>,
     chunk_mappings: Vec<ChunkMapping>,
     chunk_writes: Vec<ChunkWrite>,
+    /// Panic message of an extractor or hook that failed on this file.
+    extraction_failure: Option<String>,
+}
+
+fn read_entry(entry: &FileEntry) -> Result<Cow<'_, [u8]>> {
+    Ok(match &entry.source {
+        FileSource::Disk(path) => Cow::Owned(
+            fs::read(path).with_context(|| format!("failed to read {}", path.display()))?,
+        ),
+        FileSource::Blob(bytes) => Cow::Borrowed(bytes),
+    })
+}
+
+fn file_pointer(config: &IndexerConfig, entry: &FileEntry, content_hash: String) -> FilePointer {
+    FilePointer {
+        repository: config.repository.clone(),
+        commit_sha: config.commit.clone(),
+        file_path: utils::normalize_relative_path(&entry.relative),
+        content_hash,
+        kind: entry.kind,
+        mode: entry.mode,
+    }
 }
 
-fn process_file(config: &IndexerConfig, entry: &FileEntry) -> Result<FileArtifacts> {
-    let bytes = fs::read(&entry.absolute)
-        .with_context(|| format!("failed to read {}", entry.absolute.display()))?;
+/// A size limit a file exceeded, leaving it out of the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SizeLimit {
+    FileSize,
+    LineLength,
+}
 
-    let content_hash = utils::compute_content_hash(&bytes);
-    let language = utils::infer_language(&entry.relative).map(|s| s.to_string());
+/// The first size limit `bytes` exceeds. Symlink targets and submodule ids
+/// are never limited.
+fn exceeded_limit(limits: &IgnoreConfig, entry: &FileEntry, bytes: &[u8]) -> Option<SizeLimit> {
+    if entry.kind != FileKind::Regular {
+        return None;
+    }
+    if limits
+        .max_file_bytes
+        .is_some_and(|max| bytes.len() as u64 > max)
+    {
+        return Some(SizeLimit::FileSize);
+    }
+    if limits.max_line_length.is_some_and(|max| {
+        bytes
+            .split(|&b| b == b'\n')
+            .any(|line| line.strip_suffix(b"\r").unwrap_or(line).len() > max)
+    }) {
+        return Some(SizeLimit::LineLength);
+    }
+    None
+}
+
+/// The file pointer for a file unchanged since the base commit. Its content
+/// blob, chunks and symbols were uploaded with the base commit, so only the
+/// hash is computed.
+fn carry_forward(config: &IndexerConfig, entry: &FileEntry, bytes: &[u8]) -> FilePointer {
+    file_pointer(config, entry, utils::compute_content_hash(bytes))
+}
+
+/// An unchanged file held back until the backend confirms it still stores the
+/// content. Commit-tree contents are dropped and re-read only if it does not.
+struct CarriedFile {
+    relative: PathBuf,
+    disk_path: Option<PathBuf>,
+    kind: FileKind,
+    mode: Option<u32>,
+    file_pointer: FilePointer,
+}
+
+impl CarriedFile {
+    fn new(entry: FileEntry, file_pointer: FilePointer) -> Self {
+        let disk_path = match entry.source {
+            FileSource::Disk(path) => Some(path),
+            FileSource::Blob(_) => None,
+        };
+        Self {
+            relative: entry.relative,
+            disk_path,
+            kind: entry.kind,
+            mode: entry.mode,
+            file_pointer,
+        }
+    }
+}
+
+/// Turns carried files back into entries to index in full, re-reading the
+/// contents of commit-tree files from the git object database.
+fn reload_carried(config: &IndexerConfig, carried: Vec<CarriedFile>) -> Result<Vec<FileEntry>> {
+    let repo = if config.read_commit_tree {
+        Some(Repository::discover(&config.repo_path).with_context(|| {
+            format!(
+                "failed to open git repository at {}",
+                config.repo_path.display()
+            )
+        })?)
+    } else {
+        None
+    };
+    let tree = repo
+        .as_ref()
+        .map(|repo| {
+            repo.revparse_single(&config.commit)
+                .and_then(|object| object.peel_to_tree())
+                .with_context(|| format!("could not resolve the tree of commit {}", config.commit))
+        })
+        .transpose()?;
+
+    carried
+        .into_iter()
+        .map(|file| {
+            let source = match (file.disk_path, &repo, &tree) {
+                (Some(path), _, _) => FileSource::Disk(path),
+                (None, Some(repo), Some(tree)) => {
+                    let entry = tree.get_path(&file.relative).with_context(|| {
+                        format!(
+                            "failed to find {} at {}",
+                            file.relative.display(),
+                            config.commit
+                        )
+                    })?;
+                    let bytes = if file.kind == FileKind::Submodule {
+                        entry.id().to_string().into_bytes()
+                    } else {
+                        repo.find_blob(entry.id())
+                            .with_context(|| {
+                                format!(
+                                    "failed to read {} at {}",
+                                    file.relative.display(),
+                                    config.commit
+                                )
+                            })?
+                            .content()
+                            .to_vec()
+                    };
+                    FileSource::Blob(bytes)
+                }
+                (None, _, _) => {
+                    anyhow::bail!("no source to re-read {} from", file.relative.display())
+                }
+            };
+            Ok(FileEntry {
+                relative: file.relative,
+                source,
+                kind: file.kind,
+                mode: file.mode,
+                unchanged: false,
+            })
+        })
+        .collect()
+}
+
+fn process_file(config: &IndexerConfig, entry: &FileEntry, bytes: &[u8]) -> Result<FileArtifacts> {
+    let content_hash = utils::compute_content_hash(bytes);
     let normalized_path = utils::normalize_relative_path(&entry.relative);
+    let relative = Path::new(&normalized_path);
+    // Symlink targets and submodule commit ids are not source code.
+    let language = match entry.kind {
+        FileKind::Regular => utils::infer_language(relative).map(|s| s.to_string()),
+        FileKind::Symlink | FileKind::Submodule => None,
+    };
     let byte_len = bytes.len() as i64;
-    let line_count = utils::line_count(&bytes);
+    let line_count = utils::line_count(bytes);
 
     let mut chunk_mappings = Vec::new();
     let mut chunk_writes = Vec::new();
 
     let is_binary = bytes.iter().any(|&b| b == 0);
     if !is_binary {
-        match std::str::from_utf8(&bytes) {
+        match std::str::from_utf8(bytes) {
             Ok(full_text) => {
                 if bytes.len() < MIN_CHUNK_SIZE as usize {
-                    let chunk_hash = utils::compute_content_hash(&bytes);
+                    let chunk_hash = utils::compute_content_hash(bytes);
                     chunk_mappings.push(ChunkMapping {
                         content_hash: content_hash.clone(),
                         chunk_hash: chunk_hash.clone(),
                         chunk_index: 0,
-                        chunk_line_count: utils::line_count(&bytes),
+                        chunk_line_count: utils::line_count(bytes),
                     });
                     chunk_writes.push(ChunkWrite {
                         hash: chunk_hash,
                         text_content: full_text.to_string(),
                     });
                 } else {
-                    let (chunk_ranges, used_fallback) = compute_chunk_ranges(&bytes, full_text);
+                    let (chunk_ranges, used_fallback) = compute_chunk_ranges(bytes, full_text);
 
                     if used_fallback {
                         debug!(
@@ -430,18 +1073,45 @@ fn process_file(config: &IndexerConfig, entry: &FileEntry) -> Result<FileArtifac
         line_count,
     };
 
-    let file_pointer = FilePointer {
-        repository: config.repository.clone(),
-        commit_sha: config.commit.clone(),
-        file_path: normalized_path.clone(),
-        content_hash: content_hash.clone(),
-    };
+    let file_pointer = file_pointer(config, entry, content_hash.clone());
 
+    let mut extraction_failure = None;
     let (symbol_records, reference_records, symbol_namespaces) = match language {
         Some(ref lang) => {
-            let source = String::from_utf8_lossy(&bytes);
-            let namespace_hint = utils::namespace_from_path(Some(lang), &entry.relative);
-            let extraction = extractors::extract(lang, &source, namespace_hint.as_deref());
+            let source = String::from_utf8_lossy(bytes);
+            let namespace_hint = utils::namespace_from_path(Some(lang), relative);
+            // Tree-sitter grammars and hooks can panic on unusual input; the
+            // file is then indexed without symbols instead of aborting the run.
+            let extracted = panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut extraction = extractors::extract(lang, &source, namespace_hint.as_deref());
+                if !config.hooks.is_empty() {
+                    let file = FileContext {
+                        repository: &config.repository,
+                        commit_sha: &config.commit,
+                        path: &normalized_path,
+                        language: lang,
+                        namespace_hint: namespace_hint.as_deref(),
+                    };
+                    for hook in &config.hooks {
+                        hook.transform(&file, &mut extraction);
+                    }
+                }
+                extraction
+            }));
+            let extraction = match extracted {
+                Ok(extraction) => extraction,
+                Err(payload) => {
+                    let message = panic_message(payload.as_ref());
+                    warn!(
+                        file = %normalized_path,
+                        language = %lang,
+                        panic = %message,
+                        "symbol extraction panicked; indexing the file without symbols"
+                    );
+                    extraction_failure = Some(message);
+                    extractors::Extraction::default()
+                }
+            };
 
             let symbols = derive_symbols(&extraction.references)
                 .into_iter()
@@ -469,6 +1139,9 @@ fn process_file(config: &IndexerConfig, entry: &FileEntry) -> Result<FileArtifac
                         kind: reference.kind,
                         line: reference.line,
                         column: reference.column,
+                        end_line: reference.end_line,
+                        end_column: reference.end_column,
+                        signature: reference.signature,
                     }
                 })
                 .collect();
@@ -495,9 +1168,19 @@ fn process_file(config: &IndexerConfig, entry: &FileEntry) -> Result<FileArtifac
         reference_records,
         chunk_mappings,
         chunk_writes,
+        extraction_failure,
     })
 }
 
+/// The message a panic was raised with, for the payloads `panic!` produces.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
 use crate::extractors::ExtractedReference;
 
 fn derive_symbols(references: &[ExtractedReference]) -> Vec<ExtractedSymbol> {
@@ -644,3 +1327,572 @@ fn fallback_chunk_ranges(full_text: &str) -> Vec<(usize, usize)> {
 
     ranges
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use git2::{Commit, IndexAddOption, Oid, Signature};
+
+    use super::*;
+
+    fn commit_all(repo: &Repository, message: &str) -> Oid {
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Pointer", "pointer@example.com").unwrap();
+        let parent: Option<Commit> = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn backfill_indexes_the_commit_tree_as_a_branch_snapshot() {
+        let checkout = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let repo = Repository::init(checkout.path()).unwrap();
+        let src = checkout.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("lib.rs"), "fn first() {}\n").unwrap();
+        let old_commit = commit_all(&repo, "first").to_string();
+        fs::write(src.join("lib.rs"), "fn second() {}\n").unwrap();
+        fs::write(src.join("extra.rs"), "fn extra() {}\n").unwrap();
+        commit_all(&repo, "second");
+        fs::write(src.join("uncommitted.rs"), "fn draft() {}\n").unwrap();
+
+        let config = IndexerConfig::new(
+            checkout.path().to_path_buf(),
+            "acme".to_string(),
+            Some("main".to_string()),
+            old_commit.clone(),
+            output.path().to_path_buf(),
+            None,
+        )
+        .with_commit_tree()
+        .with_snapshot_only();
+        let artifacts = Indexer::new(config).run().unwrap();
+
+        let files = artifacts
+            .file_pointers_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_path, "src/lib.rs");
+        assert_eq!(files[0].commit_sha, old_commit);
+
+        let symbols: HashSet<String> = artifacts
+            .symbol_records_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        assert!(symbols.contains("first"));
+        assert!(!symbols.contains("second"));
+
+        assert_eq!(artifacts.branches.len(), 1);
+        let head = &artifacts.branches[0];
+        assert_eq!(head.branch, "main");
+        assert_eq!(head.commit_sha, old_commit);
+        assert!(head.snapshot_only);
+
+        assert_eq!(artifacts.commits.len(), 1);
+        let commit = &artifacts.commits[0];
+        assert_eq!(commit.commit_sha, old_commit);
+        assert_eq!(commit.subject, "first");
+        assert_eq!(commit.author_email, "pointer@example.com");
+    }
+
+    #[test]
+    fn incremental_run_only_extracts_files_changed_since_the_base() {
+        let checkout = tempfile::tempdir().unwrap();
+        let repo = Repository::init(checkout.path()).unwrap();
+        fs::write(checkout.path().join("stable.rs"), "fn stable() {}\n").unwrap();
+        fs::write(checkout.path().join("edited.rs"), "fn before() {}\n").unwrap();
+        let base = commit_all(&repo, "base").to_string();
+        fs::write(checkout.path().join("edited.rs"), "fn after() {}\n").unwrap();
+        fs::write(checkout.path().join("added.rs"), "fn added() {}\n").unwrap();
+        let target = commit_all(&repo, "target").to_string();
+
+        let index = |config: IndexerConfig| Indexer::new(config.with_commit_tree()).run().unwrap();
+        let base_output = tempfile::tempdir().unwrap();
+        let full = index(IndexerConfig::new(
+            checkout.path().to_path_buf(),
+            "acme".to_string(),
+            None,
+            base.clone(),
+            base_output.path().to_path_buf(),
+            None,
+        ));
+        let output = tempfile::tempdir().unwrap();
+        let incremental = index(
+            IndexerConfig::new(
+                checkout.path().to_path_buf(),
+                "acme".to_string(),
+                None,
+                target,
+                output.path().to_path_buf(),
+                None,
+            )
+            .with_base_commit(base),
+        );
+
+        let hashes = |artifacts: &IndexArtifacts| -> Vec<(String, String)> {
+            let mut files: Vec<(String, String)> = artifacts
+                .file_pointers_stream()
+                .unwrap()
+                .next_batch(usize::MAX)
+                .unwrap()
+                .into_iter()
+                .map(|file| (file.file_path, file.content_hash))
+                .collect();
+            files.sort();
+            files
+        };
+        let base_files = hashes(&full);
+        let files = hashes(&incremental);
+        let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["added.rs", "edited.rs", "stable.rs"]);
+        assert_eq!(files[2], base_files[1]);
+        assert_ne!(files[1], base_files[0]);
+        assert_eq!(incremental.unchanged_files, 1);
+        assert_eq!(incremental.summary().unchanged_files, 1);
+        assert_eq!(incremental.content_blob_count(), 2);
+
+        let symbols: HashSet<String> = incremental
+            .symbol_records_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        assert!(symbols.contains("after"));
+        assert!(symbols.contains("added"));
+        assert!(!symbols.contains("stable"));
+    }
+
+    /// Reports every content hash as missing, as for a file the base run
+    /// skipped or a blob garbage-collected since.
+    #[derive(Debug)]
+    struct NothingStored;
+
+    impl crate::config::ContentCheck for NothingStored {
+        fn missing(&self, hashes: &[String]) -> Result<HashSet<String>> {
+            Ok(hashes.iter().cloned().collect())
+        }
+    }
+
+    #[test]
+    fn incremental_run_fully_indexes_unchanged_files_the_backend_lacks() {
+        let checkout = tempfile::tempdir().unwrap();
+        let repo = Repository::init(checkout.path()).unwrap();
+        fs::write(checkout.path().join("stable.rs"), "fn stable() {}\n").unwrap();
+        let base = commit_all(&repo, "base").to_string();
+        fs::write(checkout.path().join("added.rs"), "fn added() {}\n").unwrap();
+        let target = commit_all(&repo, "target").to_string();
+
+        let output = tempfile::tempdir().unwrap();
+        let artifacts = Indexer::new(
+            IndexerConfig::new(
+                checkout.path().to_path_buf(),
+                "acme".to_string(),
+                None,
+                target,
+                output.path().to_path_buf(),
+                None,
+            )
+            .with_commit_tree()
+            .with_base_commit(base)
+            .with_content_check(Arc::new(NothingStored)),
+        )
+        .run()
+        .unwrap();
+
+        assert_eq!(artifacts.file_pointer_count(), 2);
+        assert_eq!(artifacts.content_blob_count(), 2);
+        assert_eq!(artifacts.unchanged_files, 0);
+        let symbols: HashSet<String> = artifacts
+            .symbol_records_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        assert!(symbols.contains("stable"));
+        assert!(symbols.contains("added"));
+    }
+
+    #[test]
+    fn backslash_paths_are_stored_with_forward_slashes() {
+        let output = tempfile::tempdir().unwrap();
+        let config = IndexerConfig::new(
+            PathBuf::from("."),
+            "acme".to_string(),
+            None,
+            "abc123".to_string(),
+            output.path().to_path_buf(),
+            None,
+        );
+        let entry = FileEntry {
+            relative: PathBuf::from("src\\nested\\widget.rs"),
+            source: FileSource::Blob(b"pub fn render() {}\n".to_vec()),
+            kind: FileKind::Regular,
+            mode: None,
+            unchanged: false,
+        };
+
+        let bytes = read_entry(&entry).unwrap();
+        let artifacts = process_file(&config, &entry, &bytes).unwrap();
+        assert_eq!(artifacts.file_pointer.file_path, "src/nested/widget.rs");
+        assert_eq!(artifacts.content_blob.language.as_deref(), Some("rust"));
+        let namespaces: Vec<&str> = artifacts
+            .symbol_namespaces
+            .iter()
+            .map(|record| record.namespace.as_str())
+            .collect();
+        assert!(namespaces.contains(&"nested::widget"));
+    }
+
+    #[test]
+    fn working_tree_walk_includes_untracked_files_only_on_request() {
+        let checkout = tempfile::tempdir().unwrap();
+        let repo = Repository::init(checkout.path()).unwrap();
+        fs::write(checkout.path().join("tracked.rs"), "fn tracked() {}\n").unwrap();
+        let commit = commit_all(&repo, "tracked").to_string();
+        fs::write(checkout.path().join("scratch.rs"), "fn scratch() {}\n").unwrap();
+
+        let indexed_paths = |include_untracked: bool| {
+            let output = tempfile::tempdir().unwrap();
+            let config = IndexerConfig::new(
+                checkout.path().to_path_buf(),
+                "acme".to_string(),
+                None,
+                commit.clone(),
+                output.path().to_path_buf(),
+                None,
+            );
+            let config = if include_untracked {
+                config.with_untracked()
+            } else {
+                config
+            };
+            let artifacts = Indexer::new(config).run().unwrap();
+            let mut paths: Vec<String> = artifacts
+                .file_pointers_stream()
+                .unwrap()
+                .next_batch(usize::MAX)
+                .unwrap()
+                .into_iter()
+                .map(|file| file.file_path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(indexed_paths(false), vec!["tracked.rs"]);
+        assert_eq!(indexed_paths(true), vec!["scratch.rs", "tracked.rs"]);
+    }
+
+    fn indexed_paths(artifacts: &IndexArtifacts) -> Vec<String> {
+        let mut paths: Vec<String> = artifacts
+            .file_pointers_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|file| file.file_path)
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn ignore_patterns_skip_nested_paths_in_both_walks() {
+        let checkout = tempfile::tempdir().unwrap();
+        let repo = Repository::init(checkout.path()).unwrap();
+        for (path, content) in [
+            ("src/lib.rs", "fn lib() {}\n"),
+            ("src/api/generated/client.rs", "fn client() {}\n"),
+            ("vendor/dep/src/lib.rs", "fn dep() {}\n"),
+            ("web/app.js", "app();\n"),
+            ("web/app.min.js", "app();\n"),
+            ("web/keep.min.js", "keep();\n"),
+        ] {
+            let path = checkout.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let commit = commit_all(&repo, "tree").to_string();
+        let ignore = IgnoreConfig {
+            patterns: ["vendor/", "**/generated/**", "*.min.js", "!web/keep.min.js"]
+                .map(str::to_string)
+                .to_vec(),
+            ..IgnoreConfig::default()
+        };
+
+        for read_commit_tree in [false, true] {
+            let output = tempfile::tempdir().unwrap();
+            let config = IndexerConfig::new(
+                checkout.path().to_path_buf(),
+                "acme".to_string(),
+                None,
+                commit.clone(),
+                output.path().to_path_buf(),
+                None,
+            )
+            .with_ignore(ignore.clone());
+            let config = if read_commit_tree {
+                config.with_commit_tree()
+            } else {
+                config
+            };
+            let artifacts = Indexer::new(config).run().unwrap();
+
+            assert_eq!(
+                indexed_paths(&artifacts),
+                vec!["src/lib.rs", "web/app.js", "web/keep.min.js"],
+                "read_commit_tree={read_commit_tree}"
+            );
+            assert_eq!(artifacts.skipped_files.ignored, 3);
+            assert_eq!(artifacts.summary().skipped_files.ignored, 3);
+        }
+    }
+
+    #[test]
+    fn commit_tree_honors_ignore_files_only_on_request() {
+        let checkout = tempfile::tempdir().unwrap();
+        let repo = Repository::init(checkout.path()).unwrap();
+        let write = |path: &str, content: &str| {
+            let path = checkout.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("docs/guide.md", "guide\n");
+        write("docs/drafts/wip.md", "wip\n");
+        write("docs/drafts/keep.md", "keep\n");
+        write("src/lib.rs", "fn lib() {}\n");
+        commit_all(&repo, "files");
+        // Added after the files they match, which git would otherwise not add.
+        write(".gitignore", "*.log\n");
+        write("docs/.gitignore", "*.md\n");
+        write("docs/.pointerignore", "drafts/\n!drafts/keep.md\n");
+        let commit = commit_all(&repo, "ignore files").to_string();
+
+        // The artifacts read from the output directory, so it has to outlive
+        // them rather than the closure.
+        let run = |use_ignore_files: bool, output: &tempfile::TempDir| {
+            let config = IndexerConfig::new(
+                checkout.path().to_path_buf(),
+                "acme".to_string(),
+                None,
+                commit.clone(),
+                output.path().to_path_buf(),
+                None,
+            )
+            .with_commit_tree()
+            .with_ignore(IgnoreConfig {
+                use_ignore_files,
+                ..IgnoreConfig::default()
+            });
+            Indexer::new(config).run().unwrap()
+        };
+
+        let unfiltered = tempfile::tempdir().unwrap();
+        assert_eq!(indexed_paths(&run(false, &unfiltered)).len(), 7);
+        // `docs/.gitignore` drops every markdown file there; `.pointerignore`
+        // in the same directory takes precedence and keeps one draft.
+        let filtered = tempfile::tempdir().unwrap();
+        let artifacts = run(true, &filtered);
+        assert_eq!(
+            indexed_paths(&artifacts),
+            vec![
+                ".gitignore",
+                "docs/.gitignore",
+                "docs/.pointerignore",
+                "docs/drafts/keep.md",
+                "src/lib.rs",
+            ]
+        );
+        assert_eq!(artifacts.skipped_files.ignored, 2);
+    }
+
+    #[test]
+    fn size_limits_skip_large_files_and_long_lines() {
+        let repo = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        fs::write(repo.path().join("small.rs"), "fn small() {}\n").unwrap();
+        fs::write(repo.path().join("large.rs"), "fn large() {}\n".repeat(100)).unwrap();
+        fs::write(
+            repo.path().join("bundle.min.js"),
+            format!("{}\n", "a();".repeat(100)),
+        )
+        .unwrap();
+        let config = IndexerConfig::new(
+            repo.path().to_path_buf(),
+            "acme".to_string(),
+            None,
+            "abc123".to_string(),
+            output.path().to_path_buf(),
+            None,
+        )
+        .with_ignore(IgnoreConfig {
+            max_file_bytes: Some(1024),
+            max_line_length: Some(200),
+            ..IgnoreConfig::default()
+        });
+
+        let artifacts = Indexer::new(config).run().unwrap();
+
+        assert_eq!(indexed_paths(&artifacts), vec!["small.rs"]);
+        assert_eq!(
+            artifacts.summary().skipped_files,
+            SkippedFiles {
+                too_large: 1,
+                long_lines: 1,
+                ..SkippedFiles::default()
+            }
+        );
+
+        let at_limit = FileEntry {
+            relative: PathBuf::from("exact.rs"),
+            source: FileSource::Blob(Vec::new()),
+            kind: FileKind::Regular,
+            mode: None,
+            unchanged: false,
+        };
+        let limits = IgnoreConfig {
+            max_file_bytes: Some(10),
+            max_line_length: Some(3),
+            ..IgnoreConfig::default()
+        };
+        assert_eq!(exceeded_limit(&limits, &at_limit, b"abc\r\nabc\n"), None);
+        assert_eq!(
+            exceeded_limit(&limits, &at_limit, b"abcd\n"),
+            Some(SizeLimit::LineLength)
+        );
+        assert_eq!(
+            exceeded_limit(&limits, &at_limit, b"ab\nab\nab\nab\n"),
+            Some(SizeLimit::FileSize)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commit_tree_records_symlinks_and_executable_modes() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+
+        let checkout = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let repo = Repository::init(checkout.path()).unwrap();
+        fs::write(checkout.path().join("lib.rs"), "fn lib() {}\n").unwrap();
+        let script = checkout.path().join("run.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        symlink("lib.rs", checkout.path().join("alias.rs")).unwrap();
+        let commit = commit_all(&repo, "modes").to_string();
+
+        let config = IndexerConfig::new(
+            checkout.path().to_path_buf(),
+            "acme".to_string(),
+            None,
+            commit,
+            output.path().to_path_buf(),
+            None,
+        )
+        .with_commit_tree();
+        let artifacts = Indexer::new(config).run().unwrap();
+
+        let mut files = artifacts
+            .file_pointers_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap();
+        files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        let kinds: Vec<(&str, FileKind, Option<u32>)> = files
+            .iter()
+            .map(|file| (file.file_path.as_str(), file.kind, file.mode))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("alias.rs", FileKind::Symlink, Some(0o120000)),
+                ("lib.rs", FileKind::Regular, Some(0o100644)),
+                ("run.sh", FileKind::Regular, Some(0o100755)),
+            ]
+        );
+
+        let link_blob = artifacts
+            .content_blobs_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap()
+            .into_iter()
+            .find(|blob| blob.hash == files[0].content_hash)
+            .unwrap();
+        assert_eq!(link_blob.language, None);
+        assert_eq!(link_blob.byte_len, "lib.rs".len() as i64);
+    }
+
+    #[derive(Debug)]
+    struct PanicOnPath(&'static str);
+
+    impl crate::hooks::ExtractionHook for PanicOnPath {
+        fn transform(&self, file: &FileContext, _extraction: &mut extractors::Extraction) {
+            if file.path == self.0 {
+                panic!("malformed syntax tree in {}", file.path);
+            }
+        }
+    }
+
+    #[test]
+    fn panicking_extraction_skips_symbols_without_aborting_the_run() {
+        let repo = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        fs::write(repo.path().join("good.rs"), "fn healthy() {}\n").unwrap();
+        fs::write(repo.path().join("bad.rs"), "fn cursed() {}\n").unwrap();
+
+        let config = IndexerConfig::new(
+            repo.path().to_path_buf(),
+            "acme".to_string(),
+            None,
+            "abc123".to_string(),
+            output.path().to_path_buf(),
+            None,
+        )
+        .with_hook(Arc::new(PanicOnPath("bad.rs")));
+        let artifacts = Indexer::new(config).run().unwrap();
+
+        assert_eq!(
+            artifacts.extraction_failures,
+            vec![ExtractionFailure {
+                file_path: "bad.rs".to_string(),
+                message: "malformed syntax tree in bad.rs".to_string(),
+            }]
+        );
+        assert_eq!(artifacts.summary().extraction_failures, 1);
+        assert_eq!(artifacts.file_pointer_count(), 2);
+        let symbols: HashSet<String> = artifacts
+            .symbol_records_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        assert!(symbols.contains("healthy"));
+        assert!(!symbols.contains("cursed"));
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
+use super::{ExtractedReference, Extraction, line_column};
 
 pub fn extract(source: &str) -> Extraction {
     let mut parser = Parser::new();
@@ -351,12 +351,16 @@ fn record_definition_node(
         let name = raw.trim();
         if !name.is_empty() {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name: name.to_string(),
                 kind: Some(kind.to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
             defined_nodes.insert(node.id());
             return Some(name.to_string());
@@ -380,12 +384,16 @@ fn record_reference_node(
         let name = raw.trim();
         if !name.is_empty() {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name: name.to_string(),
                 kind: Some("reference".to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
         }
     }
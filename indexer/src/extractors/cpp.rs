@@ -1,9 +1,11 @@
 use std::collections::HashSet;
 use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
+use super::{ExtractedReference, Extraction, apply_fallback_namespace, line_column};
 
-pub fn extract(source: &str) -> Extraction {
+/// Extracts references from a C++ file. `namespace_hint` is a path-derived
+/// namespace used only for references outside a `namespace` block.
+pub fn extract(source: &str, namespace_hint: Option<&str>) -> Extraction {
     let mut parser = Parser::new();
     parser
         .set_language(&tree_sitter_cpp::LANGUAGE.into())
@@ -17,6 +19,7 @@ pub fn extract(source: &str) -> Extraction {
     let mut references = Vec::new();
     let source_bytes = source.as_bytes();
     collect_references(&tree.root_node(), source_bytes, &mut references);
+    apply_fallback_namespace(&mut references, namespace_hint);
 
     references.into()
 }
@@ -370,12 +373,16 @@ fn record_definition_node(
     if let Ok(raw) = node.utf8_text(source) {
         if let Some(name) = normalize_cpp_name(raw) {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name: name.clone(),
                 kind: Some(kind.to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
             defined_nodes.insert(node.id());
             return Some(name);
@@ -444,12 +451,16 @@ fn record_reference_node(
     if let Ok(raw) = node.utf8_text(source) {
         if let Some(name) = normalize_cpp_name(raw) {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name,
                 kind: Some("reference".to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
         }
     }
@@ -516,7 +527,7 @@ mod tests {
             }
         "#;
 
-        let extraction = extract(source);
+        let extraction = extract(source, None);
         let references = extraction.references;
         let (definitions, references_map) = bucket_kinds(&references);
 
@@ -594,7 +605,7 @@ mod tests {
             }
         "#;
 
-        let extraction = extract(source);
+        let extraction = extract(source, None);
 
         for reference in &extraction.references {
             assert!(
@@ -619,7 +630,7 @@ mod tests {
             void Foo<T>::bar() {}
         "#;
 
-        let extraction = extract(source);
+        let extraction = extract(source, None);
         let (definitions, _references) = bucket_kinds(&extraction.references);
 
         for (name, _namespace) in definitions.keys() {
@@ -659,7 +670,7 @@ mod tests {
             }
         "#;
 
-        let extraction = extract(source);
+        let extraction = extract(source, None);
 
         for reference in &extraction.references {
             assert!(
@@ -719,7 +730,7 @@ mod tests {
             >;
         "#;
 
-        let extraction = extract(source);
+        let extraction = extract(source, None);
 
         for reference in &extraction.references {
             assert!(
@@ -731,4 +742,27 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn namespace_hint_only_fills_undeclared_namespaces() {
+        let source = r#"
+            namespace engine {
+            int start() { return 0; }
+            }
+
+            int run() { return engine::start(); }
+        "#;
+
+        let extraction = extract(source, Some("tools::runner"));
+        let namespace_of = |name: &str| {
+            extraction
+                .references
+                .iter()
+                .find(|r| r.name == name && r.kind.as_deref() == Some("definition"))
+                .and_then(|r| r.namespace.clone())
+        };
+
+        assert_eq!(namespace_of("start").as_deref(), Some("engine"));
+        assert_eq!(namespace_of("run").as_deref(), Some("tools::runner"));
+    }
 }
@@ -0,0 +1,404 @@
+use super::{ExtractedReference, Extraction, char_column};
+
+// Elm has no bundled tree-sitter grammar here, but its layout rules make the
+// top level easy to recover: every top-level declaration starts in column 1
+// and everything indented below it belongs to that declaration. We blank out
+// comments and string literals first so their contents can't look like code.
+pub fn extract(source: &str) -> Extraction {
+    let cleaned = blank_comments_and_strings(source);
+    let lines: Vec<&str> = cleaned.lines().collect();
+    // Blanking keeps byte offsets but not character counts, so columns are
+    // measured against the original lines.
+    let original: Vec<&str> = source.lines().collect();
+
+    let mut references = Vec::new();
+    let mut module_namespace: Option<String> = None;
+
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        if line.trim().is_empty() || line.starts_with(char::is_whitespace) {
+            idx += 1;
+            continue;
+        }
+
+        let mut end = idx + 1;
+        while end < lines.len()
+            && (lines[end].trim().is_empty() || lines[end].starts_with(char::is_whitespace))
+        {
+            end += 1;
+        }
+
+        collect_declaration(
+            &lines[idx..end],
+            &original[idx..end],
+            idx + 1,
+            &mut module_namespace,
+            &mut references,
+        );
+        idx = end;
+    }
+
+    references.into()
+}
+
+fn collect_declaration(
+    lines: &[&str],
+    original: &[&str],
+    first_line: usize,
+    module_namespace: &mut Option<String>,
+    references: &mut Vec<ExtractedReference>,
+) {
+    let header = lines[0];
+    let Some((_, keyword)) = next_word(header, 0) else {
+        return;
+    };
+
+    match keyword {
+        "module" | "port" | "effect" => {
+            let mut offset = keyword.len();
+            if keyword != "module" {
+                match next_word(header, offset) {
+                    Some((start, "module")) => offset = start + "module".len(),
+                    Some((start, name)) if keyword == "port" => {
+                        push_reference(
+                            references,
+                            name,
+                            module_namespace,
+                            "definition",
+                            first_line,
+                            char_column(original[0].as_bytes(), start),
+                        );
+                        return;
+                    }
+                    _ => return,
+                }
+            }
+            if let Some((start, name)) = next_word(header, offset) {
+                push_reference(
+                    references,
+                    name,
+                    &None,
+                    "definition",
+                    first_line,
+                    char_column(original[0].as_bytes(), start),
+                );
+                *module_namespace = Some(name.to_string());
+            }
+        }
+        "import" => {
+            if let Some((start, name)) = next_word(header, keyword.len()) {
+                push_reference(
+                    references,
+                    name,
+                    &None,
+                    "reference",
+                    first_line,
+                    char_column(original[0].as_bytes(), start),
+                );
+            }
+        }
+        "type" => {
+            let Some((start, name)) = next_word(header, keyword.len()) else {
+                return;
+            };
+            if name == "alias" {
+                if let Some((alias_start, alias)) = next_word(header, start + name.len()) {
+                    push_reference(
+                        references,
+                        alias,
+                        module_namespace,
+                        "definition",
+                        first_line,
+                        char_column(original[0].as_bytes(), alias_start),
+                    );
+                }
+                return;
+            }
+            push_reference(
+                references,
+                name,
+                module_namespace,
+                "definition",
+                first_line,
+                char_column(original[0].as_bytes(), start),
+            );
+            collect_variants(lines, original, first_line, module_namespace, references);
+        }
+        "infix" => {}
+        name if name.starts_with(|ch: char| ch.is_ascii_lowercase() || ch == '_') => {
+            let rest = header[name.len()..].trim_start();
+            let is_annotation = rest.starts_with(':') && !rest.starts_with("::");
+            if !is_annotation {
+                push_reference(
+                    references,
+                    name,
+                    module_namespace,
+                    "definition",
+                    first_line,
+                    1,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_variants(
+    lines: &[&str],
+    original: &[&str],
+    first_line: usize,
+    module_namespace: &Option<String>,
+    references: &mut Vec<ExtractedReference>,
+) {
+    let mut depth = 0i32;
+    let mut after_equals = false;
+    let mut expect_constructor = false;
+
+    for (line_offset, line) in lines.iter().enumerate() {
+        let mut pos = 0;
+        while pos < line.len() {
+            let ch = line.as_bytes()[pos];
+            match ch {
+                b'(' | b'{' | b'[' => depth += 1,
+                b')' | b'}' | b']' => depth -= 1,
+                b'=' if depth == 0 && !after_equals => {
+                    after_equals = true;
+                    expect_constructor = true;
+                }
+                b'|' if depth == 0 && after_equals => expect_constructor = true,
+                _ if expect_constructor && depth == 0 && ch.is_ascii_uppercase() => {
+                    if let Some((start, name)) = next_word(line, pos) {
+                        push_reference(
+                            references,
+                            name,
+                            module_namespace,
+                            "definition",
+                            first_line + line_offset,
+                            char_column(original[line_offset].as_bytes(), start),
+                        );
+                        pos = start + name.len();
+                        expect_constructor = false;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            pos += 1;
+        }
+    }
+}
+
+fn push_reference(
+    references: &mut Vec<ExtractedReference>,
+    name: &str,
+    namespace: &Option<String>,
+    kind: &str,
+    line: usize,
+    column: usize,
+) {
+    references.push(ExtractedReference {
+        name: name.to_string(),
+        kind: Some(kind.to_string()),
+        namespace: namespace.clone(),
+        line,
+        column,
+        end_line: line,
+        end_column: column + name.chars().count(),
+        signature: None,
+    });
+}
+
+/// Returns the next run of identifier characters (including `.` so that
+/// qualified module names stay whole) at or after `from`.
+fn next_word(line: &str, from: usize) -> Option<(usize, &str)> {
+    let bytes = line.as_bytes();
+    let mut start = from;
+    while start < bytes.len() && bytes[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    let mut end = start;
+    while end < bytes.len() && is_word_byte(bytes[end]) {
+        end += 1;
+    }
+    (end > start).then(|| (start, &line[start..end]))
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'.'
+}
+
+/// Replaces comments, string literals and char literals with spaces while
+/// keeping newlines (and therefore line numbers and byte offsets) intact.
+fn blank_comments_and_strings(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut i = 0;
+
+    let blank = |out: &mut Vec<u8>, from: usize, to: usize| {
+        for byte in &mut out[from..to] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    };
+
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"{-") {
+            let start = i;
+            let mut depth = 0;
+            while i < bytes.len() {
+                if bytes[i..].starts_with(b"{-") {
+                    depth += 1;
+                    i += 2;
+                } else if bytes[i..].starts_with(b"-}") {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            blank(&mut out, start, i.min(bytes.len()));
+        } else if bytes[i..].starts_with(b"--") {
+            let start = i;
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            blank(&mut out, start, i);
+        } else if bytes[i..].starts_with(b"\"\"\"") {
+            let start = i;
+            i += 3;
+            while i < bytes.len() && !bytes[i..].starts_with(b"\"\"\"") {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i = (i + 3).min(bytes.len());
+            blank(&mut out, start, i);
+        } else if bytes[i] == b'"' || bytes[i] == b'\'' {
+            let quote = bytes[i];
+            let start = i;
+            i += 1;
+            while i < bytes.len() && bytes[i] != quote && bytes[i] != b'\n' {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(bytes.len());
+            blank(&mut out, start, i);
+        } else {
+            i += 1;
+        }
+    }
+
+    String::from_utf8(out).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn definitions(extraction: &Extraction) -> HashSet<(String, Option<String>)> {
+        extraction
+            .references
+            .iter()
+            .filter(|r| r.kind.as_deref() == Some("definition"))
+            .map(|r| (r.name.clone(), r.namespace.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn extracts_elm_top_level_definitions() {
+        let source = r#"port module Acme.Widgets exposing (Model, Msg(..), update, view)
+
+import Html exposing (Html, div, text)
+import Json.Decode as Decode
+
+{-| The widget model.
+
+fake = 1
+-}
+type alias Model =
+    { count : Int
+    , label : String
+    }
+
+
+type Msg
+    = Increment
+    | Decrement Int
+    | Rename { model | label : String }
+
+
+port sendCount : Int -> Cmd msg
+
+
+-- helper = 2
+update : Msg -> Model -> Model
+update msg model =
+    case msg of
+        Increment ->
+            { model | count = model.count + 1 }
+
+        _ ->
+            model
+
+
+greeting =
+    """
+notAValue = 3
+"""
+"#;
+
+        let extraction = extract(source);
+        let defs = definitions(&extraction);
+        let ns = Some("Acme.Widgets".to_string());
+
+        let expected = HashSet::from([
+            ("Acme.Widgets".to_string(), None),
+            ("Model".to_string(), ns.clone()),
+            ("Msg".to_string(), ns.clone()),
+            ("Increment".to_string(), ns.clone()),
+            ("Decrement".to_string(), ns.clone()),
+            ("Rename".to_string(), ns.clone()),
+            ("sendCount".to_string(), ns.clone()),
+            ("update".to_string(), ns.clone()),
+            ("greeting".to_string(), ns.clone()),
+        ]);
+        assert_eq!(defs, expected);
+
+        let update = extraction
+            .references
+            .iter()
+            .find(|r| r.name == "update")
+            .expect("update definition");
+        assert_eq!((update.line, update.column), (27, 1));
+        assert_eq!((update.end_line, update.end_column), (27, 7));
+    }
+
+    #[test]
+    fn records_elm_imports_as_references() {
+        let source = r#"module Main exposing (main)
+
+import Browser
+import Json.Decode as Decode exposing (Decoder)
+
+main =
+    Browser.sandbox { init = 0, update = \_ m -> m, view = \_ -> text "" }
+"#;
+
+        let extraction = extract(source);
+        let imports: HashSet<_> = extraction
+            .references
+            .iter()
+            .filter(|r| r.kind.as_deref() == Some("reference"))
+            .map(|r| (r.name.as_str(), r.line, r.column))
+            .collect();
+
+        assert_eq!(
+            imports,
+            HashSet::from([("Browser", 3, 8), ("Json.Decode", 4, 8)])
+        );
+        assert!(definitions(&extraction).contains(&("main".to_string(), Some("Main".to_string()))));
+    }
+}
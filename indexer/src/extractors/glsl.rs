@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
+use super::{ExtractedReference, Extraction, line_column};
 
 pub fn extract(source: &str) -> Extraction {
     let mut parser = Parser::new();
@@ -244,12 +244,16 @@ fn record_definition_node(
     if let Ok(raw) = node.utf8_text(source) {
         if let Some(name) = sanitize_identifier(raw) {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name: name.clone(),
                 kind: Some(kind.to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
             defined_nodes.insert(node.id());
             return Some(name);
@@ -271,12 +275,16 @@ fn record_reference_node(
     if let Ok(raw) = node.utf8_text(source) {
         if let Some(name) = sanitize_identifier(raw) {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name,
                 kind: Some("reference".to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
         }
     }
@@ -1,9 +1,14 @@
 use std::collections::HashSet;
 use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
-
-pub fn extract(source: &str) -> Extraction {
+use super::{
+    ExtractedReference, Extraction, apply_fallback_namespace, declaration_signature, line_column,
+};
+
+/// Extracts references from a Go file. `namespace_hint` is a path-derived
+/// namespace for references the `package` clause does not scope, such as
+/// the package name itself.
+pub fn extract(source: &str, namespace_hint: Option<&str>) -> Extraction {
     let mut parser = Parser::new();
     parser
         .set_language(&tree_sitter_go::LANGUAGE.into())
@@ -17,6 +22,7 @@ pub fn extract(source: &str) -> Extraction {
     let mut references = Vec::new();
     let source_bytes = source.as_bytes();
     collect_references(&tree.root_node(), source_bytes, &mut references);
+    apply_fallback_namespace(&mut references, namespace_hint);
 
     references.into()
 }
@@ -72,6 +78,9 @@ fn collect_references(root: &Node, source: &[u8], references: &mut Vec<Extracted
                         "definition",
                         &mut defined_nodes,
                     ) {
+                        if let Some(definition) = references.last_mut() {
+                            definition.signature = declaration_signature(&node, source);
+                        }
                         next_namespace = push_namespace(&namespace_stack, &name);
                     }
                 }
@@ -107,6 +116,9 @@ fn collect_references(root: &Node, source: &[u8], references: &mut Vec<Extracted
                         "definition",
                         &mut defined_nodes,
                     ) {
+                        if let Some(definition) = references.last_mut() {
+                            definition.signature = declaration_signature(&node, source);
+                        }
                         method_namespace = push_namespace(&method_namespace, &name);
                     }
                 }
@@ -272,12 +284,16 @@ fn record_definition_node(
     if let Ok(raw) = node.utf8_text(source) {
         if let Some(name) = sanitize_identifier(raw) {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name: name.clone(),
                 kind: Some(kind.to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
             defined_nodes.insert(node.id());
             return Some(name);
@@ -300,12 +316,16 @@ fn record_reference_node(
     if let Ok(raw) = node.utf8_text(source) {
         if let Some(name) = sanitize_identifier(raw) {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name,
                 kind: Some("reference".to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
         }
     }
@@ -419,7 +439,7 @@ mod tests {
             }
         "#;
 
-        let extraction = extract(source);
+        let extraction = extract(source, None);
         let references = extraction.references;
         let (definitions, references_map) = collect_kinds(&references);
 
@@ -473,4 +493,38 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn records_function_and_method_signatures() {
+        let source = r#"package demo
+
+func Add(a, b int) int {
+	return a + b
+}
+
+type Server struct{}
+
+func (s *Server) Start(ctx context.Context) error {
+	return nil
+}
+"#;
+
+        let extraction = extract(source, None);
+        let signature = |name: &str| {
+            extraction
+                .references
+                .iter()
+                .find(|r| r.name == name && r.kind.as_deref() == Some("definition"))
+                .unwrap_or_else(|| panic!("missing definition for {name}"))
+                .signature
+                .clone()
+        };
+
+        assert_eq!(signature("Add").as_deref(), Some("func Add(a, b int) int"));
+        assert_eq!(
+            signature("Start").as_deref(),
+            Some("func (s *Server) Start(ctx context.Context) error")
+        );
+        assert_eq!(signature("Server"), None);
+    }
 }
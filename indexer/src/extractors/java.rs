@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
+use super::{ExtractedReference, Extraction, line_column};
 
 pub fn extract(source: &str) -> Extraction {
     let mut parser = Parser::new();
@@ -58,15 +58,13 @@ fn collect_references(
                 });
                 if let Some(name_node) = name_node {
                     if let Ok(name_text) = name_node.utf8_text(source) {
-                        record_definition_node(
-                            &name_node,
-                            source,
-                            references,
-                            namespace_stack,
-                            "definition",
-                            defined_nodes,
-                        );
-                        base_namespace.extend(name_text.split('.').map(|s| s.to_string()));
+                        let segments: Vec<String> = name_text
+                            .split('.')
+                            .map(|segment| segment.trim().to_string())
+                            .collect();
+                        record_package_node(&name_node, source, &segments, references);
+                        defined_nodes.insert(name_node.id());
+                        base_namespace.extend(segments);
                     }
                 }
             }
@@ -201,7 +199,7 @@ fn collect_references(
                 );
             }
         }
-        "identifier" => {
+        "identifier" | "type_identifier" => {
             record_reference_node(node, source, references, namespace_stack, defined_nodes);
         }
         _ => {}
@@ -245,14 +243,38 @@ fn push_namespace(namespace_stack: &[String], segment: &str) -> Vec<String> {
     next
 }
 
-fn namespace_from_stack(namespace_stack: &[String]) -> Option<String> {
+/// Shared with the Kotlin extractor so both JVM languages agree on namespace
+/// strings: `package com.acme` becomes `com::acme`.
+pub(super) fn namespace_from_stack(namespace_stack: &[String]) -> Option<String> {
     if namespace_stack.is_empty() {
         None
     } else {
-        Some(namespace_stack.join("."))
+        Some(namespace_stack.join("::"))
     }
 }
 
+/// Records a package declaration under the same `::`-joined name its members
+/// use as their namespace.
+fn record_package_node(
+    node: &Node,
+    source: &[u8],
+    segments: &[String],
+    references: &mut Vec<ExtractedReference>,
+) {
+    let pos = node.start_position();
+    let end = node.end_position();
+    references.push(ExtractedReference {
+        name: segments.join("::"),
+        kind: Some("definition".to_string()),
+        namespace: None,
+        line: pos.row + 1,
+        column: line_column(source, node.start_byte(), pos.column),
+        end_line: end.row + 1,
+        end_column: line_column(source, node.end_byte(), end.column),
+        signature: None,
+    });
+}
+
 fn record_definition_node(
     node: &Node,
     source: &[u8],
@@ -269,12 +291,16 @@ fn record_definition_node(
         let name = raw.trim();
         if !name.is_empty() {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name: name.to_string(),
                 kind: Some(kind.to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
             defined_nodes.insert(node.id());
             return Some(name.to_string());
@@ -298,12 +324,16 @@ fn record_reference_node(
         let name = raw.trim();
         if !name.is_empty() {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name: name.to_string(),
                 kind: Some("reference".to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
         }
     }
@@ -378,67 +408,67 @@ mod tests {
         let (definitions, references_map) = bucket_kinds(&references);
 
         let expected_definitions = HashSet::from([
-            ("com.example.demo".to_string(), None),
-            ("Widget".to_string(), Some("com.example.demo".to_string())),
+            ("com::example::demo".to_string(), None),
+            ("Widget".to_string(), Some("com::example::demo".to_string())),
             (
                 "counter".to_string(),
-                Some("com.example.demo.Widget".to_string()),
+                Some("com::example::demo::Widget".to_string()),
             ),
             (
                 "value".to_string(),
-                Some("com.example.demo.Widget".to_string()),
+                Some("com::example::demo::Widget".to_string()),
             ),
             (
                 "Widget".to_string(),
-                Some("com.example.demo.Widget".to_string()),
+                Some("com::example::demo::Widget".to_string()),
             ),
             (
                 "value".to_string(),
-                Some("com.example.demo.Widget.Widget".to_string()),
+                Some("com::example::demo::Widget::Widget".to_string()),
             ),
             (
                 "compute".to_string(),
-                Some("com.example.demo.Widget".to_string()),
+                Some("com::example::demo::Widget".to_string()),
             ),
             (
                 "delta".to_string(),
-                Some("com.example.demo.Widget.compute".to_string()),
+                Some("com::example::demo::Widget::compute".to_string()),
             ),
             (
                 "local".to_string(),
-                Some("com.example.demo.Widget.compute".to_string()),
+                Some("com::example::demo::Widget::compute".to_string()),
             ),
             (
                 "consume".to_string(),
-                Some("com.example.demo.Widget".to_string()),
+                Some("com::example::demo::Widget".to_string()),
             ),
             (
                 "items".to_string(),
-                Some("com.example.demo.Widget.consume".to_string()),
+                Some("com::example::demo::Widget::consume".to_string()),
             ),
             (
                 "item".to_string(),
-                Some("com.example.demo.Widget.consume".to_string()),
+                Some("com::example::demo::Widget::consume".to_string()),
             ),
             (
                 "Mode".to_string(),
-                Some("com.example.demo.Widget".to_string()),
+                Some("com::example::demo::Widget".to_string()),
             ),
             (
                 "OFF".to_string(),
-                Some("com.example.demo.Widget.Mode".to_string()),
+                Some("com::example::demo::Widget::Mode".to_string()),
             ),
             (
                 "ON".to_string(),
-                Some("com.example.demo.Widget.Mode".to_string()),
+                Some("com::example::demo::Widget::Mode".to_string()),
             ),
             (
                 "RecordWidget".to_string(),
-                Some("com.example.demo".to_string()),
+                Some("com::example::demo".to_string()),
             ),
             (
                 "value".to_string(),
-                Some("com.example.demo.RecordWidget".to_string()),
+                Some("com::example::demo::RecordWidget".to_string()),
             ),
         ]);
 
@@ -453,35 +483,35 @@ mod tests {
         let expected_references = HashSet::from([
             (
                 "value".to_string(),
-                Some("com.example.demo.Widget.compute".to_string()),
+                Some("com::example::demo::Widget::compute".to_string()),
             ),
             (
                 "counter".to_string(),
-                Some("com.example.demo.Widget.compute".to_string()),
+                Some("com::example::demo::Widget::compute".to_string()),
             ),
             (
                 "delta".to_string(),
-                Some("com.example.demo.Widget.compute".to_string()),
+                Some("com::example::demo::Widget::compute".to_string()),
             ),
             (
                 "System".to_string(),
-                Some("com.example.demo.Widget.consume".to_string()),
+                Some("com::example::demo::Widget::consume".to_string()),
             ),
             (
                 "out".to_string(),
-                Some("com.example.demo.Widget.consume".to_string()),
+                Some("com::example::demo::Widget::consume".to_string()),
             ),
             (
                 "println".to_string(),
-                Some("com.example.demo.Widget.consume".to_string()),
+                Some("com::example::demo::Widget::consume".to_string()),
             ),
             (
                 "items".to_string(),
-                Some("com.example.demo.Widget.consume".to_string()),
+                Some("com::example::demo::Widget::consume".to_string()),
             ),
             (
                 "item".to_string(),
-                Some("com.example.demo.Widget.consume".to_string()),
+                Some("com::example::demo::Widget::consume".to_string()),
             ),
         ]);
 
@@ -0,0 +1,820 @@
+use super::java::namespace_from_stack;
+use super::{ExtractedReference, Extraction, char_column};
+
+// There is no Kotlin grammar bundled here, so this walks a flat token stream
+// instead. Declarations are recognised by their keyword, braces open and close
+// scopes, and every other identifier is recorded as a reference. Namespaces
+// are built exactly like the Java extractor's so classes from the same package
+// line up across both languages.
+pub fn extract(source: &str) -> Extraction {
+    let tokens = tokenize(source);
+    let mut walker = Walker {
+        tokens: &tokens,
+        namespace: Vec::new(),
+        scopes: Vec::new(),
+        pending: None,
+        params: None,
+        paren_depth: 0,
+        references: Vec::new(),
+    };
+    walker.run();
+    walker.references.into()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    Punct,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl Token<'_> {
+    fn is(&self, text: &str) -> bool {
+        self.text == text
+    }
+
+    fn is_word(&self) -> bool {
+        self.kind == TokenKind::Word
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "break",
+    "class",
+    "continue",
+    "do",
+    "else",
+    "false",
+    "for",
+    "fun",
+    "if",
+    "import",
+    "in",
+    "interface",
+    "is",
+    "null",
+    "object",
+    "package",
+    "return",
+    "super",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "catch",
+    "finally",
+    "typealias",
+    "val",
+    "var",
+    "when",
+    "while",
+];
+
+// Soft keywords only act as keywords when another word follows them, so
+// `data class Foo` is a declaration while `data.size` is a plain reference.
+const MODIFIERS: &[&str] = &[
+    "abstract",
+    "actual",
+    "annotation",
+    "by",
+    "companion",
+    "const",
+    "crossinline",
+    "data",
+    "enum",
+    "expect",
+    "external",
+    "final",
+    "infix",
+    "inline",
+    "inner",
+    "internal",
+    "lateinit",
+    "noinline",
+    "open",
+    "operator",
+    "out",
+    "override",
+    "private",
+    "protected",
+    "public",
+    "reified",
+    "sealed",
+    "suspend",
+    "tailrec",
+    "value",
+    "vararg",
+];
+
+/// A declaration whose body (if any) has not been opened yet.
+#[derive(Debug)]
+struct Pending {
+    name: String,
+    is_enum: bool,
+    paren_depth: usize,
+}
+
+#[derive(Debug)]
+struct Scope {
+    named: bool,
+    is_enum: bool,
+    /// Enum entries end at the first `;` in the enum body.
+    entries_open: bool,
+}
+
+struct Walker<'t, 'a> {
+    tokens: &'t [Token<'a>],
+    namespace: Vec<String>,
+    scopes: Vec<Scope>,
+    pending: Option<Pending>,
+    /// Namespace and paren depth of the parameter list being read, if any.
+    params: Option<(Vec<String>, usize)>,
+    paren_depth: usize,
+    references: Vec<ExtractedReference>,
+}
+
+impl<'a> Walker<'_, 'a> {
+    fn run(&mut self) {
+        let mut idx = 0;
+        while idx < self.tokens.len() {
+            idx = self.step(idx);
+        }
+    }
+
+    fn peek(&self, idx: usize) -> Option<Token<'a>> {
+        self.tokens.get(idx).copied()
+    }
+
+    fn step(&mut self, idx: usize) -> usize {
+        let token = self.tokens[idx];
+        if token.kind == TokenKind::Punct {
+            self.punct(idx);
+            return idx + 1;
+        }
+
+        let next = self.peek(idx + 1);
+        let after_double_colon = idx > 0 && self.tokens[idx - 1].is("::");
+        if MODIFIERS.contains(&token.text) && next.is_some_and(|t| t.is_word()) {
+            return idx + 1;
+        }
+        if !KEYWORDS.contains(&token.text) {
+            self.word(idx);
+            return idx + 1;
+        }
+        if after_double_colon {
+            // `Foo::class` is a class literal, not a declaration.
+            return idx + 1;
+        }
+
+        match token.text {
+            "package" => self.package(idx + 1),
+            "class" | "interface" | "object" => self.type_declaration(idx),
+            "fun" => self.function(idx + 1),
+            "val" | "var" => self.property(idx + 1),
+            "typealias" => {
+                if let Some(name) = next.filter(|t| t.is_word()) {
+                    self.define(&name, None);
+                    return idx + 2;
+                }
+                idx + 1
+            }
+            "for" | "catch" => self.loop_or_catch_variables(idx + 1),
+            _ => idx + 1,
+        }
+    }
+
+    fn punct(&mut self, idx: usize) {
+        match self.tokens[idx].text {
+            "{" => {
+                let pending = self.pending.take();
+                let named = pending.is_some();
+                let is_enum = pending.as_ref().is_some_and(|p| p.is_enum);
+                if let Some(pending) = pending {
+                    self.namespace.push(pending.name);
+                }
+                self.params = None;
+                self.scopes.push(Scope {
+                    named,
+                    is_enum,
+                    entries_open: is_enum,
+                });
+            }
+            "}" => {
+                self.pending = None;
+                if let Some(scope) = self.scopes.pop()
+                    && scope.named
+                {
+                    self.namespace.pop();
+                }
+            }
+            // Expression bodies (`fun f() = ...`) never open a scope.
+            "=" if self
+                .pending
+                .as_ref()
+                .is_some_and(|p| p.paren_depth == self.paren_depth) =>
+            {
+                self.pending = None;
+            }
+            ";" => {
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.entries_open = false;
+                }
+            }
+            "(" => {
+                self.paren_depth += 1;
+                if let Some(pending) = &self.pending
+                    && pending.paren_depth + 1 == self.paren_depth
+                    && self.params.is_none()
+                {
+                    let mut namespace = self.namespace.clone();
+                    namespace.push(pending.name.clone());
+                    self.params = Some((namespace, self.paren_depth));
+                }
+            }
+            ")" => {
+                if self
+                    .params
+                    .as_ref()
+                    .is_some_and(|(_, depth)| *depth == self.paren_depth)
+                {
+                    self.params = None;
+                }
+                self.paren_depth = self.paren_depth.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+
+    fn word(&mut self, idx: usize) {
+        let token = self.tokens[idx];
+        let next = self.peek(idx + 1);
+        let previous = idx.checked_sub(1).map(|i| self.tokens[i]);
+
+        if let Some((namespace, depth)) = &self.params
+            && *depth == self.paren_depth
+            && next.is_some_and(|t| t.is(":"))
+        {
+            let namespace = namespace.clone();
+            self.define(&token, Some(&namespace));
+            return;
+        }
+
+        let in_enum_entries = self.paren_depth == 0
+            && self
+                .scopes
+                .last()
+                .is_some_and(|scope| scope.is_enum && scope.entries_open);
+        if in_enum_entries && previous.is_some_and(|t| t.is("{") || t.is(",")) {
+            self.define(&token, None);
+            return;
+        }
+
+        if token.is("constructor") && next.is_some_and(|t| t.is("(")) {
+            // Mirror Java, where a constructor is named after its class.
+            if let Some(class_name) = self.namespace.last().cloned() {
+                self.push_reference(&token, &class_name, "definition", None);
+                self.set_pending(class_name, false);
+            }
+            return;
+        }
+
+        self.push_reference(&token, token.text, "reference", None);
+    }
+
+    fn package(&mut self, mut idx: usize) -> usize {
+        let Some(first) = self.peek(idx).filter(|t| t.is_word()) else {
+            return idx;
+        };
+        let mut segments = Vec::new();
+        while let Some(token) = self.peek(idx).filter(|t| t.is_word()) {
+            segments.push(token.text.to_string());
+            idx += 1;
+            if !self.peek(idx).is_some_and(|t| t.is(".")) {
+                break;
+            }
+            idx += 1;
+        }
+        let empty: [String; 0] = [];
+        self.push_reference(&first, &segments.join("::"), "definition", Some(&empty));
+        self.namespace = segments;
+        idx
+    }
+
+    fn type_declaration(&mut self, idx: usize) -> usize {
+        let keyword = self.tokens[idx];
+        let previous = idx.checked_sub(1).map(|i| self.tokens[i]);
+        let is_enum = keyword.is("class") && previous.is_some_and(|t| t.is("enum"));
+        let is_companion = keyword.is("object") && previous.is_some_and(|t| t.is("companion"));
+
+        match self.peek(idx + 1) {
+            Some(name) if name.is_word() && !KEYWORDS.contains(&name.text) => {
+                self.define(&name, None);
+                self.set_pending(name.text.to_string(), is_enum);
+                idx + 2
+            }
+            _ if is_companion => {
+                self.set_pending("Companion".to_string(), false);
+                idx + 1
+            }
+            _ => {
+                // Anonymous `object : Foo {}` expressions get no scope of their own.
+                self.pending = None;
+                idx + 1
+            }
+        }
+    }
+
+    fn function(&mut self, idx: usize) -> usize {
+        // The name is the last word before the parameter list, which skips
+        // type parameters and extension receivers like `fun <T> List<T>.foo()`.
+        let mut end = idx;
+        while let Some(token) = self.peek(end) {
+            if token.is("(") || token.is("{") || token.is("=") {
+                break;
+            }
+            end += 1;
+        }
+        if !self.peek(end).is_some_and(|t| t.is("(")) {
+            return idx;
+        }
+        let Some(name_idx) = (idx..end).rev().find(|&i| self.tokens[i].is_word()) else {
+            return idx;
+        };
+
+        for i in idx..name_idx {
+            let token = self.tokens[i];
+            if token.is_word() && !KEYWORDS.contains(&token.text) {
+                self.push_reference(&token, token.text, "reference", None);
+            }
+        }
+        let name = self.tokens[name_idx];
+        self.define(&name, None);
+        self.set_pending(name.text.to_string(), false);
+        name_idx + 1
+    }
+
+    fn property(&mut self, idx: usize) -> usize {
+        if self
+            .params
+            .as_ref()
+            .is_some_and(|(_, depth)| *depth == self.paren_depth)
+        {
+            // `val` in a primary constructor: the parameter rule records it.
+            return idx;
+        }
+        match self.peek(idx) {
+            Some(open) if open.is("(") => self.destructuring(idx),
+            Some(name) if name.is_word() => {
+                // Extension properties: `val String.lastChar`.
+                if self.peek(idx + 1).is_some_and(|t| t.is("."))
+                    && let Some(member) = self.peek(idx + 2).filter(|t| t.is_word())
+                {
+                    self.push_reference(&name, name.text, "reference", None);
+                    self.define(&member, None);
+                    return idx + 3;
+                }
+                self.define(&name, None);
+                idx + 1
+            }
+            _ => idx,
+        }
+    }
+
+    fn loop_or_catch_variables(&mut self, idx: usize) -> usize {
+        if !self.peek(idx).is_some_and(|t| t.is("(")) {
+            return idx;
+        }
+        self.paren_depth += 1;
+        let next = idx + 1;
+        match self.peek(next) {
+            Some(open) if open.is("(") => self.destructuring(next),
+            Some(name) if name.is_word() => {
+                self.define(&name, None);
+                next + 1
+            }
+            _ => next,
+        }
+    }
+
+    /// Records every name in `(a, b)` as a definition; `idx` points at `(`.
+    fn destructuring(&mut self, idx: usize) -> usize {
+        let mut cursor = idx + 1;
+        while let Some(token) = self.peek(cursor) {
+            cursor += 1;
+            if token.is(")") {
+                break;
+            }
+            if token.is_word() {
+                self.define(&token, None);
+            }
+        }
+        cursor
+    }
+
+    fn set_pending(&mut self, name: String, is_enum: bool) {
+        self.params = None;
+        self.pending = Some(Pending {
+            name,
+            is_enum,
+            paren_depth: self.paren_depth,
+        });
+    }
+
+    fn define(&mut self, token: &Token, namespace: Option<&[String]>) {
+        self.push_reference(token, token.text, "definition", namespace);
+    }
+
+    fn push_reference(
+        &mut self,
+        token: &Token,
+        name: &str,
+        kind: &str,
+        namespace: Option<&[String]>,
+    ) {
+        let namespace = namespace.unwrap_or(&self.namespace);
+        self.references.push(ExtractedReference {
+            name: name.to_string(),
+            kind: Some(kind.to_string()),
+            namespace: namespace_from_stack(namespace),
+            line: token.line,
+            column: token.column,
+            end_line: token.line,
+            end_column: token.column + token.text.chars().count(),
+            signature: None,
+        });
+    }
+}
+
+/// Splits Kotlin source into words and punctuation, skipping comments,
+/// string/char literals and numbers. Backticked names become plain words.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line = 1;
+    let mut line_start = 0;
+
+    let skip_to = |from: usize, to: usize, line: &mut usize, line_start: &mut usize| {
+        for (offset, byte) in bytes[from..to].iter().enumerate() {
+            if *byte == b'\n' {
+                *line += 1;
+                *line_start = from + offset + 1;
+            }
+        }
+        to
+    };
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let rest = &bytes[i..];
+        let end = if byte.is_ascii_whitespace() {
+            i + 1
+        } else if rest.starts_with(b"//") {
+            i + rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len())
+        } else if rest.starts_with(b"/*") {
+            let mut depth = 0;
+            let mut j = i;
+            while j < bytes.len() {
+                if bytes[j..].starts_with(b"/*") {
+                    depth += 1;
+                    j += 2;
+                } else if bytes[j..].starts_with(b"*/") {
+                    depth -= 1;
+                    j += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    j += 1;
+                }
+            }
+            j.min(bytes.len())
+        } else if rest.starts_with(b"\"\"\"") {
+            let close = rest[3..]
+                .windows(3)
+                .position(|w| w == b"\"\"\"")
+                .map_or(bytes.len(), |p| i + 3 + p + 3);
+            close.min(bytes.len())
+        } else if byte == b'"' || byte == b'\'' {
+            let mut j = i + 1;
+            while j < bytes.len() && bytes[j] != byte && bytes[j] != b'\n' {
+                j += if bytes[j] == b'\\' { 2 } else { 1 };
+            }
+            (j + 1).min(bytes.len())
+        } else if byte.is_ascii_digit() {
+            let mut j = i + 1;
+            while j < bytes.len() && (is_word_byte(bytes[j]) || bytes[j] == b'.') {
+                j += 1;
+            }
+            j
+        } else if byte == b'`' {
+            let close = rest[1..].iter().position(|b| *b == b'`' || *b == b'\n');
+            match close {
+                Some(p) if rest[1 + p] == b'`' && p > 0 => {
+                    tokens.push(Token {
+                        kind: TokenKind::Word,
+                        text: &source[i + 1..i + 1 + p],
+                        line,
+                        column: char_column(&bytes[line_start..], i + 1 - line_start),
+                    });
+                    i + p + 2
+                }
+                _ => i + 1,
+            }
+        } else if is_word_byte(byte) {
+            let mut j = i + 1;
+            while j < bytes.len() && is_word_byte(bytes[j]) {
+                j += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Word,
+                text: &source[i..j],
+                line,
+                column: char_column(&bytes[line_start..], i - line_start),
+            });
+            j
+        } else {
+            let len = if rest.starts_with(b"::") { 2 } else { 1 };
+            if let Some(text) = source.get(i..i + len) {
+                tokens.push(Token {
+                    kind: TokenKind::Punct,
+                    text,
+                    line,
+                    column: char_column(&bytes[line_start..], i - line_start),
+                });
+            }
+            i + len
+        };
+        i = skip_to(i, end, &mut line, &mut line_start);
+    }
+
+    tokens
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::fs;
+
+    use crate::config::IndexerConfig;
+    use crate::engine::Indexer;
+
+    fn definitions(extraction: &Extraction) -> HashSet<(String, Option<String>)> {
+        extraction
+            .references
+            .iter()
+            .filter(|r| r.kind.as_deref() == Some("definition"))
+            .map(|r| (r.name.clone(), r.namespace.clone()))
+            .collect()
+    }
+
+    fn ns(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn extracts_kotlin_declarations_with_jvm_namespaces() {
+        let source = r#"package com.example.demo
+
+import kotlin.math.max
+
+/* class NotReal { fun hidden() {} } */
+data class Widget(val value: Int, private var label: String = "fun fake()") {
+    private val cache = mutableMapOf<String, Int>()
+
+    constructor(label: String) : this(0, label)
+
+    fun compute(delta: Int): Int {
+        val local = max(delta, value)
+        for ((key, count) in cache) {
+            println("$key=$count")
+        }
+        return local
+    }
+
+    enum class Mode { OFF, ON; fun flip() = if (this == OFF) ON else OFF }
+
+    companion object {
+        fun create(): Widget = Widget(1, "x")
+    }
+}
+
+fun <T> List<T>.second(): T = this[1]
+
+val String.`last char`: Char get() = this[length - 1]
+"#;
+
+        let extraction = extract(source);
+        let defs = definitions(&extraction);
+
+        let expected = HashSet::from([
+            ("com::example::demo".to_string(), None),
+            ("Widget".to_string(), ns("com::example::demo")),
+            ("value".to_string(), ns("com::example::demo::Widget")),
+            ("label".to_string(), ns("com::example::demo::Widget")),
+            ("cache".to_string(), ns("com::example::demo::Widget")),
+            ("Widget".to_string(), ns("com::example::demo::Widget")),
+            (
+                "label".to_string(),
+                ns("com::example::demo::Widget::Widget"),
+            ),
+            ("compute".to_string(), ns("com::example::demo::Widget")),
+            (
+                "delta".to_string(),
+                ns("com::example::demo::Widget::compute"),
+            ),
+            (
+                "local".to_string(),
+                ns("com::example::demo::Widget::compute"),
+            ),
+            ("key".to_string(), ns("com::example::demo::Widget::compute")),
+            (
+                "count".to_string(),
+                ns("com::example::demo::Widget::compute"),
+            ),
+            ("Mode".to_string(), ns("com::example::demo::Widget")),
+            ("OFF".to_string(), ns("com::example::demo::Widget::Mode")),
+            ("ON".to_string(), ns("com::example::demo::Widget::Mode")),
+            ("flip".to_string(), ns("com::example::demo::Widget::Mode")),
+            (
+                "create".to_string(),
+                ns("com::example::demo::Widget::Companion"),
+            ),
+            ("second".to_string(), ns("com::example::demo")),
+            ("last char".to_string(), ns("com::example::demo")),
+        ]);
+        assert_eq!(defs, expected);
+
+        let compute = extraction
+            .references
+            .iter()
+            .find(|r| r.name == "compute")
+            .expect("compute definition");
+        assert_eq!((compute.line, compute.column), (11, 9));
+        assert_eq!((compute.end_line, compute.end_column), (11, 16));
+        assert!(
+            extraction
+                .references
+                .iter()
+                .all(|r| r.name != "NotReal" && r.name != "fake")
+        );
+    }
+
+    #[test]
+    fn extracts_objects_interfaces_companion_members_and_call_sites() {
+        let source = r#"package org.sample.app
+
+interface Greeter {
+    fun greet(name: String): String
+}
+
+object Registry {
+    val greeters = mutableListOf<Greeter>()
+}
+
+class Console : Greeter {
+    override fun greet(name: String): String = "hi $name"
+
+    companion object {
+        const val PREFIX = ">"
+        fun shared(): Console = Console()
+    }
+}
+
+val defaultConsole = Console.shared()
+
+fun main() {
+    Registry.greeters.add(defaultConsole)
+}
+"#;
+
+        let extraction = extract(source);
+        let expected = HashSet::from([
+            ("org::sample::app".to_string(), None),
+            ("Greeter".to_string(), ns("org::sample::app")),
+            ("greet".to_string(), ns("org::sample::app::Greeter")),
+            ("name".to_string(), ns("org::sample::app::Greeter::greet")),
+            ("Registry".to_string(), ns("org::sample::app")),
+            ("greeters".to_string(), ns("org::sample::app::Registry")),
+            ("Console".to_string(), ns("org::sample::app")),
+            ("greet".to_string(), ns("org::sample::app::Console")),
+            ("name".to_string(), ns("org::sample::app::Console::greet")),
+            (
+                "PREFIX".to_string(),
+                ns("org::sample::app::Console::Companion"),
+            ),
+            (
+                "shared".to_string(),
+                ns("org::sample::app::Console::Companion"),
+            ),
+            ("defaultConsole".to_string(), ns("org::sample::app")),
+            ("main".to_string(), ns("org::sample::app")),
+        ]);
+        assert_eq!(definitions(&extraction), expected);
+
+        let position = |name: &str, kind: &str| {
+            extraction
+                .references
+                .iter()
+                .filter(|r| r.name == name && r.kind.as_deref() == Some(kind))
+                .map(|r| (r.line, r.column, r.end_column))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(position("Registry", "definition"), vec![(7, 8, 16)]);
+        assert_eq!(position("PREFIX", "definition"), vec![(15, 19, 25)]);
+        assert_eq!(position("shared", "definition"), vec![(16, 13, 19)]);
+
+        // Call sites are plain references in the enclosing namespace.
+        assert_eq!(position("shared", "reference"), vec![(20, 30, 36)]);
+        assert_eq!(
+            position("Console", "reference"),
+            vec![(16, 23, 30), (16, 33, 40), (20, 22, 29)]
+        );
+        let add = extraction
+            .references
+            .iter()
+            .find(|r| r.name == "add")
+            .expect("add call site");
+        assert_eq!(add.kind.as_deref(), Some("reference"));
+        assert_eq!(add.namespace, ns("org::sample::app::main"));
+        assert_eq!((add.line, add.column), (23, 23));
+    }
+
+    #[test]
+    fn java_reference_resolves_to_kotlin_definition_in_same_package() {
+        let repo = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let package_dir = repo.path().join("src/main/java/com/acme/shop");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(
+            package_dir.join("Cart.kt"),
+            "package com.acme.shop\n\nclass Cart(val owner: String) {\n    fun total(): Int = 0\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            package_dir.join("Checkout.java"),
+            "package com.acme.shop;\n\npublic class Checkout {\n    int run() {\n        Cart cart = new Cart(\"ada\");\n        return cart.total();\n    }\n}\n",
+        )
+        .unwrap();
+
+        let config = IndexerConfig::new(
+            repo.path().to_path_buf(),
+            "acme".to_string(),
+            None,
+            "abc123".to_string(),
+            output.path().to_path_buf(),
+            None,
+        );
+        let artifacts = Indexer::new(config).run().unwrap();
+        let records = artifacts
+            .reference_records_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap();
+
+        let definition = records
+            .iter()
+            .find(|r| r.name == "Cart" && r.kind.as_deref() == Some("definition"))
+            .expect("Kotlin definition of Cart");
+        assert_eq!(definition.fully_qualified, "com::acme::shop::Cart");
+        assert_eq!(definition.namespace.as_deref(), Some("com::acme::shop"));
+        assert_eq!((definition.line, definition.column), (3, 7));
+
+        // `get_symbol_references` resolves a use nested under a definition's
+        // namespace to that definition; the postgres test
+        // `java_reference_resolves_to_kotlin_definition_in_same_package` looks
+        // these exact rows up.
+        let java_uses: Vec<_> = records
+            .iter()
+            .filter(|r| r.name == "Cart" && r.kind.as_deref() == Some("reference"))
+            .map(|r| (r.namespace.as_deref(), r.line, r.column))
+            .collect();
+        assert_eq!(
+            java_uses,
+            vec![
+                (Some("com::acme::shop::Checkout::run"), 5, 9),
+                (Some("com::acme::shop::Checkout::run"), 5, 25),
+            ]
+        );
+
+        let packages: HashSet<&str> = records
+            .iter()
+            .filter(|r| r.namespace.is_none() && r.kind.as_deref() == Some("definition"))
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(packages, HashSet::from(["com::acme::shop"]));
+    }
+}
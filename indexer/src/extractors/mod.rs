@@ -1,15 +1,18 @@
 mod c;
 mod cpp;
+mod elm;
 mod glsl;
 mod go;
 mod java;
 mod javascript;
+mod kotlin;
 mod lua;
 mod nix;
 mod objective_c;
 mod php;
 mod protobuf;
 mod python;
+mod ruby;
 mod rust;
 mod swift;
 mod typescript;
@@ -25,7 +28,68 @@ pub struct ExtractedReference {
     pub kind: Option<String>, // e.g., "definition", "reference", "declaration"
     pub namespace: Option<String>,
     pub line: usize,
+    /// 1-based, counted in Unicode scalar values (Rust `char`s) from the start
+    /// of the line; the backend and UI slice lines by `char`. Build it with
+    /// [`char_column`] or [`line_column`], never from a raw byte offset.
     pub column: usize,
+    pub end_line: usize,
+    /// Exclusive: one past the last character, in the same unit as `column`.
+    pub end_column: usize,
+    /// The declaration header of a definition, e.g. `fn foo(a: u32) -> bool`,
+    /// for extractors that capture one.
+    pub signature: Option<String>,
+}
+
+/// Converts a byte offset within `line` into the column unit of
+/// [`ExtractedReference::column`]. Offsets inside a multibyte character
+/// count that character as already started.
+pub(crate) fn char_column(line: &[u8], byte_offset: usize) -> usize {
+    let end = byte_offset.min(line.len());
+    1 + line[..end]
+        .iter()
+        .filter(|byte| (**byte & 0xC0) != 0x80)
+        .count()
+}
+
+/// Like [`char_column`] for a tree-sitter position: `start_byte` is the node's
+/// offset into `source` and `byte_column` its byte offset within the line.
+pub(crate) fn line_column(source: &[u8], start_byte: usize, byte_column: usize) -> usize {
+    let line_start = start_byte.saturating_sub(byte_column);
+    char_column(&source[line_start..], byte_column)
+}
+
+/// The header of a function-like declaration: its source up to the body, with
+/// whitespace runs collapsed, e.g. `pub fn foo(a: u32) -> bool`.
+pub(crate) fn declaration_signature(node: &tree_sitter::Node, source: &[u8]) -> Option<String> {
+    let end = node
+        .child_by_field_name("body")
+        .map_or(node.end_byte(), |body| body.start_byte());
+    let text = std::str::from_utf8(source.get(node.start_byte()..end)?).ok()?;
+    let collapsed = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(" )", ")");
+    let signature = collapsed.trim_end_matches(';').trim_end();
+    (!signature.is_empty()).then(|| signature.to_string())
+}
+
+/// Gives references the source left without a namespace the path-derived
+/// `namespace_hint`, for languages whose namespaces are declared in source.
+pub(crate) fn apply_fallback_namespace(
+    references: &mut [ExtractedReference],
+    namespace_hint: Option<&str>,
+) {
+    let Some(hint) = namespace_hint
+        .map(str::trim)
+        .filter(|hint| !hint.is_empty())
+    else {
+        return;
+    };
+    for reference in references.iter_mut().filter(|r| r.namespace.is_none()) {
+        reference.namespace = Some(hint.to_string());
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -47,15 +111,18 @@ pub trait LanguageIndexer {
 // Implement the trait for each language
 pub struct CIndexer;
 pub struct CppIndexer;
+pub struct ElmIndexer;
 pub struct GoIndexer;
 pub struct JavaIndexer;
 pub struct JavaScriptIndexer;
+pub struct KotlinIndexer;
 pub struct LuaIndexer;
 pub struct NixIndexer;
 pub struct ObjectiveCIndexer;
 pub struct PhpIndexer;
 pub struct ProtobufIndexer;
 pub struct PythonIndexer;
+pub struct RubyIndexer;
 pub struct RustIndexer;
 pub struct SwiftIndexer;
 pub struct TypeScriptIndexer;
@@ -68,14 +135,20 @@ impl LanguageIndexer for CIndexer {
 }
 
 impl LanguageIndexer for CppIndexer {
+    fn index(&self, source: &str, namespace_hint: Option<&str>) -> Extraction {
+        cpp::extract(source, namespace_hint)
+    }
+}
+
+impl LanguageIndexer for ElmIndexer {
     fn index(&self, source: &str, _namespace_hint: Option<&str>) -> Extraction {
-        cpp::extract(source)
+        elm::extract(source)
     }
 }
 
 impl LanguageIndexer for GoIndexer {
-    fn index(&self, source: &str, _namespace_hint: Option<&str>) -> Extraction {
-        go::extract(source)
+    fn index(&self, source: &str, namespace_hint: Option<&str>) -> Extraction {
+        go::extract(source, namespace_hint)
     }
 }
 
@@ -91,6 +164,12 @@ impl LanguageIndexer for JavaScriptIndexer {
     }
 }
 
+impl LanguageIndexer for KotlinIndexer {
+    fn index(&self, source: &str, _namespace_hint: Option<&str>) -> Extraction {
+        kotlin::extract(source)
+    }
+}
+
 impl LanguageIndexer for LuaIndexer {
     fn index(&self, source: &str, _namespace_hint: Option<&str>) -> Extraction {
         lua::extract(source)
@@ -133,9 +212,15 @@ impl LanguageIndexer for PythonIndexer {
     }
 }
 
-impl LanguageIndexer for RustIndexer {
+impl LanguageIndexer for RubyIndexer {
     fn index(&self, source: &str, _namespace_hint: Option<&str>) -> Extraction {
-        rust::extract(source)
+        ruby::extract(source)
+    }
+}
+
+impl LanguageIndexer for RustIndexer {
+    fn index(&self, source: &str, namespace_hint: Option<&str>) -> Extraction {
+        rust::extract(source, namespace_hint)
     }
 }
 
@@ -156,15 +241,18 @@ pub fn extract(language: &str, source: &str, namespace_hint: Option<&str>) -> Ex
     match language {
         "c" => CIndexer.index(source, namespace_hint),
         "c++" | "cpp" => CppIndexer.index(source, namespace_hint),
+        "elm" => ElmIndexer.index(source, namespace_hint),
         "go" => GoIndexer.index(source, namespace_hint),
         "js" | "javascript" => JavaScriptIndexer.index(source, namespace_hint),
         "java" | "jvm" => JavaIndexer.index(source, namespace_hint),
+        "kotlin" | "kt" => KotlinIndexer.index(source, namespace_hint),
         "lua" => LuaIndexer.index(source, namespace_hint),
         "nix" => NixIndexer.index(source, namespace_hint),
         "objc" | "objective-c" | "objectivec" => ObjectiveCIndexer.index(source, namespace_hint),
         "php" => PhpIndexer.index(source, namespace_hint),
         "proto" | "protobuf" => ProtobufIndexer.index(source, namespace_hint),
         "py" | "python" => PythonIndexer.index(source, namespace_hint),
+        "rb" | "ruby" => RubyIndexer.index(source, namespace_hint),
         "rust" => RustIndexer.index(source, namespace_hint),
         "swift" => SwiftIndexer.index(source, namespace_hint),
         "ts" | "typescript" => TypeScriptIndexer.index(source, namespace_hint),
@@ -172,3 +260,90 @@ pub fn extract(language: &str, source: &str, namespace_hint: Option<&str>) -> Ex
         _ => Extraction::default(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_column_counts_characters_not_bytes() {
+        let line = "let s = \"héllo\"; marker".as_bytes();
+        assert_eq!(char_column(line, 0), 1);
+        assert_eq!(char_column(line, line.len() - "marker".len()), 18);
+        // An offset inside a character counts that character as started.
+        assert_eq!(char_column("é".as_bytes(), 1), 2);
+    }
+
+    #[test]
+    fn columns_count_characters_across_extractors() {
+        let cases = [
+            (
+                "c",
+                "const char *g = \"héllo\"; int marker(void) { return 0; }\n",
+            ),
+            (
+                "cpp",
+                "const char *g = \"héllo\"; int marker() { return 0; }\n",
+            ),
+            (
+                "elm",
+                "module Main exposing (..)\n\ntype Shape = Circle {- ü -} | Marker\n",
+            ),
+            ("glsl", "/* ünïcode */ void marker() {}\n"),
+            ("go", "package p\n\nvar g = \"héllo\"; func marker() {}\n"),
+            (
+                "java",
+                "class A { String g = \"héllo\"; void marker() {} }\n",
+            ),
+            ("javascript", "const g = \"héllo\"; function marker() {}\n"),
+            ("kotlin", "val g = \"héllo\"; fun marker() {}\n"),
+            ("lua", "local g = \"héllo\"; function marker() end\n"),
+            ("nix", "{ g = \"héllo\"; marker = 1; }\n"),
+            ("objc", "/* ünïcode */ int marker(void) { return 0; }\n"),
+            ("php", "<?php $g = \"héllo\"; function marker() {}\n"),
+            (
+                "proto",
+                "syntax = \"proto3\";\n/* ünïcode */ message Marker {}\n",
+            ),
+            ("python", "g = \"héllo\"; marker = 1\n"),
+            ("ruby", "g = \"héllo\"; def marker; end\n"),
+            ("rust", "static G: &str = \"héllo\"; fn marker() {}\n"),
+            ("swift", "let g = \"héllo\"; func marker() {}\n"),
+            (
+                "typescript",
+                "const g: string = \"héllo\"; function marker() {}\n",
+            ),
+        ];
+
+        for (language, source) in cases {
+            let needle = if source.contains("Marker") {
+                "Marker"
+            } else {
+                "marker"
+            };
+            let (line_idx, line) = source
+                .lines()
+                .enumerate()
+                .find(|(_, line)| line.contains(needle))
+                .unwrap();
+            let expected = line[..line.find(needle).unwrap()].chars().count() + 1;
+
+            let extraction = extract(language, source, None);
+            let reference = extraction
+                .references
+                .iter()
+                .find(|reference| reference.name == needle)
+                .unwrap_or_else(|| panic!("{language}: no reference to {needle}"));
+            assert_eq!(
+                (reference.line, reference.column),
+                (line_idx + 1, expected),
+                "{language}"
+            );
+            assert_eq!(
+                (reference.end_line, reference.end_column),
+                (line_idx + 1, expected + needle.len()),
+                "{language}"
+            );
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
+use super::{ExtractedReference, Extraction, line_column};
 
 pub fn extract(source: &str) -> Extraction {
     let mut parser = Parser::new();
@@ -35,6 +35,7 @@ fn collect_references(
                     if !segments.is_empty() {
                         let name = segments.pop().unwrap();
                         let pos = attr_node.start_position();
+                        let end = attr_node.end_position();
                         let mut ns = namespace_stack.to_owned();
                         ns.extend(segments);
 
@@ -47,7 +48,10 @@ fn collect_references(
                                 Some(ns.join("."))
                             },
                             line: pos.row + 1,
-                            column: pos.column + 1,
+                            column: line_column(source, attr_node.start_byte(), pos.column),
+                            end_line: end.row + 1,
+                            end_column: line_column(source, attr_node.end_byte(), end.column),
+                            signature: None,
                         });
                         if let Some(expr_node) = node.child_by_field_name("expression") {
                             if expr_node.kind() == "attrset_expression"
@@ -64,6 +68,7 @@ fn collect_references(
             if let Some(name_node) = node.child_by_field_name("universal") {
                 if let Ok(name) = name_node.utf8_text(source) {
                     let pos = name_node.start_position();
+                    let end = name_node.end_position();
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("definition".to_string()),
@@ -73,7 +78,10 @@ fn collect_references(
                             Some(namespace_stack.join("."))
                         },
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, name_node.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, name_node.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
@@ -82,6 +90,7 @@ fn collect_references(
             if !is_part_of_definition(node) {
                 if let Ok(name) = node.utf8_text(source) {
                     let pos = node.start_position();
+                    let end = node.end_position();
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("reference".to_string()),
@@ -91,7 +100,10 @@ fn collect_references(
                             Some(namespace_stack.join("."))
                         },
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, node.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, node.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
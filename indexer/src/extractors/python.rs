@@ -1,7 +1,7 @@
 use std::collections::HashSet;
-use tree_sitter::{Node, Parser, Point};
+use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
+use super::{ExtractedReference, Extraction, line_column};
 
 pub fn extract(source: &str, namespace_hint: Option<&str>) -> Extraction {
     let mut parser = Parser::new();
@@ -44,13 +44,17 @@ fn collect_references(
             if let Some(name_node) = node.child_by_field_name("name") {
                 if let Ok(name) = name_node.utf8_text(source) {
                     let pos = name_node.start_position();
+                    let end = name_node.end_position();
                     definition_positions.insert(name_node.start_byte() as usize);
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("definition".to_string()),
                         namespace: namespace_for_stack(namespace_stack),
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, name_node.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, name_node.end_byte(), end.column),
+                        signature: None,
                     });
                     new_namespace_stack.push(name.to_string());
                 }
@@ -65,14 +69,19 @@ fn collect_references(
             if let Some(target) = node.child_by_field_name(target_field) {
                 let mut bindings = Vec::new();
                 collect_binding_identifiers(&target, source, &mut bindings);
-                for (name, pos, start_byte) in bindings {
-                    definition_positions.insert(start_byte);
+                for (name, binding) in bindings {
+                    let pos = binding.start_position();
+                    let end = binding.end_position();
+                    definition_positions.insert(binding.start_byte());
                     references.push(ExtractedReference {
                         name,
                         kind: Some("definition".to_string()),
                         namespace: namespace_for_stack(namespace_stack),
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, binding.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, binding.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
@@ -81,14 +90,19 @@ fn collect_references(
             if let Some(target) = node.child_by_field_name("left") {
                 let mut bindings = Vec::new();
                 collect_binding_identifiers(&target, source, &mut bindings);
-                for (name, pos, start_byte) in bindings {
-                    definition_positions.insert(start_byte);
+                for (name, binding) in bindings {
+                    let pos = binding.start_position();
+                    let end = binding.end_position();
+                    definition_positions.insert(binding.start_byte());
                     references.push(ExtractedReference {
                         name,
                         kind: Some("definition".to_string()),
                         namespace: namespace_for_stack(namespace_stack),
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, binding.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, binding.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
@@ -97,14 +111,19 @@ fn collect_references(
             if let Some(alias) = node.child_by_field_name("alias") {
                 let mut bindings = Vec::new();
                 collect_binding_identifiers(&alias, source, &mut bindings);
-                for (name, pos, start_byte) in bindings {
-                    definition_positions.insert(start_byte);
+                for (name, binding) in bindings {
+                    let pos = binding.start_position();
+                    let end = binding.end_position();
+                    definition_positions.insert(binding.start_byte());
                     references.push(ExtractedReference {
                         name,
                         kind: Some("definition".to_string()),
                         namespace: namespace_for_stack(namespace_stack),
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, binding.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, binding.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
@@ -113,14 +132,19 @@ fn collect_references(
             if let Some(alias) = node.child_by_field_name("alias") {
                 let mut bindings = Vec::new();
                 collect_binding_identifiers(&alias, source, &mut bindings);
-                for (name, pos, start_byte) in bindings {
-                    definition_positions.insert(start_byte);
+                for (name, binding) in bindings {
+                    let pos = binding.start_position();
+                    let end = binding.end_position();
+                    definition_positions.insert(binding.start_byte());
                     references.push(ExtractedReference {
                         name,
                         kind: Some("definition".to_string()),
                         namespace: namespace_for_stack(namespace_stack),
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, binding.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, binding.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
@@ -129,13 +153,17 @@ fn collect_references(
             if let Some(alias) = node.child_by_field_name("alias") {
                 if let Ok(name) = alias.utf8_text(source) {
                     let pos = alias.start_position();
+                    let end = alias.end_position();
                     definition_positions.insert(alias.start_byte() as usize);
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("definition".to_string()),
                         namespace: namespace_for_stack(namespace_stack),
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, alias.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, alias.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
@@ -148,12 +176,16 @@ fn collect_references(
         if !definition_positions.contains(&start_byte) {
             if let Ok(name) = node.utf8_text(source) {
                 let pos = node.start_position();
+                let end = node.end_position();
                 references.push(ExtractedReference {
                     name: name.to_string(),
                     kind: Some("reference".to_string()),
                     namespace: namespace_for_stack(namespace_stack),
                     line: pos.row + 1,
-                    column: pos.column + 1,
+                    column: line_column(source, node.start_byte(), pos.column),
+                    end_line: end.row + 1,
+                    end_column: line_column(source, node.end_byte(), end.column),
+                    signature: None,
                 });
             }
         }
@@ -216,17 +248,17 @@ fn apply_namespace_hint(references: &mut [ExtractedReference], namespace_hint: O
     }
 }
 
-fn collect_binding_identifiers(node: &Node, source: &[u8], out: &mut Vec<(String, Point, usize)>) {
+fn collect_binding_identifiers<'a>(
+    node: &Node<'a>,
+    source: &[u8],
+    out: &mut Vec<(String, Node<'a>)>,
+) {
     match node.kind() {
         "identifier" => {
             if let Ok(name) = node.utf8_text(source) {
                 let trimmed = name.trim();
                 if !trimmed.is_empty() && trimmed != "_" {
-                    out.push((
-                        trimmed.to_string(),
-                        node.start_position(),
-                        node.start_byte() as usize,
-                    ));
+                    out.push((trimmed.to_string(), *node));
                 }
             }
         }
@@ -0,0 +1,319 @@
+use std::collections::HashSet;
+use tree_sitter::{Node, Parser};
+
+use super::{ExtractedReference, Extraction, line_column};
+
+pub fn extract(source: &str) -> Extraction {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_ruby::LANGUAGE.into())
+        .expect("failed to load tree-sitter Ruby grammar");
+
+    let tree = match parser.parse(source, None) {
+        Some(tree) => tree,
+        None => return Extraction::default(),
+    };
+
+    let mut references = Vec::new();
+    let source_bytes = source.as_bytes();
+    let mut definition_positions = HashSet::new();
+    collect_references(
+        &tree.root_node(),
+        source_bytes,
+        &mut references,
+        &[],
+        &mut definition_positions,
+    );
+
+    references.into()
+}
+
+fn collect_references(
+    node: &Node,
+    source: &[u8],
+    references: &mut Vec<ExtractedReference>,
+    namespace_stack: &[String],
+    definition_positions: &mut HashSet<usize>,
+) {
+    let mut new_namespace_stack = namespace_stack.to_owned();
+
+    match node.kind() {
+        // `class Foo::Bar` defines `Bar` inside `Foo`, so the scope segments
+        // become part of both the definition's namespace and the body's.
+        "class" | "module" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let mut segments = Vec::new();
+                if let Some(target) = constant_path(&name_node, source, &mut segments) {
+                    let mut namespace = namespace_stack.to_owned();
+                    namespace.extend(segments);
+                    record_definition(
+                        &target,
+                        source,
+                        references,
+                        &namespace,
+                        definition_positions,
+                    );
+                    if let Ok(name) = target.utf8_text(source) {
+                        namespace.push(name.to_string());
+                    }
+                    new_namespace_stack = namespace;
+                }
+            }
+        }
+        "method" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                record_definition(
+                    &name_node,
+                    source,
+                    references,
+                    namespace_stack,
+                    definition_positions,
+                );
+                if let Ok(name) = name_node.utf8_text(source) {
+                    new_namespace_stack.push(name.to_string());
+                }
+            }
+        }
+        // `def self.build` belongs to the enclosing class; `def Foo.build`
+        // belongs to `Foo`.
+        "singleton_method" => {
+            let mut namespace = namespace_stack.to_owned();
+            if let Some(object) = node.child_by_field_name("object") {
+                let mut segments = Vec::new();
+                if let Some(target) = constant_path(&object, source, &mut segments) {
+                    namespace.extend(segments);
+                    if let Ok(name) = target.utf8_text(source) {
+                        namespace.push(name.to_string());
+                    }
+                }
+            }
+            if let Some(name_node) = node.child_by_field_name("name") {
+                record_definition(
+                    &name_node,
+                    source,
+                    references,
+                    &namespace,
+                    definition_positions,
+                );
+                if let Ok(name) = name_node.utf8_text(source) {
+                    namespace.push(name.to_string());
+                }
+            }
+            new_namespace_stack = namespace;
+        }
+        "assignment" | "operator_assignment" => {
+            if let Some(target) = node.child_by_field_name("left") {
+                let mut bindings = Vec::new();
+                collect_binding_nodes(&target, &mut bindings);
+                for binding in bindings {
+                    record_definition(
+                        &binding,
+                        source,
+                        references,
+                        namespace_stack,
+                        definition_positions,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if matches!(node.kind(), "identifier" | "constant") {
+        let start_byte = node.start_byte();
+        if !definition_positions.contains(&start_byte)
+            && let Ok(name) = node.utf8_text(source)
+        {
+            let pos = node.start_position();
+            let end = node.end_position();
+            references.push(ExtractedReference {
+                name: name.to_string(),
+                kind: Some("reference".to_string()),
+                namespace: namespace_for_stack(namespace_stack),
+                line: pos.row + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
+            });
+        }
+    }
+
+    // A class's name and superclass are resolved in the enclosing scope; only
+    // its body is inside the new namespace.
+    let body_id = matches!(node.kind(), "class" | "module")
+        .then(|| node.child_by_field_name("body"))
+        .flatten()
+        .map(|body| body.id());
+    for child in node.children(&mut node.walk()) {
+        let child_namespace = match body_id {
+            Some(id) if child.id() != id => namespace_stack,
+            _ => &new_namespace_stack,
+        };
+        collect_references(
+            &child,
+            source,
+            references,
+            child_namespace,
+            definition_positions,
+        );
+    }
+}
+
+fn record_definition(
+    node: &Node,
+    source: &[u8],
+    references: &mut Vec<ExtractedReference>,
+    namespace_stack: &[String],
+    definition_positions: &mut HashSet<usize>,
+) {
+    if let Ok(name) = node.utf8_text(source) {
+        let pos = node.start_position();
+        let end = node.end_position();
+        definition_positions.insert(node.start_byte());
+        references.push(ExtractedReference {
+            name: name.to_string(),
+            kind: Some("definition".to_string()),
+            namespace: namespace_for_stack(namespace_stack),
+            line: pos.row + 1,
+            column: line_column(source, node.start_byte(), pos.column),
+            end_line: end.row + 1,
+            end_column: line_column(source, node.end_byte(), end.column),
+            signature: None,
+        });
+    }
+}
+
+/// Splits `A::B::C` into its leading segments (`A`, `B`) and returns the
+/// final constant node. Returns `None` for anything that isn't a constant
+/// path, such as `self`.
+fn constant_path<'a>(
+    node: &Node<'a>,
+    source: &[u8],
+    segments: &mut Vec<String>,
+) -> Option<Node<'a>> {
+    match node.kind() {
+        "constant" => Some(*node),
+        "scope_resolution" => {
+            if let Some(scope) = node.child_by_field_name("scope") {
+                let mut scope_segments = Vec::new();
+                if let Some(last) = constant_path(&scope, source, &mut scope_segments) {
+                    segments.extend(scope_segments);
+                    if let Ok(name) = last.utf8_text(source) {
+                        segments.push(name.to_string());
+                    }
+                }
+            }
+            let name = node.child_by_field_name("name")?;
+            constant_path(&name, source, segments)
+        }
+        _ => None,
+    }
+}
+
+fn collect_binding_nodes<'a>(node: &Node<'a>, out: &mut Vec<Node<'a>>) {
+    match node.kind() {
+        "identifier" | "constant" => out.push(*node),
+        "left_assignment_list" | "destructured_left_assignment" | "rest_assignment" => {
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                collect_binding_nodes(&child, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn namespace_for_stack(namespace_stack: &[String]) -> Option<String> {
+    if namespace_stack.is_empty() {
+        None
+    } else {
+        Some(namespace_stack.join("::"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn definitions(references: &[ExtractedReference]) -> HashSet<(&str, Option<&str>)> {
+        references
+            .iter()
+            .filter(|r| r.kind == Some("definition".to_string()))
+            .map(|r| (r.name.as_str(), r.namespace.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn extracts_ruby_definitions_with_nested_namespaces() {
+        let source = r#"
+module Billing; class Invoice < Base
+  LIMIT = 3
+
+  def self.build(amount)
+    total = amount + fee
+    new(total)
+  end
+
+  class << self
+    def archived; end
+  end
+
+  def charge
+    Payments::Gateway.call(@amount)
+  end
+end; end
+
+class Billing::Receipt
+  def Formatter.render; end
+end
+"#;
+
+        let extraction = extract(source);
+        let references = extraction.references;
+        let definitions = definitions(&references);
+
+        assert!(definitions.contains(&("Billing", None)));
+        assert!(definitions.contains(&("Invoice", Some("Billing"))));
+        assert!(definitions.contains(&("LIMIT", Some("Billing::Invoice"))));
+        assert!(definitions.contains(&("build", Some("Billing::Invoice"))));
+        assert!(definitions.contains(&("total", Some("Billing::Invoice::build"))));
+        assert!(definitions.contains(&("archived", Some("Billing::Invoice"))));
+        assert!(definitions.contains(&("charge", Some("Billing::Invoice"))));
+        assert!(definitions.contains(&("Receipt", Some("Billing"))));
+        assert!(definitions.contains(&("render", Some("Billing::Receipt::Formatter"))));
+        assert!(
+            !references.iter().any(|r| r.name == "self"),
+            "`self` is a receiver, not a symbol"
+        );
+
+        let refs: HashSet<_> = references
+            .iter()
+            .filter(|r| r.kind == Some("reference".to_string()))
+            .map(|r| (r.name.as_str(), r.namespace.as_deref()))
+            .collect();
+
+        assert!(refs.contains(&("Base", Some("Billing"))));
+        assert!(refs.contains(&("amount", Some("Billing::Invoice::build"))));
+        assert!(refs.contains(&("fee", Some("Billing::Invoice::build"))));
+        assert!(refs.contains(&("total", Some("Billing::Invoice::build"))));
+        assert!(refs.contains(&("Payments", Some("Billing::Invoice::charge"))));
+        assert!(refs.contains(&("Gateway", Some("Billing::Invoice::charge"))));
+        assert!(refs.contains(&("call", Some("Billing::Invoice::charge"))));
+        assert!(!refs.contains(&("build", Some("Billing::Invoice"))));
+    }
+
+    #[test]
+    fn records_each_target_of_a_multiple_assignment() {
+        let source = "first, (second, *rest) = values\n";
+
+        let extraction = extract(source);
+        let definitions = definitions(&extraction.references);
+
+        assert_eq!(
+            definitions,
+            HashSet::from([("first", None), ("second", None), ("rest", None)])
+        );
+    }
+}
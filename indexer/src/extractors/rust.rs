@@ -1,9 +1,12 @@
 use std::collections::HashSet;
 use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
+use super::{ExtractedReference, Extraction, declaration_signature, line_column};
 
-pub fn extract(source: &str) -> Extraction {
+/// Extracts references from a Rust file. `namespace_hint` is the file's module
+/// path (e.g. `foo::bar` for `src/foo/bar.rs`); Rust never declares it in
+/// source, so it roots every namespace, including inline `mod`s and `impl`s.
+pub fn extract(source: &str, namespace_hint: Option<&str>) -> Extraction {
     let mut parser = Parser::new();
     parser
         .set_language(&tree_sitter_rust::LANGUAGE.into())
@@ -16,15 +19,35 @@ pub fn extract(source: &str) -> Extraction {
 
     let mut references = Vec::new();
     let source_bytes = source.as_bytes();
-    collect_references(&tree.root_node(), source_bytes, &mut references);
+    collect_references(
+        &tree.root_node(),
+        source_bytes,
+        &mut references,
+        hint_segments(namespace_hint),
+    );
 
     references.into()
 }
 
-fn collect_references(root: &Node, source: &[u8], references: &mut Vec<ExtractedReference>) {
+fn hint_segments(namespace_hint: Option<&str>) -> Vec<String> {
+    namespace_hint
+        .unwrap_or_default()
+        .split("::")
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn collect_references(
+    root: &Node,
+    source: &[u8],
+    references: &mut Vec<ExtractedReference>,
+    root_namespace: Vec<String>,
+) {
     let mut defined_nodes = HashSet::new();
     let mut stack: Vec<(Node, Vec<String>)> = Vec::new();
-    stack.push((*root, Vec::new()));
+    stack.push((*root, root_namespace));
 
     while let Some((node, namespace_stack)) = stack.pop() {
         let mut next_namespace = namespace_stack.clone();
@@ -85,6 +108,9 @@ fn collect_references(root: &Node, source: &[u8], references: &mut Vec<Extracted
                         "definition",
                         &mut defined_nodes,
                     ) {
+                        if let Some(definition) = references.last_mut() {
+                            definition.signature = declaration_signature(&node, source);
+                        }
                         next_namespace = push_namespace(&namespace_stack, &name);
                     }
                 }
@@ -347,12 +373,16 @@ fn record_definition_node(
     if let Ok(raw) = node.utf8_text(source) {
         if let Some(name) = sanitize_identifier(raw) {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name: name.clone(),
                 kind: Some(kind.to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
             defined_nodes.insert(node.id());
             return Some(name);
@@ -374,12 +404,16 @@ fn record_reference_node(
     if let Ok(raw) = node.utf8_text(source) {
         if let Some(name) = sanitize_identifier(raw) {
             let pos = node.start_position();
+            let end = node.end_position();
             references.push(ExtractedReference {
                 name,
                 kind: Some("reference".to_string()),
                 namespace: namespace_from_stack(namespace_stack),
                 line: pos.row + 1,
-                column: pos.column + 1,
+                column: line_column(source, node.start_byte(), pos.column),
+                end_line: end.row + 1,
+                end_column: line_column(source, node.end_byte(), end.column),
+                signature: None,
             });
         }
     }
@@ -522,7 +556,7 @@ mod tests {
             }
         "#;
 
-        let extraction = extract(source);
+        let extraction = extract(source, None);
         let references = extraction.references;
         let (definitions, references_map) = bucket_kinds(&references);
 
@@ -669,7 +703,7 @@ mod tests {
             }
         "#;
 
-        let extraction = extract(source);
+        let extraction = extract(source, None);
 
         for reference in &extraction.references {
             assert!(
@@ -738,7 +772,7 @@ mod tests {
             >;
         "#;
 
-        let extraction = extract(source);
+        let extraction = extract(source, None);
 
         for reference in &extraction.references {
             assert!(
@@ -771,4 +805,92 @@ mod tests {
         assert!(refs.contains("gp_hash_tag"));
         assert!(refs.contains("Policy_Tl"));
     }
+
+    #[test]
+    fn namespace_hint_prefixes_file_level_items() {
+        let hint =
+            crate::utils::namespace_from_path(Some("rust"), std::path::Path::new("src/foo/bar.rs"));
+        assert_eq!(hint.as_deref(), Some("foo::bar"));
+
+        let source = r#"
+            pub struct Widget;
+
+            impl Widget {
+                pub fn new() -> Self {
+                    Widget
+                }
+            }
+
+            fn helper() -> Widget {
+                Widget::new()
+            }
+        "#;
+
+        let extraction = extract(source, hint.as_deref());
+        let (definitions, references) = bucket_kinds(&extraction.references);
+
+        let key = |name: &str, namespace: &str| (name.to_string(), Some(namespace.to_string()));
+        assert!(definitions.contains_key(&key("Widget", "foo::bar")));
+        assert!(definitions.contains_key(&key("helper", "foo::bar")));
+        assert!(definitions.contains_key(&key("new", "foo::bar::Widget")));
+        assert!(references.contains_key(&key("Widget", "foo::bar::helper")));
+        assert!(
+            extraction.references.iter().all(|r| r
+                .namespace
+                .as_deref()
+                .is_some_and(|ns| ns.starts_with("foo::bar"))),
+            "every reference should be under the hint: {:?}",
+            extraction.references
+        );
+    }
+
+    #[test]
+    fn records_function_signatures_on_definitions() {
+        let source = r#"pub fn foo(a: u32) -> bool {
+    a > 1
+}
+
+trait Shape {
+    fn area(&self) -> f64;
+}
+
+fn spread(
+    first: &str,
+    rest: &[String],
+) -> usize {
+    0
+}
+
+struct Point;
+"#;
+
+        let extraction = extract(source, None);
+        let signature = |name: &str| {
+            extraction
+                .references
+                .iter()
+                .find(|r| r.name == name && r.kind.as_deref() == Some("definition"))
+                .unwrap_or_else(|| panic!("missing definition for {name}"))
+                .signature
+                .clone()
+        };
+
+        assert_eq!(
+            signature("foo").as_deref(),
+            Some("pub fn foo(a: u32) -> bool")
+        );
+        assert_eq!(signature("area").as_deref(), Some("fn area(&self) -> f64"));
+        assert_eq!(
+            signature("spread").as_deref(),
+            Some("fn spread(first: &str, rest: &[String],) -> usize")
+        );
+        assert_eq!(signature("Point"), None);
+        assert!(
+            extraction
+                .references
+                .iter()
+                .filter(|r| r.kind.as_deref() == Some("reference"))
+                .all(|r| r.signature.is_none())
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
+use super::{ExtractedReference, Extraction, line_column};
 
 pub fn extract(source: &str) -> Extraction {
     let mut parser = Parser::new();
@@ -40,6 +40,7 @@ fn collect_references(
             if let Some(name_node) = node.child_by_field_name("name") {
                 if let Ok(name) = name_node.utf8_text(source) {
                     let pos = name_node.start_position();
+                    let end = name_node.end_position();
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("definition".to_string()),
@@ -49,7 +50,10 @@ fn collect_references(
                             Some(namespace_stack.join("."))
                         },
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, name_node.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, name_node.end_byte(), end.column),
+                        signature: None,
                     });
                     new_namespace_stack.push(name.to_string());
                 }
@@ -65,6 +69,7 @@ fn collect_references(
 
             for name in names {
                 let pos = node.start_position();
+                let end = node.end_position();
                 references.push(ExtractedReference {
                     name: name.to_string(),
                     kind: Some("definition".to_string()),
@@ -74,7 +79,10 @@ fn collect_references(
                         Some(namespace_stack.join("."))
                     },
                     line: pos.row + 1,
-                    column: pos.column + 1,
+                    column: line_column(source, node.start_byte(), pos.column),
+                    end_line: end.row + 1,
+                    end_column: line_column(source, node.end_byte(), end.column),
+                    signature: None,
                 });
             }
         }
@@ -82,6 +90,7 @@ fn collect_references(
             if !is_part_of_definition_or_declaration(node) {
                 if let Ok(name) = node.utf8_text(source) {
                     let pos = node.start_position();
+                    let end = node.end_position();
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("reference".to_string()),
@@ -91,7 +100,10 @@ fn collect_references(
                             Some(namespace_stack.join("."))
                         },
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, node.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, node.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
@@ -1,6 +1,6 @@
 use tree_sitter::{Node, Parser};
 
-use super::{ExtractedReference, Extraction};
+use super::{ExtractedReference, Extraction, declaration_signature, line_column};
 
 pub fn extract(source: &str) -> Extraction {
     let mut parser = Parser::new();
@@ -40,6 +40,7 @@ fn collect_references(
             if let Some(name_node) = node.child_by_field_name("name") {
                 if let Ok(name) = name_node.utf8_text(source) {
                     let pos = name_node.start_position();
+                    let end = name_node.end_position();
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("definition".to_string()),
@@ -49,7 +50,15 @@ fn collect_references(
                             Some(namespace_stack.join("."))
                         },
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, name_node.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, name_node.end_byte(), end.column),
+                        signature: matches!(
+                            node.kind(),
+                            "function_declaration" | "generator_function_declaration"
+                        )
+                        .then(|| declaration_signature(node, source))
+                        .flatten(),
                     });
                     new_namespace_stack.push(name.to_string());
                 }
@@ -68,6 +77,7 @@ fn collect_references(
             {
                 if let Ok(name) = name_node.utf8_text(source) {
                     let pos = name_node.start_position();
+                    let end = name_node.end_position();
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("definition".to_string()),
@@ -77,7 +87,12 @@ fn collect_references(
                             Some(namespace_stack.join("."))
                         },
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, name_node.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, name_node.end_byte(), end.column),
+                        signature: matches!(node.kind(), "method_definition" | "method_signature")
+                            .then(|| declaration_signature(node, source))
+                            .flatten(),
                     });
                     new_namespace_stack.push(name.to_string());
                 }
@@ -91,6 +106,7 @@ fn collect_references(
                         collect_pattern_names(&name_node, source, &mut names);
                         for name in names {
                             let pos = name_node.start_position();
+                            let end = name_node.end_position();
                             references.push(ExtractedReference {
                                 name: name.to_string(),
                                 kind: Some("definition".to_string()),
@@ -100,7 +116,10 @@ fn collect_references(
                                     Some(namespace_stack.join("."))
                                 },
                                 line: pos.row + 1,
-                                column: pos.column + 1,
+                                column: line_column(source, name_node.start_byte(), pos.column),
+                                end_line: end.row + 1,
+                                end_column: line_column(source, name_node.end_byte(), end.column),
+                                signature: None,
                             });
                         }
                     }
@@ -113,6 +132,7 @@ fn collect_references(
                 collect_pattern_names(&left, source, &mut names);
                 for name in names {
                     let pos = left.start_position();
+                    let end = left.end_position();
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("definition".to_string()),
@@ -122,7 +142,10 @@ fn collect_references(
                             Some(namespace_stack.join("."))
                         },
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, left.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, left.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
@@ -131,6 +154,7 @@ fn collect_references(
             if !is_part_of_definition_or_declaration(node) {
                 if let Ok(name) = node.utf8_text(source) {
                     let pos = node.start_position();
+                    let end = node.end_position();
                     references.push(ExtractedReference {
                         name: name.to_string(),
                         kind: Some("reference".to_string()),
@@ -140,7 +164,10 @@ fn collect_references(
                             Some(namespace_stack.join("."))
                         },
                         line: pos.row + 1,
-                        column: pos.column + 1,
+                        column: line_column(source, node.start_byte(), pos.column),
+                        end_line: end.row + 1,
+                        end_column: line_column(source, node.end_byte(), end.column),
+                        signature: None,
                     });
                 }
             }
@@ -426,4 +453,45 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn records_function_and_method_signatures() {
+        let source = r#"export function greet(name: string): string {
+  return name;
+}
+
+class Greeter {
+  async hello(times: number): Promise<void> {}
+}
+
+interface Api {
+  fetch(id: string): Promise<Item>;
+}
+"#;
+
+        let extraction = extract(source);
+        let signature = |name: &str| {
+            extraction
+                .references
+                .iter()
+                .find(|r| r.name == name && r.kind.as_deref() == Some("definition"))
+                .unwrap_or_else(|| panic!("missing definition for {name}"))
+                .signature
+                .clone()
+        };
+
+        assert_eq!(
+            signature("greet").as_deref(),
+            Some("function greet(name: string): string")
+        );
+        assert_eq!(
+            signature("hello").as_deref(),
+            Some("async hello(times: number): Promise<void>")
+        );
+        assert_eq!(
+            signature("fetch").as_deref(),
+            Some("fetch(id: string): Promise<Item>")
+        );
+        assert_eq!(signature("Greeter"), None);
+    }
 }
@@ -0,0 +1,169 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::extractors::Extraction;
+
+/// Describes the file an extraction came from.
+#[derive(Debug, Clone, Copy)]
+pub struct FileContext<'a> {
+    pub repository: &'a str,
+    pub commit_sha: &'a str,
+    pub path: &'a str,
+    pub language: &'a str,
+    pub namespace_hint: Option<&'a str>,
+}
+
+/// Rewrites extraction output before it is turned into symbol and reference
+/// records. Hooks run in the order they were registered on `IndexerConfig`.
+pub trait ExtractionHook: fmt::Debug + Send + Sync {
+    fn transform(&self, file: &FileContext, extraction: &mut Extraction);
+}
+
+/// Drops every extracted reference whose name matches a regex, e.g. to hide
+/// generated symbols.
+#[derive(Debug, Clone)]
+pub struct DropSymbolsMatching {
+    pattern: Regex,
+}
+
+impl DropSymbolsMatching {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let pattern = Regex::new(pattern)
+            .with_context(|| format!("invalid symbol filter regex `{pattern}`"))?;
+        Ok(Self { pattern })
+    }
+}
+
+impl ExtractionHook for DropSymbolsMatching {
+    fn transform(&self, _file: &FileContext, extraction: &mut Extraction) {
+        extraction
+            .references
+            .retain(|reference| !self.pattern.is_match(&reference.name));
+    }
+}
+
+/// Prepends a fixed prefix to every namespace. References without a namespace
+/// are placed under the path-derived hint first, so the prefix applies to them
+/// too.
+#[derive(Debug, Clone)]
+pub struct NamespacePrefix {
+    prefix: String,
+}
+
+impl NamespacePrefix {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl ExtractionHook for NamespacePrefix {
+    fn transform(&self, file: &FileContext, extraction: &mut Extraction) {
+        for reference in &mut extraction.references {
+            let namespace = reference
+                .namespace
+                .take()
+                .or_else(|| file.namespace_hint.map(str::to_string));
+            reference.namespace = Some(match namespace {
+                Some(ns) if !ns.is_empty() => format!("{}::{}", self.prefix, ns),
+                _ => self.prefix.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::config::IndexerConfig;
+    use crate::engine::Indexer;
+    use crate::extractors::ExtractedReference;
+    use crate::models::SymbolRecord;
+
+    fn reference(name: &str, namespace: Option<&str>) -> ExtractedReference {
+        ExtractedReference {
+            name: name.to_string(),
+            kind: Some("definition".to_string()),
+            namespace: namespace.map(str::to_string),
+            line: 1,
+            column: 1,
+            end_line: 1,
+            end_column: 1 + name.chars().count(),
+            signature: None,
+        }
+    }
+
+    fn context(namespace_hint: Option<&str>) -> FileContext<'_> {
+        FileContext {
+            repository: "acme",
+            commit_sha: "abc123",
+            path: "src/lib.rs",
+            language: "rust",
+            namespace_hint,
+        }
+    }
+
+    #[test]
+    fn namespace_prefix_covers_hinted_and_bare_references() {
+        let mut extraction =
+            Extraction::from(vec![reference("a", Some("widgets")), reference("b", None)]);
+        NamespacePrefix::new("vendor").transform(&context(Some("lib")), &mut extraction);
+
+        let namespaces: Vec<_> = extraction
+            .references
+            .iter()
+            .map(|r| r.namespace.as_deref())
+            .collect();
+        assert_eq!(
+            namespaces,
+            vec![Some("vendor::widgets"), Some("vendor::lib")]
+        );
+    }
+
+    #[test]
+    fn indexer_report_reflects_drop_hook() {
+        let repo = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        fs::write(
+            repo.path().join("lib.rs"),
+            "fn generated_handler() {}\nfn keep_me() {}\n",
+        )
+        .unwrap();
+
+        let config = IndexerConfig::new(
+            repo.path().to_path_buf(),
+            "acme".to_string(),
+            None,
+            "abc123".to_string(),
+            output.path().to_path_buf(),
+            None,
+        )
+        .with_hook(Arc::new(DropSymbolsMatching::new("^generated_").unwrap()));
+        let artifacts = Indexer::new(config).run().unwrap();
+
+        let symbols: HashSet<String> = artifacts
+            .symbol_records_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|SymbolRecord { name, .. }| name)
+            .collect();
+        assert!(symbols.contains("keep_me"));
+        assert!(!symbols.contains("generated_handler"));
+
+        let references = artifacts
+            .reference_records_stream()
+            .unwrap()
+            .next_batch(usize::MAX)
+            .unwrap();
+        assert!(references.iter().all(|r| r.name != "generated_handler"));
+    }
+}
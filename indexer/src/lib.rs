@@ -4,6 +4,7 @@ pub mod cli;
 pub mod config;
 pub mod engine;
 pub mod extractors;
+pub mod hooks;
 pub mod models;
 pub mod output;
 pub mod upload;
@@ -12,4 +13,5 @@ pub mod utils;
 pub use cli::run;
 pub use config::IndexerConfig;
 pub use engine::Indexer;
+pub use hooks::{ExtractionHook, FileContext};
 pub use models::{IndexArtifacts, IndexReport};
@@ -13,8 +13,9 @@ use tempfile::{Builder, NamedTempFile, TempPath};
 use crate::chunk_store::ChunkStore;
 
 pub use pointer_indexer_types::{
-    BranchHead, BranchPolicy, BranchSnapshotPolicy, ChunkMapping, ContentBlob, FilePointer,
-    IndexReport, ReferenceRecord, SymbolNamespaceRecord, SymbolRecord, UniqueChunk,
+    BranchHead, BranchPolicy, BranchSnapshotPolicy, ChunkMapping, CommitMetadata, ContentBlob,
+    ExtractionFailure, FileKind, FilePointer, IndexReport, IndexSummary, ReferenceRecord,
+    SkippedFiles, SymbolNamespaceRecord, SymbolRecord, UniqueChunk,
 };
 
 const NEWLINE: &[u8] = b"\n";
@@ -274,6 +275,14 @@ pub struct IndexArtifacts {
     chunk_mappings: RecordStore<ChunkMapping>,
     chunk_store: ChunkStore,
     pub branches: Vec<BranchHead>,
+    /// Metadata of the indexed commit; empty outside a git repository.
+    pub commits: Vec<CommitMetadata>,
+    /// Files carried forward from the base commit without re-extraction.
+    pub unchanged_files: usize,
+    /// Files whose extraction panicked; they were indexed without symbols.
+    pub extraction_failures: Vec<ExtractionFailure>,
+    /// Walked files left out of the index, by reason.
+    pub skipped_files: SkippedFiles,
     scratch_dir: PathBuf,
 }
 
@@ -299,10 +308,37 @@ impl IndexArtifacts {
             chunk_mappings,
             chunk_store,
             branches,
+            commits: Vec::new(),
+            unchanged_files: 0,
+            extraction_failures: Vec::new(),
+            skipped_files: SkippedFiles::default(),
             scratch_dir,
         }
     }
 
+    pub(crate) fn with_commits(mut self, commits: Vec<CommitMetadata>) -> Self {
+        self.commits = commits;
+        self
+    }
+
+    pub(crate) fn with_unchanged_files(mut self, unchanged_files: usize) -> Self {
+        self.unchanged_files = unchanged_files;
+        self
+    }
+
+    pub(crate) fn with_extraction_failures(
+        mut self,
+        extraction_failures: Vec<ExtractionFailure>,
+    ) -> Self {
+        self.extraction_failures = extraction_failures;
+        self
+    }
+
+    pub(crate) fn with_skipped_files(mut self, skipped_files: SkippedFiles) -> Self {
+        self.skipped_files = skipped_files;
+        self
+    }
+
     pub fn chunk_hashes(&self) -> &[String] {
         self.chunk_store.hashes()
     }
@@ -311,6 +347,25 @@ impl IndexArtifacts {
         self.chunk_store.len()
     }
 
+    /// Byte length of a stored chunk's text.
+    pub fn chunk_len(&self, hash: &str) -> Option<usize> {
+        self.chunk_store.chunk_len(hash)
+    }
+
+    pub fn summary(&self) -> IndexSummary {
+        IndexSummary {
+            content_blobs: self.content_blob_count(),
+            file_pointers: self.file_pointer_count(),
+            symbol_records: self.symbol_record_count(),
+            reference_records: self.reference_record_count(),
+            unique_chunks: self.chunk_count(),
+            chunk_bytes: self.chunk_store.total_bytes(),
+            unchanged_files: self.unchanged_files,
+            extraction_failures: self.extraction_failures.len(),
+            skipped_files: self.skipped_files,
+        }
+    }
+
     pub fn read_chunk(&self, hash: &str) -> Result<String> {
         match self.chunk_store.read_chunk(hash)? {
             Some(text) => Ok(text),
@@ -433,6 +488,12 @@ impl IndexArtifacts {
             write_line(&mut writer, "branch_head", &payload)?;
         }
 
+        for commit in &self.commits {
+            let payload =
+                serde_json::to_string(commit).context("failed to serialize commit metadata")?;
+            write_line(&mut writer, "commit_metadata", &payload)?;
+        }
+
         Ok(())
     }
 
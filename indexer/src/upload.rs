@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -8,13 +8,17 @@ use anyhow::{Context, Result, anyhow};
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as BASE64;
 use crossbeam_channel::bounded;
+use flate2::write::GzEncoder;
+use pointer_indexer_types::{IndexSummary, ManifestCompression};
 use reqwest::blocking::{Client, Response};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use serde::{Deserialize, Serialize};
 use tracing::info;
 use zstd::stream::Encoder;
 
+use crate::config::ContentCheck;
 use crate::models::{ChunkMapping, IndexArtifacts, ReferenceRecord, SymbolRecord, UniqueChunk};
+use crate::utils::compute_content_hash;
 
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
 const MANIFEST_SHARD_RECORD_LIMIT: usize = 50_000;
@@ -35,12 +39,15 @@ pub fn upload_index(url: &str, api_key: Option<&str>, artifacts: &IndexArtifacts
 
 pub struct UploadOptions {
     pub incremental_symbols: bool,
+    /// Codec applied to manifest shards before upload.
+    pub manifest_compression: ManifestCompression,
 }
 
 impl Default for UploadOptions {
     fn default() -> Self {
         Self {
             incremental_symbols: true,
+            manifest_compression: ManifestCompression::Zstd,
         }
     }
 }
@@ -99,6 +106,7 @@ pub fn upload_index_with_options(
         &client,
         &endpoints,
         api_key,
+        options.manifest_compression,
         artifacts,
         needed_hashes.as_ref(),
     )?;
@@ -106,7 +114,173 @@ pub fn upload_index_with_options(
     Ok(())
 }
 
-#[derive(Clone)]
+/// What `upload_index_with_options` would send, without sending it.
+#[derive(Debug, Clone)]
+pub struct DryRunReport {
+    pub summary: IndexSummary,
+    /// Unique chunks the backend already stores; `None` without a backend URL.
+    pub chunks_already_stored: Option<usize>,
+    /// Chunk text still to upload plus every compressed, base64-encoded
+    /// manifest shard. Symbol and reference records are counted in full, so
+    /// incremental uploads may send less.
+    pub estimated_upload_bytes: u64,
+}
+
+/// The commit the backend has indexed as `branch`'s head, or `None` when the
+/// branch has not been indexed yet.
+pub fn fetch_branch_head(
+    url: &str,
+    api_key: Option<&str>,
+    repository: &str,
+    branch: &str,
+) -> Result<Option<String>> {
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("failed to build HTTP client")?;
+    let endpoints = Endpoints::new(url);
+    let request = BranchHeadRequest { repository, branch };
+    let response: BranchHeadResponse =
+        post_json(&client, &endpoints.branch_head, api_key, &request)?
+            .json()
+            .context("failed to deserialize branch head response")?;
+    Ok(response.commit_sha)
+}
+
+/// `ContentCheck` against the backend's `blobs/need` endpoint.
+#[derive(Debug)]
+pub struct BackendContentCheck {
+    client: Client,
+    endpoints: Arc<Endpoints>,
+    api_key: Option<String>,
+}
+
+impl BackendContentCheck {
+    pub fn new(url: &str, api_key: Option<&str>) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("failed to build HTTP client")?;
+        Ok(Self {
+            client,
+            endpoints: Arc::new(Endpoints::new(url)),
+            api_key: api_key.map(str::to_string),
+        })
+    }
+}
+
+impl ContentCheck for BackendContentCheck {
+    fn missing(&self, hashes: &[String]) -> Result<HashSet<String>> {
+        request_needed_content_hashes(
+            &self.client,
+            &self.endpoints,
+            self.api_key.as_deref(),
+            hashes,
+        )
+    }
+}
+
+/// Sizes up an upload. With a `url`, only the read-only `chunks/need`
+/// endpoint is queried, to discount chunks the backend already has.
+pub fn dry_run(
+    url: Option<&str>,
+    api_key: Option<&str>,
+    artifacts: &IndexArtifacts,
+    options: &UploadOptions,
+) -> Result<DryRunReport> {
+    let summary = artifacts.summary();
+    let chunk_hashes = artifacts.chunk_hashes();
+
+    let (chunks_already_stored, chunk_bytes) = match url {
+        Some(url) => {
+            let client = Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .context("failed to build HTTP client")?;
+            let endpoints = Arc::new(Endpoints::new(url));
+            let needed = request_needed_chunks(&client, &endpoints, api_key, chunk_hashes)?;
+            let needed_bytes = chunk_hashes
+                .iter()
+                .filter(|hash| needed.contains(hash.as_str()))
+                .filter_map(|hash| artifacts.chunk_len(hash))
+                .map(|len| len as u64)
+                .sum();
+            (
+                Some(chunk_hashes.len().saturating_sub(needed.len())),
+                needed_bytes,
+            )
+        }
+        None => (None, summary.chunk_bytes),
+    };
+
+    let manifest_bytes = estimate_manifest_bytes(artifacts, options.manifest_compression)?;
+
+    Ok(DryRunReport {
+        summary,
+        chunks_already_stored,
+        estimated_upload_bytes: chunk_bytes + manifest_bytes,
+    })
+}
+
+fn estimate_manifest_bytes(
+    artifacts: &IndexArtifacts,
+    compression: ManifestCompression,
+) -> Result<u64> {
+    let mut total = 0u64;
+    for path in [
+        artifacts.file_pointers_path(),
+        artifacts.symbol_records_path(),
+        artifacts.symbol_namespaces_path(),
+        artifacts.reference_records_path(),
+    ] {
+        if !path.exists() {
+            continue;
+        }
+        let file = File::open(path)
+            .with_context(|| format!("failed to open record store {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        loop {
+            let mut shard = Vec::with_capacity(MANIFEST_SHARD_BYTE_LIMIT);
+            (&mut reader)
+                .take(MANIFEST_SHARD_BYTE_LIMIT as u64)
+                .read_to_end(&mut shard)
+                .context("failed to read record store")?;
+            if shard.is_empty() {
+                break;
+            }
+            total += encoded_shard_len(&shard, compression)?;
+        }
+    }
+
+    let mut branches = Vec::new();
+    for branch in &artifacts.branches {
+        serde_json::to_writer(&mut branches, branch).context("failed to serialize branch head")?;
+        branches.push(b'\n');
+    }
+    if !branches.is_empty() {
+        total += encoded_shard_len(&branches, compression)?;
+    }
+
+    let mut commits = Vec::new();
+    for commit in &artifacts.commits {
+        serde_json::to_writer(&mut commits, commit)
+            .context("failed to serialize commit metadata")?;
+        commits.push(b'\n');
+    }
+    if !commits.is_empty() {
+        total += encoded_shard_len(&commits, compression)?;
+    }
+
+    Ok(total)
+}
+
+/// Size of a shard's `data` field once compressed and base64-encoded.
+fn encoded_shard_len(data: &[u8], compression: ManifestCompression) -> Result<u64> {
+    let compressed = compress_manifest_shard(data, compression)?;
+    Ok(compressed.len().div_ceil(3) as u64 * 4)
+}
+
+#[derive(Debug, Clone)]
 struct Endpoints {
     blobs_upload: String,
     blobs_need: String,
@@ -114,6 +288,7 @@ struct Endpoints {
     chunks_upload: String,
     mappings_upload: String,
     manifest_shard: String,
+    branch_head: String,
 }
 
 impl Endpoints {
@@ -126,6 +301,7 @@ impl Endpoints {
             chunks_upload: format!("{}/chunks/upload", trimmed),
             mappings_upload: format!("{}/mappings/upload", trimmed),
             manifest_shard: format!("{}/manifest/shard", trimmed),
+            branch_head: format!("{}/branches/head", trimmed),
         }
     }
 }
@@ -383,6 +559,7 @@ fn upload_manifest_shards(
     client: &Client,
     endpoints: &Arc<Endpoints>,
     api_key: Option<&str>,
+    compression: ManifestCompression,
     artifacts: &IndexArtifacts,
     needed_hashes: Option<&HashSet<String>>,
 ) -> Result<()> {
@@ -390,6 +567,7 @@ fn upload_manifest_shards(
         client,
         endpoints,
         api_key,
+        compression,
         artifacts.file_pointers_path(),
         "file_pointer",
         artifacts.file_pointer_count(),
@@ -401,6 +579,7 @@ fn upload_manifest_shards(
                 client,
                 endpoints,
                 api_key,
+                compression,
                 artifacts.symbol_records_path(),
                 "symbol_record",
                 Some(artifacts.symbol_record_count()),
@@ -418,6 +597,7 @@ fn upload_manifest_shards(
             client,
             endpoints,
             api_key,
+            compression,
             artifacts.symbol_records_path(),
             "symbol_record",
             artifacts.symbol_record_count(),
@@ -428,6 +608,7 @@ fn upload_manifest_shards(
         client,
         endpoints,
         api_key,
+        compression,
         artifacts.symbol_namespaces_path(),
         "symbol_namespace",
         artifacts.symbol_namespace_count(),
@@ -439,6 +620,7 @@ fn upload_manifest_shards(
                 client,
                 endpoints,
                 api_key,
+                compression,
                 artifacts.reference_records_path(),
                 "reference_record",
                 Some(artifacts.reference_record_count()),
@@ -456,13 +638,15 @@ fn upload_manifest_shards(
             client,
             endpoints,
             api_key,
+            compression,
             artifacts.reference_records_path(),
             "reference_record",
             artifacts.reference_record_count(),
         )?;
     }
 
-    upload_branch_heads(client, endpoints, api_key, &artifacts.branches)?;
+    upload_branch_heads(client, endpoints, api_key, compression, &artifacts.branches)?;
+    upload_commit_metadata(client, endpoints, api_key, compression, &artifacts.commits)?;
 
     info!(
         namespaces = artifacts.symbol_namespace_count(),
@@ -477,6 +661,7 @@ fn upload_record_store_shards(
     client: &Client,
     endpoints: &Arc<Endpoints>,
     api_key: Option<&str>,
+    compression: ManifestCompression,
     path: &std::path::Path,
     section: &str,
     total_records: usize,
@@ -485,6 +670,7 @@ fn upload_record_store_shards(
         client,
         endpoints,
         api_key,
+        compression,
         path,
         section,
         Some(total_records),
@@ -496,6 +682,7 @@ fn upload_filtered_record_store_shards<F>(
     client: &Client,
     endpoints: &Arc<Endpoints>,
     api_key: Option<&str>,
+    compression: ManifestCompression,
     path: &std::path::Path,
     section: &str,
     total_records: Option<usize>,
@@ -523,6 +710,7 @@ where
             client.as_ref(),
             Arc::clone(&endpoints),
             api,
+            compression,
             section_owned.as_str(),
             shard.index,
             &shard.data,
@@ -598,6 +786,7 @@ fn upload_branch_heads(
     client: &Client,
     endpoints: &Arc<Endpoints>,
     api_key: Option<&str>,
+    compression: ManifestCompression,
     branches: &[crate::models::BranchHead],
 ) -> Result<()> {
     if branches.is_empty() {
@@ -614,16 +803,47 @@ fn upload_branch_heads(
         client,
         Arc::clone(endpoints),
         api_key,
+        compression,
         "branch_head",
         0,
         &buffer,
     )
 }
 
+fn upload_commit_metadata(
+    client: &Client,
+    endpoints: &Arc<Endpoints>,
+    api_key: Option<&str>,
+    compression: ManifestCompression,
+    commits: &[crate::models::CommitMetadata],
+) -> Result<()> {
+    if commits.is_empty() {
+        return Ok(());
+    }
+
+    let mut buffer = Vec::with_capacity(commits.len() * 256);
+    for commit in commits {
+        serde_json::to_writer(&mut buffer, commit)
+            .context("failed to serialize commit metadata")?;
+        buffer.push(b'\n');
+    }
+
+    send_manifest_shard(
+        client,
+        Arc::clone(endpoints),
+        api_key,
+        compression,
+        "commit_metadata",
+        0,
+        &buffer,
+    )
+}
+
 fn send_manifest_shard(
     client: &Client,
     endpoints: Arc<Endpoints>,
     api_key: Option<&str>,
+    compression: ManifestCompression,
     section: &str,
     shard_index: u64,
     data: &[u8],
@@ -632,19 +852,14 @@ fn send_manifest_shard(
         return Ok(());
     }
 
-    let mut encoder = Encoder::new(Vec::new(), 0)?;
-    encoder
-        .write_all(data)
-        .context("failed to compress manifest shard")?;
-    let compressed = encoder
-        .finish()
-        .context("failed to finalize manifest shard compression")?;
-
+    let encoded = compress_manifest_shard(data, compression)?;
     let payload = ManifestShardRequest {
         section: section.to_string(),
         shard_index,
-        compressed: true,
-        data: BASE64.encode(compressed),
+        compression,
+        compressed: compression != ManifestCompression::None,
+        sha256: compute_content_hash(&encoded),
+        data: BASE64.encode(encoded),
     };
 
     post_json(client, &endpoints.manifest_shard, api_key, &payload).with_context(|| {
@@ -661,6 +876,30 @@ fn send_manifest_shard(
     Ok(())
 }
 
+fn compress_manifest_shard(data: &[u8], compression: ManifestCompression) -> Result<Vec<u8>> {
+    match compression {
+        ManifestCompression::Zstd => {
+            let mut encoder = Encoder::new(Vec::new(), 0)?;
+            encoder
+                .write_all(data)
+                .context("failed to compress manifest shard")?;
+            encoder
+                .finish()
+                .context("failed to finalize manifest shard compression")
+        }
+        ManifestCompression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder
+                .write_all(data)
+                .context("failed to compress manifest shard")?;
+            encoder
+                .finish()
+                .context("failed to finalize manifest shard compression")
+        }
+        ManifestCompression::None => Ok(data.to_vec()),
+    }
+}
+
 fn post_json<T: Serialize>(
     client: &Client,
     url: &str,
@@ -771,6 +1010,17 @@ struct ChunkNeedRequest {
     hashes: Vec<String>,
 }
 
+#[derive(Serialize)]
+struct BranchHeadRequest<'a> {
+    repository: &'a str,
+    branch: &'a str,
+}
+
+#[derive(Deserialize)]
+struct BranchHeadResponse {
+    commit_sha: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct ChunkNeedResponse {
     missing: Vec<String>,
@@ -800,6 +1050,89 @@ struct ChunkMappingUploadRequest {
 struct ManifestShardRequest {
     section: String,
     shard_index: u64,
+    compression: ManifestCompression,
+    /// Kept for backends that predate `compression`; true for any codec.
     compressed: bool,
     data: String,
+    /// Digest of the encoded bytes, so the backend can reject a shard that
+    /// was truncated in transit.
+    sha256: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::config::IndexerConfig;
+    use crate::engine::Indexer;
+
+    #[test]
+    fn manifest_shards_round_trip_through_each_codec() {
+        let data = b"{\"section\":\"file_pointer\"}\n".repeat(64);
+
+        let zstd = compress_manifest_shard(&data, ManifestCompression::Zstd).unwrap();
+        assert_eq!(zstd::stream::decode_all(zstd.as_slice()).unwrap(), data);
+
+        let gzip = compress_manifest_shard(&data, ManifestCompression::Gzip).unwrap();
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(gzip.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, data);
+
+        assert_eq!(
+            compress_manifest_shard(&data, ManifestCompression::None).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn endpoints_from_the_documented_upload_url_stay_under_the_index_prefix() {
+        let endpoints = Endpoints::new("http://127.0.0.1:8080/api/v1/index/");
+
+        assert_eq!(
+            endpoints.blobs_need,
+            "http://127.0.0.1:8080/api/v1/index/blobs/need"
+        );
+        assert_eq!(
+            endpoints.branch_head,
+            "http://127.0.0.1:8080/api/v1/index/branches/head"
+        );
+    }
+
+    #[test]
+    fn dry_run_summarizes_the_index_without_a_backend() {
+        let checkout = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        git2::Repository::init(checkout.path()).unwrap();
+        fs::write(checkout.path().join("a.rs"), "fn shared() {}\n").unwrap();
+        fs::write(checkout.path().join("b.rs"), "fn shared() {}\n").unwrap();
+        fs::write(checkout.path().join("c.rs"), "fn other() { shared(); }\n").unwrap();
+
+        let config = IndexerConfig::new(
+            checkout.path().to_path_buf(),
+            "acme".to_string(),
+            Some("main".to_string()),
+            "deadbeef".to_string(),
+            output.path().to_path_buf(),
+            None,
+        )
+        .with_untracked()
+        .with_dry_run();
+        let artifacts = Indexer::new(config).run().unwrap();
+
+        let summary = artifacts.summary();
+        assert_eq!(summary.file_pointers, 3);
+        assert_eq!(summary.content_blobs, 2);
+        assert_eq!(summary.unique_chunks, artifacts.chunk_count());
+        assert!(summary.symbol_records >= 2);
+        assert!(summary.reference_records >= 1);
+        assert!(summary.chunk_bytes > 0);
+
+        let report = dry_run(None, None, &artifacts, &UploadOptions::default()).unwrap();
+        assert_eq!(report.summary, summary);
+        assert_eq!(report.chunks_already_stored, None);
+        assert!(report.estimated_upload_bytes > summary.chunk_bytes);
+    }
 }
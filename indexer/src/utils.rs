@@ -6,6 +6,8 @@ use sha2::{Digest, Sha256};
 use tracing::warn;
 use tracing_subscriber::{EnvFilter, fmt};
 
+use crate::models::CommitMetadata;
+
 #[derive(Debug, Clone)]
 pub struct RepoMetadata {
     pub commit: String,
@@ -74,6 +76,41 @@ pub fn resolve_repo_metadata(
     })
 }
 
+/// Resolves a revision (SHA, tag or ref) to the full SHA of its commit.
+pub fn resolve_commit(repo_path: &Path, revision: &str) -> Result<String> {
+    let repo = Repository::discover(repo_path)
+        .with_context(|| format!("failed to open git repository at {}", repo_path.display()))?;
+    let commit = repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| {
+            format!(
+                "could not resolve commit {revision} in {}",
+                repo_path.display()
+            )
+        })?;
+    Ok(commit.id().to_string())
+}
+
+/// Author, time and subject of `commit`, or `None` when `repo_path` is not
+/// in a git repository or the repository has no such commit.
+pub fn commit_metadata(repo_path: &Path, repository: &str, commit: &str) -> Option<CommitMetadata> {
+    let repo = Repository::discover(repo_path).ok()?;
+    let commit = repo
+        .revparse_single(commit)
+        .and_then(|object| object.peel_to_commit())
+        .ok()?;
+    let author = commit.author();
+    Some(CommitMetadata {
+        repository: repository.to_string(),
+        commit_sha: commit.id().to_string(),
+        author_name: String::from_utf8_lossy(author.name_bytes()).into_owned(),
+        author_email: String::from_utf8_lossy(author.email_bytes()).into_owned(),
+        committed_at: commit.time().seconds(),
+        subject: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned(),
+    })
+}
+
 pub fn infer_language(path: &Path) -> Option<&'static str> {
     match path
         .extension()
@@ -84,8 +121,10 @@ pub fn infer_language(path: &Path) -> Option<&'static str> {
         Some(ref ext) if matches!(ext.as_str(), "ts" | "tsx") => Some("typescript"),
         Some(ref ext) if matches!(ext.as_str(), "js" | "jsx") => Some("javascript"),
         Some(ref ext) if ext == "py" => Some("python"),
+        Some(ref ext) if ext == "rb" => Some("ruby"),
         Some(ref ext) if ext == "go" => Some("go"),
-        Some(ref ext) if matches!(ext.as_str(), "java" | "kt") => Some("jvm"),
+        Some(ref ext) if ext == "java" => Some("jvm"),
+        Some(ref ext) if matches!(ext.as_str(), "kt" | "kts") => Some("kotlin"),
         Some(ref ext) if matches!(ext.as_str(), "c") => Some("c"),
         Some(ref ext) if matches!(ext.as_str(), "m" | "mm") => Some("objc"),
         Some(ref ext)
@@ -104,6 +143,7 @@ pub fn infer_language(path: &Path) -> Option<&'static str> {
             Some("glsl")
         }
         Some(ref ext) if ext == "php" => Some("php"),
+        Some(ref ext) if ext == "elm" => Some("elm"),
         _ => None,
     }
 }
@@ -127,9 +167,18 @@ pub fn line_count(bytes: &[u8]) -> i32 {
     }
 }
 
+/// The `/`-separated form every stored `file_path` uses. Backslashes become
+/// slashes and a drive prefix such as `C:` is dropped, so a Windows walk or
+/// extractor cannot produce paths that queries and tree listings miss.
 pub fn normalize_relative_path(path: &Path) -> String {
-    path.iter()
-        .map(|component| component.to_string_lossy())
+    let raw = path.to_string_lossy().replace('\\', "/");
+    let without_drive = match raw.as_bytes() {
+        [drive, b':', ..] if drive.is_ascii_alphabetic() => &raw[2..],
+        _ => raw.as_str(),
+    };
+    without_drive
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
         .collect::<Vec<_>>()
         .join("/")
 }
@@ -137,6 +186,9 @@ pub fn normalize_relative_path(path: &Path) -> String {
 pub fn namespace_from_path(language: Option<&str>, relative_path: &Path) -> Option<String> {
     match language {
         Some("rust") => rust_namespace(relative_path),
+        // JVM files name their namespace with a package declaration; a path
+        // hint would nest the package under its own directory.
+        Some("jvm" | "kotlin") => None,
         _ => default_namespace(relative_path),
     }
 }
@@ -223,7 +275,36 @@ pub fn ensure_relative(path: &Path, root: &Path) -> Result<PathBuf> {
 
 #[cfg(test)]
 mod tests {
-    use super::line_count;
+    use std::path::Path;
+
+    use super::{line_count, namespace_from_path, normalize_relative_path};
+
+    #[test]
+    fn windows_paths_normalize_to_forward_slashes() {
+        assert_eq!(
+            normalize_relative_path(Path::new("src\\db\\mod.rs")),
+            "src/db/mod.rs"
+        );
+        assert_eq!(
+            normalize_relative_path(Path::new("C:\\src\\main.rs")),
+            "src/main.rs"
+        );
+        assert_eq!(
+            normalize_relative_path(Path::new("./docs//guide.md")),
+            "docs/guide.md"
+        );
+    }
+
+    #[test]
+    fn jvm_files_take_no_path_namespace() {
+        let path = Path::new("src/main/java/com/acme/shop/Checkout.java");
+        assert_eq!(namespace_from_path(Some("jvm"), path), None);
+        assert_eq!(namespace_from_path(Some("kotlin"), path), None);
+        assert_eq!(
+            namespace_from_path(Some("python"), Path::new("src/pkg/mod.py")).as_deref(),
+            Some("pkg::mod")
+        );
+    }
 
     #[test]
     fn line_count_ignores_single_trailing_newline() {
@@ -8,7 +8,15 @@ use glob::Pattern;
 use tokio::process::Command;
 use tracing::{error, info};
 
-use crate::config::RepoConfig;
+use crate::config::{RepoAuth, RepoConfig};
+
+const TOKEN_USERNAME_ENV: &str = "POINTER_REPOSERVER_GIT_USERNAME";
+const TOKEN_ENV: &str = "POINTER_REPOSERVER_GIT_TOKEN";
+/// Answers git's credential requests from the variables above, so a token
+/// never reaches argv, the mirror's config or the command lines we log.
+const TOKEN_CREDENTIAL_HELPER: &str = "!f() { test \"$1\" = get && \
+     echo \"username=$POINTER_REPOSERVER_GIT_USERNAME\" && \
+     echo \"password=$POINTER_REPOSERVER_GIT_TOKEN\"; }; f";
 
 #[derive(Debug, Clone)]
 pub struct Git {
@@ -96,6 +104,7 @@ impl Git {
             "ensure_mirror.init_bare",
             Some(repo.name.as_str()),
             None,
+            &[],
         )
         .await
         .with_context(|| {
@@ -118,6 +127,7 @@ impl Git {
             "ensure_mirror.remote_add",
             Some(repo.name.as_str()),
             None,
+            &[],
         )
         .await
         .with_context(|| {
@@ -176,9 +186,16 @@ impl Git {
         paths: &RepoPaths,
         branches: &[String],
     ) -> Result<()> {
-        self.fetch_exact_branches(paths, branches, "fetch_branches", Some(repo.name.as_str()))
-            .await
-            .with_context(|| format!("git fetch failed for repo '{}'", repo.name))?;
+        let env = auth_env(repo)?;
+        self.fetch_exact_branches(
+            paths,
+            branches,
+            "fetch_branches",
+            Some(repo.name.as_str()),
+            &env,
+        )
+        .await
+        .with_context(|| format!("git fetch failed for repo '{}'", repo.name))?;
         Ok(())
     }
 
@@ -188,6 +205,7 @@ impl Git {
         branches: &[String],
         operation: &str,
         repo: Option<&str>,
+        env: &[(&str, String)],
     ) -> Result<()> {
         if branches.is_empty() {
             return Ok(());
@@ -208,7 +226,7 @@ impl Git {
             args.push(refspec);
         }
 
-        self.run(args, None, operation, repo, None).await
+        self.run(args, None, operation, repo, None, env).await
     }
 
     pub async fn resolve_branches(
@@ -256,6 +274,7 @@ impl Git {
                 "prepare_worktree.add",
                 Some(repo_name),
                 Some(branch),
+                &[],
             )
             .await
             .with_context(|| {
@@ -273,6 +292,7 @@ impl Git {
             "prepare_worktree.checkout",
             Some(repo_name),
             Some(branch),
+            &[],
         )
         .await
         .with_context(|| {
@@ -289,6 +309,7 @@ impl Git {
             "prepare_worktree.reset_hard",
             Some(repo_name),
             Some(branch),
+            &[],
         )
         .await
         .with_context(|| {
@@ -308,6 +329,7 @@ impl Git {
         repo: &RepoConfig,
     ) -> Result<BTreeMap<String, String>> {
         let mirror = paths.mirror.display().to_string();
+        let env = auth_env(repo)?;
         let output = self
             .run_capture(
                 [
@@ -321,6 +343,7 @@ impl Git {
                 "list_origin_heads",
                 Some(repo.name.as_str()),
                 None,
+                &env,
             )
             .await?;
 
@@ -355,6 +378,7 @@ impl Git {
         operation: &str,
         repo: Option<&str>,
         branch: Option<&str>,
+        env: &[(&str, String)],
     ) -> Result<()>
     where
         I: IntoIterator<Item = S>,
@@ -379,6 +403,7 @@ impl Git {
 
         let mut cmd = Command::new(&self.bin);
         cmd.args(&args_vec);
+        cmd.envs(env.iter().map(|(key, value)| (key, value)));
         if let Some(cwd) = cwd {
             cmd.current_dir(cwd);
         }
@@ -437,6 +462,7 @@ impl Git {
         operation: &str,
         repo: Option<&str>,
         branch: Option<&str>,
+        env: &[(&str, String)],
     ) -> Result<String>
     where
         I: IntoIterator<Item = S>,
@@ -461,6 +487,7 @@ impl Git {
 
         let mut cmd = Command::new(&self.bin);
         cmd.args(&args_vec);
+        cmd.envs(env.iter().map(|(key, value)| (key, value)));
         if let Some(cwd) = cwd {
             cmd.current_dir(cwd);
         }
@@ -513,6 +540,36 @@ impl Git {
     }
 }
 
+/// Environment that makes git authenticate as `repo.auth` says. Values may
+/// hold secrets, so they are only handed to the child process, never logged.
+fn auth_env(repo: &RepoConfig) -> Result<Vec<(&'static str, String)>> {
+    match &repo.auth {
+        None => Ok(Vec::new()),
+        Some(RepoAuth::SshKey(path)) => Ok(vec![(
+            "GIT_SSH_COMMAND",
+            format!(
+                "ssh -i '{}' -o IdentitiesOnly=yes",
+                path.display().to_string().replace('\'', r"'\''")
+            ),
+        )]),
+        Some(RepoAuth::Token { env, username }) => {
+            let token = std::env::var(env)
+                .with_context(|| format!("repo '{}' auth.token_env {env} is not set", repo.name))?;
+            Ok(vec![
+                ("GIT_TERMINAL_PROMPT", "0".to_string()),
+                // The empty helper drops any helpers from the user's config.
+                ("GIT_CONFIG_COUNT", "2".to_string()),
+                ("GIT_CONFIG_KEY_0", "credential.helper".to_string()),
+                ("GIT_CONFIG_VALUE_0", String::new()),
+                ("GIT_CONFIG_KEY_1", "credential.helper".to_string()),
+                ("GIT_CONFIG_VALUE_1", TOKEN_CREDENTIAL_HELPER.to_string()),
+                (TOKEN_USERNAME_ENV, username.clone()),
+                (TOKEN_ENV, token),
+            ])
+        }
+    }
+}
+
 fn select_branches(
     repo: &RepoConfig,
     remote_heads: &BTreeMap<String, String>,
@@ -596,6 +653,7 @@ mod tests {
             per_branch: Vec::new(),
             pre_index_hooks: Vec::new(),
             post_upload_hooks: Vec::new(),
+            auth: None,
         }
     }
 
@@ -661,4 +719,88 @@ mod tests {
 
         fs::remove_dir_all(&temp).expect("remove temp dir");
     }
+
+    /// Collects formatted log lines so tests can inspect what was logged.
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn token_auth_reaches_git_fetch_but_not_the_logs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = std::env::temp_dir().join(format!(
+            "pointer-reposerver-git-auth-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&temp);
+        fs::create_dir_all(&temp).expect("create temp dir");
+        // Stands in for git and records how it was invoked.
+        let spy = temp.join("git");
+        fs::write(
+            &spy,
+            "#!/bin/sh\nprintf '%s\\n' \"$@\" > \"$(dirname \"$0\")/args\"\nenv > \"$(dirname \"$0\")/env\"\n",
+        )
+        .expect("write spy");
+        fs::set_permissions(&spy, fs::Permissions::from_mode(0o755)).expect("chmod spy");
+
+        let token_var = format!("POINTER_REPOSERVER_TEST_TOKEN_{}", std::process::id());
+        let token = "s3cr3t-token-value";
+        // SAFETY: the variable name is unique to this test and nothing else
+        // reads it.
+        unsafe { std::env::set_var(&token_var, token) };
+
+        let mut repo = repo_config(vec!["main"], Vec::new());
+        repo.auth = Some(RepoAuth::Token {
+            env: token_var.clone(),
+            username: "x-access-token".to_string(),
+        });
+        let paths = RepoPaths {
+            mirror: temp.join("mirror.git"),
+            worktrees_root: temp.join("worktrees"),
+        };
+
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let result = {
+            let _guard = tracing::subscriber::set_default(subscriber);
+            Git::new(spy.display().to_string())
+                .fetch_branches(&repo, &paths, &["main".to_string()])
+                .await
+        };
+        unsafe { std::env::remove_var(&token_var) };
+        result.expect("fetch with spy git");
+
+        let args = fs::read_to_string(temp.join("args")).expect("read spy args");
+        let env = fs::read_to_string(temp.join("env")).expect("read spy env");
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).expect("utf8 logs");
+        fs::remove_dir_all(&temp).expect("remove temp dir");
+
+        assert!(args.lines().any(|arg| arg == "fetch"));
+        assert!(!args.contains(token));
+        assert!(
+            env.lines()
+                .any(|line| line == format!("{TOKEN_ENV}={token}"))
+        );
+        assert!(
+            env.lines()
+                .any(|line| line == "GIT_CONFIG_KEY_1=credential.helper")
+        );
+        assert!(logs.contains("git command completed"), "{logs}");
+        assert!(!logs.contains(token), "{logs}");
+    }
 }
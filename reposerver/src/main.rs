@@ -5,6 +5,7 @@ mod indexer;
 mod logging;
 mod scheduler;
 mod state;
+mod webhook;
 
 use std::path::PathBuf;
 
@@ -13,7 +14,9 @@ use clap::Parser;
 use config::AppConfig;
 use logging::init_logging;
 use scheduler::Scheduler;
+use tokio::sync::mpsc;
 use tracing::{error, info};
+use webhook::WebhookListener;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -103,6 +106,8 @@ async fn main() -> Result<()> {
         "configuration validation completed"
     );
 
+    let webhook = cfg.global.webhook.clone();
+    let repos = cfg.repos.clone();
     let scheduler = Scheduler::new(cfg)?;
 
     scheduler
@@ -135,7 +140,14 @@ async fn main() -> Result<()> {
             mode = "forever",
             "running continuously"
         );
-        scheduler.run_forever().await;
+        let (trigger, triggers) = mpsc::unbounded_channel();
+        if let Some(webhook) = webhook {
+            let listener = WebhookListener::bind(&webhook, &repos, trigger)
+                .await
+                .context("failed to start webhook listener")?;
+            tokio::spawn(listener.serve());
+        }
+        scheduler.run_forever(triggers).await;
     }
 
     Ok(())
@@ -4,7 +4,8 @@ use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use tokio::process::Command;
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::{Mutex, Semaphore, mpsc};
+use tokio::task::JoinSet;
 use tracing::{error, info};
 
 use crate::config::{AppConfig, RepoConfig};
@@ -42,7 +43,7 @@ impl Scheduler {
         let state = PersistedState::load(&state_path)?;
 
         Ok(Self {
-            semaphore: Arc::new(Semaphore::new(cfg.global.max_repo_concurrency)),
+            semaphore: Arc::new(Semaphore::new(cfg.global.max_concurrent_indexers)),
             git: Git::new(cfg.global.git_bin.clone()),
             cfg: Arc::new(cfg),
             state_path,
@@ -188,7 +189,11 @@ impl Scheduler {
             let repo = repo.clone();
             let this = self.clone();
             handles.push(tokio::spawn(async move {
-                this.run_repo_cycle(repo).await;
+                let wait_start = Instant::now();
+                let Ok(_permit) = this.semaphore.acquire().await else {
+                    return;
+                };
+                this.run_repo_cycle(repo, wait_start.elapsed()).await;
             }));
         }
 
@@ -207,105 +212,58 @@ impl Scheduler {
         let _ = self.run_global_finish_hook("once", 1).await;
     }
 
-    pub async fn run_forever(&self) {
+    /// Polls repos on their intervals until ctrl-c. Repo names received on
+    /// `triggers` are polled early, after the webhook debounce.
+    pub async fn run_forever(&self, triggers: mpsc::UnboundedReceiver<String>) {
         info!(
             stage = "startup",
             event = "startup.ready",
             mode = "forever",
             repo_count = self.cfg.repos.len(),
+            max_concurrent_indexers = self.cfg.global.max_concurrent_indexers,
             "scheduler starting in forever mode"
         );
 
-        let mut next_due: HashMap<String, Instant> = self
+        let debounce = self
             .cfg
-            .repos
-            .iter()
-            .map(|repo| (repo.name.clone(), Instant::now()))
-            .collect();
-
-        let mut sweep_completed: HashSet<String> = HashSet::new();
-        let mut sweep_id: u64 = 1;
-
-        loop {
-            let now = Instant::now();
-            let mut due_repos = Vec::new();
-
-            for repo in &self.cfg.repos {
-                if let Some(next) = next_due.get(&repo.name) {
-                    if *next <= now {
-                        due_repos.push(repo.clone());
-                    }
-                }
-            }
-
-            if due_repos.is_empty() {
-                let next_wake = next_due
-                    .values()
-                    .min()
-                    .copied()
-                    .unwrap_or_else(|| now + Duration::from_secs(1));
-
-                tokio::select! {
-                    _ = tokio::time::sleep_until(tokio::time::Instant::from_std(next_wake)) => {}
-                    _ = tokio::signal::ctrl_c() => {
-                        info!(stage = "startup", event = "startup.shutdown", "received ctrl-c, shutting down");
-                        return;
-                    }
-                }
-
-                continue;
-            }
-
-            let mut handles = Vec::new();
-            for repo in due_repos {
-                next_due.insert(repo.name.clone(), Instant::now() + repo.interval);
-                let repo_name = repo.name.clone();
-                let this = self.clone();
-                handles.push(tokio::spawn(async move {
-                    this.run_repo_cycle(repo).await;
-                    repo_name
-                }));
-            }
-
-            for handle in handles {
-                match handle.await {
-                    Ok(repo_name) => {
-                        sweep_completed.insert(repo_name);
-                    }
-                    Err(err) => {
-                        error!(
-                            stage = "cycle",
-                            event = "cycle.join",
-                            result = "fail",
-                            error = %err,
-                            "repo cycle task panicked or was cancelled"
-                        );
-                    }
+            .global
+            .webhook
+            .as_ref()
+            .map_or(Duration::ZERO, |webhook| webhook.debounce);
+        let this = self.clone();
+        let finish_hook = self.clone();
+        poll_repos(
+            &self.cfg.repos,
+            Arc::clone(&self.semaphore),
+            triggers,
+            debounce,
+            move |repo, queued_for| {
+                let this = this.clone();
+                async move { this.run_repo_cycle(repo, queued_for).await }
+            },
+            |sweep_id| {
+                let finish_hook = finish_hook.clone();
+                async move {
+                    let _ = finish_hook
+                        .run_global_finish_hook("forever", sweep_id)
+                        .await;
                 }
-            }
-
-            if sweep_completed.len() == self.cfg.repos.len() {
-                let _ = self.run_global_finish_hook("forever", sweep_id).await;
-                sweep_completed.clear();
-                sweep_id = sweep_id.saturating_add(1);
-            }
-        }
+            },
+            async {
+                let _ = tokio::signal::ctrl_c().await;
+            },
+        )
+        .await;
     }
 
-    async fn run_repo_cycle(&self, repo: RepoConfig) {
-        let wait_start = Instant::now();
-        let permit = match self.semaphore.acquire().await {
-            Ok(permit) => permit,
-            Err(_) => return,
-        };
-
+    async fn run_repo_cycle(&self, repo: RepoConfig, queued_for: Duration) {
         let cycle_start = Instant::now();
         info!(
             stage = "cycle",
             event = "cycle.begin",
             repo = %repo.name,
             interval_secs = repo.interval.as_secs(),
-            semaphore_wait_ms = wait_start.elapsed().as_millis(),
+            semaphore_wait_ms = queued_for.as_millis(),
             "starting repo poll cycle"
         );
 
@@ -347,8 +305,6 @@ impl Scheduler {
                 "poll cycle failed"
             ),
         }
-
-        drop(permit);
     }
 
     async fn run_repo_cycle_inner(
@@ -807,6 +763,137 @@ impl Clone for Scheduler {
     }
 }
 
+/// Polls every repo on its own interval until `shutdown` resolves. At most
+/// as many cycles as `permits` holds run at once; repos that come due while
+/// the permits are taken queue for one in the order they came due. A repo is
+/// never polled twice in parallel: if it comes due again while queued or
+/// running, it is polled as soon as that cycle ends.
+///
+/// A repo name received on `triggers` brings that repo's next poll forward to
+/// `debounce` from now, unless it is already due sooner, so a burst of pushes
+/// leads to one extra cycle rather than one per push.
+async fn poll_repos<C, CFut, H, HFut>(
+    repos: &[RepoConfig],
+    permits: Arc<Semaphore>,
+    mut triggers: mpsc::UnboundedReceiver<String>,
+    debounce: Duration,
+    cycle: C,
+    mut on_sweep: H,
+    shutdown: impl Future<Output = ()>,
+) where
+    C: Fn(RepoConfig, Duration) -> CFut + Send + Sync + 'static,
+    CFut: Future<Output = ()> + Send + 'static,
+    H: FnMut(u64) -> HFut,
+    HFut: Future<Output = ()>,
+{
+    let cycle = Arc::new(cycle);
+    let start = tokio::time::Instant::now();
+    let mut next_due: HashMap<String, tokio::time::Instant> = repos
+        .iter()
+        .map(|repo| (repo.name.clone(), start))
+        .collect();
+    let mut running = JoinSet::new();
+    let mut in_flight: HashMap<tokio::task::Id, String> = HashMap::new();
+    let mut sweep_completed: HashSet<String> = HashSet::new();
+    let mut sweep_id: u64 = 1;
+    tokio::pin!(shutdown);
+
+    loop {
+        let now = tokio::time::Instant::now();
+        for repo in repos {
+            if in_flight.values().any(|name| *name == repo.name) {
+                continue;
+            }
+            let Some(due) = next_due.get_mut(&repo.name) else {
+                continue;
+            };
+            if *due > now {
+                continue;
+            }
+            *due = now + repo.interval;
+
+            let repo = repo.clone();
+            let name = repo.name.clone();
+            let permits = Arc::clone(&permits);
+            let cycle = Arc::clone(&cycle);
+            let handle = running.spawn(async move {
+                let Ok(_permit) = permits.acquire_owned().await else {
+                    return;
+                };
+                cycle(repo, now.elapsed()).await;
+            });
+            in_flight.insert(handle.id(), name);
+        }
+
+        let next_wake = next_due
+            .iter()
+            .filter(|(name, _)| !in_flight.values().any(|running| running == *name))
+            .map(|(_, due)| *due)
+            .min();
+
+        tokio::select! {
+            Some(joined) = running.join_next_with_id() => {
+                let id = match &joined {
+                    Ok((id, ())) => *id,
+                    Err(err) => err.id(),
+                };
+                let repo_name = in_flight.remove(&id);
+                match (joined, repo_name) {
+                    (Ok(_), Some(repo_name)) => {
+                        sweep_completed.insert(repo_name);
+                    }
+                    (Ok(_), None) => {}
+                    (Err(err), repo_name) => {
+                        error!(
+                            stage = "cycle",
+                            event = "cycle.join",
+                            repo = repo_name.as_deref().unwrap_or("unknown"),
+                            result = "fail",
+                            error = %err,
+                            "repo cycle task panicked or was cancelled"
+                        );
+                    }
+                }
+
+                if sweep_completed.len() == repos.len() {
+                    on_sweep(sweep_id).await;
+                    sweep_completed.clear();
+                    sweep_id = sweep_id.saturating_add(1);
+                }
+            }
+            Some(repo_name) = triggers.recv() => {
+                let Some(due) = next_due.get_mut(&repo_name) else {
+                    continue;
+                };
+                let triggered_due = tokio::time::Instant::now() + debounce;
+                if triggered_due < *due {
+                    *due = triggered_due;
+                }
+                info!(
+                    stage = "cycle",
+                    event = "cycle.triggered",
+                    repo = %repo_name,
+                    debounce_ms = debounce.as_millis(),
+                    "repo poll requested early"
+                );
+            }
+            _ = tokio::time::sleep_until(next_wake.unwrap_or(now)), if next_wake.is_some() => {}
+            _ = &mut shutdown => {
+                info!(
+                    stage = "startup",
+                    event = "startup.shutdown",
+                    in_flight_count = in_flight.len(),
+                    "received ctrl-c, shutting down"
+                );
+                // Queued repos give up; cycles already running finish.
+                permits.close();
+                while running.join_next().await.is_some() {}
+                return;
+            }
+        }
+    }
+}
+
 fn summarize_output(prefix: &str, stdout: &str, stderr: &str) -> String {
     let out = stdout.lines().last().unwrap_or("").trim();
     let err = stderr.lines().last().unwrap_or("").trim();
@@ -857,3 +944,141 @@ async fn validate_binary_exists(bin: &str) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(name: &str, interval_secs: u64) -> RepoConfig {
+        RepoConfig {
+            name: name.to_string(),
+            url: format!("git@example.com:{name}.git"),
+            interval: Duration::from_secs(interval_secs),
+            branches: vec!["main".to_string()],
+            branch_patterns: Vec::new(),
+            indexer_args: Vec::new(),
+            per_branch: Vec::new(),
+            pre_index_hooks: Vec::new(),
+            post_upload_hooks: Vec::new(),
+            auth: None,
+        }
+    }
+
+    struct Run {
+        repo: String,
+        queued_at: u64,
+        started_at: u64,
+        finished_at: u64,
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn single_permit_indexes_repos_serially_on_their_intervals() {
+        let repos = vec![repo("a", 10), repo("b", 10), repo("c", 25)];
+        let origin = tokio::time::Instant::now();
+        let runs = Arc::new(std::sync::Mutex::new(Vec::<Run>::new()));
+        let mut sweeps = Vec::new();
+
+        let recorded = Arc::clone(&runs);
+        poll_repos(
+            &repos,
+            Arc::new(Semaphore::new(1)),
+            mpsc::unbounded_channel().1,
+            Duration::ZERO,
+            move |repo, queued_for| {
+                let recorded = Arc::clone(&recorded);
+                async move {
+                    let started = tokio::time::Instant::now();
+                    tokio::time::sleep(Duration::from_secs(3)).await;
+                    recorded.lock().unwrap().push(Run {
+                        repo: repo.name,
+                        queued_at: (started - queued_for - origin).as_secs(),
+                        started_at: (started - origin).as_secs(),
+                        finished_at: origin.elapsed().as_secs(),
+                    });
+                }
+            },
+            |sweep_id| {
+                sweeps.push(sweep_id);
+                async {}
+            },
+            tokio::time::sleep(Duration::from_secs(58)),
+        )
+        .await;
+
+        let runs = runs.lock().unwrap();
+        for pair in runs.windows(2) {
+            assert!(
+                pair[1].started_at >= pair[0].finished_at,
+                "{} started at {}s while {} ran until {}s",
+                pair[1].repo,
+                pair[1].started_at,
+                pair[0].repo,
+                pair[0].finished_at
+            );
+        }
+
+        let queued_at = |name: &str| -> Vec<u64> {
+            runs.iter()
+                .filter(|run| run.repo == name)
+                .map(|run| run.queued_at)
+                .collect()
+        };
+        assert_eq!(queued_at("a"), vec![0, 10, 20, 30, 40, 50]);
+        assert_eq!(queued_at("b"), vec![0, 10, 20, 30, 40, 50]);
+        assert_eq!(queued_at("c"), vec![0, 25, 50]);
+        // The cycle running at shutdown still finishes.
+        assert_eq!(runs.last().map(|run| run.finished_at), Some(59));
+        assert_eq!(sweeps, vec![1, 2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn triggered_repos_are_polled_once_per_burst_after_the_debounce() {
+        let repos = vec![repo("a", 100), repo("b", 100)];
+        let origin = tokio::time::Instant::now();
+        let starts = Arc::new(std::sync::Mutex::new(Vec::<(String, u64)>::new()));
+        let (trigger, triggers) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            // A burst of pushes to "a", then one more while its cycle runs.
+            for at in [10, 11, 12, 16] {
+                tokio::time::sleep_until(origin + Duration::from_secs(at)).await;
+                trigger.send("a".to_string()).unwrap();
+            }
+            trigger.send("unknown".to_string()).unwrap();
+        });
+
+        let recorded = Arc::clone(&starts);
+        poll_repos(
+            &repos,
+            Arc::new(Semaphore::new(1)),
+            triggers,
+            Duration::from_secs(5),
+            move |repo, _queued_for| {
+                let recorded = Arc::clone(&recorded);
+                async move {
+                    recorded
+                        .lock()
+                        .unwrap()
+                        .push((repo.name, origin.elapsed().as_secs()));
+                    tokio::time::sleep(Duration::from_secs(3)).await;
+                }
+            },
+            |_| async {},
+            tokio::time::sleep(Duration::from_secs(40)),
+        )
+        .await;
+
+        let starts = starts.lock().unwrap();
+        let started_at = |name: &str| -> Vec<u64> {
+            starts
+                .iter()
+                .filter(|(repo, _)| repo == name)
+                .map(|(_, at)| *at)
+                .collect()
+        };
+        // The pushes at 10-12s share the poll at 15s; the push at 16s lands
+        // mid-cycle and is polled once that cycle has finished.
+        assert_eq!(started_at("a"), vec![0, 15, 21]);
+        assert_eq!(started_at("b"), vec![3]);
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use anyhow::{Context, Result, anyhow};
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::{RepoConfig, WebhookConfig};
+
+/// Payload fields GitHub (`repository`) and GitLab (`project`, `repository`)
+/// use for the pushed repo's remote URLs.
+const URL_FIELDS: &[&str] = &[
+    "clone_url",
+    "ssh_url",
+    "git_url",
+    "html_url",
+    "git_http_url",
+    "git_ssh_url",
+    "web_url",
+    "homepage",
+    "url",
+];
+
+/// A bound webhook listener; `serve` runs it until the process exits.
+pub struct WebhookListener {
+    listener: TcpListener,
+    state: Arc<WebhookState>,
+}
+
+struct WebhookState {
+    secret: String,
+    /// Normalized remote URL to the configured repos fetching it.
+    repos: HashMap<String, Vec<String>>,
+    triggers: mpsc::UnboundedSender<String>,
+}
+
+impl WebhookListener {
+    pub async fn bind(
+        config: &WebhookConfig,
+        repos: &[RepoConfig],
+        triggers: mpsc::UnboundedSender<String>,
+    ) -> Result<Self> {
+        let secret = std::env::var(&config.secret_env)
+            .ok()
+            .filter(|secret| !secret.trim().is_empty())
+            .ok_or_else(|| {
+                anyhow!(
+                    "global.webhook.secret_env names {}, which is unset or empty",
+                    config.secret_env
+                )
+            })?;
+
+        let mut by_url: HashMap<String, Vec<String>> = HashMap::new();
+        for repo in repos {
+            by_url
+                .entry(normalize_remote_url(&repo.url))
+                .or_default()
+                .push(repo.name.clone());
+        }
+
+        let listener = TcpListener::bind(config.bind)
+            .await
+            .with_context(|| format!("failed to bind webhook listener on {}", config.bind))?;
+        info!(
+            stage = "startup",
+            event = "webhook.listen",
+            bind = %config.bind,
+            debounce_ms = config.debounce.as_millis(),
+            "webhook listener ready"
+        );
+
+        Ok(Self {
+            listener,
+            state: Arc::new(WebhookState {
+                secret,
+                repos: by_url,
+                triggers,
+            }),
+        })
+    }
+
+    pub async fn serve(self) {
+        let app = Router::new()
+            .route("/webhook", post(receive_push))
+            .with_state(self.state);
+        if let Err(err) = axum::serve(self.listener, app).await {
+            error!(
+                stage = "webhook",
+                event = "webhook.serve",
+                result = "fail",
+                error = %err,
+                "webhook listener stopped"
+            );
+        }
+    }
+}
+
+async fn receive_push(
+    State(state): State<Arc<WebhookState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, String) {
+    if !verify_request(&headers, &body, &state.secret) {
+        warn!(
+            stage = "webhook",
+            event = "webhook.reject",
+            reason = "signature",
+            "rejected webhook with a missing or invalid secret"
+        );
+        return (
+            StatusCode::UNAUTHORIZED,
+            "invalid webhook secret".to_string(),
+        );
+    }
+    if !is_push_event(&headers) {
+        return (StatusCode::OK, "ignored non-push event".to_string());
+    }
+
+    let urls = match push_repository_urls(&body) {
+        Ok(urls) => urls,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()),
+    };
+    let matched: BTreeSet<&String> = urls
+        .iter()
+        .filter_map(|url| state.repos.get(&normalize_remote_url(url)))
+        .flatten()
+        .collect();
+    if matched.is_empty() {
+        warn!(
+            stage = "webhook",
+            event = "webhook.unmatched",
+            urls = ?urls,
+            "push did not match a configured repo"
+        );
+        return (
+            StatusCode::NOT_FOUND,
+            "no configured repo matches the pushed repository".to_string(),
+        );
+    }
+
+    for name in &matched {
+        info!(
+            stage = "webhook",
+            event = "webhook.trigger",
+            repo = %name,
+            "push received; requesting an early poll"
+        );
+        if state.triggers.send((*name).clone()).is_err() {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "scheduler is shutting down".to_string(),
+            );
+        }
+    }
+    let names: Vec<&str> = matched.iter().map(|name| name.as_str()).collect();
+    (StatusCode::ACCEPTED, format!("queued {}", names.join(", ")))
+}
+
+/// Checks a GitHub `X-Hub-Signature-256` HMAC of the body, or a GitLab
+/// `X-Gitlab-Token`, against the shared secret.
+fn verify_request(headers: &HeaderMap, body: &[u8], secret: &str) -> bool {
+    if let Some(signature) = header(headers, "x-hub-signature-256") {
+        let Some(digest) = signature
+            .strip_prefix("sha256=")
+            .and_then(|digest| hex::decode(digest).ok())
+        else {
+            return false;
+        };
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+        mac.update(body);
+        return mac.verify_slice(&digest).is_ok();
+    }
+    if let Some(token) = header(headers, "x-gitlab-token") {
+        return constant_time_eq(token.as_bytes(), secret.as_bytes());
+    }
+    false
+}
+
+/// Whether the event headers name a branch push. Senders that do not name
+/// the event are taken to be pushes.
+fn is_push_event(headers: &HeaderMap) -> bool {
+    if let Some(event) = header(headers, "x-github-event") {
+        return event == "push";
+    }
+    if let Some(event) = header(headers, "x-gitlab-event") {
+        return event == "Push Hook";
+    }
+    true
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Every remote URL a GitHub or GitLab push payload gives for the pushed
+/// repository.
+fn push_repository_urls(body: &[u8]) -> Result<Vec<String>> {
+    let payload: Value = serde_json::from_slice(body).context("push payload is not valid JSON")?;
+    let mut urls = Vec::new();
+    for object in ["repository", "project"] {
+        let Some(fields) = payload.get(object).and_then(Value::as_object) else {
+            continue;
+        };
+        for url in URL_FIELDS
+            .iter()
+            .filter_map(|field| fields.get(*field).and_then(Value::as_str))
+        {
+            if !url.is_empty() && !urls.iter().any(|seen| seen == url) {
+                urls.push(url.to_string());
+            }
+        }
+    }
+    if urls.is_empty() {
+        return Err(anyhow!("push payload names no repository URL"));
+    }
+    Ok(urls)
+}
+
+/// Reduces a remote URL to lowercase `host/path` without credentials, port
+/// or `.git` suffix, so the https, ssh and scp-style forms of one repo match.
+fn normalize_remote_url(url: &str) -> String {
+    let url = url.trim();
+    let (host, path) = match url.split_once("://") {
+        Some((_, rest)) => {
+            let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+            let host = authority.rsplit('@').next().unwrap_or(authority);
+            (host.split(':').next().unwrap_or(host), path)
+        }
+        // scp-style `git@host:org/repo.git`
+        None => {
+            let rest = url.rsplit_once('@').map_or(url, |(_, rest)| rest);
+            rest.split_once(':').unwrap_or((rest, ""))
+        }
+    };
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    format!("{host}/{path}").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_url_forms_normalize_alike() {
+        for url in [
+            "git@github.com:Org/Pointer.git",
+            "https://github.com/org/pointer",
+            "https://github.com/org/pointer.git/",
+            "ssh://git@github.com:22/org/pointer.git",
+            "git://github.com/org/pointer.git",
+        ] {
+            assert_eq!(normalize_remote_url(url), "github.com/org/pointer", "{url}");
+        }
+        assert_ne!(
+            normalize_remote_url("https://gitlab.com/org/pointer.git"),
+            "github.com/org/pointer"
+        );
+    }
+
+    #[test]
+    fn parses_github_and_gitlab_push_payloads() {
+        let github = br#"{
+            "ref": "refs/heads/main",
+            "repository": {
+                "full_name": "org/pointer",
+                "clone_url": "https://github.com/org/pointer.git",
+                "ssh_url": "git@github.com:org/pointer.git",
+                "html_url": "https://github.com/org/pointer"
+            }
+        }"#;
+        assert_eq!(
+            push_repository_urls(github).unwrap(),
+            vec![
+                "https://github.com/org/pointer.git",
+                "git@github.com:org/pointer.git",
+                "https://github.com/org/pointer",
+            ]
+        );
+
+        let gitlab = br#"{
+            "object_kind": "push",
+            "project": {
+                "git_http_url": "https://gitlab.com/org/pointer.git",
+                "git_ssh_url": "git@gitlab.com:org/pointer.git"
+            },
+            "repository": {
+                "url": "git@gitlab.com:org/pointer.git",
+                "homepage": "https://gitlab.com/org/pointer"
+            }
+        }"#;
+        assert_eq!(
+            push_repository_urls(gitlab).unwrap(),
+            vec![
+                "https://gitlab.com/org/pointer",
+                "git@gitlab.com:org/pointer.git",
+                "https://gitlab.com/org/pointer.git",
+            ]
+        );
+
+        assert!(push_repository_urls(br#"{"zen": "ping"}"#).is_err());
+        assert!(push_repository_urls(b"not json").is_err());
+    }
+
+    #[test]
+    fn verifies_github_signatures_and_gitlab_tokens() {
+        let body = br#"{"ref":"refs/heads/main"}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(body);
+        let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+        let mut github = HeaderMap::new();
+        github.insert("x-hub-signature-256", signature.parse().unwrap());
+        assert!(verify_request(&github, body, "s3cret"));
+        assert!(!verify_request(&github, body, "other"));
+        assert!(!verify_request(&github, b"{}", "s3cret"));
+
+        let mut gitlab = HeaderMap::new();
+        gitlab.insert("x-gitlab-token", "s3cret".parse().unwrap());
+        assert!(verify_request(&gitlab, body, "s3cret"));
+        assert!(!verify_request(&gitlab, body, "s3cret2"));
+
+        assert!(!verify_request(&HeaderMap::new(), body, "s3cret"));
+    }
+
+    #[test]
+    fn only_push_events_trigger_polls() {
+        let mut headers = HeaderMap::new();
+        assert!(is_push_event(&headers));
+        headers.insert("x-github-event", "ping".parse().unwrap());
+        assert!(!is_push_event(&headers));
+        headers.insert("x-github-event", "push".parse().unwrap());
+        assert!(is_push_event(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-gitlab-event", "Merge Request Hook".parse().unwrap());
+        assert!(!is_push_event(&headers));
+        headers.insert("x-gitlab-event", "Push Hook".parse().unwrap());
+        assert!(is_push_event(&headers));
+    }
+}
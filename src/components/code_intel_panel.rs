@@ -1,12 +1,17 @@
+use crate::components::editor_link::OpenInEditorButton;
 use crate::components::path_filter_actions::PathFilterActions;
 use crate::db::{
     SnippetResponse,
     models::{FileReference, SymbolResult as DbSymbolResult},
 };
-use crate::pages::file_viewer::{SymbolInsightsParams, SymbolSearchScope, fetch_symbol_insights};
+use crate::pages::file_viewer::{
+    REFERENCE_PAGE_SIZE, SymbolInsightsParams, SymbolReferencePageParams, SymbolSearchScope,
+    fetch_symbol_insights, fetch_symbol_reference_page, use_viewed_commit,
+};
 use leptos::either::Either;
 use leptos::html::Div;
 use leptos::prelude::*;
+use leptos::task::spawn_local;
 use leptos_router::components::A;
 use serde::{Deserialize, Serialize};
 
@@ -20,7 +25,20 @@ pub struct SymbolInsightsResponse {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SymbolMatch {
     pub definition: DbSymbolResult,
+    /// The first page of references.
     pub references: Vec<SymbolReferenceWithSnippet>,
+    /// References across every page.
+    #[serde(default)]
+    pub total_references: usize,
+    #[serde(default)]
+    pub has_more_references: bool,
+}
+
+/// A further page of a definition's references.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SymbolReferencePage {
+    pub references: Vec<SymbolReferenceWithSnippet>,
+    pub has_more: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -75,12 +93,13 @@ pub fn CodeIntelPanel(
 
     let included_paths_for_resource = included_paths.clone();
     let excluded_paths_for_resource = excluded_paths.clone();
+    let commit = use_viewed_commit(branch);
     let insights_resource = Resource::new(
         move || {
             (
                 selected_symbol.get(),
                 repo.get(),
-                branch.get(),
+                commit.get(),
                 path.get(),
                 scope.get(),
                 language_filter.get(),
@@ -88,11 +107,11 @@ pub fn CodeIntelPanel(
                 excluded_paths_for_resource.get(),
             )
         },
-        |(symbol_opt, repo, branch, path, scope, language, include_paths, excluded_paths)| async move {
+        |(symbol_opt, repo, commit, path, scope, language, include_paths, excluded_paths)| async move {
             if let Some(symbol) = symbol_opt {
                 fetch_symbol_insights(SymbolInsightsParams {
                     repo,
-                    branch,
+                    branch: commit,
                     path,
                     symbol,
                     language,
@@ -470,6 +489,19 @@ pub fn CodeIntelPanel(
                                                             .map(|symbol_match| {
                                                                 let definition = symbol_match.definition;
                                                                 let references = symbol_match.references;
+                                                                let total_references = symbol_match.total_references;
+                                                                let more_params = definition
+                                                                    .line
+                                                                    .filter(|_| symbol_match.has_more_references)
+                                                                    .map(|line| SymbolReferencePageParams {
+                                                                        repository: definition.repository.clone(),
+                                                                        commit_sha: definition.commit_sha.clone(),
+                                                                        fully_qualified: definition.fully_qualified.clone(),
+                                                                        file_path: definition.file_path.clone(),
+                                                                        line,
+                                                                        column: definition.column,
+                                                                        offset: REFERENCE_PAGE_SIZE,
+                                                                    });
                                                                 let definition_language = definition
                                                                     .language
                                                                     .clone()
@@ -509,7 +541,11 @@ pub fn CodeIntelPanel(
                                                                     .unwrap_or_else(|| definition_file_path_for_label.clone());
                                                                 let display_title = display_path.clone();
                                                                 let display_text = display_path.clone();
-                                                                let reference_count = references.len();
+                                                                let reference_count = if more_params.is_some() {
+                                                                    format!("{} of {total_references}", references.len())
+                                                                } else {
+                                                                    references.len().to_string()
+                                                                };
                                                                 let definition_repo = definition.repository.clone();
                                                                 let grouped_references = {
                                                                     let mut groups: Vec<
@@ -570,6 +606,16 @@ pub fn CodeIntelPanel(
                                                                                 excluded_paths=excluded_paths.clone()
                                                                             />
                                                                         </div>
+                                                                        {definition
+                                                                            .signature
+                                                                            .clone()
+                                                                            .map(|signature| {
+                                                                                view! {
+                                                                                    <code class="block mt-1 text-xs font-mono text-slate-700 dark:text-slate-200 whitespace-pre-wrap break-all">
+                                                                                        {signature}
+                                                                                    </code>
+                                                                                }
+                                                                            })}
                                                                         {definition_line
                                                                             .map(|line| {
                                                                                 view! {
@@ -617,6 +663,13 @@ pub fn CodeIntelPanel(
                                                                                                     } else {
                                                                                                         format!("{file_reference_count} matches")
                                                                                                     };
+                                                                                                    // Further pages come from the definition's own file, so that
+                                                                                                    // group is the one that grows.
+                                                                                                    let more_references = more_params
+                                                                                                        .clone()
+                                                                                                        .filter(|params| {
+                                                                                                            repo_name == params.repository && file_path == params.file_path
+                                                                                                        });
                                                                                                     let summary_label = if repo_name == definition_repo {
                                                                                                         file_path.clone()
                                                                                                     } else {
@@ -642,80 +695,26 @@ pub fn CodeIntelPanel(
                                                                                                                 {entries
                                                                                                                     .into_iter()
                                                                                                                     .map(|entry| {
-                                                                                                                        let reference = entry.reference;
-                                                                                                                        let line_number = reference.line.max(1);
-                                                                                                                        let reference_link = format!(
-                                                                                                                            "/repo/{}/tree/{}/{}#L{}",
-                                                                                                                            reference.repository,
-                                                                                                                            reference.commit_sha,
-                                                                                                                            reference.file_path,
-                                                                                                                            line_number,
-                                                                                                                        );
-                                                                                                                        let reference_file_path = reference.file_path.clone();
-                                                                                                                        let reference_title = reference_file_path.clone();
                                                                                                                         view! {
-                                                                                                                            <div class="rounded border border-slate-200 dark:border-slate-800 bg-white/90 dark:bg-slate-950/40 transition-colors overflow-hidden">
-                                                                                                                                <div class="flex items-center justify-between gap-2 px-3 py-2">
-                                                                                                                                    <div class="min-w-0">
-                                                                                                                                        <A
-                                                                                                                                            href=reference_link.clone()
-                                                                                                                                            attr:class="text-xs text-slate-500 dark:text-slate-300 hover:underline block"
-                                                                                                                                            attr:title=reference_title.clone()
-                                                                                                                                        >
-                                                                                                                                            <span class="block text-ellipsis overflow-hidden whitespace-nowrap flex-1 min-w-0">
-                                                                                                                                                {format!(
-                                                                                                                                                    "Line {}  •  Column {}",
-                                                                                                                                                    line_number,
-                                                                                                                                                    reference.column,
-                                                                                                                                                )}
-                                                                                                                                            </span>
-                                                                                                                                        </A>
-                                                                                                                                    </div>
-                                                                                                                                    <PathFilterActions
-                                                                                                                                        path=reference_file_path.clone()
-                                                                                                                                        included_paths=included_paths.clone()
-                                                                                                                                        excluded_paths=excluded_paths.clone()
-                                                                                                                                    />
-                                                                                                                                </div>
-                                                                                                                                {entry
-                                                                                                                                    .snippet
-                                                                                                                                    .map(|snippet| {
-                                                                                                                                        let highlight_line = snippet.highlight_line;
-                                                                                                                                        let start_line = snippet.start_line;
-                                                                                                                                        view! {
-                                                                                                                                            <div class="bg-slate-50/80 dark:bg-slate-900/60 border-t border-slate-200 dark:border-slate-800 px-3 py-2 text-xs font-mono text-slate-900 dark:text-slate-100 overflow-x-auto">
-                                                                                                                                                {snippet
-                                                                                                                                                    .lines
-                                                                                                                                                    .into_iter()
-                                                                                                                                                    .enumerate()
-                                                                                                                                                    .map(|(idx, text)| {
-                                                                                                                                                        let current_line = start_line + idx as u32;
-                                                                                                                                                        let is_highlight = current_line == highlight_line;
-                                                                                                                                                        let display_text = collapse_snippet_whitespace(&text);
-                                                                                                                                                        let row_class = if is_highlight {
-                                                                                                                                                            "flex gap-3 bg-blue-100/80 dark:bg-blue-900/40 rounded px-2 py-1"
-                                                                                                                                                        } else {
-                                                                                                                                                            "flex gap-3 px-2 py-1"
-                                                                                                                                                        };
-                                                                                                                                                        view! {
-                                                                                                                                                            <div class=row_class>
-                                                                                                                                                                <span class="w-12 text-right text-[10px] text-slate-500 dark:text-slate-300">
-                                                                                                                                                                    {current_line}
-                                                                                                                                                                </span>
-                                                                                                                                                                <span class="flex-1 whitespace-nowrap min-w-max">
-                                                                                                                                                                    {display_text}
-                                                                                                                                                                </span>
-                                                                                                                                                            </div>
-                                                                                                                                                        }
-                                                                                                                                                    })
-                                                                                                                                                    .collect_view()}
-                                                                                                                                            </div>
-                                                                                                                                        }
-                                                                                                                                    })}
-                                                                                                                            </div>
+                                                                                                                            <ReferenceEntry
+                                                                                                                                entry=entry
+                                                                                                                                included_paths=included_paths
+                                                                                                                                excluded_paths=excluded_paths
+                                                                                                                            />
                                                                                                                         }
                                                                                                                     })
                                                                                                                     .collect_view()}
+                                                                                                                {more_references
+                                                                                                                    .map(|params| {
+                                                                                                                        view! {
+                                                                                                                            <MoreReferences
+                                                                                                                                params=params
+                                                                                                                                filter=needle.clone()
+                                                                                                                                included_paths=included_paths
+                                                                                                                                excluded_paths=excluded_paths
+                                                                                                                            />
+                                                                                                                        }
+                                                                                                                    })}
                                                                                                             </div>
                                                                                                         </details>
                                                                                                     }
@@ -761,6 +760,170 @@ pub fn CodeIntelPanel(
     }
 }
 
+/// One reference with its snippet, linking to the referencing line.
+#[component]
+fn ReferenceEntry(
+    entry: SymbolReferenceWithSnippet,
+    included_paths: RwSignal<Vec<String>>,
+    excluded_paths: RwSignal<Vec<String>>,
+) -> impl IntoView {
+    let reference = entry.reference;
+    let line_number = reference.line.max(1);
+    let reference_link = format!(
+        "/repo/{}/tree/{}/{}#L{}",
+        reference.repository, reference.commit_sha, reference.file_path, line_number,
+    );
+    let reference_file_path = reference.file_path.clone();
+    let reference_title = reference_file_path.clone();
+    view! {
+        <div class="rounded border border-slate-200 dark:border-slate-800 bg-white/90 dark:bg-slate-950/40 transition-colors overflow-hidden">
+            <div class="flex items-center justify-between gap-2 px-3 py-2">
+                <div class="min-w-0">
+                    <A
+                        href=reference_link.clone()
+                        attr:class="text-xs text-slate-500 dark:text-slate-300 hover:underline block"
+                        attr:title=reference_title.clone()
+                    >
+                        <span class="block text-ellipsis overflow-hidden whitespace-nowrap flex-1 min-w-0">
+                            {format!(
+                                "Line {}  •  Column {}",
+                                line_number,
+                                reference.column,
+                            )}
+                        </span>
+                    </A>
+                </div>
+                <OpenInEditorButton
+                    repo=reference.repository.clone()
+                    path=reference_file_path.clone()
+                    line=Some(line_number.max(1) as u32)
+                    column=Some(reference.column.max(1) as u32)
+                    compact=true
+                />
+                <PathFilterActions
+                    path=reference_file_path.clone()
+                    included_paths=included_paths.clone()
+                    excluded_paths=excluded_paths.clone()
+                />
+            </div>
+            {entry
+                .snippet
+                .map(|snippet| {
+                    let highlight_start = snippet.highlight_start_line;
+                    let highlight_end = snippet.highlight_end_line;
+                    let start_line = snippet.start_line;
+                    view! {
+                        <div class="bg-slate-50/80 dark:bg-slate-900/60 border-t border-slate-200 dark:border-slate-800 px-3 py-2 text-xs font-mono text-slate-900 dark:text-slate-100 overflow-x-auto">
+                            {snippet
+                                .lines
+                                .into_iter()
+                                .enumerate()
+                                .map(|(idx, text)| {
+                                    let current_line = start_line + idx as u32;
+                                    let is_highlight = (highlight_start..=highlight_end)
+                                        .contains(&current_line);
+                                    let display_text = collapse_snippet_whitespace(&text);
+                                    let row_class = if is_highlight {
+                                        "flex gap-3 bg-blue-100/80 dark:bg-blue-900/40 rounded px-2 py-1"
+                                    } else {
+                                        "flex gap-3 px-2 py-1"
+                                    };
+                                    view! {
+                                        <div class=row_class>
+                                            <span class="w-12 text-right text-[10px] text-slate-500 dark:text-slate-300">
+                                                {current_line}
+                                            </span>
+                                            <span class="flex-1 whitespace-nowrap min-w-max">
+                                                {display_text}
+                                            </span>
+                                        </div>
+                                    }
+                                })
+                                .collect_view()}
+                        </div>
+                    }
+                })}
+        </div>
+    }
+}
+
+/// References past a definition's first page, fetched a page at a time when
+/// the user asks for them.
+#[component]
+fn MoreReferences(
+    params: SymbolReferencePageParams,
+    filter: String,
+    included_paths: RwSignal<Vec<String>>,
+    excluded_paths: RwSignal<Vec<String>>,
+) -> impl IntoView {
+    let loaded = RwSignal::new(Vec::<SymbolReferenceWithSnippet>::new());
+    let next_offset = RwSignal::new(params.offset);
+    let has_more = RwSignal::new(true);
+    let loading = RwSignal::new(false);
+    let error = RwSignal::new(None::<String>);
+
+    let load_more = move |_| {
+        if loading.get_untracked() {
+            return;
+        }
+        loading.set(true);
+        let params = SymbolReferencePageParams {
+            offset: next_offset.get_untracked(),
+            ..params.clone()
+        };
+        spawn_local(async move {
+            match fetch_symbol_reference_page(params).await {
+                Ok(page) => {
+                    next_offset.update(|offset| *offset += page.references.len() as u32);
+                    has_more.set(page.has_more && !page.references.is_empty());
+                    loaded.update(|loaded| loaded.extend(page.references));
+                    error.set(None);
+                }
+                Err(err) => error.set(Some(err.to_string())),
+            }
+            loading.set(false);
+        });
+    };
+
+    view! {
+        {move || {
+            loaded
+                .get()
+                .into_iter()
+                .filter(|entry| snippet_matches_filter(entry, &filter))
+                .map(|entry| {
+                    view! {
+                        <ReferenceEntry
+                            entry=entry
+                            included_paths=included_paths
+                            excluded_paths=excluded_paths
+                        />
+                    }
+                })
+                .collect_view()
+        }}
+        <Show when=move || has_more.get()>
+            <button
+                type="button"
+                class="btn btn-xs btn-ghost w-full text-blue-600 dark:text-blue-400"
+                disabled=move || loading.get()
+                on:click=load_more.clone()
+            >
+                {move || if loading.get() { "Loading..." } else { "Load more references" }}
+            </button>
+        </Show>
+        {move || {
+            error
+                .get()
+                .map(|err| {
+                    view! {
+                        <p class="text-xs text-red-500">"Error loading references: " {err}</p>
+                    }
+                })
+        }}
+    }
+}
+
 pub fn snippet_matches_filter(reference: &SymbolReferenceWithSnippet, needle: &str) -> bool {
     if needle.is_empty() {
         return true;
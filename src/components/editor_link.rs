@@ -0,0 +1,490 @@
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+const EDITOR_SETTINGS_KEY: &str = "pointer.editor_settings";
+const TEMPLATE_PLACEHOLDERS: [&str; 3] = ["path", "line", "column"];
+const BLOCKED_SCHEMES: [&str; 4] = ["javascript", "data", "vbscript", "file"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EditorKind {
+    #[default]
+    VsCode,
+    JetBrains,
+    Sublime,
+    Custom,
+}
+
+impl EditorKind {
+    pub const ALL: [EditorKind; 4] = [
+        EditorKind::VsCode,
+        EditorKind::JetBrains,
+        EditorKind::Sublime,
+        EditorKind::Custom,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EditorKind::VsCode => "vs_code",
+            EditorKind::JetBrains => "jet_brains",
+            EditorKind::Sublime => "sublime",
+            EditorKind::Custom => "custom",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditorKind::VsCode => "VS Code",
+            EditorKind::JetBrains => "JetBrains",
+            EditorKind::Sublime => "Sublime Text",
+            EditorKind::Custom => "Custom template",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Self {
+        match value.trim() {
+            "jet_brains" => EditorKind::JetBrains,
+            "sublime" => EditorKind::Sublime,
+            "custom" => EditorKind::Custom,
+            _ => EditorKind::VsCode,
+        }
+    }
+}
+
+/// Client-side editor preferences, persisted in local storage next to the theme.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EditorSettings {
+    #[serde(default)]
+    pub editor: EditorKind,
+    #[serde(default)]
+    pub custom_template: String,
+    /// Local checkout directory for each repository name.
+    #[serde(default)]
+    pub workspace_roots: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditorLinkError {
+    MissingRoot,
+    InvalidTemplate(String),
+}
+
+impl fmt::Display for EditorLinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditorLinkError::MissingRoot => {
+                write!(f, "no local workspace root is set for this repository")
+            }
+            EditorLinkError::InvalidTemplate(msg) => write!(f, "invalid editor template: {msg}"),
+        }
+    }
+}
+
+pub fn load_editor_settings() -> EditorSettings {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(EDITOR_SETTINGS_KEY).ok().flatten())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_editor_settings(settings: &EditorSettings) {
+    let Ok(raw) = serde_json::to_string(settings) else {
+        return;
+    };
+    if let Some(storage) =
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    {
+        if let Err(err) = storage.set_item(EDITOR_SETTINGS_KEY, &raw) {
+            tracing::warn!("failed to persist editor settings: {err:?}");
+        }
+    }
+}
+
+pub fn validate_custom_template(template: &str) -> Result<(), EditorLinkError> {
+    let template = template.trim();
+    if template.is_empty() {
+        return Err(EditorLinkError::InvalidTemplate(
+            "template cannot be empty".to_string(),
+        ));
+    }
+
+    let scheme = template
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| {
+            let mut chars = scheme.chars();
+            chars.next().is_some_and(|ch| ch.is_ascii_alphabetic())
+                && chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+        })
+        .ok_or_else(|| {
+            EditorLinkError::InvalidTemplate(
+                "template must start with a URL scheme such as `myeditor://`".to_string(),
+            )
+        })?;
+    if BLOCKED_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) {
+        return Err(EditorLinkError::InvalidTemplate(format!(
+            "`{scheme}:` URLs are not allowed"
+        )));
+    }
+
+    let mut rest = template;
+    let mut has_path = false;
+    while let Some(open) = rest.find('{') {
+        let after = &rest[open + 1..];
+        let close = after.find('}').ok_or_else(|| {
+            EditorLinkError::InvalidTemplate("unterminated `{` placeholder".to_string())
+        })?;
+        let name = &after[..close];
+        if !TEMPLATE_PLACEHOLDERS.contains(&name) {
+            return Err(EditorLinkError::InvalidTemplate(format!(
+                "unknown placeholder `{{{name}}}`; expected {{path}}, {{line}} or {{column}}"
+            )));
+        }
+        has_path |= name == "path";
+        rest = &after[close + 1..];
+    }
+    if rest.contains('}') {
+        return Err(EditorLinkError::InvalidTemplate(
+            "unmatched `}` in template".to_string(),
+        ));
+    }
+    if !has_path {
+        return Err(EditorLinkError::InvalidTemplate(
+            "template must contain {path}".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Builds the editor protocol URL for `path` inside `repo`, using the local
+/// workspace root the user mapped for that repository.
+pub fn build_editor_url(
+    settings: &EditorSettings,
+    repo: &str,
+    path: &str,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> Result<String, EditorLinkError> {
+    let root = settings
+        .workspace_roots
+        .get(repo)
+        .map(|root| root.trim())
+        .filter(|root| !root.is_empty())
+        .ok_or(EditorLinkError::MissingRoot)?;
+    let absolute = join_workspace_path(root, path);
+    let uri_path = if absolute.starts_with('/') {
+        absolute.clone()
+    } else {
+        format!("/{absolute}")
+    };
+    let line = line.unwrap_or(1).max(1);
+    let column = column.unwrap_or(1).max(1);
+
+    let url = match settings.editor {
+        EditorKind::VsCode => format!("vscode://file{}:{line}:{column}", encode_path(&uri_path)),
+        EditorKind::JetBrains => format!(
+            "idea://open?file={}&line={line}&column={column}",
+            urlencoding::encode(&absolute)
+        ),
+        EditorKind::Sublime => format!(
+            "subl://open?url={}&line={line}&column={column}",
+            urlencoding::encode(&format!("file://{uri_path}"))
+        ),
+        EditorKind::Custom => {
+            validate_custom_template(&settings.custom_template)?;
+            settings
+                .custom_template
+                .trim()
+                .replace("{path}", &encode_path(&absolute))
+                .replace("{line}", &line.to_string())
+                .replace("{column}", &column.to_string())
+        }
+    };
+    Ok(url)
+}
+
+fn join_workspace_path(root: &str, path: &str) -> String {
+    let root = root.replace('\\', "/");
+    let root = root.trim_end_matches('/');
+    let relative = path.replace('\\', "/");
+    let relative = relative.trim_start_matches('/');
+    format!("{root}/{relative}")
+}
+
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/' | b':') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn current_hash_line() -> Option<u32> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    let digits: String = hash
+        .strip_prefix("#L")?
+        .chars()
+        .take_while(|ch| ch.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn open_editor_url(url: &str) {
+    if let Some(window) = web_sys::window() {
+        if let Err(err) = window.location().set_href(url) {
+            tracing::warn!("failed to open editor link: {err:?}");
+        }
+    }
+}
+
+#[component]
+pub fn OpenInEditorButton(
+    repo: String,
+    path: String,
+    #[prop(optional)] line: Option<u32>,
+    #[prop(optional)] column: Option<u32>,
+    #[prop(optional)] compact: bool,
+) -> impl IntoView {
+    let target = StoredValue::new((repo, path));
+    let show_form = RwSignal::new(false);
+    let root_input = RwSignal::new(String::new());
+    let error = RwSignal::new(None::<String>);
+
+    let open = move || {
+        let settings = load_editor_settings();
+        let line = line.or_else(current_hash_line);
+        let result =
+            target.with_value(|(repo, path)| build_editor_url(&settings, repo, path, line, column));
+        match result {
+            Ok(url) => {
+                error.set(None);
+                show_form.set(false);
+                open_editor_url(&url);
+            }
+            Err(EditorLinkError::MissingRoot) => {
+                error.set(None);
+                show_form.set(true);
+            }
+            Err(err) => error.set(Some(err.to_string())),
+        }
+    };
+
+    let save_root = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        let root = root_input.get_untracked().trim().to_string();
+        if root.is_empty() {
+            error.set(Some("Enter the local path of this repository.".to_string()));
+            return;
+        }
+        let mut settings = load_editor_settings();
+        target.with_value(|(repo, _)| {
+            settings.workspace_roots.insert(repo.clone(), root);
+        });
+        save_editor_settings(&settings);
+        open();
+    };
+
+    let button_class = if compact {
+        "inline-flex items-center text-xs text-slate-500 dark:text-slate-300 hover:text-blue-600 dark:hover:text-blue-400 hover:underline"
+    } else {
+        "inline-flex items-center gap-2 text-xs font-semibold border border-slate-300 dark:border-slate-600 rounded-md px-3 py-1.5 bg-white/80 dark:bg-slate-900/50 text-slate-700 dark:text-slate-100 hover:bg-slate-100 dark:hover:bg-slate-800 transition-colors"
+    };
+
+    view! {
+        <div class="inline-flex flex-col gap-1 w-fit">
+            <button
+                class=button_class
+                type="button"
+                title="Open in local editor"
+                on:click=move |_| open()
+            >
+                {if compact { "Editor" } else { "Open in editor" }}
+            </button>
+            <Show when=move || show_form.get() fallback=|| ()>
+                <form class="flex items-center gap-1" on:submit=save_root>
+                    <input
+                        type="text"
+                        class="input input-xs input-bordered font-mono w-48"
+                        placeholder="Local checkout path"
+                        prop:value=move || root_input.get()
+                        on:input=move |ev| root_input.set(event_target_value(&ev))
+                    />
+                    <button type="submit" class="btn btn-xs">
+                        "Save"
+                    </button>
+                    <button
+                        type="button"
+                        class="btn btn-xs btn-ghost"
+                        on:click=move |_| show_form.set(false)
+                    >
+                        "Cancel"
+                    </button>
+                </form>
+            </Show>
+            <Show when=move || error.get().is_some() fallback=|| ()>
+                <span class="text-xs text-red-500">
+                    {move || error.get().unwrap_or_default()}
+                </span>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+pub fn EditorPreferences() -> impl IntoView {
+    let settings = RwSignal::new(EditorSettings::default());
+    let template_draft = RwSignal::new(String::new());
+    let template_error = RwSignal::new(None::<String>);
+
+    Effect::new(move |_| {
+        let loaded = load_editor_settings();
+        template_draft.set(loaded.custom_template.clone());
+        settings.set(loaded);
+    });
+
+    view! {
+        <div class="form-control gap-2">
+            <label class="label-text text-slate-700 dark:text-slate-200">"Open in editor"</label>
+            <select
+                class="select select-sm select-bordered"
+                on:change=move |ev| {
+                    let kind = EditorKind::from_str(&event_target_value(&ev));
+                    settings.update(|s| s.editor = kind);
+                    save_editor_settings(&settings.get_untracked());
+                }
+            >
+                {EditorKind::ALL
+                    .into_iter()
+                    .map(|kind| {
+                        view! {
+                            <option
+                                value=kind.as_str()
+                                selected=move || settings.get().editor == kind
+                            >
+                                {kind.label()}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
+            <Show when=move || settings.get().editor == EditorKind::Custom fallback=|| ()>
+                <input
+                    type="text"
+                    class="input input-sm input-bordered font-mono"
+                    placeholder="myeditor://open?file={path}&line={line}"
+                    prop:value=move || template_draft.get()
+                    on:input=move |ev| template_draft.set(event_target_value(&ev))
+                    on:change=move |_| {
+                        let template = template_draft.get_untracked();
+                        match validate_custom_template(&template) {
+                            Ok(()) => {
+                                template_error.set(None);
+                                settings.update(|s| s.custom_template = template.trim().to_string());
+                                save_editor_settings(&settings.get_untracked());
+                            }
+                            Err(err) => template_error.set(Some(err.to_string())),
+                        }
+                    }
+                />
+                <Show when=move || template_error.get().is_some() fallback=|| ()>
+                    <span class="text-xs text-red-500">
+                        {move || template_error.get().unwrap_or_default()}
+                    </span>
+                </Show>
+            </Show>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(editor: EditorKind, repo: &str, root: &str) -> EditorSettings {
+        let mut settings = EditorSettings {
+            editor,
+            ..EditorSettings::default()
+        };
+        settings
+            .workspace_roots
+            .insert(repo.to_string(), root.to_string());
+        settings
+    }
+
+    #[test]
+    fn vscode_url_joins_root_and_path() {
+        let settings = settings(EditorKind::VsCode, "pointer", "/home/dev/src/pointer/");
+        assert_eq!(
+            build_editor_url(&settings, "pointer", "src/lib.rs", Some(12), Some(4)).unwrap(),
+            "vscode://file/home/dev/src/pointer/src/lib.rs:12:4"
+        );
+    }
+
+    #[test]
+    fn vscode_url_handles_windows_root() {
+        let settings = settings(EditorKind::VsCode, "pointer", r"C:\Users\dev\pointer");
+        assert_eq!(
+            build_editor_url(&settings, "pointer", "src/lib.rs", Some(3), None).unwrap(),
+            "vscode://file/C:/Users/dev/pointer/src/lib.rs:3:1"
+        );
+    }
+
+    #[test]
+    fn jetbrains_url_encodes_spaces() {
+        let settings = settings(
+            EditorKind::JetBrains,
+            "pointer",
+            "/home/dev/My Projects/pointer",
+        );
+        assert_eq!(
+            build_editor_url(&settings, "pointer", "docs/User Guide.md", Some(7), Some(2)).unwrap(),
+            "idea://open?file=%2Fhome%2Fdev%2FMy%20Projects%2Fpointer%2Fdocs%2FUser%20Guide.md&line=7&column=2"
+        );
+    }
+
+    #[test]
+    fn sublime_url_wraps_file_url() {
+        let settings = settings(EditorKind::Sublime, "pointer", r"D:\work space\pointer");
+        assert_eq!(
+            build_editor_url(&settings, "pointer", "src/main.rs", None, None).unwrap(),
+            "subl://open?url=file%3A%2F%2F%2FD%3A%2Fwork%20space%2Fpointer%2Fsrc%2Fmain.rs&line=1&column=1"
+        );
+    }
+
+    #[test]
+    fn custom_template_substitutes_placeholders() {
+        let mut settings = settings(EditorKind::Custom, "pointer", "/srv/my repo");
+        settings.custom_template = "nvim://open?file={path}&line={line}".to_string();
+        assert_eq!(
+            build_editor_url(&settings, "pointer", "a b.rs", Some(9), None).unwrap(),
+            "nvim://open?file=/srv/my%20repo/a%20b.rs&line=9"
+        );
+    }
+
+    #[test]
+    fn custom_template_validation_rejects_bad_templates() {
+        assert!(validate_custom_template("").is_err());
+        assert!(validate_custom_template("no-scheme {path}").is_err());
+        assert!(validate_custom_template("nvim://open?line={line}").is_err());
+        assert!(validate_custom_template("nvim://open?file={path}&x={row}").is_err());
+        assert!(validate_custom_template("nvim://open?file={path").is_err());
+        assert!(validate_custom_template("javascript:alert('{path}')").is_err());
+        assert!(validate_custom_template("nvim://open?file={path}:{line}:{column}").is_ok());
+    }
+
+    #[test]
+    fn missing_root_is_reported() {
+        let settings = settings(EditorKind::VsCode, "other", "/tmp/other");
+        assert_eq!(
+            build_editor_url(&settings, "pointer", "src/lib.rs", None, None),
+            Err(EditorLinkError::MissingRoot)
+        );
+    }
+}
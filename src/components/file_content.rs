@@ -1,18 +1,162 @@
+use crate::db::models::{FileReference, TokenOccurrence};
 #[cfg(feature = "hydrate")]
 use crate::scope_parser::visible_scope_chain;
 use crate::scope_parser::{ScopeBreadcrumb, ScopeInfo, extract_scopes};
-use leptos::html::{Code, Div};
+use leptos::html::{Code, Div, Input};
 use leptos::prelude::*;
 use leptos_router::hooks::use_location;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::rc::Rc;
 use web_sys::wasm_bindgen::JsCast;
 use web_sys::wasm_bindgen::UnwrapThrowExt;
 
 const SYMBOL_HIGHLIGHT_CLASS: &str = "selected-symbol-highlight";
+const SYMBOL_LINK_CLASS: &str = "symbol-link";
+const FIND_MATCH_CLASS: &str = "find-match";
+const FIND_MATCH_ACTIVE_CLASS: &str = "find-match-active";
+/// Find-in-file stops counting here so a one-letter query in a huge
+/// generated file doesn't wrap the whole DOM.
+const MAX_FIND_MATCHES: usize = 1000;
 const BREADCRUMB_BAR_ID: &str = "scope-breadcrumb-bar";
 const CODE_SCROLL_CONTAINER_ID: &str = "code-scroll-container";
 const STICKY_SCROLL_PADDING: f64 = 12.0;
 
+/// What clicking an indexed identifier in the viewer does.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymbolLinkTarget {
+    /// Jump to the symbol's only definition in this file.
+    Line(u32),
+    /// Open the code-intel panel for the symbol.
+    Insights,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolLink {
+    pub token: TokenOccurrence,
+    pub target: SymbolLinkTarget,
+}
+
+/// Splits `content` into identifier-shaped tokens. Lines and columns are
+/// 1-based and columns count `char`s, like the indexer's references.
+pub fn identifier_tokens(content: &str) -> Vec<TokenOccurrence> {
+    let is_identifier_char = |ch: char| ch.is_alphanumeric() || ch == '_';
+    let mut tokens = Vec::new();
+    for (line_idx, line) in content.lines().enumerate() {
+        let mut chars = line.chars().enumerate().peekable();
+        while let Some((start, ch)) = chars.next() {
+            if !is_identifier_char(ch) {
+                continue;
+            }
+            let mut token = String::from(ch);
+            while let Some((_, next)) = chars.next_if(|(_, next)| is_identifier_char(*next)) {
+                token.push(next);
+            }
+            if ch.is_numeric() {
+                continue;
+            }
+            tokens.push(TokenOccurrence {
+                length: token.chars().count() as u32,
+                token,
+                line: line_idx as u32 + 1,
+                column: start as u32 + 1,
+            });
+        }
+    }
+    tokens
+}
+
+/// Keeps the tokens that sit exactly where the index recorded a symbol of the
+/// same name. A reference jumps to its definition when the file defines that
+/// name exactly once; definitions and everything else open insights.
+pub fn link_symbol_tokens(
+    tokens: &[TokenOccurrence],
+    references: &[FileReference],
+) -> Vec<SymbolLink> {
+    let is_definition = |reference: &FileReference| reference.kind.as_deref() == Some("definition");
+    let mut by_position = HashMap::new();
+    let mut definition_lines: HashMap<&str, Vec<u32>> = HashMap::new();
+    for reference in references {
+        let (Ok(line), Ok(column)) = (
+            u32::try_from(reference.line),
+            u32::try_from(reference.column),
+        ) else {
+            continue;
+        };
+        let slot = by_position.entry((line, column)).or_insert(reference);
+        if is_definition(reference) {
+            *slot = reference;
+            let lines = definition_lines.entry(reference.name.as_str()).or_default();
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+    }
+
+    tokens
+        .iter()
+        .filter_map(|token| {
+            let reference = by_position.get(&(token.line, token.column))?;
+            if reference.name != token.token {
+                return None;
+            }
+            let target = match definition_lines
+                .get(token.token.as_str())
+                .map(Vec::as_slice)
+            {
+                Some([line]) if !is_definition(reference) => SymbolLinkTarget::Line(*line),
+                _ => SymbolLinkTarget::Insights,
+            };
+            Some(SymbolLink {
+                token: token.clone(),
+                target,
+            })
+        })
+        .collect()
+}
+
+/// A find-in-file hit. Lines and columns are 1-based and columns count
+/// `char`s, like [`TokenOccurrence`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FindMatch {
+    pub line: u32,
+    pub column: u32,
+    pub length: u32,
+}
+
+/// Non-overlapping occurrences of `needle` in the raw file text, in reading
+/// order and capped at `MAX_FIND_MATCHES`.
+pub fn find_in_content(content: &str, needle: &str, case_sensitive: bool) -> Vec<FindMatch> {
+    let needle: Vec<char> = needle.chars().collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let same =
+        |a: char, b: char| a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()));
+    let mut matches = Vec::new();
+    for (line_idx, line) in content.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut start = 0;
+        while start + needle.len() <= chars.len() {
+            let window = &chars[start..start + needle.len()];
+            if !window.iter().zip(&needle).all(|(a, b)| same(*a, *b)) {
+                start += 1;
+                continue;
+            }
+            matches.push(FindMatch {
+                line: line_idx as u32 + 1,
+                column: start as u32 + 1,
+                length: needle.len() as u32,
+            });
+            if matches.len() == MAX_FIND_MATCHES {
+                return matches;
+            }
+            start += needle.len();
+        }
+    }
+    matches
+}
+
 #[component]
 pub fn FileContent(
     html: String,
@@ -20,6 +164,7 @@ pub fn FileContent(
     selected_symbol: RwSignal<Option<String>>,
     content: String,
     language: Option<String>,
+    symbol_links: Vec<SymbolLink>,
 ) -> impl IntoView {
     let code_ref = NodeRef::<Code>::new();
     let scroll_container_ref = NodeRef::<Div>::new();
@@ -32,6 +177,34 @@ pub fn FileContent(
     );
     let scopes_collapsed = RwSignal::new(false);
 
+    let find_open = RwSignal::new(false);
+    let find_query = RwSignal::new(String::new());
+    let find_case_sensitive = RwSignal::new(false);
+    let find_current = RwSignal::new(0usize);
+    let find_input_ref = NodeRef::<Input>::new();
+    let find_matches = {
+        let content = content.clone();
+        Memo::new(move |_| {
+            if !find_open.get() {
+                return Vec::new();
+            }
+            find_query.with(|query| find_in_content(&content, query, find_case_sensitive.get()))
+        })
+    };
+    let step_find = move |forward: bool| {
+        let total = find_matches.with(Vec::len);
+        if total == 0 {
+            return;
+        }
+        find_current.update(|current| {
+            *current = if forward {
+                (*current + 1) % total
+            } else {
+                (*current + total - 1) % total
+            };
+        });
+    };
+
     let code_ref = code_ref.clone();
     Effect::new(move |_| {
         use leptos::leptos_dom::helpers::window_event_listener;
@@ -72,6 +245,79 @@ pub fn FileContent(
         on_cleanup(move || handle.remove());
     });
 
+    // Ctrl/Cmd+F opens find-in-file; pressing it again while the find input
+    // is focused falls through to the browser's own search.
+    Effect::new(move |_| {
+        use leptos::leptos_dom::helpers::window_event_listener;
+        let handle =
+            window_event_listener(leptos::ev::keydown, move |ev: web_sys::KeyboardEvent| {
+                let uses_modifier = ev.ctrl_key() || ev.meta_key();
+                if !uses_modifier || !ev.key().eq_ignore_ascii_case("f") {
+                    return;
+                }
+                let Some(input) = find_input_ref.get_untracked() else {
+                    ev.prevent_default();
+                    find_open.set(true);
+                    return;
+                };
+                let input_focused = document()
+                    .active_element()
+                    .is_some_and(|active| active.is_same_node(Some(input.as_ref())));
+                if !input_focused {
+                    ev.prevent_default();
+                    let _ = input.focus();
+                    input.select();
+                }
+            });
+        on_cleanup(move || handle.remove());
+    });
+
+    Effect::new(move |_| {
+        if let Some(input) = find_input_ref.get() {
+            let _ = input.focus();
+            input.select();
+        }
+    });
+
+    {
+        let code_ref = code_ref.clone();
+        Effect::new(move |_| {
+            let matches = find_matches.get();
+            let Some(code_el) = code_ref.get() else {
+                return;
+            };
+            let element: web_sys::Element = code_el.unchecked_into();
+            let document = document();
+            clear_find_matches(&element);
+            apply_find_matches(&document, &element, &matches);
+            find_current.set(0);
+            activate_find_match(&element, 0);
+        });
+    }
+
+    {
+        let code_ref = code_ref.clone();
+        Effect::new(move |_| {
+            let current = find_current.get();
+            if let Some(code_el) = code_ref.get_untracked() {
+                let element: web_sys::Element = code_el.unchecked_into();
+                activate_find_match(&element, current);
+            }
+        });
+    }
+
+    let on_find_keydown = move |ev: leptos::ev::KeyboardEvent| match ev.key().as_str() {
+        "Enter" => {
+            ev.prevent_default();
+            step_find(!ev.shift_key());
+        }
+        "Escape" => {
+            ev.prevent_default();
+            find_open.set(false);
+        }
+        _ => {}
+    };
+
     let on_mouse_up = {
         let selected_symbol = selected_symbol.clone();
         move |_event: leptos::ev::MouseEvent| {
@@ -100,6 +346,36 @@ pub fn FileContent(
         }
     };
 
+    // Links that jump to a definition carry an href and go through the router;
+    // the rest open the code-intel panel like a selection would.
+    let on_click = {
+        let selected_symbol = selected_symbol.clone();
+        move |event: leptos::ev::MouseEvent| {
+            let link = event
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+                .and_then(|element| {
+                    element
+                        .closest(&format!(".{SYMBOL_LINK_CLASS}"))
+                        .ok()
+                        .flatten()
+                });
+            if let Some(link) = link.filter(|link| !link.has_attribute("href")) {
+                selected_symbol.set(link.get_attribute("data-symbol"));
+            }
+        }
+    };
+
+    {
+        let code_ref = code_ref.clone();
+        Effect::new(move |_| {
+            if let Some(code_el) = code_ref.get() {
+                let element: web_sys::Element = code_el.unchecked_into();
+                apply_symbol_links(&document(), &element, &symbol_links);
+            }
+        });
+    }
+
     {
         let code_ref = code_ref.clone();
         let selected_symbol = selected_symbol.clone();
@@ -183,6 +459,67 @@ pub fn FileContent(
                     collapsed=scopes_collapsed.clone()
                 />
             </Show>
+            <Show when=move || find_open.get() fallback=|| ()>
+                <div class="fixed bottom-4 right-4 z-40 flex items-center gap-2 rounded-md border border-gray-200 dark:border-gray-700 bg-white/95 dark:bg-gray-900/95 px-2 py-1.5 text-xs text-gray-700 dark:text-gray-200 shadow-lg backdrop-blur">
+                    <input
+                        type="text"
+                        class="w-48 px-2 py-1 text-sm rounded border border-gray-300 dark:border-gray-600 bg-white dark:bg-gray-950 text-gray-900 dark:text-gray-100 focus-visible:outline focus-visible:outline-2 focus-visible:outline-sky-600 dark:focus-visible:outline-sky-400"
+                        placeholder="Find in file"
+                        node_ref=find_input_ref
+                        prop:value=move || find_query.get()
+                        on:input=move |ev| find_query.set(event_target_value(&ev))
+                        on:keydown=on_find_keydown
+                    />
+                    <span class="min-w-[4.5rem] text-center tabular-nums text-gray-500 dark:text-gray-400">
+                        {move || {
+                            let total = find_matches.with(Vec::len);
+                            if find_query.with(String::is_empty) {
+                                String::new()
+                            } else if total == 0 {
+                                "No results".to_string()
+                            } else {
+                                let capped = if total == MAX_FIND_MATCHES { "+" } else { "" };
+                                format!("{} of {total}{capped}", find_current.get() + 1)
+                            }
+                        }}
+                    </span>
+                    <button
+                        class="px-1.5 py-0.5 rounded hover:bg-gray-100 dark:hover:bg-gray-800"
+                        title="Previous match (Shift+Enter)"
+                        on:click=move |_| step_find(false)
+                    >
+                        "↑"
+                    </button>
+                    <button
+                        class="px-1.5 py-0.5 rounded hover:bg-gray-100 dark:hover:bg-gray-800"
+                        title="Next match (Enter)"
+                        on:click=move |_| step_find(true)
+                    >
+                        "↓"
+                    </button>
+                    <button
+                        class=move || {
+                            if find_case_sensitive.get() {
+                                "px-1.5 py-0.5 rounded font-semibold bg-sky-100 text-sky-800 dark:bg-sky-900 dark:text-sky-100"
+                            } else {
+                                "px-1.5 py-0.5 rounded hover:bg-gray-100 dark:hover:bg-gray-800"
+                            }
+                        }
+                        title="Match case"
+                        aria-pressed=move || find_case_sensitive.get().to_string()
+                        on:click=move |_| find_case_sensitive.update(|value| *value = !*value)
+                    >
+                        "Aa"
+                    </button>
+                    <button
+                        class="px-1.5 py-0.5 rounded hover:bg-gray-100 dark:hover:bg-gray-800"
+                        title="Close (Escape)"
+                        on:click=move |_| find_open.set(false)
+                    >
+                        "✕"
+                    </button>
+                </div>
+            </Show>
             <div
                 id=CODE_SCROLL_CONTAINER_ID
                 class="relative rounded-md"
@@ -205,7 +542,12 @@ pub fn FileContent(
                             })
                             .collect_view()}
                     </div>
-                    <div class="flex-grow" tabindex="0" on:mouseup=on_mouse_up>
+                    <div
+                        class="flex-grow"
+                        tabindex="0"
+                        on:mouseup=on_mouse_up
+                        on:click=on_click
+                    >
                         <code id="code-content" inner_html=html node_ref=code_ref />
                     </div>
                     <LineHighlighter />
@@ -594,6 +936,197 @@ fn apply_symbol_highlights(document: &web_sys::Document, root: &web_sys::Element
     highlight_text_nodes(document, &root_node, needle);
 }
 
+fn apply_symbol_links(document: &web_sys::Document, root: &web_sys::Element, links: &[SymbolLink]) {
+    let already_linked = matches!(
+        root.query_selector(&format!(".{SYMBOL_LINK_CLASS}")),
+        Ok(Some(_))
+    );
+    if links.is_empty() || already_linked {
+        return;
+    }
+    for link in links {
+        let selector = format!("[data-line='{}']", link.token.line);
+        if let Ok(Some(line_el)) = root.query_selector(&selector) {
+            wrap_symbol_link(document, &line_el, link);
+        }
+    }
+}
+
+/// Wraps the token's characters in an anchor. Tokens split across highlight
+/// spans are left alone.
+fn wrap_symbol_link(document: &web_sys::Document, line_el: &web_sys::Element, link: &SymbolLink) {
+    let start = link.token.column.saturating_sub(1) as usize;
+    let end = start + link.token.length as usize;
+    let mut nodes = Vec::new();
+    text_nodes(line_el, &mut nodes);
+
+    let mut offset = 0;
+    for node in nodes {
+        let value = node.node_value().unwrap_or_default();
+        let len = value.chars().count();
+        if start >= offset + len {
+            offset += len;
+            continue;
+        }
+        if start < offset || end > offset + len {
+            return;
+        }
+        // DOM offsets count UTF-16 code units.
+        let utf16_offset = |chars: usize| {
+            value
+                .chars()
+                .take(chars)
+                .map(char::len_utf16)
+                .sum::<usize>() as u32
+        };
+        let Ok(range) = document.create_range() else {
+            return;
+        };
+        if range
+            .set_start(&node, utf16_offset(start - offset))
+            .is_err()
+            || range.set_end(&node, utf16_offset(end - offset)).is_err()
+        {
+            return;
+        }
+        let Ok(anchor) = document.create_element("a") else {
+            return;
+        };
+        anchor.set_class_name(SYMBOL_LINK_CLASS);
+        let _ = anchor.set_attribute("data-symbol", &link.token.token);
+        if let SymbolLinkTarget::Line(line) = link.target {
+            let _ = anchor.set_attribute("href", &format!("#L{line}"));
+        }
+        let _ = range.surround_contents(&anchor);
+        return;
+    }
+}
+
+fn text_nodes(node: &web_sys::Node, out: &mut Vec<web_sys::Node>) {
+    let mut child_opt = node.first_child();
+    while let Some(child) = child_opt {
+        match child.node_type() {
+            web_sys::Node::TEXT_NODE => out.push(child.clone()),
+            web_sys::Node::ELEMENT_NODE => text_nodes(&child, out),
+            _ => {}
+        }
+        child_opt = child.next_sibling();
+    }
+}
+
+/// Unwraps find marks in place, keeping anything nested inside them such as
+/// symbol links or highlights.
+fn clear_find_matches(root: &web_sys::Element) {
+    let Ok(nodes) = root.query_selector_all(&format!(".{FIND_MATCH_CLASS}")) else {
+        return;
+    };
+    for idx in 0..nodes.length() {
+        let Some(mark) = nodes.item(idx) else {
+            continue;
+        };
+        let Some(parent) = mark.parent_node() else {
+            continue;
+        };
+        while let Some(child) = mark.first_child() {
+            let _ = parent.insert_before(&child, Some(&mark));
+        }
+        let _ = parent.remove_child(&mark);
+    }
+    let root_node: web_sys::Node = root.clone().into();
+    root_node.normalize();
+}
+
+/// Marks each match in its rendered line. A match that crosses highlight
+/// spans gets one mark per text node, all sharing the match's index.
+fn apply_find_matches(
+    document: &web_sys::Document,
+    root: &web_sys::Element,
+    matches: &[FindMatch],
+) {
+    let mut line_el: Option<(u32, web_sys::Element)> = None;
+    for (index, found) in matches.iter().enumerate() {
+        if line_el.as_ref().is_none_or(|(line, _)| *line != found.line) {
+            let selector = format!("[data-line='{}']", found.line);
+            line_el = root
+                .query_selector(&selector)
+                .ok()
+                .flatten()
+                .map(|element| (found.line, element));
+        }
+        let Some((_, line)) = line_el.as_ref() else {
+            continue;
+        };
+
+        let start = found.column.saturating_sub(1) as usize;
+        let end = start + found.length as usize;
+        let mut nodes = Vec::new();
+        text_nodes(line, &mut nodes);
+        let mut segments = Vec::new();
+        let mut offset = 0;
+        for node in nodes {
+            let value = node.node_value().unwrap_or_default();
+            let len = value.chars().count();
+            let (from, to) = (start.max(offset), end.min(offset + len));
+            if from < to {
+                // DOM offsets count UTF-16 code units.
+                let utf16_offset = |chars: usize| {
+                    value
+                        .chars()
+                        .take(chars)
+                        .map(char::len_utf16)
+                        .sum::<usize>() as u32
+                };
+                segments.push((node, utf16_offset(from - offset), utf16_offset(to - offset)));
+            }
+            offset += len;
+            if offset >= end {
+                break;
+            }
+        }
+        for (node, from, to) in segments {
+            let Ok(range) = document.create_range() else {
+                return;
+            };
+            if range.set_start(&node, from).is_err() || range.set_end(&node, to).is_err() {
+                continue;
+            }
+            let Ok(mark) = document.create_element("mark") else {
+                return;
+            };
+            mark.set_class_name(FIND_MATCH_CLASS);
+            let _ = mark.set_attribute("data-find-match", &index.to_string());
+            let _ = range.surround_contents(&mark);
+        }
+    }
+}
+
+fn activate_find_match(root: &web_sys::Element, index: usize) {
+    if let Ok(active) = root.query_selector_all(&format!(".{FIND_MATCH_ACTIVE_CLASS}")) {
+        for idx in 0..active.length() {
+            if let Some(element) = active
+                .item(idx)
+                .and_then(|node| node.dyn_into::<web_sys::Element>().ok())
+            {
+                let _ = element.class_list().remove_1(FIND_MATCH_ACTIVE_CLASS);
+            }
+        }
+    }
+    let Ok(marks) = root.query_selector_all(&format!("[data-find-match='{index}']")) else {
+        return;
+    };
+    for idx in 0..marks.length() {
+        if let Some(element) = marks
+            .item(idx)
+            .and_then(|node| node.dyn_into::<web_sys::Element>().ok())
+        {
+            let _ = element.class_list().add_1(FIND_MATCH_ACTIVE_CLASS);
+            if idx == 0 {
+                scroll_with_sticky_offset(&element);
+            }
+        }
+    }
+}
+
 #[component]
 pub fn LineHighlighter() -> impl IntoView {
     let location = use_location();
@@ -642,3 +1175,110 @@ pub fn LineHighlighter() -> impl IntoView {
     // This component doesn't render anything itself
     view! { <div id="mehigh" class="hidden"></div> }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(name: &str, kind: &str, line: i32, column: i32) -> FileReference {
+        FileReference {
+            repository: "repo".to_string(),
+            commit_sha: "abc".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            namespace: None,
+            name: name.to_string(),
+            kind: Some(kind.to_string()),
+            line,
+            column,
+        }
+    }
+
+    #[test]
+    fn identifier_tokens_use_character_columns() {
+        let tokens = identifier_tokens("let é = 42;\n  call(é, x_1)");
+        let summary: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.token.as_str(), t.line, t.column, t.length))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("let", 1, 1, 3),
+                ("é", 1, 5, 1),
+                ("call", 2, 3, 4),
+                ("é", 2, 8, 1),
+                ("x_1", 2, 11, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_in_content_matches_raw_lines_case_insensitively_by_default() {
+        let content = "let Foo = foo();\n// FOOfoo é\nnone here";
+        let summary = |matches: Vec<FindMatch>| -> Vec<(u32, u32, u32)> {
+            matches
+                .iter()
+                .map(|found| (found.line, found.column, found.length))
+                .collect()
+        };
+
+        assert_eq!(
+            summary(find_in_content(content, "foo", false)),
+            [(1, 5, 3), (1, 11, 3), (2, 4, 3), (2, 7, 3)]
+        );
+        assert_eq!(
+            summary(find_in_content(content, "foo", true)),
+            [(1, 11, 3), (2, 7, 3)]
+        );
+        // Columns count characters, and matches on a line don't overlap.
+        assert_eq!(summary(find_in_content(content, "É", false)), [(2, 11, 1)]);
+        assert_eq!(
+            summary(find_in_content("aaaa", "aa", false)),
+            [(1, 1, 2), (1, 3, 2)]
+        );
+        assert!(find_in_content(content, "", false).is_empty());
+    }
+
+    #[test]
+    fn tokens_link_to_in_file_definitions_or_insights() {
+        let content = "fn helper() {}\nfn main() { helper(); other(); }\nfn dup() {}\nfn dup() {}\nfn run() { dup(); stale(); }";
+        let references = vec![
+            reference("helper", "definition", 1, 4),
+            reference("main", "definition", 2, 4),
+            reference("helper", "reference", 2, 13),
+            reference("other", "reference", 2, 23),
+            reference("dup", "definition", 3, 4),
+            reference("dup", "definition", 4, 4),
+            reference("run", "definition", 5, 4),
+            reference("dup", "reference", 5, 12),
+            // Recorded somewhere the content no longer has it.
+            reference("stale", "reference", 5, 1),
+        ];
+
+        let links = link_symbol_tokens(&identifier_tokens(content), &references);
+        let summary: Vec<_> = links
+            .iter()
+            .map(|link| {
+                (
+                    link.token.token.as_str(),
+                    link.token.line,
+                    link.target.clone(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                ("helper", 1, SymbolLinkTarget::Insights),
+                ("main", 2, SymbolLinkTarget::Insights),
+                ("helper", 2, SymbolLinkTarget::Line(1)),
+                ("other", 2, SymbolLinkTarget::Insights),
+                ("dup", 3, SymbolLinkTarget::Insights),
+                ("dup", 4, SymbolLinkTarget::Insights),
+                ("run", 5, SymbolLinkTarget::Insights),
+                ("dup", 5, SymbolLinkTarget::Insights),
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use crate::db::TreeEntry;
-use crate::pages::file_viewer::{FileViewerData, get_file_viewer_data};
+use crate::pages::file_viewer::{FileViewerData, get_file_viewer_data, use_viewed_commit};
+use leptos::either::Either;
+use leptos::html::Div;
 use leptos::prelude::*;
 use leptos_router::components::A;
 use std::collections::HashSet;
@@ -44,12 +46,56 @@ pub fn DirectoryIcon() -> impl IntoView {
     }
 }
 
+#[component]
+pub fn SymlinkIcon() -> impl IntoView {
+    view! {
+        <svg
+            xmlns="http://www.w3.org/2000/svg"
+            class="h-5 w-5 text-gray-500"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="currentColor"
+        >
+            <path
+                stroke-linecap="round"
+                stroke-linejoin="round"
+                stroke-width="2"
+                d="M13.828 10.172a4 4 0 00-5.656 0l-4 4a4 4 0 105.656 5.656l1.102-1.101m-.758-4.899a4 4 0 005.656 0l4-4a4 4 0 00-5.656-5.656l-1.1 1.1"
+            ></path>
+        </svg>
+    }
+}
+
+/// The directories that must be expanded for `path` to show in the tree,
+/// outermost first: `src/db/mod.rs` yields `src` and `src/db`.
+pub fn ancestor_dirs(path: &str) -> Vec<String> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    (1..segments.len())
+        .map(|depth| segments[..depth].join("/"))
+        .collect()
+}
+
+/// Expands every ancestor of `path`. Each newly expanded node fetches its own
+/// children, so deeper levels load as their parents arrive.
+pub fn reveal_path(expanded: RwSignal<HashSet<String>>, path: &str) {
+    let ancestors = ancestor_dirs(path);
+    if ancestors
+        .iter()
+        .all(|dir| expanded.with_untracked(|dirs| dirs.contains(dir)))
+    {
+        return;
+    }
+    expanded.update(|dirs| dirs.extend(ancestors));
+}
+
 #[component]
 pub fn FileTreeNodes(
     entries: Vec<TreeEntry>,
     repo: Signal<String>,
     branch: Signal<String>,
     expanded: RwSignal<HashSet<String>>,
+    /// Path to highlight and scroll into view once its row renders.
+    reveal: Signal<Option<String>>,
 ) -> impl IntoView {
     view! {
         <ul class="pl-4">
@@ -57,7 +103,15 @@ pub fn FileTreeNodes(
                 each=move || entries.clone()
                 key=|child| child.path.clone()
                 children=move |child| {
-                    view! { <FileTreeNode entry=child repo=repo branch=branch expanded=expanded /> }
+                    view! {
+                        <FileTreeNode
+                            entry=child
+                            repo=repo
+                            branch=branch
+                            expanded=expanded
+                            reveal=reveal
+                        />
+                    }
                 }
             />
         </ul>
@@ -71,8 +125,10 @@ pub fn FileTreeNode(
     repo: Signal<String>,
     branch: Signal<String>,
     expanded: RwSignal<HashSet<String>>,
+    reveal: Signal<Option<String>>,
 ) -> impl IntoView {
     let is_dir = entry.kind == "dir";
+    let row_ref = NodeRef::<Div>::new();
     let children: RwSignal<Option<Vec<TreeEntry>>> = RwSignal::new(None);
 
     let path = entry.path.clone();
@@ -80,13 +136,14 @@ pub fn FileTreeNode(
     let dir_path = entry.path.clone();
     let child_entry = entry.clone();
     let expand_entry = entry.clone();
+    let commit = use_viewed_commit(branch);
     let child_resource = Resource::new(
-        move || (is_dir, expanded.get().contains(&path), repo(), branch()),
-        move |(is_dir, is_expanded, repo, branch)| {
+        move || (is_dir, expanded.get().contains(&path), repo(), commit()),
+        move |(is_dir, is_expanded, repo, commit)| {
             let entry = child_entry.clone();
             async move {
                 if is_dir && is_expanded {
-                    return get_file_viewer_data(repo, branch, Some(entry.path.clone() + "/"))
+                    return get_file_viewer_data(repo, commit, Some(entry.path.clone() + "/"))
                         .await
                         .ok();
                 }
@@ -116,12 +173,34 @@ pub fn FileTreeNode(
         }
     };
 
+    let reveal_target = entry.path.clone();
+    let is_revealed =
+        Memo::new(move |_| reveal.with(|target| target.as_deref() == Some(reveal_target.as_str())));
+    Effect::new(move |_| {
+        if !is_revealed.get() {
+            return;
+        }
+        if let Some(row) = row_ref.get() {
+            let options = web_sys::ScrollIntoViewOptions::new();
+            options.set_block(web_sys::ScrollLogicalPosition::Nearest);
+            row.scroll_into_view_with_scroll_into_view_options(&options);
+        }
+    });
+
     let link = move || format!("/repo/{}/tree/{}/{}", repo.get(), branch.get(), link_path);
+    let row_class = move || {
+        if is_revealed.get() {
+            "flex items-center cursor-pointer py-1 rounded bg-blue-50 dark:bg-gray-700"
+        } else {
+            "flex items-center cursor-pointer py-1"
+        }
+    };
 
     view! {
         <li>
             <div
-                class="flex items-center cursor-pointer py-1"
+                node_ref=row_ref
+                class=row_class
                 on:click=on_click
                 // Use a normal link for files, but handle dirs with the on:click
                 role=if is_dir { "button" } else { "" }
@@ -142,8 +221,14 @@ pub fn FileTreeNode(
                         .into_any()
                 } else {
                     let name = entry.name.clone();
+                    let executable = entry.executable;
+                    let icon = if entry.kind == "symlink" {
+                        Either::Left(view! { <SymlinkIcon /> })
+                    } else {
+                        Either::Right(view! { <FileIcon /> })
+                    };
                     view! {
-                        <FileIcon />
+                        {icon}
                         <span class="w-4"></span>
                         <A
                             href=link
@@ -152,6 +237,17 @@ pub fn FileTreeNode(
                         >
                             {entry.name}
                         </A>
+                        {executable
+                            .then(|| {
+                                view! {
+                                    <span
+                                        class="ml-1 text-xs text-gray-500 dark:text-gray-400"
+                                        title="Executable"
+                                    >
+                                        "*"
+                                    </span>
+                                }
+                            })}
                     }
                         .into_any()
                 }}
@@ -170,6 +266,7 @@ pub fn FileTreeNode(
                                             repo=repo
                                             branch=branch
                                             expanded=expanded
+                                            reveal=reveal
                                         />
                                     }
                                         .into_any()
@@ -180,3 +277,16 @@ pub fn FileTreeNode(
         </li>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ancestor_dirs_lists_each_parent_outermost_first() {
+        assert_eq!(ancestor_dirs("src/db/mod.rs"), ["src", "src/db"]);
+        assert_eq!(ancestor_dirs("/src//db/"), ["src"]);
+        assert!(ancestor_dirs("README.md").is_empty());
+        assert!(ancestor_dirs("").is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::components::editor_link::EditorPreferences;
 use crate::components::search_bar::SearchBar;
 use leptos::leptos_dom::helpers::window_event_listener;
 use leptos::tachys::dom::event_target_checked;
@@ -137,6 +138,11 @@ pub fn Header() -> impl IntoView {
                                 </div>
                             </div>
                         </li>
+                        <li>
+                            <div class="p-2">
+                                <EditorPreferences />
+                            </div>
+                        </li>
                     </ul>
                 </details>
             </div>
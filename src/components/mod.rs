@@ -1,17 +1,21 @@
 pub mod breadcrumbs;
 pub mod code_intel_panel;
+pub mod editor_link;
 pub mod file_content;
 pub mod file_tree;
 pub mod header;
 pub mod path_filter_actions;
 pub mod quick_navigator;
 pub mod repo_list;
+pub mod repo_overview;
 pub mod search_bar;
+pub mod service_unavailable;
 
 pub use breadcrumbs::{Breadcrumbs, CopyPathButton};
 pub use code_intel_panel::{
     CodeIntelPanel, SymbolInsightsResponse, SymbolMatch, SymbolReferenceWithSnippet,
 };
+pub use editor_link::{EditorPreferences, OpenInEditorButton};
 pub use file_content::{
     FileContent, LineHighlighter, ScopeBreadcrumbBar, scroll_with_sticky_offset,
 };
@@ -20,4 +24,6 @@ pub use header::Header;
 pub use path_filter_actions::PathFilterActions;
 pub use quick_navigator::FileQuickNavigator;
 pub use repo_list::RepositoriesList;
+pub use repo_overview::RepoOverviewCard;
 pub use search_bar::SearchBar;
+pub use service_unavailable::ServiceUnavailable;
@@ -1,5 +1,8 @@
 use crate::components::file_tree::{DirectoryIcon, FileIcon};
-use crate::pages::file_viewer::search_repo_paths;
+use crate::db::models::FileSymbolSuggestion;
+use crate::pages::file_viewer::{search_repo_paths, use_viewed_commit};
+use crate::services::search_service::{FILE_SYMBOL_SUGGESTION_LIMIT, autocomplete_symbols_in_file};
+use crate::utils::path_location::parse_path_location;
 use leptos::either::Either;
 use leptos::html::Div;
 use leptos::prelude::*;
@@ -11,7 +14,8 @@ use web_sys::wasm_bindgen::JsCast;
 pub fn FileQuickNavigator(repo: Signal<String>, branch: Signal<String>) -> impl IntoView {
     let (query, set_query) = signal(String::new());
     let repo_for_search = repo.clone();
-    let branch_for_search = branch.clone();
+    // Search the snapshot being viewed rather than whatever the branch points at now.
+    let commit_for_search = use_viewed_commit(branch);
     let params = use_params::<crate::pages::file_viewer::FileViewerParams>();
     let container_ref = NodeRef::<Div>::new();
 
@@ -37,14 +41,61 @@ pub fn FileQuickNavigator(repo: Signal<String>, branch: Signal<String>) -> impl
         on_cleanup(move || handle.remove());
     });
 
+    let open_file = Memo::new(move |_| {
+        params
+            .read()
+            .as_ref()
+            .ok()
+            .and_then(|p| p.path.clone())
+            .filter(|path| !path.is_empty() && !path.ends_with('/'))
+    });
+    // With a file open, an `@` query jumps to one of its definitions instead.
+    let symbol_term = Memo::new(move |_| {
+        open_file.get()?;
+        query
+            .get()
+            .trim_start()
+            .strip_prefix('@')
+            .map(|term| term.trim().to_string())
+    });
+
     let search_resource = Resource::new(
-        move || (repo_for_search(), branch_for_search(), query.get()),
-        |(repo, branch, query)| async move {
-            let trimmed = query.trim().to_string();
-            if trimmed.is_empty() {
+        move || (repo_for_search(), commit_for_search(), query.get()),
+        move |(repo, commit, query)| async move {
+            // A pasted `path:line` jumps straight to that line, so search for the path alone.
+            let trimmed = match parse_path_location(&query) {
+                Some(location) => location.path,
+                None => query.trim().to_string(),
+            };
+            if trimmed.is_empty() || symbol_term.get_untracked().is_some() {
                 Ok(Vec::new())
             } else {
-                search_repo_paths(repo, branch, trimmed, Some(10)).await
+                search_repo_paths(repo, commit, trimmed, Some(10)).await
+            }
+        },
+    );
+    let symbol_resource = Resource::new(
+        move || {
+            (
+                repo_for_search(),
+                commit_for_search(),
+                open_file.get(),
+                symbol_term.get(),
+            )
+        },
+        |(repo, commit, path, term)| async move {
+            match (path, term) {
+                (Some(path), Some(term)) => {
+                    autocomplete_symbols_in_file(
+                        repo,
+                        commit,
+                        path,
+                        term,
+                        FILE_SYMBOL_SUGGESTION_LIMIT,
+                    )
+                    .await
+                }
+                _ => Ok(Vec::new()),
             }
         },
     );
@@ -54,12 +105,31 @@ pub fn FileQuickNavigator(repo: Signal<String>, branch: Signal<String>) -> impl
             <input
                 type="text"
                 class="w-full px-3 py-2 text-sm rounded-md border border-slate-200 dark:border-slate-700 bg-white/95 text-slate-900 dark:bg-slate-950/60 dark:text-slate-100 focus-visible:outline focus-visible:outline-2 focus-visible:outline-sky-600 dark:focus-visible:outline-sky-400"
-                placeholder="Go to file..."
+                placeholder=move || {
+                    if open_file.get().is_some() {
+                        "Go to file... (@ for symbols)"
+                    } else {
+                        "Go to file..."
+                    }
+                }
                 prop:value=query
                 on:input=move |ev| set_query.set(event_target_value(&ev))
             />
             <Show when=move || !query.get().trim().is_empty() fallback=|| ()>
                 <div class="absolute left-0 right-0 z-30 mt-1 bg-white/95 dark:bg-slate-950/85 border border-slate-200 dark:border-slate-800 rounded-md shadow-lg text-slate-900 dark:text-slate-100">
+                    <Show
+                        when=move || symbol_term.get().is_none()
+                        fallback=move || {
+                            view! {
+                                <FileSymbolResults
+                                    symbols=symbol_resource
+                                    repo=repo
+                                    branch=branch
+                                    path=open_file.into()
+                                />
+                            }
+                        }
+                    >
                     <Suspense fallback=move || {
                         view! {
                             <div class="px-3 py-2 text-sm text-slate-600 dark:text-slate-300">
@@ -82,6 +152,7 @@ pub fn FileQuickNavigator(repo: Signal<String>, branch: Signal<String>) -> impl
                                         } else {
                                             let current_repo = repo.get();
                                             let current_branch = branch.get();
+                                            let location = parse_path_location(&query.get());
                                             view! {
                                                 <ul class="divide-y divide-slate-200 dark:divide-slate-800">
                                                     {entries
@@ -96,6 +167,11 @@ pub fn FileQuickNavigator(repo: Signal<String>, branch: Signal<String>) -> impl
                                                             );
                                                             if entry.kind == "dir" {
                                                                 href.push('/');
+                                                            } else if let Some(location) = location
+                                                                .as_ref()
+                                                                .filter(|location| location.path == entry.path)
+                                                            {
+                                                                href.push_str(&format!("#L{}", location.line));
                                                             }
                                                             let mut display_path = entry.path.clone();
                                                             if entry.kind == "dir" && !display_path.ends_with('/') {
@@ -142,9 +218,97 @@ pub fn FileQuickNavigator(repo: Signal<String>, branch: Signal<String>) -> impl
                                 .unwrap_or_else(|| view! { <div></div> }.into_any())
                         }}
                     </Suspense>
+                    </Show>
                 </div>
             </Show>
         </div>
     }
     .into_any()
 }
+
+#[component]
+fn FileSymbolResults(
+    symbols: Resource<Result<Vec<FileSymbolSuggestion>, ServerFnError>>,
+    repo: Signal<String>,
+    branch: Signal<String>,
+    path: Signal<Option<String>>,
+) -> impl IntoView {
+    view! {
+        <Suspense fallback=move || {
+            view! {
+                <div class="px-3 py-2 text-sm text-slate-600 dark:text-slate-300">
+                    "Searching symbols..."
+                </div>
+            }
+        }>
+            {move || {
+                symbols
+                    .get()
+                    .map(|result| match result {
+                        Ok(symbols) if symbols.is_empty() => {
+                            view! {
+                                <div class="px-3 py-2 text-sm text-slate-600 dark:text-slate-300">
+                                    "No symbols in this file"
+                                </div>
+                            }
+                                .into_any()
+                        }
+                        Ok(symbols) => {
+                            let file_href = format!(
+                                "/repo/{}/tree/{}/{}",
+                                repo.get(),
+                                branch.get(),
+                                path.get().unwrap_or_default(),
+                            );
+                            view! {
+                                <ul class="divide-y divide-slate-200 dark:divide-slate-800">
+                                    {symbols
+                                        .into_iter()
+                                        .map(|symbol| {
+                                            let href = format!("{file_href}#L{}", symbol.line);
+                                            view! {
+                                                <li>
+                                                    <A
+                                                        href=href
+                                                        attr:class="flex items-center justify-between gap-2 px-3 py-2 text-sm hover:bg-slate-100 dark:hover:bg-slate-800 transition-colors rounded-md text-slate-900 dark:text-slate-100"
+                                                    >
+                                                        <div class="flex flex-col min-w-0">
+                                                            <span class="font-medium font-mono truncate">
+                                                                {symbol.name}
+                                                            </span>
+                                                            {symbol
+                                                                .namespace
+                                                                .map(|namespace| {
+                                                                    view! {
+                                                                        <span class="text-xs text-slate-600 dark:text-slate-300 truncate">
+                                                                            {namespace}
+                                                                        </span>
+                                                                    }
+                                                                })}
+                                                        </div>
+                                                        <span class="text-xs text-slate-500 dark:text-slate-400 shrink-0">
+                                                            {format!("L{}", symbol.line)}
+                                                        </span>
+                                                    </A>
+                                                </li>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </ul>
+                            }
+                                .into_any()
+                        }
+                        Err(e) => {
+                            view! {
+                                <div class="px-3 py-2 text-sm text-red-500">
+                                    {"Error: "} {e.to_string()}
+                                </div>
+                            }
+                                .into_any()
+                        }
+                    })
+                    .unwrap_or_else(|| view! { <div></div> }.into_any())
+            }}
+        </Suspense>
+    }
+}
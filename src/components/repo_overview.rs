@@ -0,0 +1,144 @@
+use leptos::either::Either;
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::db::models::RepoOverview;
+use crate::utils::repo_overview::format_byte_len;
+
+/// "What's in this repo" card built from index data: top-level directories,
+/// the biggest files, likely entry points and build manifests.
+#[component]
+pub fn RepoOverviewCard(repo: String, branch: String, overview: RepoOverview) -> impl IntoView {
+    let tree_link = move |path: &str| format!("/repo/{repo}/tree/{branch}/{path}");
+
+    let directories = overview
+        .directories
+        .into_iter()
+        .map(|dir| {
+            let (label, href) = if dir.name.is_empty() {
+                ("(root files)".to_string(), None)
+            } else {
+                (format!("{}/", dir.name), Some(tree_link(&format!("{}/", dir.name))))
+            };
+            let files = if dir.file_count == 1 {
+                "1 file".to_string()
+            } else {
+                format!("{} files", dir.file_count)
+            };
+            view! {
+                <li class="flex items-center justify-between gap-3 py-1">
+                    {match href {
+                        Some(href) => {
+                            Either::Left(
+                                view! {
+                                    <A href=href attr:class="font-mono text-blue-600 hover:underline truncate">
+                                        {label}
+                                    </A>
+                                },
+                            )
+                        }
+                        None => {
+                            Either::Right(
+                                view! {
+                                    <span class="font-mono text-gray-600 dark:text-gray-400 truncate">
+                                        {label}
+                                    </span>
+                                },
+                            )
+                        }
+                    }}
+                    <span class="text-xs text-gray-500 dark:text-gray-400 whitespace-nowrap">
+                        {files}
+                        {dir.dominant_language.map(|lang| format!(" · {lang}"))}
+                    </span>
+                </li>
+            }
+        })
+        .collect_view();
+
+    let largest_files = overview
+        .largest_files
+        .into_iter()
+        .map(|file| {
+            view! {
+                <li class="flex items-center justify-between gap-3 py-1">
+                    <A href=tree_link(&file.path) attr:class="font-mono text-blue-600 hover:underline truncate">
+                        {file.path.clone()}
+                    </A>
+                    <span class="text-xs text-gray-500 dark:text-gray-400 whitespace-nowrap">
+                        {format_byte_len(file.byte_len)}
+                    </span>
+                </li>
+            }
+        })
+        .collect_view();
+
+    let has_entry_points = !overview.entry_points.is_empty();
+    let entry_points = overview
+        .entry_points
+        .into_iter()
+        .map(|path| {
+            view! {
+                <li class="py-1">
+                    <A href=tree_link(&path) attr:class="font-mono text-blue-600 hover:underline">
+                        {path.clone()}
+                    </A>
+                </li>
+            }
+        })
+        .collect_view();
+
+    let has_build_files = !overview.build_files.is_empty();
+    let build_files = overview
+        .build_files
+        .into_iter()
+        .map(|build| {
+            view! {
+                <li class="flex items-center justify-between gap-3 py-1">
+                    <A href=tree_link(&build.path) attr:class="font-mono text-blue-600 hover:underline truncate">
+                        {build.path.clone()}
+                    </A>
+                    <span class="text-xs text-gray-500 dark:text-gray-400 whitespace-nowrap">
+                        {build.system}
+                    </span>
+                </li>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div class="bg-white dark:bg-gray-800 rounded-lg shadow p-6 border border-gray-200 dark:border-gray-700 mb-6">
+            <h2 class="text-lg font-semibold mb-4 text-gray-800 dark:text-gray-200">
+                "What's in this repository"
+            </h2>
+            <div class="grid grid-cols-1 md:grid-cols-2 gap-6 text-sm">
+                <section>
+                    <h3 class="font-semibold text-gray-700 dark:text-gray-300 mb-2">"Directories"</h3>
+                    <ul class="divide-y divide-gray-100 dark:divide-gray-700">{directories}</ul>
+                </section>
+                <section>
+                    <h3 class="font-semibold text-gray-700 dark:text-gray-300 mb-2">"Largest files"</h3>
+                    <ul class="divide-y divide-gray-100 dark:divide-gray-700">{largest_files}</ul>
+                </section>
+                {has_entry_points
+                    .then(|| {
+                        view! {
+                            <section>
+                                <h3 class="font-semibold text-gray-700 dark:text-gray-300 mb-2">"Entry points"</h3>
+                                <ul>{entry_points}</ul>
+                            </section>
+                        }
+                    })}
+                {has_build_files
+                    .then(|| {
+                        view! {
+                            <section>
+                                <h3 class="font-semibold text-gray-700 dark:text-gray-300 mb-2">"Build files"</h3>
+                                <ul class="divide-y divide-gray-100 dark:divide-gray-700">{build_files}</ul>
+                            </section>
+                        }
+                    })}
+            </div>
+        </div>
+    }
+}
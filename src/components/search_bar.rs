@@ -1,11 +1,17 @@
-use crate::db::models::SymbolSuggestion;
+use crate::db::models::{FileLocationMatch, SymbolSuggestion};
 use crate::dsl::{TextSearchRequest, tokenize_for_autocomplete};
 use crate::services::search_service::{
-    autocomplete_branches, autocomplete_files, autocomplete_languages, autocomplete_paths,
-    autocomplete_repositories, autocomplete_symbols,
+    BRANCH_SUGGESTION_LIMIT, FILE_SUGGESTION_LIMIT, LANGUAGE_SUGGESTION_LIMIT,
+    MAX_RENDERED_SUGGESTIONS, PATH_SUGGESTION_LIMIT, REPO_SUGGESTION_LIMIT,
+    SYMBOL_SUGGESTION_LIMIT, autocomplete_branches, autocomplete_files, autocomplete_languages,
+    autocomplete_paths, autocomplete_repositories, autocomplete_symbols, find_file_locations,
+};
+use crate::utils::path_location::{
+    LocationOutcome, PathLocation, location_outcome, location_url, split_location_query,
 };
 use leptos::either::Either;
 use leptos::prelude::*;
+use leptos::task::spawn_local;
 use leptos_router::hooks::use_navigate;
 use std::rc::Rc;
 use web_sys;
@@ -23,28 +29,65 @@ pub fn SearchBar(
     let on_complete_cb = on_complete.clone();
     let has_interacted = RwSignal::new(false);
 
-    let on_search = move || {
-        let q = query.get().trim().to_string();
-        if !q.is_empty() {
-            let encoded = urlencoding::encode(&q);
-            let url = format!("/search?q={}&page=1", encoded);
-            if open_in_new_tab {
-                let opened_in_new_tab = web_sys::window()
-                    .and_then(|w| w.open_with_url_and_target(&url, "_blank").ok())
-                    .flatten()
-                    .is_some();
-
-                if !opened_in_new_tab {
-                    navigate(&url, Default::default());
-                }
-            } else {
+    let open_url = move |url: String| {
+        if open_in_new_tab {
+            let opened_in_new_tab = web_sys::window()
+                .and_then(|w| w.open_with_url_and_target(&url, "_blank").ok())
+                .flatten()
+                .is_some();
+
+            if !opened_in_new_tab {
                 navigate(&url, Default::default());
             }
+        } else {
+            navigate(&url, Default::default());
+        }
+
+        if let Some(cb) = on_complete_cb.clone() {
+            cb.as_ref()();
+        }
+    };
 
-            if let Some(cb) = on_complete_cb.clone() {
-                cb.as_ref()();
+    // Compiler-style `path:line` input that matched the same file in several
+    // repositories, and the entry the user picked from that list.
+    let location_choices = RwSignal::new(None::<(PathLocation, Vec<FileLocationMatch>)>);
+    let chosen_location = RwSignal::new(None::<String>);
+    Effect::new({
+        let open_url = open_url.clone();
+        move |_| {
+            if let Some(url) = chosen_location.get() {
+                chosen_location.set(None);
+                location_choices.set(None);
+                open_url(url);
             }
         }
+    });
+
+    let on_search = move || {
+        let q = query.get().trim().to_string();
+        if q.is_empty() {
+            return;
+        }
+        let open_url = open_url.clone();
+        match split_location_query(&q) {
+            Some((location, repositories)) => spawn_local(async move {
+                let matches =
+                    find_file_locations(vec![location.path.clone()], repositories.clone())
+                        .await
+                        .unwrap_or_else(|err| {
+                            tracing::warn!("file location lookup failed: {err}");
+                            Vec::new()
+                        });
+                match location_outcome(&location, &repositories, &matches) {
+                    LocationOutcome::Navigate(url) => open_url(url),
+                    LocationOutcome::Choose(matches) => {
+                        location_choices.set(Some((location, matches)))
+                    }
+                    LocationOutcome::Search(query) => open_url(search_url(&query)),
+                }
+            }),
+            None => open_url(search_url(&q)),
+        }
     };
 
     // Focus the input when requested (used by overlay/modal invocations)
@@ -71,7 +114,9 @@ pub fn SearchBar(
             return None;
         }
 
-        match TextSearchRequest::from_query_str(&q) {
+        match TextSearchRequest::from_query_str(&q)
+            .and_then(|request| request.reject_symbol_only_filters())
+        {
             Ok(_) => Some(ValidationState {
                 status: ValidationStatus::Valid,
                 message: None,
@@ -99,7 +144,7 @@ pub fn SearchBar(
         },
         DslHint {
             syntax: "lang:",
-            description: "Search in specific language",
+            description: "Search in languages (lang:rust,go; -lang: excludes)",
         },
         DslHint {
             syntax: "branch:",
@@ -117,6 +162,30 @@ pub fn SearchBar(
             syntax: "historical:",
             description: "Include historical commits (historical:yes)",
         },
+        DslHint {
+            syntax: "fuzzy:",
+            description: "Also match camelCase/snake_case spellings of terms (fuzzy:yes)",
+        },
+        DslHint {
+            syntax: "word:",
+            description: "Match terms only as whole words (word:yes)",
+        },
+        DslHint {
+            syntax: "name_contains:",
+            description: "Only files with a symbol whose name contains this text",
+        },
+        DslHint {
+            syntax: "symbol:",
+            description: "Lines naming a symbol that the file defines or references",
+        },
+        DslHint {
+            syntax: "sym:",
+            description: "Only files that define a symbol (-sym: excludes)",
+        },
+        DslHint {
+            syntax: "path_depth:",
+            description: "Filter by path segment count (path_depth:<=2)",
+        },
     ];
 
     // Example queries for users
@@ -130,7 +199,7 @@ pub fn SearchBar(
     let autocomplete_resource = LocalResource::new(move || {
         let state = autocomplete_state.get();
         async move {
-            let limit = 10;
+            let limit = state.mode.suggestion_limit();
             match state.mode {
                 AutocompleteMode::RepoValue => autocomplete_repositories(state.term, limit)
                     .await
@@ -146,7 +215,7 @@ pub fn SearchBar(
                             ..AutocompleteResults::default()
                         })
                 }
-                AutocompleteMode::Symbol => {
+                AutocompleteMode::Symbol | AutocompleteMode::SymValue => {
                     autocomplete_symbols(state.term, limit)
                         .await
                         .map(|symbols| AutocompleteResults {
@@ -194,6 +263,7 @@ pub fn SearchBar(
                     | AutocompleteMode::LangValue
                     | AutocompleteMode::BranchValue
                     | AutocompleteMode::FileValue
+                    | AutocompleteMode::SymValue
                     | AutocompleteMode::CaseValue
                     | AutocompleteMode::HistoricalValue
                     | AutocompleteMode::None
@@ -352,7 +422,12 @@ pub fn SearchBar(
                     .map(|lang| {
                         let item = SuggestionItem {
                             label: lang.clone(),
-                            replacement: format!("lang:{}", lang),
+                            replacement: format!(
+                                "{}:{}{}",
+                                state.active_key.as_deref().unwrap_or("lang"),
+                                state.value_prefix,
+                                lang
+                            ),
                             subtitle: None,
                             index,
                         };
@@ -405,6 +480,30 @@ pub fn SearchBar(
                     items,
                 });
             }
+            AutocompleteMode::SymValue => {
+                let items = results
+                    .symbols
+                    .into_iter()
+                    .map(|symbol| {
+                        let item = SuggestionItem {
+                            label: symbol.name.clone(),
+                            replacement: format!(
+                                "{}:{}",
+                                state.active_key.as_deref().unwrap_or("sym"),
+                                symbol.name
+                            ),
+                            subtitle: Some(format!("{}/{}", symbol.repository, symbol.file_path)),
+                            index,
+                        };
+                        index += 1;
+                        item
+                    })
+                    .collect();
+                groups.push(SuggestionGroup {
+                    title: "Symbols",
+                    items,
+                });
+            }
             AutocompleteMode::CaseValue => {
                 let term = state.term.to_ascii_lowercase();
                 let options = ["yes", "no", "auto"];
@@ -452,7 +551,7 @@ pub fn SearchBar(
             AutocompleteMode::None => {}
         }
 
-        groups
+        cap_suggestion_groups(groups, MAX_RENDERED_SUGGESTIONS)
     });
 
     let flat_suggestions = Memo::new(move |_| {
@@ -469,6 +568,7 @@ pub fn SearchBar(
     let on_input = move |ev| {
         set_query.set(event_target_value(&ev));
         active_index.set(None);
+        location_choices.set(None);
     };
 
     let apply_selection = {
@@ -728,6 +828,17 @@ pub fn SearchBar(
 
                             view! {
                                 <div class="p-3 text-sm text-gray-600 dark:text-gray-300">
+                                    {move || {
+                                        location_choices
+                                            .get()
+                                            .map(|(location, matches)| {
+                                                render_location_choices(
+                                                    location,
+                                                    matches,
+                                                    chosen_location,
+                                                )
+                                            })
+                                    }}
                                     <For
                                         each=move || other_groups.clone()
                                         key=|group| group.title
@@ -780,16 +891,40 @@ enum AutocompleteMode {
     LangValue,
     BranchValue,
     FileValue,
+    /// The name after `sym:`, completed from symbol suggestions.
+    SymValue,
     CaseValue,
     HistoricalValue,
     Symbol,
 }
 
+impl AutocompleteMode {
+    /// Suggestions to fetch for this mode; modes served without a server
+    /// round trip fetch none.
+    fn suggestion_limit(&self) -> i64 {
+        match self {
+            AutocompleteMode::RepoValue => REPO_SUGGESTION_LIMIT,
+            AutocompleteMode::PathValue => PATH_SUGGESTION_LIMIT,
+            AutocompleteMode::FileValue => FILE_SUGGESTION_LIMIT,
+            AutocompleteMode::LangValue => LANGUAGE_SUGGESTION_LIMIT,
+            AutocompleteMode::BranchValue => BRANCH_SUGGESTION_LIMIT,
+            AutocompleteMode::Symbol | AutocompleteMode::SymValue => SYMBOL_SUGGESTION_LIMIT,
+            AutocompleteMode::None
+            | AutocompleteMode::DslKey
+            | AutocompleteMode::CaseValue
+            | AutocompleteMode::HistoricalValue => 0,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq)]
 struct AutocompleteState {
     mode: AutocompleteMode,
     term: String,
     active_key: Option<String>,
+    /// Values already listed before the one being completed, e.g. `rust,`
+    /// in `lang:rust,g`.
+    value_prefix: String,
     repo_filters: Vec<String>,
     active_start: usize,
 }
@@ -818,6 +953,20 @@ struct SuggestionGroup {
     items: Vec<SuggestionItem>,
 }
 
+/// Keeps the first `max` suggestions across groups, in display order. Item
+/// indices run across groups, so the survivors keep theirs.
+fn cap_suggestion_groups(groups: Vec<SuggestionGroup>, max: usize) -> Vec<SuggestionGroup> {
+    let mut remaining = max;
+    groups
+        .into_iter()
+        .map(|mut group| {
+            group.items.truncate(remaining);
+            remaining -= group.items.len();
+            group
+        })
+        .collect()
+}
+
 fn render_group_view(
     group: SuggestionGroup,
     active_idx: Option<usize>,
@@ -893,7 +1042,50 @@ fn render_group_view(
     }
 }
 
-const DSL_KEYS: [&str; 8] = [
+fn render_location_choices(
+    location: PathLocation,
+    matches: Vec<FileLocationMatch>,
+    chosen_location: RwSignal<Option<String>>,
+) -> impl IntoView {
+    let rows = matches
+        .into_iter()
+        .map(|found| {
+            let url = location_url(&found, location.line);
+            let target = format!("{}:{}", found.file_path, location.line);
+            view! {
+                <div
+                    class="flex items-baseline gap-2 cursor-pointer hover:bg-gray-100 dark:hover:bg-gray-700 p-2 rounded"
+                    on:mousedown=move |ev| {
+                        ev.prevent_default();
+                        chosen_location.set(Some(url.clone()));
+                    }
+                >
+                    <span class="font-mono text-sm text-gray-900 dark:text-gray-100">
+                        {found.repository}
+                    </span>
+                    <span class="text-xs text-gray-500 dark:text-gray-400 truncate">
+                        {format!("{target} @ {}", found.branch)}
+                    </span>
+                </div>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div class="mb-3">
+            <p class="text-xs uppercase tracking-wide text-gray-500 dark:text-gray-400 mb-2">
+                "Open file in"
+            </p>
+            <div class="space-y-1">{rows}</div>
+        </div>
+    }
+}
+
+fn search_url(query: &str) -> String {
+    format!("/search?q={}&page=1", urlencoding::encode(query))
+}
+
+const DSL_KEYS: [&str; 12] = [
     "repo:",
     "path:",
     "file:",
@@ -902,6 +1094,10 @@ const DSL_KEYS: [&str; 8] = [
     "regex:",
     "case:",
     "historical:",
+    "name_contains:",
+    "symbol:",
+    "sym:",
+    "path_depth:",
 ];
 
 fn build_autocomplete_state(query: &str) -> AutocompleteState {
@@ -940,6 +1136,7 @@ fn build_autocomplete_state(query: &str) -> AutocompleteState {
     let mut mode = AutocompleteMode::None;
     let mut term = String::new();
     let mut active_key = None;
+    let mut value_prefix = String::new();
 
     if let Some(token) = active_token {
         if token.first_colon_in_quotes {
@@ -960,14 +1157,24 @@ fn build_autocomplete_state(query: &str) -> AutocompleteState {
                 mode = AutocompleteMode::FileValue;
                 term = cleaned.to_string();
                 active_key = Some(key.to_string());
-            } else if key_lc == "lang" || key_lc == "l" {
+            } else if matches!(key_lc.trim_start_matches('-'), "lang" | "l") {
+                // Only the value after the last comma is being typed; the
+                // languages before it are kept in the replacement.
                 mode = AutocompleteMode::LangValue;
-                term = cleaned.to_string();
+                let (listed, current) = cleaned.rsplit_once(',').unwrap_or(("", cleaned));
+                if !listed.is_empty() {
+                    value_prefix = format!("{listed},");
+                }
+                term = current.trim().to_string();
                 active_key = Some(key.to_string());
             } else if key_lc == "branch" || key_lc == "b" {
                 mode = AutocompleteMode::BranchValue;
                 term = cleaned.to_string();
                 active_key = Some(key.to_string());
+            } else if key_lc.trim_start_matches('-') == "sym" {
+                mode = AutocompleteMode::SymValue;
+                term = cleaned.to_string();
+                active_key = Some(key.to_string());
             } else if key_lc == "case" {
                 mode = AutocompleteMode::CaseValue;
                 term = cleaned.to_string();
@@ -998,6 +1205,7 @@ fn build_autocomplete_state(query: &str) -> AutocompleteState {
         mode,
         term,
         active_key,
+        value_prefix,
         repo_filters,
         active_start,
     }
@@ -1074,3 +1282,66 @@ fn apply_autocomplete_replacement(query: &str, active_start: usize, replacement:
         next
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(title: &'static str, start: usize, len: usize) -> SuggestionGroup {
+        SuggestionGroup {
+            title,
+            items: (start..start + len)
+                .map(|index| SuggestionItem {
+                    label: format!("item{index}"),
+                    replacement: format!("item{index}"),
+                    subtitle: None,
+                    index,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn suggestion_limits_follow_the_active_category() {
+        let limit = |query: &str| build_autocomplete_state(query).mode.suggestion_limit();
+        assert_eq!(limit("repo:poi"), REPO_SUGGESTION_LIMIT);
+        assert_eq!(limit("path:src/"), PATH_SUGGESTION_LIMIT);
+        assert_eq!(limit("lang:ru"), LANGUAGE_SUGGESTION_LIMIT);
+        assert_eq!(limit("parse_qu"), SYMBOL_SUGGESTION_LIMIT);
+        assert_eq!(limit("case:"), 0);
+        assert_ne!(REPO_SUGGESTION_LIMIT, PATH_SUGGESTION_LIMIT);
+        assert_ne!(PATH_SUGGESTION_LIMIT, SYMBOL_SUGGESTION_LIMIT);
+    }
+
+    #[test]
+    fn lang_autocomplete_completes_the_last_listed_language() {
+        let state = build_autocomplete_state("needle -lang:rust,g");
+        assert!(state.mode == AutocompleteMode::LangValue);
+        assert_eq!(state.term, "g");
+        assert_eq!(state.value_prefix, "rust,");
+        assert_eq!(state.active_key.as_deref(), Some("-lang"));
+
+        let state = build_autocomplete_state("lang:ru");
+        assert_eq!(state.term, "ru");
+        assert_eq!(state.value_prefix, "");
+    }
+
+    #[test]
+    fn sym_autocomplete_completes_the_symbol_name() {
+        let state = build_autocomplete_state("needle -sym:Pars");
+        assert!(state.mode == AutocompleteMode::SymValue);
+        assert_eq!(state.term, "Pars");
+        assert_eq!(state.active_key.as_deref(), Some("-sym"));
+        assert_eq!(state.mode.suggestion_limit(), SYMBOL_SUGGESTION_LIMIT);
+    }
+
+    #[test]
+    fn capping_suggestions_trims_later_groups_first() {
+        let groups = vec![group("Symbols", 0, 25), group("DSL", 25, 9)];
+        let capped = cap_suggestion_groups(groups, 30);
+
+        assert_eq!(capped[0].items.len(), 25);
+        let dsl: Vec<usize> = capped[1].items.iter().map(|item| item.index).collect();
+        assert_eq!(dsl, [25, 26, 27, 28, 29]);
+    }
+}
@@ -0,0 +1,56 @@
+use leptos::prelude::*;
+#[cfg(feature = "hydrate")]
+use std::time::Duration;
+
+/// Friendly stand-in for results while the database is unreachable. Counts
+/// down from the server's retry hint and then calls `on_retry`.
+#[component]
+pub fn ServiceUnavailable<F>(retry_after_secs: u64, on_retry: F) -> impl IntoView
+where
+    F: Fn() + Clone + Send + Sync + 'static,
+{
+    let remaining = RwSignal::new(retry_after_secs.max(1));
+    schedule_countdown_tick(remaining, on_retry.clone());
+
+    view! {
+        <div class="mx-auto max-w-md text-center py-10 space-y-3">
+            <p class="text-lg font-semibold text-gray-800 dark:text-gray-100">
+                "Search is temporarily unavailable"
+            </p>
+            <p class="text-sm text-gray-600 dark:text-gray-400">
+                "The database isn't responding. Retrying in " {move || remaining.get()} "s…"
+            </p>
+            <button
+                class="px-3 py-1 text-sm rounded-md border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-200 hover:bg-gray-100 dark:hover:bg-gray-700"
+                on:click=move |_| on_retry()
+            >
+                "Retry now"
+            </button>
+        </div>
+    }
+}
+
+#[cfg_attr(not(feature = "hydrate"), allow(unused_variables))]
+fn schedule_countdown_tick<F>(remaining: RwSignal<u64>, on_retry: F)
+where
+    F: Fn() + Clone + Send + Sync + 'static,
+{
+    #[cfg(feature = "hydrate")]
+    {
+        set_timeout(
+            move || {
+                // The signal is gone once the view has been replaced.
+                let Some(left) = remaining.try_get_untracked() else {
+                    return;
+                };
+                if left <= 1 {
+                    on_retry();
+                    return;
+                }
+                remaining.set(left - 1);
+                schedule_countdown_tick(remaining, on_retry);
+            },
+            Duration::from_secs(1),
+        );
+    }
+}
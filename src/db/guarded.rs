@@ -0,0 +1,554 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::db::models::{
+    BranchListing, CommitMetadata, FileLocationMatch, FileReference, FileSymbolSuggestion,
+    GraphEdge, InFileMatch, RepoBranchInfo, RepoOverview, SearchExplainReport, SearchResultsPage,
+    SymbolResult, SymbolSuggestion,
+};
+use crate::db::{
+    Database, DbError, DbUniqueChunk, DefinitionLocations, DefinitionLookup, FileContentRequest,
+    GraphGranularity, RawFileBytes, RawFileContent, RepoSummary, RepoTreeQuery, SearchRequest,
+    SearchResponse, SnippetRequest, SnippetResponse, SymbolReferenceRequest,
+    SymbolReferenceResponse, TreeEntry, TreeResponse,
+};
+use crate::dsl::{CaseSensitivity, TextSearchRequest};
+use crate::utils::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+
+/// Groups of `Database` methods that share a circuit breaker, so a slow
+/// search path can't take down repository browsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryClass {
+    Browse,
+    Search,
+    Symbols,
+    Autocomplete,
+    Ingest,
+}
+
+impl QueryClass {
+    pub const ALL: [QueryClass; 5] = [
+        QueryClass::Browse,
+        QueryClass::Search,
+        QueryClass::Symbols,
+        QueryClass::Autocomplete,
+        QueryClass::Ingest,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QueryClass::Browse => "browse",
+            QueryClass::Search => "search",
+            QueryClass::Symbols => "symbols",
+            QueryClass::Autocomplete => "autocomplete",
+            QueryClass::Ingest => "ingest",
+        }
+    }
+}
+
+/// One circuit breaker per [`QueryClass`], shared by every [`GuardedDb`]
+/// handle built from the same app state.
+#[derive(Debug)]
+pub struct DbCircuitBreakers {
+    breakers: Vec<CircuitBreaker>,
+}
+
+impl DbCircuitBreakers {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            breakers: QueryClass::ALL
+                .iter()
+                .map(|class| CircuitBreaker::new(class.as_str(), config))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, class: QueryClass) -> &CircuitBreaker {
+        &self.breakers[class as usize]
+    }
+
+    pub fn states(&self) -> Vec<(&'static str, CircuitState)> {
+        self.breakers
+            .iter()
+            .map(|breaker| (breaker.name(), breaker.state()))
+            .collect()
+    }
+}
+
+/// A `Database` that routes every call on `inner` through the shared circuit
+/// breakers, so an unreachable database fails fast instead of waiting out
+/// pool timeouts. Query failures are labelled with the method that raised
+/// them.
+#[derive(Clone)]
+pub struct GuardedDb<D> {
+    inner: D,
+    breakers: Arc<DbCircuitBreakers>,
+}
+
+impl<D: Database> GuardedDb<D> {
+    pub fn new(inner: D, breakers: Arc<DbCircuitBreakers>) -> Self {
+        Self { inner, breakers }
+    }
+
+    /// Runs `call` behind `class`'s circuit breaker and labels any query
+    /// failure with `op`, the `Database` method name.
+    async fn guarded<T>(
+        &self,
+        class: QueryClass,
+        op: &'static str,
+        call: impl Future<Output = Result<T, DbError>>,
+    ) -> Result<T, DbError> {
+        let permit = self.breakers.get(class).try_acquire().map_err(|rejected| {
+            tracing::debug!(
+                class = class.as_str(),
+                "database circuit open, failing fast"
+            );
+            DbError::Unavailable {
+                reason: format!("{} queries are failing fast", class.as_str()),
+                retry_after_secs: rejected.retry_after.as_secs().max(1),
+            }
+        })?;
+        let result = call.await.map_err(|err| label_query_error(op, err));
+        if matches!(result, Err(DbError::Unavailable { .. })) {
+            permit.failure();
+        } else {
+            permit.success();
+        }
+        result
+    }
+}
+
+/// Attaches `op` to an unlabelled query failure and logs it, so a failing
+/// query can be traced without matching on its SQL error text.
+fn label_query_error(op: &'static str, err: DbError) -> DbError {
+    if !matches!(&err, DbError::Database { op: label, .. } if label.is_empty()) {
+        return err;
+    }
+    let err = err.in_op(op);
+    tracing::warn!(op, error = %err, "database query failed");
+    err
+}
+
+/// Forwards `method(args..)` to the inner database behind `class`'s breaker.
+macro_rules! guard {
+    ($self:ident, $class:ident, $method:ident($($arg:expr),*)) => {
+        $self
+            .guarded(
+                QueryClass::$class,
+                stringify!($method),
+                $self.inner.$method($($arg),*),
+            )
+            .await
+    };
+}
+
+#[async_trait]
+impl<D: Database> Database for GuardedDb<D> {
+    async fn get_all_repositories(&self) -> Result<Vec<RepoSummary>, DbError> {
+        guard!(self, Browse, get_all_repositories())
+    }
+
+    async fn get_branches_for_repository(
+        &self,
+        repository: &str,
+    ) -> Result<Vec<RepoBranchInfo>, DbError> {
+        guard!(self, Browse, get_branches_for_repository(repository))
+    }
+
+    async fn get_all_branches(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<BranchListing>, DbError> {
+        guard!(self, Browse, get_all_branches(limit, offset))
+    }
+
+    async fn resolve_branch_head(
+        &self,
+        repository: &str,
+        branch: &str,
+    ) -> Result<Option<String>, DbError> {
+        guard!(self, Browse, resolve_branch_head(repository, branch))
+    }
+
+    async fn get_commit_metadata(
+        &self,
+        repository: &str,
+        commit_shas: &[String],
+    ) -> Result<Vec<CommitMetadata>, DbError> {
+        guard!(self, Browse, get_commit_metadata(repository, commit_shas))
+    }
+
+    async fn chunk_need(&self, hashes: Vec<String>) -> Result<Vec<String>, DbError> {
+        guard!(self, Ingest, chunk_need(hashes))
+    }
+
+    async fn chunk_upload(&self, chunks: Vec<DbUniqueChunk>) -> Result<(), DbError> {
+        guard!(self, Ingest, chunk_upload(chunks))
+    }
+
+    async fn store_manifest_chunk(
+        &self,
+        upload_id: String,
+        chunk_index: i32,
+        total_chunks: i32,
+        data: Vec<u8>,
+    ) -> Result<(), DbError> {
+        guard!(
+            self,
+            Ingest,
+            store_manifest_chunk(upload_id, chunk_index, total_chunks, data)
+        )
+    }
+
+    async fn finalize_manifest(
+        &self,
+        upload_id: String,
+        compressed: Option<bool>,
+    ) -> Result<(), DbError> {
+        guard!(self, Ingest, finalize_manifest(upload_id, compressed))
+    }
+
+    async fn list_commits(&self, repository: &str) -> Result<Vec<String>, DbError> {
+        guard!(self, Browse, list_commits(repository))
+    }
+
+    async fn get_repo_tree(
+        &self,
+        repository: &str,
+        query: RepoTreeQuery,
+    ) -> Result<TreeResponse, DbError> {
+        guard!(self, Browse, get_repo_tree(repository, query))
+    }
+
+    async fn search_repo_paths(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<TreeEntry>, DbError> {
+        guard!(
+            self,
+            Search,
+            search_repo_paths(repository, commit_sha, query, limit)
+        )
+    }
+
+    async fn find_file_in_repos(
+        &self,
+        paths: &[String],
+        repositories: &[String],
+    ) -> Result<Vec<FileLocationMatch>, DbError> {
+        guard!(self, Search, find_file_in_repos(paths, repositories))
+    }
+
+    async fn get_file_content(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+    ) -> Result<RawFileContent, DbError> {
+        guard!(
+            self,
+            Browse,
+            get_file_content(repository, commit_sha, file_path)
+        )
+    }
+
+    async fn get_file_contents(
+        &self,
+        requests: Vec<FileContentRequest>,
+    ) -> Result<Vec<RawFileContent>, DbError> {
+        guard!(self, Browse, get_file_contents(requests))
+    }
+
+    async fn get_raw_file_bytes(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+    ) -> Result<RawFileBytes, DbError> {
+        guard!(
+            self,
+            Browse,
+            get_raw_file_bytes(repository, commit_sha, file_path)
+        )
+    }
+
+    async fn search_in_file(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+        pattern: &str,
+        case: CaseSensitivity,
+    ) -> Result<Vec<InFileMatch>, DbError> {
+        guard!(
+            self,
+            Browse,
+            search_in_file(repository, commit_sha, file_path, pattern, case)
+        )
+    }
+
+    async fn get_repo_overview(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+    ) -> Result<RepoOverview, DbError> {
+        guard!(self, Browse, get_repo_overview(repository, commit_sha))
+    }
+
+    async fn get_reference_graph(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        granularity: GraphGranularity,
+        limit: i64,
+    ) -> Result<Vec<GraphEdge>, DbError> {
+        guard!(
+            self,
+            Symbols,
+            get_reference_graph(repository, commit_sha, granularity, limit)
+        )
+    }
+
+    async fn get_file_snippet(&self, request: SnippetRequest) -> Result<SnippetResponse, DbError> {
+        guard!(self, Browse, get_file_snippet(request))
+    }
+
+    async fn get_file_snippets(
+        &self,
+        requests: Vec<SnippetRequest>,
+    ) -> Result<Vec<SnippetResponse>, DbError> {
+        guard!(self, Browse, get_file_snippets(requests))
+    }
+
+    async fn get_symbol_references(
+        &self,
+        request: SymbolReferenceRequest,
+    ) -> Result<SymbolReferenceResponse, DbError> {
+        guard!(self, Symbols, get_symbol_references(request))
+    }
+
+    async fn get_file_references(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+    ) -> Result<Vec<FileReference>, DbError> {
+        guard!(
+            self,
+            Symbols,
+            get_file_references(repository, commit_sha, file_path)
+        )
+    }
+
+    async fn get_definitions_batch(
+        &self,
+        lookups: Vec<DefinitionLookup>,
+    ) -> Result<Vec<DefinitionLocations>, DbError> {
+        guard!(self, Symbols, get_definitions_batch(lookups))
+    }
+
+    async fn search_symbols(&self, request: SearchRequest) -> Result<SearchResponse, DbError> {
+        guard!(self, Symbols, search_symbols(request))
+    }
+
+    async fn get_symbol_by_id(&self, id: i32) -> Result<SymbolResult, DbError> {
+        guard!(self, Symbols, get_symbol_by_id(id))
+    }
+
+    async fn text_search(&self, request: &TextSearchRequest) -> Result<SearchResultsPage, DbError> {
+        guard!(self, Search, text_search(request))
+    }
+
+    async fn explain_search(
+        &self,
+        request: &TextSearchRequest,
+    ) -> Result<SearchExplainReport, DbError> {
+        guard!(self, Search, explain_search(request))
+    }
+
+    async fn autocomplete_repositories(
+        &self,
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, DbError> {
+        guard!(self, Autocomplete, autocomplete_repositories(term, limit))
+    }
+
+    async fn autocomplete_paths(
+        &self,
+        repositories: &[String],
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, DbError> {
+        guard!(
+            self,
+            Autocomplete,
+            autocomplete_paths(repositories, term, limit)
+        )
+    }
+
+    async fn autocomplete_files(
+        &self,
+        repositories: &[String],
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, DbError> {
+        guard!(
+            self,
+            Autocomplete,
+            autocomplete_files(repositories, term, limit)
+        )
+    }
+
+    async fn autocomplete_languages(
+        &self,
+        repositories: &[String],
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, DbError> {
+        guard!(
+            self,
+            Autocomplete,
+            autocomplete_languages(repositories, term, limit)
+        )
+    }
+
+    async fn autocomplete_branches(
+        &self,
+        repositories: &[String],
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, DbError> {
+        guard!(
+            self,
+            Autocomplete,
+            autocomplete_branches(repositories, term, limit)
+        )
+    }
+
+    async fn autocomplete_symbols(
+        &self,
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<SymbolSuggestion>, DbError> {
+        guard!(self, Autocomplete, autocomplete_symbols(term, limit))
+    }
+
+    async fn autocomplete_symbols_in_file(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<FileSymbolSuggestion>, DbError> {
+        guard!(
+            self,
+            Autocomplete,
+            autocomplete_symbols_in_file(repository, commit_sha, file_path, term, limit)
+        )
+    }
+
+    /// Not behind a breaker, so health checks keep reporting while a circuit
+    /// is open.
+    async fn health_check(&self) -> Result<String, DbError> {
+        self.inner
+            .health_check()
+            .await
+            .map_err(|err| label_query_error("health_check", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::postgres::PostgresDb;
+
+    #[tokio::test]
+    async fn unreachable_database_fails_fast_after_threshold() {
+        use std::time::{Duration, Instant};
+
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(300))
+            .connect_lazy("postgres://pointer@127.0.0.1:1/pointer")
+            .unwrap();
+        let breakers = Arc::new(DbCircuitBreakers::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            base_open: Duration::from_secs(30),
+            max_open: Duration::from_secs(30),
+        }));
+        let db = GuardedDb::new(PostgresDb::new(pool), breakers.clone());
+
+        for _ in 0..2 {
+            let err = db.get_all_repositories().await.unwrap_err();
+            assert!(matches!(err, DbError::Unavailable { .. }), "{err}");
+        }
+        assert_eq!(breakers.get(QueryClass::Browse).state(), CircuitState::Open);
+
+        let started = Instant::now();
+        let err = db.list_commits("acme").await.unwrap_err();
+        assert!(started.elapsed() < Duration::from_millis(50));
+        assert!(
+            matches!(err, DbError::Unavailable { retry_after_secs, .. } if retry_after_secs >= 29),
+            "{err}"
+        );
+
+        assert_eq!(
+            breakers.get(QueryClass::Search).state(),
+            CircuitState::Closed
+        );
+    }
+
+    #[tokio::test]
+    async fn query_failures_name_the_failing_operation() {
+        let Ok(url) = std::env::var("DATABASE_URL") else {
+            eprintln!("DATABASE_URL is not set; skipping database test");
+            return;
+        };
+        // An empty search path hides every table, so each query fails in
+        // Postgres rather than on the connection.
+        let options = url
+            .parse::<sqlx::postgres::PgConnectOptions>()
+            .unwrap()
+            .options([("search_path", "pointer_missing_schema")]);
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .expect("connect to DATABASE_URL");
+        let breakers = Arc::new(DbCircuitBreakers::new(CircuitBreakerConfig::default()));
+        let db = GuardedDb::new(PostgresDb::new(pool), breakers);
+
+        let err = db
+            .get_all_repositories()
+            .await
+            .expect_err("files table is hidden");
+        match &err {
+            DbError::Database { op, source } => {
+                assert_eq!(op, "get_all_repositories");
+                assert!(source.contains("does not exist"), "{source}");
+            }
+            other => panic!("expected a database error, got {other:?}"),
+        }
+        assert!(
+            err.to_string()
+                .starts_with("Database error in get_all_repositories: "),
+            "{err}"
+        );
+
+        let err = db
+            .get_symbol_by_id(1)
+            .await
+            .expect_err("symbols table is hidden");
+        assert!(
+            matches!(&err, DbError::Database { op, .. } if op == "get_symbol_by_id"),
+            "{err:?}"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "ssr")]
+pub mod guarded;
 pub mod models;
 #[cfg(feature = "ssr")]
 pub mod postgres;
@@ -6,12 +8,13 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 use crate::db::models::{
-    FileReference, HighlightedLine, RepoBranchInfo, SearchResultsPage, SymbolResult,
-    SymbolSuggestion, TokenOccurrence,
+    BranchListing, CommitMetadata, FileLocationMatch, FileReference, FileSymbolSuggestion,
+    GraphEdge, HighlightedLine, InFileMatch, RepoBranchInfo, RepoOverview, SearchExplainReport,
+    SearchResultsPage, SymbolResult, SymbolSuggestion, TokenOccurrence,
 };
 #[cfg(feature = "ssr")]
 use crate::db::models::{ReferenceResult, SearchResult};
-use crate::dsl::TextSearchRequest;
+use crate::dsl::{CaseSensitivity, PathDepth, TextSearchRequest};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SnippetRequest {
@@ -19,6 +22,11 @@ pub struct SnippetRequest {
     pub commit_sha: String,
     pub file_path: String,
     pub line: u32,
+    /// 1-based column of the match; helps find where a reference ends.
+    #[serde(default)]
+    pub column: Option<u32>,
+    /// Lines on each side of `line`; 3 by default, capped by the server's
+    /// `MAX_SNIPPET_CONTEXT`.
     pub context: Option<u32>,
     pub highlight: Option<String>,
     pub case_sensitive: Option<bool>,
@@ -28,11 +36,18 @@ pub struct SnippetRequest {
 pub struct SnippetResponse {
     pub start_line: u32,
     pub highlight_line: u32,
+    /// Lines covered by the match. Both equal `highlight_line` unless the
+    /// reference continues onto following lines, e.g. a multi-line call.
+    pub highlight_start_line: u32,
+    pub highlight_end_line: u32,
     pub total_lines: u32,
     pub lines: Vec<String>,
     pub truncated: bool,
 }
 
+/// Most references one [`Database::get_symbol_references`] page returns.
+pub const MAX_SYMBOL_REFERENCE_PAGE: u32 = 500;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolReferenceRequest {
     pub repository: String,
@@ -41,11 +56,50 @@ pub struct SymbolReferenceRequest {
     pub file_path: Option<String>,
     pub line: Option<usize>,
     pub column: Option<usize>,
+    /// Page size, capped at (and defaulting to) [`MAX_SYMBOL_REFERENCE_PAGE`].
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// References to skip. Pages are ordered by file path, line and column,
+    /// so consecutive offsets never overlap.
+    #[serde(default)]
+    pub offset: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolReferenceResponse {
     pub references: Vec<FileReference>,
+    /// References matching the request across all pages; zero when `offset`
+    /// is past the last one.
+    #[serde(default)]
+    pub total_count: i64,
+    /// Whether references remain past this page.
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// One symbol to resolve in a batched definition lookup.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DefinitionLookup {
+    pub repository: String,
+    pub commit_sha: String,
+    pub fully_qualified: String,
+}
+
+/// One file to load in a batched [`Database::get_file_contents`] call.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileContentRequest {
+    pub repository: String,
+    pub commit_sha: String,
+    pub file_path: String,
+}
+
+/// Definition sites for one [`DefinitionLookup`]; empty when the symbol has
+/// no recorded definition in that snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefinitionLocations {
+    #[serde(flatten)]
+    pub lookup: DefinitionLookup,
+    pub definitions: Vec<FileReference>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +107,11 @@ pub struct SearchRequest {
     pub q: Option<String>,
     pub name: Option<String>,
     pub name_regex: Option<String>,
+    /// Case-insensitive substring of the symbol name, e.g. `parse` matches
+    /// `parse_query` and `reparse`. Names close by trigram similarity match
+    /// too, so `rendr` finds `render`.
+    #[serde(default)]
+    pub name_contains: Option<String>,
     pub namespace: Option<String>,
     pub namespace_prefix: Option<String>,
     pub kind: Option<Vec<String>>,
@@ -62,17 +121,32 @@ pub struct SearchRequest {
     pub path: Option<String>,
     pub path_regex: Option<String>,
     pub path_hint: Option<String>,
+    /// Bounds the number of `/`-separated segments in the file path.
+    #[serde(default)]
+    pub path_depth: Option<PathDepth>,
     #[serde(default)]
     pub include_paths: Vec<String>,
     #[serde(default)]
     pub excluded_paths: Vec<String>,
     pub include_references: Option<bool>,
     pub limit: Option<i64>,
+    /// Only definitions whose name has no `reference`-kind usage in the same
+    /// snapshot, i.e. dead-code candidates. Needs `repository`.
+    #[serde(default)]
+    pub unreferenced: bool,
+    /// Keep only the best-ranked row per symbol name, so `limit` and `total`
+    /// count distinct names rather than definition and reference sites.
+    #[serde(default)]
+    pub group_by_name: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResponse {
     pub symbols: Vec<SymbolResult>,
+    /// Matches before the limit was applied, so callers can report how many
+    /// `symbols` leaves out.
+    #[serde(default)]
+    pub total: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,7 +167,11 @@ pub struct TreeResponse {
 pub struct TreeEntry {
     pub name: String,
     pub path: String,
+    /// "dir" or "file", or "symlink"/"submodule" for those tree entries.
     pub kind: String,
+    /// Whether the file's recorded mode has an executable bit set.
+    #[serde(default)]
+    pub executable: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,6 +191,28 @@ pub struct RawFileContent {
     pub file_path: String,
     pub content: String,
     pub language: Option<String>,
+    /// Binary files come back with empty `content` and no `language` rather
+    /// than a lossy decoding; read them with `get_raw_file_bytes`.
+    #[serde(default)]
+    pub is_binary: bool,
+    /// Set when the file is a symlink; `content` then holds the same target.
+    #[serde(default)]
+    pub symlink_target: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawFileBytes {
+    pub bytes: Vec<u8>,
+    pub is_binary: bool,
+    /// Set when the indexer recorded the file without storing its content,
+    /// as it does for binaries; `bytes` is then empty.
+    #[serde(default)]
+    pub content_skipped: bool,
+}
+
+/// Treats content holding a NUL byte as binary.
+pub fn is_binary_content(bytes: &[u8]) -> bool {
+    bytes.contains(&0)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,6 +227,15 @@ pub struct DbUniqueChunk {
     pub text_content: String,
 }
 
+/// Node type for the exported reference graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphGranularity {
+    #[default]
+    File,
+    Namespace,
+}
+
 #[async_trait]
 pub trait Database: Clone + Send + Sync + 'static {
     // Repository and Branch operations
@@ -135,11 +244,23 @@ pub trait Database: Clone + Send + Sync + 'static {
         &self,
         repository: &str,
     ) -> Result<Vec<RepoBranchInfo>, DbError>;
+    /// Branches of every repository, ordered by repository then branch.
+    async fn get_all_branches(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<BranchListing>, DbError>;
     async fn resolve_branch_head(
         &self,
         repository: &str,
         branch: &str,
     ) -> Result<Option<String>, DbError>;
+    /// Metadata of whichever of `commit_shas` the indexer recorded any for.
+    async fn get_commit_metadata(
+        &self,
+        repository: &str,
+        commit_shas: &[String],
+    ) -> Result<Vec<CommitMetadata>, DbError>;
 
     // Existing backend operations
     async fn chunk_need(&self, hashes: Vec<String>) -> Result<Vec<String>, DbError>;
@@ -169,13 +290,62 @@ pub trait Database: Clone + Send + Sync + 'static {
         query: &str,
         limit: i64,
     ) -> Result<Vec<TreeEntry>, DbError>;
+    /// Looks up exact file paths at the live head of each repository. An empty
+    /// `repositories` slice searches every repository.
+    async fn find_file_in_repos(
+        &self,
+        paths: &[String],
+        repositories: &[String],
+    ) -> Result<Vec<FileLocationMatch>, DbError>;
     async fn get_file_content(
         &self,
         repository: &str,
         commit_sha: &str,
         file_path: &str,
     ) -> Result<RawFileContent, DbError>;
+    /// Loads several files in one pass, e.g. an open file and its siblings.
+    /// Results line up with `requests`; files sharing content are only
+    /// reassembled once. Fails if any requested file is not indexed.
+    async fn get_file_contents(
+        &self,
+        requests: Vec<FileContentRequest>,
+    ) -> Result<Vec<RawFileContent>, DbError>;
+    /// The file's bytes exactly as indexed, with no UTF-8 decoding.
+    async fn get_raw_file_bytes(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+    ) -> Result<RawFileBytes, DbError>;
+    /// Finds `pattern` literally in one file's content, without going through
+    /// the cross-repository search. Matches do not overlap.
+    async fn search_in_file(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+        pattern: &str,
+        case: CaseSensitivity,
+    ) -> Result<Vec<InFileMatch>, DbError>;
+    /// Summarises a snapshot from index data alone, in a fixed number of
+    /// queries regardless of repository size.
+    async fn get_repo_overview(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+    ) -> Result<RepoOverview, DbError>;
+    /// Aggregates cross-file references into weighted edges, heaviest first,
+    /// keeping at most `limit` edges.
+    async fn get_reference_graph(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        granularity: GraphGranularity,
+        limit: i64,
+    ) -> Result<Vec<GraphEdge>, DbError>;
     async fn get_file_snippet(&self, request: SnippetRequest) -> Result<SnippetResponse, DbError>;
+    /// One response per request, in order. A request for a file that is not
+    /// indexed gets a response with no `lines`.
     async fn get_file_snippets(
         &self,
         requests: Vec<SnippetRequest>,
@@ -184,8 +354,31 @@ pub trait Database: Clone + Send + Sync + 'static {
         &self,
         request: SymbolReferenceRequest,
     ) -> Result<SymbolReferenceResponse, DbError>;
+    /// Every symbol occurrence the indexer recorded for one file, in source
+    /// order.
+    async fn get_file_references(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+    ) -> Result<Vec<FileReference>, DbError>;
+    /// Resolves definitions for many symbols in one round trip. Results line
+    /// up with `lookups`, one entry per lookup.
+    async fn get_definitions_batch(
+        &self,
+        lookups: Vec<DefinitionLookup>,
+    ) -> Result<Vec<DefinitionLocations>, DbError>;
     async fn search_symbols(&self, request: SearchRequest) -> Result<SearchResponse, DbError>;
+    /// Re-fetches one symbol from a search result by its `id`, with its
+    /// definition location and every reference, e.g. to serve a permalink.
+    async fn get_symbol_by_id(&self, id: i32) -> Result<SymbolResult, DbError>;
     async fn text_search(&self, request: &TextSearchRequest) -> Result<SearchResultsPage, DbError>;
+    /// Runs `EXPLAIN` on the search queries `request` would issue and flags
+    /// sequential scans on large tables. Purely diagnostic.
+    async fn explain_search(
+        &self,
+        request: &TextSearchRequest,
+    ) -> Result<SearchExplainReport, DbError>;
     async fn autocomplete_repositories(
         &self,
         term: &str,
@@ -220,24 +413,75 @@ pub trait Database: Clone + Send + Sync + 'static {
         term: &str,
         limit: i64,
     ) -> Result<Vec<SymbolSuggestion>, DbError>;
+    /// Definitions in one file whose name contains `term`, prefix matches
+    /// first and then in file order. An empty term lists every definition.
+    async fn autocomplete_symbols_in_file(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<FileSymbolSuggestion>, DbError>;
     async fn health_check(&self) -> Result<String, DbError>;
 }
 
 #[derive(Debug)]
 pub enum DbError {
-    Database(String),
+    /// A query reached Postgres and failed there. `op` names the `Database`
+    /// method that issued it, or is empty if the error never passed through
+    /// one.
+    Database {
+        op: String,
+        source: String,
+    },
     Serialization(String),
     Compression(String),
     Internal(String),
+    /// The requested row doesn't exist; the message names what was looked up.
+    NotFound(String),
+    /// The database can't be reached right now; callers should retry later.
+    Unavailable {
+        reason: String,
+        retry_after_secs: u64,
+    },
+}
+
+impl DbError {
+    /// Labels a `Database` error with the operation that raised it, keeping
+    /// any label an inner call already attached.
+    pub fn in_op(self, op: &str) -> Self {
+        match self {
+            DbError::Database {
+                op: existing,
+                source,
+            } if existing.is_empty() => DbError::Database {
+                op: op.to_string(),
+                source,
+            },
+            other => other,
+        }
+    }
 }
 
 impl std::fmt::Display for DbError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            DbError::Database(msg) => write!(f, "Database error: {}", msg),
+            DbError::Database { op, source } if op.is_empty() => {
+                write!(f, "Database error: {}", source)
+            }
+            DbError::Database { op, source } => write!(f, "Database error in {op}: {source}"),
             DbError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
             DbError::Compression(msg) => write!(f, "Compression error: {}", msg),
             DbError::Internal(msg) => write!(f, "Internal error: {}", msg),
+            DbError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            DbError::Unavailable {
+                reason,
+                retry_after_secs,
+            } => write!(
+                f,
+                "Database unavailable (retry in {retry_after_secs}s): {reason}"
+            ),
         }
     }
 }
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::utils::time::format_age;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
 pub struct FileReference {
@@ -28,7 +30,7 @@ pub struct HighlightedSegment {
     pub italic: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TokenOccurrence {
     pub token: String,
     pub line: u32,
@@ -38,6 +40,9 @@ pub struct TokenOccurrence {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolResult {
+    /// Row id of the symbol, for re-fetching it with `get_symbol_by_id`.
+    #[serde(default)]
+    pub id: i32,
     pub symbol: String,
     pub namespace: Option<String>,
     pub kind: Option<String>,
@@ -48,6 +53,10 @@ pub struct SymbolResult {
     pub language: Option<String>,
     pub line: Option<usize>,
     pub column: Option<usize>,
+    /// Declaration header of the definition at `line`, e.g.
+    /// `fn foo(a: u32) -> bool`, for languages whose extractor records one.
+    #[serde(default)]
+    pub signature: Option<String>,
     pub references: Option<Vec<ReferenceResult>>,
     pub score: f64,
 }
@@ -71,6 +80,14 @@ pub struct SearchMatchSpan {
     pub end: usize,
 }
 
+/// A line of a single file that matches an in-file find. Spans are byte
+/// offsets into that line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InFileMatch {
+    pub line: u32,
+    pub spans: Vec<SearchMatchSpan>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchSnippet {
     pub start_line: i32,
@@ -87,6 +104,16 @@ pub struct SymbolSuggestion {
     pub file_path: String,
 }
 
+/// A definition inside one file, for jumping to a symbol in the open file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct FileSymbolSuggestion {
+    pub name: String,
+    pub namespace: Option<String>,
+    pub line: i32,
+    pub column: i32,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct SearchResult {
     pub repository: String,
@@ -102,6 +129,36 @@ pub struct SearchResult {
     pub live_branches: Vec<String>,
     pub is_historical: bool,
     pub snapshot_indexed_at: Option<String>,
+    /// Metadata of `commit_sha`, when its indexer recorded any.
+    #[serde(default)]
+    pub commit: Option<CommitMetadata>,
+}
+
+/// Author, time and subject of an indexed commit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommitMetadata {
+    pub commit_sha: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// RFC 3339 commit time.
+    pub committed_at: String,
+    /// First line of the commit message.
+    pub subject: String,
+}
+
+impl CommitMetadata {
+    /// `abc1234 — Fix GC race (2 days ago)`, with the age measured to `now`.
+    pub fn label(&self, now: chrono::DateTime<chrono::Utc>) -> String {
+        let short_sha: String = self.commit_sha.chars().take(7).collect();
+        match chrono::DateTime::parse_from_rfc3339(&self.committed_at) {
+            Ok(committed_at) => format!(
+                "{short_sha} — {} ({})",
+                self.subject,
+                format_age((now - committed_at.to_utc()).num_seconds())
+            ),
+            Err(_) => format!("{short_sha} — {}", self.subject),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,6 +169,33 @@ pub struct RepoBranchInfo {
     pub is_live: bool,
 }
 
+/// A branch of any repository, for listings that span every repository.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BranchListing {
+    pub repository: String,
+    pub branch: String,
+    pub commit_sha: String,
+    pub is_live: bool,
+    pub indexed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchListingPage {
+    pub branches: Vec<BranchListing>,
+    pub page: u32,
+    pub page_size: u32,
+    pub has_more: bool,
+}
+
+/// A file that exists at the live head of a repository.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileLocationMatch {
+    pub repository: String,
+    pub branch: String,
+    pub commit_sha: String,
+    pub file_path: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FacetCount {
     pub value: String,
@@ -123,12 +207,25 @@ pub struct SearchResultsStats {
     pub common_directories: Vec<FacetCount>,
     pub top_repositories: Vec<FacetCount>,
     pub top_branches: Vec<FacetCount>,
+    /// Files without a detected language count under [`UNKNOWN_LANGUAGE`].
+    #[serde(default)]
+    pub top_languages: Vec<FacetCount>,
 }
 
+/// Facet value for files whose language wasn't detected; not a `lang:` value.
+pub const UNKNOWN_LANGUAGE: &str = "unknown";
+
+/// Ceiling on [`SearchResultsPage::total_estimate`]; counting stops here.
+pub const TOTAL_ESTIMATE_CAP: u64 = 10_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResultsPage {
     pub results: Vec<SearchResult>,
     pub has_more: bool,
+    /// Approximate number of matching files, at most
+    /// [`TOTAL_ESTIMATE_CAP`]; `None` when no count was taken.
+    #[serde(default)]
+    pub total_estimate: Option<u64>,
     pub page: u32,
     pub page_size: u32,
     pub query: String,
@@ -140,6 +237,7 @@ impl SearchResultsPage {
         Self {
             results: Vec::new(),
             has_more: false,
+            total_estimate: None,
             page,
             page_size,
             query,
@@ -147,3 +245,59 @@ impl SearchResultsPage {
         }
     }
 }
+
+/// Index-derived summary of a repository snapshot, shown above its README.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct RepoOverview {
+    pub directories: Vec<DirectoryOverview>,
+    pub largest_files: Vec<OverviewFile>,
+    pub entry_points: Vec<String>,
+    pub build_files: Vec<BuildFile>,
+}
+
+/// A top-level directory. Files at the repository root are grouped under an
+/// empty `name`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DirectoryOverview {
+    pub name: String,
+    pub file_count: i64,
+    pub dominant_language: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OverviewFile {
+    pub path: String,
+    pub byte_len: i64,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BuildFile {
+    pub path: String,
+    pub system: String,
+}
+
+/// Aggregated references from `source` to definitions in `target`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub weight: i64,
+}
+
+/// `EXPLAIN` output for one of the core search queries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainedQuery {
+    pub name: String,
+    /// The plan exactly as `EXPLAIN (FORMAT JSON)` returned it.
+    pub plan: serde_json::Value,
+}
+
+/// Index diagnostics for a sample search; findings are advice, nothing is
+/// changed in the database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchExplainReport {
+    pub query: String,
+    pub plans: Vec<ExplainedQuery>,
+    pub findings: Vec<String>,
+}
@@ -1,43 +1,274 @@
 use crate::db::models::{
-    FacetCount, FileReference as DbFileReference, RepoBranchInfo, SearchMatchSpan,
-    SearchResultsPage, SearchResultsStats, SearchSnippet, SymbolSuggestion,
+    BranchListing, CommitMetadata, DirectoryOverview, ExplainedQuery, FacetCount,
+    FileLocationMatch, FileReference as DbFileReference, FileSymbolSuggestion, GraphEdge,
+    InFileMatch, OverviewFile, RepoBranchInfo, RepoOverview, SearchExplainReport, SearchMatchSpan,
+    SearchResultsPage, SearchResultsStats, SearchSnippet, SymbolSuggestion, TOTAL_ESTIMATE_CAP,
+    UNKNOWN_LANGUAGE,
 };
 use crate::db::{
-    Database, DbError, DbUniqueChunk, FileReference, RawFileContent, ReferenceResult, RepoSummary,
-    RepoTreeQuery, SearchRequest, SearchResponse, SearchResult, SnippetRequest, SnippetResponse,
-    SymbolReferenceRequest, SymbolReferenceResponse, SymbolResult, TreeEntry, TreeResponse,
+    Database, DbError, DbUniqueChunk, DefinitionLocations, DefinitionLookup, FileContentRequest,
+    FileReference, GraphGranularity, MAX_SYMBOL_REFERENCE_PAGE, RawFileBytes, RawFileContent,
+    ReferenceResult, RepoSummary, RepoTreeQuery, SearchRequest, SearchResponse, SearchResult,
+    SnippetRequest, SnippetResponse, SymbolReferenceRequest, SymbolReferenceResponse, SymbolResult,
+    TreeEntry, TreeResponse, is_binary_content,
 };
 use crate::dsl::{
-    CaseSensitivity, ContentPredicate, TextSearchPlan, TextSearchRequest, escape_sql_like_literal,
+    CaseSensitivity, ContentPredicate, SearchOrder, TextSearchPlan, TextSearchRequest,
+    escape_sql_like_literal,
 };
+use crate::utils::ttl_cache::TtlCache;
+use crate::utils::{query_plan, repo_overview};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use pointer_indexer_types::validation::{
+    describe_rejections, normalize_file_pointers, validate_branch_heads, validate_commit_metadata,
+};
 use pointer_indexer_types::{
-    BranchHead, ContentBlob, FilePointer, IndexReport, ReferenceRecord, SymbolRecord,
+    BranchHead, CommitMetadata as IndexedCommit, ContentBlob, FilePointer, IndexReport,
+    ReferenceRecord, SymbolRecord,
 };
 use sqlx::postgres::PgArguments;
 use sqlx::{Execute, PgPool, Postgres, QueryBuilder, Transaction, types::Json};
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     io::Read,
+    sync::Arc,
 };
 
+/// Retry hint for connection failures that happen before a breaker opens.
+const UNAVAILABLE_RETRY_SECS: u64 = 5;
+
+/// How many of the biggest files the repository overview lists.
+const REPO_OVERVIEW_LARGEST_FILES: i64 = 8;
+
+impl From<sqlx::Error> for DbError {
+    fn from(err: sqlx::Error) -> Self {
+        if is_connection_error(&err) {
+            DbError::Unavailable {
+                reason: err.to_string(),
+                retry_after_secs: UNAVAILABLE_RETRY_SECS,
+            }
+        } else {
+            DbError::Database {
+                op: String::new(),
+                source: err.to_string(),
+            }
+        }
+    }
+}
+
+/// Whether `err` means the server couldn't be reached, as opposed to a query
+/// that reached Postgres and failed there.
+fn is_connection_error(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => true,
+        // Class 08 is "connection exception"; 57P01-57P03 cover shutdowns
+        // and a server that isn't accepting connections yet.
+        sqlx::Error::Database(db_err) => db_err.code().is_some_and(|code| {
+            code.starts_with("08") || matches!(&*code, "57P01" | "57P02" | "57P03")
+        }),
+        _ => false,
+    }
+}
+
+/// Trigram indexes every search relies on, as `(table, indexed expression)`.
+const REQUIRED_TRIGRAM_INDEXES: [(&str, &str); 2] =
+    [("chunks", "text_content"), ("unique_symbols", "name_lc")];
+
+/// Whether the database has what content and symbol search need: the
+/// `pg_trgm` extension and the trigram indexes over chunk text and symbol
+/// names. Run once at startup and reported by `/readyz`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaCheck {
+    pub pg_trgm_installed: bool,
+    /// Missing trigram indexes, as `table (expression)`.
+    pub missing_indexes: Vec<String>,
+}
+
+impl SchemaCheck {
+    /// Compares installed extension names and `(table, indexdef)` pairs
+    /// against what search needs. The `lower(text_content)` index is only
+    /// needed with `lowercase_content`.
+    pub(crate) fn from_catalog(
+        extensions: &[String],
+        indexes: &[(String, String)],
+        lowercase_content: bool,
+    ) -> Self {
+        let lowercase_index = lowercase_content.then_some(("chunks", "lower(text_content)"));
+        let missing_indexes = REQUIRED_TRIGRAM_INDEXES
+            .into_iter()
+            .chain(lowercase_index)
+            .filter(|(table, expression)| {
+                let column = format!("({expression} gin_trgm_ops)");
+                !indexes
+                    .iter()
+                    .any(|(indexed, definition)| indexed == table && definition.contains(&column))
+            })
+            .map(|(table, expression)| format!("{table} ({expression})"))
+            .collect();
+        Self {
+            pg_trgm_installed: extensions.iter().any(|name| name == "pg_trgm"),
+            missing_indexes,
+        }
+    }
+
+    /// Searches fail outright without `pg_trgm`; a missing index only makes
+    /// them slow.
+    pub fn is_ready(&self) -> bool {
+        self.pg_trgm_installed
+    }
+
+    /// What is missing and how to fix it, one line per problem.
+    pub fn problems(&self) -> Vec<String> {
+        let extension = (!self.pg_trgm_installed).then(|| {
+            "the pg_trgm extension is not installed; run `CREATE EXTENSION pg_trgm;` as a \
+             database owner, then rerun the backend migrations"
+                .to_string()
+        });
+        extension
+            .into_iter()
+            .chain(self.missing_indexes.iter().map(|index| {
+                format!(
+                    "the trigram index on {index} is missing; rerun the backend migrations to create it"
+                )
+            }))
+            .collect()
+    }
+}
+
+/// Reads the extensions and trigram indexes of the current schema for a
+/// [`SchemaCheck`]. Nothing is created: the server's role may lack the
+/// privileges, and the backend migrations own the schema.
+pub async fn check_schema(pool: &PgPool, lowercase_content: bool) -> Result<SchemaCheck, DbError> {
+    let extensions: Vec<String> = sqlx::query_scalar("SELECT extname::text FROM pg_extension")
+        .fetch_all(pool)
+        .await
+        .map_err(DbError::from)?;
+    let indexes: Vec<(String, String)> = sqlx::query_as(
+        "SELECT tablename::text, indexdef
+         FROM pg_indexes
+         WHERE schemaname = current_schema() AND indexdef LIKE '%gin_trgm_ops%'",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(DbError::from)?;
+    Ok(SchemaCheck::from_catalog(
+        &extensions,
+        &indexes,
+        lowercase_content,
+    ))
+}
+
+/// Shortest search term treated as a possible symbol name unless configured
+/// otherwise. Matches the query parser's minimum term length; tiny names like
+/// `i` or `x` would otherwise boost nearly every file.
+pub const DEFAULT_MIN_SYMBOL_LENGTH: usize = 3;
+
+/// Most context lines a snippet request gets on each side of its line unless
+/// configured otherwise.
+pub const DEFAULT_MAX_SNIPPET_CONTEXT: u32 = 20;
+
+/// Recent `search_symbols` responses keyed by [`symbol_cache_key`].
+pub type SymbolSearchCache = TtlCache<String, SearchResponse>;
+
 #[derive(Clone)]
 pub struct PostgresDb {
     pool: PgPool,
+    read_pool: Option<PgPool>,
+    max_snippet_chars: Option<usize>,
+    default_excluded_dirs: Vec<String>,
+    min_symbol_length: usize,
+    max_snippet_context: u32,
+    symbol_cache: Option<Arc<SymbolSearchCache>>,
+    lowercase_content: bool,
 }
 
 impl PostgresDb {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            read_pool: None,
+            max_snippet_chars: None,
+            default_excluded_dirs: Vec::new(),
+            min_symbol_length: DEFAULT_MIN_SYMBOL_LENGTH,
+            max_snippet_context: DEFAULT_MAX_SNIPPET_CONTEXT,
+            symbol_cache: None,
+            lowercase_content: false,
+        }
+    }
+
+    /// Caps the length of every search snippet returned by `text_search`.
+    pub fn with_max_snippet_chars(mut self, max_snippet_chars: usize) -> Self {
+        self.max_snippet_chars = Some(max_snippet_chars);
+        self
+    }
+
+    /// Hides files under these directory names (at any depth) from
+    /// `text_search` and `search_symbols` unless a path filter targets them.
+    pub fn with_default_excluded_dirs(mut self, dirs: Vec<String>) -> Self {
+        self.default_excluded_dirs = dirs;
+        self
+    }
+
+    /// Search terms shorter than this many characters are still matched as
+    /// text but are not treated as symbol names, so they no longer boost
+    /// files with matching symbols.
+    pub fn with_min_symbol_length(mut self, min_symbol_length: usize) -> Self {
+        self.min_symbol_length = min_symbol_length;
+        self
+    }
+
+    /// Larger `SnippetRequest::context` values are clamped to this many lines.
+    pub fn with_max_snippet_context(mut self, max_snippet_context: u32) -> Self {
+        self.max_snippet_context = max_snippet_context;
+        self
+    }
+
+    /// Sends search, browse and autocomplete reads to a read replica. Ingestion
+    /// keeps using the primary. A replica lags the primary, so a snapshot that
+    /// was just ingested can take a moment to become searchable.
+    pub fn with_read_pool(mut self, read_pool: PgPool) -> Self {
+        self.read_pool = Some(read_pool);
+        self
+    }
+
+    /// Pool for read-only queries: the replica when one is configured,
+    /// otherwise the primary.
+    fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
+    /// Serves repeated `search_symbols` requests from a shared short-lived
+    /// cache, so re-renders that ask the same question skip the ranked query.
+    /// Results can trail ingestion by up to the cache's TTL.
+    pub fn with_symbol_cache(mut self, cache: Arc<SymbolSearchCache>) -> Self {
+        self.symbol_cache = Some(cache);
+        self
+    }
+
+    /// Matches case-insensitive plain terms in `text_search` against
+    /// `lower(text_content)`, which the lowercased trigram index covers,
+    /// instead of `ILIKE`. Results are the same; only the plan changes.
+    pub fn with_lowercase_content(mut self, lowercase_content: bool) -> Self {
+        self.lowercase_content = lowercase_content;
+        self
     }
 }
 
+/// Pushes `column` matching `predicate`. With `lowercase_content`,
+/// case-insensitive plain terms compare `lower(column)` so the
+/// `idx_chunks_text_content_lower_trgm` expression index can serve them.
 fn push_content_predicate(
     qb: &mut QueryBuilder<'_, Postgres>,
     predicate: &ContentPredicate,
     case_mode: CaseSensitivity,
     column: &str,
+    lowercase_content: bool,
 ) {
     let (like_op, regex_op) = match case_mode {
         CaseSensitivity::Yes => (" LIKE ", " ~ "),
@@ -45,6 +276,16 @@ fn push_content_predicate(
     };
 
     match predicate {
+        ContentPredicate::Plain(value)
+            if lowercase_content && !matches!(case_mode, CaseSensitivity::Yes) =>
+        {
+            let escaped = escape_sql_like_literal(value);
+            qb.push("lower(");
+            qb.push(column);
+            qb.push(") LIKE '%' || lower(");
+            qb.push_bind(escaped);
+            qb.push(") || '%' ESCAPE '\\'");
+        }
         ContentPredicate::Plain(value) => {
             let escaped = escape_sql_like_literal(value);
             qb.push(column);
@@ -66,6 +307,7 @@ fn push_content_condition(
     predicate: &ContentPredicate,
     case_mode: CaseSensitivity,
     negate: bool,
+    lowercase_content: bool,
 ) {
     qb.push(" AND ");
     if negate {
@@ -74,7 +316,13 @@ fn push_content_condition(
         qb.push("(");
     }
 
-    push_content_predicate(qb, predicate, case_mode, "c.text_content");
+    push_content_predicate(
+        qb,
+        predicate,
+        case_mode,
+        "c.text_content",
+        lowercase_content,
+    );
 
     qb.push(")");
 }
@@ -187,6 +435,7 @@ fn push_search_ctes<'a>(
     needs_live_branch_filter: bool,
     symbol_terms: &'a [String],
     definition_terms: &'a [String],
+    lowercase_content: bool,
 ) {
     qb.push("WITH ");
 
@@ -300,10 +549,10 @@ fn push_search_ctes<'a>(
                 );
             }
 
-            push_content_condition(qb, seed_predicate, case_mode, false);
+            push_content_condition(qb, seed_predicate, case_mode, false, lowercase_content);
 
             for predicate in &plan.excluded_terms {
-                push_content_condition(qb, predicate, case_mode, true);
+                push_content_condition(qb, predicate, case_mode, true, lowercase_content);
             }
 
             qb.push(
@@ -323,7 +572,13 @@ fn push_search_ctes<'a>(
 
             for predicate in remaining_predicates {
                 qb.push(" AND (");
-                push_content_predicate(qb, predicate, case_mode, "seed.text_content");
+                push_content_predicate(
+                    qb,
+                    predicate,
+                    case_mode,
+                    "seed.text_content",
+                    lowercase_content,
+                );
                 qb.push(")");
             }
 
@@ -445,12 +700,12 @@ fn push_search_ctes<'a>(
 
             if !plan.required_terms.is_empty() {
                 for predicate in &plan.required_terms {
-                    push_content_condition(qb, predicate, case_mode, false);
+                    push_content_condition(qb, predicate, case_mode, false, lowercase_content);
                 }
             }
 
             for predicate in &plan.excluded_terms {
-                push_content_condition(qb, predicate, case_mode, true);
+                push_content_condition(qb, predicate, case_mode, true, lowercase_content);
             }
 
             qb.push(
@@ -511,6 +766,13 @@ fn push_search_ctes<'a>(
             }
         }
 
+        for bound in &plan.path_depths {
+            qb.push(" AND array_length(string_to_array(files.file_path, '/'), 1) ");
+            qb.push(bound.comparison.sql_operator());
+            qb.push(" ");
+            qb.push_bind(bound.depth);
+        }
+
         if !plan.langs.is_empty() {
             qb.push(" AND cb.language = ANY(");
             qb.push_bind(&plan.langs);
@@ -523,6 +785,47 @@ fn push_search_ctes<'a>(
             qb.push("))");
         }
 
+        for part in &plan.symbol_name_contains {
+            qb.push(
+                " AND EXISTS (SELECT 1 FROM symbols s
+                    WHERE s.content_hash = files.content_hash
+                      AND s.name_lc IN (SELECT us.name_lc FROM unique_symbols us WHERE us.name_lc LIKE ",
+            );
+            qb.push_bind(format!("%{}%", escape_sql_like_literal(part)));
+            qb.push(" ESCAPE '\\'))");
+        }
+
+        for name in &plan.symbol_terms {
+            qb.push(
+                " AND EXISTS (SELECT 1 FROM symbols s
+                    WHERE s.content_hash = files.content_hash AND s.name = ",
+            );
+            qb.push_bind(name);
+            qb.push(")");
+        }
+
+        for name in &plan.defined_symbols {
+            qb.push(
+                " AND EXISTS (SELECT 1 FROM symbols s
+                    JOIN symbol_references sr ON sr.symbol_id = s.id
+                    WHERE s.content_hash = files.content_hash
+                      AND sr.kind = 'definition' AND s.name = ",
+            );
+            qb.push_bind(name);
+            qb.push(")");
+        }
+
+        for name in &plan.excluded_defined_symbols {
+            qb.push(
+                " AND NOT EXISTS (SELECT 1 FROM symbols s
+                    JOIN symbol_references sr ON sr.symbol_id = s.id
+                    WHERE s.content_hash = files.content_hash
+                      AND sr.kind = 'definition' AND s.name = ",
+            );
+            qb.push_bind(name);
+            qb.push(")");
+        }
+
         if !plan.branches.is_empty() {
             qb.push(" AND (files.commit_sha = ANY(");
             qb.push_bind(&plan.branches);
@@ -868,6 +1171,184 @@ fn push_search_ctes<'a>(
     );
 }
 
+/// Serializes `request` with its filter lists sorted and deduplicated. Those
+/// lists are matched as sets, so reordering them must not miss the cache.
+/// The key is the whole normalized request, so distinct requests never share
+/// an entry. `None` when the request can't be serialized; it then bypasses
+/// the cache.
+fn symbol_cache_key(request: &SearchRequest) -> Option<String> {
+    let set = |values: &[String]| {
+        let mut values = values.to_vec();
+        values.sort_unstable();
+        values.dedup();
+        values
+    };
+    let mut normalized = request.clone();
+    normalized.kind = request.kind.as_deref().map(set).filter(|v| !v.is_empty());
+    normalized.language = request
+        .language
+        .as_deref()
+        .map(set)
+        .filter(|v| !v.is_empty());
+    normalized.include_paths = set(&request.include_paths);
+    normalized.excluded_paths = set(&request.excluded_paths);
+
+    serde_json::to_string(&normalized).ok()
+}
+
+/// Lowercased, sorted symbol and definition terms used to rank phase 1.
+fn ranking_terms(request: &TextSearchRequest, min_len: usize) -> (Vec<String>, Vec<String>) {
+    let mut symbol_terms: Vec<String> = collect_symbol_terms(request, min_len)
+        .into_iter()
+        .map(|t| t.to_lowercase())
+        .collect();
+    symbol_terms.sort_unstable();
+    let mut definition_terms: Vec<String> = collect_definition_terms(request, min_len)
+        .into_iter()
+        .map(|t| t.to_lowercase())
+        .collect();
+    definition_terms.sort_unstable();
+    (symbol_terms, definition_terms)
+}
+
+/// Builds the phase 1 ranking query: every matching file chunk, best first,
+/// up to the fetch budget.
+fn build_phase1_query<'a>(
+    request: &'a TextSearchRequest,
+    symbol_terms: &'a [String],
+    definition_terms: &'a [String],
+    lowercase_content: bool,
+) -> QueryBuilder<'a, Postgres> {
+    let SearchBudgets {
+        fetch_limit,
+        file_limit,
+        plan_row_limit,
+    } = compute_search_budgets(request);
+
+    let needs_live_branch_filter = request
+        .plans
+        .iter()
+        .any(|plan| plan.branches.is_empty() && !plan.include_historical);
+
+    let mut qb = QueryBuilder::new("");
+    push_search_ctes(
+        &mut qb,
+        request,
+        plan_row_limit,
+        fetch_limit,
+        file_limit,
+        needs_live_branch_filter,
+        symbol_terms,
+        definition_terms,
+        lowercase_content,
+    );
+    qb.push(
+        "
+        SELECT
+            fr.file_id,
+            fr.repository,
+            fr.commit_sha,
+            fr.file_path,
+            fr.content_hash,
+            fr.chunk_index,
+            fr.total_score,
+            fr.definition_matches,
+            fr.include_historical,
+            fr.branches,
+            fr.live_branches,
+            fr.is_historical,
+            fr.snapshot_indexed_at,
+            fr.highlight_pattern,
+            fr.highlight_case_sensitive,
+            cb.language
+        FROM filtered_ranked fr
+        LEFT JOIN content_blobs cb ON cb.hash = fr.content_hash
+        ORDER BY
+            fr.definition_matches DESC,
+            fr.total_score DESC,
+            fr.repository,
+            fr.commit_sha,
+            fr.file_path,
+            fr.chunk_index
+        LIMIT ",
+    );
+    qb.push_bind(fetch_limit);
+    qb
+}
+
+/// Counts matching files, grouped the way results are, up to
+/// [`TOTAL_ESTIMATE_CAP`]. Regex plans keep their usual row budget since each
+/// scanned chunk is costly; other plans scan far enough to reach the cap.
+fn build_total_estimate_query(
+    request: &TextSearchRequest,
+    lowercase_content: bool,
+) -> QueryBuilder<'_, Postgres> {
+    let cap = TOTAL_ESTIMATE_CAP as i64;
+    let plan_row_limit = if request_has_regex(request) {
+        compute_search_budgets(request).plan_row_limit
+    } else {
+        cap
+    };
+
+    let needs_live_branch_filter = request
+        .plans
+        .iter()
+        .any(|plan| plan.branches.is_empty() && !plan.include_historical);
+
+    let mut qb = QueryBuilder::new("");
+    push_search_ctes(
+        &mut qb,
+        request,
+        plan_row_limit,
+        cap,
+        cap,
+        needs_live_branch_filter,
+        &[],
+        &[],
+        lowercase_content,
+    );
+    qb.push(
+        "
+        SELECT COUNT(*)
+        FROM (
+            SELECT DISTINCT f.repository, f.commit_sha, f.file_path, pr.content_hash
+            FROM plan_results pr
+            JOIN files f ON f.id = pr.file_id
+            LIMIT ",
+    );
+    qb.push_bind(cap);
+    qb.push(
+        "
+        ) matched_files",
+    );
+    qb
+}
+
+/// Finds where each file's best-ranked chunk starts. This is all the
+/// no-snippet mode needs, so it never touches chunk text.
+fn build_location_query(page_rows: &[RankedFileRow]) -> QueryBuilder<'_, Postgres> {
+    let mut qb = QueryBuilder::new("WITH paged_files (ord, content_hash, chunk_index) AS (");
+    qb.push_values(page_rows.iter().enumerate(), |mut b, (ord, row)| {
+        b.push_bind(ord as i64)
+            .push_bind(&row.content_hash)
+            .push_bind(row.chunk_index);
+    });
+    qb.push(
+        "
+        )
+        SELECT
+            pf.ord,
+            (1 + COALESCE(SUM(cbc.chunk_line_count), 0))::BIGINT AS start_line
+        FROM paged_files pf
+        LEFT JOIN content_blob_chunks cbc
+          ON cbc.content_hash = pf.content_hash
+         AND cbc.chunk_index < pf.chunk_index
+        GROUP BY pf.ord
+        ORDER BY pf.ord",
+    );
+    qb
+}
+
 #[async_trait]
 impl Database for PostgresDb {
     async fn get_all_repositories(&self) -> Result<Vec<RepoSummary>, DbError> {
@@ -887,9 +1368,9 @@ impl Database for PostgresDb {
             GROUP BY f.repository
             ORDER BY f.repository",
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
+        .map_err(DbError::from)?;
 
         let repos = rows
             .into_iter()
@@ -927,18 +1408,18 @@ impl Database for PostgresDb {
             "#,
             repository
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
+        .map_err(DbError::from)?;
 
         if rows.is_empty() {
             let commits: Vec<String> = sqlx::query_scalar(
                 "SELECT DISTINCT commit_sha FROM files WHERE repository = $1 ORDER BY commit_sha DESC",
             )
             .bind(repository)
-            .fetch_all(&self.pool)
+            .fetch_all(self.read_pool())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
             let fallback = commits
                 .into_iter()
@@ -965,6 +1446,52 @@ impl Database for PostgresDb {
         Ok(branches)
     }
 
+    async fn get_all_branches(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<BranchListing>, DbError> {
+        let rows: Vec<(String, String, String, bool, Option<DateTime<Utc>>)> = sqlx::query_as(
+            r#"
+            SELECT
+                b.repository,
+                b.branch,
+                b.commit_sha,
+                lb.branch IS NOT NULL AS is_live,
+                COALESCE(snapshot.latest_indexed_at, b.indexed_at) AS indexed_at
+            FROM branches b
+            LEFT JOIN repo_live_branches lb
+              ON lb.repository = b.repository
+             AND lb.branch = b.branch
+            LEFT JOIN LATERAL (
+                SELECT MAX(indexed_at) AS latest_indexed_at
+                FROM branch_snapshots bs
+                WHERE bs.repository = b.repository AND bs.branch = b.branch
+            ) snapshot ON TRUE
+            ORDER BY b.repository, b.branch
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind(limit.max(0))
+        .bind(offset.max(0))
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(repository, branch, commit_sha, is_live, indexed_at)| BranchListing {
+                    repository,
+                    branch,
+                    commit_sha,
+                    is_live,
+                    indexed_at: indexed_at.map(|dt| dt.to_rfc3339()),
+                },
+            )
+            .collect())
+    }
+
     async fn resolve_branch_head(
         &self,
         repository: &str,
@@ -975,13 +1502,47 @@ impl Database for PostgresDb {
         )
         .bind(repository)
         .bind(branch)
-        .fetch_optional(&self.pool)
+        .fetch_optional(self.read_pool())
         .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
+        .map_err(DbError::from)?;
 
         Ok(commit)
     }
 
+    async fn get_commit_metadata(
+        &self,
+        repository: &str,
+        commit_shas: &[String],
+    ) -> Result<Vec<CommitMetadata>, DbError> {
+        if commit_shas.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<(String, String, String, DateTime<Utc>, String)> = sqlx::query_as(
+            "SELECT commit_sha, author_name, author_email, committed_at, subject
+             FROM commits
+             WHERE repository = $1 AND commit_sha = ANY($2)",
+        )
+        .bind(repository)
+        .bind(commit_shas)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(commit_sha, author_name, author_email, committed_at, subject)| CommitMetadata {
+                    commit_sha,
+                    author_name,
+                    author_email,
+                    committed_at: committed_at.to_rfc3339(),
+                    subject,
+                },
+            )
+            .collect())
+    }
+
     async fn chunk_need(&self, hashes: Vec<String>) -> Result<Vec<String>, DbError> {
         if hashes.is_empty() {
             return Ok(Vec::new());
@@ -992,7 +1553,7 @@ impl Database for PostgresDb {
                 .bind(&hashes)
                 .fetch_all(&self.pool)
                 .await
-                .map_err(|e| DbError::Database(e.to_string()))?;
+                .map_err(DbError::from)?;
 
         let present: std::collections::HashSet<String> =
             existing.into_iter().map(|row| row.0).collect();
@@ -1018,7 +1579,7 @@ impl Database for PostgresDb {
             qb.build()
                 .execute(&self.pool)
                 .await
-                .map_err(|e| DbError::Database(e.to_string()))?;
+                .map_err(DbError::from)?;
         }
 
         Ok(())
@@ -1049,7 +1610,7 @@ impl Database for PostgresDb {
         .bind(data)
         .execute(&self.pool)
         .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
+        .map_err(DbError::from)?;
 
         Ok(())
     }
@@ -1067,7 +1628,7 @@ impl Database for PostgresDb {
         .bind(&upload_id)
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
+        .map_err(DbError::from)?;
 
         if rows.is_empty() {
             return Err(DbError::Internal(
@@ -1120,7 +1681,7 @@ impl Database for PostgresDb {
             .bind(&upload_id)
             .execute(&self.pool)
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
         Ok(())
     }
@@ -1130,9 +1691,9 @@ impl Database for PostgresDb {
             "SELECT DISTINCT commit_sha FROM files WHERE repository = $1 ORDER BY commit_sha DESC",
         )
         .bind(repository)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
+        .map_err(DbError::from)?;
 
         Ok(commits)
     }
@@ -1158,25 +1719,26 @@ impl Database for PostgresDb {
             )
         };
 
-        let rows: Vec<String> = sqlx::query_scalar(
-            "SELECT file_path FROM files WHERE repository = $1 AND commit_sha = $2 AND (file_path = $3 OR file_path LIKE $4)",
+        let rows: Vec<(String, String, Option<i32>)> = sqlx::query_as(
+            "SELECT file_path, kind, mode FROM files WHERE repository = $1 AND commit_sha = $2 AND (file_path = $3 OR file_path LIKE $4)",
         )
         .bind(repository)
         .bind(&query.commit)
         .bind(normalized_prefix)
         .bind(like_pattern)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
+        .map_err(DbError::from)?;
 
         if rows.is_empty() && !normalized_prefix.is_empty() {
             return Err(DbError::Internal("path not found".to_string()));
         }
 
         let mut directories: std::collections::HashSet<String> = std::collections::HashSet::new();
-        let mut files: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut files: std::collections::HashMap<String, (String, Option<i32>)> =
+            std::collections::HashMap::new();
 
-        for path in rows {
+        for (path, kind, mode) in rows {
             let relative = if normalized_prefix.is_empty() {
                 path.clone()
             } else if path == normalized_prefix {
@@ -1206,7 +1768,7 @@ impl Database for PostgresDb {
                 } else {
                     format!("{}/{}", normalized_prefix, relative)
                 };
-                files.insert(file_path);
+                files.insert(file_path, (kind, mode));
             }
         }
 
@@ -1216,10 +1778,11 @@ impl Database for PostgresDb {
                 name: dir.rsplit('/').next().unwrap_or(&dir).to_string(),
                 path: dir,
                 kind: "dir".to_string(),
+                executable: false,
             })
             .collect();
 
-        entries.extend(files.into_iter().map(|file_path| {
+        entries.extend(files.into_iter().map(|(file_path, (kind, mode))| {
             TreeEntry {
                 name: file_path
                     .rsplit('/')
@@ -1227,14 +1790,15 @@ impl Database for PostgresDb {
                     .unwrap_or(&file_path)
                     .to_string(),
                 path: file_path,
-                kind: "file".to_string(),
+                kind: tree_entry_kind(&kind).to_string(),
+                executable: kind == "regular" && mode_is_executable(mode),
             }
         }));
 
-        entries.sort_by(|a, b| match (a.kind.as_str(), b.kind.as_str()) {
-            ("dir", "file") => std::cmp::Ordering::Less,
-            ("file", "dir") => std::cmp::Ordering::Greater,
-            _ => a.name.cmp(&b.name),
+        entries.sort_by(|a, b| {
+            (a.kind != "dir")
+                .cmp(&(b.kind != "dir"))
+                .then_with(|| a.name.cmp(&b.name))
         });
 
         Ok(TreeResponse {
@@ -1272,82 +1836,93 @@ impl Database for PostgresDb {
             }
         }
         let pattern = format!("%{escaped}%");
-        let fetch_limit = (limit.saturating_mul(5)).clamp(1, 200);
+        // Ranking happens after the fetch, so pull a wide candidate set,
+        // shortest paths first since they tend to be the closest matches.
+        let fetch_limit = (limit.saturating_mul(20)).clamp(1, 1000);
 
         let rows: Vec<String> = sqlx::query_scalar(
             "SELECT file_path
-             FROM files
-             WHERE repository = $1
-             AND commit_sha = $2
-             AND file_path ILIKE $3 ESCAPE '\\'
-             ORDER BY file_path
-             LIMIT $4",
+         FROM files
+         WHERE repository = $1
+         AND commit_sha = $2
+         AND file_path ILIKE $3 ESCAPE '\\'
+         ORDER BY char_length(file_path), file_path
+         LIMIT $4",
         )
         .bind(repository)
         .bind(commit_sha)
         .bind(&pattern)
         .bind(fetch_limit)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
-
-        if rows.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let query_lower = trimmed.to_ascii_lowercase();
-        let mut dir_set: std::collections::HashSet<String> = std::collections::HashSet::new();
-        let mut file_paths: Vec<String> = Vec::new();
-        let mut seen_files: std::collections::HashSet<String> = std::collections::HashSet::new();
+        .map_err(DbError::from)?;
 
-        for path in rows {
-            let lower = path.to_ascii_lowercase();
-            if lower.contains(&query_lower) && seen_files.insert(path.clone()) {
-                file_paths.push(path.clone());
-            }
+        Ok(rank_path_matches(
+            &rows,
+            trimmed,
+            usize::try_from(limit).unwrap_or(usize::MAX),
+        ))
+    }
 
-            let mut segments: Vec<&str> = path.split('/').collect();
-            if segments.len() > 1 {
-                segments.pop();
-                while !segments.is_empty() {
-                    let dir = segments.join("/");
-                    if dir.to_ascii_lowercase().contains(&query_lower) {
-                        dir_set.insert(dir.clone());
-                    }
-                    segments.pop();
-                }
-            }
+    async fn find_file_in_repos(
+        &self,
+        paths: &[String],
+        repositories: &[String],
+    ) -> Result<Vec<FileLocationMatch>, DbError> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let mut directories: Vec<String> = dir_set.into_iter().collect();
-        directories.sort();
-
-        let mut entries = Vec::new();
-        for dir in directories {
-            let name = dir.rsplit('/').next().unwrap_or(&dir).to_string();
-            entries.push(TreeEntry {
-                name,
-                path: dir,
-                kind: "dir".to_string(),
-            });
-            if entries.len() as i64 >= limit {
-                return Ok(entries);
-            }
+        let mut qb = QueryBuilder::new(
+            "WITH live_heads AS (
+                SELECT b.repository, b.branch, b.commit_sha
+                FROM repo_live_branches lb
+                JOIN branches b
+                  ON b.repository = lb.repository
+                 AND b.branch = lb.branch
+                UNION
+                SELECT b.repository, b.branch, b.commit_sha
+                FROM branches b
+                WHERE NOT EXISTS (
+                    SELECT 1
+                    FROM repo_live_branches lb
+                    WHERE lb.repository = b.repository
+                )
+            )
+            SELECT DISTINCT ON (f.repository, f.file_path)
+                f.repository, lh.branch, f.commit_sha, f.file_path
+            FROM files f
+            JOIN live_heads lh
+              ON lh.repository = f.repository
+             AND lh.commit_sha = f.commit_sha
+            WHERE f.file_path = ANY(",
+        );
+        qb.push_bind(paths);
+        qb.push(")");
+        if !repositories.is_empty() {
+            qb.push(" AND f.repository = ANY(");
+            qb.push_bind(repositories);
+            qb.push(")");
         }
+        qb.push(" ORDER BY f.repository, f.file_path, lh.branch LIMIT 50");
 
-        for path in file_paths {
-            let name = path.rsplit('/').next().unwrap_or(&path).to_string();
-            entries.push(TreeEntry {
-                name,
-                path,
-                kind: "file".to_string(),
-            });
-            if entries.len() as i64 >= limit {
-                break;
-            }
-        }
+        let rows: Vec<(String, String, String, String)> = qb
+            .build_query_as()
+            .fetch_all(self.read_pool())
+            .await
+            .map_err(DbError::from)?;
 
-        Ok(entries)
+        Ok(rows
+            .into_iter()
+            .map(
+                |(repository, branch, commit_sha, file_path)| FileLocationMatch {
+                    repository,
+                    branch,
+                    commit_sha,
+                    file_path,
+                },
+            )
+            .collect())
     }
 
     async fn get_file_content(
@@ -1365,149 +1940,494 @@ impl Database for PostgresDb {
         let data = self
             .load_file_data(repository, commit_sha, file_path)
             .await?;
-
-        let text = String::from_utf8_lossy(&data.bytes).to_string();
-        Ok(RawFileContent {
-            repository: repository.to_string(),
-            commit_sha: commit_sha.to_string(),
-            file_path: file_path.to_string(),
-            language: data.language,
-            content: text,
-        })
-    }
-
-    async fn get_file_snippet(&self, request: SnippetRequest) -> Result<SnippetResponse, DbError> {
-        let snippets = self.get_file_snippets(vec![request]).await?;
-        snippets
-            .into_iter()
-            .next()
-            .ok_or_else(|| DbError::Internal("missing snippet response".to_string()))
+        Ok(raw_file_content(
+            repository.to_string(),
+            commit_sha.to_string(),
+            file_path.to_string(),
+            data,
+        ))
     }
 
-    async fn get_file_snippets(
+    async fn get_file_contents(
         &self,
-        requests: Vec<SnippetRequest>,
-    ) -> Result<Vec<SnippetResponse>, DbError> {
+        requests: Vec<FileContentRequest>,
+    ) -> Result<Vec<RawFileContent>, DbError> {
         if requests.is_empty() {
             return Ok(Vec::new());
         }
+        let mut ords = Vec::with_capacity(requests.len());
+        let mut repositories = Vec::with_capacity(requests.len());
+        let mut commits = Vec::with_capacity(requests.len());
+        let mut paths = Vec::with_capacity(requests.len());
+        for (ord, request) in requests.iter().enumerate() {
+            ords.push(i32::try_from(ord).unwrap_or(i32::MAX));
+            repositories.push(request.repository.as_str());
+            commits.push(request.commit_sha.as_str());
+            paths.push(request.file_path.as_str());
+        }
 
-        let total = requests.len();
-        let mut repositories = Vec::with_capacity(total);
-        let mut commits = Vec::with_capacity(total);
-        let mut paths = Vec::with_capacity(total);
-        let mut lines = Vec::with_capacity(total);
-        let mut contexts = Vec::with_capacity(total);
-
-        for request in requests {
-            if request.line == 0 {
+        let files: Vec<(i32, String, Option<String>, String, i64)> = sqlx::query_as(
+            "SELECT r.ord, f.content_hash, cb.language, f.kind, cb.byte_len
+             FROM UNNEST($1::INT[], $2::TEXT[], $3::TEXT[], $4::TEXT[])
+                 AS r(ord, repository, commit_sha, file_path)
+             JOIN files f
+               ON f.repository = r.repository
+              AND f.commit_sha = r.commit_sha
+              AND f.file_path = r.file_path
+             JOIN content_blobs cb ON cb.hash = f.content_hash",
+        )
+        .bind(&ords)
+        .bind(&repositories)
+        .bind(&commits)
+        .bind(&paths)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
+
+        let mut found: Vec<Option<(String, Option<String>, String, i64)>> =
+            vec![None; requests.len()];
+        for (ord, content_hash, language, kind, byte_len) in files {
+            if let Some(slot) = usize::try_from(ord).ok().and_then(|ord| found.get_mut(ord)) {
+                *slot = Some((content_hash, language, kind, byte_len));
+            }
+        }
+        if let Some(missing) = found.iter().position(Option::is_none) {
+            return Err(DbError::Internal(format!(
+                "file not found: {}",
+                requests[missing].file_path
+            )));
+        }
+
+        // Identical files share a content hash, so each blob's chunks are
+        // fetched and stitched together once.
+        let mut hashes: Vec<&str> = found
+            .iter()
+            .flatten()
+            .map(|(hash, ..)| hash.as_str())
+            .collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        let chunk_rows: Vec<(String, String, Option<Vec<u8>>)> = sqlx::query_as(
+            "SELECT cbc.content_hash, c.text_content, c.compressed_content
+             FROM content_blob_chunks cbc
+             JOIN chunks c ON cbc.chunk_hash = c.chunk_hash
+             WHERE cbc.content_hash = ANY($1)
+             ORDER BY cbc.content_hash, cbc.chunk_index",
+        )
+        .bind(&hashes)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
+
+        let mut blobs: HashMap<String, Vec<u8>> = HashMap::with_capacity(hashes.len());
+        for (content_hash, text_content, compressed_content) in chunk_rows {
+            blobs
+                .entry(content_hash)
+                .or_default()
+                .extend(chunk_bytes(text_content, compressed_content)?);
+        }
+
+        Ok(requests
+            .into_iter()
+            .zip(found.into_iter().flatten())
+            .map(|(request, (content_hash, language, kind, byte_len))| {
+                let bytes = blobs.get(&content_hash).cloned().unwrap_or_default();
+                let data = FileData {
+                    content_skipped: bytes.is_empty() && byte_len > 0,
+                    bytes,
+                    language,
+                    kind,
+                };
+                raw_file_content(
+                    request.repository,
+                    request.commit_sha,
+                    request.file_path,
+                    data,
+                )
+            })
+            .collect())
+    }
+
+    async fn get_raw_file_bytes(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+    ) -> Result<RawFileBytes, DbError> {
+        if commit_sha.is_empty() {
+            return Err(DbError::Internal("missing commit parameter".to_string()));
+        }
+        if file_path.is_empty() {
+            return Err(DbError::Internal("missing file path".to_string()));
+        }
+        let data = self
+            .load_file_data(repository, commit_sha, file_path)
+            .await?;
+
+        Ok(RawFileBytes {
+            is_binary: is_binary_content(&data.bytes),
+            content_skipped: data.content_skipped,
+            bytes: data.bytes,
+        })
+    }
+
+    async fn search_in_file(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+        pattern: &str,
+        case: CaseSensitivity,
+    ) -> Result<Vec<InFileMatch>, DbError> {
+        if pattern.is_empty() {
+            return Ok(Vec::new());
+        }
+        let data = self
+            .load_file_data(repository, commit_sha, file_path)
+            .await?;
+        let case_sensitive = match case {
+            CaseSensitivity::Yes => true,
+            CaseSensitivity::No => false,
+            CaseSensitivity::Auto => has_uppercase(pattern),
+        };
+        Ok(find_in_file_lines(
+            &String::from_utf8_lossy(&data.bytes),
+            pattern,
+            case_sensitive,
+        ))
+    }
+
+    async fn get_repo_overview(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+    ) -> Result<RepoOverview, DbError> {
+        if commit_sha.is_empty() {
+            return Err(DbError::Internal("missing commit parameter".to_string()));
+        }
+
+        let directories: Vec<(String, i64, Option<String>)> = sqlx::query_as(
+            "WITH per_language AS (
+                SELECT
+                    CASE WHEN strpos(f.file_path, '/') > 0
+                        THEN split_part(f.file_path, '/', 1)
+                        ELSE ''
+                    END AS dir,
+                    cb.language,
+                    COUNT(*) AS files
+                FROM files f
+                JOIN content_blobs cb ON cb.hash = f.content_hash
+                WHERE f.repository = $1 AND f.commit_sha = $2
+                GROUP BY 1, 2
+            )
+            SELECT
+                dir,
+                SUM(files)::BIGINT,
+                (ARRAY_AGG(language ORDER BY files DESC, language)
+                    FILTER (WHERE language IS NOT NULL))[1]
+            FROM per_language
+            GROUP BY dir
+            ORDER BY dir",
+        )
+        .bind(repository)
+        .bind(commit_sha)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
+
+        let largest_files: Vec<(String, i64, Option<String>)> = sqlx::query_as(
+            "SELECT f.file_path, cb.byte_len, cb.language
+            FROM files f
+            JOIN content_blobs cb ON cb.hash = f.content_hash
+            WHERE f.repository = $1 AND f.commit_sha = $2
+            ORDER BY cb.byte_len DESC, f.file_path
+            LIMIT $3",
+        )
+        .bind(repository)
+        .bind(commit_sha)
+        .bind(REPO_OVERVIEW_LARGEST_FILES)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
+
+        let candidates: Vec<String> = sqlx::query_scalar(
+            "SELECT file_path
+            FROM files
+            WHERE repository = $1 AND commit_sha = $2 AND file_path ~ $3
+            ORDER BY file_path
+            LIMIT 2000",
+        )
+        .bind(repository)
+        .bind(commit_sha)
+        .bind(repo_overview::CANDIDATE_PATH_PATTERN)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
+        let (entry_points, build_files) = repo_overview::classify_candidates(&candidates);
+
+        Ok(RepoOverview {
+            directories: directories
+                .into_iter()
+                .map(|(name, file_count, dominant_language)| DirectoryOverview {
+                    name,
+                    file_count,
+                    dominant_language,
+                })
+                .collect(),
+            largest_files: largest_files
+                .into_iter()
+                .map(|(path, byte_len, language)| OverviewFile {
+                    path,
+                    byte_len,
+                    language,
+                })
+                .collect(),
+            entry_points,
+            build_files,
+        })
+    }
+
+    async fn get_reference_graph(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        granularity: GraphGranularity,
+        limit: i64,
+    ) -> Result<Vec<GraphEdge>, DbError> {
+        if commit_sha.is_empty() {
+            return Err(DbError::Internal("missing commit parameter".to_string()));
+        }
+
+        // Uses carry the namespace of the symbol they refer to, so a use
+        // joins to every definition of the same name and namespace that
+        // lives in another file of the snapshot.
+        let mut qb = QueryBuilder::new(
+            "WITH repo_refs AS (
+                SELECT f.file_path, s.name, sr.namespace_id, sn.namespace, sr.kind
+                FROM files f
+                JOIN symbols s ON s.content_hash = f.content_hash
+                JOIN symbol_references sr ON sr.symbol_id = s.id
+                JOIN symbol_namespaces sn ON sn.id = sr.namespace_id
+                WHERE f.repository = ",
+        );
+        qb.push_bind(repository)
+            .push(" AND f.commit_sha = ")
+            .push_bind(commit_sha)
+            .push(
+                "
+            ),
+            definitions AS (
+                SELECT DISTINCT file_path, name, namespace_id, namespace
+                FROM repo_refs
+                WHERE kind = 'definition'
+            ),
+            edges AS (
+                SELECT
+                    u.file_path AS source_file,
+                    d.file_path AS target_file,
+                    d.namespace AS target_namespace
+                FROM repo_refs u
+                JOIN definitions d
+                  ON d.name = u.name
+                 AND d.namespace_id = u.namespace_id
+                 AND d.file_path <> u.file_path
+                WHERE u.kind IS DISTINCT FROM 'definition'
+            )",
+            );
+
+        match granularity {
+            GraphGranularity::File => {
+                qb.push(
+                    "
+            SELECT e.source_file AS source, e.target_file AS target, COUNT(*)::BIGINT AS weight
+            FROM edges e",
+                );
+            }
+            GraphGranularity::Namespace => {
+                // A file belongs to the namespace most of its definitions
+                // live in; files without definitions land in the global one.
+                qb.push(
+                    ",
+            file_namespaces AS (
+                SELECT DISTINCT ON (file_path) file_path, namespace
+                FROM definitions
+                GROUP BY file_path, namespace
+                ORDER BY file_path, COUNT(*) DESC, namespace
+            )
+            SELECT
+                COALESCE(fn.namespace, '') AS source,
+                e.target_namespace AS target,
+                COUNT(*)::BIGINT AS weight
+            FROM edges e
+            LEFT JOIN file_namespaces fn ON fn.file_path = e.source_file
+            WHERE COALESCE(fn.namespace, '') <> e.target_namespace",
+                );
+            }
+        }
+        qb.push(
+            "
+            GROUP BY 1, 2
+            ORDER BY weight DESC, source, target
+            LIMIT ",
+        )
+        .push_bind(limit);
+
+        let rows: Vec<(String, String, i64)> = qb
+            .build_query_as()
+            .fetch_all(self.read_pool())
+            .await
+            .map_err(DbError::from)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(source, target, weight)| GraphEdge {
+                source,
+                target,
+                weight,
+            })
+            .collect())
+    }
+
+    async fn get_file_snippet(&self, request: SnippetRequest) -> Result<SnippetResponse, DbError> {
+        let snippets = self.get_file_snippets(vec![request]).await?;
+        snippets
+            .into_iter()
+            .next()
+            .ok_or_else(|| DbError::Internal("missing snippet response".to_string()))
+    }
+
+    async fn get_file_snippets(
+        &self,
+        requests: Vec<SnippetRequest>,
+    ) -> Result<Vec<SnippetResponse>, DbError> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let total = requests.len();
+        let mut repositories = Vec::with_capacity(total);
+        let mut commits = Vec::with_capacity(total);
+        let mut paths = Vec::with_capacity(total);
+        let mut lines = Vec::with_capacity(total);
+        let mut contexts = Vec::with_capacity(total);
+        let mut matches = Vec::with_capacity(total);
+
+        for request in requests {
+            if request.line == 0 {
                 return Err(DbError::Internal("line numbers are 1-based".to_string()));
             }
 
             repositories.push(request.repository);
             commits.push(request.commit_sha);
             paths.push(request.file_path);
-            lines.push(i32::try_from(request.line).unwrap_or(i32::MAX));
-            contexts.push(request.context.unwrap_or(3).min(3) as i32);
+            let line = i32::try_from(request.line).unwrap_or(i32::MAX);
+            lines.push(line);
+            // Clamped to the configured maximum, and `line + context`
+            // has to stay inside the SQL integer range.
+            let context = request.context.unwrap_or(3).min(self.max_snippet_context);
+            let context = i32::try_from(context).unwrap_or(i32::MAX);
+            contexts.push(context.min(i32::MAX - line));
+            matches.push(SnippetMatch {
+                column: request.column,
+                highlight: request.highlight,
+                case_sensitive: request.case_sensitive.unwrap_or(false),
+            });
         }
 
         let rows: Vec<SnippetRow> = sqlx::query_as(
             r#"
 WITH req AS (
-    SELECT
-        (ordinality - 1)::int AS idx,
-        repo,
-        commit_sha,
-        file_path,
-        line,
-        context
-    FROM
-        unnest($1::text[], $2::text[], $3::text[], $4::int[], $5::int[])
-        WITH ORDINALITY AS t(repo, commit_sha, file_path, line, context, ordinality)
+SELECT
+    (ordinality - 1)::int AS idx,
+    repo,
+    commit_sha,
+    file_path,
+    line,
+    context
+FROM
+    unnest($1::text[], $2::text[], $3::text[], $4::int[], $5::int[])
+    WITH ORDINALITY AS t(repo, commit_sha, file_path, line, context, ordinality)
 ), data AS (
-    SELECT
-        req.idx,
-        req.line,
-        req.context,
-        cb.line_count,
-        string_agg(chunks.text_content, '' ORDER BY cbc.chunk_index) AS text_content
-    FROM req
-    JOIN files f
-      ON f.repository = req.repo
-     AND f.commit_sha = req.commit_sha
-     AND f.file_path = req.file_path
-    JOIN content_blobs cb
-      ON cb.hash = f.content_hash
-    JOIN content_blob_chunks cbc
-      ON cbc.content_hash = cb.hash
-    JOIN chunks
-      ON chunks.chunk_hash = cbc.chunk_hash
-    GROUP BY req.idx, req.line, req.context, cb.line_count
+SELECT
+    req.idx,
+    req.line,
+    req.context,
+    cb.line_count,
+    string_agg(chunks.text_content, '' ORDER BY cbc.chunk_index) AS text_content,
+    bool_or(chunks.compressed_content IS NOT NULL) AS compressed
+FROM req
+JOIN files f
+  ON f.repository = req.repo
+ AND f.commit_sha = req.commit_sha
+ AND f.file_path = req.file_path
+JOIN content_blobs cb
+  ON cb.hash = f.content_hash
+JOIN content_blob_chunks cbc
+  ON cbc.content_hash = cb.hash
+JOIN chunks
+  ON chunks.chunk_hash = cbc.chunk_hash
+GROUP BY req.idx, req.line, req.context, cb.line_count
 )
 SELECT
-    idx,
-    line,
-    context,
-    line_count,
-    GREATEST(line - context, 1) AS start_line,
-    LEAST(line + context, line_count) AS end_line,
-    array_to_string(
-        (string_to_array(text_content, E'\n'))[
-            GREATEST(line - context, 1):
-            LEAST(line + context, line_count)
-        ],
-        E'\n'
-    ) AS snippet
+idx,
+line,
+context,
+line_count,
+compressed,
+GREATEST(line - context, 1) AS start_line,
+LEAST(line + GREATEST(context, $6), line_count) AS end_line,
+array_to_string(
+    (string_to_array(text_content, E'\n'))[
+        GREATEST(line - context, 1):
+        LEAST(line + GREATEST(context, $6), line_count)
+    ],
+    E'\n'
+) AS snippet
 FROM data
 ORDER BY idx
-            "#,
+        "#,
         )
         .bind(&repositories)
         .bind(&commits)
         .bind(&paths)
         .bind(&lines)
         .bind(&contexts)
-        .fetch_all(&self.pool)
+        .bind(MAX_HIGHLIGHT_SPAN_LINES as i32 - 1)
+        .fetch_all(self.read_pool())
         .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
+        .map_err(DbError::from)?;
 
         let mut responses: Vec<Option<SnippetResponse>> = vec![None; total];
 
-        for row in rows {
+        for mut row in rows {
             let idx = usize::try_from(row.idx)
                 .map_err(|_| DbError::Internal("invalid snippet index".to_string()))?;
             if idx >= responses.len() {
                 return Err(DbError::Internal("snippet index out of bounds".to_string()));
             }
+            // The SQL slice only sees plain text_content; compressed
+            // files are decoded here and sliced the same way.
+            if row.compressed {
+                let data = self
+                    .load_file_data(&repositories[idx], &commits[idx], &paths[idx])
+                    .await?;
+                row.snippet = Some(slice_lines(
+                    &String::from_utf8_lossy(&data.bytes),
+                    row.start_line,
+                    row.end_line,
+                ));
+            }
 
-            let snippet_text = row.snippet.unwrap_or_default();
-            let lines_vec: Vec<String> = if snippet_text.is_empty() {
-                Vec::new()
-            } else {
-                snippet_text.split('\n').map(|s| s.to_string()).collect()
-            };
-
-            let start_line = row.start_line.max(1) as u32;
-            let highlight_line = row.line.max(1) as u32;
-            let total_lines = row.line_count.max(0) as u32;
-            let end_line = row.end_line.max(row.start_line);
-            let truncated = start_line > 1 || end_line < row.line_count;
-
-            responses[idx] = Some(SnippetResponse {
-                start_line,
-                highlight_line,
-                total_lines,
-                lines: lines_vec,
-                truncated,
-            });
+            responses[idx] = Some(assemble_snippet(row, &matches[idx]));
         }
 
-        responses
+        // Requests for files that are not indexed get no row; they come
+        // back empty instead of failing the rest of the batch.
+        Ok(responses
             .into_iter()
-            .map(|snippet| {
-                snippet.ok_or_else(|| DbError::Internal("missing snippet response".to_string()))
-            })
-            .collect()
+            .zip(lines)
+            .map(|(snippet, line)| snippet.unwrap_or_else(|| missing_file_snippet(line)))
+            .collect())
     }
 
     async fn get_symbol_references(
@@ -1547,9 +2467,9 @@ ORDER BY idx
 
             let def_rows: Vec<(i32, Option<String>)> = qb
                 .build_query_as()
-                .fetch_all(&self.pool)
+                .fetch_all(self.read_pool())
                 .await
-                .map_err(|e| DbError::Database(e.to_string()))?;
+                .map_err(DbError::from)?;
 
             for (symbol_id, ns) in def_rows {
                 symbol_ids.push(symbol_id);
@@ -1563,7 +2483,7 @@ ORDER BY idx
 
         let mut qb = QueryBuilder::new(
             "SELECT f.repository, f.commit_sha, f.file_path, NULLIF(sn.namespace, '') AS namespace, s.name AS name, sr.kind, \
-                    sr.line_number AS line, sr.column_number AS column \
+                    sr.line_number AS line, sr.column_number AS column, COUNT(*) OVER () AS total_count \
              FROM symbol_references sr \
              JOIN symbols s ON s.id = sr.symbol_id \
              JOIN symbol_namespaces sn ON sn.id = sr.namespace_id \
@@ -1581,22 +2501,44 @@ ORDER BY idx
         } else {
             qb.push(" AND s.name = ").push_bind(&name);
             if let Some(ns) = namespace_filter {
-                qb.push(" AND COALESCE(sn.namespace, '') = ").push_bind(ns);
+                // A use inside a nested scope, such as a method body in the
+                // same package, refers to the enclosing namespace's definition.
+                let nested = format!("{ns}::");
+                qb.push(" AND (COALESCE(sn.namespace, '') = ")
+                    .push_bind(ns)
+                    .push(" OR (sr.kind = 'reference' AND starts_with(sn.namespace, ")
+                    .push_bind(nested)
+                    .push(")))");
             }
         }
 
-        qb.push(" ORDER BY f.file_path, sr.line_number, sr.column_number");
-
-        let rows: Vec<DbFileReference> = qb
+        let limit = request
+            .limit
+            .unwrap_or(MAX_SYMBOL_REFERENCE_PAGE)
+            .clamp(1, MAX_SYMBOL_REFERENCE_PAGE);
+        let offset = request.offset.unwrap_or(0);
+        // The reference id breaks ties between rows at the same position,
+        // so pages neither overlap nor skip rows.
+        qb.push(" ORDER BY f.file_path, sr.line_number, sr.column_number, sr.id LIMIT ")
+            .push_bind(i64::from(limit))
+            .push(" OFFSET ")
+            .push_bind(i64::from(offset));
+
+        let rows: Vec<SymbolReferenceRow> = qb
             .build_query_as()
-            .fetch_all(&self.pool)
+            .fetch_all(self.read_pool())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
+
+        let total_count = rows.first().map_or(0, |row| row.total_count);
+        let has_more = i64::from(offset) + (rows.len() as i64) < total_count;
 
         Ok(SymbolReferenceResponse {
+            total_count,
+            has_more,
             references: rows
                 .into_iter()
-                .map(|r| FileReference {
+                .map(|SymbolReferenceRow { reference: r, .. }| FileReference {
                     repository: r.repository,
                     commit_sha: r.commit_sha,
                     file_path: r.file_path,
@@ -1610,306 +2552,169 @@ ORDER BY idx
         })
     }
 
-    async fn search_symbols(&self, request: SearchRequest) -> Result<SearchResponse, DbError> {
-        let needle = request.name.clone();
-        let namespace_hint = request
-            .namespace
-            .clone()
-            .or_else(|| request.namespace_prefix.clone());
+    async fn get_file_references(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+    ) -> Result<Vec<FileReference>, DbError> {
+        sqlx::query_as::<_, DbFileReference>(
+            "SELECT f.repository, f.commit_sha, f.file_path, NULLIF(sn.namespace, '') AS namespace, s.name AS name, sr.kind, \
+                    sr.line_number AS line, sr.column_number AS column \
+             FROM files f \
+             JOIN symbols s ON s.content_hash = f.content_hash \
+             JOIN symbol_references sr ON sr.symbol_id = s.id \
+             JOIN symbol_namespaces sn ON sn.id = sr.namespace_id \
+             WHERE f.repository = $1 AND f.commit_sha = $2 AND f.file_path = $3 \
+             ORDER BY sr.line_number, sr.column_number",
+        )
+        .bind(repository)
+        .bind(commit_sha)
+        .bind(file_path)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)
+    }
 
-        let matching_hashes = if let Some(q) = &request.q {
-            let hashes: Vec<String> = sqlx::query_scalar(
-                "SELECT DISTINCT cbc.content_hash \
-                 FROM chunks c \
-                 JOIN content_blob_chunks cbc ON c.chunk_hash = cbc.chunk_hash \
-                 WHERE c.text_content LIKE '%' || $1 || '%'",
+    async fn get_definitions_batch(
+        &self,
+        lookups: Vec<DefinitionLookup>,
+    ) -> Result<Vec<DefinitionLocations>, DbError> {
+        if lookups.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut ords = Vec::with_capacity(lookups.len());
+        let mut repositories = Vec::with_capacity(lookups.len());
+        let mut commits = Vec::with_capacity(lookups.len());
+        let mut namespaces = Vec::with_capacity(lookups.len());
+        let mut names = Vec::with_capacity(lookups.len());
+        for (ord, lookup) in lookups.iter().enumerate() {
+            let (namespace, name) = split_fully_qualified(&lookup.fully_qualified);
+            ords.push(i32::try_from(ord).unwrap_or(i32::MAX));
+            repositories.push(lookup.repository.as_str());
+            commits.push(lookup.commit_sha.as_str());
+            namespaces.push(namespace.filter(|ns| !ns.is_empty()));
+            names.push(name);
+        }
+
+        // Every lookup is matched in one pass over (content_hash, name)
+        // instead of one query per symbol.
+        let rows: Vec<DefinitionRow> = sqlx::query_as(
+            "WITH lookups AS (
+                SELECT *
+                FROM UNNEST($1::INT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[])
+                    AS l(ord, repository, commit_sha, namespace, name)
             )
-            .bind(q)
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            SELECT l.ord, f.repository, f.commit_sha, f.file_path,
+                   NULLIF(sn.namespace, '') AS namespace, s.name, sr.kind,
+                   sr.line_number AS line, sr.column_number AS column
+            FROM lookups l
+            JOIN files f ON f.repository = l.repository AND f.commit_sha = l.commit_sha
+            JOIN symbols s ON s.content_hash = f.content_hash AND s.name = l.name
+            JOIN symbol_references sr ON sr.symbol_id = s.id
+            JOIN symbol_namespaces sn ON sn.id = sr.namespace_id
+            WHERE sr.kind = 'definition'
+              AND (l.namespace IS NULL OR COALESCE(sn.namespace, '') = l.namespace)
+            ORDER BY l.ord, f.file_path, sr.line_number, sr.column_number",
+        )
+        .bind(&ords)
+        .bind(&repositories)
+        .bind(&commits)
+        .bind(&namespaces)
+        .bind(&names)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
 
-            if hashes.is_empty() {
-                return Ok(SearchResponse {
-                    symbols: Vec::new(),
-                });
+        let mut results: Vec<DefinitionLocations> = lookups
+            .into_iter()
+            .map(|lookup| DefinitionLocations {
+                lookup,
+                definitions: Vec::new(),
+            })
+            .collect();
+        for row in rows {
+            if let Some(result) = usize::try_from(row.ord)
+                .ok()
+                .and_then(|ord| results.get_mut(ord))
+            {
+                result.definitions.push(row.reference);
             }
+        }
+        Ok(results)
+    }
 
-            Some(hashes)
-        } else {
-            None
+    async fn search_symbols(&self, request: SearchRequest) -> Result<SearchResponse, DbError> {
+        let (Some(cache), Some(key)) = (&self.symbol_cache, symbol_cache_key(&request)) else {
+            return self.search_symbols_uncached(request).await;
         };
+        cache
+            .get_or_try_fetch(key, || self.search_symbols_uncached(request))
+            .await
+    }
 
-        let mut qb = QueryBuilder::new(
-            "WITH ranked AS ( \
-                 SELECT DISTINCT ON (s.id) \
-                     s.id, \
-                     s.name AS symbol, \
-                     NULLIF(sn.namespace, '') AS namespace, \
-                     COALESCE(sr.kind, 'definition') AS kind, \
-                     CASE \
-                         WHEN sn.namespace IS NULL OR sn.namespace = '' THEN s.name \
-                         ELSE sn.namespace || '::' || s.name \
-                     END AS fully_qualified, \
-                     cb.language, \
-                     f.repository, \
-                     f.commit_sha, \
-                    f.file_path, \
-                    sr.line_number AS line_number, \
-                    sr.column_number AS column_number, \
-                    symbol_weight( \
-                        s.name, \
-                        CASE \
-                            WHEN sn.namespace IS NULL OR sn.namespace = '' THEN s.name \
-                            ELSE sn.namespace || '::' || s.name \
-                        END, \
-                        NULLIF(sn.namespace, ''), \
-                        COALESCE(sr.kind, 'definition'), \
-                        ",
-        );
-        qb.push_bind(needle.as_deref());
-        qb.push(
-            ", \
-                        ",
-        );
-        qb.push_bind(namespace_hint.as_deref());
-        qb.push(
-            ", \
-                        f.file_path, \
-                        ",
-        );
-
-        let path_hint = request.path_hint.clone().or(request.path.clone());
-        qb.push_bind(path_hint.as_deref());
-
-        qb.push(
-            ") AS score \
-                 FROM symbols s \
-                 JOIN symbol_references sr ON sr.symbol_id = s.id \
-                 JOIN symbol_namespaces sn ON sn.id = sr.namespace_id \
-                 JOIN files f ON f.content_hash = s.content_hash \
-                 LEFT JOIN content_blobs cb ON cb.hash = s.content_hash \
-                 WHERE 1=1",
-        );
-
-        if let Some(hashes) = matching_hashes {
-            qb.push(" AND s.content_hash = ANY(")
-                .push_bind(hashes)
-                .push(")");
-        }
-
-        if let Some(name) = &request.name {
-            qb.push(" AND s.name = ").push_bind(name);
-        }
-
-        if let Some(regex) = &request.name_regex {
-            qb.push(" AND s.name ~ ").push_bind(regex);
-        }
-
-        if let Some(namespace) = &request.namespace {
-            qb.push(" AND sn.namespace = ").push_bind(namespace);
-        }
-
-        if let Some(prefix) = &request.namespace_prefix {
-            qb.push(" AND sn.namespace LIKE ")
-                .push_bind(format!("{}%", prefix));
-        }
-
-        if let Some(kinds) = &request.kind {
-            if !kinds.is_empty() {
-                qb.push(" AND COALESCE(sr.kind, 'definition') = ANY(")
-                    .push_bind(kinds)
-                    .push(")");
-            }
-        }
-
-        if let Some(languages) = &request.language {
-            if !languages.is_empty() {
-                qb.push(" AND cb.language = ANY(")
-                    .push_bind(languages)
-                    .push(")");
-            }
-        }
-
-        if let Some(repo) = &request.repository {
-            qb.push(" AND f.repository = ").push_bind(repo);
-        }
-
-        if let Some(commit) = &request.commit_sha {
-            qb.push(" AND f.commit_sha = ").push_bind(commit);
-        }
-
-        if let Some(path) = &request.path {
-            qb.push(" AND f.file_path ILIKE ")
-                .push_bind(format!("%{}%", path));
-        }
-
-        if let Some(regex) = &request.path_regex {
-            qb.push(" AND f.file_path ~* ").push_bind(regex);
-        }
-
-        if !request.include_paths.is_empty() {
-            qb.push(
-                " AND EXISTS (
-                    SELECT 1
-                    FROM unnest(",
-            )
-            .push_bind(&request.include_paths)
-            .push(
-                ") AS include_path(value)
-                    WHERE
-                        f.file_path = include_path.value
-                        OR (
-                            RIGHT(include_path.value, 1) = '/'
-                            AND f.file_path LIKE include_path.value || '%'
-                        )
-                )",
-            );
-        }
-
-        if !request.excluded_paths.is_empty() {
-            qb.push(
-                " AND NOT EXISTS (
-                    SELECT 1
-                    FROM unnest(",
-            )
-            .push_bind(&request.excluded_paths)
-            .push(
-                ") AS excluded_path(value)
-                    WHERE
-                        f.file_path = excluded_path.value
-                        OR (
-                            RIGHT(excluded_path.value, 1) = '/'
-                            AND f.file_path LIKE excluded_path.value || '%'
-                        )
-                )",
-            );
-        }
-
-        qb.push(
-            " ORDER BY \
-                 s.id, \
-                 score DESC, \
-                 (sr.kind = 'definition') DESC, \
-                 sr.line_number, \
-                 sr.column_number \
-             ) ",
-        );
-
-        let include_refs = request.include_references.unwrap_or(false);
-        if include_refs {
-            qb.push(
-                "SELECT ranked.id, ranked.symbol, ranked.namespace, ranked.kind, ranked.fully_qualified, ranked.language, \
-                        ranked.repository, ranked.commit_sha, ranked.file_path, ranked.line_number, ranked.column_number, ranked.score, \
-                        refs.references \
-                 FROM ranked \
-                 LEFT JOIN LATERAL ( \
-                     SELECT jsonb_agg( \
-                         jsonb_build_object( \
-                             'namespace', NULLIF(sn_all.namespace, ''), \
-                             'name', ranked.symbol, \
-                             'kind', sr_all.kind, \
-                             'line', sr_all.line_number, \
-                             'column', sr_all.column_number, \
-                             'repository', ranked.repository, \
-                             'commit_sha', ranked.commit_sha, \
-                             'file_path', ranked.file_path \
-                         ) ORDER BY sr_all.line_number, sr_all.column_number \
-                     ) AS references \
-                     FROM symbol_references sr_all \
-                     JOIN symbol_namespaces sn_all ON sn_all.id = sr_all.namespace_id \
-                     WHERE sr_all.symbol_id = ranked.id \
-                 ) refs ON TRUE \
-                 ORDER BY ranked.score DESC, ranked.symbol ASC LIMIT ",
-            );
-        } else {
-            qb.push(
-                "SELECT ranked.id, ranked.symbol, ranked.namespace, ranked.kind, ranked.fully_qualified, ranked.language, \
-                        ranked.repository, ranked.commit_sha, ranked.file_path, ranked.line_number, ranked.column_number, ranked.score, \
-                        NULL::jsonb AS references \
-                 FROM ranked \
-                 ORDER BY ranked.score DESC, ranked.symbol ASC LIMIT ",
-            );
-        }
-
-        let limit = request.limit.unwrap_or(100).clamp(1, 1000);
-        qb.push_bind(limit);
-
-        let rows: Vec<SymbolRow> = qb
-            .build_query_as()
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
-
-        let mut results = Vec::with_capacity(rows.len());
-        for row in rows {
-            let references = if include_refs {
-                row.references.as_ref().map(|refs_json| {
-                    refs_json
-                        .0
-                        .iter()
-                        .map(|r| ReferenceResult {
-                            name: r.name.clone(),
-                            namespace: r.namespace.clone(),
-                            kind: r.kind.clone(),
-                            fully_qualified: r
-                                .namespace
-                                .as_ref()
-                                .map(|ns| format!("{}::{}", ns, r.name))
-                                .unwrap_or_else(|| r.name.clone()),
-                            repository: r.repository.clone(),
-                            commit_sha: r.commit_sha.clone(),
-                            file_path: r.file_path.clone(),
-                            line: r.line.unwrap_or_default().max(0) as usize,
-                            column: r.column.unwrap_or_default().max(0) as usize,
-                        })
-                        .collect()
-                })
-            } else {
-                None
-            };
-
-            let line = row
-                .line
-                .and_then(|line| line.try_into().ok())
-                .and_then(|line: i32| (line > 0).then(|| line as usize));
-            let column = row
-                .column
-                .and_then(|column| column.try_into().ok())
-                .and_then(|column: i32| (column > 0).then(|| column as usize));
-
-            let kind = row.kind.clone().unwrap_or_else(|| "definition".to_string());
-
-            tracing::debug!(
-                target: "pointer::search_symbols",
-                symbol = %row.fully_qualified,
-                score = row.score,
-                repository = %row.repository,
-                file_path = %row.file_path,
-                kind = %kind,
-                "symbol ranking debug"
-            );
-
-            results.push(SymbolResult {
-                symbol: row.symbol,
-                namespace: row.namespace,
-                kind: Some(kind),
-                fully_qualified: row.fully_qualified,
-                repository: row.repository,
-                commit_sha: row.commit_sha,
-                file_path: row.file_path,
-                language: row.language,
-                line,
-                column,
-                references,
-                score: row.score,
-            });
-        }
+    async fn get_symbol_by_id(&self, id: i32) -> Result<SymbolResult, DbError> {
+        // The same content can be indexed at several paths and commits;
+        // report the first location so the result is stable.
+        let row: Option<SymbolRow> = sqlx::query_as(
+            "WITH target AS (
+                 SELECT s.id,
+                        s.name AS symbol,
+                        NULLIF(sn.namespace, '') AS namespace,
+                        COALESCE(sr.kind, 'definition') AS kind,
+                        CASE
+                            WHEN sn.namespace IS NULL OR sn.namespace = '' THEN s.name
+                            ELSE sn.namespace || '::' || s.name
+                        END AS fully_qualified,
+                        cb.language,
+                        f.repository,
+                        f.commit_sha,
+                        f.file_path,
+                        sr.line_number,
+                        sr.column_number,
+                        sr.signature
+                 FROM symbols s
+                 JOIN symbol_references sr ON sr.symbol_id = s.id
+                 JOIN symbol_namespaces sn ON sn.id = sr.namespace_id
+                 JOIN files f ON f.content_hash = s.content_hash
+                 LEFT JOIN content_blobs cb ON cb.hash = s.content_hash
+                 WHERE s.id = $1
+                 ORDER BY (COALESCE(sr.kind, 'definition') = 'definition') DESC,
+                          f.repository, f.commit_sha, f.file_path,
+                          sr.line_number, sr.column_number
+                 LIMIT 1
+             )
+             SELECT target.*, 0::float8 AS score, 1::bigint AS total, refs.references
+             FROM target
+             LEFT JOIN LATERAL (
+                 SELECT jsonb_agg(
+                     jsonb_build_object(
+                         'namespace', NULLIF(sn_all.namespace, ''),
+                         'name', target.symbol,
+                         'kind', sr_all.kind,
+                         'line', sr_all.line_number,
+                         'column', sr_all.column_number,
+                         'repository', target.repository,
+                         'commit_sha', target.commit_sha,
+                         'file_path', target.file_path
+                     ) ORDER BY sr_all.line_number, sr_all.column_number
+                 ) AS references
+                 FROM symbol_references sr_all
+                 JOIN symbol_namespaces sn_all ON sn_all.id = sr_all.namespace_id
+                 WHERE sr_all.symbol_id = target.id
+             ) refs ON TRUE",
+        )
+        .bind(id)
+        .fetch_optional(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
 
-        Ok(SearchResponse { symbols: results })
+        row.map(|row| row.into_result(true))
+            .ok_or_else(|| DbError::NotFound("symbol not found".to_string()))
     }
 
     async fn text_search(&self, request: &TextSearchRequest) -> Result<SearchResultsPage, DbError> {
+        let request = &apply_default_excludes(request, &self.default_excluded_dirs);
         if request.plans.is_empty() {
             return Ok(SearchResultsPage::empty(
                 request.original_query.clone(),
@@ -1918,71 +2723,17 @@ ORDER BY idx
             ));
         }
 
-        let SearchBudgets {
-            fetch_limit,
-            file_limit,
-            plan_row_limit,
-        } = compute_search_budgets(request);
-
-        let needs_live_branch_filter = request
-            .plans
-            .iter()
-            .any(|plan| plan.branches.is_empty() && !plan.include_historical);
-
-        let mut symbol_terms: Vec<String> = collect_symbol_terms(request)
-            .into_iter()
-            .map(|t| t.to_lowercase())
-            .collect();
-        symbol_terms.sort_unstable();
-        let mut definition_terms: Vec<String> = collect_definition_terms(request)
-            .into_iter()
-            .map(|t| t.to_lowercase())
-            .collect();
-        definition_terms.sort_unstable();
+        let fetch_limit = compute_search_budgets(request).fetch_limit;
+        let (symbol_terms, definition_terms) = ranking_terms(request, self.min_symbol_length);
 
         let explain_requested = std::env::var("POINTER_EXPLAIN_SEARCH_SQL").is_ok();
 
-        let mut phase1_qb = QueryBuilder::new("");
-        push_search_ctes(
-            &mut phase1_qb,
+        let mut phase1_qb = build_phase1_query(
             request,
-            plan_row_limit,
-            fetch_limit,
-            file_limit,
-            needs_live_branch_filter,
             &symbol_terms,
             &definition_terms,
+            self.lowercase_content,
         );
-        phase1_qb.push(
-            "
-            SELECT
-                fr.file_id,
-                fr.repository,
-                fr.commit_sha,
-                fr.file_path,
-                fr.content_hash,
-                fr.chunk_index,
-                fr.total_score,
-                fr.definition_matches,
-                fr.include_historical,
-                fr.branches,
-                fr.live_branches,
-                fr.is_historical,
-                fr.snapshot_indexed_at,
-                fr.highlight_pattern,
-                fr.highlight_case_sensitive
-            FROM filtered_ranked fr
-            ORDER BY
-                fr.definition_matches DESC,
-                fr.total_score DESC,
-                fr.repository,
-                fr.commit_sha,
-                fr.file_path,
-                fr.chunk_index
-            LIMIT ",
-        );
-        phase1_qb.push_bind(fetch_limit);
-
         let mut phase1_query = phase1_qb.build_query_as::<RankedFileRow>();
 
         if explain_requested {
@@ -1990,7 +2741,7 @@ ORDER BY idx
             if let Ok(Some(args)) = phase1_query.take_arguments() {
                 let explain_args = args.clone();
                 match sqlx::query_scalar_with::<Postgres, String, _>(&sql, explain_args)
-                    .fetch_all(&self.pool)
+                    .fetch_all(self.read_pool())
                     .await
                 {
                     Ok(rows) => {
@@ -2006,10 +2757,11 @@ ORDER BY idx
             }
         }
 
-        let ranked_rows = phase1_query
-            .fetch_all(&self.pool)
+        let mut ranked_rows = phase1_query
+            .fetch_all(self.read_pool())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
+        order_ranked_rows(&mut ranked_rows, request.order_by);
 
         let row_limit_hit = (ranked_rows.len() as i64) >= fetch_limit;
 
@@ -2021,6 +2773,15 @@ ORDER BY idx
             ));
         }
 
+        let estimated: i64 = build_total_estimate_query(request, self.lowercase_content)
+            .build_query_scalar()
+            .fetch_one(self.read_pool())
+            .await
+            .map_err(DbError::from)?;
+        // The estimate scans its own budget, so never report fewer files
+        // than ranking already found.
+        let total_estimate = (estimated.max(0) as u64).max(ranked_rows.len() as u64);
+
         let total = ranked_rows.len();
         let page_index = request.page.saturating_sub(1) as usize;
         let page_size = request.page_size as usize;
@@ -2031,9 +2792,13 @@ ORDER BY idx
         }
 
         let stats = build_search_stats(&ranked_rows);
+        let max_snippet_chars = self.max_snippet_chars;
 
         let results = if start >= total {
             Vec::new()
+        } else if !request.snippets {
+            let end = start.saturating_add(page_size).min(total);
+            self.locate_ranked_files(&ranked_rows[start..end]).await?
         } else {
             let end = start.saturating_add(page_size).min(total);
             let page_rows = &ranked_rows[start..end];
@@ -2142,7 +2907,7 @@ ORDER BY idx
                 if let Ok(Some(args)) = phase2_query.take_arguments() {
                     let explain_args = args.clone();
                     match sqlx::query_scalar_with::<Postgres, String, _>(&sql, explain_args)
-                        .fetch_all(&self.pool)
+                        .fetch_all(self.read_pool())
                         .await
                     {
                         Ok(rows) => {
@@ -2164,9 +2929,9 @@ ORDER BY idx
             }
 
             let rows = phase2_query
-                .fetch_all(&self.pool)
+                .fetch_all(self.read_pool())
                 .await
-                .map_err(|e| DbError::Database(e.to_string()))?;
+                .map_err(DbError::from)?;
 
             let mut aggregates: Vec<FileAggregate> = Vec::new();
             let mut index_map: HashMap<FileGroupKey, usize> = HashMap::new();
@@ -2270,7 +3035,13 @@ ORDER BY idx
                         });
                     }
 
-                    let merged_snippets = merge_overlapping_snippets(snippets);
+                    let mut merged_snippets = merge_overlapping_snippets(snippets);
+                    if let Some(max_chars) = max_snippet_chars {
+                        merged_snippets = merged_snippets
+                            .into_iter()
+                            .map(|snippet| truncate_snippet(snippet, max_chars))
+                            .collect();
+                    }
                     let primary_snippet = merged_snippets
                         .iter()
                         .find(|snippet| {
@@ -2304,6 +3075,7 @@ ORDER BY idx
                             .snapshot_indexed_at
                             .as_ref()
                             .map(|dt| dt.to_rfc3339()),
+                        commit: None,
                     }
                 })
                 .collect()
@@ -2312,6 +3084,7 @@ ORDER BY idx
         Ok(SearchResultsPage {
             results,
             has_more,
+            total_estimate: Some(total_estimate.min(TOTAL_ESTIMATE_CAP)),
             page: request.page,
             page_size: request.page_size,
             query: request.original_query.clone(),
@@ -2319,12 +3092,113 @@ ORDER BY idx
         })
     }
 
-    async fn autocomplete_repositories(
+    async fn explain_search(
         &self,
-        term: &str,
-        limit: i64,
-    ) -> Result<Vec<String>, DbError> {
-        let escaped = escape_sql_like_literal(term);
+        request: &TextSearchRequest,
+    ) -> Result<SearchExplainReport, DbError> {
+        let request = &apply_default_excludes(request, &self.default_excluded_dirs);
+        let mut plans = Vec::new();
+        let mut scans = Vec::new();
+
+        if !request.plans.is_empty() {
+            let (symbol_terms, definition_terms) = ranking_terms(request, self.min_symbol_length);
+            let mut phase1_qb = build_phase1_query(
+                request,
+                &symbol_terms,
+                &definition_terms,
+                self.lowercase_content,
+            );
+            let mut phase1_query = phase1_qb.build();
+            // Plain EXPLAIN only plans the query, so this is safe on a busy
+            // index; the estimates come from the current table statistics.
+            let sql = format!("EXPLAIN (FORMAT JSON) {}", phase1_query.sql());
+            let args = phase1_query
+                .take_arguments()
+                .map_err(|err| DbError::Internal(err.to_string()))?
+                .unwrap_or_default();
+            let Json(plan) =
+                sqlx::query_scalar_with::<Postgres, Json<serde_json::Value>, _>(&sql, args)
+                    .fetch_one(self.read_pool())
+                    .await
+                    .map_err(DbError::from)?;
+            scans = query_plan::collect_seq_scans(&plan);
+            plans.push(ExplainedQuery {
+                name: "text_search".to_string(),
+                plan,
+            });
+        }
+
+        let mut relations: Vec<String> = scans
+            .iter()
+            .map(|scan| scan.relation.clone())
+            .chain(std::iter::once("content_blob_chunks".to_string()))
+            .collect();
+        relations.sort_unstable();
+        relations.dedup();
+        let table_rows: HashMap<String, i64> = sqlx::query_as(
+            "SELECT c.relname::TEXT, GREATEST(c.reltuples, 0)::BIGINT
+             FROM pg_class c
+             WHERE c.oid IN (SELECT to_regclass(t) FROM UNNEST($1::TEXT[]) t)",
+        )
+        .bind(&relations)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)?
+        .into_iter()
+        .collect();
+
+        let mut findings = query_plan::seq_scan_findings("text_search", &scans, |table| {
+            table_rows.get(table).copied()
+        });
+
+        // Chunk-to-blob joins only stay index-only with content_hash in
+        // the index, which the base schema doesn't provide.
+        let covering_index: bool = sqlx::query_scalar(
+            "SELECT EXISTS (
+                SELECT 1
+                FROM pg_index i
+                JOIN pg_attribute lead
+                  ON lead.attrelid = i.indrelid AND lead.attnum = i.indkey[0]
+                JOIN pg_attribute inc
+                  ON inc.attrelid = i.indrelid AND inc.attnum = ANY(i.indkey::INT2[])
+                WHERE i.indrelid = to_regclass('content_blob_chunks')
+                  AND lead.attname = 'chunk_hash'
+                  AND inc.attname = 'content_hash'
+            )",
+        )
+        .fetch_one(self.read_pool())
+        .await
+        .map_err(DbError::from)?;
+        let blob_chunk_rows = table_rows
+            .get("content_blob_chunks")
+            .copied()
+            .unwrap_or_default();
+        if !covering_index
+            && blob_chunk_rows >= query_plan::LARGE_TABLE_ROWS
+            && !findings
+                .iter()
+                .any(|finding| finding.contains(query_plan::COVERING_CHUNK_INDEX))
+        {
+            findings.push(format!(
+                "`content_blob_chunks` (~{blob_chunk_rows} rows) has no covering index for \
+                 chunk_hash lookups; consider {}",
+                query_plan::COVERING_CHUNK_INDEX
+            ));
+        }
+
+        Ok(SearchExplainReport {
+            query: request.original_query.clone(),
+            plans,
+            findings,
+        })
+    }
+
+    async fn autocomplete_repositories(
+        &self,
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, DbError> {
+        let escaped = escape_sql_like_literal(term);
         let pattern = format!("%{}%", escaped);
         let mut query = sqlx::query_scalar(
             "SELECT DISTINCT repository \
@@ -2342,7 +3216,7 @@ ORDER BY idx
                 let args: PgArguments = args;
                 let explain_args = args.clone();
                 match sqlx::query_scalar_with::<Postgres, String, PgArguments>(&sql, explain_args)
-                    .fetch_all(&self.pool)
+                    .fetch_all(self.read_pool())
                     .await
                 {
                     Ok(rows) => {
@@ -2363,9 +3237,9 @@ ORDER BY idx
         }
 
         let rows: Vec<String> = query
-            .fetch_all(&self.pool)
+            .fetch_all(self.read_pool())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
         Ok(rows)
     }
@@ -2413,7 +3287,7 @@ ORDER BY idx
                 let args: PgArguments = args;
                 let explain_args = args.clone();
                 match sqlx::query_scalar_with::<Postgres, String, PgArguments>(&sql, explain_args)
-                    .fetch_all(&self.pool)
+                    .fetch_all(self.read_pool())
                     .await
                 {
                     Ok(rows) => {
@@ -2434,9 +3308,9 @@ ORDER BY idx
         }
 
         let rows: Vec<String> = query
-            .fetch_all(&self.pool)
+            .fetch_all(self.read_pool())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
         Ok(rows)
     }
@@ -2472,7 +3346,7 @@ ORDER BY idx
                 let args: PgArguments = args;
                 let explain_args = args.clone();
                 match sqlx::query_scalar_with::<Postgres, String, PgArguments>(&sql, explain_args)
-                    .fetch_all(&self.pool)
+                    .fetch_all(self.read_pool())
                     .await
                 {
                     Ok(rows) => {
@@ -2493,9 +3367,9 @@ ORDER BY idx
         }
 
         let rows: Vec<String> = query
-            .fetch_all(&self.pool)
+            .fetch_all(self.read_pool())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
         Ok(rows)
     }
@@ -2532,7 +3406,7 @@ ORDER BY idx
                 let args: PgArguments = args;
                 let explain_args = args.clone();
                 match sqlx::query_scalar_with::<Postgres, String, PgArguments>(&sql, explain_args)
-                    .fetch_all(&self.pool)
+                    .fetch_all(self.read_pool())
                     .await
                 {
                     Ok(rows) => {
@@ -2556,9 +3430,9 @@ ORDER BY idx
         }
 
         let rows: Vec<Option<String>> = query
-            .fetch_all(&self.pool)
+            .fetch_all(self.read_pool())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
         Ok(rows.into_iter().flatten().collect())
     }
@@ -2594,7 +3468,7 @@ ORDER BY idx
                 let args: PgArguments = args;
                 let explain_args = args.clone();
                 match sqlx::query_scalar_with::<Postgres, String, PgArguments>(&sql, explain_args)
-                    .fetch_all(&self.pool)
+                    .fetch_all(self.read_pool())
                     .await
                 {
                     Ok(rows) => {
@@ -2615,9 +3489,9 @@ ORDER BY idx
         }
 
         let rows: Vec<String> = query
-            .fetch_all(&self.pool)
+            .fetch_all(self.read_pool())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
         Ok(rows)
     }
@@ -2655,7 +3529,7 @@ ORDER BY idx
                 let args: PgArguments = args;
                 let explain_args = args.clone();
                 match sqlx::query_scalar_with::<Postgres, String, PgArguments>(&sql, explain_args)
-                    .fetch_all(&self.pool)
+                    .fetch_all(self.read_pool())
                     .await
                 {
                     Ok(rows) => {
@@ -2679,9 +3553,9 @@ ORDER BY idx
         }
 
         let rows: Vec<(String, String, String)> = query
-            .fetch_all(&self.pool)
+            .fetch_all(self.read_pool())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
         Ok(rows
             .into_iter()
@@ -2693,165 +3567,606 @@ ORDER BY idx
             .collect())
     }
 
+    async fn autocomplete_symbols_in_file(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<FileSymbolSuggestion>, DbError> {
+        let escaped = escape_sql_like_literal(term);
+        sqlx::query_as(
+            "SELECT s.name, NULLIF(sn.namespace, '') AS namespace,
+                sr.line_number AS line, sr.column_number AS column
+         FROM files f
+         JOIN symbols s ON s.content_hash = f.content_hash
+         JOIN symbol_references sr ON sr.symbol_id = s.id
+         JOIN symbol_namespaces sn ON sn.id = sr.namespace_id
+         WHERE f.repository = $1 AND f.commit_sha = $2 AND f.file_path = $3
+           AND sr.kind = 'definition'
+           AND s.name ILIKE $4 ESCAPE '\\'
+         ORDER BY (s.name ILIKE $5 ESCAPE '\\') DESC, sr.line_number, sr.column_number
+         LIMIT $6",
+        )
+        .bind(repository)
+        .bind(commit_sha)
+        .bind(file_path)
+        .bind(format!("%{escaped}%"))
+        .bind(format!("{escaped}%"))
+        .bind(limit)
+        .fetch_all(self.read_pool())
+        .await
+        .map_err(DbError::from)
+    }
+
     async fn health_check(&self) -> Result<String, DbError> {
         sqlx::query_scalar::<_, i32>("SELECT 1")
             .fetch_one(&self.pool)
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
         Ok("ok".to_string())
     }
 }
 
 impl PostgresDb {
-    async fn load_file_data(
+    async fn search_symbols_uncached(
         &self,
-        repository: &str,
-        commit_sha: &str,
-        file_path: &str,
-    ) -> Result<FileData, DbError> {
-        let row: (String, Option<String>) = sqlx::query_as(
-            "SELECT f.content_hash, cb.language
-             FROM files f
-             JOIN content_blobs cb ON cb.hash = f.content_hash
-             WHERE f.repository = $1 AND f.commit_sha = $2 AND f.file_path = $3",
-        )
-        .bind(repository)
-        .bind(commit_sha)
-        .bind(file_path)
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(|e| DbError::Database(e.to_string()))?
-        .ok_or_else(|| DbError::Internal("file not found".to_string()))?;
+        request: SearchRequest,
+    ) -> Result<SearchResponse, DbError> {
+        let needle = request.name.clone();
+        let namespace_hint = request
+            .namespace
+            .clone()
+            .or_else(|| request.namespace_prefix.clone());
 
-        let (content_hash, language) = row;
+        let matching_hashes = if let Some(q) = &request.q {
+            let hashes: Vec<String> = sqlx::query_scalar(
+                "SELECT DISTINCT cbc.content_hash \
+                 FROM chunks c \
+                 JOIN content_blob_chunks cbc ON c.chunk_hash = cbc.chunk_hash \
+                 WHERE c.text_content LIKE '%' || $1 || '%'",
+            )
+            .bind(q)
+            .fetch_all(self.read_pool())
+            .await
+            .map_err(DbError::from)?;
 
-        let chunk_rows: Vec<(String,)> = sqlx::query_as(
-            "SELECT c.text_content
-             FROM content_blob_chunks cbc
-             JOIN chunks c ON cbc.chunk_hash = c.chunk_hash
-             WHERE cbc.content_hash = $1
-             ORDER BY cbc.chunk_index",
-        )
-        .bind(&content_hash)
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|e| DbError::Database(e.to_string()))?;
+            if hashes.is_empty() {
+                return Ok(SearchResponse {
+                    symbols: Vec::new(),
+                    total: 0,
+                });
+            }
 
-        if chunk_rows.is_empty() {
-            // This could happen for binary files or empty files
-            return Ok(FileData {
-                bytes: Vec::new(),
-                language,
-            });
-        }
+            Some(hashes)
+        } else {
+            None
+        };
 
-        let bytes = chunk_rows
-            .into_iter()
-            .map(|s| s.0)
-            .flat_map(|v| v.into_bytes().into_iter())
-            .collect();
+        let mut qb = QueryBuilder::new(
+            "WITH ranked AS ( \
+                 SELECT DISTINCT ON (s.id) \
+                     s.id, \
+                     s.name AS symbol, \
+                     NULLIF(sn.namespace, '') AS namespace, \
+                     COALESCE(sr.kind, 'definition') AS kind, \
+                     CASE \
+                         WHEN sn.namespace IS NULL OR sn.namespace = '' THEN s.name \
+                         ELSE sn.namespace || '::' || s.name \
+                     END AS fully_qualified, \
+                     cb.language, \
+                     f.repository, \
+                     f.commit_sha, \
+                    f.file_path, \
+                    sr.line_number AS line_number, \
+                    sr.column_number AS column_number, \
+                    sr.signature, \
+                    symbol_weight( \
+                        s.name, \
+                        CASE \
+                            WHEN sn.namespace IS NULL OR sn.namespace = '' THEN s.name \
+                            ELSE sn.namespace || '::' || s.name \
+                        END, \
+                        NULLIF(sn.namespace, ''), \
+                        COALESCE(sr.kind, 'definition'), \
+                        ",
+        );
+        qb.push_bind(needle.as_deref());
+        qb.push(
+            ", \
+                        ",
+        );
+        qb.push_bind(namespace_hint.as_deref());
+        qb.push(
+            ", \
+                        f.file_path, \
+                        ",
+        );
 
-        Ok(FileData { bytes, language })
-    }
+        let path_hint = request.path_hint.clone().or(request.path.clone());
+        qb.push_bind(path_hint.as_deref());
 
-    async fn ingest_report(&self, report: IndexReport) -> Result<(), DbError> {
-        let mut tx = self
-            .pool
-            .begin()
-            .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+        qb.push(
+            ") AS score \
+                 FROM symbols s \
+                 JOIN symbol_references sr ON sr.symbol_id = s.id \
+                 JOIN symbol_namespaces sn ON sn.id = sr.namespace_id \
+                 JOIN files f ON f.content_hash = s.content_hash \
+                 LEFT JOIN content_blobs cb ON cb.hash = s.content_hash \
+                 WHERE 1=1",
+        );
 
-        self.insert_content_blobs(&mut tx, &report.content_blobs)
-            .await?;
-        self.insert_file_pointers(&mut tx, &report.file_pointers)
-            .await?;
-        self.insert_symbol_records(&mut tx, &report.symbol_records)
-            .await?;
-        self.insert_reference_records(&mut tx, &report.reference_records)
-            .await?;
-        self.upsert_branch_heads(&mut tx, &report.branches).await?;
+        if let Some(hashes) = matching_hashes {
+            qb.push(" AND s.content_hash = ANY(")
+                .push_bind(hashes)
+                .push(")");
+        }
 
-        tx.commit()
-            .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+        if let Some(name) = &request.name {
+            qb.push(" AND s.name = ").push_bind(name);
+        }
 
-        Ok(())
-    }
+        if let Some(regex) = &request.name_regex {
+            qb.push(" AND s.name ~ ").push_bind(regex);
+        }
 
-    async fn insert_content_blobs(
-        &self,
-        tx: &mut Transaction<'_, Postgres>,
-        blobs: &[ContentBlob],
-    ) -> Result<(), DbError> {
-        if blobs.is_empty() {
-            return Ok(());
+        // Resolve the substring against the trigram-indexed unique_symbols
+        // first; scanning symbols.name_lc with a leading wildcard can't use
+        // an index. Trigram-similar names are kept too so near misses like
+        // `rendr` still find `render`.
+        let name_contains = request
+            .name_contains
+            .as_deref()
+            .map(str::to_lowercase)
+            .filter(|part| !part.is_empty());
+        if let Some(part) = &name_contains {
+            qb.push(
+                " AND s.name_lc IN (SELECT us.name_lc FROM unique_symbols us WHERE us.name_lc LIKE ",
+            )
+            .push_bind(format!("%{}%", escape_sql_like_literal(part)))
+            .push(" ESCAPE '\\' OR us.name_lc % ")
+            .push_bind(part.clone())
+            .push(")");
         }
 
-        let deduped = dedup_by_key(blobs, |blob| blob.hash.clone());
+        if let Some(namespace) = &request.namespace {
+            qb.push(" AND sn.namespace = ").push_bind(namespace);
+        }
 
-        for chunk in deduped.chunks(INSERT_BATCH_SIZE) {
-            let mut qb = QueryBuilder::new(
-                "INSERT INTO content_blobs (hash, language, byte_len, line_count) ",
-            );
-            qb.push_values(chunk.iter().copied(), |mut b, blob| {
-                b.push_bind(&blob.hash)
-                    .push_bind(&blob.language)
-                    .push_bind(blob.byte_len)
-                    .push_bind(blob.line_count);
-            });
-            qb.push(
-                " ON CONFLICT (hash) DO UPDATE SET language = EXCLUDED.language, byte_len = EXCLUDED.byte_len, line_count = EXCLUDED.line_count",
-            );
+        if let Some(prefix) = &request.namespace_prefix {
+            qb.push(" AND sn.namespace LIKE ")
+                .push_bind(format!("{}%", prefix));
+        }
 
-            qb.build()
-                .execute(tx.as_mut())
-                .await
-                .map_err(|e| DbError::Database(e.to_string()))?;
+        if let Some(kinds) = &request.kind {
+            if !kinds.is_empty() {
+                qb.push(" AND COALESCE(sr.kind, 'definition') = ANY(")
+                    .push_bind(kinds)
+                    .push(")");
+            }
         }
 
-        Ok(())
-    }
+        if let Some(languages) = &request.language {
+            if !languages.is_empty() {
+                qb.push(" AND cb.language = ANY(")
+                    .push_bind(languages)
+                    .push(")");
+            }
+        }
 
-    async fn insert_file_pointers(
-        &self,
-        tx: &mut Transaction<'_, Postgres>,
-        files: &[FilePointer],
-    ) -> Result<(), DbError> {
-        if files.is_empty() {
-            return Ok(());
+        if let Some(repo) = &request.repository {
+            qb.push(" AND f.repository = ").push_bind(repo);
         }
 
-        let deduped = dedup_by_key(files, |file| {
-            (
-                file.repository.clone(),
-                file.commit_sha.clone(),
-                file.file_path.clone(),
-            )
-        });
+        if let Some(commit) = &request.commit_sha {
+            qb.push(" AND f.commit_sha = ").push_bind(commit);
+        }
 
-        for chunk in deduped.chunks(INSERT_BATCH_SIZE) {
-            let mut qb = QueryBuilder::new(
-                "INSERT INTO files (repository, commit_sha, file_path, content_hash) ",
-            );
-            qb.push_values(chunk.iter().copied(), |mut b, file| {
-                b.push_bind(&file.repository)
-                    .push_bind(&file.commit_sha)
-                    .push_bind(&file.file_path)
-                    .push_bind(&file.content_hash);
-            });
+        if request.unreferenced {
+            if request.repository.is_none() {
+                return Err(DbError::Internal(
+                    "unreferenced symbol search needs a repository".to_string(),
+                ));
+            }
             qb.push(
-                " ON CONFLICT (repository, commit_sha, file_path) DO UPDATE SET content_hash = EXCLUDED.content_hash",
+                " AND COALESCE(sr.kind, 'definition') = 'definition' \
+                  AND NOT EXISTS ( \
+                      SELECT 1 \
+                      FROM symbols s_use \
+                      JOIN symbol_references sr_use ON sr_use.symbol_id = s_use.id \
+                      JOIN files f_use ON f_use.content_hash = s_use.content_hash \
+                      WHERE s_use.name = s.name \
+                        AND sr_use.kind = 'reference' \
+                        AND f_use.repository = f.repository \
+                        AND f_use.commit_sha = f.commit_sha \
+                  )",
             );
+        }
 
-            qb.build()
-                .execute(tx.as_mut())
-                .await
-                .map_err(|e| DbError::Database(e.to_string()))?;
+        if let Some(path) = &request.path {
+            qb.push(" AND f.file_path ILIKE ")
+                .push_bind(format!("%{}%", path));
         }
 
-        Ok(())
-    }
+        if let Some(regex) = &request.path_regex {
+            qb.push(" AND f.file_path ~* ").push_bind(regex);
+        }
+
+        if let Some(bound) = &request.path_depth {
+            qb.push(" AND array_length(string_to_array(f.file_path, '/'), 1) ")
+                .push(bound.comparison.sql_operator())
+                .push(" ")
+                .push_bind(bound.depth);
+        }
+
+        if !request.include_paths.is_empty() {
+            qb.push(
+                " AND EXISTS (
+                    SELECT 1
+                    FROM unnest(",
+            )
+            .push_bind(&request.include_paths)
+            .push(
+                ") AS include_path(value)
+                    WHERE
+                        f.file_path = include_path.value
+                        OR (
+                            RIGHT(include_path.value, 1) = '/'
+                            AND f.file_path LIKE include_path.value || '%'
+                        )
+                )",
+            );
+        }
+
+        if !request.excluded_paths.is_empty() {
+            qb.push(
+                " AND NOT EXISTS (
+                    SELECT 1
+                    FROM unnest(",
+            )
+            .push_bind(&request.excluded_paths)
+            .push(
+                ") AS excluded_path(value)
+                    WHERE
+                        f.file_path = excluded_path.value
+                        OR (
+                            RIGHT(excluded_path.value, 1) = '/'
+                            AND f.file_path LIKE excluded_path.value || '%'
+                        )
+                )",
+            );
+        }
+
+        let mut targeted_paths: Vec<&str> =
+            request.include_paths.iter().map(String::as_str).collect();
+        targeted_paths.extend(request.path.as_deref());
+        targeted_paths.extend(request.path_regex.as_deref());
+        for pattern in default_exclude_patterns(&self.default_excluded_dirs, &targeted_paths) {
+            qb.push(" AND f.file_path NOT LIKE ")
+                .push_bind(pattern)
+                .push(" ESCAPE '\\'");
+        }
+
+        qb.push(
+            " ORDER BY \
+                 s.id, \
+                 score DESC, \
+                 (sr.kind = 'definition') DESC, \
+                 sr.line_number, \
+                 sr.column_number \
+             ) ",
+        );
+
+        // Grouping happens before the outer ORDER BY and LIMIT, so the limit
+        // and the total count names instead of sites.
+        let source = if request.group_by_name {
+            "(SELECT DISTINCT ON (symbol) * FROM ranked \
+              ORDER BY symbol, score DESC, repository, commit_sha, file_path, \
+                       line_number, column_number, id) ranked"
+        } else {
+            "ranked"
+        };
+
+        let include_refs = request.include_references.unwrap_or(false);
+        if include_refs {
+            qb.push(
+                "SELECT ranked.id, ranked.symbol, ranked.namespace, ranked.kind, ranked.fully_qualified, ranked.language, \
+                        ranked.repository, ranked.commit_sha, ranked.file_path, ranked.line_number, ranked.column_number, ranked.signature, ranked.score, \
+                        COUNT(*) OVER () AS total, refs.references \
+                 FROM ",
+            );
+            qb.push(source);
+            qb.push(
+                " LEFT JOIN LATERAL ( \
+                     SELECT jsonb_agg( \
+                         jsonb_build_object( \
+                             'namespace', NULLIF(sn_all.namespace, ''), \
+                             'name', ranked.symbol, \
+                             'kind', sr_all.kind, \
+                             'line', sr_all.line_number, \
+                             'column', sr_all.column_number, \
+                             'repository', ranked.repository, \
+                             'commit_sha', ranked.commit_sha, \
+                             'file_path', ranked.file_path \
+                         ) ORDER BY sr_all.line_number, sr_all.column_number \
+                     ) AS references \
+                     FROM symbol_references sr_all \
+                     JOIN symbol_namespaces sn_all ON sn_all.id = sr_all.namespace_id \
+                     WHERE sr_all.symbol_id = ranked.id \
+                 ) refs ON TRUE",
+            );
+        } else {
+            qb.push(
+                "SELECT ranked.id, ranked.symbol, ranked.namespace, ranked.kind, ranked.fully_qualified, ranked.language, \
+                        ranked.repository, ranked.commit_sha, ranked.file_path, ranked.line_number, ranked.column_number, ranked.signature, ranked.score, \
+                        COUNT(*) OVER () AS total, NULL::jsonb AS references \
+                 FROM ",
+            );
+            qb.push(source);
+        }
+
+        qb.push(" ORDER BY ");
+        if let Some(part) = &name_contains {
+            // Names that start with the substring outrank mid-identifier
+            // hits, and closer trigram matches outrank looser ones.
+            qb.push("(LOWER(ranked.symbol) LIKE ")
+                .push_bind(format!("{}%", escape_sql_like_literal(part)))
+                .push(" ESCAPE '\\') DESC, similarity(LOWER(ranked.symbol), ")
+                .push_bind(part.clone())
+                .push(") DESC, ");
+        }
+        // Equal scores fall back to location so repeated searches return
+        // the same rows in the same order.
+        qb.push(
+            "ranked.score DESC, ranked.symbol ASC, ranked.repository, ranked.commit_sha, \
+             ranked.file_path, ranked.line_number, ranked.column_number, ranked.id LIMIT ",
+        );
+
+        let limit = request.limit.unwrap_or(100).clamp(1, 1000);
+        qb.push_bind(limit);
+
+        let rows: Vec<SymbolRow> = qb
+            .build_query_as()
+            .fetch_all(self.read_pool())
+            .await
+            .map_err(DbError::from)?;
+
+        let total = rows.first().map_or(0, |row| row.total);
+        let mut results = Vec::with_capacity(rows.len());
+        for row in rows {
+            tracing::debug!(
+                target: "pointer::search_symbols",
+                symbol = %row.fully_qualified,
+                score = row.score,
+                repository = %row.repository,
+                file_path = %row.file_path,
+                kind = row.kind.as_deref().unwrap_or("definition"),
+                "symbol ranking debug"
+            );
+            results.push(row.into_result(include_refs));
+        }
+
+        Ok(SearchResponse {
+            symbols: results,
+            total,
+        })
+    }
+
+    /// Builds snippet-free results for a page of ranked chunks, one per file,
+    /// pointing at the first line of the file's best-ranked chunk.
+    async fn locate_ranked_files(
+        &self,
+        page_rows: &[RankedFileRow],
+    ) -> Result<Vec<SearchResult>, DbError> {
+        let mut seen = HashSet::new();
+        let best_rows: Vec<RankedFileRow> = page_rows
+            .iter()
+            .filter(|row| {
+                seen.insert(FileGroupKey {
+                    repository: row.repository.clone(),
+                    commit_sha: row.commit_sha.clone(),
+                    file_path: row.file_path.clone(),
+                    content_hash: row.content_hash.clone(),
+                })
+            })
+            .cloned()
+            .collect();
+
+        let start_lines: Vec<(i64, i64)> = build_location_query(&best_rows)
+            .build_query_as()
+            .fetch_all(self.read_pool())
+            .await
+            .map_err(DbError::from)?;
+
+        Ok(best_rows
+            .into_iter()
+            .zip(start_lines)
+            .map(|(row, (_, start_line))| {
+                let line: i32 = start_line.try_into().unwrap_or(i32::MAX);
+                SearchResult {
+                    repository: row.repository,
+                    commit_sha: row.commit_sha,
+                    file_path: row.file_path,
+                    start_line: line,
+                    end_line: line,
+                    match_line: line,
+                    content_text: String::new(),
+                    match_spans: Vec::new(),
+                    snippets: Vec::new(),
+                    branches: row.branches,
+                    live_branches: row.live_branches,
+                    is_historical: row.is_historical,
+                    snapshot_indexed_at: row.snapshot_indexed_at.as_ref().map(|dt| dt.to_rfc3339()),
+                    commit: None,
+                }
+            })
+            .collect())
+    }
+
+    async fn load_file_data(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        file_path: &str,
+    ) -> Result<FileData, DbError> {
+        let row: (String, Option<String>, String, i64) = sqlx::query_as(
+            "SELECT f.content_hash, cb.language, f.kind, cb.byte_len
+             FROM files f
+             JOIN content_blobs cb ON cb.hash = f.content_hash
+             WHERE f.repository = $1 AND f.commit_sha = $2 AND f.file_path = $3",
+        )
+        .bind(repository)
+        .bind(commit_sha)
+        .bind(file_path)
+        .fetch_optional(self.read_pool())
+        .await
+        .map_err(DbError::from)?
+        .ok_or_else(|| DbError::NotFound("file not found".to_string()))?;
+
+        let (content_hash, language, kind, byte_len) = row;
+
+        // Generated files can span hundreds of chunks, so append each row as
+        // it arrives into a buffer sized from the blob's recorded length
+        // rather than collecting every row first.
+        let mut rows = sqlx::query_as::<_, (String, Option<Vec<u8>>)>(
+            "SELECT c.text_content, c.compressed_content
+             FROM content_blob_chunks cbc
+             JOIN chunks c ON cbc.chunk_hash = c.chunk_hash
+             WHERE cbc.content_hash = $1
+             ORDER BY cbc.chunk_index",
+        )
+        .bind(&content_hash)
+        .fetch(self.read_pool());
+
+        let mut bytes = Vec::with_capacity(usize::try_from(byte_len).unwrap_or(0));
+        let mut saw_chunk = false;
+        while let Some((text_content, compressed_content)) =
+            rows.try_next().await.map_err(DbError::from)?
+        {
+            saw_chunk = true;
+            bytes.extend(chunk_bytes(text_content, compressed_content)?);
+        }
+
+        if !saw_chunk {
+            // Empty files have no chunks, and neither do binary files the
+            // indexer only recorded metadata for.
+            return Ok(FileData {
+                bytes: Vec::new(),
+                language,
+                kind,
+                content_skipped: byte_len > 0,
+            });
+        }
+
+        Ok(FileData {
+            bytes,
+            language,
+            kind,
+            content_skipped: false,
+        })
+    }
+
+    async fn ingest_report(&self, report: IndexReport) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await.map_err(DbError::from)?;
+
+        self.insert_content_blobs(&mut tx, &report.content_blobs)
+            .await?;
+        self.insert_file_pointers(&mut tx, &report.file_pointers)
+            .await?;
+        self.insert_symbol_records(&mut tx, &report.symbol_records)
+            .await?;
+        self.insert_reference_records(&mut tx, &report.reference_records)
+            .await?;
+        self.upsert_branch_heads(&mut tx, &report.branches).await?;
+        self.upsert_commit_metadata(&mut tx, &report.commits)
+            .await?;
+
+        tx.commit().await.map_err(DbError::from)?;
+
+        Ok(())
+    }
+
+    async fn insert_content_blobs(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        blobs: &[ContentBlob],
+    ) -> Result<(), DbError> {
+        if blobs.is_empty() {
+            return Ok(());
+        }
+
+        let deduped = dedup_by_key(blobs, |blob| blob.hash.clone());
+
+        for chunk in deduped.chunks(INSERT_BATCH_SIZE) {
+            let mut qb = QueryBuilder::new(
+                "INSERT INTO content_blobs (hash, language, byte_len, line_count) ",
+            );
+            qb.push_values(chunk.iter().copied(), |mut b, blob| {
+                b.push_bind(&blob.hash)
+                    .push_bind(&blob.language)
+                    .push_bind(blob.byte_len)
+                    .push_bind(blob.line_count);
+            });
+            qb.push(
+                " ON CONFLICT (hash) DO UPDATE SET language = EXCLUDED.language, byte_len = EXCLUDED.byte_len, line_count = EXCLUDED.line_count",
+            );
+
+            qb.build()
+                .execute(tx.as_mut())
+                .await
+                .map_err(DbError::from)?;
+        }
+
+        Ok(())
+    }
+
+    async fn insert_file_pointers(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        files: &[FilePointer],
+    ) -> Result<(), DbError> {
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        let mut files = files.to_vec();
+        normalize_file_pointers(&mut files).map_err(|rejected| {
+            DbError::Internal(describe_rejections("file pointers", &rejected))
+        })?;
+        let deduped = dedup_by_key(&files, |file| {
+            (
+                file.repository.clone(),
+                file.commit_sha.clone(),
+                file.file_path.clone(),
+            )
+        });
+
+        for chunk in deduped.chunks(INSERT_BATCH_SIZE) {
+            let mut qb = QueryBuilder::new(
+                "INSERT INTO files (repository, commit_sha, file_path, content_hash, kind, mode) ",
+            );
+            qb.push_values(chunk.iter().copied(), |mut b, file| {
+                b.push_bind(&file.repository)
+                    .push_bind(&file.commit_sha)
+                    .push_bind(&file.file_path)
+                    .push_bind(&file.content_hash)
+                    .push_bind(file.kind.as_str())
+                    .push_bind(file.mode.map(|mode| mode as i32));
+            });
+            qb.push(
+                " ON CONFLICT (repository, commit_sha, file_path) DO UPDATE SET content_hash = EXCLUDED.content_hash, kind = EXCLUDED.kind, mode = EXCLUDED.mode",
+            );
+
+            qb.build()
+                .execute(tx.as_mut())
+                .await
+                .map_err(DbError::from)?;
+        }
+
+        Ok(())
+    }
 
     async fn insert_symbol_records(
         &self,
@@ -2879,7 +4194,7 @@ impl PostgresDb {
             qb.build()
                 .execute(tx.as_mut())
                 .await
-                .map_err(|e| DbError::Database(e.to_string()))?;
+                .map_err(DbError::from)?;
         }
 
         Ok(())
@@ -2928,11 +4243,11 @@ impl PostgresDb {
                     .build()
                     .execute(tx.as_mut())
                     .await
-                    .map_err(|e| DbError::Database(e.to_string()))?;
+                    .map_err(DbError::from)?;
             }
 
             let mut qb = QueryBuilder::new(
-                "WITH data (content_hash, namespace, name, kind, line_number, column_number) AS (",
+                "WITH data (content_hash, namespace, name, kind, line_number, column_number, signature) AS (",
             );
             qb.push_values(chunk.iter().copied(), |mut b, reference| {
                 let line: i32 = reference.line.try_into().unwrap_or(i32::MAX);
@@ -2947,11 +4262,12 @@ impl PostgresDb {
                     .push_bind(&reference.name)
                     .push_bind(&reference.kind)
                     .push_bind(line)
-                    .push_bind(column);
+                    .push_bind(column)
+                    .push_bind(&reference.signature);
             });
             qb.push(
-                ") INSERT INTO symbol_references (symbol_id, namespace_id, kind, line_number, column_number) \
-                 SELECT s.id, sn.id, data.kind, data.line_number, data.column_number \
+                ") INSERT INTO symbol_references (symbol_id, namespace_id, kind, line_number, column_number, signature) \
+                 SELECT s.id, sn.id, data.kind, data.line_number, data.column_number, data.signature \
                  FROM data \
                  JOIN symbols s \
                    ON s.content_hash = data.content_hash \
@@ -2964,7 +4280,7 @@ impl PostgresDb {
             qb.build()
                 .execute(tx.as_mut())
                 .await
-                .map_err(|e| DbError::Database(e.to_string()))?;
+                .map_err(DbError::from)?;
         }
 
         Ok(())
@@ -2978,10 +4294,22 @@ impl PostgresDb {
         if branches.is_empty() {
             return Ok(());
         }
+        validate_branch_heads(branches).map_err(|rejected| {
+            DbError::Internal(describe_rejections("branch heads", &rejected))
+        })?;
 
-        let deduped = dedup_by_key(branches, |branch| {
+        // This path keeps no snapshots, so snapshot-only heads have nothing
+        // to record.
+        let heads: Vec<&BranchHead> = branches
+            .iter()
+            .filter(|branch| !branch.snapshot_only)
+            .collect();
+        let deduped = dedup_by_key(&heads, |branch| {
             (branch.repository.clone(), branch.branch.clone())
         });
+        if deduped.is_empty() {
+            return Ok(());
+        }
 
         let mut qb = QueryBuilder::new("INSERT INTO branches (repository, branch, commit_sha) ");
         qb.push_values(deduped.into_iter(), |mut b, branch| {
@@ -2997,14 +4325,60 @@ impl PostgresDb {
         qb.build()
             .execute(tx.as_mut())
             .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
+            .map_err(DbError::from)?;
 
         Ok(())
     }
-}
 
-const FILE_SAMPLE_FACTOR: u32 = 6;
-const REGEX_FILE_SAMPLE_FACTOR: u32 = 2;
+    async fn upsert_commit_metadata(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        commits: &[IndexedCommit],
+    ) -> Result<(), DbError> {
+        if commits.is_empty() {
+            return Ok(());
+        }
+        validate_commit_metadata(commits).map_err(|rejected| {
+            DbError::Internal(describe_rejections("commit metadata", &rejected))
+        })?;
+
+        let deduped = dedup_by_key(commits, |commit| {
+            (commit.repository.clone(), commit.commit_sha.clone())
+        });
+        for chunk in deduped.chunks(INSERT_BATCH_SIZE) {
+            let mut qb = QueryBuilder::new(
+                "INSERT INTO commits (repository, commit_sha, author_name, author_email, committed_at, subject) ",
+            );
+            qb.push_values(chunk.iter().copied(), |mut b, commit| {
+                b.push_bind(&commit.repository)
+                    .push_bind(&commit.commit_sha)
+                    .push_bind(&commit.author_name)
+                    .push_bind(&commit.author_email)
+                    .push("to_timestamp(")
+                    .push_bind_unseparated(commit.committed_at as f64)
+                    .push_unseparated(")")
+                    .push_bind(&commit.subject);
+            });
+            qb.push(
+                " ON CONFLICT (repository, commit_sha)
+                  DO UPDATE SET author_name = EXCLUDED.author_name,
+                                author_email = EXCLUDED.author_email,
+                                committed_at = EXCLUDED.committed_at,
+                                subject = EXCLUDED.subject",
+            );
+
+            qb.build()
+                .execute(tx.as_mut())
+                .await
+                .map_err(DbError::from)?;
+        }
+
+        Ok(())
+    }
+}
+
+const FILE_SAMPLE_FACTOR: u32 = 6;
+const REGEX_FILE_SAMPLE_FACTOR: u32 = 2;
 const DEFAULT_FETCH_LIMIT_CAP: i64 = 5000;
 const REGEX_FETCH_LIMIT_CAP: i64 = 1000;
 const FILE_LIMIT_CAP: i64 = 25000;
@@ -3022,6 +4396,132 @@ struct UploadChunkRow {
 struct FileData {
     bytes: Vec<u8>,
     language: Option<String>,
+    /// The `files.kind` column: "regular", "symlink" or "submodule".
+    kind: String,
+    /// The blob has a size but no stored chunks, so `bytes` is not its content.
+    content_skipped: bool,
+}
+
+/// The `TreeEntry::kind` for a `files.kind` value; regular files stay "file".
+fn tree_entry_kind(kind: &str) -> &str {
+    match kind {
+        "regular" => "file",
+        other => other,
+    }
+}
+
+/// Whether a `files.mode` value has any executable bit set. Files indexed
+/// before modes were recorded have no mode and count as not executable.
+fn mode_is_executable(mode: Option<i32>) -> bool {
+    mode.is_some_and(|mode| mode & 0o111 != 0)
+}
+
+/// A chunk's original bytes, decoding `compressed_content` when the chunk was
+/// stored zstd-compressed.
+/// Orders path autocomplete candidates: filename matches (exact, then prefix,
+/// then substring) ahead of directories, with files that only match through a
+/// parent directory last. A query containing `/` is compared against as many
+/// trailing path segments as it has, so `src/main.rs` is an exact match for
+/// `crates/app/src/main.rs`. Ties go to the shorter path.
+fn rank_path_matches(paths: &[String], query: &str, limit: usize) -> Vec<TreeEntry> {
+    let query = query.trim().trim_matches('/').to_ascii_lowercase();
+    if query.is_empty() || limit == 0 {
+        return Vec::new();
+    }
+    let depth = query.split('/').count();
+    let tail_tier = |lower: &str| {
+        let tail = match lower.rmatch_indices('/').nth(depth - 1) {
+            Some((idx, _)) => &lower[idx + 1..],
+            None => lower,
+        };
+        if tail == query {
+            Some(0)
+        } else if tail.starts_with(&query) {
+            Some(1)
+        } else if tail.contains(&query) {
+            Some(2)
+        } else {
+            None
+        }
+    };
+
+    const DIRECTORY_TIER: u8 = 3;
+    const PARENT_ONLY_TIER: u8 = 4;
+    let mut ranked: Vec<(u8, &str, &str)> = Vec::new();
+    let mut directories = HashSet::new();
+    for path in paths {
+        let lower = path.to_ascii_lowercase();
+        if !lower.contains(&query) {
+            continue;
+        }
+        let tier = tail_tier(&lower).unwrap_or(PARENT_ONLY_TIER);
+        ranked.push((tier, path.as_str(), "file"));
+
+        let mut dir = path.as_str();
+        while let Some((parent, _)) = dir.rsplit_once('/') {
+            if parent.to_ascii_lowercase().contains(&query) && directories.insert(parent) {
+                ranked.push((DIRECTORY_TIER, parent, "dir"));
+            }
+            dir = parent;
+        }
+    }
+
+    ranked.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then_with(|| a.1.len().cmp(&b.1.len()))
+            .then_with(|| a.1.cmp(b.1))
+    });
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(_, path, kind)| TreeEntry {
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            path: path.to_string(),
+            kind: kind.to_string(),
+            executable: false,
+        })
+        .collect()
+}
+
+/// Decodes loaded file data for display. Binary files keep no content or
+/// language, and symlinks also report their target.
+fn raw_file_content(
+    repository: String,
+    commit_sha: String,
+    file_path: String,
+    data: FileData,
+) -> RawFileContent {
+    let is_binary = is_binary_content(&data.bytes);
+    let symlink_target =
+        (data.kind == "symlink").then(|| String::from_utf8_lossy(&data.bytes).into_owned());
+    let (content, language) = if is_binary {
+        (String::new(), None)
+    } else {
+        (
+            String::from_utf8_lossy(&data.bytes).into_owned(),
+            data.language,
+        )
+    };
+    RawFileContent {
+        repository,
+        commit_sha,
+        file_path,
+        language,
+        content,
+        is_binary,
+        symlink_target,
+    }
+}
+
+fn chunk_bytes(
+    text_content: String,
+    compressed_content: Option<Vec<u8>>,
+) -> Result<Vec<u8>, DbError> {
+    match compressed_content {
+        Some(compressed) => zstd::stream::decode_all(compressed.as_slice())
+            .map_err(|err| DbError::Internal(format!("failed to decode chunk: {err}"))),
+        None => Ok(text_content.into_bytes()),
+    }
 }
 
 #[derive(sqlx::FromRow, Debug, Clone)]
@@ -3067,11 +4567,26 @@ struct RankedFileRow {
     highlight_pattern: String,
     #[allow(dead_code)]
     highlight_case_sensitive: bool,
+    language: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct DefinitionRow {
+    /// Position of the lookup this definition answers.
+    ord: i32,
+    #[sqlx(flatten)]
+    reference: DbFileReference,
+}
+
+#[derive(sqlx::FromRow)]
+struct SymbolReferenceRow {
+    #[sqlx(flatten)]
+    reference: DbFileReference,
+    total_count: i64,
 }
 
 #[derive(sqlx::FromRow)]
 struct SymbolRow {
-    #[allow(dead_code)]
     id: i32,
     symbol: String,
     namespace: Option<String>,
@@ -3085,11 +4600,67 @@ struct SymbolRow {
     line: Option<i32>,
     #[sqlx(rename = "column_number")]
     column: Option<i32>,
+    signature: Option<String>,
     #[sqlx(rename = "score")]
     score: f64,
+    total: i64,
     references: Option<Json<Vec<ReferenceEntry>>>,
 }
 
+impl SymbolRow {
+    fn into_result(self, include_refs: bool) -> SymbolResult {
+        let references = if include_refs {
+            self.references.as_ref().map(|refs_json| {
+                refs_json
+                    .0
+                    .iter()
+                    .map(|r| ReferenceResult {
+                        name: r.name.clone(),
+                        namespace: r.namespace.clone(),
+                        kind: r.kind.clone(),
+                        fully_qualified: r
+                            .namespace
+                            .as_ref()
+                            .map(|ns| format!("{}::{}", ns, r.name))
+                            .unwrap_or_else(|| r.name.clone()),
+                        repository: r.repository.clone(),
+                        commit_sha: r.commit_sha.clone(),
+                        file_path: r.file_path.clone(),
+                        line: r.line.unwrap_or_default().max(0) as usize,
+                        column: r.column.unwrap_or_default().max(0) as usize,
+                    })
+                    .collect()
+            })
+        } else {
+            None
+        };
+
+        let line = self
+            .line
+            .and_then(|line| (line > 0).then_some(line as usize));
+        let column = self
+            .column
+            .and_then(|column| (column > 0).then_some(column as usize));
+
+        SymbolResult {
+            id: self.id,
+            symbol: self.symbol,
+            namespace: self.namespace,
+            kind: Some(self.kind.unwrap_or_else(|| "definition".to_string())),
+            fully_qualified: self.fully_qualified,
+            repository: self.repository,
+            commit_sha: self.commit_sha,
+            file_path: self.file_path,
+            language: self.language,
+            line,
+            column,
+            signature: self.signature,
+            references,
+            score: self.score,
+        }
+    }
+}
+
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 struct ReferenceEntry {
     namespace: Option<String>,
@@ -3106,12 +4677,173 @@ struct ReferenceEntry {
 struct SnippetRow {
     idx: i32,
     line: i32,
+    context: i32,
     line_count: i32,
+    compressed: bool,
     start_line: i32,
     end_line: i32,
     snippet: Option<String>,
 }
 
+/// Lines `start_line..=end_line` (1-based) of `text`, joined like the SQL
+/// snippet slice.
+fn slice_lines(text: &str, start_line: i32, end_line: i32) -> String {
+    let start = usize::try_from(start_line.max(1) - 1).unwrap_or(0);
+    let count = usize::try_from(end_line - start_line.max(1) + 1).unwrap_or(0);
+    text.split('\n')
+        .skip(start)
+        .take(count)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// What a snippet request was pointing at, used to find where the match ends.
+struct SnippetMatch {
+    column: Option<u32>,
+    highlight: Option<String>,
+    case_sensitive: bool,
+}
+
+/// Longest reference, in lines, that is highlighted as a range. Snippets fetch
+/// this many lines past the match so a range can be found.
+const MAX_HIGHLIGHT_SPAN_LINES: usize = 8;
+
+/// Trims the fetched lines back to the requested context, extended to cover a
+/// reference that continues past its first line.
+/// The snippet for a request whose file could not be found.
+fn missing_file_snippet(line: i32) -> SnippetResponse {
+    let line = line.max(1) as u32;
+    SnippetResponse {
+        start_line: line,
+        highlight_line: line,
+        highlight_start_line: line,
+        highlight_end_line: line,
+        total_lines: 0,
+        lines: Vec::new(),
+        truncated: false,
+    }
+}
+
+fn assemble_snippet(row: SnippetRow, target: &SnippetMatch) -> SnippetResponse {
+    let snippet_text = row.snippet.unwrap_or_default();
+    let mut lines: Vec<String> = if snippet_text.is_empty() {
+        Vec::new()
+    } else {
+        snippet_text.split('\n').map(|s| s.to_string()).collect()
+    };
+
+    let start_line = row.start_line.max(1);
+    let highlight_line = row.line.max(1);
+    let offset = usize::try_from(highlight_line - start_line).unwrap_or(0);
+    let extra_lines = lines
+        .get(offset..)
+        .map(|following| reference_extra_lines(following, target))
+        .unwrap_or(0);
+    let highlight_end_line = highlight_line + extra_lines as i32;
+
+    let end_line = (highlight_line + row.context)
+        .max(highlight_end_line)
+        .min(row.end_line)
+        .max(start_line);
+    lines.truncate(usize::try_from(end_line - start_line + 1).unwrap_or(0));
+
+    SnippetResponse {
+        start_line: start_line as u32,
+        highlight_line: highlight_line as u32,
+        highlight_start_line: highlight_line as u32,
+        highlight_end_line: highlight_end_line as u32,
+        total_lines: row.line_count.max(0) as u32,
+        lines,
+        truncated: start_line > 1 || end_line < row.line_count,
+    }
+}
+
+/// Counts the lines a reference continues onto after `lines[0]`. Only calls,
+/// indexing and struct literals whose brackets open on the first line and
+/// close within [`MAX_HIGHLIGHT_SPAN_LINES`] are treated as spanning.
+fn reference_extra_lines(lines: &[String], target: &SnippetMatch) -> usize {
+    let Some(first) = lines.first() else {
+        return 0;
+    };
+    let chars: Vec<char> = first.chars().collect();
+    let search_from = target
+        .column
+        .map(|column| (column.max(1) - 1) as usize)
+        .unwrap_or(0)
+        .min(chars.len());
+
+    let token_end = match target.highlight.as_deref().filter(|h| !h.is_empty()) {
+        Some(highlight) => {
+            let needle: Vec<char> = highlight.chars().collect();
+            let matches_at = |at: usize| {
+                chars.get(at..at + needle.len()).is_some_and(|window| {
+                    window.iter().zip(&needle).all(|(a, b)| {
+                        if target.case_sensitive {
+                            a == b
+                        } else {
+                            a.to_lowercase().eq(b.to_lowercase())
+                        }
+                    })
+                })
+            };
+            match (search_from..chars.len()).find(|&at| matches_at(at)) {
+                Some(at) => at + needle.len(),
+                None => return 0,
+            }
+        }
+        None if target.column.is_some() => {
+            let mut end = search_from;
+            while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                end += 1;
+            }
+            end
+        }
+        None => return 0,
+    };
+
+    let mut rest = chars[token_end..].iter().skip_while(|c| c.is_whitespace());
+    if !matches!(rest.next(), Some('(' | '[' | '{')) {
+        return 0;
+    }
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (line_idx, line) in lines.iter().take(MAX_HIGHLIGHT_SPAN_LINES).enumerate() {
+        let text: String = if line_idx == 0 {
+            chars[token_end..].iter().collect()
+        } else {
+            line.clone()
+        };
+        let mut previous = None;
+        for ch in text.chars() {
+            if in_string {
+                match ch {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match ch {
+                '"' => in_string = true,
+                '/' if previous == Some('/') => break,
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return line_idx;
+                    }
+                }
+                _ => {}
+            }
+            previous = Some(ch);
+        }
+    }
+    0
+}
+
 #[derive(Clone, Debug)]
 struct FileAggregate {
     entries: Vec<SearchResultRow>,
@@ -3233,6 +4965,63 @@ fn snippet_term_coverage(text: &str, pattern: &str, case_sensitive: bool) -> Opt
     Some((covered_terms as i32, terms.len() as i32))
 }
 
+/// Every line of `text` containing `pattern`, with the byte span of each
+/// non-overlapping occurrence.
+fn find_in_file_lines(text: &str, pattern: &str, case_sensitive: bool) -> Vec<InFileMatch> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    text.lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let spans: Vec<SearchMatchSpan> = if case_sensitive {
+                line.match_indices(pattern)
+                    .map(|(start, matched)| SearchMatchSpan {
+                        start,
+                        end: start + matched.len(),
+                    })
+                    .collect()
+            } else {
+                caseless_match_spans(line, pattern)
+            };
+            (!spans.is_empty()).then(|| InFileMatch {
+                line: u32::try_from(idx + 1).unwrap_or(u32::MAX),
+                spans,
+            })
+        })
+        .collect()
+}
+
+fn caseless_match_spans(line: &str, pattern: &str) -> Vec<SearchMatchSpan> {
+    let mut spans = Vec::new();
+    let mut from = 0;
+    while let Some(rest) = line.get(from..).filter(|rest| !rest.is_empty()) {
+        match caseless_prefix_len(rest, pattern) {
+            Some(len) => {
+                spans.push(SearchMatchSpan {
+                    start: from,
+                    end: from + len,
+                });
+                from += len.max(1);
+            }
+            None => from += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    spans
+}
+
+/// Byte length of the start of `haystack` that equals `needle` ignoring case.
+fn caseless_prefix_len(haystack: &str, needle: &str) -> Option<usize> {
+    let mut chars = haystack.char_indices();
+    for expected in needle.chars() {
+        let (_, ch) = chars.next()?;
+        if !ch.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(haystack.len(), |(idx, _)| idx))
+}
+
 fn find_literal_match_spans(
     text: &str,
     terms: &[String],
@@ -3527,9 +5316,140 @@ fn build_snippet_from_map(
     }
 }
 
+const SNIPPET_ELLIPSIS: &str = "…";
+
+/// Bounds a snippet to roughly `max_chars` characters. Overlong lines are cut
+/// down to a window around their first highlight, and if the snippet is still
+/// too long, context lines furthest from the match line are dropped. Cuts only
+/// land inside a highlight when the first one alone exceeds the budget; other
+/// spans that would be split are dropped instead.
+fn truncate_snippet(snippet: SearchSnippet, max_chars: usize) -> SearchSnippet {
+    if snippet.content_text.chars().count() <= max_chars {
+        return snippet;
+    }
+
+    let mut lines: Vec<(String, Vec<SearchMatchSpan>)> = split_snippet_lines(&snippet)
+        .into_iter()
+        .map(|(line, spans)| truncate_snippet_line(line, spans, max_chars))
+        .collect();
+
+    let mut start_line = snippet.start_line;
+    let mut total_chars = lines
+        .iter()
+        .map(|(line, _)| line.chars().count())
+        .sum::<usize>()
+        + lines.len().saturating_sub(1);
+    while total_chars > max_chars && lines.len() > 1 {
+        let match_idx = snippet
+            .match_line
+            .saturating_sub(start_line)
+            .clamp(0, lines.len() as i32 - 1) as usize;
+        let removed = if match_idx >= lines.len() - 1 - match_idx {
+            start_line += 1;
+            lines.remove(0)
+        } else {
+            lines.pop().expect("snippet has more than one line")
+        };
+        total_chars -= removed.0.chars().count() + 1;
+    }
+
+    let mut content_text = String::new();
+    let mut match_spans = Vec::new();
+    for (idx, (line, spans)) in lines.iter().enumerate() {
+        if idx > 0 {
+            content_text.push('\n');
+        }
+        let offset = content_text.len();
+        match_spans.extend(spans.iter().map(|span| SearchMatchSpan {
+            start: offset + span.start,
+            end: offset + span.end,
+        }));
+        content_text.push_str(line);
+    }
+
+    SearchSnippet {
+        start_line,
+        end_line: start_line + lines.len() as i32 - 1,
+        match_line: snippet.match_line,
+        content_text,
+        match_spans,
+    }
+}
+
+fn truncate_snippet_line(
+    line: String,
+    spans: Vec<SearchMatchSpan>,
+    max_chars: usize,
+) -> (String, Vec<SearchMatchSpan>) {
+    if line.chars().count() <= max_chars {
+        return (line, spans);
+    }
+
+    // Leave room for an ellipsis on either side of the window.
+    let budget = max_chars.saturating_sub(2).max(1);
+    let char_starts: Vec<usize> = line.char_indices().map(|(idx, _)| idx).collect();
+    let byte_at = |char_idx: usize| char_starts.get(char_idx).copied().unwrap_or(line.len());
+    let char_at = |byte_idx: usize| char_starts.partition_point(|&start| start < byte_idx);
+
+    let primary = spans
+        .first()
+        .map(|span| (char_at(span.start), char_at(span.end)));
+    let (window_start, window_end, spans) = match primary {
+        // The match alone overflows the budget, so cut inside it rather
+        // than let the window grow past `max_chars`.
+        Some((span_start, span_end)) if span_end - span_start > budget => {
+            let start = byte_at(span_start);
+            let end = byte_at(span_start + budget);
+            (start, end, vec![SearchMatchSpan { start, end }])
+        }
+        Some((span_start, span_end)) => {
+            let lead = budget.saturating_sub(span_end - span_start) / 2;
+            let first = span_start
+                .saturating_sub(lead)
+                .min(char_starts.len().saturating_sub(budget));
+            let last = (first + budget).max(span_end);
+            let (mut window_start, mut window_end) = (byte_at(first), byte_at(last));
+
+            // Pull the window edges in so they never split a highlight.
+            for span in &spans {
+                if span.start < window_start && span.end > window_start {
+                    window_start = span.end.min(window_end);
+                }
+                if span.start < window_end && span.end > window_end {
+                    window_end = span.start.max(window_start);
+                }
+            }
+            (window_start, window_end, spans)
+        }
+        None => (0, byte_at(budget), spans),
+    };
+
+    let prefix = if window_start > 0 {
+        SNIPPET_ELLIPSIS
+    } else {
+        ""
+    };
+    let suffix = if window_end < line.len() {
+        SNIPPET_ELLIPSIS
+    } else {
+        ""
+    };
+    let text = format!("{prefix}{}{suffix}", &line[window_start..window_end]);
+    let spans = spans
+        .into_iter()
+        .filter(|span| span.start >= window_start && span.end <= window_end)
+        .map(|span| SearchMatchSpan {
+            start: span.start - window_start + prefix.len(),
+            end: span.end - window_start + prefix.len(),
+        })
+        .collect();
+    (text, spans)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pointer_indexer_types::FileKind;
 
     fn build_phase1_sql(request: &TextSearchRequest) -> String {
         let SearchBudgets {
@@ -3543,15 +5463,17 @@ mod tests {
             .iter()
             .any(|plan| plan.branches.is_empty() && !plan.include_historical);
 
-        let mut symbol_terms: Vec<String> = collect_symbol_terms(request)
-            .into_iter()
-            .map(|t| t.to_lowercase())
-            .collect();
+        let mut symbol_terms: Vec<String> =
+            collect_symbol_terms(request, DEFAULT_MIN_SYMBOL_LENGTH)
+                .into_iter()
+                .map(|t| t.to_lowercase())
+                .collect();
         symbol_terms.sort_unstable();
-        let mut definition_terms: Vec<String> = collect_definition_terms(request)
-            .into_iter()
-            .map(|t| t.to_lowercase())
-            .collect();
+        let mut definition_terms: Vec<String> =
+            collect_definition_terms(request, DEFAULT_MIN_SYMBOL_LENGTH)
+                .into_iter()
+                .map(|t| t.to_lowercase())
+                .collect();
         definition_terms.sort_unstable();
 
         let mut qb = QueryBuilder::new("");
@@ -3564,6 +5486,7 @@ mod tests {
             needs_live_branch_filter,
             &symbol_terms,
             &definition_terms,
+            false,
         );
         qb.sql().to_string()
     }
@@ -3580,15 +5503,17 @@ mod tests {
             .iter()
             .any(|plan| plan.branches.is_empty() && !plan.include_historical);
 
-        let mut symbol_terms: Vec<String> = collect_symbol_terms(request)
-            .into_iter()
-            .map(|t| t.to_lowercase())
-            .collect();
+        let mut symbol_terms: Vec<String> =
+            collect_symbol_terms(request, DEFAULT_MIN_SYMBOL_LENGTH)
+                .into_iter()
+                .map(|t| t.to_lowercase())
+                .collect();
         symbol_terms.sort_unstable();
-        let mut definition_terms: Vec<String> = collect_definition_terms(request)
-            .into_iter()
-            .map(|t| t.to_lowercase())
-            .collect();
+        let mut definition_terms: Vec<String> =
+            collect_definition_terms(request, DEFAULT_MIN_SYMBOL_LENGTH)
+                .into_iter()
+                .map(|t| t.to_lowercase())
+                .collect();
         definition_terms.sort_unstable();
 
         let mut phase1_qb = QueryBuilder::new("");
@@ -3601,6 +5526,7 @@ mod tests {
             needs_live_branch_filter,
             &symbol_terms,
             &definition_terms,
+            false,
         );
         phase1_qb.push(
             "
@@ -3619,8 +5545,10 @@ mod tests {
                 fr.is_historical,
                 fr.snapshot_indexed_at,
                 fr.highlight_pattern,
-                fr.highlight_case_sensitive
+                fr.highlight_case_sensitive,
+                cb.language
             FROM filtered_ranked fr
+            LEFT JOIN content_blobs cb ON cb.hash = fr.content_hash
             ORDER BY
                 fr.definition_matches DESC,
                 fr.total_score DESC,
@@ -3647,6 +5575,7 @@ mod tests {
             snapshot_indexed_at: None,
             highlight_pattern: request.plans[0].highlight_pattern.clone(),
             highlight_case_sensitive: false,
+            language: None,
         }];
 
         let mut phase2_qb = QueryBuilder::new(
@@ -3910,59 +5839,255 @@ mod tests {
     }
 
     #[test]
-    fn parse_plain_highlight_pattern_round_trips_escaped_literals() {
-        let terms = parse_plain_highlight_pattern(r#"failed for block|pg_fatal\(\)"#)
-            .expect("pattern should parse as plain literals");
+    fn truncate_snippet_bounds_long_line_and_keeps_highlight() {
+        let long_line = format!(
+            "{}needle{}",
+            "a = 1;".repeat(50_000),
+            "b = 2;".repeat(50_000)
+        );
+        let match_start = 6 * 50_000 + "line9\n".len();
+        let snippet = SearchSnippet {
+            start_line: 9,
+            end_line: 11,
+            match_line: 10,
+            content_text: format!("line9\n{long_line}\nline11"),
+            match_spans: vec![SearchMatchSpan {
+                start: match_start,
+                end: match_start + "needle".len(),
+            }],
+        };
+
+        let truncated = truncate_snippet(snippet, 200);
+
+        assert!(truncated.content_text.chars().count() <= 200);
+        assert_eq!(truncated.match_spans.len(), 1);
+        let span = &truncated.match_spans[0];
+        assert_eq!(&truncated.content_text[span.start..span.end], "needle");
+        assert_eq!(truncated.match_line, 10);
+        assert!(truncated.start_line <= 10 && truncated.end_line >= 10);
         assert_eq!(
-            terms,
-            vec!["failed for block".to_string(), "pg_fatal()".to_string()]
+            truncated.end_line - truncated.start_line + 1,
+            truncated.content_text.split('\n').count() as i32
         );
+        let match_text = truncated
+            .content_text
+            .split('\n')
+            .nth((10 - truncated.start_line) as usize)
+            .unwrap();
+        assert!(match_text.starts_with('…') && match_text.ends_with('…'));
     }
 
     #[test]
-    fn parse_plain_highlight_pattern_keeps_regex_like_literals_plain() {
-        assert_eq!(
-            parse_plain_highlight_pattern("foo.*bar"),
-            Some(vec!["foo.*bar".to_string()])
+    fn truncate_snippet_does_not_split_secondary_highlights() {
+        let line = format!(
+            "{}hit{}hit{}",
+            "x".repeat(40),
+            "y".repeat(6),
+            "z".repeat(40)
         );
+        let first = 40;
+        let second = first + 3 + 6;
+        let snippet = SearchSnippet {
+            start_line: 1,
+            end_line: 1,
+            match_line: 1,
+            content_text: line,
+            match_spans: vec![
+                SearchMatchSpan {
+                    start: first,
+                    end: first + 3,
+                },
+                SearchMatchSpan {
+                    start: second,
+                    end: second + 3,
+                },
+            ],
+        };
+
+        for max_chars in 5..40 {
+            let truncated = truncate_snippet(snippet.clone(), max_chars);
+            assert!(!truncated.match_spans.is_empty());
+            for span in &truncated.match_spans {
+                assert_eq!(&truncated.content_text[span.start..span.end], "hit");
+            }
+        }
     }
 
     #[test]
-    fn normalize_literal_match_spans_recomputes_shifted_plain_phrase() {
-        let text = r#"pg_fatal("seek failed for block %u", blockno);"#;
-        let original = vec![SearchMatchSpan { start: 17, end: 33 }];
+    fn truncate_snippet_cuts_inside_a_match_longer_than_the_budget() {
+        let needle = "needle".repeat(20);
+        let line = format!("let x = {needle}; // end");
+        let match_start = "let x = ".len();
+        let snippet = SearchSnippet {
+            start_line: 1,
+            end_line: 1,
+            match_line: 1,
+            content_text: line,
+            match_spans: vec![SearchMatchSpan {
+                start: match_start,
+                end: match_start + needle.len(),
+            }],
+        };
 
-        let normalized = normalize_literal_match_spans(text, &original, "failed for block", true);
+        let truncated = truncate_snippet(snippet, 40);
 
-        let expected_start = text.find("failed for block").expect("phrase should exist");
-        assert_eq!(
-            normalized,
-            vec![SearchMatchSpan {
-                start: expected_start,
-                end: expected_start + "failed for block".len(),
-            }]
-        );
+        assert_eq!(truncated.content_text.chars().count(), 40);
+        assert!(truncated.content_text.starts_with('…'));
+        assert!(truncated.content_text.ends_with('…'));
+        assert_eq!(truncated.match_spans.len(), 1);
+        let span = &truncated.match_spans[0];
+        let highlighted = &truncated.content_text[span.start..span.end];
+        assert_eq!(highlighted.len(), 38);
+        assert!(needle.starts_with(highlighted));
     }
 
     #[test]
-    fn normalize_literal_match_spans_preserves_regex_patterns() {
-        let original = vec![SearchMatchSpan { start: 5, end: 11 }];
-        let normalized = normalize_literal_match_spans("abcde failed", &original, "fail.*", true);
-        assert_eq!(normalized, original);
+    fn truncate_snippet_leaves_short_snippets_untouched() {
+        let snippet = SearchSnippet {
+            start_line: 1,
+            end_line: 2,
+            match_line: 1,
+            content_text: "fn main() {\n}".to_string(),
+            match_spans: vec![SearchMatchSpan { start: 3, end: 7 }],
+        };
+        let truncated = truncate_snippet(snippet.clone(), 100);
+        assert_eq!(truncated.content_text, snippet.content_text);
+        assert_eq!(truncated.match_spans.len(), 1);
     }
 
-    #[test]
-    fn multi_term_search_uses_chunk_local_and_filter() {
-        let request = TextSearchRequest::from_query_str("polly LinkAllPasses").unwrap();
-        let sql = build_phase1_sql(&request);
-        assert!(sql.contains("seed_rows AS ("));
-        assert!(sql.contains("matched_rows AS ("));
-        assert!(sql.contains("seed.text_content"));
+    fn snippet_row(line: i32, context: i32, start_line: i32, text: &str) -> SnippetRow {
+        let line_count = text.split('\n').count() as i32 + start_line - 1;
+        SnippetRow {
+            idx: 0,
+            line,
+            context,
+            line_count,
+            compressed: false,
+            start_line,
+            end_line: line_count,
+            snippet: Some(text.to_string()),
+        }
     }
 
     #[test]
-    fn ranked_top_preserves_chunk_row_identity() {
-        let request = TextSearchRequest::from_query_str("polly LinkAllPasses").unwrap();
+    fn snippet_highlights_every_line_of_a_multi_line_call() {
+        let row = snippet_row(
+            2,
+            0,
+            2,
+            "    let total = sum(\n        a,\n        \"b)\", // c)\n    );\n    done();",
+        );
+        let target = SnippetMatch {
+            column: Some(17),
+            highlight: Some("SUM".to_string()),
+            case_sensitive: false,
+        };
+        let snippet = assemble_snippet(row, &target);
+
+        assert_eq!(snippet.highlight_line, 2);
+        assert_eq!(snippet.highlight_start_line, 2);
+        assert_eq!(snippet.highlight_end_line, 5);
+        assert_eq!(snippet.lines.len(), 4);
+        assert_eq!(snippet.lines.last().map(String::as_str), Some("    );"));
+        assert!(snippet.truncated);
+    }
+
+    #[test]
+    fn compressed_chunks_decode_to_their_original_bytes() {
+        let text = "fn main() {\n    println!(\"héllo\");\n}\n";
+        let compressed = zstd::stream::encode_all(text.as_bytes(), 0).unwrap();
+
+        assert_eq!(
+            chunk_bytes(String::new(), Some(compressed)).unwrap(),
+            text.as_bytes()
+        );
+        assert_eq!(
+            chunk_bytes(text.to_string(), None).unwrap(),
+            text.as_bytes()
+        );
+        assert!(chunk_bytes(String::new(), Some(b"not zstd".to_vec())).is_err());
+    }
+
+    #[test]
+    fn slice_lines_matches_the_sql_snippet_slice() {
+        let text = "a\nb\nc\nd\n";
+        assert_eq!(slice_lines(text, 2, 3), "b\nc");
+        assert_eq!(slice_lines(text, 1, 1), "a");
+        assert_eq!(slice_lines(text, 4, 9), "d\n");
+        assert_eq!(slice_lines(text, 3, 2), "");
+    }
+
+    #[test]
+    fn snippet_keeps_single_line_references_on_one_line() {
+        let text = "fn main() {\n    run(config);\n    let f = run;\n}";
+        for (line, column) in [(2, 5), (3, 13)] {
+            let row = snippet_row(line, 1, 1, text);
+            let target = SnippetMatch {
+                column: Some(column),
+                highlight: None,
+                case_sensitive: false,
+            };
+            let snippet = assemble_snippet(row, &target);
+            assert_eq!(snippet.highlight_start_line, line as u32);
+            assert_eq!(snippet.highlight_end_line, line as u32);
+            assert_eq!(snippet.lines.len(), line as usize + 1);
+        }
+    }
+
+    #[test]
+    fn parse_plain_highlight_pattern_round_trips_escaped_literals() {
+        let terms = parse_plain_highlight_pattern(r#"failed for block|pg_fatal\(\)"#)
+            .expect("pattern should parse as plain literals");
+        assert_eq!(
+            terms,
+            vec!["failed for block".to_string(), "pg_fatal()".to_string()]
+        );
+    }
+
+    #[test]
+    fn parse_plain_highlight_pattern_keeps_regex_like_literals_plain() {
+        assert_eq!(
+            parse_plain_highlight_pattern("foo.*bar"),
+            Some(vec!["foo.*bar".to_string()])
+        );
+    }
+
+    #[test]
+    fn normalize_literal_match_spans_recomputes_shifted_plain_phrase() {
+        let text = r#"pg_fatal("seek failed for block %u", blockno);"#;
+        let original = vec![SearchMatchSpan { start: 17, end: 33 }];
+
+        let normalized = normalize_literal_match_spans(text, &original, "failed for block", true);
+
+        let expected_start = text.find("failed for block").expect("phrase should exist");
+        assert_eq!(
+            normalized,
+            vec![SearchMatchSpan {
+                start: expected_start,
+                end: expected_start + "failed for block".len(),
+            }]
+        );
+    }
+
+    #[test]
+    fn normalize_literal_match_spans_preserves_regex_patterns() {
+        let original = vec![SearchMatchSpan { start: 5, end: 11 }];
+        let normalized = normalize_literal_match_spans("abcde failed", &original, "fail.*", true);
+        assert_eq!(normalized, original);
+    }
+
+    #[test]
+    fn multi_term_search_uses_chunk_local_and_filter() {
+        let request = TextSearchRequest::from_query_str("polly LinkAllPasses").unwrap();
+        let sql = build_phase1_sql(&request);
+        assert!(sql.contains("seed_rows AS ("));
+        assert!(sql.contains("matched_rows AS ("));
+        assert!(sql.contains("seed.text_content"));
+    }
+
+    #[test]
+    fn ranked_top_preserves_chunk_row_identity() {
+        let request = TextSearchRequest::from_query_str("polly LinkAllPasses").unwrap();
         let sql = build_phase1_sql(&request);
 
         assert!(
@@ -3971,6 +6096,14 @@ mod tests {
         assert!(!sql.contains("MIN(lp.chunk_index) AS chunk_index"));
     }
 
+    #[test]
+    fn repeated_negated_langs_emit_one_exclusion() {
+        let request =
+            TextSearchRequest::from_query_str("-lang:rust -lang:go -lang:Rust polly").unwrap();
+        let sql = build_phase1_sql(&request);
+        assert_eq!(sql.matches("NOT (cb.language = ANY(").count(), 1);
+    }
+
     #[test]
     fn single_term_search_omits_intersect_filter() {
         let request = TextSearchRequest::from_query_str("polly").unwrap();
@@ -4019,6 +6152,32 @@ mod tests {
         assert!(!sql.contains("definition_scores AS"));
     }
 
+    #[test]
+    fn lowercase_content_only_rewrites_case_insensitive_plain_terms() {
+        let sql_for = |query: &str| {
+            let request = TextSearchRequest::from_query_str(query).unwrap();
+            build_phase1_query(&request, &[], &[], true)
+                .sql()
+                .to_string()
+        };
+
+        let sql = sql_for("needle -haystack");
+        assert!(sql.contains("lower(c.text_content) LIKE '%' || lower("));
+        assert!(sql.contains("NOT (lower(c.text_content) LIKE"));
+        assert!(!sql.contains("ILIKE '%' ||"));
+
+        let sql = sql_for("needle case:yes");
+        assert!(!sql.contains("lower(c.text_content)"));
+        assert!(sql.contains("c.text_content LIKE '%' ||"));
+
+        let sql = sql_for("regex:\"foo.*bar\"");
+        assert!(!sql.contains("lower(c.text_content)"));
+        assert!(sql.contains("c.text_content ~* "));
+
+        let request = TextSearchRequest::from_query_str("needle").unwrap();
+        assert!(!build_phase1_sql(&request).contains("lower(c.text_content)"));
+    }
+
     #[test]
     fn snippet_rank_score_prioritizes_definition_matches() {
         let reference_score = snippet_rank_score(
@@ -4075,6 +6234,233 @@ mod tests {
         assert!(sql.contains("COALESCE(ctx.context_snippet, c.text_content)"));
     }
 
+    #[test]
+    fn language_facets_rank_unknown_after_known_languages() {
+        let row = |file_path: &str, language: Option<&str>| RankedFileRow {
+            file_id: 1,
+            repository: "repo".to_string(),
+            commit_sha: "commit".to_string(),
+            file_path: file_path.to_string(),
+            content_hash: file_path.to_string(),
+            chunk_index: 0,
+            total_score: 1.0,
+            definition_matches: 0,
+            include_historical: false,
+            branches: Vec::new(),
+            live_branches: Vec::new(),
+            is_historical: false,
+            snapshot_indexed_at: None,
+            highlight_pattern: "needle".to_string(),
+            highlight_case_sensitive: false,
+            language: language.map(str::to_string),
+        };
+        let mut rows = vec![
+            row("src/a.rs", Some("rust")),
+            row("src/b.rs", Some("rust")),
+            row("cmd/main.go", Some("go")),
+        ];
+        rows.extend((0..5).map(|i| row(&format!("data/{i}.bin"), None)));
+
+        let facets: Vec<(String, u32)> = build_search_stats(&rows)
+            .top_languages
+            .into_iter()
+            .map(|facet| (facet.value, facet.count))
+            .collect();
+        assert_eq!(
+            facets,
+            [
+                ("rust".to_string(), 2),
+                ("go".to_string(), 1),
+                (UNKNOWN_LANGUAGE.to_string(), 5)
+            ]
+        );
+
+        let crowded: HashMap<String, u32> =
+            (0..FACET_LIMIT).map(|i| (format!("lang{i}"), 1)).collect();
+        let facets = language_facets(crowded, 100);
+        assert_eq!(facets.len(), FACET_LIMIT);
+        assert!(facets.iter().all(|facet| facet.value != UNKNOWN_LANGUAGE));
+    }
+
+    fn ranked_paths(paths: &[&str], query: &str, limit: usize) -> Vec<(String, String)> {
+        let paths: Vec<String> = paths.iter().map(|path| path.to_string()).collect();
+        rank_path_matches(&paths, query, limit)
+            .into_iter()
+            .map(|entry| (entry.path, entry.kind))
+            .collect()
+    }
+
+    fn entries(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(path, kind)| (path.to_string(), kind.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn path_matches_rank_filenames_before_directories() {
+        let paths = [
+            "main.rs.d/readme.md",
+            "crates/app/src/main.rs",
+            "src/domain.rs",
+            "docs/other.md",
+            "bin/main.rs.orig",
+            "src/main.rs",
+        ];
+        assert_eq!(
+            ranked_paths(&paths, "main.rs", 10),
+            entries(&[
+                ("src/main.rs", "file"),
+                ("crates/app/src/main.rs", "file"),
+                ("bin/main.rs.orig", "file"),
+                ("src/domain.rs", "file"),
+                ("main.rs.d", "dir"),
+                ("main.rs.d/readme.md", "file"),
+            ])
+        );
+
+        let paths = [
+            "src/main/java/App.java",
+            "lib/domain_main.rs",
+            "cmd/server/main.go",
+            "MAIN",
+        ];
+        assert_eq!(
+            ranked_paths(&paths, "Main", 10),
+            entries(&[
+                ("MAIN", "file"),
+                ("cmd/server/main.go", "file"),
+                ("lib/domain_main.rs", "file"),
+                ("src/main", "dir"),
+                ("src/main/java", "dir"),
+                ("src/main/java/App.java", "file"),
+            ])
+        );
+        assert_eq!(
+            ranked_paths(&paths, "main", 2),
+            entries(&[("MAIN", "file"), ("cmd/server/main.go", "file")])
+        );
+    }
+
+    #[test]
+    fn path_queries_with_slashes_match_path_suffixes() {
+        let paths = [
+            "tests/src/main.rs/fixture.txt",
+            "xsrc/main.rs",
+            "crates/app/src/main.rs",
+            "src/main.rs.bak",
+            "src/main.rs",
+        ];
+        assert_eq!(
+            ranked_paths(&paths, "src/main.rs", 10),
+            entries(&[
+                ("src/main.rs", "file"),
+                ("crates/app/src/main.rs", "file"),
+                ("src/main.rs.bak", "file"),
+                ("xsrc/main.rs", "file"),
+                ("tests/src/main.rs", "dir"),
+                ("tests/src/main.rs/fixture.txt", "file"),
+            ])
+        );
+        assert!(ranked_paths(&paths, "/", 10).is_empty());
+    }
+
+    #[test]
+    fn ranked_rows_follow_the_requested_order() {
+        let indexed = |day: u32| DateTime::from_timestamp(i64::from(day) * 86_400, 0);
+        let row = |repository: &str, file_path: &str, day: Option<u32>| RankedFileRow {
+            file_id: 1,
+            repository: repository.to_string(),
+            commit_sha: "commit".to_string(),
+            file_path: file_path.to_string(),
+            content_hash: file_path.to_string(),
+            chunk_index: 0,
+            total_score: 1.0,
+            definition_matches: 0,
+            include_historical: false,
+            branches: Vec::new(),
+            live_branches: Vec::new(),
+            is_historical: false,
+            snapshot_indexed_at: day.and_then(indexed),
+            highlight_pattern: "needle".to_string(),
+            highlight_case_sensitive: false,
+            language: None,
+        };
+        // Phase 1 hands rows over by relevance.
+        let by_relevance = vec![
+            row("beta", "src/zeta.rs", Some(2)),
+            row("alpha", "src/alpha.rs", None),
+            row("beta", "lib/mid.rs", Some(9)),
+            row("alpha", "src/zeta.rs", Some(9)),
+        ];
+        let ordered = |order: SearchOrder| {
+            let mut rows = by_relevance.clone();
+            order_ranked_rows(&mut rows, order);
+            rows.into_iter()
+                .map(|row| format!("{}/{}", row.repository, row.file_path))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ordered(SearchOrder::Relevance),
+            [
+                "beta/src/zeta.rs",
+                "alpha/src/alpha.rs",
+                "beta/lib/mid.rs",
+                "alpha/src/zeta.rs"
+            ]
+        );
+        assert_eq!(
+            ordered(SearchOrder::Path),
+            [
+                "beta/lib/mid.rs",
+                "alpha/src/alpha.rs",
+                "alpha/src/zeta.rs",
+                "beta/src/zeta.rs"
+            ]
+        );
+        // Ties on snapshot time keep their relevance order.
+        assert_eq!(
+            ordered(SearchOrder::Recency),
+            [
+                "beta/lib/mid.rs",
+                "alpha/src/zeta.rs",
+                "beta/src/zeta.rs",
+                "alpha/src/alpha.rs"
+            ]
+        );
+    }
+
+    #[test]
+    fn location_query_skips_chunk_text() {
+        let row = RankedFileRow {
+            file_id: 1,
+            repository: "repo".to_string(),
+            commit_sha: "commit".to_string(),
+            file_path: "file".to_string(),
+            content_hash: "hash".to_string(),
+            chunk_index: 2,
+            total_score: 1.0,
+            definition_matches: 0,
+            include_historical: false,
+            branches: Vec::new(),
+            live_branches: Vec::new(),
+            is_historical: false,
+            snapshot_indexed_at: None,
+            highlight_pattern: "needle".to_string(),
+            highlight_case_sensitive: false,
+            language: None,
+        };
+        let rows = [row];
+        let qb = build_location_query(&rows);
+        let sql = qb.sql();
+
+        assert!(!sql.contains("extract_context_with_highlight"));
+        assert!(!sql.contains("JOIN chunks"));
+        assert!(!sql.contains("symbol_references"));
+        assert!(sql.contains("SUM(cbc.chunk_line_count)"));
+    }
+
     #[test]
     fn regex_search_uses_smaller_phase1_budgets() {
         let request = TextSearchRequest::from_query_str("regex:\"foo.*bar\"").unwrap();
@@ -4091,45 +6477,2582 @@ mod tests {
     }
 
     #[test]
-    fn plain_search_keeps_default_phase1_budgets() {
-        let request = TextSearchRequest::from_query_str("polly").unwrap();
-        let budgets = compute_search_budgets(&request);
+    fn unavailable_retry_hint_survives_the_server_fn_boundary() {
+        use crate::services::search_service::SearchError;
 
-        assert_eq!(
-            budgets,
-            SearchBudgets {
-                fetch_limit: 301,
-                file_limit: 301,
-                plan_row_limit: DEFAULT_PLAN_ROW_LIMIT,
+        let err = SearchError::from(DbError::Unavailable {
+            reason: "pool timed out".to_string(),
+            retry_after_secs: 12,
+        });
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(matches!(
+            serde_json::from_str::<SearchError>(&json).unwrap(),
+            SearchError::Unavailable {
+                retry_after_secs: 12,
+                ..
             }
+        ));
+        let failed = SearchError::from(DbError::Database {
+            op: "search_symbols".to_string(),
+            source: "boom".to_string(),
+        });
+        assert!(matches!(failed, SearchError::Failed(_)));
+    }
+
+    fn symbol_request(kind: Option<Vec<&str>>, include_paths: Vec<&str>) -> SearchRequest {
+        SearchRequest {
+            q: None,
+            name: Some("parse".to_string()),
+            name_regex: None,
+            name_contains: None,
+            namespace: None,
+            namespace_prefix: None,
+            kind: kind.map(|kinds| kinds.into_iter().map(str::to_string).collect()),
+            language: None,
+            repository: Some("pointer".to_string()),
+            commit_sha: None,
+            path: None,
+            path_regex: None,
+            path_hint: None,
+            path_depth: None,
+            include_paths: include_paths.into_iter().map(str::to_string).collect(),
+            excluded_paths: Vec::new(),
+            include_references: None,
+            limit: Some(20),
+            unreferenced: false,
+            group_by_name: false,
+        }
+    }
+
+    #[test]
+    fn in_file_matches_report_byte_spans_per_line() {
+        let spans = |matches: Vec<InFileMatch>| -> Vec<(u32, Vec<(usize, usize)>)> {
+            matches
+                .into_iter()
+                .map(|m| {
+                    (
+                        m.line,
+                        m.spans.iter().map(|span| (span.start, span.end)).collect(),
+                    )
+                })
+                .collect()
+        };
+        let text = "ÉCOLE école\nnone here\naaaa";
+
+        assert_eq!(
+            spans(find_in_file_lines(text, "école", false)),
+            [(1, vec![(0, 6), (7, 13)])]
+        );
+        assert_eq!(
+            spans(find_in_file_lines(text, "école", true)),
+            [(1, vec![(7, 13)])]
+        );
+        assert_eq!(
+            spans(find_in_file_lines(text, "aa", false)),
+            [(3, vec![(0, 2), (2, 4)])]
         );
+        assert!(find_in_file_lines(text, "", false).is_empty());
     }
-}
 
-fn build_search_stats(rows: &[RankedFileRow]) -> SearchResultsStats {
-    let mut directory_counts: HashMap<String, u32> = HashMap::new();
-    let mut repository_counts: HashMap<String, u32> = HashMap::new();
-    let mut branch_counts: HashMap<String, u32> = HashMap::new();
+    #[test]
+    fn short_terms_are_not_symbol_candidates() {
+        assert!(!looks_like_symbol("x", DEFAULT_MIN_SYMBOL_LENGTH));
+        assert!(!looks_like_symbol("io", DEFAULT_MIN_SYMBOL_LENGTH));
+        assert!(looks_like_symbol("io", 2));
+
+        let request = TextSearchRequest::from_query_str("parse_query tmp").unwrap();
+        let sorted = |terms: HashSet<String>| {
+            let mut terms: Vec<String> = terms.into_iter().collect();
+            terms.sort();
+            terms
+        };
+        assert_eq!(
+            sorted(collect_symbol_terms(&request, DEFAULT_MIN_SYMBOL_LENGTH)),
+            ["parse_query", "tmp"]
+        );
+        assert_eq!(sorted(collect_symbol_terms(&request, 4)), ["parse_query"]);
+        assert_eq!(
+            sorted(collect_definition_terms(&request, 4)),
+            ["parse_query"]
+        );
+    }
 
-    for row in rows {
-        if let Some(directory) = parent_directory(&row.file_path) {
-            *directory_counts.entry(directory).or_insert(0) += 1;
+    #[test]
+    fn symbol_cache_key_ignores_filter_order() {
+        let key = symbol_cache_key(&symbol_request(
+            Some(vec!["function", "struct"]),
+            vec!["src/", "lib/"],
+        ));
+
+        assert_eq!(
+            key,
+            symbol_cache_key(&symbol_request(
+                Some(vec!["struct", "function", "struct"]),
+                vec!["lib/", "src/"],
+            ))
+        );
+        assert_eq!(
+            symbol_cache_key(&symbol_request(Some(Vec::new()), Vec::new())),
+            symbol_cache_key(&symbol_request(None, Vec::new()))
+        );
+        assert_ne!(
+            key,
+            symbol_cache_key(&symbol_request(
+                Some(vec!["function"]),
+                vec!["src/", "lib/"]
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_symbol_search_skips_the_database() {
+        // Nothing listens on this port, so any query would fail.
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://pointer@127.0.0.1:1/pointer")
+            .unwrap();
+        let cache = Arc::new(SymbolSearchCache::new(
+            std::time::Duration::from_secs(10),
+            16,
+        ));
+        let request = symbol_request(Some(vec!["function"]), Vec::new());
+        cache.insert(
+            symbol_cache_key(&request).expect("request should serialize"),
+            SearchResponse {
+                symbols: Vec::new(),
+                total: 0,
+            },
+        );
+
+        let db = PostgresDb::new(pool).with_symbol_cache(cache);
+        let response = db
+            .search_symbols(request)
+            .await
+            .expect("cached response should not touch the database");
+        assert!(response.symbols.is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_pool_falls_back_to_the_primary() {
+        let lazy = |url: &str| {
+            sqlx::postgres::PgPoolOptions::new()
+                .connect_lazy(url)
+                .unwrap()
+        };
+        let database = |pool: &PgPool| pool.connect_options().get_database().map(str::to_string);
+
+        let db = PostgresDb::new(lazy("postgres://pointer@127.0.0.1:1/primary"));
+        assert_eq!(database(db.read_pool()).as_deref(), Some("primary"));
+
+        let db = db.with_read_pool(lazy("postgres://pointer@127.0.0.1:1/replica"));
+        assert_eq!(database(db.read_pool()).as_deref(), Some("replica"));
+        assert_eq!(database(&db.pool).as_deref(), Some("primary"));
+    }
+
+    /// `DATABASE_URL`, or `None` when it is unset and database tests are
+    /// skipped.
+    fn database_url() -> Option<String> {
+        let url = std::env::var("DATABASE_URL").ok();
+        if url.is_none() {
+            eprintln!("DATABASE_URL is not set; skipping database test");
         }
-        *repository_counts.entry(row.repository.clone()).or_insert(0) += 1;
+        url
+    }
 
-        if !row.branches.is_empty() {
-            let unique_branches: HashSet<&String> = row.branches.iter().collect();
-            for branch in unique_branches {
-                *branch_counts.entry(branch.clone()).or_insert(0) += 1;
+    async fn test_pool() -> Option<PgPool> {
+        let url = database_url()?;
+        Some(
+            sqlx::postgres::PgPoolOptions::new()
+                .max_connections(2)
+                .connect(&url)
+                .await
+                .expect("failed to connect to postgres"),
+        )
+    }
+
+    /// Rows one database test inserts under its own repository, all at
+    /// commit `abc123`. Dropping the fixture deletes them again, so a failed
+    /// assertion doesn't leave rows behind for the next run.
+    struct Fixture {
+        pool: PgPool,
+        repository: String,
+        repositories: Vec<String>,
+        blobs: Vec<String>,
+        chunks: Vec<String>,
+        symbol_names: Vec<String>,
+    }
+
+    impl Fixture {
+        /// `None` when database tests are skipped.
+        async fn new(name: &str) -> Option<Self> {
+            Some(Self::with_pool(name, test_pool().await?))
+        }
+
+        /// Like [`Fixture::new`], for tests that need their own connection
+        /// settings.
+        fn with_pool(name: &str, pool: PgPool) -> Self {
+            let repository = format!("{name}-fixture-{}", std::process::id());
+            Self {
+                pool,
+                repositories: vec![repository.clone()],
+                repository,
+                blobs: Vec::new(),
+                chunks: Vec::new(),
+                symbol_names: Vec::new(),
             }
         }
-    }
 
-    SearchResultsStats {
+        fn db(&self) -> PostgresDb {
+            PostgresDb::new(self.pool.clone())
+        }
+
+        /// Names another repository, `{repository}-{suffix}`, whose branch
+        /// rows are cleaned up with the fixture's own.
+        fn add_repository(&mut self, suffix: &str) -> String {
+            let repository = format!("{}-{suffix}", self.repository);
+            self.repositories.push(repository.clone());
+            repository
+        }
+
+        /// Points `branch` of the fixture's repository at `abc123`.
+        async fn insert_branch(&self, branch: &str) {
+            sqlx::query(
+                "INSERT INTO branches (repository, branch, commit_sha) VALUES ($1, $2, 'abc123')",
+            )
+            .bind(&self.repository)
+            .bind(branch)
+            .execute(&self.pool)
+            .await
+            .expect("failed to insert branch");
+        }
+
+        /// Inserts `file` with its own content blob, `{repository}:{path}`,
+        /// and returns the blob's hash.
+        async fn insert_file(&mut self, file: TestFile<'_>) -> String {
+            let hash = format!("{}:{}", self.repository, file.path);
+            let byte_len = file.byte_len.unwrap_or_else(|| {
+                file.chunks
+                    .iter()
+                    .map(|chunk| chunk.bytes().len() as i64)
+                    .sum()
+            });
+            let line_count: usize = file.chunks.iter().map(TestChunk::line_count).sum();
+            sqlx::query(
+                "INSERT INTO content_blobs (hash, language, byte_len, line_count)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(&hash)
+            .bind(file.language)
+            .bind(byte_len)
+            .bind(line_count as i32)
+            .execute(&self.pool)
+            .await
+            .expect("failed to insert content blob");
+            self.blobs.push(hash.clone());
+            for (index, chunk) in file.chunks.into_iter().enumerate() {
+                self.insert_chunk(&hash, index as i32, chunk).await;
+            }
+            self.link_file(file.path, &hash).await;
+            hash
+        }
+
+        /// Adds `path` to the fixture's repository as another file backed by
+        /// the existing blob `content_hash`.
+        async fn link_file(&self, path: &str, content_hash: &str) {
+            sqlx::query(
+                "INSERT INTO files (repository, commit_sha, file_path, content_hash)
+                 VALUES ($1, 'abc123', $2, $3)",
+            )
+            .bind(&self.repository)
+            .bind(path)
+            .bind(content_hash)
+            .execute(&self.pool)
+            .await
+            .expect("failed to insert file");
+        }
+
+        /// Stores `chunk` as chunk `index` of `content_hash`, under the chunk
+        /// hash `{content_hash}#{index}`.
+        async fn insert_chunk(
+            &mut self,
+            content_hash: &str,
+            index: i32,
+            chunk: impl Into<TestChunk>,
+        ) {
+            let chunk = chunk.into();
+            let chunk_hash = format!("{content_hash}#{index}");
+            let (text_content, compressed_content) = match &chunk {
+                TestChunk::Text(text) => (text.as_str(), None),
+                TestChunk::Compressed(bytes) => (
+                    "",
+                    Some(
+                        zstd::stream::encode_all(bytes.as_slice(), 0)
+                            .expect("failed to compress chunk"),
+                    ),
+                ),
+            };
+            sqlx::query(
+                "INSERT INTO chunks (chunk_hash, text_content, compressed_content)
+                 VALUES ($1, $2, $3)",
+            )
+            .bind(&chunk_hash)
+            .bind(text_content)
+            .bind(compressed_content)
+            .execute(&self.pool)
+            .await
+            .expect("failed to insert chunk");
+            self.chunks.push(chunk_hash.clone());
+            sqlx::query(
+                "INSERT INTO content_blob_chunks (content_hash, chunk_hash, chunk_index, chunk_line_count)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(content_hash)
+            .bind(&chunk_hash)
+            .bind(index)
+            .bind(chunk.line_count() as i32)
+            .execute(&self.pool)
+            .await
+            .expect("failed to link chunk");
+        }
+
+        /// Records `symbol` in `content_hash`, reusing the symbol row when the
+        /// blob already names it, and returns the symbol's id.
+        async fn insert_symbol(&mut self, content_hash: &str, symbol: TestSymbol<'_>) -> i32 {
+            let symbol_id = sqlx::query_scalar(
+                "WITH symbol AS (
+                    INSERT INTO symbols (content_hash, name, name_lc)
+                    VALUES ($1, $2, lower($2))
+                    ON CONFLICT (content_hash, name) DO UPDATE SET name = EXCLUDED.name
+                    RETURNING id
+                ), namespace AS (
+                    INSERT INTO symbol_namespaces (namespace)
+                    VALUES ($3)
+                    ON CONFLICT (namespace) DO UPDATE SET namespace = EXCLUDED.namespace
+                    RETURNING id
+                ), unique_name AS (
+                    INSERT INTO unique_symbols (name_lc)
+                    VALUES (lower($2))
+                    ON CONFLICT (name_lc) DO NOTHING
+                )
+                INSERT INTO symbol_references (symbol_id, namespace_id, kind, line_number, column_number)
+                SELECT symbol.id, namespace.id, $4, $5, $6 FROM symbol, namespace
+                RETURNING symbol_id",
+            )
+            .bind(content_hash)
+            .bind(symbol.name)
+            .bind(symbol.namespace)
+            .bind(symbol.kind)
+            .bind(symbol.line)
+            .bind(symbol.column)
+            .fetch_one(&self.pool)
+            .await
+            .expect("failed to insert symbol");
+            self.symbol_names.push(symbol.name.to_lowercase());
+            symbol_id
+        }
+
+        /// Ingests `report` the way an upload would, tracking its blobs and
+        /// symbols for cleanup.
+        async fn ingest(&mut self, report: IndexReport) {
+            self.blobs
+                .extend(report.content_blobs.iter().map(|blob| blob.hash.clone()));
+            self.symbol_names.extend(
+                report
+                    .symbol_records
+                    .iter()
+                    .map(|symbol| symbol.name.to_lowercase()),
+            );
+            self.db()
+                .ingest_report(report)
+                .await
+                .expect("ingest failed");
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+            let blobs = std::mem::take(&mut self.blobs);
+            let chunks = std::mem::take(&mut self.chunks);
+            let symbol_names = std::mem::take(&mut self.symbol_names);
+            let repositories = std::mem::take(&mut self.repositories);
+            // The test's runtime is blocked on this destructor, so the cleanup
+            // runs on its own thread, runtime and connection.
+            let cleanup = std::thread::spawn(move || {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("failed to start the cleanup runtime")
+                    .block_on(async move {
+                        use sqlx::Connection;
+
+                        let mut conn = sqlx::PgConnection::connect(&url)
+                            .await
+                            .expect("failed to connect to postgres");
+                        for (sql, keys) in [
+                            ("DELETE FROM content_blobs WHERE hash = ANY($1)", &blobs),
+                            ("DELETE FROM chunks WHERE chunk_hash = ANY($1)", &chunks),
+                            (
+                                "DELETE FROM unique_symbols us
+                                 WHERE us.name_lc = ANY($1)
+                                   AND NOT EXISTS (SELECT 1 FROM symbols s WHERE s.name_lc = us.name_lc)",
+                                &symbol_names,
+                            ),
+                            (
+                                "DELETE FROM branch_policies WHERE repository = ANY($1)",
+                                &repositories,
+                            ),
+                            (
+                                "DELETE FROM branches WHERE repository = ANY($1)",
+                                &repositories,
+                            ),
+                            (
+                                "DELETE FROM commits WHERE repository = ANY($1)",
+                                &repositories,
+                            ),
+                        ] {
+                            sqlx::query(sql)
+                                .bind(keys)
+                                .execute(&mut conn)
+                                .await
+                                .expect("failed to clean up fixture");
+                        }
+                    })
+            });
+            if cleanup.join().is_err() && !std::thread::panicking() {
+                panic!("failed to clean up fixture");
+            }
+        }
+    }
+
+    /// A file for [`Fixture::insert_file`]. Its blob's size and line count
+    /// come from `chunks` unless `byte_len` is set.
+    struct TestFile<'a> {
+        path: &'a str,
+        language: Option<&'a str>,
+        byte_len: Option<i64>,
+        chunks: Vec<TestChunk>,
+    }
+
+    impl<'a> TestFile<'a> {
+        fn new(path: &'a str) -> Self {
+            Self {
+                path,
+                language: Some("rust"),
+                byte_len: None,
+                chunks: Vec::new(),
+            }
+        }
+
+        fn language(mut self, language: Option<&'a str>) -> Self {
+            self.language = language;
+            self
+        }
+
+        fn byte_len(mut self, byte_len: i64) -> Self {
+            self.byte_len = Some(byte_len);
+            self
+        }
+
+        fn chunk(mut self, chunk: impl Into<TestChunk>) -> Self {
+            self.chunks.push(chunk.into());
+            self
+        }
+    }
+
+    /// Chunk content for a [`TestFile`]. Compressed chunks are stored with
+    /// zstd and empty `text_content`, the way the indexer uploads them.
+    enum TestChunk {
+        Text(String),
+        Compressed(Vec<u8>),
+    }
+
+    impl TestChunk {
+        fn bytes(&self) -> &[u8] {
+            match self {
+                TestChunk::Text(text) => text.as_bytes(),
+                TestChunk::Compressed(bytes) => bytes,
+            }
+        }
+
+        fn line_count(&self) -> usize {
+            String::from_utf8_lossy(self.bytes()).lines().count()
+        }
+    }
+
+    impl From<&str> for TestChunk {
+        fn from(text: &str) -> Self {
+            TestChunk::Text(text.to_string())
+        }
+    }
+
+    impl From<String> for TestChunk {
+        fn from(text: String) -> Self {
+            TestChunk::Text(text)
+        }
+    }
+
+    /// A symbol and one reference to it, for [`Fixture::insert_symbol`].
+    struct TestSymbol<'a> {
+        name: &'a str,
+        namespace: &'a str,
+        kind: &'a str,
+        line: i32,
+        column: i32,
+    }
+
+    impl<'a> TestSymbol<'a> {
+        fn new(name: &'a str, kind: &'a str, line: i32) -> Self {
+            Self {
+                name,
+                namespace: "",
+                kind,
+                line,
+                column: 1,
+            }
+        }
+
+        fn namespace(mut self, namespace: &'a str) -> Self {
+            self.namespace = namespace;
+            self
+        }
+
+        fn column(mut self, column: i32) -> Self {
+            self.column = column;
+            self
+        }
+    }
+
+    #[tokio::test]
+    async fn repo_overview_summarises_polyglot_fixture() {
+        let Some(mut fixture) = Fixture::new("overview").await else {
+            return;
+        };
+        for (path, language, byte_len) in [
+            ("Cargo.toml", Some("toml"), 300),
+            ("go.mod", None, 80),
+            ("Dockerfile", Some("dockerfile"), 120),
+            ("README.md", Some("markdown"), 2_000),
+            ("src/main.rs", Some("rust"), 400),
+            ("src/lib.rs", Some("rust"), 9_000),
+            ("cmd/pointer/main.go", Some("go"), 700),
+            ("web/package.json", Some("json"), 500),
+            ("web/index.ts", Some("typescript"), 1_500),
+            ("web/util.ts", Some("typescript"), 600),
+            ("web/app.css", Some("css"), 50),
+        ] {
+            fixture
+                .insert_file(TestFile::new(path).language(language).byte_len(byte_len))
+                .await;
+        }
+
+        let overview = fixture
+            .db()
+            .get_repo_overview(&fixture.repository, "abc123")
+            .await
+            .expect("overview query failed");
+
+        let directories: Vec<_> = overview
+            .directories
+            .iter()
+            .map(|dir| {
+                (
+                    dir.name.as_str(),
+                    dir.file_count,
+                    dir.dominant_language.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            directories,
+            vec![
+                ("", 4, Some("dockerfile")),
+                ("cmd", 1, Some("go")),
+                ("src", 2, Some("rust")),
+                ("web", 4, Some("typescript")),
+            ]
+        );
+        assert_eq!(overview.largest_files[0].path, "src/lib.rs");
+        assert_eq!(overview.largest_files[1].path, "README.md");
+        assert_eq!(
+            overview.entry_points,
+            vec!["cmd/pointer/main.go", "src/main.rs", "web/index.ts"]
+        );
+        let build_files: Vec<_> = overview
+            .build_files
+            .iter()
+            .map(|build| (build.path.as_str(), build.system.as_str()))
+            .collect();
+        assert_eq!(
+            build_files,
+            vec![
+                ("Cargo.toml", "Cargo"),
+                ("Dockerfile", "Docker"),
+                ("go.mod", "Go modules"),
+                ("web/package.json", "npm"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reference_graph_exports_cross_file_edges() {
+        let Some(mut fixture) = Fixture::new("graph").await else {
+            return;
+        };
+        let mut hashes = Vec::new();
+        for path in ["src/a.rs", "src/b.rs", "src/util/c.rs"] {
+            hashes.push(fixture.insert_file(TestFile::new(path)).await);
+        }
+        // (file index, symbol, namespace, kind, line)
+        let references = [
+            (0, "Widget", "app", "definition", 1),
+            (1, "Gadget", "app", "definition", 1),
+            (1, "Widget", "app", "reference", 3),
+            (1, "Widget", "app", "reference", 4),
+            (2, "helper", "app::util", "definition", 1),
+            (0, "helper", "app::util", "reference", 5),
+            (1, "helper", "app::util", "reference", 6),
+        ];
+        for (file, name, namespace, kind, line) in references {
+            fixture
+                .insert_symbol(
+                    &hashes[file],
+                    TestSymbol::new(name, kind, line).namespace(namespace),
+                )
+                .await;
+        }
+
+        let db = fixture.db();
+        let by_file = db
+            .get_reference_graph(&fixture.repository, "abc123", GraphGranularity::File, 10)
+            .await
+            .expect("file graph query failed");
+        let by_namespace = db
+            .get_reference_graph(
+                &fixture.repository,
+                "abc123",
+                GraphGranularity::Namespace,
+                10,
+            )
+            .await
+            .expect("namespace graph query failed");
+
+        let dot = crate::utils::symbol_graph::render_dot(
+            &fixture.repository,
+            "abc123",
+            GraphGranularity::File,
+            &by_file,
+        );
+        for line in [
+            "  \"src/a.rs\";",
+            "  \"src/b.rs\";",
+            "  \"src/util/c.rs\";",
+            "  \"src/b.rs\" -> \"src/a.rs\" [label=\"2\"];",
+            "  \"src/a.rs\" -> \"src/util/c.rs\" [label=\"1\"];",
+            "  \"src/b.rs\" -> \"src/util/c.rs\" [label=\"1\"];",
+        ] {
+            assert!(dot.contains(line), "missing `{line}` in\n{dot}");
+        }
+        assert!(!dot.contains("\"src/a.rs\" -> \"src/a.rs\""));
+
+        assert_eq!(
+            by_namespace,
+            vec![GraphEdge {
+                source: "app".to_string(),
+                target: "app::util".to_string(),
+                weight: 2,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn all_branches_spans_repositories() {
+        let Some(mut fixture) = Fixture::new("branches").await else {
+            return;
+        };
+        let repos = [fixture.add_repository("a"), fixture.add_repository("b")];
+        // (repo index, branch, commit, live)
+        let branches = [
+            (0, "main", "a1", true),
+            (0, "topic", "a2", false),
+            (1, "main", "b1", true),
+        ];
+        for (repo, branch, commit, live) in branches {
+            sqlx::query(
+                "INSERT INTO branches (repository, branch, commit_sha, indexed_at)
+                 VALUES ($1, $2, $3, '2024-01-01T00:00:00Z')",
+            )
+            .bind(&repos[repo])
+            .bind(branch)
+            .bind(commit)
+            .execute(&fixture.pool)
+            .await
+            .expect("failed to insert branch");
+            sqlx::query("INSERT INTO branch_policies (repository, branch) VALUES ($1, $2)")
+                .bind(&repos[repo])
+                .bind(branch)
+                .execute(&fixture.pool)
+                .await
+                .expect("failed to insert branch policy");
+            if live {
+                sqlx::query("INSERT INTO repo_live_branches (repository, branch) VALUES ($1, $2)")
+                    .bind(&repos[repo])
+                    .bind(branch)
+                    .execute(&fixture.pool)
+                    .await
+                    .expect("failed to mark branch live");
+            }
+        }
+        sqlx::query(
+            "INSERT INTO branch_snapshots (repository, branch, commit_sha, indexed_at)
+             VALUES ($1, 'main', 'b1', '2024-02-01T00:00:00Z')",
+        )
+        .bind(&repos[1])
+        .execute(&fixture.pool)
+        .await
+        .expect("failed to insert snapshot");
+
+        let db = fixture.db();
+        let listed = db.get_all_branches(i64::MAX, 0).await;
+        let first = listed
+            .as_ref()
+            .ok()
+            .and_then(|all| all.iter().position(|b| b.repository == repos[0]))
+            .unwrap_or_default();
+        let paged = db.get_all_branches(2, first as i64 + 1).await;
+
+        let listed: Vec<BranchListing> = listed
+            .expect("branch listing failed")
+            .into_iter()
+            .filter(|branch| branch.repository.starts_with(&fixture.repository))
+            .collect();
+        let summary: Vec<_> = listed
+            .iter()
+            .map(|b| {
+                (
+                    b.repository.as_str(),
+                    b.branch.as_str(),
+                    b.commit_sha.as_str(),
+                    b.is_live,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (repos[0].as_str(), "main", "a1", true),
+                (repos[0].as_str(), "topic", "a2", false),
+                (repos[1].as_str(), "main", "b1", true),
+            ]
+        );
+        let indexed_month = |b: &BranchListing| {
+            DateTime::parse_from_rfc3339(b.indexed_at.as_deref().unwrap())
+                .unwrap()
+                .with_timezone(&Utc)
+                .format("%Y-%m")
+                .to_string()
+        };
+        assert_eq!(indexed_month(&listed[0]), "2024-01");
+        // The newest snapshot wins over the branch row's own timestamp.
+        assert_eq!(indexed_month(&listed[2]), "2024-02");
+        assert_eq!(paged.expect("paged listing failed"), listed[1..]);
+    }
+
+    #[tokio::test]
+    async fn file_symbol_completions_stay_inside_the_file() {
+        let Some(mut fixture) = Fixture::new("file-symbols").await else {
+            return;
+        };
+        let repository = fixture.repository.clone();
+        let mut hashes = Vec::new();
+        for path in ["src/parser.rs", "src/lexer.rs"] {
+            hashes.push(fixture.insert_file(TestFile::new(path)).await);
+        }
+        // (file index, name, kind, line)
+        let symbols = [
+            (0, "parse_expr", "definition", 12),
+            (0, "Parser", "definition", 3),
+            (0, "parse_token", "reference", 20),
+            (1, "parse_token", "definition", 7),
+            (1, "Lexer", "definition", 2),
+        ];
+        for (file, name, kind, line) in symbols {
+            fixture
+                .insert_symbol(&hashes[file], TestSymbol::new(name, kind, line))
+                .await;
+        }
+
+        let db = fixture.db();
+        let parse = db
+            .autocomplete_symbols_in_file(&repository, "abc123", "src/parser.rs", "parse", 10)
+            .await;
+        let everything = db
+            .autocomplete_symbols_in_file(&repository, "abc123", "src/parser.rs", "", 10)
+            .await;
+        let lexer_only = db
+            .autocomplete_symbols_in_file(&repository, "abc123", "src/parser.rs", "lex", 10)
+            .await;
+
+        let names = |result: Result<Vec<FileSymbolSuggestion>, DbError>| -> Vec<(String, i32)> {
+            result
+                .expect("file symbol autocomplete failed")
+                .into_iter()
+                .map(|symbol| (symbol.name, symbol.line))
+                .collect()
+        };
+        // The lexer's `parse_token` definition and the parser's reference to
+        // it are both left out.
+        assert_eq!(
+            names(parse),
+            [("Parser".to_string(), 3), ("parse_expr".to_string(), 12)]
+        );
+        assert_eq!(
+            names(everything),
+            [("Parser".to_string(), 3), ("parse_expr".to_string(), 12)]
+        );
+        assert!(names(lexer_only).is_empty());
+    }
+
+    #[tokio::test]
+    async fn symbols_can_be_fetched_by_id_with_their_references() {
+        let Some(mut fixture) = Fixture::new("symbol-by-id").await else {
+            return;
+        };
+        let hash = fixture.insert_file(TestFile::new("src/lib.rs")).await;
+        // (kind, line, column); the reference comes first in the file.
+        let mut symbol_id = 0;
+        for (kind, line, column) in [
+            ("reference", 3, 9),
+            ("definition", 10, 8),
+            ("reference", 21, 5),
+        ] {
+            let symbol = TestSymbol::new("render", kind, line)
+                .namespace("ui")
+                .column(column);
+            symbol_id = fixture.insert_symbol(&hash, symbol).await;
+        }
+
+        let db = fixture.db();
+        let found = db.get_symbol_by_id(symbol_id).await;
+        drop(fixture);
+        let missing = db.get_symbol_by_id(symbol_id).await;
+
+        let symbol = found.expect("symbol lookup by id failed");
+        assert_eq!(symbol.id, symbol_id);
+        assert_eq!(symbol.fully_qualified, "ui::render");
+        assert_eq!(symbol.kind.as_deref(), Some("definition"));
+        assert_eq!(
+            (symbol.file_path.as_str(), symbol.line, symbol.column),
+            ("src/lib.rs", Some(10), Some(8))
+        );
+        let references: Vec<_> = symbol
+            .references
+            .expect("references are always included")
+            .into_iter()
+            .map(|reference| (reference.kind, reference.line, reference.column))
+            .collect();
+        assert_eq!(
+            references,
+            [
+                (Some("reference".to_string()), 3, 9),
+                (Some("definition".to_string()), 10, 8),
+                (Some("reference".to_string()), 21, 5),
+            ]
+        );
+        assert!(matches!(missing, Err(DbError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn symbol_reference_pages_do_not_overlap() {
+        let Some(mut fixture) = Fixture::new("reference-pages").await else {
+            return;
+        };
+        let mut hashes = Vec::new();
+        for path in ["src/a.rs", "src/b.rs"] {
+            hashes.push(fixture.insert_file(TestFile::new(path)).await);
+        }
+        // (file index, kind, line, column); two references of different kinds
+        // share a position.
+        let references = [
+            (1, "reference", 4, 2),
+            (0, "definition", 1, 1),
+            (1, "definition", 4, 2),
+            (0, "reference", 9, 5),
+            (0, "reference", 3, 7),
+        ];
+        for (file, kind, line, column) in references {
+            fixture
+                .insert_symbol(
+                    &hashes[file],
+                    TestSymbol::new("log", kind, line).column(column),
+                )
+                .await;
+        }
+
+        let db = fixture.db();
+        let mut pages = Vec::new();
+        for offset in [0, 2, 4, 6] {
+            pages.push(
+                db.get_symbol_references(SymbolReferenceRequest {
+                    repository: fixture.repository.clone(),
+                    commit_sha: "abc123".to_string(),
+                    fully_qualified: "log".to_string(),
+                    file_path: None,
+                    line: None,
+                    column: None,
+                    limit: Some(2),
+                    offset: Some(offset),
+                })
+                .await,
+            );
+        }
+
+        let pages: Vec<SymbolReferenceResponse> = pages
+            .into_iter()
+            .map(|page| page.expect("reference lookup failed"))
+            .collect();
+        assert_eq!(
+            pages
+                .iter()
+                .map(|page| (page.total_count, page.has_more))
+                .collect::<Vec<_>>(),
+            [(5, true), (5, true), (5, false), (0, false)]
+        );
+        let locations: Vec<(String, i32, i32)> = pages
+            .into_iter()
+            .flat_map(|page| page.references)
+            .map(|reference| (reference.file_path, reference.line, reference.column))
+            .collect();
+        assert_eq!(
+            locations,
+            [
+                ("src/a.rs".to_string(), 1, 1),
+                ("src/a.rs".to_string(), 3, 7),
+                ("src/a.rs".to_string(), 9, 5),
+                ("src/b.rs".to_string(), 4, 2),
+                ("src/b.rs".to_string(), 4, 2),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn java_reference_resolves_to_kotlin_definition_in_same_package() {
+        let Some(mut fixture) = Fixture::new("jvm-namespaces").await else {
+            return;
+        };
+        let cart = fixture
+            .insert_file(
+                TestFile::new("src/main/java/com/acme/shop/Cart.kt").language(Some("kotlin")),
+            )
+            .await;
+        let checkout = fixture
+            .insert_file(
+                TestFile::new("src/main/java/com/acme/shop/Checkout.java").language(Some("java")),
+            )
+            .await;
+        // What the Kotlin and Java extractors emit for `class Cart` and for
+        // `Cart cart = new Cart("ada");` inside `Checkout.run`.
+        fixture
+            .insert_symbol(
+                &cart,
+                TestSymbol::new("Cart", "definition", 3)
+                    .namespace("com::acme::shop")
+                    .column(7),
+            )
+            .await;
+        for column in [9, 25] {
+            fixture
+                .insert_symbol(
+                    &checkout,
+                    TestSymbol::new("Cart", "reference", 5)
+                        .namespace("com::acme::shop::Checkout::run")
+                        .column(column),
+                )
+                .await;
+        }
+        fixture
+            .insert_symbol(
+                &checkout,
+                TestSymbol::new("Cart", "reference", 9).namespace("org::other"),
+            )
+            .await;
+
+        let response = fixture
+            .db()
+            .get_symbol_references(SymbolReferenceRequest {
+                repository: fixture.repository.clone(),
+                commit_sha: "abc123".to_string(),
+                fully_qualified: "com::acme::shop::Cart".to_string(),
+                file_path: None,
+                line: None,
+                column: None,
+                limit: None,
+                offset: None,
+            })
+            .await
+            .expect("reference lookup failed");
+
+        let found: Vec<(&str, Option<&str>, i32, i32)> = response
+            .references
+            .iter()
+            .map(|reference| {
+                (
+                    reference.file_path.as_str(),
+                    reference.kind.as_deref(),
+                    reference.line,
+                    reference.column,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "src/main/java/com/acme/shop/Cart.kt",
+                    Some("definition"),
+                    3,
+                    7
+                ),
+                (
+                    "src/main/java/com/acme/shop/Checkout.java",
+                    Some("reference"),
+                    5,
+                    9
+                ),
+                (
+                    "src/main/java/com/acme/shop/Checkout.java",
+                    Some("reference"),
+                    5,
+                    25
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn batched_definitions_match_individual_lookups() {
+        let Some(mut fixture) = Fixture::new("definitions").await else {
+            return;
+        };
+        let mut hashes = Vec::new();
+        for path in ["src/a.rs", "src/b.rs", "src/util.rs"] {
+            hashes.push(fixture.insert_file(TestFile::new(path)).await);
+        }
+        // (file index, symbol, namespace, kind, line)
+        let references = [
+            (0, "Widget", "app", "definition", 1),
+            (1, "Widget", "app", "definition", 7),
+            (1, "Widget", "app", "reference", 9),
+            (2, "helper", "app::util", "definition", 3),
+            (0, "helper", "app::util", "reference", 5),
+            (2, "helper", "other", "definition", 12),
+        ];
+        for (file, name, namespace, kind, line) in references {
+            fixture
+                .insert_symbol(
+                    &hashes[file],
+                    TestSymbol::new(name, kind, line).namespace(namespace),
+                )
+                .await;
+        }
+
+        let db = fixture.db();
+        let lookups: Vec<DefinitionLookup> = [
+            "app::Widget",
+            "app::util::helper",
+            "helper",
+            "app::Missing",
+            "app::Widget",
+        ]
+        .into_iter()
+        .map(|fully_qualified| DefinitionLookup {
+            repository: fixture.repository.clone(),
+            commit_sha: "abc123".to_string(),
+            fully_qualified: fully_qualified.to_string(),
+        })
+        .collect();
+        let batched = db.get_definitions_batch(lookups.clone()).await;
+        let mut individual = Vec::new();
+        for lookup in &lookups {
+            individual.push(
+                db.get_symbol_references(SymbolReferenceRequest {
+                    repository: lookup.repository.clone(),
+                    commit_sha: lookup.commit_sha.clone(),
+                    fully_qualified: lookup.fully_qualified.clone(),
+                    file_path: None,
+                    line: None,
+                    column: None,
+                    limit: None,
+                    offset: None,
+                })
+                .await,
+            );
+        }
+
+        let locations = |references: &[FileReference]| -> Vec<(String, Option<String>, i32)> {
+            references
+                .iter()
+                .filter(|reference| reference.kind.as_deref() == Some("definition"))
+                .map(|reference| {
+                    (
+                        reference.file_path.clone(),
+                        reference.namespace.clone(),
+                        reference.line,
+                    )
+                })
+                .collect()
+        };
+        let batched = batched.expect("batched lookup failed");
+        assert_eq!(batched.len(), lookups.len());
+        for ((result, lookup), single) in batched.iter().zip(&lookups).zip(individual) {
+            assert_eq!(&result.lookup, lookup);
+            let single = single.expect("individual lookup failed");
+            assert_eq!(
+                locations(&result.definitions),
+                locations(&single.references),
+                "{}",
+                lookup.fully_qualified
+            );
+        }
+        assert_eq!(batched[0].definitions.len(), 2);
+        assert_eq!(batched[2].definitions.len(), 2);
+        assert!(batched[3].definitions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn name_contains_matches_inside_identifiers() {
+        let Some(mut fixture) = Fixture::new("name-contains").await else {
+            return;
+        };
+        let hash = fixture.insert_file(TestFile::new("src/lib.rs")).await;
+        for (line, name) in [
+            (1, "reparse"),
+            (2, "can_parse"),
+            (3, "parse_query"),
+            (4, "render"),
+        ] {
+            fixture
+                .insert_symbol(&hash, TestSymbol::new(name, "definition", line))
+                .await;
+        }
+
+        let request = SearchRequest {
+            q: None,
+            name: None,
+            name_regex: None,
+            name_contains: Some("PARSE".to_string()),
+            namespace: None,
+            namespace_prefix: None,
+            kind: None,
+            language: None,
+            repository: Some(fixture.repository.clone()),
+            commit_sha: Some("abc123".to_string()),
+            path: None,
+            path_regex: None,
+            path_hint: None,
+            path_depth: None,
+            include_paths: Vec::new(),
+            excluded_paths: Vec::new(),
+            include_references: None,
+            limit: None,
+            unreferenced: false,
+            group_by_name: false,
+        };
+        let db = fixture.db();
+        let response = db.search_symbols(request.clone()).await;
+        let fuzzy_response = db
+            .search_symbols(SearchRequest {
+                name_contains: Some("rendr".to_string()),
+                ..request
+            })
+            .await;
+
+        let names: Vec<String> = response
+            .expect("symbol search failed")
+            .symbols
+            .into_iter()
+            .map(|symbol| symbol.symbol)
+            .collect();
+        // The prefix match ranks first even though it sorts last by name, and
+        // `can_parse` shares more trigrams with the needle than `reparse`.
+        assert_eq!(names, vec!["parse_query", "can_parse", "reparse"]);
+
+        let names: Vec<String> = fuzzy_response
+            .expect("symbol search failed")
+            .symbols
+            .into_iter()
+            .map(|symbol| symbol.symbol)
+            .collect();
+        assert_eq!(names, vec!["render"]);
+    }
+
+    #[tokio::test]
+    async fn dsl_count_caps_distinct_symbol_names() {
+        let Some(mut fixture) = Fixture::new("symbol-count").await else {
+            return;
+        };
+        // Every name is defined in three files, so rows outnumber names.
+        for path in ["src/a.rs", "src/b.rs", "src/c.rs"] {
+            let hash = fixture.insert_file(TestFile::new(path)).await;
+            for (line, name) in [(1, "parse_one"), (2, "parse_two"), (3, "parse_three")] {
+                fixture
+                    .insert_symbol(&hash, TestSymbol::new(name, "definition", line))
+                    .await;
+            }
+        }
+
+        let query = format!("repo:{} name_contains:parse count:2", fixture.repository);
+        let request = TextSearchRequest::from_query_str(&query)
+            .expect("query should plan")
+            .symbol_search_request()
+            .expect("query should map to a symbol search");
+        assert_eq!(request.limit, Some(2));
+        assert!(request.group_by_name);
+
+        let db = fixture.db();
+        let grouped = db
+            .search_symbols(request.clone())
+            .await
+            .expect("symbol search failed");
+        let ungrouped = db
+            .search_symbols(SearchRequest {
+                group_by_name: false,
+                ..request
+            })
+            .await
+            .expect("symbol search failed");
+
+        let names: HashSet<String> = grouped
+            .symbols
+            .iter()
+            .map(|symbol| symbol.symbol.clone())
+            .collect();
+        assert_eq!(grouped.symbols.len(), 2);
+        assert_eq!(names.len(), 2);
+        assert_eq!(grouped.total, 3);
+
+        assert_eq!(ungrouped.symbols.len(), 2);
+        assert_eq!(ungrouped.total, 9);
+    }
+
+    #[tokio::test]
+    async fn unreferenced_search_returns_only_unused_definitions() {
+        let Some(mut fixture) = Fixture::new("unreferenced").await else {
+            return;
+        };
+        let lib = fixture.insert_file(TestFile::new("src/lib.rs")).await;
+        let main = fixture.insert_file(TestFile::new("src/main.rs")).await;
+        // `used_fn` is called from the other file; `dead_fn` never is.
+        let symbols = [
+            (&lib, "used_fn", "definition"),
+            (&lib, "dead_fn", "definition"),
+            (&main, "used_fn", "reference"),
+        ];
+        for (line, (hash, name, kind)) in symbols.into_iter().enumerate() {
+            fixture
+                .insert_symbol(hash, TestSymbol::new(name, kind, line as i32 + 1))
+                .await;
+        }
+
+        let db = fixture.db();
+        let mut request = symbol_request(None, Vec::new());
+        request.name = None;
+        request.repository = Some(fixture.repository.clone());
+        request.unreferenced = true;
+        let response = db.search_symbols(request.clone()).await;
+        request.repository = None;
+        let unscoped = db.search_symbols(request).await;
+
+        let names: Vec<String> = response
+            .expect("symbol search failed")
+            .symbols
+            .into_iter()
+            .map(|symbol| symbol.symbol)
+            .collect();
+        assert_eq!(names, vec!["dead_fn"]);
+        assert!(unscoped.is_err(), "refs:0 needs a repository");
+    }
+
+    fn vendored_dirs() -> Vec<String> {
+        vec!["node_modules".to_string(), "vendor".to_string()]
+    }
+
+    #[test]
+    fn vendored_dirs_are_excluded_by_default() {
+        let request = TextSearchRequest::from_query_str("needle").unwrap();
+        let request = apply_default_excludes(&request, &vendored_dirs());
+        let plan = &request.plans[0];
+
+        for pattern in [
+            "node\\_modules/%",
+            "%/node\\_modules/%",
+            "vendor/%",
+            "%/vendor/%",
+        ] {
+            assert!(
+                plan.excluded_file_globs.iter().any(|glob| glob == pattern),
+                "missing {pattern} in {:?}",
+                plan.excluded_file_globs
+            );
+        }
+        assert!(build_phase1_sql(&request).contains("NOT ILIKE"));
+    }
+
+    #[test]
+    fn explicit_path_filter_returns_vendored_results() {
+        let request = TextSearchRequest::from_query_str("needle path:node_modules/*").unwrap();
+        let request = apply_default_excludes(&request, &vendored_dirs());
+        let plan = &request.plans[0];
+
+        assert!(
+            plan.excluded_file_globs
+                .iter()
+                .all(|glob| !glob.contains("node\\_modules"))
+        );
+        assert!(
+            plan.excluded_file_globs
+                .iter()
+                .any(|glob| glob == "vendor/%")
+        );
+    }
+
+    #[test]
+    fn symbol_path_filters_suppress_matching_default_excludes() {
+        let dirs = vendored_dirs();
+        assert_eq!(default_exclude_patterns(&dirs, &[]).len(), 4);
+        assert_eq!(
+            default_exclude_patterns(&dirs, &["web/node_modules/react/"]),
+            vec!["vendor/%".to_string(), "%/vendor/%".to_string()]
+        );
+        assert!(default_exclude_patterns(&dirs, &["^vendor/", "node_modules"]).is_empty());
+        assert_eq!(default_exclude_patterns(&dirs, &["my_vendor/"]).len(), 4);
+    }
+
+    #[test]
+    fn plain_search_keeps_default_phase1_budgets() {
+        let request = TextSearchRequest::from_query_str("polly").unwrap();
+        let budgets = compute_search_budgets(&request);
+
+        assert_eq!(
+            budgets,
+            SearchBudgets {
+                fetch_limit: 301,
+                file_limit: 301,
+                plan_row_limit: DEFAULT_PLAN_ROW_LIMIT,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn explain_search_returns_a_parseable_plan() {
+        let request = TextSearchRequest::from_query_str("repo:pointer lang:rust parse_query")
+            .expect("sample query should parse");
+
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let report = PostgresDb::new(pool)
+            .explain_search(&request)
+            .await
+            .expect("explain failed");
+
+        assert_eq!(report.plans.len(), 1);
+        assert_eq!(report.plans[0].name, "text_search");
+        let root = &report.plans[0].plan[0]["Plan"];
+        assert!(root["Node Type"].is_string(), "unexpected plan: {root}");
+        assert!(root["Total Cost"].as_f64().is_some());
+    }
+
+    #[tokio::test]
+    async fn text_search_filters_to_listed_languages() {
+        let Some(mut fixture) = Fixture::new("lang-filter").await else {
+            return;
+        };
+        let needle = format!("langfixture{}", std::process::id());
+        fixture.insert_branch("main").await;
+        for (path, language) in [
+            ("src/lib.rs", "rust"),
+            ("cmd/main.go", "go"),
+            ("tools/gen.py", "python"),
+        ] {
+            let file = TestFile::new(path)
+                .language(Some(language))
+                .chunk(format!("let {needle} = 1;\n"));
+            fixture.insert_file(file).await;
+        }
+
+        let repository = &fixture.repository;
+        let request =
+            TextSearchRequest::from_query_str(&format!("repo:{repository} lang:rust,go {needle}"))
+                .expect("query should plan");
+        let db = fixture.db();
+        let page = db.text_search(&request).await;
+        let excluded_request = TextSearchRequest::from_query_str(&format!(
+            "repo:{repository} -lang:rust -lang:Go {needle}"
+        ))
+        .expect("query should plan");
+        let excluded_page = db.text_search(&excluded_request).await;
+
+        let mut paths: Vec<String> = page
+            .expect("text search failed")
+            .results
+            .into_iter()
+            .map(|result| result.file_path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, ["cmd/main.go", "src/lib.rs"]);
+
+        let paths: Vec<String> = excluded_page
+            .expect("text search failed")
+            .results
+            .into_iter()
+            .map(|result| result.file_path)
+            .collect();
+        assert_eq!(paths, ["tools/gen.py"]);
+    }
+
+    #[tokio::test]
+    async fn text_search_filters_by_path_depth() {
+        let Some(mut fixture) = Fixture::new("path-depth").await else {
+            return;
+        };
+        let repository = fixture.repository.clone();
+        let needle = format!("depthfixture{}", std::process::id());
+        fixture.insert_branch("main").await;
+        for path in ["build.rs", "src/lib.rs", "src/db/postgres.rs"] {
+            fixture
+                .insert_file(TestFile::new(path).chunk(format!("let {needle} = 1;\n")))
+                .await;
+        }
+
+        let request = TextSearchRequest::from_query_str(&format!(
+            "repo:{repository} path_depth:<=2 {needle}"
+        ))
+        .expect("query should plan");
+        let db = fixture.db();
+        let page = db.text_search(&request).await;
+        let deep_request =
+            TextSearchRequest::from_query_str(&format!("repo:{repository} path_depth:>2 {needle}"))
+                .expect("query should plan");
+        let deep_page = db.text_search(&deep_request).await;
+
+        let mut paths: Vec<String> = page
+            .expect("text search failed")
+            .results
+            .into_iter()
+            .map(|result| result.file_path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, ["build.rs", "src/lib.rs"]);
+
+        let paths: Vec<String> = deep_page
+            .expect("text search failed")
+            .results
+            .into_iter()
+            .map(|result| result.file_path)
+            .collect();
+        assert_eq!(paths, ["src/db/postgres.rs"]);
+    }
+
+    #[tokio::test]
+    async fn fuzzy_search_matches_and_highlights_identifier_variants() {
+        let Some(mut fixture) = Fixture::new("fuzzy").await else {
+            return;
+        };
+        let repository = fixture.repository.clone();
+        let pid = std::process::id();
+        let snake = format!("parse_query_fixture{pid}");
+        fixture.insert_branch("main").await;
+        fixture
+            .insert_file(TestFile::new("src/lib.rs").chunk(format!("fn {snake}() {{}}\n")))
+            .await;
+
+        let db = fixture.db();
+        let exact = TextSearchRequest::from_query_str(&format!(
+            "repo:{repository} content:parseQueryFixture{pid}"
+        ))
+        .expect("query should plan");
+        let exact_page = db.text_search(&exact).await;
+        let fuzzy = TextSearchRequest::from_query_str(&format!(
+            "repo:{repository} content:parseQueryFixture{pid} fuzzy:yes"
+        ))
+        .expect("query should plan");
+        let fuzzy_page = db.text_search(&fuzzy).await;
+
+        assert!(exact_page.expect("text search failed").results.is_empty());
+        let results = fuzzy_page.expect("text search failed").results;
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.file_path, "src/lib.rs");
+        let highlighted: Vec<&str> = result
+            .match_spans
+            .iter()
+            .map(|span| &result.content_text[span.start..span.end])
+            .collect();
+        assert_eq!(highlighted, [snake.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn word_search_skips_substring_matches() {
+        let Some(mut fixture) = Fixture::new("word").await else {
+            return;
+        };
+        let repository = fixture.repository.clone();
+        let needle = format!("get{}", std::process::id());
+        fixture.insert_branch("main").await;
+        for (path, text) in [
+            ("src/call.rs", format!("let value = {needle}(cache);\n")),
+            ("src/forget.rs", format!("fn for{needle}() {{}}\n")),
+        ] {
+            fixture.insert_file(TestFile::new(path).chunk(text)).await;
+        }
+
+        let db = fixture.db();
+        let substring =
+            TextSearchRequest::from_query_str(&format!("repo:{repository} content:{needle}"))
+                .expect("query should plan");
+        let substring_page = db.text_search(&substring).await;
+        let word = TextSearchRequest::from_query_str(&format!(
+            "repo:{repository} content:{needle} word:yes"
+        ))
+        .expect("query should plan");
+        let word_page = db.text_search(&word).await;
+
+        let mut substring_paths: Vec<String> = substring_page
+            .expect("text search failed")
+            .results
+            .into_iter()
+            .map(|result| result.file_path)
+            .collect();
+        substring_paths.sort();
+        assert_eq!(substring_paths, ["src/call.rs", "src/forget.rs"]);
+
+        let results = word_page.expect("text search failed").results;
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.file_path, "src/call.rs");
+        let highlighted: Vec<&str> = result
+            .match_spans
+            .iter()
+            .map(|span| &result.content_text[span.start..span.end])
+            .collect();
+        assert_eq!(highlighted, [needle.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn lowercase_content_search_matches_ilike_and_uses_its_index() {
+        let Some(url) = database_url() else {
+            return;
+        };
+        // With only bitmap scans allowed, the trigram index is the planner's
+        // cheapest start even on near-empty fixture tables.
+        let options = url
+            .parse::<sqlx::postgres::PgConnectOptions>()
+            .unwrap()
+            .options([("enable_seqscan", "off"), ("enable_indexscan", "off")]);
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .connect_with(options)
+            .await
+            .expect("failed to connect to postgres");
+        // The index is opt-in; build it as the backend does with the flag set.
+        sqlx::raw_sql(include_str!(
+            "../../backend/deferred_migrations/chunks_lower_content_trgm.sql"
+        ))
+        .execute(&pool)
+        .await
+        .expect("failed to build the lowercase content index");
+        let mut fixture = Fixture::with_pool("lowercase", pool);
+        let pid = std::process::id();
+        let needle = format!("lowerneedle{pid}");
+        fixture.insert_branch("main").await;
+        for (path, text) in [
+            (
+                "src/upper.rs",
+                format!("const {}: u8 = 1;\n", needle.to_uppercase()),
+            ),
+            ("src/mixed.rs", format!("fn Lower{}() {{}}\n", &needle[5..])),
+            ("src/other.rs", "fn unrelated() {}\n".to_string()),
+        ] {
+            fixture.insert_file(TestFile::new(path).chunk(text)).await;
+        }
+
+        // The needle is unique to this run, so the query can leave out
+        // `repo:`; a repository filter would start the plan from files
+        // instead of the chunk index.
+        let request = TextSearchRequest::from_query_str(&format!("content:{needle}"))
+            .expect("query should plan");
+        let ilike_page = fixture.db().text_search(&request).await;
+        let lowered_db = fixture.db().with_lowercase_content(true);
+        let lowered_page = lowered_db.text_search(&request).await;
+        let report = lowered_db.explain_search(&request).await;
+
+        let matches = |page: Result<SearchResultsPage, DbError>| {
+            let mut matches: Vec<(String, String)> = page
+                .expect("text search failed")
+                .results
+                .into_iter()
+                .map(|result| (result.file_path, result.content_text))
+                .collect();
+            matches.sort();
+            matches
+        };
+        let ilike = matches(ilike_page);
+        assert_eq!(
+            ilike
+                .iter()
+                .map(|(path, _)| path.as_str())
+                .collect::<Vec<_>>(),
+            ["src/mixed.rs", "src/upper.rs"]
+        );
+        assert_eq!(matches(lowered_page), ilike);
+
+        let report = report.expect("explain failed");
+        let plan = serde_json::to_string(&report.plans[0].plan).unwrap();
+        assert!(
+            plan.contains("idx_chunks_text_content_lower_trgm"),
+            "lowered search should use the expression index: {plan}"
+        );
+    }
+
+    #[tokio::test]
+    async fn text_search_estimates_total_matching_files() {
+        let Some(mut fixture) = Fixture::new("total-estimate").await else {
+            return;
+        };
+        let needle = format!("estimatefixture{}", std::process::id());
+        fixture.insert_branch("main").await;
+        for idx in 0..25 {
+            // Two matching lines per file must still count the file once.
+            let path = format!("src/file{idx}.rs");
+            let file =
+                TestFile::new(&path).chunk(format!("let {needle} = 1;\nlet {needle}_again = 2;\n"));
+            fixture.insert_file(file).await;
+        }
+
+        let request = TextSearchRequest::from_query_str_with_page(
+            &format!("repo:{} {needle}", fixture.repository),
+            1,
+            10,
+        )
+        .expect("query should plan");
+        let page = fixture.db().text_search(&request).await;
+
+        let page = page.expect("text search failed");
+        assert_eq!(page.results.len(), 10);
+        assert!(page.has_more);
+        assert_eq!(page.total_estimate, Some(25));
+    }
+
+    #[tokio::test]
+    async fn text_search_symbol_filter_keeps_files_naming_the_symbol() {
+        let Some(mut fixture) = Fixture::new("symbol-filter").await else {
+            return;
+        };
+        let symbol = format!("symbolfixture{}", std::process::id());
+        fixture.insert_branch("main").await;
+        // Both files mention the name, but only the first was indexed with it
+        // as a symbol; the second has it inside a string literal.
+        let parser = fixture
+            .insert_file(TestFile::new("src/parser.rs").chunk(format!("fn {symbol}() {{}}\n")))
+            .await;
+        fixture
+            .insert_symbol(&parser, TestSymbol::new(&symbol, "definition", 1))
+            .await;
+        fixture
+            .insert_file(
+                TestFile::new("src/docs.rs").chunk(format!("const DOC: &str = \"{symbol}\";\n")),
+            )
+            .await;
+
+        let request = TextSearchRequest::from_query_str(&format!(
+            "repo:{} symbol:{symbol} lang:rust",
+            fixture.repository
+        ))
+        .expect("query should plan");
+        let page = fixture.db().text_search(&request).await;
+
+        let results = page.expect("text search failed").results;
+        let paths: Vec<&str> = results
+            .iter()
+            .map(|result| result.file_path.as_str())
+            .collect();
+        assert_eq!(paths, ["src/parser.rs"]);
+    }
+
+    #[tokio::test]
+    async fn search_in_file_finds_every_occurrence() {
+        let Some(mut fixture) = Fixture::new("in-file").await else {
+            return;
+        };
+        // The file is split mid-way so matches come from reassembled content.
+        let file = TestFile::new("src/lib.rs")
+            .chunk("fn needle() {}\nlet x = needle();\n")
+            .chunk("// Needle again: needle\n");
+        fixture.insert_file(file).await;
+
+        let db = fixture.db();
+        let find = |pattern: &'static str, case| {
+            let db = db.clone();
+            let repository = fixture.repository.clone();
+            async move {
+                db.search_in_file(&repository, "abc123", "src/lib.rs", pattern, case)
+                    .await
+            }
+        };
+        let insensitive = find("needle", CaseSensitivity::No).await;
+        let auto = find("Needle", CaseSensitivity::Auto).await;
+
+        let positions = |matches: Vec<InFileMatch>| -> Vec<(u32, usize, usize)> {
+            matches
+                .into_iter()
+                .flat_map(|m| {
+                    m.spans
+                        .into_iter()
+                        .map(move |span| (m.line, span.start, span.end))
+                })
+                .collect()
+        };
+        assert_eq!(
+            positions(insensitive.expect("case-insensitive find failed")),
+            [(1, 3, 9), (2, 8, 14), (3, 3, 9), (3, 17, 23)]
+        );
+        assert_eq!(
+            positions(auto.expect("case-sensitive find failed")),
+            [(3, 3, 9)]
+        );
+    }
+
+    #[tokio::test]
+    async fn compressed_chunks_are_served_by_file_views_and_snippets() {
+        let Some(mut fixture) = Fixture::new("compressed").await else {
+            return;
+        };
+        // Only the first chunk is compressed, so files mixing both forms are
+        // reassembled in order.
+        let file = TestFile::new("src/lib.rs")
+            .chunk(TestChunk::Compressed(
+                b"fn one() {}\nfn two() {}\n".to_vec(),
+            ))
+            .chunk("fn three() {}\nfn four() {}\n");
+        fixture.insert_file(file).await;
+
+        let db = fixture.db();
+        let content = db
+            .get_file_content(&fixture.repository, "abc123", "src/lib.rs")
+            .await;
+        let snippet = db
+            .get_file_snippet(SnippetRequest {
+                repository: fixture.repository.clone(),
+                commit_sha: "abc123".to_string(),
+                file_path: "src/lib.rs".to_string(),
+                line: 2,
+                column: None,
+                context: Some(1),
+                highlight: None,
+                case_sensitive: None,
+            })
+            .await;
+
+        assert_eq!(
+            content.expect("file content failed").content,
+            "fn one() {}\nfn two() {}\nfn three() {}\nfn four() {}\n"
+        );
+        assert_eq!(
+            snippet.expect("snippet failed").lines,
+            ["fn one() {}", "fn two() {}", "fn three() {}"]
+        );
+    }
+
+    #[tokio::test]
+    async fn raw_file_bytes_keep_invalid_utf8_intact() {
+        let Some(mut fixture) = Fixture::new("raw-bytes").await else {
+            return;
+        };
+        let repository = fixture.repository.clone();
+        // Chunk text is stored as TEXT, so non-UTF-8 bytes only survive in
+        // compressed chunks.
+        let files: [(&str, &[u8]); 2] = [
+            ("notes/latin1.txt", b"caf\xe9\n"),
+            ("assets/logo.png", b"\x89PNG\r\n\x00\xff"),
+        ];
+        for (path, bytes) in files {
+            let file = TestFile::new(path)
+                .language(Some("text"))
+                .chunk(TestChunk::Compressed(bytes.to_vec()));
+            fixture.insert_file(file).await;
+        }
+
+        let db = fixture.db();
+        let text_bytes = db
+            .get_raw_file_bytes(&repository, "abc123", "notes/latin1.txt")
+            .await;
+        let text_content = db
+            .get_file_content(&repository, "abc123", "notes/latin1.txt")
+            .await;
+        let binary_bytes = db
+            .get_raw_file_bytes(&repository, "abc123", "assets/logo.png")
+            .await;
+        let binary_content = db
+            .get_file_content(&repository, "abc123", "assets/logo.png")
+            .await;
+        let missing = db
+            .get_raw_file_bytes(&repository, "abc123", "assets/gone.png")
+            .await;
+
+        let text_bytes = text_bytes.expect("raw bytes failed");
+        assert_eq!(text_bytes.bytes, files[0].1);
+        assert!(!text_bytes.is_binary);
+        let text_content = text_content.expect("file content failed");
+        assert_eq!(text_content.content, "caf\u{FFFD}\n");
+        assert_eq!(text_content.language.as_deref(), Some("text"));
+        assert!(!text_content.is_binary);
+
+        let binary_bytes = binary_bytes.expect("raw bytes failed");
+        assert_eq!(binary_bytes.bytes, files[1].1);
+        assert!(binary_bytes.is_binary);
+        let binary_content = binary_content.expect("file content failed");
+        assert!(binary_content.is_binary);
+        assert!(binary_content.content.is_empty());
+        assert_eq!(binary_content.language, None);
+        assert!(matches!(missing, Err(DbError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn file_content_batches_keep_request_order_and_share_blobs() {
+        let Some(mut fixture) = Fixture::new("file-batch").await else {
+            return;
+        };
+        // The shared blob spans two chunks so reassembly order matters.
+        let shared = TestFile::new("src/main.rs")
+            .chunk("fn main() {\n")
+            .chunk("}\n");
+        let shared_hash = fixture.insert_file(shared).await;
+        fixture.link_file("bin/copy.rs", &shared_hash).await;
+        let readme = TestFile::new("README.md")
+            .language(Some("markdown"))
+            .chunk("# Readme\n");
+        fixture.insert_file(readme).await;
+
+        let request = |path: &str| FileContentRequest {
+            repository: fixture.repository.clone(),
+            commit_sha: "abc123".to_string(),
+            file_path: path.to_string(),
+        };
+        let db = fixture.db();
+        let batch = db
+            .get_file_contents(vec![
+                request("bin/copy.rs"),
+                request("README.md"),
+                request("src/main.rs"),
+            ])
+            .await;
+        let with_missing = db
+            .get_file_contents(vec![request("README.md"), request("src/gone.rs")])
+            .await;
+
+        let batch: Vec<(String, String, Option<String>)> = batch
+            .expect("batch failed")
+            .into_iter()
+            .map(|file| (file.file_path, file.content, file.language))
+            .collect();
+        let rust = Some("rust".to_string());
+        assert_eq!(
+            batch,
+            [
+                (
+                    "bin/copy.rs".to_string(),
+                    "fn main() {\n}\n".to_string(),
+                    rust.clone()
+                ),
+                (
+                    "README.md".to_string(),
+                    "# Readme\n".to_string(),
+                    Some("markdown".to_string())
+                ),
+                (
+                    "src/main.rs".to_string(),
+                    "fn main() {\n}\n".to_string(),
+                    rust
+                ),
+            ]
+        );
+        assert!(matches!(
+            with_missing,
+            Err(DbError::Internal(message)) if message.contains("src/gone.rs")
+        ));
+    }
+
+    #[tokio::test]
+    async fn large_files_reassemble_chunks_in_order() {
+        let Some(mut fixture) = Fixture::new("large-file").await else {
+            return;
+        };
+        let chunks: Vec<String> = (0..500).map(|index| format!("line {index:04}\n")).collect();
+        let expected: String = chunks.concat();
+
+        let file = TestFile::new("gen/large.txt")
+            .language(Some("text"))
+            .byte_len(expected.len() as i64);
+        let content_hash = fixture.insert_file(file).await;
+        // Link chunks newest-first so the result only comes out right if the
+        // read orders by chunk_index.
+        for (index, text) in chunks.iter().enumerate().rev() {
+            fixture
+                .insert_chunk(&content_hash, index as i32, text.as_str())
+                .await;
+        }
+
+        let raw = fixture
+            .db()
+            .get_raw_file_bytes(&fixture.repository, "abc123", "gen/large.txt")
+            .await;
+
+        let raw = raw.expect("failed to load large file");
+        assert!(!raw.content_skipped);
+        assert_eq!(raw.bytes.len(), expected.len());
+        assert_eq!(raw.bytes, expected.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn symbol_search_surfaces_definition_signatures() {
+        let Some(mut fixture) = Fixture::new("signature").await else {
+            return;
+        };
+        let repository = fixture.repository.clone();
+        let hash = format!("{repository}:src/lib.rs");
+        let reference =
+            |name: &str, kind: &str, line: usize, signature: Option<&str>| ReferenceRecord {
+                content_hash: hash.clone(),
+                namespace: None,
+                name: name.to_string(),
+                fully_qualified: name.to_string(),
+                kind: Some(kind.to_string()),
+                line,
+                column: 8,
+                end_line: line,
+                end_column: 8 + name.len(),
+                signature: signature.map(str::to_string),
+            };
+        let report = IndexReport {
+            content_blobs: vec![ContentBlob {
+                hash: hash.clone(),
+                language: Some("rust".to_string()),
+                byte_len: 1,
+                line_count: 8,
+            }],
+            symbol_records: ["is_big", "Widget"]
+                .into_iter()
+                .map(|name| SymbolRecord {
+                    content_hash: hash.clone(),
+                    name: name.to_string(),
+                })
+                .collect(),
+            file_pointers: vec![FilePointer {
+                repository: repository.clone(),
+                commit_sha: "abc123".to_string(),
+                file_path: "src/lib.rs".to_string(),
+                content_hash: hash.clone(),
+                kind: FileKind::Regular,
+                mode: None,
+            }],
+            reference_records: vec![
+                reference(
+                    "is_big",
+                    "definition",
+                    1,
+                    Some("pub fn is_big(a: u32) -> bool"),
+                ),
+                reference("is_big", "reference", 6, None),
+                reference("Widget", "definition", 3, None),
+            ],
+            ..IndexReport::default()
+        };
+        fixture.ingest(report).await;
+        let db = fixture.db();
+
+        let request = |name: &str| SearchRequest {
+            q: None,
+            name: Some(name.to_string()),
+            name_regex: None,
+            name_contains: None,
+            namespace: None,
+            namespace_prefix: None,
+            kind: Some(vec!["definition".to_string()]),
+            language: None,
+            repository: Some(repository.clone()),
+            commit_sha: Some("abc123".to_string()),
+            path: None,
+            path_regex: None,
+            path_hint: None,
+            path_depth: None,
+            include_paths: Vec::new(),
+            excluded_paths: Vec::new(),
+            include_references: None,
+            limit: None,
+            unreferenced: false,
+            group_by_name: false,
+        };
+        let function = db.search_symbols(request("is_big")).await;
+        let structure = db.search_symbols(request("Widget")).await;
+
+        let signatures: Vec<(String, Option<String>)> = function
+            .expect("symbol search failed")
+            .symbols
+            .into_iter()
+            .chain(structure.expect("symbol search failed").symbols)
+            .map(|symbol| (symbol.symbol, symbol.signature))
+            .collect();
+        assert_eq!(
+            signatures,
+            [
+                (
+                    "is_big".to_string(),
+                    Some("pub fn is_big(a: u32) -> bool".to_string())
+                ),
+                ("Widget".to_string(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn symbol_search_reports_total_beyond_the_limit() {
+        let Some(mut fixture) = Fixture::new("symbol-total").await else {
+            return;
+        };
+        let repository = fixture.repository.clone();
+        // The same definition in several files scores identically, so only the
+        // tie-break decides which rows fit under the limit.
+        let paths = ["src/c.rs", "src/a.rs", "src/d.rs", "src/b.rs"];
+        let hashes: Vec<String> = paths
+            .iter()
+            .map(|path| format!("{repository}:{path}"))
+            .collect();
+        let report = IndexReport {
+            content_blobs: hashes
+                .iter()
+                .map(|hash| ContentBlob {
+                    hash: hash.clone(),
+                    language: Some("rust".to_string()),
+                    byte_len: 1,
+                    line_count: 1,
+                })
+                .collect(),
+            symbol_records: hashes
+                .iter()
+                .map(|hash| SymbolRecord {
+                    content_hash: hash.clone(),
+                    name: "render".to_string(),
+                })
+                .collect(),
+            file_pointers: paths
+                .iter()
+                .zip(&hashes)
+                .map(|(path, hash)| FilePointer {
+                    repository: repository.clone(),
+                    commit_sha: "abc123".to_string(),
+                    file_path: path.to_string(),
+                    content_hash: hash.clone(),
+                    kind: FileKind::Regular,
+                    mode: None,
+                })
+                .collect(),
+            reference_records: hashes
+                .iter()
+                .map(|hash| ReferenceRecord {
+                    content_hash: hash.clone(),
+                    namespace: None,
+                    name: "render".to_string(),
+                    fully_qualified: "render".to_string(),
+                    kind: Some("definition".to_string()),
+                    line: 1,
+                    column: 4,
+                    end_line: 1,
+                    end_column: 10,
+                    signature: None,
+                })
+                .collect(),
+            ..IndexReport::default()
+        };
+        fixture.ingest(report).await;
+        let db = fixture.db();
+
+        let response = db
+            .search_symbols(SearchRequest {
+                q: None,
+                name: Some("render".to_string()),
+                name_regex: None,
+                name_contains: None,
+                namespace: None,
+                namespace_prefix: None,
+                kind: Some(vec!["definition".to_string()]),
+                language: None,
+                repository: Some(repository.clone()),
+                commit_sha: Some("abc123".to_string()),
+                path: None,
+                path_regex: None,
+                path_hint: None,
+                path_depth: None,
+                include_paths: Vec::new(),
+                excluded_paths: Vec::new(),
+                include_references: None,
+                limit: Some(2),
+                unreferenced: false,
+                group_by_name: false,
+            })
+            .await;
+
+        let response = response.expect("symbol search failed");
+        assert_eq!(response.total, 4);
+        let returned: Vec<String> = response
+            .symbols
+            .into_iter()
+            .map(|symbol| symbol.file_path)
+            .collect();
+        assert_eq!(returned, ["src/a.rs", "src/b.rs"]);
+    }
+
+    #[tokio::test]
+    async fn ingested_symlinks_surface_their_kind_and_target() {
+        let Some(mut fixture) = Fixture::new("file-kind").await else {
+            return;
+        };
+        let repository = fixture.repository.clone();
+        let files = [
+            ("docs/guide.md", "# Guide\n", FileKind::Regular, 0o100644),
+            ("docs/latest.md", "guide.md", FileKind::Symlink, 0o120000),
+            ("docs/serve.sh", "#!/bin/sh\n", FileKind::Regular, 0o100755),
+        ];
+        let hashes: Vec<String> = files
+            .iter()
+            .map(|(path, ..)| format!("{repository}:{path}"))
+            .collect();
+        let report = IndexReport {
+            content_blobs: files
+                .iter()
+                .zip(&hashes)
+                .map(|((_, text, ..), hash)| ContentBlob {
+                    hash: hash.clone(),
+                    language: None,
+                    byte_len: text.len() as i64,
+                    line_count: 1,
+                })
+                .collect(),
+            file_pointers: files
+                .iter()
+                .zip(&hashes)
+                .map(|((path, _, kind, mode), hash)| FilePointer {
+                    repository: repository.clone(),
+                    commit_sha: "abc123".to_string(),
+                    file_path: path.to_string(),
+                    content_hash: hash.clone(),
+                    kind: *kind,
+                    mode: Some(*mode),
+                })
+                .collect(),
+            ..IndexReport::default()
+        };
+        fixture.ingest(report).await;
+        for ((_, text, ..), hash) in files.iter().zip(&hashes) {
+            fixture.insert_chunk(hash, 0, *text).await;
+        }
+
+        let db = fixture.db();
+        let tree = db
+            .get_repo_tree(
+                &repository,
+                RepoTreeQuery {
+                    commit: "abc123".to_string(),
+                    path: Some("docs/".to_string()),
+                },
+            )
+            .await;
+        let link = db
+            .get_file_content(&repository, "abc123", "docs/latest.md")
+            .await;
+        let guide = db
+            .get_file_content(&repository, "abc123", "docs/guide.md")
+            .await;
+        let modes: Vec<Option<i32>> =
+            sqlx::query_scalar("SELECT mode FROM files WHERE repository = $1 ORDER BY file_path")
+                .bind(&repository)
+                .fetch_all(&fixture.pool)
+                .await
+                .expect("failed to read modes");
+
+        let kinds: Vec<(String, String, bool)> = tree
+            .expect("tree failed")
+            .entries
+            .into_iter()
+            .map(|entry| (entry.name, entry.kind, entry.executable))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("guide.md".to_string(), "file".to_string(), false),
+                ("latest.md".to_string(), "symlink".to_string(), false),
+                ("serve.sh".to_string(), "file".to_string(), true),
+            ]
+        );
+        assert_eq!(
+            link.expect("file content failed").symlink_target.as_deref(),
+            Some("guide.md")
+        );
+        assert_eq!(guide.expect("file content failed").symlink_target, None);
+        assert_eq!(modes, vec![Some(0o100644), Some(0o120000), Some(0o100755)]);
+    }
+
+    #[tokio::test]
+    async fn snippets_expand_past_the_default_context() {
+        let Some(mut fixture) = Fixture::new("expand-snippet").await else {
+            return;
+        };
+        let text: String = (1..=21).map(|line| format!("line {line}\n")).collect();
+        fixture
+            .insert_file(TestFile::new("src/lib.rs").chunk(text))
+            .await;
+
+        let db = fixture.db();
+        let snippet_with = |context| SnippetRequest {
+            repository: fixture.repository.clone(),
+            commit_sha: "abc123".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            line: 11,
+            column: None,
+            context: Some(context),
+            highlight: None,
+            case_sensitive: None,
+        };
+        let original = db.get_file_snippet(snippet_with(1)).await;
+        let expanded = db.get_file_snippet(snippet_with(8)).await;
+
+        let original = original.expect("snippet failed");
+        let expanded = expanded.expect("expanded snippet failed");
+        assert_eq!(original.lines, ["line 10", "line 11", "line 12"]);
+        assert_eq!((expanded.start_line, expanded.highlight_line), (3, 11));
+        assert_eq!(expanded.lines.len(), 17);
+        assert_eq!(expanded.lines[11 - 3], "line 11");
+        assert!(expanded.truncated);
+    }
+
+    #[tokio::test]
+    async fn snippet_context_is_honoured_up_to_the_configured_cap() {
+        let Some(mut fixture) = Fixture::new("snippet-cap").await else {
+            return;
+        };
+        let text: String = (1..=30).map(|line| format!("line {line}\n")).collect();
+        fixture
+            .insert_file(TestFile::new("src/lib.rs").chunk(text))
+            .await;
+
+        let db = fixture.db().with_max_snippet_context(12);
+        let snippet_with = |context| SnippetRequest {
+            repository: fixture.repository.clone(),
+            commit_sha: "abc123".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            line: 15,
+            column: None,
+            context: Some(context),
+            highlight: None,
+            case_sensitive: None,
+        };
+        let requested = db.get_file_snippet(snippet_with(10)).await;
+        let capped = db.get_file_snippet(snippet_with(50)).await;
+
+        let requested = requested.expect("snippet failed");
+        assert_eq!(requested.start_line, 5);
+        assert_eq!(requested.lines.len(), 21);
+        assert_eq!(requested.lines.first().map(String::as_str), Some("line 5"));
+        assert!(requested.truncated);
+
+        let capped = capped.expect("capped snippet failed");
+        assert_eq!(capped.start_line, 3);
+        assert_eq!(capped.lines.len(), 25);
+        assert_eq!(capped.lines.last().map(String::as_str), Some("line 27"));
+        assert!(capped.truncated);
+    }
+
+    #[tokio::test]
+    async fn snippet_batches_tolerate_missing_files() {
+        let Some(mut fixture) = Fixture::new("missing-snippet").await else {
+            return;
+        };
+        fixture
+            .insert_file(TestFile::new("src/lib.rs").chunk("one\ntwo\nthree\n"))
+            .await;
+
+        let db = fixture.db();
+        let request = |commit_sha: &str, file_path: &str, line| SnippetRequest {
+            repository: fixture.repository.clone(),
+            commit_sha: commit_sha.to_string(),
+            file_path: file_path.to_string(),
+            line,
+            column: None,
+            context: Some(1),
+            highlight: None,
+            case_sensitive: None,
+        };
+        let snippets = db
+            .get_file_snippets(vec![
+                request("abc123", "src/deleted.rs", 4),
+                request("abc123", "src/lib.rs", 2),
+                request("stale00", "src/lib.rs", 7),
+            ])
+            .await;
+
+        let snippets = snippets.expect("snippet batch failed");
+        assert_eq!(snippets.len(), 3);
+        assert_eq!(snippets[1].lines, ["one", "two", "three"]);
+        for (missing, line) in [(&snippets[0], 4), (&snippets[2], 7)] {
+            assert!(missing.lines.is_empty());
+            assert!(!missing.truncated);
+            assert_eq!(missing.highlight_line, line);
+        }
+    }
+
+    #[tokio::test]
+    async fn text_search_without_snippets_returns_bare_locations() {
+        let Some(mut fixture) = Fixture::new("no-snippet").await else {
+            return;
+        };
+        let needle = format!("nosnippet{}", std::process::id());
+        fixture.insert_branch("main").await;
+        let file = TestFile::new("src/lib.rs")
+            .chunk("a\nb\nc\nd\ne\n")
+            .chunk(format!("let {needle} = 1;\n"));
+        fixture.insert_file(file).await;
+
+        let db = fixture.db();
+        let request =
+            TextSearchRequest::from_query_str(&format!("repo:{} {needle}", fixture.repository))
+                .expect("query should plan");
+        let with_snippets = db.text_search(&request).await;
+        let without_snippets = db.text_search(&request.with_snippets(false)).await;
+
+        let with_snippets = with_snippets.expect("text search failed").results;
+        let without_snippets = without_snippets.expect("text search failed").results;
+        assert_eq!(with_snippets.len(), 1);
+        assert!(with_snippets[0].content_text.contains(&needle));
+        assert_eq!(without_snippets.len(), 1);
+        let bare = &without_snippets[0];
+        assert_eq!(bare.file_path, "src/lib.rs");
+        assert_eq!(bare.match_line, 6);
+        assert!(bare.content_text.is_empty());
+        assert!(bare.snippets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reads_use_the_read_pool_and_ingest_the_primary() {
+        let Some(working) = test_pool().await else {
+            return;
+        };
+        // A pool for a database that doesn't exist fails every query, which
+        // shows which pool a call was routed to.
+        let broken = || {
+            let options = (*working.connect_options())
+                .clone()
+                .database("pointer_missing_database");
+            sqlx::postgres::PgPoolOptions::new()
+                .max_connections(1)
+                .connect_lazy_with(options)
+        };
+        let hashes = || vec!["not-a-real-chunk".to_string()];
+
+        let replica_down = PostgresDb::new(working.clone()).with_read_pool(broken());
+        assert!(replica_down.get_all_repositories().await.is_err());
+        assert!(replica_down.chunk_need(hashes()).await.is_ok());
+
+        let primary_down = PostgresDb::new(broken()).with_read_pool(working);
+        assert!(primary_down.get_all_repositories().await.is_ok());
+        assert!(primary_down.chunk_need(hashes()).await.is_err());
+    }
+
+    #[test]
+    fn schema_check_matches_trigram_indexes_by_table_and_expression() {
+        let extensions = vec!["plpgsql".to_string(), "pg_trgm".to_string()];
+        let indexes = vec![
+            (
+                "chunks".to_string(),
+                "CREATE INDEX idx_chunks_text_content_lower_trgm ON public.chunks USING gin (lower(text_content) gin_trgm_ops)".to_string(),
+            ),
+            (
+                "unique_symbols".to_string(),
+                "CREATE INDEX unique_symbols_new_name_lc_trgm ON public.unique_symbols USING gin (name_lc gin_trgm_ops)".to_string(),
+            ),
+        ];
+
+        let check = SchemaCheck::from_catalog(&extensions, &indexes, true);
+        assert!(check.is_ready());
+        assert_eq!(check.missing_indexes, vec!["chunks (text_content)"]);
+
+        let check = SchemaCheck::from_catalog(&extensions[..1], &[], false);
+        assert!(!check.is_ready());
+        assert_eq!(
+            check.missing_indexes,
+            vec!["chunks (text_content)", "unique_symbols (name_lc)"]
+        );
+        assert_eq!(check.problems().len(), 3);
+        assert!(check.problems()[0].contains("CREATE EXTENSION pg_trgm"));
+    }
+
+    #[tokio::test]
+    async fn migrated_schema_passes_the_startup_check() {
+        let Some(url) = database_url() else {
+            return;
+        };
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&url)
+            .await
+            .expect("failed to connect to postgres");
+
+        let check = check_schema(&pool, true)
+            .await
+            .expect("schema check failed");
+        assert_eq!(check.problems(), Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn ingested_commit_metadata_is_looked_up_by_sha() {
+        let Some(mut fixture) = Fixture::new("commit-metadata").await else {
+            return;
+        };
+        let repository = fixture.repository.clone();
+        let report = IndexReport {
+            commits: vec![IndexedCommit {
+                repository: repository.clone(),
+                commit_sha: "abc1234def".to_string(),
+                author_name: "Pointer".to_string(),
+                author_email: "pointer@example.com".to_string(),
+                committed_at: 1_700_000_000,
+                subject: "Fix GC race".to_string(),
+            }],
+            ..IndexReport::default()
+        };
+        fixture.ingest(report).await;
+
+        let found = fixture
+            .db()
+            .get_commit_metadata(
+                &repository,
+                &["abc1234def".to_string(), "unrecorded".to_string()],
+            )
+            .await;
+
+        let found = found.expect("commit metadata lookup failed");
+        assert_eq!(found.len(), 1);
+        let now = DateTime::parse_from_rfc3339("2023-11-16T22:13:20Z")
+            .unwrap()
+            .to_utc();
+        assert_eq!(found[0].label(now), "abc1234 — Fix GC race (2 days ago)");
+    }
+}
+
+/// Adds the default directory excludes to every plan whose own path filters
+/// don't already reach into those directories.
+fn apply_default_excludes(request: &TextSearchRequest, dirs: &[String]) -> TextSearchRequest {
+    let mut request = request.clone();
+    for plan in &mut request.plans {
+        let targeted: Vec<&str> = plan.file_globs.iter().map(String::as_str).collect();
+        let patterns = default_exclude_patterns(dirs, &targeted);
+        plan.excluded_file_globs.extend(patterns);
+    }
+    request
+}
+
+/// Builds `LIKE` patterns matching `dirs` as a leading or nested directory,
+/// skipping any directory that one of `targeted_paths` explicitly names.
+fn default_exclude_patterns(dirs: &[String], targeted_paths: &[&str]) -> Vec<String> {
+    let mut patterns = Vec::new();
+    for dir in dirs {
+        let dir = dir.trim().trim_matches('/');
+        if dir.is_empty()
+            || targeted_paths
+                .iter()
+                .any(|path| path_targets_dir(path, dir))
+        {
+            continue;
+        }
+        let escaped = escape_sql_like_literal(dir);
+        patterns.push(format!("{escaped}/%"));
+        patterns.push(format!("%/{escaped}/%"));
+    }
+    patterns
+}
+
+/// Whether a path filter (plain path, `LIKE` glob or regex) names `dir` as a
+/// whole path component, e.g. `node_modules/react/` or `%/vendor/%`.
+fn path_targets_dir(path: &str, dir: &str) -> bool {
+    let normalized: String = path
+        .replace('\\', "")
+        .chars()
+        .map(|ch| match ch {
+            '%' | '*' | '^' | '$' => '/',
+            ch => ch.to_ascii_lowercase(),
+        })
+        .collect();
+    format!("/{normalized}/").contains(&format!("/{}/", dir.to_ascii_lowercase()))
+}
+
+/// Reorders ranked files for the requested `order_by` before paging. Phase 1
+/// already returns them by relevance, and the stable sort keeps that order
+/// among files that tie on path or snapshot time.
+fn order_ranked_rows(rows: &mut [RankedFileRow], order: SearchOrder) {
+    match order {
+        SearchOrder::Relevance => {}
+        SearchOrder::Path => rows.sort_by(|a, b| {
+            a.file_path
+                .cmp(&b.file_path)
+                .then_with(|| a.repository.cmp(&b.repository))
+        }),
+        // `None` sorts below any timestamp, so unknown snapshots land last.
+        SearchOrder::Recency => rows.sort_by_key(|row| std::cmp::Reverse(row.snapshot_indexed_at)),
+    }
+}
+
+fn build_search_stats(rows: &[RankedFileRow]) -> SearchResultsStats {
+    let mut directory_counts: HashMap<String, u32> = HashMap::new();
+    let mut repository_counts: HashMap<String, u32> = HashMap::new();
+    let mut branch_counts: HashMap<String, u32> = HashMap::new();
+    let mut language_counts: HashMap<String, u32> = HashMap::new();
+    let mut unknown_language = 0u32;
+
+    for row in rows {
+        if let Some(directory) = parent_directory(&row.file_path) {
+            *directory_counts.entry(directory).or_insert(0) += 1;
+        }
+        *repository_counts.entry(row.repository.clone()).or_insert(0) += 1;
+        match &row.language {
+            Some(language) => *language_counts.entry(language.clone()).or_insert(0) += 1,
+            None => unknown_language += 1,
+        }
+
+        if !row.branches.is_empty() {
+            let unique_branches: HashSet<&String> = row.branches.iter().collect();
+            for branch in unique_branches {
+                *branch_counts.entry(branch.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    SearchResultsStats {
         common_directories: map_to_facets(directory_counts, FACET_LIMIT),
         top_repositories: map_to_facets(repository_counts, FACET_LIMIT),
         top_branches: map_to_facets(branch_counts, FACET_LIMIT),
+        top_languages: language_facets(language_counts, unknown_language),
+    }
+}
+
+/// Known languages rank first; files without one are grouped under
+/// [`UNKNOWN_LANGUAGE`] and only fill a slot the known ones left free.
+fn language_facets(counts: HashMap<String, u32>, unknown: u32) -> Vec<FacetCount> {
+    let mut facets = map_to_facets(counts, FACET_LIMIT);
+    if unknown > 0 && facets.len() < FACET_LIMIT {
+        facets.push(FacetCount {
+            value: UNKNOWN_LANGUAGE.to_string(),
+            count: unknown,
+        });
     }
+    facets
 }
 
 fn map_to_facets(counts: HashMap<String, u32>, limit: usize) -> Vec<FacetCount> {
@@ -4167,12 +9090,12 @@ where
     deduped
 }
 
-fn collect_symbol_terms(request: &TextSearchRequest) -> HashSet<String> {
+fn collect_symbol_terms(request: &TextSearchRequest, min_len: usize) -> HashSet<String> {
     let mut terms = HashSet::new();
     for plan in &request.plans {
         for predicate in &plan.required_terms {
             if let ContentPredicate::Plain(value) = predicate {
-                if !value.is_empty() && looks_like_symbol(value) {
+                if looks_like_symbol(value, min_len) {
                     terms.insert(value.clone());
                 }
             }
@@ -4181,16 +9104,18 @@ fn collect_symbol_terms(request: &TextSearchRequest) -> HashSet<String> {
     terms
 }
 
-fn collect_definition_terms(request: &TextSearchRequest) -> HashSet<String> {
-    collect_symbol_terms(request)
+fn collect_definition_terms(request: &TextSearchRequest, min_len: usize) -> HashSet<String> {
+    collect_symbol_terms(request, min_len)
         .into_iter()
         .map(|term| split_fully_qualified(&term).1)
-        .filter(|term| !term.is_empty())
+        .filter(|term| !term.is_empty() && term.chars().count() >= min_len)
         .collect()
 }
 
-fn looks_like_symbol(term: &str) -> bool {
-    if term.is_empty() || term.len() > 128 {
+/// Whether `term` could name a symbol: symbol characters only, at least one
+/// letter, and at least `min_len` characters long.
+fn looks_like_symbol(term: &str, min_len: usize) -> bool {
+    if term.is_empty() || term.len() > 128 || term.chars().count() < min_len {
         return false;
     }
     let mut has_alpha = false;
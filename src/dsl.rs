@@ -2,17 +2,101 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fmt;
 
+use crate::db::SearchRequest;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Filter {
     Content(String),
     Repo(String),
     File(String),
-    Lang(String),
+    /// Any of these languages; `lang:rust,go` lists several.
+    Lang(Vec<String>),
     Branch(String),
     Regex(String),
     CaseSensitive(CaseSensitivity),
     Type(ResultType),
     Historical(bool),
+    NameContains(String),
+    /// A file must define or reference a symbol with exactly this name.
+    Symbol(String),
+    /// A file must define a symbol with exactly this name; unlike `Symbol`
+    /// it adds no content term and can be negated.
+    Defines(String),
+    /// Compares the number of `/`-separated segments in a file's path.
+    PathDepth(PathDepth),
+    /// Also match identifier-case variants of plain content terms, so
+    /// `parseQuery` finds `parse_query`.
+    Fuzzy(bool),
+    /// Match content terms only as whole words, so `get` skips `forget`.
+    Word(bool),
+    /// Caps a symbol search at this many distinct symbol names.
+    Count(u32),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum DepthComparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl DepthComparison {
+    pub fn sql_operator(self) -> &'static str {
+        match self {
+            DepthComparison::Eq => "=",
+            DepthComparison::Lt => "<",
+            DepthComparison::Le => "<=",
+            DepthComparison::Gt => ">",
+            DepthComparison::Ge => ">=",
+        }
+    }
+}
+
+/// A `path_depth:` bound; `src/lib.rs` has depth 2.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct PathDepth {
+    pub comparison: DepthComparison,
+    pub depth: i32,
+}
+
+impl PathDepth {
+    fn parse(value: &str) -> Result<Self, ParseError> {
+        let (comparison, depth) = if let Some(rest) = value.strip_prefix("<=") {
+            (DepthComparison::Le, rest)
+        } else if let Some(rest) = value.strip_prefix(">=") {
+            (DepthComparison::Ge, rest)
+        } else if let Some(rest) = value.strip_prefix('<') {
+            (DepthComparison::Lt, rest)
+        } else if let Some(rest) = value.strip_prefix('>') {
+            (DepthComparison::Gt, rest)
+        } else if let Some(rest) = value.strip_prefix('=') {
+            (DepthComparison::Eq, rest)
+        } else {
+            (DepthComparison::Eq, value)
+        };
+        let depth = depth
+            .parse::<i32>()
+            .ok()
+            .filter(|depth| *depth >= 0)
+            .ok_or_else(|| {
+                ParseError::InvalidFilter(format!(
+                    "path_depth must be N, =N, <N, <=N, >N or >=N, got {}",
+                    value
+                ))
+            })?;
+        Ok(PathDepth { comparison, depth })
+    }
+}
+
+impl fmt::Display for PathDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.comparison {
+            DepthComparison::Eq => write!(f, "{}", self.depth),
+            other => write!(f, "{}{}", other.sql_operator(), self.depth),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -46,7 +130,7 @@ impl fmt::Display for Filter {
             Filter::Content(s) => write!(f, "content:\"{}\"", s),
             Filter::Repo(s) => write!(f, "repo:\"{}\"", s),
             Filter::File(s) => write!(f, "file:\"{}\"", s),
-            Filter::Lang(s) => write!(f, "lang:\"{}\"", s),
+            Filter::Lang(langs) => write!(f, "lang:\"{}\"", langs.join(",")),
             Filter::Branch(s) => write!(f, "branch:\"{}\"", s),
             Filter::Regex(s) => write!(f, "regex:\"{}\"", s),
             Filter::CaseSensitive(cs) => match cs {
@@ -60,6 +144,13 @@ impl fmt::Display for Filter {
                 ResultType::File => write!(f, "type:file"),
                 ResultType::Repo => write!(f, "type:repo"),
             },
+            Filter::NameContains(s) => write!(f, "name_contains:\"{}\"", s),
+            Filter::Symbol(s) => write!(f, "symbol:\"{}\"", s),
+            Filter::Defines(s) => write!(f, "sym:\"{}\"", s),
+            Filter::PathDepth(depth) => write!(f, "path_depth:{}", depth),
+            Filter::Fuzzy(flag) => write!(f, "fuzzy:{}", if *flag { "yes" } else { "no" }),
+            Filter::Word(flag) => write!(f, "word:{}", if *flag { "yes" } else { "no" }),
+            Filter::Count(count) => write!(f, "count:{}", count),
             Filter::Historical(flag) => {
                 if *flag {
                     write!(f, "historical:yes")
@@ -106,6 +197,10 @@ impl fmt::Display for QueryNode {
 pub enum ParseError {
     InvalidFilter(String),
     UnmatchedParenthesis,
+    /// A `(` inside another group; alternatives only group one level deep.
+    NestedGroup,
+    /// An `or` or a group with no term on one side, e.g. `foo or` or `()`.
+    EmptyAlternative,
     EmptyQuery,
 }
 
@@ -120,6 +215,10 @@ impl fmt::Display for ParseError {
                 }
             }
             ParseError::UnmatchedParenthesis => write!(f, "Unmatched parenthesis"),
+            ParseError::NestedGroup => write!(f, "Nested parentheses are not supported"),
+            ParseError::EmptyAlternative => {
+                write!(f, "Each side of `or` and each group needs a search term")
+            }
             ParseError::EmptyQuery => write!(f, "Empty query"),
         }
     }
@@ -174,9 +273,33 @@ impl QueryParser {
             "file" => Ok(Filter::File(value.clone())),
             "f" => Ok(Filter::File(value.clone())), // alias for file
             "path" => Ok(Filter::File(value)),
-            "lang" | "l" => Ok(Filter::Lang(value)),
+            "lang" | "l" => {
+                let langs: Vec<String> = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|lang| !lang.is_empty())
+                    .map(str::to_lowercase)
+                    .collect();
+                if langs.is_empty() {
+                    return Err(ParseError::InvalidFilter(
+                        "lang requires at least one language".to_string(),
+                    ));
+                }
+                Ok(Filter::Lang(langs))
+            }
             "branch" | "b" => Ok(Filter::Branch(value)),
             "regex" => Ok(Filter::Regex(preprocess_regex_pattern(&value)?)),
+            "name_contains" => Ok(Filter::NameContains(value)),
+            "symbol" => Ok(Filter::Symbol(value)),
+            "sym" => {
+                if value.is_empty() {
+                    return Err(ParseError::InvalidFilter(
+                        "sym requires a symbol name".to_string(),
+                    ));
+                }
+                Ok(Filter::Defines(value))
+            }
+            "path_depth" => Ok(Filter::PathDepth(PathDepth::parse(&value)?)),
             "case" => match value.as_str() {
                 "yes" => Ok(Filter::CaseSensitive(CaseSensitivity::Yes)),
                 "no" => Ok(Filter::CaseSensitive(CaseSensitivity::No)),
@@ -204,151 +327,130 @@ impl QueryParser {
                     value
                 ))),
             },
+            "fuzzy" => match value.to_ascii_lowercase().as_str() {
+                "yes" | "true" | "1" => Ok(Filter::Fuzzy(true)),
+                "no" | "false" | "0" => Ok(Filter::Fuzzy(false)),
+                _ => Err(ParseError::InvalidFilter(format!(
+                    "fuzzy must be yes or no, got {}",
+                    value
+                ))),
+            },
+            "word" => match value.to_ascii_lowercase().as_str() {
+                "yes" | "true" | "1" => Ok(Filter::Word(true)),
+                "no" | "false" | "0" => Ok(Filter::Word(false)),
+                _ => Err(ParseError::InvalidFilter(format!(
+                    "word must be yes or no, got {}",
+                    value
+                ))),
+            },
+            "count" => value
+                .parse::<u32>()
+                .ok()
+                .filter(|count| *count > 0)
+                .map(Filter::Count)
+                .ok_or_else(|| {
+                    ParseError::InvalidFilter(format!(
+                        "count must be a positive number, got {}",
+                        value
+                    ))
+                }),
             _ => Err(ParseError::InvalidFilter(filter_type.to_string())),
         }
     }
 
-    fn parse_term(&mut self) -> Result<QueryNode, ParseError> {
-        if let Some(token) = self.consume() {
-            let token_value = token.value;
-            if token_value.starts_with('-') {
-                // Handle negation
-                let inner_token = token_value[1..].to_string();
-                if inner_token.starts_with('(') {
-                    // -(...) case
-                    let inner_expr = self.parse_group(&inner_token[1..])?;
-                    Ok(QueryNode::Not(Box::new(inner_expr)))
-                } else if !token.first_colon_in_quotes {
-                    if let Some((filter_type, value)) = inner_token.split_once(':') {
-                        // -filter:value case
-                        let filter = self.parse_filter(filter_type, value.to_string())?;
-                        Ok(QueryNode::Not(Box::new(QueryNode::Filter(filter))))
-                    } else {
-                        // -term case
-                        Ok(QueryNode::Not(Box::new(QueryNode::Term(inner_token))))
-                    }
-                } else {
-                    // -term case
-                    Ok(QueryNode::Not(Box::new(QueryNode::Term(inner_token))))
-                }
-            } else if token_value.starts_with('(') {
-                // Handle group
-                self.parse_group(&token_value[1..])
-            } else if !token.first_colon_in_quotes {
-                if let Some((filter_type, value)) = token_value.split_once(':') {
-                    // Handle filter
-                    let filter = self.parse_filter(filter_type, value.to_string())?;
-                    Ok(QueryNode::Filter(filter))
-                } else {
-                    // Regular term
-                    Ok(QueryNode::Term(token_value))
-                }
-            } else {
-                // Regular term
-                Ok(QueryNode::Term(token_value))
+    /// Parses one term, filter, negation or parenthesized group. Groups
+    /// only nest one level deep; `depth` is how many are already open.
+    fn parse_term(&mut self, depth: usize) -> Result<QueryNode, ParseError> {
+        let Some(token) = self.consume() else {
+            return Err(ParseError::EmptyQuery);
+        };
+        let token_value = token.value;
+        if token_value == "(" {
+            return self.parse_group(depth);
+        }
+        if token_value == "-" && self.peek() == Some("(") {
+            // -(...) case
+            self.consume();
+            let inner_expr = self.parse_group(depth)?;
+            return Ok(QueryNode::Not(Box::new(inner_expr)));
+        }
+        if let Some(inner_token) = token_value.strip_prefix('-') {
+            // Handle negation
+            if !token.first_colon_in_quotes
+                && let Some((filter_type, value)) = inner_token.split_once(':')
+            {
+                // -filter:value case
+                let filter = self.parse_filter(filter_type, value.to_string())?;
+                return Ok(QueryNode::Not(Box::new(QueryNode::Filter(filter))));
             }
-        } else {
-            Err(ParseError::EmptyQuery)
+            // -term case
+            return Ok(QueryNode::Not(Box::new(QueryNode::Term(
+                inner_token.to_string(),
+            ))));
         }
+        if !token.first_colon_in_quotes
+            && let Some((filter_type, value)) = token_value.split_once(':')
+        {
+            // Handle filter
+            let filter = self.parse_filter(filter_type, value.to_string())?;
+            return Ok(QueryNode::Filter(filter));
+        }
+        // Regular term
+        Ok(QueryNode::Term(token_value))
     }
 
-    fn parse_group(&mut self, initial_content: &str) -> Result<QueryNode, ParseError> {
-        // This is a simplified approach - in a real implementation we'd need more sophisticated parsing
-        let mut group_content = initial_content.to_string();
-
-        // If the initial content doesn't end with ')', we need to collect more tokens
-        if !initial_content.contains(')') {
-            // Look for the matching parenthesis
-            let mut paren_count = 1; // We already have one '(' from initial_content
-            while let Some(token) = self.consume() {
-                let token_value = token.value;
-                if token_value.contains('(') {
-                    paren_count += token_value.matches('(').count();
-                }
-                if token_value.contains(')') {
-                    paren_count -= token_value.matches(')').count();
-                    if paren_count == 0 {
-                        // Found the matching parenthesis
-                        group_content.push_str(&format!(" {}", token_value));
-                        break;
-                    }
-                }
-                group_content.push_str(&format!(" {}", token_value));
-            }
+    /// Parses the rest of a group whose `(` was just consumed, through its
+    /// closing `)`.
+    fn parse_group(&mut self, depth: usize) -> Result<QueryNode, ParseError> {
+        if depth > 0 {
+            return Err(ParseError::NestedGroup);
         }
+        let inner = self.parse_alternation(depth + 1)?;
+        match self.consume() {
+            Some(token) if token.value == ")" => Ok(QueryNode::Group(Box::new(inner))),
+            _ => Err(ParseError::UnmatchedParenthesis),
+        }
+    }
 
-        // Extract content between parentheses
-        let end_paren_pos = group_content.find(')').unwrap_or(group_content.len());
-        let inner_content = &group_content[..end_paren_pos];
-
-        // Parse the inner content (simplified - in a real implementation this would be recursive)
-        let inner_query = parse_query(inner_content)?;
-
-        // Handle OR operator inside the group if present
-        if inner_content.contains(" or ") {
-            let parts: Vec<&str> = inner_content.split(" or ").collect();
-            let mut or_nodes = Vec::new();
-            for part in parts {
-                let part_query = parse_query(part.trim())?;
-                or_nodes.push(QueryNode::Group(Box::new(part_query)));
-            }
-            Ok(QueryNode::Or(or_nodes))
+    /// `a or b or c`, where each alternative is an implicitly ANDed run of
+    /// terms.
+    fn parse_alternation(&mut self, depth: usize) -> Result<QueryNode, ParseError> {
+        let mut alternatives = vec![self.parse_sequence(depth)?];
+        while self.peek() == Some("or") {
+            self.consume();
+            alternatives.push(self.parse_sequence(depth)?);
+        }
+        if alternatives.len() == 1 {
+            Ok(alternatives.remove(0))
         } else {
-            Ok(QueryNode::Group(Box::new(inner_query)))
+            Ok(QueryNode::Or(alternatives))
         }
     }
 
-    fn parse_expression(&mut self) -> Result<QueryNode, ParseError> {
+    fn parse_sequence(&mut self, depth: usize) -> Result<QueryNode, ParseError> {
         let mut nodes = Vec::new();
-
         while let Some(peeked) = self.peek() {
-            if peeked == ")" {
+            if peeked == ")" || peeked == "or" {
                 break;
             }
-
-            let term = self.parse_term()?;
-            nodes.push(term);
-
-            // Check for OR operator
-            if let Some(next) = self.peek() {
-                if next == "or" || next == "OR" {
-                    self.consume(); // consume "or"
-                    // For simplicity in this implementation, we'll handle OR at a higher level
-                    break;
-                }
-            }
+            nodes.push(self.parse_term(depth)?);
         }
 
-        if nodes.len() == 1 {
-            Ok(nodes.into_iter().next().unwrap())
-        } else {
-            Ok(QueryNode::And(nodes))
+        match nodes.len() {
+            0 if self.tokens.is_empty() => Err(ParseError::EmptyQuery),
+            0 => Err(ParseError::EmptyAlternative),
+            1 => Ok(nodes.remove(0)),
+            _ => Ok(QueryNode::And(nodes)),
         }
     }
 
     pub fn parse(mut self) -> Result<QueryNode, ParseError> {
-        let mut expressions = Vec::new();
-
-        while self.pos < self.tokens.len() {
-            let expr = self.parse_expression()?;
-            expressions.push(expr);
-
-            // Check for OR operator between expressions
-            if let Some(token) = self.peek() {
-                if token == "or" || token == "OR" {
-                    self.consume(); // consume "or"
-                    continue; // continue to parse more expressions for OR
-                }
-            }
-        }
-
-        if expressions.is_empty() {
-            return Err(ParseError::EmptyQuery);
-        } else if expressions.len() == 1 {
-            Ok(expressions.into_iter().next().unwrap())
-        } else {
-            Ok(QueryNode::And(expressions))
+        let node = self.parse_alternation(0)?;
+        // Only a `)` without a matching `(` stops the top level early.
+        if self.pos < self.tokens.len() {
+            return Err(ParseError::UnmatchedParenthesis);
         }
+        Ok(node)
     }
 }
 
@@ -392,12 +494,105 @@ fn preprocess_regex_pattern(raw: &str) -> Result<String, ParseError> {
     let (normalized, start_anchored, end_anchored) = normalize_line_anchors(&decoded);
     let prefix = if start_anchored { "" } else { ".*" };
     let suffix = if end_anchored { "" } else { ".*" };
-    Ok(format!(
+    let pattern = format!(
         "(?m)^{prefix}{core}{suffix}$",
         prefix = prefix,
         core = normalized,
         suffix = suffix
-    ))
+    );
+    validate_regex_syntax(&pattern)?;
+    Ok(pattern)
+}
+
+/// Largest `{m,n}` bound a Postgres regex accepts.
+const MAX_REGEX_REPEAT: u32 = 255;
+
+/// Checks a regex before it reaches Postgres, where a bad pattern only shows
+/// up as a database error. Postgres' word boundary escapes (`\m`, `\M`, `\y`,
+/// `\Y`) and `\Z` are accepted; escapes Postgres reads differently from
+/// other regex dialects, or not at all, are refused with a hint.
+fn validate_regex_syntax(pattern: &str) -> Result<(), ParseError> {
+    fn invalid(reason: impl fmt::Display) -> ParseError {
+        ParseError::InvalidFilter(format!("invalid regex: {reason}"))
+    }
+
+    // Rewrite the Postgres-only escapes into their nearest equivalents so
+    // the rest of the pattern can be checked by a standard parser.
+    let mut translated = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            translated.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('m' | 'M' | 'y') => translated.push_str(r"\b"),
+            Some('Y') => translated.push_str(r"\B"),
+            Some('Z') => translated.push_str(r"\z"),
+            Some('b' | 'B') => {
+                return Err(invalid(
+                    "`\\b` is not a word boundary here; use `\\y`, `\\m` (word start) or `\\M` (word end)",
+                ));
+            }
+            Some('p' | 'P') => {
+                return Err(invalid(
+                    "Unicode classes such as `\\p{L}` are not supported",
+                ));
+            }
+            Some('z') => return Err(invalid("`\\z` is not supported; use `\\Z`")),
+            Some('x') if chars.peek() == Some(&'{') => {
+                return Err(invalid(
+                    "`\\x{...}` is not supported; write the hex digits without braces",
+                ));
+            }
+            Some(other) => {
+                translated.push('\\');
+                translated.push(other);
+            }
+            None => translated.push('\\'),
+        }
+    }
+
+    let ast = regex_syntax::ast::parse::Parser::new()
+        .parse(&translated)
+        .map_err(|err| invalid(err.kind()))?;
+    if largest_repeat(&ast) > MAX_REGEX_REPEAT {
+        return Err(invalid(format_args!(
+            "repetition counts above {MAX_REGEX_REPEAT} are not supported"
+        )));
+    }
+    regex_syntax::hir::translate::Translator::new()
+        .translate(&translated, &ast)
+        .map_err(|err| invalid(err.kind()))?;
+    Ok(())
+}
+
+/// The largest `{m}`, `{m,}` or `{m,n}` count anywhere in `ast`.
+fn largest_repeat(ast: &regex_syntax::ast::Ast) -> u32 {
+    use regex_syntax::ast::{Ast, RepetitionKind, RepetitionRange};
+
+    match ast {
+        Ast::Repetition(repetition) => {
+            let count = match repetition.op.kind {
+                RepetitionKind::Range(
+                    RepetitionRange::Exactly(count)
+                    | RepetitionRange::AtLeast(count)
+                    | RepetitionRange::Bounded(_, count),
+                ) => count,
+                _ => 0,
+            };
+            count.max(largest_repeat(&repetition.ast))
+        }
+        Ast::Group(group) => largest_repeat(&group.ast),
+        Ast::Alternation(alternation) => alternation
+            .asts
+            .iter()
+            .map(largest_repeat)
+            .max()
+            .unwrap_or(0),
+        Ast::Concat(concat) => concat.asts.iter().map(largest_repeat).max().unwrap_or(0),
+        _ => 0,
+    }
 }
 
 fn normalize_line_anchors(pattern: &str) -> (String, bool, bool) {
@@ -504,10 +699,18 @@ fn tokenize_query(query: &str) -> Vec<Token> {
                 if !in_quotes {
                     if let Some(&next_ch) = chars.peek() {
                         if next_ch != '"' && next_ch != '\'' {
+                            // A `)` the value didn't open closes an enclosing
+                            // group, as in `(sym:Foo or sym:Bar)`.
+                            let mut open_parens = 0usize;
                             while let Some(&next_ch) = chars.peek() {
-                                if next_ch.is_whitespace() {
+                                if next_ch.is_whitespace() || (next_ch == ')' && open_parens == 0) {
                                     break;
                                 }
+                                match next_ch {
+                                    '(' => open_parens += 1,
+                                    ')' => open_parens -= 1,
+                                    _ => {}
+                                }
                                 current_token.push(chars.next().unwrap());
                             }
                             push_token(&mut tokens, &mut current_token, &mut first_colon_in_quotes);
@@ -587,6 +790,32 @@ pub struct TextSearchPlan {
     pub highlight_pattern: String,
     pub result_type: Option<ResultType>,
     pub include_historical: bool,
+    /// Lowercased substrings that some symbol in a matching file must contain.
+    pub symbol_name_contains: Vec<String>,
+    /// Exact symbol names a matching file must define or reference.
+    pub symbol_terms: Vec<String>,
+    /// Exact symbol names a matching file must define.
+    pub defined_symbols: Vec<String>,
+    /// Exact symbol names a matching file must not define.
+    pub excluded_defined_symbols: Vec<String>,
+    /// Bounds every matching file's path depth must satisfy.
+    pub path_depths: Vec<PathDepth>,
+    /// Distinct symbol names a symbol search returns. Only plans with a
+    /// `symbol:` or `name_contains:` filter carry it; text search rejects it.
+    pub symbol_count: Option<u32>,
+}
+
+/// How `text_search` orders matching files before paging them.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchOrder {
+    /// Highest computed score first.
+    #[default]
+    Relevance,
+    /// Alphabetical by file path, then repository.
+    Path,
+    /// Most recently indexed snapshot first.
+    Recency,
 }
 
 #[derive(Debug, Clone)]
@@ -595,6 +824,10 @@ pub struct TextSearchRequest {
     pub plans: Vec<TextSearchPlan>,
     pub page: u32,
     pub page_size: u32,
+    /// When false, results carry only their location and no snippet text,
+    /// which skips the costly context extraction.
+    pub snippets: bool,
+    pub order_by: SearchOrder,
 }
 
 #[derive(Debug, PartialEq)]
@@ -608,7 +841,7 @@ pub enum QueryPlanError {
 impl fmt::Display for QueryPlanError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            QueryPlanError::Parse(err) => write!(f, "failed to parse query: {:?}", err),
+            QueryPlanError::Parse(err) => write!(f, "failed to parse query: {}", err),
             QueryPlanError::EmptyPlan => write!(f, "query did not produce any executable plan"),
             QueryPlanError::Unsupported(msg) => write!(f, "unsupported query: {}", msg),
             QueryPlanError::Invalid(msg) => write!(f, "invalid query: {}", msg),
@@ -654,9 +887,21 @@ impl TextSearchRequest {
             plans,
             page,
             page_size,
+            snippets: true,
+            order_by: SearchOrder::default(),
         })
     }
 
+    pub fn with_snippets(mut self, snippets: bool) -> Self {
+        self.snippets = snippets;
+        self
+    }
+
+    pub fn with_order_by(mut self, order_by: SearchOrder) -> Self {
+        self.order_by = order_by;
+        self
+    }
+
     pub fn limit_plus_one(&self) -> i64 {
         (self.page_size + 1) as i64
     }
@@ -665,6 +910,103 @@ impl TextSearchRequest {
         let page_index = self.page.saturating_sub(1) as i64;
         page_index * self.page_size as i64
     }
+
+    /// Fails for filters that only mean something to a symbol search, so a
+    /// content search doesn't silently drop them.
+    pub fn reject_symbol_only_filters(&self) -> Result<(), QueryPlanError> {
+        if self.plans.iter().any(|plan| plan.symbol_count.is_some()) {
+            return Err(QueryPlanError::Unsupported(
+                "count: only caps symbol searches, not content search".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Turns a `symbol:`/`name_contains:` query into a symbol search. Rows
+    /// are grouped by name, so `count:` caps distinct symbols rather than
+    /// definition and reference sites. Only `repo:` (one repository) and
+    /// `lang:` carry over; other filters are rejected.
+    pub fn symbol_search_request(&self) -> Result<SearchRequest, QueryPlanError> {
+        let [plan] = self.plans.as_slice() else {
+            return Err(QueryPlanError::Unsupported(
+                "symbol searches do not support OR".to_string(),
+            ));
+        };
+        let name = match plan.symbol_terms.as_slice() {
+            [] => None,
+            [name] => Some(name.clone()),
+            _ => {
+                return Err(QueryPlanError::Unsupported(
+                    "symbol searches take one symbol: filter".to_string(),
+                ));
+            }
+        };
+        let name_contains = match plan.symbol_name_contains.as_slice() {
+            [] => None,
+            [part] => Some(part.clone()),
+            _ => {
+                return Err(QueryPlanError::Unsupported(
+                    "symbol searches take one name_contains: filter".to_string(),
+                ));
+            }
+        };
+        if name.is_none() && name_contains.is_none() {
+            return Err(QueryPlanError::Invalid(
+                "symbol searches need a symbol: or name_contains: filter".to_string(),
+            ));
+        }
+        // Both filters also add their name as a content term; anything
+        // beyond that has no symbol search equivalent.
+        let name_terms = usize::from(name.is_some()) + usize::from(name_contains.is_some());
+        if plan.required_terms.len() > name_terms
+            || !plan.excluded_terms.is_empty()
+            || !plan.excluded_repos.is_empty()
+            || !plan.file_globs.is_empty()
+            || !plan.excluded_file_globs.is_empty()
+            || !plan.excluded_langs.is_empty()
+            || !plan.branches.is_empty()
+            || !plan.excluded_branches.is_empty()
+            || !plan.defined_symbols.is_empty()
+            || !plan.excluded_defined_symbols.is_empty()
+            || !plan.path_depths.is_empty()
+        {
+            return Err(QueryPlanError::Unsupported(
+                "symbol searches only combine with repo: and lang: filters".to_string(),
+            ));
+        }
+        let repository = match plan.repos.as_slice() {
+            [] => None,
+            [repo] => Some(repo.clone()),
+            _ => {
+                return Err(QueryPlanError::Unsupported(
+                    "symbol searches take one repo: filter".to_string(),
+                ));
+            }
+        };
+
+        Ok(SearchRequest {
+            q: None,
+            name,
+            name_regex: None,
+            name_contains,
+            namespace: None,
+            namespace_prefix: None,
+            kind: None,
+            language: (!plan.langs.is_empty()).then(|| plan.langs.clone()),
+            repository,
+            commit_sha: None,
+            path: None,
+            path_regex: None,
+            path_hint: None,
+            path_depth: None,
+            include_paths: Vec::new(),
+            excluded_paths: Vec::new(),
+            include_references: None,
+            limit: plan.symbol_count.map(i64::from),
+            unreferenced: false,
+            group_by_name: true,
+        })
+    }
 }
 
 impl TextSearchPlan {
@@ -713,6 +1055,38 @@ impl TryFrom<FlatQuery> for TextSearchPlan {
             }
         }
 
+        let has_regex = value
+            .required_terms
+            .iter()
+            .chain(&value.excluded_terms)
+            .any(|term| matches!(term, ContentPredicate::Regex(_)));
+        if value.word.unwrap_or(false) && has_regex {
+            return Err(QueryPlanError::Invalid(
+                "word:yes cannot be combined with regex:; use \\m and \\M in the regex instead"
+                    .to_string(),
+            ));
+        }
+
+        if value.count.is_some()
+            && value.symbol_terms.is_empty()
+            && value.symbol_name_contains.is_empty()
+        {
+            return Err(QueryPlanError::Invalid(
+                "count: needs a symbol: or name_contains: filter".to_string(),
+            ));
+        }
+
+        if value.fuzzy.unwrap_or(false) {
+            value.required_terms = value
+                .required_terms
+                .into_iter()
+                .map(expand_identifier_variants)
+                .collect();
+        }
+        if value.word.unwrap_or(false) {
+            value.required_terms = value.required_terms.into_iter().map(whole_word).collect();
+            value.excluded_terms = value.excluded_terms.into_iter().map(whole_word).collect();
+        }
         let highlight_pattern = TextSearchPlan::highlight_from_terms(&value.required_terms);
 
         value.required_terms = dedup_content_terms(value.required_terms);
@@ -725,6 +1099,12 @@ impl TryFrom<FlatQuery> for TextSearchPlan {
         dedup_vec(&mut value.excluded_langs);
         dedup_vec(&mut value.branches);
         dedup_vec(&mut value.excluded_branches);
+        dedup_vec(&mut value.symbol_name_contains);
+        dedup_vec(&mut value.symbol_terms);
+        dedup_vec(&mut value.defined_symbols);
+        dedup_vec(&mut value.excluded_defined_symbols);
+        let mut seen_depths = HashSet::new();
+        value.path_depths.retain(|depth| seen_depths.insert(*depth));
 
         Ok(TextSearchPlan {
             highlight_pattern,
@@ -741,6 +1121,12 @@ impl TryFrom<FlatQuery> for TextSearchPlan {
             case_sensitivity: value.case_sensitivity,
             result_type: value.result_type,
             include_historical: value.include_historical.unwrap_or(false),
+            symbol_name_contains: value.symbol_name_contains,
+            symbol_terms: value.symbol_terms,
+            defined_symbols: value.defined_symbols,
+            excluded_defined_symbols: value.excluded_defined_symbols,
+            path_depths: value.path_depths,
+            symbol_count: value.count,
         })
     }
 }
@@ -760,6 +1146,14 @@ struct FlatQuery {
     case_sensitivity: Option<CaseSensitivity>,
     result_type: Option<ResultType>,
     include_historical: Option<bool>,
+    fuzzy: Option<bool>,
+    word: Option<bool>,
+    count: Option<u32>,
+    symbol_name_contains: Vec<String>,
+    symbol_terms: Vec<String>,
+    defined_symbols: Vec<String>,
+    excluded_defined_symbols: Vec<String>,
+    path_depths: Vec<PathDepth>,
 }
 
 impl Default for FlatQuery {
@@ -778,6 +1172,14 @@ impl Default for FlatQuery {
             case_sensitivity: None,
             result_type: None,
             include_historical: None,
+            fuzzy: None,
+            word: None,
+            count: None,
+            symbol_name_contains: Vec::new(),
+            symbol_terms: Vec::new(),
+            defined_symbols: Vec::new(),
+            excluded_defined_symbols: Vec::new(),
+            path_depths: Vec::new(),
         }
     }
 }
@@ -807,7 +1209,22 @@ impl FlatQuery {
 
         self.case_sensitivity = merge_case(self.case_sensitivity, other.case_sensitivity.clone())?;
         self.result_type = merge_result_type(self.result_type, other.result_type.clone())?;
-        self.include_historical = merge_bool(self.include_historical, other.include_historical)?;
+        self.include_historical = merge_bool(
+            "historical",
+            self.include_historical,
+            other.include_historical,
+        )?;
+        self.fuzzy = merge_bool("fuzzy", self.fuzzy, other.fuzzy)?;
+        self.word = merge_bool("word", self.word, other.word)?;
+        self.count = merge_count(self.count, other.count)?;
+        self.symbol_name_contains
+            .extend(other.symbol_name_contains.iter().cloned());
+        self.symbol_terms.extend(other.symbol_terms.iter().cloned());
+        self.defined_symbols
+            .extend(other.defined_symbols.iter().cloned());
+        self.excluded_defined_symbols
+            .extend(other.excluded_defined_symbols.iter().cloned());
+        self.path_depths.extend(other.path_depths.iter().copied());
 
         Ok(self)
     }
@@ -843,11 +1260,11 @@ impl FlatQuery {
                     base.file_globs.push(pattern);
                 }
             }
-            Filter::Lang(value) => {
+            Filter::Lang(langs) => {
                 if negate {
-                    base.excluded_langs.push(value.clone());
+                    base.excluded_langs.extend(langs.iter().cloned());
                 } else {
-                    base.langs.push(value.clone());
+                    base.langs.extend(langs.iter().cloned());
                 }
             }
             Filter::Branch(value) => {
@@ -889,6 +1306,80 @@ impl FlatQuery {
                 }
                 base.include_historical = Some(*flag);
             }
+            Filter::Fuzzy(flag) => {
+                if negate {
+                    return Err(QueryPlanError::Unsupported(
+                        "negating fuzzy: filters is not supported".to_string(),
+                    ));
+                }
+                base.fuzzy = Some(*flag);
+            }
+            Filter::Word(flag) => {
+                if negate {
+                    return Err(QueryPlanError::Unsupported(
+                        "negating word: filters is not supported".to_string(),
+                    ));
+                }
+                base.word = Some(*flag);
+            }
+            Filter::NameContains(value) => {
+                if negate {
+                    return Err(QueryPlanError::Unsupported(
+                        "negating name_contains: filters is not supported".to_string(),
+                    ));
+                }
+                if value.chars().count() < 3 {
+                    return Err(QueryPlanError::Invalid(
+                        "name_contains: needs at least 3 characters".to_string(),
+                    ));
+                }
+                // Symbol names are matched case-insensitively, so the content
+                // term is lowercased too to keep it from forcing case:auto on.
+                let part = value.to_lowercase();
+                base.required_terms
+                    .push(ContentPredicate::Plain(part.clone()));
+                base.symbol_name_contains.push(part);
+            }
+            Filter::Symbol(name) => {
+                if negate {
+                    return Err(QueryPlanError::Unsupported(
+                        "negating symbol: filters is not supported".to_string(),
+                    ));
+                }
+                if name.chars().count() < 3 {
+                    return Err(QueryPlanError::Invalid(
+                        "symbol: needs at least 3 characters".to_string(),
+                    ));
+                }
+                // The name is also a content term so that matches land on the
+                // lines where the symbol appears.
+                base.required_terms
+                    .push(ContentPredicate::Plain(name.clone()));
+                base.symbol_terms.push(name.clone());
+            }
+            Filter::Defines(name) => {
+                if negate {
+                    base.excluded_defined_symbols.push(name.clone());
+                } else {
+                    base.defined_symbols.push(name.clone());
+                }
+            }
+            Filter::Count(count) => {
+                if negate {
+                    return Err(QueryPlanError::Unsupported(
+                        "negating count: filters is not supported".to_string(),
+                    ));
+                }
+                base.count = Some(*count);
+            }
+            Filter::PathDepth(depth) => {
+                if negate {
+                    return Err(QueryPlanError::Unsupported(
+                        "negating path_depth: filters is not supported".to_string(),
+                    ));
+                }
+                base.path_depths.push(*depth);
+            }
         }
         Ok(base)
     }
@@ -981,18 +1472,148 @@ fn merge_result_type(
     }
 }
 
-fn merge_bool(left: Option<bool>, right: Option<bool>) -> Result<Option<bool>, QueryPlanError> {
+fn merge_bool(
+    filter: &str,
+    left: Option<bool>,
+    right: Option<bool>,
+) -> Result<Option<bool>, QueryPlanError> {
+    match (left, right) {
+        (None, other) => Ok(other),
+        (other, None) => Ok(other),
+        (Some(a), Some(b)) if a == b => Ok(Some(a)),
+        (Some(a), Some(b)) => Err(QueryPlanError::Invalid(format!(
+            "conflicting {} filters: {} vs {}",
+            filter, a, b
+        ))),
+    }
+}
+
+fn merge_count(left: Option<u32>, right: Option<u32>) -> Result<Option<u32>, QueryPlanError> {
     match (left, right) {
         (None, other) => Ok(other),
         (other, None) => Ok(other),
         (Some(a), Some(b)) if a == b => Ok(Some(a)),
         (Some(a), Some(b)) => Err(QueryPlanError::Invalid(format!(
-            "conflicting historical filters: {} vs {}",
+            "conflicting count filters: {} vs {}",
             a, b
         ))),
     }
 }
 
+/// Splits an identifier into lowercase words at `_`/`-` separators and
+/// camel-case boundaries, keeping acronyms together: `parseHTTPQuery`
+/// becomes `parse`, `http`, `query`. Returns `None` for anything that is
+/// not identifier-shaped.
+fn split_identifier_words(term: &str) -> Option<Vec<String>> {
+    if term.is_empty()
+        || !term
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+    {
+        return None;
+    }
+
+    let chars: Vec<char> = term.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for (idx, &ch) in chars.iter().enumerate() {
+        if ch == '_' || ch == '-' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if ch.is_ascii_uppercase() && !current.is_empty() {
+            let prev = chars[idx - 1];
+            let next_is_lower = chars
+                .get(idx + 1)
+                .is_some_and(|next| next.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_is_lower)
+            {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(ch.to_ascii_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    Some(words)
+}
+
+/// Spellings of `term` in the common identifier styles, starting with
+/// `term` itself. Single-word terms have no variants.
+fn identifier_variants(term: &str) -> Vec<String> {
+    let mut variants = vec![term.to_string()];
+    let Some(words) = split_identifier_words(term).filter(|words| words.len() > 1) else {
+        return variants;
+    };
+
+    let capitalize = |word: &String| {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+            .unwrap_or_default()
+    };
+    let pascal: String = words.iter().map(capitalize).collect();
+    let camel = words[0].clone() + &words[1..].iter().map(capitalize).collect::<String>();
+    variants.extend([
+        camel,
+        pascal,
+        words.join("_"),
+        words.join("_").to_ascii_uppercase(),
+        words.join("-"),
+    ]);
+    dedup_vec(&mut variants);
+    variants
+}
+
+/// Widens a plain term to a regex matching any of its identifier variants;
+/// regexes and terms without variants are returned unchanged.
+fn expand_identifier_variants(term: ContentPredicate) -> ContentPredicate {
+    let ContentPredicate::Plain(value) = &term else {
+        return term;
+    };
+    let variants = identifier_variants(value);
+    if variants.len() == 1 {
+        return term;
+    }
+    ContentPredicate::Regex(
+        variants
+            .iter()
+            .map(|variant| regex_escape(variant))
+            .collect::<Vec<_>>()
+            .join("|"),
+    )
+}
+
+/// Bounds a term with Postgres' `\m`/`\M` word-boundary escapes. A plain
+/// term only gets a bound on an edge that is a word character, so
+/// `word:yes content:"->next"` still matches; a `fuzzy:` variant regex is
+/// grouped so the bounds apply to every alternative.
+fn whole_word(term: ContentPredicate) -> ContentPredicate {
+    let is_word_char = |ch: char| ch.is_alphanumeric() || ch == '_';
+    match term {
+        ContentPredicate::Plain(value) => {
+            let start = if value.starts_with(is_word_char) {
+                r"\m"
+            } else {
+                ""
+            };
+            let end = if value.ends_with(is_word_char) {
+                r"\M"
+            } else {
+                ""
+            };
+            ContentPredicate::Regex(format!("{start}{}{end}", regex_escape(&value)))
+        }
+        ContentPredicate::Regex(pattern) => ContentPredicate::Regex(format!(r"\m(?:{pattern})\M")),
+    }
+}
+
 fn regex_escape(input: &str) -> String {
     let mut escaped = String::with_capacity(input.len());
     for ch in input.chars() {
@@ -1198,9 +1819,408 @@ mod tests {
         }
     }
 
+    #[test]
+    fn regex_syntax_errors_are_reported_before_querying() {
+        let cases = [
+            ("[abc", "unclosed character class"),
+            ("foo{2,1}", "invalid repetition"),
+            ("a{300}", "above 255"),
+            ("[z-a]", "invalid character class range"),
+            (r"\bfoo\b", "word boundary"),
+            (r"\p{L}+", "Unicode classes"),
+            (r"foo\z", r"use `\Z`"),
+            (r"\x{41}", "without braces"),
+            (r"\1", "backreferences"),
+        ];
+        for (raw, expected) in cases {
+            match TextSearchRequest::from_query_str(&format!("regex:{raw}")) {
+                Err(err) => {
+                    let msg = err.to_string();
+                    assert!(msg.contains("invalid regex: "), "{raw}: {msg}");
+                    assert!(msg.contains(expected), "{raw}: {msg}");
+                }
+                Ok(request) => panic!("expected {raw} to be rejected, got {request:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn complex_regex_with_postgres_escapes_is_accepted() {
+        let raw = r"\m[[:alpha:]_][\w-]*\s*=\s*(\d{1,3}|0x[0-9a-f]+)?\M\y.+?\Z";
+        let pattern = preprocess_regex_pattern(raw).expect("should validate");
+        assert!(pattern.contains(r"\d{1,3}"));
+    }
+
     #[test]
     fn escape_sql_like_literal_escapes_wildcards() {
         let escaped = escape_sql_like_literal("100%_done\\");
         assert_eq!(escaped, "100\\%\\_done\\\\");
     }
+
+    #[test]
+    fn name_contains_requires_symbol_substring() {
+        let request = TextSearchRequest::from_query_str("name_contains:Parse lang:rust")
+            .expect("query should plan");
+        let plan = &request.plans[0];
+        assert_eq!(plan.symbol_name_contains, vec!["parse".to_string()]);
+        assert_eq!(
+            plan.required_terms,
+            vec![ContentPredicate::Plain("parse".to_string())]
+        );
+
+        assert!(TextSearchRequest::from_query_str("name_contains:ab").is_err());
+        assert!(TextSearchRequest::from_query_str("needle -name_contains:parse").is_err());
+    }
+
+    #[test]
+    fn symbol_filter_requires_the_exact_symbol() {
+        assert_eq!(
+            parse_query("symbol:parse_query").expect("should parse"),
+            QueryNode::Filter(Filter::Symbol("parse_query".to_string()))
+        );
+
+        let request = TextSearchRequest::from_query_str("symbol:parse_query lang:rust")
+            .expect("query should plan");
+        let plan = &request.plans[0];
+        assert_eq!(plan.symbol_terms, vec!["parse_query".to_string()]);
+        assert_eq!(
+            plan.required_terms,
+            vec![ContentPredicate::Plain("parse_query".to_string())]
+        );
+        assert_eq!(plan.langs, vec!["rust".to_string()]);
+
+        let request =
+            TextSearchRequest::from_query_str("symbol:Parser needle").expect("query should plan");
+        assert_eq!(request.plans[0].symbol_terms, vec!["Parser".to_string()]);
+        assert_eq!(request.plans[0].required_terms.len(), 2);
+
+        assert!(TextSearchRequest::from_query_str("symbol:ab").is_err());
+        assert!(TextSearchRequest::from_query_str("needle -symbol:parse_query").is_err());
+    }
+
+    #[test]
+    fn count_filter_caps_distinct_symbols() {
+        assert_eq!(
+            parse_query("count:5").expect("should parse"),
+            QueryNode::Filter(Filter::Count(5))
+        );
+        assert!(parse_query("count:0").is_err());
+        assert!(parse_query("count:many").is_err());
+
+        let request = TextSearchRequest::from_query_str("symbol:parse_query count:5 lang:rust")
+            .expect("query should plan");
+        assert_eq!(request.plans[0].symbol_count, Some(5));
+        assert!(request.reject_symbol_only_filters().is_err());
+        let symbols = request
+            .symbol_search_request()
+            .expect("query should map to a symbol search");
+        assert_eq!(symbols.name.as_deref(), Some("parse_query"));
+        assert_eq!(symbols.limit, Some(5));
+        assert!(symbols.group_by_name);
+        assert_eq!(symbols.language, Some(vec!["rust".to_string()]));
+
+        let symbols = TextSearchRequest::from_query_str("name_contains:Parse repo:pointer")
+            .expect("query should plan")
+            .symbol_search_request()
+            .expect("query should map to a symbol search");
+        assert_eq!(symbols.name_contains.as_deref(), Some("parse"));
+        assert_eq!(symbols.repository.as_deref(), Some("pointer"));
+        assert_eq!(symbols.limit, None);
+
+        for query in [
+            "symbol:parse_query count:5 -count:2",
+            "symbol:parse_query count:5 count:2",
+            "needle count:5",
+            "lang:rust regex:parse count:5",
+        ] {
+            assert!(TextSearchRequest::from_query_str(query).is_err(), "{query}");
+        }
+        for query in [
+            "symbol:parse_query needle count:5",
+            "symbol:parse_query or symbol:render_page count:5",
+        ] {
+            let request = TextSearchRequest::from_query_str(query).expect("query should plan");
+            assert!(request.symbol_search_request().is_err(), "{query}");
+        }
+    }
+
+    #[test]
+    fn sym_filter_restricts_to_defining_files() {
+        assert_eq!(
+            parse_query("sym:Foo content:bar -sym:Baz").expect("should parse"),
+            QueryNode::And(vec![
+                QueryNode::Filter(Filter::Defines("Foo".to_string())),
+                QueryNode::Filter(Filter::Content("bar".to_string())),
+                QueryNode::Not(Box::new(QueryNode::Filter(Filter::Defines(
+                    "Baz".to_string()
+                )))),
+            ])
+        );
+
+        let request = TextSearchRequest::from_query_str("sym:Foo content:bar -sym:Baz")
+            .expect("query should plan");
+        let plan = &request.plans[0];
+        assert_eq!(plan.defined_symbols, vec!["Foo".to_string()]);
+        assert_eq!(plan.excluded_defined_symbols, vec!["Baz".to_string()]);
+        // Unlike symbol:, the name does not become a content term.
+        assert_eq!(
+            plan.required_terms,
+            vec![ContentPredicate::Plain("bar".to_string())]
+        );
+        assert!(plan.symbol_terms.is_empty());
+
+        let request = TextSearchRequest::from_query_str("(sym:Foo or sym:Qux) needle")
+            .expect("query should plan");
+        let defined: Vec<&[String]> = request
+            .plans
+            .iter()
+            .map(|plan| plan.defined_symbols.as_slice())
+            .collect();
+        assert_eq!(defined, [["Foo".to_string()], ["Qux".to_string()]]);
+
+        assert!(parse_query("sym: needle").is_err());
+        assert!(TextSearchRequest::from_query_str("sym:Foo").is_err());
+    }
+
+    #[test]
+    fn lang_filter_accepts_comma_lists() {
+        let node = parse_query("lang:rust,go").expect("should parse");
+        assert_eq!(
+            node,
+            QueryNode::Filter(Filter::Lang(vec!["rust".to_string(), "go".to_string()]))
+        );
+
+        let request = TextSearchRequest::from_query_str("needle lang:rust, lang:go,rust")
+            .expect("query should plan");
+        assert_eq!(request.plans[0].langs, vec!["rust", "go"]);
+
+        assert!(parse_query("lang:,").is_err());
+    }
+
+    #[test]
+    fn negated_lang_filter_excludes_each_language() {
+        let request = TextSearchRequest::from_query_str("needle lang:rust -lang:generated,proto")
+            .expect("query should plan");
+        let plan = &request.plans[0];
+        assert_eq!(plan.langs, vec!["rust"]);
+        assert_eq!(plan.excluded_langs, vec!["generated", "proto"]);
+    }
+
+    #[test]
+    fn path_depth_filter_parses_each_comparator() {
+        for (value, comparison, depth) in [
+            ("2", DepthComparison::Eq, 2),
+            ("=2", DepthComparison::Eq, 2),
+            ("<3", DepthComparison::Lt, 3),
+            ("<=3", DepthComparison::Le, 3),
+            (">1", DepthComparison::Gt, 1),
+            (">=4", DepthComparison::Ge, 4),
+        ] {
+            let node = parse_query(&format!("path_depth:{value}")).expect("should parse");
+            assert_eq!(
+                node,
+                QueryNode::Filter(Filter::PathDepth(PathDepth { comparison, depth }))
+            );
+        }
+
+        assert!(parse_query("path_depth:deep").is_err());
+        assert!(parse_query("path_depth:<=-1").is_err());
+        assert!(parse_query("path_depth:=>2").is_err());
+        assert!(TextSearchRequest::from_query_str("needle -path_depth:2").is_err());
+
+        let request = TextSearchRequest::from_query_str("needle path_depth:<=2 path_depth:<=2")
+            .expect("query should plan");
+        assert_eq!(
+            request.plans[0].path_depths,
+            vec![PathDepth {
+                comparison: DepthComparison::Le,
+                depth: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn repeated_negated_lang_filters_accumulate() {
+        let request = TextSearchRequest::from_query_str("-lang:rust -lang:go -lang:Rust needle")
+            .expect("query should plan");
+        assert_eq!(request.plans.len(), 1);
+        let plan = &request.plans[0];
+        assert!(plan.langs.is_empty());
+        assert_eq!(plan.excluded_langs, vec!["rust", "go"]);
+    }
+
+    #[test]
+    fn fuzzy_filter_expands_identifier_variants() {
+        assert_eq!(
+            split_identifier_words("parseHTTPQuery2Fast"),
+            Some(vec![
+                "parse".to_string(),
+                "http".to_string(),
+                "query2".to_string(),
+                "fast".to_string(),
+            ])
+        );
+        assert_eq!(split_identifier_words("foo.bar"), None);
+
+        let request = TextSearchRequest::from_query_str("content:parseQuery fuzzy:yes")
+            .expect("query should plan");
+        let plan = &request.plans[0];
+        assert_eq!(
+            plan.required_terms,
+            vec![ContentPredicate::Regex(
+                "parseQuery|ParseQuery|parse_query|PARSE_QUERY|parse-query".to_string()
+            )]
+        );
+        assert_eq!(
+            plan.highlight_pattern,
+            "parseQuery|ParseQuery|parse_query|PARSE_QUERY|parse-query"
+        );
+
+        let request = TextSearchRequest::from_query_str("content:parseQuery needle.rs fuzzy:yes")
+            .expect("query should plan");
+        assert_eq!(
+            request.plans[0].required_terms[1],
+            ContentPredicate::Plain("needle.rs".to_string())
+        );
+
+        let request =
+            TextSearchRequest::from_query_str("content:parseQuery").expect("query should plan");
+        assert_eq!(
+            request.plans[0].required_terms,
+            vec![ContentPredicate::Plain("parseQuery".to_string())]
+        );
+        assert!(TextSearchRequest::from_query_str("parseQuery -fuzzy:yes").is_err());
+        assert!(TextSearchRequest::from_query_str("parseQuery fuzzy:yes fuzzy:no").is_err());
+    }
+
+    #[test]
+    fn word_filter_bounds_terms_at_word_edges() {
+        assert_eq!(
+            parse_query("word:yes").unwrap(),
+            QueryNode::Filter(Filter::Word(true))
+        );
+        assert!(parse_query("word:maybe").is_err());
+
+        let request =
+            TextSearchRequest::from_query_str("content:get word:yes").expect("query should plan");
+        let plan = &request.plans[0];
+        assert_eq!(
+            plan.required_terms,
+            vec![ContentPredicate::Regex(r"\mget\M".to_string())]
+        );
+        assert_eq!(plan.highlight_pattern, r"\mget\M");
+
+        let request = TextSearchRequest::from_query_str(r#"content:"->next()" -old_name word:yes"#)
+            .expect("query should plan");
+        let plan = &request.plans[0];
+        assert_eq!(
+            plan.required_terms,
+            vec![ContentPredicate::Regex(r"->next\(\)".to_string())]
+        );
+        assert_eq!(
+            plan.excluded_terms,
+            vec![ContentPredicate::Regex(r"\mold_name\M".to_string())]
+        );
+
+        let request = TextSearchRequest::from_query_str("content:parseQuery fuzzy:yes word:yes")
+            .expect("query should plan");
+        assert_eq!(
+            request.plans[0].highlight_pattern,
+            r"\m(?:parseQuery|ParseQuery|parse_query|PARSE_QUERY|parse-query)\M"
+        );
+
+        let request =
+            TextSearchRequest::from_query_str("content:get word:no").expect("query should plan");
+        assert_eq!(
+            request.plans[0].required_terms,
+            vec![ContentPredicate::Plain("get".to_string())]
+        );
+        assert!(matches!(
+            TextSearchRequest::from_query_str("regex:foo word:yes"),
+            Err(QueryPlanError::Invalid(_))
+        ));
+        assert!(matches!(
+            TextSearchRequest::from_query_str("content:get -regex:foo word:yes"),
+            Err(QueryPlanError::Invalid(_))
+        ));
+        assert!(TextSearchRequest::from_query_str(r"regex:\mfoo\M word:no").is_ok());
+        assert!(TextSearchRequest::from_query_str("content:get -word:yes").is_err());
+        assert!(TextSearchRequest::from_query_str("content:get word:yes word:no").is_err());
+    }
+
+    #[test]
+    fn grouped_alternatives_each_inherit_outer_filters() {
+        let request = TextSearchRequest::from_query_str("(content:foo or content:bar) lang:rust")
+            .expect("query should plan");
+        assert_eq!(request.plans.len(), 2);
+        for (plan, term) in request.plans.iter().zip(["foo", "bar"]) {
+            assert_eq!(
+                plan.required_terms,
+                vec![ContentPredicate::Plain(term.to_string())]
+            );
+            assert_eq!(plan.langs, vec!["rust".to_string()]);
+        }
+
+        let request = TextSearchRequest::from_query_str(
+            r#"repo:pointer (needle or "two words" file:*.rs) -lang:go"#,
+        )
+        .expect("query should plan");
+        assert_eq!(request.plans.len(), 2);
+        for plan in &request.plans {
+            assert_eq!(plan.repos, vec!["pointer".to_string()]);
+            assert_eq!(plan.excluded_langs, vec!["go".to_string()]);
+        }
+        assert_eq!(
+            request.plans[0].required_terms,
+            vec![ContentPredicate::Plain("needle".to_string())]
+        );
+        assert!(request.plans[0].file_globs.is_empty());
+        assert_eq!(
+            request.plans[1].required_terms,
+            vec![ContentPredicate::Plain("two words".to_string())]
+        );
+        assert_eq!(request.plans[1].file_globs, vec!["%.rs".to_string()]);
+
+        let request = TextSearchRequest::from_query_str("(foo or bar) (baz or qux)")
+            .expect("query should plan");
+        assert_eq!(request.plans.len(), 4);
+
+        let request =
+            TextSearchRequest::from_query_str("needle OR haystack").expect("query should plan");
+        assert_eq!(request.plans.len(), 2);
+
+        // A filter value keeps the parentheses it balances itself.
+        let request = TextSearchRequest::from_query_str("(regex:call(arg) or needle) lang:rust")
+            .expect("query should plan");
+        assert_eq!(request.plans.len(), 2);
+        assert!(matches!(
+            &request.plans[0].required_terms[0],
+            ContentPredicate::Regex(pattern) if pattern.contains("arg")
+        ));
+    }
+
+    #[test]
+    fn malformed_groups_are_parse_errors() {
+        let err = parse_query("((foo or bar) baz) lang:rust").unwrap_err();
+        assert_eq!(err, ParseError::NestedGroup);
+        assert_eq!(
+            TextSearchRequest::from_query_str("(foo (bar or baz))")
+                .unwrap_err()
+                .to_string(),
+            "failed to parse query: Nested parentheses are not supported"
+        );
+
+        assert_eq!(
+            parse_query("(foo or bar"),
+            Err(ParseError::UnmatchedParenthesis)
+        );
+        assert_eq!(
+            parse_query("foo) bar"),
+            Err(ParseError::UnmatchedParenthesis)
+        );
+        assert_eq!(parse_query("foo or"), Err(ParseError::EmptyAlternative));
+        assert_eq!(parse_query("or foo"), Err(ParseError::EmptyAlternative));
+        assert_eq!(parse_query("() foo"), Err(ParseError::EmptyAlternative));
+        assert_eq!(parse_query(""), Err(ParseError::EmptyQuery));
+    }
 }
@@ -1,6 +1,4 @@
 #![recursion_limit = "256"]
-#[cfg(feature = "ssr")]
-pub mod server;
 
 #[cfg(feature = "ssr")]
 #[tokio::main]
@@ -9,7 +7,7 @@ async fn main() -> anyhow::Result<()> {
 
     use clap::Parser;
     dotenvy::dotenv().ok();
-    let config = crate::server::ServerConfig::parse();
+    let config = pointer::server::ServerConfig::parse();
 
     // Initialize logging system with colored output
     use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
@@ -45,8 +43,50 @@ async fn main() -> anyhow::Result<()> {
         .connect(&config.database_url)
         .await
         .context("failed to connect to postgres")?;
+    let read_pool = match &config.read_database_url {
+        Some(url) => Some(
+            PgPoolOptions::new()
+                .max_connections(config.max_connections)
+                .connect(url)
+                .await
+                .context("failed to connect to the postgres read replica")?,
+        ),
+        None => None,
+    };
+
+    let schema_check = match pointer::db::postgres::check_schema(
+        read_pool.as_ref().unwrap_or(&pool),
+        config.lowercase_content_search,
+    )
+    .await
+    {
+        Ok(check) => {
+            for problem in check.problems() {
+                tracing::warn!("database schema check: {problem}");
+            }
+            Some(check)
+        }
+        Err(err) => {
+            tracing::warn!("could not check the database schema: {err}");
+            None
+        }
+    };
 
-    let state = Arc::new(pointer::server::AppState { pool });
+    let state = Arc::new(pointer::server::AppState {
+        pool,
+        read_pool,
+        max_snippet_chars: config.max_snippet_chars,
+        default_excluded_dirs: config.default_excluded_dirs.clone(),
+        min_symbol_length: config.min_symbol_length,
+        max_snippet_context: config.max_snippet_context,
+        db_breakers: Arc::new(pointer::db::guarded::DbCircuitBreakers::new(
+            config.breaker_config(),
+        )),
+        repo_overviews: Default::default(),
+        symbol_cache: config.symbol_cache(),
+        lowercase_content_search: config.lowercase_content_search,
+        schema_check,
+    });
     let file_state = state.clone();
     let render_state = state.clone();
 
@@ -70,6 +110,18 @@ async fn main() -> anyhow::Result<()> {
             move || shell(val.clone())
         })
         .merge(mcp::server::router(state.clone()))
+        .merge(pointer::server::readiness_router(state.clone()))
+        .merge(pointer::server::graph_router(state.clone()))
+        .merge(pointer::server::definitions_router(state.clone()))
+        .merge(pointer::server::symbols_router(state.clone()))
+        .merge(pointer::server::files_router(state.clone()))
+        .merge(pointer::server::raw_router(state.clone()));
+    let app = if config.enable_admin_explain {
+        app.merge(pointer::server::admin_router(state.clone()))
+    } else {
+        app
+    };
+    let app = app
         .fallback(leptos_axum::file_and_error_handler_with_context(
             move || provide_context(file_state.clone()),
             shell,
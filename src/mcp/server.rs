@@ -409,7 +409,7 @@ fn mcp_tools() -> Vec<Value> {
                 "properties": {
                     "repo": { "type": "string", "description": "Exact repository key from repositories. Example: \"pointer\"." },
                     "branch": { "type": "string", "description": "Exact branch name from repo_branches. Example: \"main\"." },
-                    "lang": { "type": "string", "description": "Language filter. Example: \"rust\"." },
+                    "lang": { "type": "string", "description": "Language filter; comma-separate several. Example: \"rust,go\"." },
                     "path": { "type": "string", "description": "Glob-like path filter only. Example: \"src/mcp/**\". Do not use this for path substring lookup." },
                     "file": { "type": "string", "description": "Glob-like filename/path filter. Example: \"*.rs\"." },
                     "regex": { "type": "string", "description": "Content regex pattern only. Do not prefix with `regex:`. JSON-escape backslashes, for example \"\\\\bQueryParser\\\\(\"." },
@@ -4,8 +4,8 @@ use serde_json::{Map, Value, json};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use tokio::time::{Duration, timeout};
 
+use crate::db::Database;
 use crate::db::models::{FacetCount, SearchResult, SearchResultsPage};
-use crate::db::{Database, postgres::PostgresDb};
 use crate::mcp::types::{
     ApiResponse, BranchFreshness, FileContentSnippet, FileContentToolRequest,
     FileContentToolResponse, FileListEntry, FileListToolRequest, FileListToolResponse,
@@ -90,8 +90,7 @@ pub async fn execute_file_content(
     payload: FileContentToolRequest,
 ) -> Result<FileContentToolResponse, String> {
     let state = leptos::prelude::expect_context::<crate::server::GlobalAppState>();
-    let pool = state.pool.clone();
-    let db = PostgresDb::new(pool);
+    let db = state.db();
 
     let commit = db
         .resolve_branch_head(&payload.repo, &payload.branch)
@@ -103,6 +102,9 @@ pub async fn execute_file_content(
         .get_file_content(&payload.repo, &commit, &payload.path)
         .await
         .map_err(|err| err.to_string())?;
+    if raw.is_binary {
+        return Err(format!("{} is a binary file", raw.file_path));
+    }
 
     let line_count = raw.content.lines().count();
     let (content, snippet, returned_line_count, message) = slice_file_content(
@@ -192,8 +194,8 @@ pub async fn execute_file_list(
     payload: FileListToolRequest,
 ) -> Result<FileListToolResponse, String> {
     let state = leptos::prelude::expect_context::<crate::server::GlobalAppState>();
-    let pool = state.pool.clone();
-    let db = PostgresDb::new(pool.clone());
+    let pool = state.read_pool().clone();
+    let db = state.db();
 
     let commit = db
         .resolve_branch_head(&payload.repo, &payload.branch)
@@ -692,7 +694,9 @@ async fn execute_single_search(
         "mcp search query"
     );
 
-    let page_data = search(query, page).await.map_err(|err| err.to_string())?;
+    let page_data = search(query, page, None, None)
+        .await
+        .map_err(|err| err.to_string())?;
 
     let mut freshness = freshness_from_search_results(&page_data.results);
     if freshness.indexed_at.is_none() {
@@ -742,12 +746,14 @@ async fn execute_single_search(
         "page": page_data.page,
         "page_size": page_data.page_size,
         "has_more": has_more,
+        "total_estimate": page_data.total_estimate,
         "results": truncation_state.results,
         "stats": page_data.stats,
         "facets": {
             "common_directories": page_data.stats.common_directories,
             "top_repositories": page_data.stats.top_repositories,
             "top_branches": page_data.stats.top_branches,
+            "top_languages": page_data.stats.top_languages,
             "top_filetypes": top_filetypes,
         },
         "index_freshness": freshness,
@@ -781,7 +787,7 @@ async fn execute_batch_search(
 
     let mut pages: Vec<(String, SearchResultsPage)> = Vec::with_capacity(queries.len());
     for query in &queries {
-        let page = search(query.query.clone(), 1)
+        let page = search(query.query.clone(), 1, None, None)
             .await
             .map_err(|err| err.to_string())?;
         pages.push((query.any_term.clone(), page));
@@ -1,5 +1,5 @@
 use crate::db::TreeEntry;
-use leptos::either::{Either, EitherOf4};
+use leptos::either::{Either, EitherOf3, EitherOf5};
 use leptos::prelude::*;
 use leptos_router::components::A;
 use leptos_router::hooks::use_params;
@@ -9,9 +9,14 @@ use std::collections::HashSet;
 
 use crate::components::breadcrumbs::{Breadcrumbs, CopyPathButton};
 use crate::components::code_intel_panel::CodeIntelPanel;
-use crate::components::file_content::FileContent;
-use crate::components::file_tree::{DirectoryIcon, FileIcon, FileTreeNode};
+use crate::components::editor_link::OpenInEditorButton;
+use crate::components::file_content::{FileContent, SymbolLink};
+use crate::components::file_tree::{
+    DirectoryIcon, FileIcon, FileTreeNode, SymlinkIcon, reveal_path,
+};
 use crate::components::quick_navigator::FileQuickNavigator;
+use crate::components::repo_overview::RepoOverviewCard;
+use crate::db::models::RepoOverview;
 
 #[derive(Params, PartialEq, Clone, Debug)]
 pub struct FileViewerParams {
@@ -27,16 +32,78 @@ pub enum FileViewerData {
         line_count: usize,
         language: Option<String>,
         content: String,
+        symbol_links: Vec<SymbolLink>,
     },
     Binary {
         download_url: String,
     },
+    Symlink {
+        target: String,
+        /// The target as a repository path, when it stays inside the repo.
+        resolved: Option<String>,
+    },
     Directory {
         entries: Vec<TreeEntry>,
         readme: Option<String>,
     },
 }
 
+/// The commit the file viewer is showing. `reference` is the branch name or
+/// SHA taken from the route and `commit` is what it resolved to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewedCommit {
+    pub reference: String,
+    pub commit: String,
+    pub is_live_head: bool,
+}
+
+impl ViewedCommit {
+    pub fn from_resolution(
+        reference: String,
+        branch_head: Option<String>,
+        live_heads: &[String],
+    ) -> Self {
+        match branch_head {
+            Some(commit) => Self {
+                reference,
+                commit,
+                is_live_head: true,
+            },
+            None => Self {
+                is_live_head: live_heads.contains(&reference),
+                commit: reference.clone(),
+                reference,
+            },
+        }
+    }
+
+    pub fn short_sha(&self) -> &str {
+        self.commit.get(..7).unwrap_or(&self.commit)
+    }
+}
+
+/// Shares the resolved commit with everything rendered inside the file viewer
+/// so the tree, navigator and code intel panel all read the same snapshot.
+#[derive(Clone, Copy)]
+pub struct ViewedCommitContext(pub Signal<Option<ViewedCommit>>);
+
+/// Picks the revision to fetch with: the resolved SHA when it belongs to the
+/// current route, otherwise the route's branch or commit as-is.
+pub fn fetch_revision(viewed: Option<&ViewedCommit>, route_ref: &str) -> String {
+    viewed
+        .filter(|viewed| viewed.reference == route_ref)
+        .map(|viewed| viewed.commit.clone())
+        .unwrap_or_else(|| route_ref.to_string())
+}
+
+pub fn use_viewed_commit(branch: Signal<String>) -> Signal<String> {
+    let context = use_context::<ViewedCommitContext>();
+    Signal::derive(move || {
+        let viewed = context.and_then(|ctx| ctx.0.get());
+        fetch_revision(viewed.as_ref(), &branch.get())
+    })
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SymbolSearchScope {
     Repository,
@@ -59,6 +126,24 @@ pub struct SymbolInsightsParams {
     pub excluded_paths: Vec<String>,
 }
 
+/// References the code intel panel shows per definition before offering to
+/// load more.
+pub const REFERENCE_PAGE_SIZE: u32 = 100;
+
+/// Where to resume listing a definition's references.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SymbolReferencePageParams {
+    pub repository: String,
+    pub commit_sha: String,
+    pub fully_qualified: String,
+    /// File and position of the definition, which pin down the symbol.
+    pub file_path: String,
+    pub line: usize,
+    pub column: Option<usize>,
+    /// References already shown.
+    pub offset: u32,
+}
+
 impl SymbolSearchScope {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -115,10 +200,67 @@ impl<'de> Deserialize<'de> for SymbolSearchScope {
     }
 }
 
-#[cfg(feature = "ssr")]
-fn is_binary(content: &str) -> bool {
-    // Simple heuristic: check for NUL byte.
-    content.as_bytes().contains(&0)
+#[server]
+pub async fn resolve_viewed_commit(
+    repo: String,
+    branch: String,
+) -> Result<ViewedCommit, ServerFnError> {
+    use crate::db::Database;
+
+    let state = expect_context::<crate::server::GlobalAppState>();
+    let db = state.db();
+
+    let branch_head = db
+        .resolve_branch_head(&repo, &branch)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let live_heads: Vec<String> = if branch_head.is_some() {
+        Vec::new()
+    } else {
+        db.get_branches_for_repository(&repo)
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+            .into_iter()
+            .map(|info| info.commit_sha)
+            .collect()
+    };
+
+    Ok(ViewedCommit::from_resolution(
+        branch,
+        branch_head,
+        &live_heads,
+    ))
+}
+
+/// Index-derived overview for the repository landing page. Cached per
+/// resolved commit since indexed snapshots never change.
+#[server]
+pub async fn get_repo_overview(
+    repo: String,
+    branch: String,
+) -> Result<RepoOverview, ServerFnError> {
+    use crate::db::Database;
+
+    let state = expect_context::<crate::server::GlobalAppState>();
+    let db = state.db();
+
+    let commit = db
+        .resolve_branch_head(&repo, &branch)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?
+        .unwrap_or(branch);
+    if let Some(overview) = state.repo_overviews.get(&repo, &commit) {
+        return Ok(overview);
+    }
+
+    let overview = db
+        .get_repo_overview(&repo, &commit)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    state
+        .repo_overviews
+        .insert(&repo, &commit, overview.clone());
+    Ok(overview)
 }
 
 #[server]
@@ -127,11 +269,12 @@ pub async fn get_file_viewer_data(
     branch: String,
     path: Option<String>,
 ) -> Result<FileViewerData, ServerFnError> {
-    use crate::db::{Database, RepoTreeQuery, postgres::PostgresDb};
+    use crate::components::file_content::{identifier_tokens, link_symbol_tokens};
+    use crate::db::{Database, RepoTreeQuery};
     use std::path::Path;
 
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
+    let db = state.db();
 
     let commit = db
         .resolve_branch_head(&repo, &branch)
@@ -183,7 +326,7 @@ pub async fn get_file_viewer_data(
             .await
             .map_err(|e| ServerFnError::new(e.to_string()))?;
 
-        if file_content.language.is_none() && is_binary(&file_content.content) {
+        if file_content.is_binary {
             let download_url = format!(
                 "/api/download_raw?repo={}&branch={}&path={}",
                 repo, commit, path_str
@@ -191,6 +334,11 @@ pub async fn get_file_viewer_data(
             return Ok(FileViewerData::Binary { download_url });
         }
 
+        if let Some(target) = file_content.symlink_target {
+            let resolved = resolve_symlink_target(&path_str, &target);
+            return Ok(FileViewerData::Symlink { target, resolved });
+        }
+
         // For text files, we'll add line numbers.
         let line_count = file_content.content.lines().count();
 
@@ -212,15 +360,83 @@ pub async fn get_file_viewer_data(
             .unwrap();
         let html = highlight(&file_content.content, formatter);
 
+        // Links are an enhancement; the file still renders without them.
+        let references = db
+            .get_file_references(&repo, &commit, &path_str)
+            .await
+            .unwrap_or_else(|err| {
+                tracing::warn!("Failed to load symbol references for {path_str}: {err}");
+                Vec::new()
+            });
+        let symbol_links =
+            link_symbol_tokens(&identifier_tokens(&file_content.content), &references);
+
         Ok(FileViewerData::File {
             html,
             line_count,
             language: file_content.language.clone(),
             content: file_content.content.clone(),
+            symbol_links,
         })
     }
 }
 
+/// Resolves a symlink `target` against the directory holding `path`. Absolute
+/// targets and targets that climb above the repository root have no
+/// repository path.
+pub fn resolve_symlink_target(path: &str, target: &str) -> Option<String> {
+    if target.starts_with('/') {
+        return None;
+    }
+    let mut segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    segments.pop();
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            other => segments.push(other),
+        }
+    }
+    (!segments.is_empty()).then(|| segments.join("/"))
+}
+
+/// Backs the viewer's find bar: literal matches within the open file only.
+#[server]
+pub async fn find_in_file(
+    repo: String,
+    branch: String,
+    path: String,
+    pattern: String,
+    case: Option<crate::dsl::CaseSensitivity>,
+) -> Result<Vec<crate::db::models::InFileMatch>, ServerFnError> {
+    use crate::db::Database;
+
+    if pattern.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let state = expect_context::<crate::server::GlobalAppState>();
+    let db = state.db();
+
+    let commit = db
+        .resolve_branch_head(&repo, &branch)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?
+        .unwrap_or_else(|| branch.clone());
+
+    db.search_in_file(
+        &repo,
+        &commit,
+        &path,
+        &pattern,
+        case.unwrap_or(crate::dsl::CaseSensitivity::Auto),
+    )
+    .await
+    .map_err(|e| ServerFnError::new(e.to_string()))
+}
+
 #[server]
 pub async fn search_repo_paths(
     repo: String,
@@ -228,7 +444,7 @@ pub async fn search_repo_paths(
     query: String,
     limit: Option<u16>,
 ) -> Result<Vec<TreeEntry>, ServerFnError> {
-    use crate::db::{Database, postgres::PostgresDb};
+    use crate::db::Database;
 
     let trimmed = query.trim();
     if trimmed.is_empty() {
@@ -236,7 +452,7 @@ pub async fn search_repo_paths(
     }
 
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
+    let db = state.db();
 
     let commit = db
         .resolve_branch_head(&repo, &branch)
@@ -255,17 +471,15 @@ pub async fn fetch_symbol_insights(
     params: SymbolInsightsParams,
 ) -> Result<crate::components::code_intel_panel::SymbolInsightsResponse, ServerFnError> {
     use crate::components::breadcrumbs::directory_prefix;
-    use crate::components::code_intel_panel::{
-        SymbolInsightsResponse, SymbolMatch, SymbolReferenceWithSnippet,
-    };
-    use crate::db::{Database, SearchRequest, models::FileReference, postgres::PostgresDb};
+    use crate::components::code_intel_panel::{SymbolInsightsResponse, SymbolMatch};
+    use crate::db::{Database, SearchRequest, models::FileReference};
 
     if params.symbol.trim().is_empty() {
         return Err(ServerFnError::new("symbol cannot be empty"));
     }
 
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
+    let db = state.db();
 
     let commit = db
         .resolve_branch_head(&params.repo, &params.branch)
@@ -277,6 +491,7 @@ pub async fn fetch_symbol_insights(
         q: None,
         name: Some(params.symbol.clone()),
         name_regex: None,
+        name_contains: None,
         namespace: None,
         namespace_prefix: None,
         kind: None,
@@ -286,10 +501,13 @@ pub async fn fetch_symbol_insights(
         path: None,
         path_regex: None,
         path_hint: None,
+        path_depth: None,
         include_paths: params.include_paths.clone(),
         excluded_paths: params.excluded_paths.clone(),
         include_references: Some(true),
         limit: Some(50),
+        unreferenced: false,
+        group_by_name: false,
     };
 
     let dir_hint = params.path.as_deref().and_then(directory_prefix);
@@ -334,63 +552,31 @@ pub async fn fetch_symbol_insights(
 
     for mut definition in search_response.symbols {
         let references = definition.references.take().unwrap_or_default();
-
-        let mut reference_entries = Vec::with_capacity(references.len());
-        let mut snippet_requests = Vec::with_capacity(references.len());
-
-        for reference in references {
-            let line = reference.line.max(1);
-            let file_reference = FileReference {
-                repository: reference.repository.clone(),
-                commit_sha: reference.commit_sha.clone(),
-                file_path: reference.file_path.clone(),
-                namespace: reference.namespace.clone(),
-                name: reference.name.clone(),
-                kind: reference.kind.clone(),
+        let total_references = references.len();
+
+        // Only the first page gets snippets; the panel loads the rest on
+        // demand through `fetch_symbol_reference_page`.
+        let reference_entries: Vec<FileReference> = references
+            .into_iter()
+            .take(REFERENCE_PAGE_SIZE as usize)
+            .map(|reference| FileReference {
+                repository: reference.repository,
+                commit_sha: reference.commit_sha,
+                file_path: reference.file_path,
+                namespace: reference.namespace,
+                name: reference.name,
+                kind: reference.kind,
                 line: reference.line.try_into().unwrap_or(i32::MAX),
                 column: reference.column.try_into().unwrap_or(i32::MAX),
-            };
-
-            snippet_requests.push(crate::db::SnippetRequest {
-                repository: file_reference.repository.clone(),
-                commit_sha: file_reference.commit_sha.clone(),
-                file_path: file_reference.file_path.clone(),
-                line: line.max(1) as u32,
-                context: Some(1),
-                highlight: Some(reference.name.clone()),
-                case_sensitive: Some(true),
-            });
-
-            reference_entries.push(file_reference);
-        }
-
-        let snippet_responses = if snippet_requests.is_empty() {
-            Vec::new()
-        } else {
-            match db.get_file_snippets(snippet_requests).await {
-                Ok(snippets) => snippets,
-                Err(err) => {
-                    tracing::warn!(
-                        "Failed to fetch snippets for {} references: {err}",
-                        reference_entries.len()
-                    );
-                    Vec::new()
-                }
-            }
-        };
-
-        let mut enriched = Vec::with_capacity(reference_entries.len());
-        for (idx, file_reference) in reference_entries.into_iter().enumerate() {
-            let snippet = snippet_responses.get(idx).cloned();
-            enriched.push(SymbolReferenceWithSnippet {
-                reference: file_reference,
-                snippet,
-            });
-        }
+            })
+            .collect();
+        let has_more_references = reference_entries.len() < total_references;
 
         matches.push(SymbolMatch {
             definition,
-            references: enriched,
+            references: with_snippets(&db, reference_entries).await,
+            total_references,
+            has_more_references,
         });
     }
 
@@ -401,6 +587,85 @@ pub async fn fetch_symbol_insights(
     })
 }
 
+/// Loads one page of a definition's references, picking up where the code
+/// intel panel's initial `REFERENCE_PAGE_SIZE` references left off.
+#[server]
+pub async fn fetch_symbol_reference_page(
+    params: SymbolReferencePageParams,
+) -> Result<crate::components::code_intel_panel::SymbolReferencePage, ServerFnError> {
+    use crate::components::code_intel_panel::SymbolReferencePage;
+    use crate::db::{Database, SymbolReferenceRequest};
+
+    let state = expect_context::<crate::server::GlobalAppState>();
+    let db = state.db();
+    let response = db
+        .get_symbol_references(SymbolReferenceRequest {
+            repository: params.repository,
+            commit_sha: params.commit_sha,
+            fully_qualified: params.fully_qualified,
+            file_path: Some(params.file_path),
+            line: Some(params.line),
+            column: params.column,
+            limit: Some(REFERENCE_PAGE_SIZE),
+            offset: Some(params.offset),
+        })
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    Ok(SymbolReferencePage {
+        references: with_snippets(&db, response.references).await,
+        has_more: response.has_more,
+    })
+}
+
+/// Pairs each reference with a short snippet around it. Snippet failures are
+/// logged and leave every reference without one.
+#[cfg(feature = "ssr")]
+async fn with_snippets(
+    db: &impl crate::db::Database,
+    references: Vec<crate::db::models::FileReference>,
+) -> Vec<crate::components::code_intel_panel::SymbolReferenceWithSnippet> {
+    use crate::components::code_intel_panel::SymbolReferenceWithSnippet;
+
+    let snippet_requests: Vec<crate::db::SnippetRequest> = references
+        .iter()
+        .map(|reference| crate::db::SnippetRequest {
+            repository: reference.repository.clone(),
+            commit_sha: reference.commit_sha.clone(),
+            file_path: reference.file_path.clone(),
+            line: reference.line.max(1) as u32,
+            column: u32::try_from(reference.column).ok(),
+            context: Some(1),
+            highlight: Some(reference.name.clone()),
+            case_sensitive: Some(true),
+        })
+        .collect();
+
+    let snippet_responses = if snippet_requests.is_empty() {
+        Vec::new()
+    } else {
+        match db.get_file_snippets(snippet_requests).await {
+            Ok(snippets) => snippets,
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to fetch snippets for {} references: {err}",
+                    references.len()
+                );
+                Vec::new()
+            }
+        }
+    };
+
+    references
+        .into_iter()
+        .enumerate()
+        .map(|(idx, reference)| SymbolReferenceWithSnippet {
+            reference,
+            snippet: snippet_responses.get(idx).cloned(),
+        })
+        .collect()
+}
+
 #[cfg(feature = "pulldown-cmark")]
 pub fn render_markdown(markdown: &str) -> String {
     use pulldown_cmark::{Options, Parser, html};
@@ -453,13 +718,45 @@ pub fn FileViewer() -> impl IntoView {
             .flatten()
     });
 
+    // Resolve branch names once so every panel reads the same snapshot, even
+    // if the branch moves while the page is open.
+    let viewed_commit = Resource::new(
+        move || (repo(), branch()),
+        |(repo, branch)| resolve_viewed_commit(repo, branch),
+    );
+    let viewed = Signal::derive(move || viewed_commit.get().and_then(Result::ok));
+    provide_context(ViewedCommitContext(viewed));
+
     // Resource for the main content panel (right side)
     let repo_for_data = repo.clone();
     let branch_for_data = branch.clone();
     let path_for_data = path.clone();
     let data_resource = Resource::new(
         move || (repo_for_data(), branch_for_data(), path_for_data()),
-        |(repo, branch, path)| get_file_viewer_data(repo, branch, path),
+        move |(repo, branch, path)| async move {
+            let viewed = viewed_commit.await.ok();
+            let commit = fetch_revision(viewed.as_ref(), &branch);
+            get_file_viewer_data(repo, commit, path).await
+        },
+    );
+
+    // The overview card only shows on the repository root.
+    let overview_resource = Resource::new(
+        move || {
+            (
+                repo(),
+                branch(),
+                path().unwrap_or_default().trim_matches('/').is_empty(),
+            )
+        },
+        move |(repo, branch, is_root)| async move {
+            if !is_root {
+                return Ok(None);
+            }
+            let viewed = viewed_commit.await.ok();
+            let commit = fetch_revision(viewed.as_ref(), &branch);
+            get_repo_overview(repo, commit).await.map(Some)
+        },
     );
 
     // Resource for the file tree (left side), always fetching the root
@@ -467,10 +764,25 @@ pub fn FileViewer() -> impl IntoView {
     let branch_for_tree = branch.clone();
     let tree_resource = Resource::new(
         move || (repo_for_tree(), branch_for_tree()),
-        |(repo, branch)| get_file_viewer_data(repo, branch, Some("".to_string())),
+        move |(repo, branch)| async move {
+            let viewed = viewed_commit.await.ok();
+            let commit = fetch_revision(viewed.as_ref(), &branch);
+            get_file_viewer_data(repo, commit, Some("".to_string())).await
+        },
     );
 
     let expanded_dirs = RwSignal::new(HashSet::<String>::new());
+    // Opening a path (e.g. from the quick navigator) expands the tree down to it.
+    let reveal = Signal::derive(move || {
+        path()
+            .map(|p| p.trim_matches('/').to_string())
+            .filter(|p| !p.is_empty())
+    });
+    Effect::new(move |_| {
+        if let Some(target) = reveal.get() {
+            reveal_path(expanded_dirs, &target);
+        }
+    });
     let selected_symbol = RwSignal::new(None::<String>);
     let file_language = RwSignal::new(None::<String>);
     let included_paths = RwSignal::new(Vec::<String>::new());
@@ -495,6 +807,9 @@ pub fn FileViewer() -> impl IntoView {
             Some(Ok(FileViewerData::Binary { .. })) => {
                 format!("Binary · {context_label} · Pointer")
             }
+            Some(Ok(FileViewerData::Symlink { .. })) => {
+                format!("Symlink · {context_label} · Pointer")
+            }
             Some(Ok(FileViewerData::Directory { .. })) => {
                 format!("Directory · {context_label} · Pointer")
             }
@@ -535,6 +850,22 @@ pub fn FileViewer() -> impl IntoView {
                         <h2 class="text-xl font-semibold mb-4 text-gray-800 dark:text-gray-200">
                             "Files"
                         </h2>
+                        {move || {
+                            viewed
+                                .get()
+                                .filter(|viewed| !viewed.is_live_head)
+                                .map(|viewed| {
+                                    view! {
+                                        <div
+                                            class="mb-2 inline-flex w-fit items-center gap-1 rounded-full border border-amber-300 dark:border-amber-600 bg-amber-50 dark:bg-amber-900/40 px-2 py-0.5 text-xs text-amber-800 dark:text-amber-200"
+                                            title=format!("Viewing commit {}, not the live branch head", viewed.commit)
+                                        >
+                                            "viewing "
+                                            <span class="font-mono">{viewed.short_sha().to_string()}</span>
+                                        </div>
+                                    }
+                                })
+                        }}
                         <FileQuickNavigator repo=repo.into() branch=branch.into() />
                         <div class="flex-1 min-h-0 overflow-y-auto pr-1">
                             <Suspense fallback=move || {
@@ -558,6 +889,7 @@ pub fn FileViewer() -> impl IntoView {
                                                                             repo=repo.into()
                                                                             branch=branch.into()
                                                                             expanded=expanded_dirs
+                                                                            reveal=reveal
                                                                         />
                                                                     }
                                                                 }
@@ -591,8 +923,9 @@ pub fn FileViewer() -> impl IntoView {
                                                         line_count,
                                                         language,
                                                         content,
+                                                        symbol_links,
                                                     } => {
-                                                        EitherOf4::A(
+                                                        EitherOf5::A(
                                                             view! {
                                                                 <div class="bg-white dark:bg-gray-800 rounded-lg shadow border border-gray-200 dark:border-gray-700 p-4">
                                                                     <FileContent
@@ -601,13 +934,14 @@ pub fn FileViewer() -> impl IntoView {
                                                                         selected_symbol=selected_symbol
                                                                         content=content
                                                                         language=language
+                                                                        symbol_links=symbol_links
                                                                     />
                                                                 </div>
                                                             },
                                                         )
                                                     }
                                                     FileViewerData::Binary { download_url } => {
-                                                        EitherOf4::B(
+                                                        EitherOf5::B(
                                                             view! {
                                                                 <div class="bg-white dark:bg-gray-800 rounded-lg shadow p-8 border border-gray-200 dark:border-gray-700 text-center">
                                                                     <p class="mb-4">
@@ -623,8 +957,42 @@ pub fn FileViewer() -> impl IntoView {
                                                             },
                                                         )
                                                     }
+                                                    FileViewerData::Symlink { target, resolved } => {
+                                                        EitherOf5::E(
+                                                            view! {
+                                                                <div class="bg-white dark:bg-gray-800 rounded-lg shadow p-8 border border-gray-200 dark:border-gray-700 flex items-center gap-2">
+                                                                    <SymlinkIcon />
+                                                                    <span>"Symbolic link to"</span>
+                                                                    {match resolved {
+                                                                        Some(resolved) => {
+                                                                            Either::Left(
+                                                                                view! {
+                                                                                    <A
+                                                                                        href=format!(
+                                                                                            "/repo/{}/tree/{}/{}",
+                                                                                            repo(),
+                                                                                            branch(),
+                                                                                            resolved,
+                                                                                        )
+                                                                                        attr:class="font-mono text-blue-600 hover:underline"
+                                                                                    >
+                                                                                        {target}
+                                                                                    </A>
+                                                                                },
+                                                                            )
+                                                                        }
+                                                                        None => {
+                                                                            Either::Right(
+                                                                                view! { <span class="font-mono">{target}</span> },
+                                                                            )
+                                                                        }
+                                                                    }}
+                                                                </div>
+                                                            },
+                                                        )
+                                                    }
                                                     FileViewerData::Directory { entries, readme } => {
-                                                        EitherOf4::C(
+                                                        EitherOf5::C(
                                                             view! {
                                                                 // Top half: File list
                                                                 <div class="bg-white dark:bg-gray-800 rounded-lg shadow p-4 border border-gray-200 dark:border-gray-700 mb-6">
@@ -641,10 +1009,10 @@ pub fn FileViewer() -> impl IntoView {
                                                                                 if entry.kind == "dir" {
                                                                                     link.push('/');
                                                                                 }
-                                                                                let icon = if entry.kind == "dir" {
-                                                                                    Either::Left(view! { <DirectoryIcon /> })
-                                                                                } else {
-                                                                                    Either::Right(view! { <FileIcon /> })
+                                                                                let icon = match entry.kind.as_str() {
+                                                                                    "dir" => EitherOf3::A(view! { <DirectoryIcon /> }),
+                                                                                    "symlink" => EitherOf3::B(view! { <SymlinkIcon /> }),
+                                                                                    _ => EitherOf3::C(view! { <FileIcon /> }),
                                                                                 };
                                                                                 let name = entry.name.clone();
                                                                                 view! {
@@ -661,6 +1029,21 @@ pub fn FileViewer() -> impl IntoView {
                                                                             .collect_view()}
                                                                     </div>
                                                                 </div>
+                                                                {move || {
+                                                                    overview_resource
+                                                                        .get()
+                                                                        .and_then(Result::ok)
+                                                                        .flatten()
+                                                                        .map(|overview| {
+                                                                            view! {
+                                                                                <RepoOverviewCard
+                                                                                    repo=repo()
+                                                                                    branch=branch()
+                                                                                    overview=overview
+                                                                                />
+                                                                            }
+                                                                        })
+                                                                }}
                                                                 // Bottom half: README
                                                                 {readme
                                                                     .map(|readme_content| {
@@ -682,7 +1065,7 @@ pub fn FileViewer() -> impl IntoView {
                                                 }
                                             }
                                             Err(e) => {
-                                                EitherOf4::D(
+                                                EitherOf5::D(
                                                     view! {
                                                         <p class="text-red-500">"Error: " {e.to_string()}</p>
                                                     },
@@ -696,6 +1079,19 @@ pub fn FileViewer() -> impl IntoView {
                             <CopyPathButton path=Signal::derive(move || {
                                 path().unwrap_or_default()
                             }) />
+                            {move || {
+                                let file_path = path().unwrap_or_default();
+                                let is_file = !file_path.is_empty() && !file_path.ends_with('/');
+                                is_file
+                                    .then(|| {
+                                        view! {
+                                            <OpenInEditorButton
+                                                repo=repo()
+                                                path=file_path.trim_matches('/').to_string()
+                                            />
+                                        }
+                                    })
+                            }}
                             <CodeIntelPanel
                                 repo=repo.into()
                                 branch=branch.into()
@@ -712,3 +1108,57 @@ pub fn FileViewer() -> impl IntoView {
         </main>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEAD: &str = "4f2c9e1d0b7a8c6e5f4d3c2b1a0f9e8d7c6b5a49";
+    const OLD: &str = "0a1b2c3d4e5f60718293a4b5c6d7e8f901234567";
+
+    #[test]
+    fn symlink_targets_resolve_against_the_link_directory() {
+        assert_eq!(
+            resolve_symlink_target("docs/latest.md", "guide.md").as_deref(),
+            Some("docs/guide.md")
+        );
+        assert_eq!(
+            resolve_symlink_target("docs/api/index.md", "../../README.md").as_deref(),
+            Some("README.md")
+        );
+        assert_eq!(
+            resolve_symlink_target("bin/tool", "./../scripts/tool.sh").as_deref(),
+            Some("scripts/tool.sh")
+        );
+        assert_eq!(resolve_symlink_target("docs/latest.md", "../../etc"), None);
+        assert_eq!(
+            resolve_symlink_target("docs/latest.md", "/etc/passwd"),
+            None
+        );
+    }
+
+    #[test]
+    fn branch_route_fetches_with_resolved_head() {
+        let viewed = ViewedCommit::from_resolution("main".to_string(), Some(HEAD.to_string()), &[]);
+        assert!(viewed.is_live_head);
+        assert_eq!(fetch_revision(Some(&viewed), "main"), HEAD);
+    }
+
+    #[test]
+    fn sha_route_fetches_with_viewed_commit() {
+        let viewed = ViewedCommit::from_resolution(OLD.to_string(), None, &[HEAD.to_string()]);
+        assert!(!viewed.is_live_head);
+        assert_eq!(viewed.short_sha(), "0a1b2c3");
+        assert_eq!(fetch_revision(Some(&viewed), OLD), OLD);
+
+        let live = ViewedCommit::from_resolution(HEAD.to_string(), None, &[HEAD.to_string()]);
+        assert!(live.is_live_head);
+    }
+
+    #[test]
+    fn stale_resolution_is_ignored_after_route_change() {
+        let viewed = ViewedCommit::from_resolution("main".to_string(), Some(HEAD.to_string()), &[]);
+        assert_eq!(fetch_revision(Some(&viewed), "release"), "release");
+        assert_eq!(fetch_revision(None, "release"), "release");
+    }
+}
@@ -6,6 +6,8 @@ use leptos_router::hooks::use_params;
 use leptos_router::params::Params;
 use serde::{Deserialize, Serialize};
 
+use crate::db::models::CommitMetadata;
+
 #[derive(Params, Debug, PartialEq)]
 struct RepoParams {
     repo: String,
@@ -19,23 +21,40 @@ pub struct RepoBranchDisplay {
     pub commit_sha: String,
     pub indexed_at: Option<String>,
     pub is_live: bool,
+    pub commit: Option<CommitMetadata>,
 }
 
 #[server]
 pub async fn get_repo_branches(repo: String) -> Result<Vec<RepoBranchDisplay>, ServerFnError> {
-    use crate::db::{Database, postgres::PostgresDb};
+    use crate::db::Database;
 
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
+    let db = state.db();
 
     let branches = db
         .get_branches_for_repository(&repo)
         .await
         .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let heads: Vec<String> = branches
+        .iter()
+        .map(|branch| branch.commit_sha.clone())
+        .collect();
+    // Heads indexed before commit metadata existed just show their SHA.
+    let commits: std::collections::HashMap<String, CommitMetadata> = db
+        .get_commit_metadata(&repo, &heads)
+        .await
+        .unwrap_or_else(|err| {
+            tracing::warn!(error = %err, repository = %repo, "failed to load commit metadata");
+            Vec::new()
+        })
+        .into_iter()
+        .map(|commit| (commit.commit_sha.clone(), commit))
+        .collect();
 
     Ok(branches
         .into_iter()
         .map(|branch| RepoBranchDisplay {
+            commit: commits.get(&branch.commit_sha).cloned(),
             name: branch.name,
             commit_sha: branch.commit_sha,
             indexed_at: branch.indexed_at,
@@ -139,6 +158,15 @@ pub fn RepoDetailPage() -> impl IntoView {
                                                                     .chars()
                                                                     .take(7)
                                                                     .collect();
+                                                                let head_author = branch
+                                                                    .commit
+                                                                    .as_ref()
+                                                                    .map(|commit| format!("{} <{}>", commit.author_name, commit.author_email));
+                                                                let head_label = branch
+                                                                    .commit
+                                                                    .as_ref()
+                                                                    .map(|commit| commit.label(Utc::now()))
+                                                                    .unwrap_or_else(|| format!("Head {short_commit}"));
                                                                 let live_badge = branch
                                                                     .is_live
                                                                     .then(|| {
@@ -170,7 +198,7 @@ pub fn RepoDetailPage() -> impl IntoView {
                                                                                     {branch.name.clone()}
                                                                                 </span>
                                                                                 <div class="flex flex-wrap items-center gap-2 text-[11px] text-slate-600 dark:text-slate-300">
-                                                                                    <span>{format!("Head {}", short_commit)}</span>
+                                                                                    <span title=head_author>{head_label}</span>
                                                                                     {live_badge}
                                                                                     {indexed_badge}
                                                                                 </div>
@@ -1,12 +1,15 @@
+use crate::components::ServiceUnavailable;
 use crate::db::models::{
-    FacetCount, SearchMatchSpan, SearchResult, SearchResultsPage, SearchResultsStats, SearchSnippet,
+    FacetCount, SearchMatchSpan, SearchResult, SearchResultsPage, SearchResultsStats,
+    SearchSnippet, TOTAL_ESTIMATE_CAP, UNKNOWN_LANGUAGE,
 };
 use crate::dsl::DEFAULT_PAGE_SIZE;
-use crate::services::search_service::search;
+use crate::services::search_service::{SearchError, search};
 use crate::utils::time::{TimePoint, elapsed_since, now_seconds};
 use chrono::Utc;
 use leptos::either::{Either, EitherOf3};
 use leptos::prelude::*;
+use leptos::tachys::dom::event_target_checked;
 use leptos_router::{
     NavigateOptions,
     hooks::{use_navigate, use_query},
@@ -52,7 +55,7 @@ pub fn SearchPage() -> impl IntoView {
                         DEFAULT_PAGE_SIZE,
                     ));
                 }
-                search(search_text, page as u32).await
+                search(search_text, page as u32, None, None).await
             }
             Err(_) => Ok(SearchResultsPage::empty(
                 String::new(),
@@ -71,6 +74,7 @@ pub fn SearchPage() -> impl IntoView {
     let search_final_elapsed = RwSignal::new(None::<f64>);
     let search_started_at = RwSignal::new(None::<TimePoint>);
     let pending_query_signature = RwSignal::new(None::<String>);
+    let group_by_repo = RwSignal::new(false);
 
     Effect::new({
         let query = query.clone();
@@ -199,6 +203,7 @@ pub fn SearchPage() -> impl IntoView {
                                     } else if results_page.stats.common_directories.is_empty()
                                         && results_page.stats.top_repositories.is_empty()
                                         && results_page.stats.top_branches.is_empty()
+                                        && results_page.stats.top_languages.is_empty()
                                     {
                                         view! {
                                             <p class="text-xs text-gray-500">
@@ -314,21 +319,60 @@ pub fn SearchPage() -> impl IntoView {
                                             let has_more = results_page.has_more;
                                             let prev_page = page.saturating_sub(1).max(1);
                                             let next_page = page + 1;
+                                            let results = results_page.results;
+                                            let top_repositories = results_page.stats.top_repositories;
                                             EitherOf3::B(
                                                 view! {
                                                     <div class="space-y-4 overflow-x-auto max-w-full">
-                                                        <p class="text-sm text-gray-600 dark:text-gray-400">
-                                                            {format!(
-                                                                "Showing page {} ({} results per page)",
-                                                                page,
-                                                                results_page.page_size,
-                                                            )}
-                                                        </p>
-                                                        {results_page
-                                                            .results
-                                                            .into_iter()
-                                                            .map(|result| view! { <SearchResultCard result=result /> })
-                                                            .collect_view()}
+                                                        <div class="flex flex-wrap items-center justify-between gap-2">
+                                                            <p class="text-sm text-gray-600 dark:text-gray-400">
+                                                                {match results_page.total_estimate {
+                                                                    Some(total) => format!(
+                                                                        "About {} files, showing page {} ({} results per page)",
+                                                                        format_total_estimate(total),
+                                                                        page,
+                                                                        results_page.page_size,
+                                                                    ),
+                                                                    None => format!(
+                                                                        "Showing page {} ({} results per page)",
+                                                                        page,
+                                                                        results_page.page_size,
+                                                                    ),
+                                                                }}
+                                                            </p>
+                                                            <label class="flex items-center gap-2 text-sm text-gray-600 dark:text-gray-400 cursor-pointer">
+                                                                <input
+                                                                    type="checkbox"
+                                                                    class="checkbox checkbox-xs"
+                                                                    prop:checked=move || group_by_repo.get()
+                                                                    on:change=move |ev| {
+                                                                        group_by_repo.set(event_target_checked(&ev))
+                                                                    }
+                                                                />
+                                                                "Group by repository"
+                                                            </label>
+                                                        </div>
+                                                        {move || {
+                                                            if group_by_repo.get() {
+                                                                Either::Left(
+                                                                    group_results_by_repository(
+                                                                            results.clone(),
+                                                                            &top_repositories,
+                                                                        )
+                                                                        .into_iter()
+                                                                        .map(|group| view! { <RepositoryResultGroup group=group /> })
+                                                                        .collect_view(),
+                                                                )
+                                                            } else {
+                                                                Either::Right(
+                                                                    results
+                                                                        .clone()
+                                                                        .into_iter()
+                                                                        .map(|result| view! { <SearchResultCard result=result /> })
+                                                                        .collect_view(),
+                                                                )
+                                                            }
+                                                        }}
                                                         <div class="flex items-center justify-between pt-4">
                                                             <button
                                                                 class="px-4 py-2 rounded bg-gray-200 dark:bg-gray-700 hover:bg-gray-300 dark:hover:bg-gray-600 disabled:opacity-50 disabled:cursor-not-allowed"
@@ -371,10 +415,26 @@ pub fn SearchPage() -> impl IntoView {
                                     }
                                     Err(e) => {
                                         EitherOf3::C(
-                                            view! {
-                                                <p class="text-red-500 text-center py-8">
-                                                    "Error: " {e.to_string()}
-                                                </p>
+                                            match e {
+                                                SearchError::Unavailable { retry_after_secs, .. } => {
+                                                    Either::Left(
+                                                        view! {
+                                                            <ServiceUnavailable
+                                                                retry_after_secs=retry_after_secs
+                                                                on_retry=move || search_results.refetch()
+                                                            />
+                                                        },
+                                                    )
+                                                }
+                                                SearchError::Failed(message) => {
+                                                    Either::Right(
+                                                        view! {
+                                                            <p class="text-red-500 text-center py-8">
+                                                                "Error: " {message}
+                                                            </p>
+                                                        },
+                                                    )
+                                                }
                                             },
                                         )
                                     }
@@ -534,6 +594,14 @@ fn format_elapsed_time(seconds: f64) -> String {
     }
 }
 
+fn format_total_estimate(total: u64) -> String {
+    if total >= TOTAL_ESTIMATE_CAP {
+        format!("{TOTAL_ESTIMATE_CAP}+")
+    } else {
+        total.to_string()
+    }
+}
+
 fn params_signature(params: &SearchParams) -> Option<String> {
     params
         .q
@@ -565,6 +633,7 @@ where
         common_directories,
         top_repositories,
         top_branches,
+        top_languages,
     } = stats;
 
     fn section_header(title: &'static str) -> impl IntoView {
@@ -579,6 +648,20 @@ where
         view! { <p class="text-xs text-gray-500">{message}</p> }
     }
 
+    fn count_item(facet: FacetCount) -> impl IntoView {
+        let FacetCount { value, count } = facet;
+        view! {
+            <li class="flex items-center justify-between gap-2 rounded-md border border-gray-200 dark:border-gray-700 p-2 bg-gray-50 dark:bg-gray-900/30">
+                <span class="text-sm font-medium text-gray-500 dark:text-gray-400 truncate">
+                    {value}
+                </span>
+                <span class="text-xs text-gray-500 dark:text-gray-400">
+                    {format!("{} hits", count)}
+                </span>
+            </li>
+        }
+    }
+
     fn list_item<FInc, FExc>(
         facet: FacetCount,
         include_action: FInc,
@@ -738,6 +821,49 @@ where
         )
     };
 
+    let languages_view = if top_languages.is_empty() {
+        Either::Left(empty_message("No language stats yet."))
+    } else {
+        let query_text = query_text.clone();
+        let navigate = navigate.clone();
+        Either::Right(
+            top_languages
+                .into_iter()
+                .map(move |facet| {
+                    // There is no lang: value that selects undetected files.
+                    if facet.value == UNKNOWN_LANGUAGE {
+                        return Either::Left(count_item(facet));
+                    }
+                    let include_value = facet.value.clone();
+                    let exclude_value = include_value.clone();
+                    let query_text_include = query_text.clone();
+                    let navigate_include = navigate.clone();
+                    let query_text_exclude = query_text.clone();
+                    let navigate_exclude = navigate.clone();
+                    Either::Right(list_item(
+                        facet,
+                        move || {
+                            append_filter(
+                                &query_text_include,
+                                &navigate_include,
+                                "lang",
+                                include_value.clone(),
+                            )
+                        },
+                        move || {
+                            append_negated_filter(
+                                &query_text_exclude,
+                                &navigate_exclude,
+                                "lang",
+                                exclude_value.clone(),
+                            )
+                        },
+                    ))
+                })
+                .collect_view(),
+        )
+    };
+
     view! {
         <div class="space-y-4">
             <div>
@@ -747,6 +873,9 @@ where
                 {section_header("Top Repositories")} <ul class="space-y-2">{repositories_view}</ul>
             </div>
             <div>{section_header("Top Branches")} <ul class="space-y-2">{branches_view}</ul></div>
+            <div>
+                {section_header("Languages")} <ul class="space-y-2">{languages_view}</ul>
+            </div>
         </div>
     }
 }
@@ -929,6 +1058,76 @@ mod tests {
     use super::*;
     use crate::db::models::SearchMatchSpan;
 
+    fn result_in(repository: &str, file_path: &str) -> SearchResult {
+        SearchResult {
+            repository: repository.to_string(),
+            commit_sha: "abc123".to_string(),
+            file_path: file_path.to_string(),
+            start_line: 1,
+            end_line: 1,
+            match_line: 1,
+            content_text: String::new(),
+            match_spans: Vec::new(),
+            snippets: Vec::new(),
+            branches: Vec::new(),
+            live_branches: Vec::new(),
+            is_historical: false,
+            snapshot_indexed_at: None,
+            commit: None,
+        }
+    }
+
+    #[test]
+    fn format_total_estimate_marks_capped_counts() {
+        assert_eq!(format_total_estimate(25), "25");
+        assert_eq!(format_total_estimate(TOTAL_ESTIMATE_CAP), "10000+");
+    }
+
+    #[test]
+    fn group_results_by_repository_keeps_rank_order() {
+        let results = vec![
+            result_in("beta", "src/one.rs"),
+            result_in("alpha", "lib/a.rs"),
+            result_in("beta", "src/two.rs"),
+            result_in("gamma", "main.go"),
+            result_in("alpha", "lib/b.rs"),
+        ];
+        let top_repositories = vec![
+            FacetCount {
+                value: "alpha".to_string(),
+                count: 40,
+            },
+            FacetCount {
+                value: "beta".to_string(),
+                count: 12,
+            },
+        ];
+
+        let groups = group_results_by_repository(results, &top_repositories);
+        let summary: Vec<(&str, u32, Vec<&str>)> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.repository.as_str(),
+                    group.match_count,
+                    group
+                        .results
+                        .iter()
+                        .map(|result| result.file_path.as_str())
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("beta", 12, vec!["src/one.rs", "src/two.rs"]),
+                ("alpha", 40, vec!["lib/a.rs", "lib/b.rs"]),
+                ("gamma", 1, vec!["main.go"]),
+            ]
+        );
+    }
+
     #[test]
     fn split_query_tokens_preserves_quoted_filters() {
         let tokens = split_query_tokens(r#"repo:Unvanquished regex:"def .* (.*):" lang:rust"#);
@@ -1089,6 +1288,78 @@ where
     append_token(query_text, navigate, token);
 }
 
+/// Results from one repository, in the order the search ranked them.
+#[derive(Debug, Clone)]
+struct RepositoryGroup {
+    repository: String,
+    /// Matches for the whole query from the `top_repositories` facet, or the
+    /// results on this page when the facet doesn't list the repository.
+    match_count: u32,
+    results: Vec<SearchResult>,
+}
+
+/// Clusters a ranked page of results by repository. Groups are ordered by
+/// their best-ranked result and keep the ranking within each group.
+fn group_results_by_repository(
+    results: Vec<SearchResult>,
+    top_repositories: &[FacetCount],
+) -> Vec<RepositoryGroup> {
+    let mut groups: Vec<RepositoryGroup> = Vec::new();
+    for result in results {
+        match groups
+            .iter_mut()
+            .find(|group| group.repository == result.repository)
+        {
+            Some(group) => group.results.push(result),
+            None => groups.push(RepositoryGroup {
+                repository: result.repository.clone(),
+                match_count: 0,
+                results: vec![result],
+            }),
+        }
+    }
+    for group in &mut groups {
+        group.match_count = top_repositories
+            .iter()
+            .find(|facet| facet.value == group.repository)
+            .map(|facet| facet.count)
+            .unwrap_or(group.results.len() as u32);
+    }
+    groups
+}
+
+#[component]
+fn RepositoryResultGroup(group: RepositoryGroup) -> impl IntoView {
+    let RepositoryGroup {
+        repository,
+        match_count,
+        results,
+    } = group;
+    let count_label = if match_count == 1 {
+        "1 match".to_string()
+    } else {
+        format!("{match_count} matches")
+    };
+    view! {
+        <details open class="space-y-3">
+            <summary class="flex items-center justify-between gap-3 cursor-pointer select-none rounded-md bg-gray-100 dark:bg-gray-800 px-3 py-2">
+                <span class="font-semibold font-mono text-gray-800 dark:text-gray-200 truncate">
+                    {repository}
+                </span>
+                <span class="text-xs text-gray-500 dark:text-gray-400 whitespace-nowrap">
+                    {count_label}
+                </span>
+            </summary>
+            <div class="space-y-4 pl-2 border-l-2 border-gray-200 dark:border-gray-700">
+                {results
+                    .into_iter()
+                    .map(|result| view! { <SearchResultCard result=result /> })
+                    .collect_view()}
+            </div>
+        </details>
+    }
+}
+
 #[component]
 fn SearchResultCard(result: SearchResult) -> impl IntoView {
     let SearchResult {
@@ -1105,6 +1376,7 @@ fn SearchResultCard(result: SearchResult) -> impl IntoView {
         is_historical,
         snapshot_indexed_at,
         snippets,
+        commit,
     } = result;
 
     let mut snippet_vec = snippets;
@@ -1155,6 +1427,12 @@ fn SearchResultCard(result: SearchResult) -> impl IntoView {
         });
 
     let short_commit: String = commit_sha.chars().take(7).collect();
+    let commit_author = commit
+        .as_ref()
+        .map(|commit| format!("{} <{}>", commit.author_name, commit.author_email));
+    let commit_label = commit
+        .map(|commit| commit.label(Utc::now()))
+        .unwrap_or_else(|| format!("Commit {short_commit}"));
     let primary_label = format!(
         "{}/{}:{}",
         repository, file_path, primary_snippet.match_line
@@ -1248,16 +1526,24 @@ fn SearchResultCard(result: SearchResult) -> impl IntoView {
 
     view! {
         <div class="mt-4 p-4 border border-gray-300 dark:border-gray-700 rounded-md bg-white dark:bg-gray-800 break-words max-w-full overflow-x-auto">
-            <p class="font-mono text-sm break-all">
-                <a
-                    href=primary_link
-                    class="hover:underline text-blue-600 dark:text-blue-400 break-all"
-                >
-                    {primary_label}
-                </a>
-            </p>
+            <div class="flex items-start justify-between gap-2">
+                <p class="font-mono text-sm break-all">
+                    <a
+                        href=primary_link
+                        class="hover:underline text-blue-600 dark:text-blue-400 break-all"
+                    >
+                        {primary_label}
+                    </a>
+                </p>
+                <OpenInEditorButton
+                    repo=repository.clone()
+                    path=file_path.clone()
+                    line=Some(primary_snippet.match_line.max(1) as u32)
+                    compact=true
+                />
+            </div>
             <div class="flex flex-wrap items-center gap-2 mt-1 text-xs text-gray-600 dark:text-gray-400">
-                <span>{format!("Commit {}", short_commit)}</span>
+                <span title=commit_author>{commit_label}</span>
                 {indexed_badge}
                 {historical_badge}
             </div>
@@ -1,24 +1,499 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
 
+use axum::{
+    Json, Router,
+    extract::{Extension, Path, Query},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
 use clap::Parser;
+use leptos::config::LeptosOptions;
+use serde::Deserialize;
+use serde_json::json;
 use sqlx::postgres::PgPool;
 
+use crate::db::guarded::{DbCircuitBreakers, GuardedDb};
+use crate::db::models::RepoOverview;
+use crate::db::postgres::{
+    DEFAULT_MAX_SNIPPET_CONTEXT, DEFAULT_MIN_SYMBOL_LENGTH, PostgresDb, SchemaCheck,
+    SymbolSearchCache,
+};
+use crate::db::{Database, DbError, DefinitionLookup, FileContentRequest, GraphGranularity};
+use crate::dsl::TextSearchRequest;
+use crate::utils::circuit_breaker::{CircuitBreakerConfig, CircuitState};
+use crate::utils::content_type::content_type_for;
+use crate::utils::symbol_graph::{DEFAULT_EDGE_LIMIT, MAX_EDGE_LIMIT, render_dot};
+
 #[derive(Debug, Parser)]
 pub struct ServerConfig {
     /// Postgres connection string
     #[arg(long, env = "DATABASE_URL")]
     pub database_url: String,
+    /// Read replica for search and browsing; ingestion stays on the primary.
+    /// Replica lag can briefly hide freshly ingested snapshots. Defaults to
+    /// the primary.
+    #[arg(long, env = "READ_DATABASE_URL")]
+    pub read_database_url: Option<String>,
     /// Address to bind the HTTP server to
     #[arg(long, env = "BIND_ADDRESS", default_value = "127.0.0.1:8080")]
     pub bind: String,
     /// Maximum database connections
     #[arg(long, env = "MAX_CONNECTIONS", default_value_t = 10)]
     pub max_connections: u32,
+    /// Maximum characters returned per search result snippet
+    #[arg(long, env = "MAX_SNIPPET_CHARS", default_value_t = 2000)]
+    pub max_snippet_chars: usize,
+    /// Directories hidden from search results unless a query targets them
+    #[arg(
+        long,
+        env = "DEFAULT_EXCLUDED_DIRS",
+        value_delimiter = ',',
+        default_value = "node_modules,vendor,target,.venv"
+    )]
+    pub default_excluded_dirs: Vec<String>,
+    /// Shortest search term treated as a possible symbol name when ranking
+    /// results; shorter terms are matched as plain text only
+    #[arg(long, env = "MIN_SYMBOL_LENGTH", default_value_t = DEFAULT_MIN_SYMBOL_LENGTH)]
+    pub min_symbol_length: usize,
+    /// Most context lines a file snippet shows on each side of its line
+    #[arg(long, env = "MAX_SNIPPET_CONTEXT", default_value_t = DEFAULT_MAX_SNIPPET_CONTEXT)]
+    pub max_snippet_context: u32,
+    /// Consecutive connection failures before database calls start failing fast
+    #[arg(long, env = "DB_BREAKER_THRESHOLD", default_value_t = 5)]
+    pub db_breaker_threshold: u32,
+    /// Seconds database calls fail fast before a recovery probe is let through
+    #[arg(long, env = "DB_BREAKER_OPEN_SECS", default_value_t = 5)]
+    pub db_breaker_open_secs: u64,
+    /// Longest fail-fast period once repeated probes keep failing
+    #[arg(long, env = "DB_BREAKER_MAX_OPEN_SECS", default_value_t = 60)]
+    pub db_breaker_max_open_secs: u64,
+    /// Seconds identical symbol searches are served from memory (e.g. 10);
+    /// 0 disables the cache. Results may trail ingestion by this long.
+    #[arg(long, env = "SYMBOL_CACHE_TTL_SECS", default_value_t = 0)]
+    pub symbol_cache_ttl_secs: u64,
+    /// Match case-insensitive content terms against lowercased chunk text,
+    /// served by the `lower(text_content)` trigram index, instead of ILIKE.
+    /// Worth it when case-insensitive search dominates. The backend only
+    /// builds that index when started with the same flag.
+    #[arg(long, env = "LOWERCASE_CONTENT_SEARCH", default_value_t = false)]
+    pub lowercase_content_search: bool,
+    /// Serve `/api/v1/admin/explain`, which runs EXPLAIN against the database
+    /// for any query. It has no auth of its own, so only enable it where the
+    /// web UI isn't publicly reachable.
+    #[arg(long, env = "ENABLE_ADMIN_EXPLAIN", default_value_t = false)]
+    pub enable_admin_explain: bool,
+}
+
+impl ServerConfig {
+    pub fn breaker_config(&self) -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_threshold: self.db_breaker_threshold.max(1),
+            base_open: Duration::from_secs(self.db_breaker_open_secs),
+            max_open: Duration::from_secs(self.db_breaker_max_open_secs),
+        }
+    }
+
+    pub fn symbol_cache(&self) -> Option<Arc<SymbolSearchCache>> {
+        (self.symbol_cache_ttl_secs > 0).then(|| {
+            Arc::new(SymbolSearchCache::new(
+                Duration::from_secs(self.symbol_cache_ttl_secs),
+                SYMBOL_CACHE_CAPACITY,
+            ))
+        })
+    }
 }
 
+/// Distinct symbol searches kept when the cache is enabled.
+const SYMBOL_CACHE_CAPACITY: usize = 512;
+
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
+    pub read_pool: Option<PgPool>,
+    pub max_snippet_chars: usize,
+    pub default_excluded_dirs: Vec<String>,
+    pub min_symbol_length: usize,
+    pub max_snippet_context: u32,
+    pub db_breakers: Arc<DbCircuitBreakers>,
+    pub repo_overviews: Arc<RepoOverviewCache>,
+    pub symbol_cache: Option<Arc<SymbolSearchCache>>,
+    pub lowercase_content_search: bool,
+    /// Startup [`SchemaCheck`]; `None` when the catalogs couldn't be read.
+    pub schema_check: Option<SchemaCheck>,
+}
+
+impl AppState {
+    /// Database handle with the server-wide search settings and circuit
+    /// breakers applied.
+    pub fn db(&self) -> GuardedDb<PostgresDb> {
+        let db = PostgresDb::new(self.pool.clone())
+            .with_max_snippet_chars(self.max_snippet_chars)
+            .with_default_excluded_dirs(self.default_excluded_dirs.clone())
+            .with_min_symbol_length(self.min_symbol_length)
+            .with_max_snippet_context(self.max_snippet_context)
+            .with_lowercase_content(self.lowercase_content_search);
+        let db = match &self.symbol_cache {
+            Some(cache) => db.with_symbol_cache(cache.clone()),
+            None => db,
+        };
+        let db = match &self.read_pool {
+            Some(read_pool) => db.with_read_pool(read_pool.clone()),
+            None => db,
+        };
+        GuardedDb::new(db, self.db_breakers.clone())
+    }
+
+    /// Pool for read-only queries issued outside [`PostgresDb`].
+    pub fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
 }
 
 pub type GlobalAppState = Arc<AppState>;
+
+/// Repository overviews keyed by repository and commit. Indexed snapshots
+/// never change, so entries don't expire; the map is cleared once it holds
+/// `capacity` entries to keep memory bounded.
+pub struct RepoOverviewCache {
+    capacity: usize,
+    entries: Mutex<HashMap<(String, String), RepoOverview>>,
+}
+
+impl RepoOverviewCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, repository: &str, commit_sha: &str) -> Option<RepoOverview> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(repository.to_string(), commit_sha.to_string()))
+            .cloned()
+    }
+
+    pub fn insert(&self, repository: &str, commit_sha: &str, overview: RepoOverview) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries.len() >= self.capacity {
+            entries.clear();
+        }
+        entries.insert((repository.to_string(), commit_sha.to_string()), overview);
+    }
+}
+
+impl Default for RepoOverviewCache {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+pub fn readiness_router(state: GlobalAppState) -> Router<LeptosOptions> {
+    Router::<LeptosOptions>::new()
+        .route("/readyz", get(readyz))
+        .layer(Extension(state))
+}
+
+/// Reports the database circuit breakers and the startup schema check
+/// without touching the database, so probes stay cheap during an outage.
+async fn readyz(Extension(state): Extension<GlobalAppState>) -> impl IntoResponse {
+    let (status, body) = readiness(state.db_breakers.states(), state.schema_check.as_ref());
+    (status, Json(body))
+}
+
+fn readiness(
+    states: Vec<(&'static str, CircuitState)>,
+    schema_check: Option<&SchemaCheck>,
+) -> (StatusCode, serde_json::Value) {
+    let ready = states
+        .iter()
+        .all(|(_, circuit)| *circuit == CircuitState::Closed)
+        && schema_check.is_none_or(SchemaCheck::is_ready);
+    let breakers: serde_json::Map<String, serde_json::Value> = states
+        .into_iter()
+        .map(|(name, circuit)| (name.to_string(), json!(circuit)))
+        .collect();
+    let schema = schema_check.map(|check| {
+        json!({
+            "pg_trgm_installed": check.pg_trgm_installed,
+            "missing_indexes": check.missing_indexes,
+            "problems": check.problems(),
+        })
+    });
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        json!({
+            "status": if ready { "ready" } else { "degraded" },
+            "database_breakers": breakers,
+            "schema": schema,
+        }),
+    )
+}
+
+pub fn graph_router(state: GlobalAppState) -> Router<LeptosOptions> {
+    Router::<LeptosOptions>::new()
+        .route("/api/v1/graph", get(reference_graph))
+        .layer(Extension(state))
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphParams {
+    repo: String,
+    /// Branch name or commit SHA.
+    commit: String,
+    #[serde(default)]
+    granularity: GraphGranularity,
+    limit: Option<i64>,
+}
+
+/// Exports cross-file references for a snapshot as a GraphViz digraph, keeping
+/// only the heaviest `limit` edges.
+async fn reference_graph(
+    Extension(state): Extension<GlobalAppState>,
+    Query(params): Query<GraphParams>,
+) -> Response {
+    let db = state.db();
+    let result = async {
+        let commit = db
+            .resolve_branch_head(&params.repo, &params.commit)
+            .await?
+            .unwrap_or_else(|| params.commit.clone());
+        let limit = params
+            .limit
+            .unwrap_or(DEFAULT_EDGE_LIMIT)
+            .clamp(1, MAX_EDGE_LIMIT);
+        let edges = db
+            .get_reference_graph(&params.repo, &commit, params.granularity, limit)
+            .await?;
+        Ok::<_, DbError>(render_dot(
+            &params.repo,
+            &commit,
+            params.granularity,
+            &edges,
+        ))
+    }
+    .await;
+
+    match result {
+        Ok(dot) => (
+            [(header::CONTENT_TYPE, "text/vnd.graphviz; charset=utf-8")],
+            dot,
+        )
+            .into_response(),
+        Err(err) => db_error_response(err),
+    }
+}
+
+/// Upper bound on symbols resolved by one `/api/v1/definitions/batch` call.
+const MAX_DEFINITION_BATCH: usize = 1000;
+
+pub fn definitions_router(state: GlobalAppState) -> Router<LeptosOptions> {
+    Router::<LeptosOptions>::new()
+        .route("/api/v1/definitions/batch", post(definitions_batch))
+        .layer(Extension(state))
+}
+
+/// Resolves definition locations for a list of symbols at once, e.g. to
+/// prefetch hovers for everything visible in an editor. Results come back in
+/// request order.
+async fn definitions_batch(
+    Extension(state): Extension<GlobalAppState>,
+    Json(lookups): Json<Vec<DefinitionLookup>>,
+) -> Response {
+    if lookups.len() > MAX_DEFINITION_BATCH {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("at most {MAX_DEFINITION_BATCH} symbols per batch"),
+        )
+            .into_response();
+    }
+    match state.db().get_definitions_batch(lookups).await {
+        Ok(results) => Json(results).into_response(),
+        Err(err) => db_error_response(err),
+    }
+}
+
+pub fn symbols_router(state: GlobalAppState) -> Router<LeptosOptions> {
+    Router::<LeptosOptions>::new()
+        .route("/api/v1/symbols/{id}", get(symbol_by_id))
+        .layer(Extension(state))
+}
+
+/// Returns one symbol by the `id` a symbol search reported, with its
+/// definition and references, so permalinks don't have to re-run the search.
+async fn symbol_by_id(
+    Extension(state): Extension<GlobalAppState>,
+    Path(id): Path<i32>,
+) -> Response {
+    match state.db().get_symbol_by_id(id).await {
+        Ok(symbol) => Json(symbol).into_response(),
+        Err(DbError::NotFound(_)) => {
+            (StatusCode::NOT_FOUND, format!("no symbol with id {id}")).into_response()
+        }
+        Err(err) => db_error_response(err),
+    }
+}
+
+/// Upper bound on files loaded by one `/api/v1/files/batch` call.
+const MAX_FILE_BATCH: usize = 64;
+
+pub fn files_router(state: GlobalAppState) -> Router<LeptosOptions> {
+    Router::<LeptosOptions>::new()
+        .route("/api/v1/files/batch", post(file_contents_batch))
+        .layer(Extension(state))
+}
+
+/// Returns the content of several files at once, so the viewer can prefetch
+/// an open file's siblings. Results come back in request order.
+async fn file_contents_batch(
+    Extension(state): Extension<GlobalAppState>,
+    Json(requests): Json<Vec<FileContentRequest>>,
+) -> Response {
+    if requests.len() > MAX_FILE_BATCH {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("at most {MAX_FILE_BATCH} files per batch"),
+        )
+            .into_response();
+    }
+    match state.db().get_file_contents(requests).await {
+        Ok(files) => Json(files).into_response(),
+        Err(err) => db_error_response(err),
+    }
+}
+
+pub fn raw_router(state: GlobalAppState) -> Router<LeptosOptions> {
+    Router::<LeptosOptions>::new()
+        .route("/repo/{repo}/raw/{commit}/{*path}", get(raw_file))
+        .layer(Extension(state))
+}
+
+/// Serves a file's indexed bytes as a download, e.g. to `curl` a config file
+/// out of the index. `commit` may also be a branch name.
+async fn raw_file(
+    Extension(state): Extension<GlobalAppState>,
+    Path((repo, commit, path)): Path<(String, String, String)>,
+) -> Response {
+    let db = state.db();
+    let result = async {
+        let commit = db
+            .resolve_branch_head(&repo, &commit)
+            .await?
+            .unwrap_or(commit);
+        db.get_raw_file_bytes(&repo, &commit, &path).await
+    }
+    .await;
+
+    let file = match result {
+        Ok(file) => file,
+        Err(DbError::NotFound(_)) => {
+            return (
+                StatusCode::NOT_FOUND,
+                format!("{path} is not indexed in {repo}"),
+            )
+                .into_response();
+        }
+        Err(err) => return db_error_response(err),
+    };
+    if file.content_skipped {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("{path} was indexed without its content, so it can't be downloaded"),
+        )
+            .into_response();
+    }
+
+    let file_name = path.rsplit('/').next().unwrap_or(&path).replace('"', "");
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                content_type_for(&path, file.is_binary).to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        file.bytes,
+    )
+        .into_response()
+}
+
+fn db_error_response(err: DbError) -> Response {
+    match err {
+        DbError::Unavailable {
+            retry_after_secs, ..
+        } => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            err.to_string(),
+        )
+            .into_response(),
+        err => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+/// Operator-only endpoints; mounted only with `--enable-admin-explain`.
+pub fn admin_router(state: GlobalAppState) -> Router<LeptosOptions> {
+    Router::<LeptosOptions>::new()
+        .route("/api/v1/admin/explain", get(explain_search))
+        .layer(Extension(state))
+}
+
+#[derive(Debug, Deserialize)]
+struct ExplainParams {
+    q: String,
+}
+
+/// Plans the search queries for `q` and reports sequential scans on large
+/// tables along with the index that should have served them. Nothing is
+/// changed; acting on the advice is left to the operator.
+async fn explain_search(
+    Extension(state): Extension<GlobalAppState>,
+    Query(params): Query<ExplainParams>,
+) -> Response {
+    let request = match TextSearchRequest::from_query_str(&params.q) {
+        Ok(request) => request,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    match state.db().explain_search(&request).await {
+        Ok(report) => Json(report).into_response(),
+        Err(err) => db_error_response(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readiness_reports_a_missing_trigram_extension() {
+        let closed = vec![("search", CircuitState::Closed)];
+        let missing = SchemaCheck::from_catalog(&["plpgsql".to_string()], &[], false);
+
+        let (status, body) = readiness(closed.clone(), Some(&missing));
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["schema"]["pg_trgm_installed"], false);
+        assert!(
+            body["schema"]["problems"][0]
+                .as_str()
+                .is_some_and(|problem| problem.contains("CREATE EXTENSION pg_trgm"))
+        );
+
+        let (status, body) = readiness(closed, None);
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["schema"].is_null());
+    }
+}
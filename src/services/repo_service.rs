@@ -1,15 +1,16 @@
 use crate::db::RepoSummary;
+use crate::db::models::BranchListingPage;
 use leptos::prelude::*;
 
 #[cfg(feature = "ssr")]
-use crate::db::{Database, postgres::PostgresDb};
+use crate::db::Database;
 
 #[server]
 pub async fn get_repositories(limit: usize) -> Result<Vec<RepoSummary>, ServerFnError> {
     let state = expect_context::<crate::server::GlobalAppState>();
 
     // Create a database instance using the pool
-    let db = PostgresDb::new(state.pool.clone());
+    let db = state.db();
 
     // Get all repositories from the database
     let repos = db.get_all_repositories().await?;
@@ -19,3 +20,30 @@ pub async fn get_repositories(limit: usize) -> Result<Vec<RepoSummary>, ServerFn
 
     Ok(repos)
 }
+
+/// One page of branches across every repository, for the admin branches view.
+#[server]
+pub async fn get_all_branches(
+    page: u32,
+    page_size: u32,
+) -> Result<BranchListingPage, ServerFnError> {
+    let state = expect_context::<crate::server::GlobalAppState>();
+    let db = state.db();
+
+    let page = page.max(1);
+    let page_size = page_size.clamp(1, 200);
+    let offset = i64::from(page - 1) * i64::from(page_size);
+    // One extra row tells us whether another page follows.
+    let mut branches = db
+        .get_all_branches(i64::from(page_size) + 1, offset)
+        .await?;
+    let has_more = branches.len() > page_size as usize;
+    branches.truncate(page_size as usize);
+
+    Ok(BranchListingPage {
+        branches,
+        page,
+        page_size,
+        has_more,
+    })
+}
@@ -1,15 +1,88 @@
 use leptos::prelude::*;
 
 #[cfg(feature = "ssr")]
-use crate::db::Database;
-use crate::db::models::{SearchResultsPage, SymbolSuggestion};
+use crate::db::models::SearchResult;
+use crate::db::models::{
+    FileLocationMatch, FileSymbolSuggestion, SearchResultsPage, SymbolSuggestion,
+};
 #[cfg(feature = "ssr")]
-use crate::db::postgres::PostgresDb;
+use crate::db::{Database, SnippetRequest};
+use crate::db::{DbError, SearchResponse, SnippetResponse};
+use crate::dsl::SearchOrder;
 #[cfg(feature = "ssr")]
 use crate::dsl::{DEFAULT_PAGE_SIZE, TextSearchRequest};
+use leptos::server_fn::codec::JsonEncoding;
+use leptos::server_fn::error::{FromServerFnError, ServerFnErrorErr};
+use serde::{Deserialize, Serialize};
 
+// Suggestions requested per autocomplete category. Repositories and languages
+// are few and distinct, while paths and symbols need a longer list before the
+// one the user wants shows up.
+pub const REPO_SUGGESTION_LIMIT: i64 = 10;
+pub const PATH_SUGGESTION_LIMIT: i64 = 30;
+pub const FILE_SUGGESTION_LIMIT: i64 = 30;
+pub const SYMBOL_SUGGESTION_LIMIT: i64 = 25;
+pub const LANGUAGE_SUGGESTION_LIMIT: i64 = 10;
+pub const BRANCH_SUGGESTION_LIMIT: i64 = 15;
+pub const FILE_SYMBOL_SUGGESTION_LIMIT: i64 = 20;
+/// Largest limit the autocomplete endpoints honour for a single category.
+pub const MAX_SUGGESTION_LIMIT: i64 = 50;
+/// Suggestions rendered in the dropdown across every group.
+pub const MAX_RENDERED_SUGGESTIONS: usize = 40;
+
+/// Error returned by [`search`]. An unavailable database keeps its retry hint
+/// so the results page can count down to the next attempt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SearchError {
+    Unavailable {
+        message: String,
+        retry_after_secs: u64,
+    },
+    Failed(String),
+}
+
+impl std::fmt::Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchError::Unavailable { message, .. } | SearchError::Failed(message) => {
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+impl From<DbError> for SearchError {
+    fn from(err: DbError) -> Self {
+        match err {
+            DbError::Unavailable {
+                retry_after_secs, ..
+            } => SearchError::Unavailable {
+                message: err.to_string(),
+                retry_after_secs,
+            },
+            err => SearchError::Failed(err.to_string()),
+        }
+    }
+}
+
+impl FromServerFnError for SearchError {
+    type Encoder = JsonEncoding;
+
+    fn from_server_fn_error(value: ServerFnErrorErr) -> Self {
+        SearchError::Failed(value.to_string())
+    }
+}
+
+/// Runs a content search. Pass `snippets: Some(false)` to get only result
+/// locations, which is much cheaper when the caller just lists or counts files.
+/// `order_by` defaults to relevance.
 #[server]
-pub async fn search(query: String, page: u32) -> Result<SearchResultsPage, ServerFnError> {
+pub async fn search(
+    query: String,
+    page: u32,
+    snippets: Option<bool>,
+    order_by: Option<SearchOrder>,
+) -> Result<SearchResultsPage, SearchError> {
     let normalized_page = page.max(1);
     tracing::info!(
         target: "pointer::search",
@@ -19,22 +92,106 @@ pub async fn search(query: String, page: u32) -> Result<SearchResultsPage, Serve
     );
     let request =
         TextSearchRequest::from_query_str_with_page(&query, normalized_page, DEFAULT_PAGE_SIZE)
-            .map_err(|e| ServerFnError::new(e.to_string()))?;
+            .map_err(|e| SearchError::Failed(e.to_string()))?
+            .with_snippets(snippets.unwrap_or(true))
+            .with_order_by(order_by.unwrap_or_default());
+    request
+        .reject_symbol_only_filters()
+        .map_err(|e| SearchError::Failed(e.to_string()))?;
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
-    db.text_search(&request)
+    let db = state.db();
+    let mut page = db.text_search(&request).await?;
+    attach_commit_metadata(&db, &mut page.results).await;
+    Ok(page)
+}
+
+/// Runs a `symbol:` or `name_contains:` query as a symbol search. Results are
+/// grouped by name, so `count:` caps the number of distinct symbols.
+#[server]
+pub async fn symbol_search(query: String) -> Result<SearchResponse, ServerFnError> {
+    tracing::info!(target: "pointer::search", query = %query, "symbol search request");
+    let request = TextSearchRequest::from_query_str(&query)
+        .and_then(|request| request.symbol_search_request())
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let state = expect_context::<crate::server::GlobalAppState>();
+    state
+        .db()
+        .search_symbols(request)
         .await
         .map_err(|e| ServerFnError::new(e.to_string()))
 }
 
+/// Fills in each result's commit metadata. Commits indexed before metadata
+/// existed, or a failed lookup, leave results showing just their SHA.
+#[cfg(feature = "ssr")]
+async fn attach_commit_metadata(db: &impl Database, results: &mut [SearchResult]) {
+    let mut shas_by_repo: std::collections::BTreeMap<String, Vec<String>> = Default::default();
+    for result in results.iter() {
+        let shas = shas_by_repo.entry(result.repository.clone()).or_default();
+        if !shas.contains(&result.commit_sha) {
+            shas.push(result.commit_sha.clone());
+        }
+    }
+
+    let mut commits = std::collections::HashMap::new();
+    for (repository, shas) in shas_by_repo {
+        match db.get_commit_metadata(&repository, &shas).await {
+            Ok(found) => commits.extend(
+                found
+                    .into_iter()
+                    .map(|commit| ((repository.clone(), commit.commit_sha.clone()), commit)),
+            ),
+            Err(err) => tracing::warn!(
+                target: "pointer::search",
+                error = %err,
+                repository = %repository,
+                "failed to load commit metadata"
+            ),
+        }
+    }
+
+    for result in results {
+        result.commit = commits
+            .get(&(result.repository.clone(), result.commit_sha.clone()))
+            .cloned();
+    }
+}
+
+/// Reloads a result's snippet with `context` lines on each side of
+/// `match_line`, so one snippet can grow without re-running the search.
+/// `context` is capped by the server's `MAX_SNIPPET_CONTEXT`.
+#[server]
+pub async fn expand_snippet(
+    repository: String,
+    commit_sha: String,
+    file_path: String,
+    match_line: u32,
+    context: u32,
+) -> Result<SnippetResponse, ServerFnError> {
+    let state = expect_context::<crate::server::GlobalAppState>();
+    let db = state.db();
+    db.get_file_snippet(SnippetRequest {
+        repository,
+        commit_sha,
+        file_path,
+        line: match_line,
+        column: None,
+        context: Some(context),
+        highlight: None,
+        case_sensitive: None,
+    })
+    .await
+    .map_err(|e| ServerFnError::new(e.to_string()))
+}
+
 #[server]
 pub async fn autocomplete_repositories(
     term: String,
     limit: i64,
 ) -> Result<Vec<String>, ServerFnError> {
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
-    let normalized_limit = limit.max(1).min(20);
+    let db = state.db();
+    let normalized_limit = limit.clamp(1, MAX_SUGGESTION_LIMIT);
     db.autocomplete_repositories(term.trim(), normalized_limit)
         .await
         .map_err(|e| ServerFnError::new(e.to_string()))
@@ -47,8 +204,8 @@ pub async fn autocomplete_paths(
     limit: i64,
 ) -> Result<Vec<String>, ServerFnError> {
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
-    let normalized_limit = limit.max(1).min(20);
+    let db = state.db();
+    let normalized_limit = limit.clamp(1, MAX_SUGGESTION_LIMIT);
     let repos: Vec<String> = repositories
         .into_iter()
         .map(|repo| repo.trim().to_string())
@@ -69,13 +226,43 @@ pub async fn autocomplete_symbols(
         return Ok(Vec::new());
     }
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
-    let normalized_limit = limit.max(1).min(20);
+    let db = state.db();
+    let normalized_limit = limit.clamp(1, MAX_SUGGESTION_LIMIT);
     db.autocomplete_symbols(trimmed, normalized_limit)
         .await
         .map_err(|e| ServerFnError::new(e.to_string()))
 }
 
+/// Definitions in one file whose names contain `term`, prefix matches first.
+/// `commit_sha` may also be a branch name, which is resolved to its head. An
+/// empty term lists the file's definitions in source order.
+#[server]
+pub async fn autocomplete_symbols_in_file(
+    repository: String,
+    commit_sha: String,
+    file_path: String,
+    term: String,
+    limit: i64,
+) -> Result<Vec<FileSymbolSuggestion>, ServerFnError> {
+    let state = expect_context::<crate::server::GlobalAppState>();
+    let db = state.db();
+    let commit = db
+        .resolve_branch_head(&repository, &commit_sha)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?
+        .unwrap_or(commit_sha);
+    let normalized_limit = limit.clamp(1, MAX_SUGGESTION_LIMIT);
+    db.autocomplete_symbols_in_file(
+        &repository,
+        &commit,
+        file_path.trim_start_matches('/'),
+        term.trim(),
+        normalized_limit,
+    )
+    .await
+    .map_err(|e| ServerFnError::new(e.to_string()))
+}
+
 #[server]
 pub async fn autocomplete_languages(
     term: String,
@@ -83,8 +270,8 @@ pub async fn autocomplete_languages(
     limit: i64,
 ) -> Result<Vec<String>, ServerFnError> {
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
-    let normalized_limit = limit.max(1).min(20);
+    let db = state.db();
+    let normalized_limit = limit.clamp(1, MAX_SUGGESTION_LIMIT);
     let repos: Vec<String> = repositories
         .into_iter()
         .map(|repo| repo.trim().to_string())
@@ -102,8 +289,8 @@ pub async fn autocomplete_branches(
     limit: i64,
 ) -> Result<Vec<String>, ServerFnError> {
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
-    let normalized_limit = limit.max(1).min(20);
+    let db = state.db();
+    let normalized_limit = limit.clamp(1, MAX_SUGGESTION_LIMIT);
     let repos: Vec<String> = repositories
         .into_iter()
         .map(|repo| repo.trim().to_string())
@@ -121,8 +308,8 @@ pub async fn autocomplete_files(
     limit: i64,
 ) -> Result<Vec<String>, ServerFnError> {
     let state = expect_context::<crate::server::GlobalAppState>();
-    let db = PostgresDb::new(state.pool.clone());
-    let normalized_limit = limit.max(1).min(20);
+    let db = state.db();
+    let normalized_limit = limit.clamp(1, MAX_SUGGESTION_LIMIT);
     let repos: Vec<String> = repositories
         .into_iter()
         .map(|repo| repo.trim().to_string())
@@ -132,3 +319,29 @@ pub async fn autocomplete_files(
         .await
         .map_err(|e| ServerFnError::new(e.to_string()))
 }
+
+#[server]
+pub async fn find_file_locations(
+    paths: Vec<String>,
+    repositories: Vec<String>,
+) -> Result<Vec<FileLocationMatch>, ServerFnError> {
+    let paths: Vec<String> = paths
+        .into_iter()
+        .map(|path| path.trim().trim_start_matches('/').to_string())
+        .filter(|path| !path.is_empty())
+        .take(20)
+        .collect();
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    let state = expect_context::<crate::server::GlobalAppState>();
+    let db = state.db();
+    let repos: Vec<String> = repositories
+        .into_iter()
+        .map(|repo| repo.trim().to_string())
+        .filter(|repo| !repo.is_empty())
+        .collect();
+    db.find_file_in_repos(&paths, &repos)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))
+}
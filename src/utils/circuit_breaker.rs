@@ -0,0 +1,302 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Tuning knobs for a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open after the first trip.
+    pub base_open: Duration,
+    /// Upper bound for the open period; each failed probe doubles it.
+    pub max_open: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            base_open: Duration::from_secs(5),
+            max_open: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// Returned instead of a permit while the circuit is open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejected {
+    pub retry_after: Duration,
+}
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probe_in_flight: bool },
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: State,
+    /// Consecutive trips without a successful probe, drives the backoff.
+    trips: u32,
+}
+
+/// Fails calls fast once a dependency keeps failing, then lets a single probe
+/// through after a backoff to find out whether it recovered.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: &'static str,
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+/// Admission for one call. Report the outcome with [`Permit::success`] or
+/// [`Permit::failure`]; dropping it unreported frees a half-open probe slot
+/// without changing the state.
+#[must_use]
+#[derive(Debug)]
+pub struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    reported: bool,
+}
+
+impl Permit<'_> {
+    pub fn success(self) {
+        self.finish(false, Instant::now());
+    }
+
+    pub fn failure(self) {
+        self.finish(true, Instant::now());
+    }
+
+    fn finish(mut self, failed: bool, now: Instant) {
+        self.reported = true;
+        self.breaker.record(self.probe, failed, now);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.reported {
+            self.breaker.release_probe();
+        }
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &'static str, config: CircuitBreakerConfig) -> Self {
+        Self {
+            name,
+            config,
+            inner: Mutex::new(Inner {
+                state: State::Closed { failures: 0 },
+                trips: 0,
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn state(&self) -> CircuitState {
+        match self.lock().state {
+            State::Closed { .. } => CircuitState::Closed,
+            State::Open { .. } => CircuitState::Open,
+            State::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    pub fn try_acquire(&self) -> Result<Permit<'_>, Rejected> {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&self, now: Instant) -> Result<Permit<'_>, Rejected> {
+        let mut inner = self.lock();
+        let probe = match inner.state {
+            State::Closed { .. } => false,
+            State::Open { until } if now < until => {
+                return Err(Rejected {
+                    retry_after: until - now,
+                });
+            }
+            State::Open { .. }
+            | State::HalfOpen {
+                probe_in_flight: false,
+            } => {
+                tracing::info!(breaker = self.name, "circuit half-open, sending probe");
+                inner.state = State::HalfOpen {
+                    probe_in_flight: true,
+                };
+                true
+            }
+            State::HalfOpen {
+                probe_in_flight: true,
+            } => {
+                return Err(Rejected {
+                    retry_after: self.config.base_open,
+                });
+            }
+        };
+        Ok(Permit {
+            breaker: self,
+            probe,
+            reported: false,
+        })
+    }
+
+    fn record(&self, probe: bool, failed: bool, now: Instant) {
+        let mut guard = self.lock();
+        let inner = &mut *guard;
+        match &mut inner.state {
+            State::Closed { failures } if !failed => *failures = 0,
+            State::Closed { failures } => {
+                *failures += 1;
+                let failures = *failures;
+                if failures >= self.config.failure_threshold {
+                    self.open(inner, now, failures);
+                }
+            }
+            State::HalfOpen { .. } if probe && failed => self.open(inner, now, 1),
+            State::HalfOpen { .. } if probe => {
+                tracing::info!(breaker = self.name, "circuit closed, probe succeeded");
+                inner.state = State::Closed { failures: 0 };
+                inner.trips = 0;
+            }
+            // Stragglers admitted before the circuit opened don't move it.
+            _ => {}
+        }
+    }
+
+    fn open(&self, inner: &mut Inner, now: Instant, failures: u32) {
+        let open_for = self
+            .config
+            .base_open
+            .saturating_mul(1 << inner.trips.min(16))
+            .min(self.config.max_open);
+        tracing::warn!(
+            breaker = self.name,
+            failures,
+            open_for_ms = open_for.as_millis() as u64,
+            "circuit opened"
+        );
+        inner.state = State::Open {
+            until: now + open_for,
+        };
+        inner.trips = inner.trips.saturating_add(1);
+    }
+
+    fn release_probe(&self) {
+        let mut inner = self.lock();
+        if let State::HalfOpen { probe_in_flight } = &mut inner.state {
+            *probe_in_flight = false;
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(
+            "test",
+            CircuitBreakerConfig {
+                failure_threshold: 3,
+                base_open: Duration::from_secs(1),
+                max_open: Duration::from_secs(3),
+            },
+        )
+    }
+
+    fn fail(breaker: &CircuitBreaker, now: Instant) {
+        breaker
+            .try_acquire_at(now)
+            .expect("permit")
+            .finish(true, now);
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures_only() {
+        let breaker = breaker();
+        let now = Instant::now();
+
+        fail(&breaker, now);
+        fail(&breaker, now);
+        breaker.try_acquire_at(now).unwrap().success();
+        fail(&breaker, now);
+        fail(&breaker, now);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        fail(&breaker, now);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        let rejected = breaker.try_acquire_at(now).unwrap_err();
+        assert_eq!(rejected.retry_after, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn half_open_admits_a_single_probe() {
+        let breaker = breaker();
+        let now = Instant::now();
+        for _ in 0..3 {
+            fail(&breaker, now);
+        }
+
+        let later = now + Duration::from_secs(1);
+        let probe = breaker.try_acquire_at(later).expect("probe");
+        assert!(probe.probe);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire_at(later).is_err());
+
+        probe.success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(!breaker.try_acquire_at(later).unwrap().probe);
+    }
+
+    #[test]
+    fn failed_probes_back_off_up_to_the_cap() {
+        let breaker = breaker();
+        let mut now = Instant::now();
+        for _ in 0..3 {
+            fail(&breaker, now);
+        }
+
+        for expected in [2, 3, 3] {
+            now += breaker.try_acquire_at(now).unwrap_err().retry_after;
+            fail(&breaker, now);
+            assert_eq!(breaker.state(), CircuitState::Open);
+            let rejected = breaker.try_acquire_at(now).unwrap_err();
+            assert_eq!(rejected.retry_after, Duration::from_secs(expected));
+        }
+    }
+
+    #[test]
+    fn dropped_probe_frees_the_slot() {
+        let breaker = breaker();
+        let now = Instant::now();
+        for _ in 0..3 {
+            fail(&breaker, now);
+        }
+
+        let later = now + Duration::from_secs(1);
+        drop(breaker.try_acquire_at(later).expect("probe"));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire_at(later).expect("new probe").probe);
+    }
+}
@@ -0,0 +1,59 @@
+/// Guesses a `Content-Type` for a downloaded file from its extension. Files
+/// with an unrecognised extension are served as plain text unless their bytes
+/// look binary.
+pub fn content_type_for(path: &str, is_binary: bool) -> &'static str {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" | "cjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "csv" => "text/csv; charset=utf-8",
+        "md" | "markdown" => "text/markdown; charset=utf-8",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "wasm" => "application/wasm",
+        _ if is_binary => "application/octet-stream",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guesses_from_the_extension_and_falls_back_on_content() {
+        assert_eq!(
+            content_type_for("config/app.JSON", false),
+            "application/json"
+        );
+        assert_eq!(content_type_for("assets/logo.png", true), "image/png");
+        assert_eq!(
+            content_type_for("src/main.rs", false),
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(
+            content_type_for("Makefile", false),
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(
+            content_type_for("build/.cache/blob", true),
+            "application/octet-stream"
+        );
+    }
+}
@@ -1 +1,10 @@
+#[cfg(feature = "ssr")]
+pub mod circuit_breaker;
+pub mod content_type;
+pub mod path_location;
+pub mod query_plan;
+pub mod repo_overview;
+pub mod symbol_graph;
 pub mod time;
+#[cfg(feature = "ssr")]
+pub mod ttl_cache;
@@ -0,0 +1,217 @@
+use crate::db::models::FileLocationMatch;
+
+/// A file position as printed by compilers and linters, e.g.
+/// `src/main.rs:12:5` or `src\main.cpp(12,5)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathLocation {
+    pub path: String,
+    pub line: u32,
+    pub column: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LocationOutcome {
+    /// Exactly one repository has the file; go straight to it.
+    Navigate(String),
+    /// Several repositories have the file; let the user pick.
+    Choose(Vec<FileLocationMatch>),
+    /// Nothing matched; run a regular search scoped to the path instead.
+    Search(String),
+}
+
+pub fn parse_path_location(input: &str) -> Option<PathLocation> {
+    let raw = input.trim();
+    let raw = raw.strip_prefix("-->").unwrap_or(raw).trim();
+    if raw.is_empty() || raw.contains(char::is_whitespace) {
+        return None;
+    }
+    let raw = raw.trim_end_matches(':').replace('\\', "/");
+
+    let (path, line, column) = if let Some(inner) = raw.strip_suffix(')') {
+        // MSVC style: `path(line)` or `path(line,col)`.
+        let (path, position) = inner.rsplit_once('(')?;
+        let (line, column) = match position.split_once(',') {
+            Some((line, column)) => (line, Some(column)),
+            None => (position, None),
+        };
+        (path, line, column)
+    } else {
+        let (rest, last) = raw.rsplit_once(':')?;
+        match rest.rsplit_once(':') {
+            Some((path, line)) if is_number(line) => (path, line, Some(last)),
+            _ => (rest, last, None),
+        }
+    };
+
+    let line = parse_position(line)?;
+    let column = match column {
+        Some(column) => Some(parse_position(column)?),
+        None => None,
+    };
+
+    let mut path = path;
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest;
+    }
+    let path = path.trim_start_matches('/');
+    if !looks_like_file_path(path) {
+        return None;
+    }
+
+    Some(PathLocation {
+        path: path.to_string(),
+        line,
+        column,
+    })
+}
+
+/// Splits a search-bar query into a path location plus any `repo:` filters.
+/// Returns `None` unless the query is exactly one location with optional
+/// repository atoms around it.
+pub fn split_location_query(query: &str) -> Option<(PathLocation, Vec<String>)> {
+    let mut repositories = Vec::new();
+    let mut location = None;
+    for token in query.split_whitespace() {
+        let repo = token
+            .strip_prefix("repo:")
+            .or_else(|| token.strip_prefix("r:"));
+        match repo {
+            Some(repo) if !repo.is_empty() => repositories.push(repo.to_string()),
+            _ if location.is_none() => location = Some(parse_path_location(token)?),
+            _ => return None,
+        }
+    }
+    location.map(|location| (location, repositories))
+}
+
+pub fn location_outcome(
+    location: &PathLocation,
+    repositories: &[String],
+    matches: &[FileLocationMatch],
+) -> LocationOutcome {
+    match matches {
+        [] => {
+            let mut query = String::new();
+            for repo in repositories {
+                query.push_str(&format!("repo:{repo} "));
+            }
+            query.push_str(&format!("path:{}", location.path));
+            LocationOutcome::Search(query)
+        }
+        [only] => LocationOutcome::Navigate(location_url(only, location.line)),
+        _ => LocationOutcome::Choose(matches.to_vec()),
+    }
+}
+
+pub fn location_url(found: &FileLocationMatch, line: u32) -> String {
+    format!(
+        "/repo/{}/tree/{}/{}#L{}",
+        found.repository, found.branch, found.file_path, line
+    )
+}
+
+fn is_number(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn parse_position(value: &str) -> Option<u32> {
+    if !is_number(value) {
+        return None;
+    }
+    value.parse().ok().filter(|n| *n > 0)
+}
+
+fn looks_like_file_path(path: &str) -> bool {
+    if path.is_empty() || path.contains(':') || path.ends_with('/') {
+        return false;
+    }
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    path.contains('/') || file_name.contains('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(path: &str, line: u32, column: Option<u32>) -> Option<PathLocation> {
+        Some(PathLocation {
+            path: path.to_string(),
+            line,
+            column,
+        })
+    }
+
+    fn found(repository: &str) -> FileLocationMatch {
+        FileLocationMatch {
+            repository: repository.to_string(),
+            branch: "main".to_string(),
+            commit_sha: "abc123".to_string(),
+            file_path: "src/login.rs".to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_compiler_style_locations() {
+        let cases = [
+            (
+                "services/auth/src/login.rs:142:7",
+                location("services/auth/src/login.rs", 142, Some(7)),
+            ),
+            ("src/main.rs:3", location("src/main.rs", 3, None)),
+            ("./src/main.rs:3:1:", location("src/main.rs", 3, Some(1))),
+            ("--> src/lib.rs:10:5", location("src/lib.rs", 10, Some(5))),
+            (
+                r"src\win\main.cpp(12,5)",
+                location("src/win/main.cpp", 12, Some(5)),
+            ),
+            (r".\src\main.cpp(12):", location("src/main.cpp", 12, None)),
+            ("main.go:8", location("main.go", 8, None)),
+            ("foo:12", None),
+            ("repo:pointer", None),
+            ("src/main.rs", None),
+            ("src/main.rs:0", None),
+            ("src/main.rs:abc", None),
+            ("fn main() {}", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_path_location(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn splits_repo_filters_from_location() {
+        let (parsed, repos) = split_location_query("repo:pointer src/lib.rs:4").unwrap();
+        assert_eq!(Some(parsed), location("src/lib.rs", 4, None));
+        assert_eq!(repos, vec!["pointer".to_string()]);
+        assert!(split_location_query("src/lib.rs:4 src/main.rs:5").is_none());
+        assert!(split_location_query("lang:rust src/lib.rs:4").is_none());
+    }
+
+    #[test]
+    fn single_match_navigates_to_line() {
+        let parsed = parse_path_location("src/login.rs:142:7").unwrap();
+        assert_eq!(
+            location_outcome(&parsed, &[], &[found("auth")]),
+            LocationOutcome::Navigate("/repo/auth/tree/main/src/login.rs#L142".to_string())
+        );
+    }
+
+    #[test]
+    fn multiple_matches_ask_for_a_choice() {
+        let parsed = parse_path_location("src/login.rs:142").unwrap();
+        let matches = vec![found("auth"), found("billing")];
+        assert_eq!(
+            location_outcome(&parsed, &[], &matches),
+            LocationOutcome::Choose(matches)
+        );
+    }
+
+    #[test]
+    fn no_match_falls_back_to_path_search() {
+        let parsed = parse_path_location("./src/login.rs:142").unwrap();
+        assert_eq!(
+            location_outcome(&parsed, &["auth".to_string()], &[]),
+            LocationOutcome::Search("repo:auth path:src/login.rs".to_string())
+        );
+    }
+}
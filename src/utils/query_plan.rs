@@ -0,0 +1,148 @@
+use serde_json::Value;
+
+/// Tables below this many estimated rows are cheap enough to scan outright.
+pub const LARGE_TABLE_ROWS: i64 = 10_000;
+
+/// Lets chunk-to-blob lookups run as index-only scans.
+pub const COVERING_CHUNK_INDEX: &str = "CREATE INDEX CONCURRENTLY \
+     idx_content_blob_chunks_chunk_hash_covering ON content_blob_chunks (chunk_hash) \
+     INCLUDE (content_hash, chunk_index)";
+
+/// A `Seq Scan` node found in an `EXPLAIN (FORMAT JSON)` plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeqScan {
+    pub relation: String,
+    pub plan_rows: f64,
+}
+
+/// Walks a JSON plan, as returned by `EXPLAIN (FORMAT JSON)`, and collects
+/// every sequential scan in it, outermost first.
+pub fn collect_seq_scans(plan: &Value) -> Vec<SeqScan> {
+    let mut scans = Vec::new();
+    visit(plan, &mut scans);
+    scans
+}
+
+fn visit(node: &Value, scans: &mut Vec<SeqScan>) {
+    match node {
+        Value::Array(items) => items.iter().for_each(|item| visit(item, scans)),
+        Value::Object(fields) => {
+            if fields.get("Node Type").and_then(Value::as_str) == Some("Seq Scan")
+                && let Some(relation) = fields.get("Relation Name").and_then(Value::as_str)
+            {
+                scans.push(SeqScan {
+                    relation: relation.to_string(),
+                    plan_rows: fields
+                        .get("Plan Rows")
+                        .and_then(Value::as_f64)
+                        .unwrap_or_default(),
+                });
+            }
+            if let Some(child) = fields.get("Plan") {
+                visit(child, scans);
+            }
+            if let Some(children) = fields.get("Plans") {
+                visit(children, scans);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The index the search queries expect to use on `relation`, if any.
+pub fn index_hint(relation: &str) -> Option<&'static str> {
+    match relation {
+        "chunks" => Some("idx_chunks_text_content_trgm (GIN trigram on text_content)"),
+        "content_blob_chunks" => Some(COVERING_CHUNK_INDEX),
+        "files" => Some("idx_files_content_hash or idx_files_repository_commit"),
+        "symbols" => Some("idx_symbols_name_lc_content_hash"),
+        "unique_symbols" => Some("idx_unique_symbols_name_lc_trgm (GIN trigram on name_lc)"),
+        "symbol_references" => Some("idx_symbol_references_symbol_id"),
+        _ => None,
+    }
+}
+
+/// Turns the sequential scans in `query`'s plan into readable findings. Scans
+/// are only flagged when `table_rows` reports the table as large; each table
+/// is reported once per query.
+pub fn seq_scan_findings(
+    query: &str,
+    scans: &[SeqScan],
+    table_rows: impl Fn(&str) -> Option<i64>,
+) -> Vec<String> {
+    let mut seen = Vec::new();
+    let mut findings = Vec::new();
+    for scan in scans {
+        if seen.contains(&scan.relation.as_str()) {
+            continue;
+        }
+        let Some(rows) = table_rows(&scan.relation).filter(|rows| *rows >= LARGE_TABLE_ROWS) else {
+            continue;
+        };
+        seen.push(scan.relation.as_str());
+        let mut finding = format!("{query} is seq-scanning `{}` (~{rows} rows)", scan.relation);
+        if let Some(hint) = index_hint(&scan.relation) {
+            finding.push_str("; consider ");
+            finding.push_str(hint);
+        }
+        findings.push(finding);
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_plan() -> Value {
+        json!([{
+            "Plan": {
+                "Node Type": "Hash Join",
+                "Plan Rows": 2066,
+                "Plans": [
+                    {
+                        "Node Type": "Seq Scan",
+                        "Relation Name": "content_blob_chunks",
+                        "Plan Rows": 810
+                    },
+                    {
+                        "Node Type": "Hash",
+                        "Plans": [{
+                            "Node Type": "Seq Scan",
+                            "Relation Name": "files",
+                            "Plan Rows": 510
+                        }]
+                    },
+                    {
+                        "Node Type": "Bitmap Heap Scan",
+                        "Relation Name": "chunks",
+                        "Plan Rows": 12
+                    }
+                ]
+            }
+        }])
+    }
+
+    #[test]
+    fn collects_nested_seq_scans() {
+        let scans = collect_seq_scans(&sample_plan());
+        let relations: Vec<&str> = scans.iter().map(|scan| scan.relation.as_str()).collect();
+        assert_eq!(relations, ["content_blob_chunks", "files"]);
+        assert_eq!(scans[0].plan_rows, 810.0);
+    }
+
+    #[test]
+    fn flags_only_large_tables() {
+        let scans = collect_seq_scans(&sample_plan());
+        let findings = seq_scan_findings("text_search", &scans, |table| match table {
+            "content_blob_chunks" => Some(2_000_000),
+            "files" => Some(500),
+            _ => None,
+        });
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].starts_with(
+            "text_search is seq-scanning `content_blob_chunks` (~2000000 rows); consider CREATE INDEX"
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::db::models::BuildFile;
+
+/// Postgres regex that pre-filters the paths worth classifying. It only has to
+/// be a superset of what [`is_entry_point`] and [`build_system`] accept.
+pub const CANDIDATE_PATH_PATTERN: &str =
+    r"(^|/)((main|index|app)\.[^/]+|Cargo\.toml|package\.json|go\.mod|BUILD(\.bazel)?|Dockerfile)$";
+
+pub const MAX_ENTRY_POINTS: usize = 8;
+pub const MAX_BUILD_FILES: usize = 12;
+
+const ENTRY_POINT_STEMS: &[&str] = &["main", "index", "app"];
+
+/// Extensions that share an entry-point stem without being code, e.g.
+/// `index.md` or `app.css`.
+const NON_CODE_EXTENSIONS: &[&str] = &[
+    "md", "txt", "rst", "css", "scss", "less", "json", "lock", "map", "snap", "svg", "png",
+];
+
+/// Directories whose `main.*` files belong to dependencies or build output.
+const IGNORED_DIRS: &[&str] = &["node_modules", "vendor", "target", "dist", "build", ".venv"];
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn depth(path: &str) -> usize {
+    path.matches('/').count()
+}
+
+fn in_ignored_dir(path: &str) -> bool {
+    let mut dirs = path.split('/');
+    dirs.next_back();
+    dirs.any(|dir| IGNORED_DIRS.contains(&dir))
+}
+
+/// Names the build system a manifest belongs to, or `None` if `path` isn't
+/// one we recognise.
+pub fn build_system(path: &str) -> Option<&'static str> {
+    match file_name(path) {
+        "Cargo.toml" => Some("Cargo"),
+        "package.json" => Some("npm"),
+        "go.mod" => Some("Go modules"),
+        "BUILD" | "BUILD.bazel" => Some("Bazel"),
+        "Dockerfile" => Some("Docker"),
+        _ => None,
+    }
+}
+
+pub fn is_entry_point(path: &str) -> bool {
+    entry_point_rank(path).is_some()
+}
+
+/// Lower ranks are better guesses: `cmd/<tool>/main.go` first, then
+/// `main`/`index`/`app` files, shallowest first.
+fn entry_point_rank(path: &str) -> Option<(u8, usize)> {
+    if in_ignored_dir(path) {
+        return None;
+    }
+    let (stem, extension) = file_name(path).split_once('.')?;
+    if !ENTRY_POINT_STEMS.contains(&stem)
+        || extension.is_empty()
+        || extension.contains('.')
+        || NON_CODE_EXTENSIONS.contains(&extension)
+    {
+        return None;
+    }
+    let segments: Vec<&str> = path.split('/').collect();
+    let is_go_command =
+        path.ends_with("/main.go") && segments.len() >= 3 && segments[segments.len() - 3] == "cmd";
+    Some((u8::from(!is_go_command), depth(path)))
+}
+
+/// Splits candidate paths into ranked entry points and build manifests.
+pub fn classify_candidates<I, S>(paths: I) -> (Vec<String>, Vec<BuildFile>)
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut entry_points = Vec::new();
+    let mut build_files = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        if let Some(rank) = entry_point_rank(path) {
+            entry_points.push((rank, path.to_string()));
+        }
+        if let Some(system) = build_system(path)
+            && !in_ignored_dir(path)
+        {
+            build_files.push(BuildFile {
+                path: path.to_string(),
+                system: system.to_string(),
+            });
+        }
+    }
+
+    entry_points.sort();
+    build_files.sort_by(|a, b| {
+        depth(&a.path)
+            .cmp(&depth(&b.path))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    (
+        entry_points
+            .into_iter()
+            .take(MAX_ENTRY_POINTS)
+            .map(|(_, path)| path)
+            .collect(),
+        build_files.into_iter().take(MAX_BUILD_FILES).collect(),
+    )
+}
+
+/// Formats a file size for display, e.g. `1.5 KB`.
+pub fn format_byte_len(bytes: i64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_entry_points() {
+        for path in [
+            "main.rs",
+            "src/main.rs",
+            "web/index.ts",
+            "app.py",
+            "cmd/server/main.go",
+        ] {
+            assert!(is_entry_point(path), "{path}");
+        }
+        for path in [
+            "README.md",
+            "docs/index.md",
+            "static/app.css",
+            "src/domain.rs",
+            "node_modules/left-pad/index.js",
+            "index.d.ts",
+            "main",
+        ] {
+            assert!(!is_entry_point(path), "{path}");
+        }
+    }
+
+    #[test]
+    fn recognises_build_files() {
+        assert_eq!(build_system("Cargo.toml"), Some("Cargo"));
+        assert_eq!(build_system("web/package.json"), Some("npm"));
+        assert_eq!(build_system("go.mod"), Some("Go modules"));
+        assert_eq!(build_system("tools/BUILD.bazel"), Some("Bazel"));
+        assert_eq!(build_system("deploy/Dockerfile"), Some("Docker"));
+        assert_eq!(build_system("Cargo.lock"), None);
+        assert_eq!(build_system("docs/BUILDING.md"), None);
+    }
+
+    #[test]
+    fn ranks_go_commands_then_shallow_entry_points() {
+        let (entry_points, build_files) = classify_candidates([
+            "services/api/src/main.rs",
+            "web/index.ts",
+            "cmd/pointer/main.go",
+            "main.py",
+            "services/api/Cargo.toml",
+            "Dockerfile",
+            "node_modules/pkg/package.json",
+        ]);
+        assert_eq!(
+            entry_points,
+            vec![
+                "cmd/pointer/main.go",
+                "main.py",
+                "web/index.ts",
+                "services/api/src/main.rs",
+            ]
+        );
+        let build_paths: Vec<_> = build_files.iter().map(|b| b.path.as_str()).collect();
+        assert_eq!(build_paths, vec!["Dockerfile", "services/api/Cargo.toml"]);
+    }
+
+    #[test]
+    fn formats_byte_lengths() {
+        assert_eq!(format_byte_len(512), "512 B");
+        assert_eq!(format_byte_len(1536), "1.5 KB");
+        assert_eq!(format_byte_len(3 * 1024 * 1024), "3.0 MB");
+    }
+}
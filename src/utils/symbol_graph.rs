@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::db::GraphGranularity;
+use crate::db::models::GraphEdge;
+
+pub const DEFAULT_EDGE_LIMIT: i64 = 200;
+pub const MAX_EDGE_LIMIT: i64 = 2_000;
+
+/// Label for references whose namespace is empty.
+const GLOBAL_NAMESPACE: &str = "(global)";
+
+/// Renders reference edges as a GraphViz digraph. Edge labels carry the number
+/// of references that were folded into each edge.
+pub fn render_dot(
+    repository: &str,
+    commit_sha: &str,
+    granularity: GraphGranularity,
+    edges: &[GraphEdge],
+) -> String {
+    let node_name = |name: &str| match granularity {
+        GraphGranularity::Namespace if name.is_empty() => GLOBAL_NAMESPACE.to_string(),
+        _ => name.to_string(),
+    };
+
+    let mut dot = String::new();
+    let _ = writeln!(
+        dot,
+        "digraph {} {{",
+        quote(&format!("{repository}@{commit_sha}"))
+    );
+    dot.push_str("  rankdir=LR;\n");
+    let shape = match granularity {
+        GraphGranularity::File => "note",
+        GraphGranularity::Namespace => "box",
+    };
+    let _ = writeln!(dot, "  node [shape={shape}, fontname=\"monospace\"];");
+
+    let mut seen = HashSet::new();
+    for edge in edges {
+        for node in [&edge.source, &edge.target] {
+            if seen.insert(node.as_str()) {
+                let _ = writeln!(dot, "  {};", quote(&node_name(node)));
+            }
+        }
+    }
+    for edge in edges {
+        let _ = writeln!(
+            dot,
+            "  {} -> {} [label=\"{}\"];",
+            quote(&node_name(&edge.source)),
+            quote(&node_name(&edge.target)),
+            edge.weight
+        );
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn quote(id: &str) -> String {
+    let mut quoted = String::with_capacity(id.len() + 2);
+    quoted.push('"');
+    for ch in id.chars() {
+        match ch {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(ch);
+            }
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: &str, target: &str, weight: i64) -> GraphEdge {
+        GraphEdge {
+            source: source.to_string(),
+            target: target.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn renders_file_edges_with_weights() {
+        let edges = vec![
+            edge("src/b.rs", "src/a.rs", 2),
+            edge("src/a.rs", "src/util/c.rs", 1),
+        ];
+        let dot = render_dot("acme", "abc123", GraphGranularity::File, &edges);
+
+        assert!(dot.starts_with("digraph \"acme@abc123\" {\n"));
+        for node in ["\"src/a.rs\";", "\"src/b.rs\";", "\"src/util/c.rs\";"] {
+            assert_eq!(dot.matches(node).count(), 1, "{node} in {dot}");
+        }
+        assert!(dot.contains("\"src/b.rs\" -> \"src/a.rs\" [label=\"2\"];"));
+        assert!(dot.contains("\"src/a.rs\" -> \"src/util/c.rs\" [label=\"1\"];"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn names_the_global_namespace_and_escapes_ids() {
+        let edges = vec![edge("", "app::\"quoted\"", 3)];
+        let dot = render_dot("acme", "abc123", GraphGranularity::Namespace, &edges);
+
+        assert!(dot.contains("\"(global)\" -> \"app::\\\"quoted\\\"\" [label=\"3\"];"));
+        assert!(dot.contains("node [shape=box"));
+    }
+}
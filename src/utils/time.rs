@@ -31,3 +31,43 @@ pub fn now_seconds() -> TimePoint {
 pub fn elapsed_since(start: TimePoint) -> f64 {
     (now_seconds() - start).max(0.0)
 }
+
+/// A coarse age such as `just now`, `5 minutes ago` or `2 days ago`.
+/// Negative ages, from clock skew, read as `just now`.
+pub fn format_age(seconds: i64) -> String {
+    const UNITS: [(&str, i64); 6] = [
+        ("year", 365 * 24 * 60 * 60),
+        ("month", 30 * 24 * 60 * 60),
+        ("day", 24 * 60 * 60),
+        ("hour", 60 * 60),
+        ("minute", 60),
+        ("second", 1),
+    ];
+    if seconds < 10 {
+        return "just now".to_string();
+    }
+    let (unit, size) = UNITS
+        .into_iter()
+        .find(|(_, size)| seconds >= *size)
+        .expect("seconds are at least one");
+    match seconds / size {
+        1 => format!("1 {unit} ago"),
+        count => format!("{count} {unit}s ago"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages_use_the_largest_whole_unit() {
+        assert_eq!(format_age(-30), "just now");
+        assert_eq!(format_age(5), "just now");
+        assert_eq!(format_age(45), "45 seconds ago");
+        assert_eq!(format_age(60), "1 minute ago");
+        assert_eq!(format_age(2 * 60 * 60 + 59), "2 hours ago");
+        assert_eq!(format_age(2 * 24 * 60 * 60 + 3600), "2 days ago");
+        assert_eq!(format_age(400 * 24 * 60 * 60), "1 year ago");
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A small least-recently-used cache whose entries also expire `ttl` after
+/// they were stored. Safe to share between request handlers.
+#[derive(Debug)]
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    capacity: usize,
+    inner: Mutex<Inner<K, V>>,
+}
+
+#[derive(Debug)]
+struct Inner<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    /// Bumped on every access; an entry's `last_used` orders eviction.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    stored_at: Instant,
+    last_used: u64,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> TtlCache<K, V> {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity: capacity.max(1),
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.clock += 1;
+        let clock = inner.clock;
+        let entry = inner.entries.get_mut(key)?;
+        if entry.stored_at.elapsed() >= self.ttl {
+            inner.entries.remove(key);
+            return None;
+        }
+        entry.last_used = clock;
+        Some(entry.value.clone())
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.clock += 1;
+        let clock = inner.clock;
+        if !inner.entries.contains_key(&key) && inner.entries.len() >= self.capacity {
+            let ttl = self.ttl;
+            inner
+                .entries
+                .retain(|_, entry| entry.stored_at.elapsed() < ttl);
+            if inner.entries.len() >= self.capacity
+                && let Some(oldest) = inner
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone())
+            {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.entries.insert(
+            key,
+            Entry {
+                value,
+                stored_at: Instant::now(),
+                last_used: clock,
+            },
+        );
+    }
+
+    /// Returns the cached value for `key`, or runs `fetch` and caches what it
+    /// returns. Errors are not cached. Concurrent misses for the same key may
+    /// each run `fetch`; the last one to finish wins.
+    pub async fn get_or_try_fetch<E, Fut>(
+        &self,
+        key: K,
+        fetch: impl FnOnce() -> Fut,
+    ) -> Result<V, E>
+    where
+        Fut: Future<Output = Result<V, E>>,
+    {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+        let value = fetch().await?;
+        self.insert(key, value.clone());
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn repeated_fetches_within_the_ttl_hit_the_cache() {
+        let cache = TtlCache::new(Duration::from_secs(10), 8);
+        let fetches = AtomicUsize::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, ()>("response")
+        };
+
+        assert_eq!(cache.get_or_try_fetch(1, fetch).await, Ok("response"));
+        assert_eq!(cache.get_or_try_fetch(1, fetch).await, Ok("response"));
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        let expired = TtlCache::new(Duration::ZERO, 8);
+        expired.insert(1, "stale");
+        assert_eq!(expired.get(&1), None);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry_when_full() {
+        let cache = TtlCache::new(Duration::from_secs(10), 2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert_eq!(cache.get(&"a"), Some(1));
+        cache.insert("c", 3);
+
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"c"), Some(3));
+    }
+}